@@ -0,0 +1,239 @@
+// Tokenizer throughput benchmarks
+//
+// Run with `cargo bench`.  Criterion measures each case and reports its
+// throughput, and its change since the last run, so regressions can be
+// tracked.  The run then checks each case against the performance budget
+// below, so a regression past the budget shows up as a failed bench run
+// rather than a number nobody looks at.
+//
+// The cases cover the shapes of document that dominate real workloads:
+//
+//  * text heavy documents, with the formatting churn of a word processor
+//    (`tests/sample.rtf` repeated to a few megabytes)
+//  * mail exports, which are mostly short paragraphs of plain text with
+//    `\htmlrtf` encapsulation markup around them
+//  * image heavy documents, which are mostly hex encoded `\pict` data
+//
+// Documents named in `RTFG_BENCH_DOCS`, separated as `PATH` is, are
+// benchmarked too, such as the RTF specification itself:
+//
+//     RTFG_BENCH_DOCS=path/to/RTF-Spec-1.9.1.rtf cargo bench --bench tokenize
+//
+// With the `arena` feature, tokenizing into an arena is measured too:
+//
+//     cargo bench --features arena
+//
+// Performance budget
+// ------------------
+//
+// Minimum throughput, in MB/s of input, for an optimized build, on every
+// case:
+//
+//  * tokenizing with `parse`, and with `arena::parse_in`: 20 MB/s
+//  * tokenizing and extracting text with `extract_text`: 15 MB/s
+//  * extracting text without tokenizing, with `scan_text`: 60 MB/s
+//
+// The budget is deliberately well under what a current desktop machine
+// manages, so that only real regressions trip it, not a noisy CI runner.
+// None of it needs options beyond the defaults.
+//
+
+#[cfg(feature = "arena")]
+extern crate bumpalo;
+#[macro_use]
+extern crate criterion;
+extern crate rtf_grimoire;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, Criterion, Throughput};
+
+use rtf_grimoire::scan::scan_text;
+use rtf_grimoire::text::{extract_text, TextOptions};
+use rtf_grimoire::tokenizer::parse;
+
+const BUDGET_TOKENIZE: f64 = 20.0;
+const BUDGET_EXTRACT: f64 = 15.0;
+const BUDGET_SCAN: f64 = 60.0;
+
+const TARGET_SIZE: usize = 4 * 1024 * 1024;
+
+fn repeat_to_size(header: &[u8], body: &[u8], footer: &[u8]) -> Vec<u8> {
+    let mut doc = header.to_vec();
+    while doc.len() < TARGET_SIZE {
+        doc.extend_from_slice(body);
+    }
+    doc.extend_from_slice(footer);
+    doc
+}
+
+fn word_processor_doc() -> Vec<u8> {
+    let sample: &[u8] = include_bytes!("../tests/sample.rtf");
+    // Repeat the body of the sample, inside one outer group
+    let start = sample.iter().position(|b| *b == b'{').map_or(0, |i| i + 1);
+    let end = sample
+        .iter()
+        .rposition(|b| *b == b'}')
+        .unwrap_or(sample.len());
+    repeat_to_size(b"{", &sample[start..end], b"}")
+}
+
+fn mail_doc() -> Vec<u8> {
+    repeat_to_size(
+        b"{\\rtf1\\ansi\\ansicpg1252\\fromhtml1 \\deff0{\\fonttbl{\\f0\\fswiss Arial;}}",
+        b"{\\*\\htmltag64 <p>}\\htmlrtf {\\htmlrtf0 Thanks for the update, see you on \\'e9 Monday.\\par\n}\\htmlrtf0 {\\*\\htmltag72 </p>}\r\n",
+        b"}",
+    )
+}
+
+fn image_doc() -> Vec<u8> {
+    let mut picture = b"{\\pict\\pngblip\\picw64\\pich64 ".to_vec();
+    for row in 0..512 {
+        for byte in 0..64u32 {
+            picture.extend_from_slice(format!("{:02x}", (byte * 7 + row) & 0xff).as_bytes());
+        }
+        picture.push(b'\n');
+    }
+    picture.extend_from_slice(b"}\\par Figure caption\\par\n");
+    repeat_to_size(b"{\\rtf1\\ansi ", &picture, b"}")
+}
+
+// The documents to benchmark, and their names
+fn documents() -> Vec<(String, Vec<u8>)> {
+    let mut docs = vec![
+        ("word processor".to_string(), word_processor_doc()),
+        ("mail export".to_string(), mail_doc()),
+        ("images".to_string(), image_doc()),
+    ];
+    if let Some(paths) = env::var_os("RTFG_BENCH_DOCS") {
+        for path in env::split_paths(&paths) {
+            match fs::read(&path) {
+                Ok(doc) => docs.push((path.display().to_string(), doc)),
+                Err(error) => {
+                    eprintln!("{}: {}", path.display(), error);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+    docs
+}
+
+fn bench_documents(c: &mut Criterion) {
+    for (name, doc) in documents() {
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(doc.len() as u64));
+        group.sample_size(10);
+
+        group.bench_function("tokenize", |b| {
+            b.iter(|| parse(black_box(&doc)).expect("benchmark document should tokenize"))
+        });
+
+        #[cfg(feature = "arena")]
+        group.bench_function("arena", |b| {
+            let mut arena = bumpalo::Bump::new();
+            b.iter(|| {
+                arena.reset();
+                rtf_grimoire::arena::parse_in(&arena, black_box(&doc))
+                    .expect("benchmark document should tokenize")
+                    .len()
+            })
+        });
+
+        group.bench_function("extract", |b| {
+            b.iter(|| {
+                let tokens = parse(black_box(&doc)).expect("benchmark document should tokenize");
+                extract_text(&tokens)
+            })
+        });
+
+        let options = TextOptions::default();
+        let mut text = String::new();
+        group.bench_function("scan", |b| {
+            b.iter(|| {
+                text.clear();
+                scan_text(black_box(&doc), &options, &mut text);
+            })
+        });
+        group.finish();
+    }
+}
+
+// Median time of `runs` calls to `f`
+fn measure<F: FnMut()>(runs: usize, mut f: F) -> Duration {
+    let mut times: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[times.len() / 2]
+}
+
+// Report a case that's under budget, returning false if it is
+fn within_budget(case: &str, bench: &str, bytes: usize, time: Duration, budget: f64) -> bool {
+    let throughput = bytes as f64 / 1_000_000.0 / time.as_secs_f64();
+    if throughput < budget {
+        println!(
+            "{}/{}: {:.1} MB/s, OVER BUDGET of {} MB/s",
+            case, bench, throughput, budget
+        );
+    }
+    throughput >= budget
+}
+
+// Check every case against the performance budget
+fn check_budget() -> bool {
+    let runs = 5;
+    let mut ok = true;
+    for (case, doc) in documents() {
+        let time = measure(runs, || {
+            parse(&doc).expect("benchmark document should tokenize");
+        });
+        ok &= within_budget(&case, "tokenize", doc.len(), time, BUDGET_TOKENIZE);
+
+        #[cfg(feature = "arena")]
+        {
+            let mut arena = bumpalo::Bump::new();
+            let time = measure(runs, || {
+                arena.reset();
+                rtf_grimoire::arena::parse_in(&arena, &doc)
+                    .expect("benchmark document should tokenize");
+            });
+            ok &= within_budget(&case, "arena", doc.len(), time, BUDGET_TOKENIZE);
+        }
+
+        let time = measure(runs, || {
+            extract_text(&parse(&doc).expect("benchmark document should tokenize"));
+        });
+        ok &= within_budget(&case, "extract", doc.len(), time, BUDGET_EXTRACT);
+
+        let options = TextOptions::default();
+        let mut text = String::new();
+        let time = measure(runs, || {
+            text.clear();
+            scan_text(&doc, &options, &mut text);
+        });
+        ok &= within_budget(&case, "scan", doc.len(), time, BUDGET_SCAN);
+    }
+    ok
+}
+
+criterion_group!(benches, bench_documents);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+
+    // Debug builds are far too slow to be held to the budget, and `cargo
+    // test` runs benches as tests, once, just to see they work
+    let testing = env::args().any(|arg| arg == "--test");
+    if !testing && !cfg!(debug_assertions) && !check_budget() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,34 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rtf_grimoire::document::Document;
+use rtf_grimoire::scan::scan_text;
+use rtf_grimoire::text::{extract_text_with, TextOptions};
+use rtf_grimoire::tokenizer::{parse, parse_spanned};
+
+fuzz_target!(|data: &[u8]| {
+    let tokens = match parse(data) {
+        Ok(tokens) => tokens,
+        Err(_) => return,
+    };
+
+    // Spans are contiguous, and cover the same tokens
+    let spanned = parse_spanned(data).unwrap();
+    assert_eq!(spanned.len(), tokens.len());
+    let mut end = 0;
+    for (spanned, token) in spanned.iter().zip(&tokens) {
+        assert_eq!(spanned.span.start, end);
+        assert!(spanned.span.end > spanned.span.start);
+        assert_eq!(&spanned.token, token);
+        end = spanned.span.end;
+    }
+    assert!(end <= data.len());
+
+    // The token-free scanner agrees with the decoder
+    let options = TextOptions::default();
+    let mut scanned = String::new();
+    scan_text(data, &options, &mut scanned);
+    assert_eq!(scanned, extract_text_with(&tokens, &options));
+
+    Document::from_tokens(&tokens);
+});
@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use nom::types::CompleteByteSlice as Input;
+use rtf_grimoire::tokenizer::read_token;
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Input(data);
+    while !input.is_empty() {
+        match read_token(input) {
+            // A token that consumes nothing, like the empty text before a
+            // lone `\`, is where the tokenizer stops, as `parse` does
+            Ok((rest, _)) if rest.len() == input.len() => break,
+            Ok((rest, _)) => input = rest,
+            Err(_) => break,
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rtf_grimoire::fuzz::{arbitrary_tokens, roundtrip};
+
+// Any token stream the tokenizer can produce must survive serialization
+fuzz_target!(|data: &[u8]| {
+    let tokens = arbitrary_tokens(data);
+    if let Err(reparsed) = roundtrip(&tokens) {
+        panic!("{:?}\nread back as\n{:?}", tokens, reparsed);
+    }
+});
@@ -0,0 +1,230 @@
+// Annotated token streams
+//
+// Pipelines that label tokens, say with what kind of content they hold,
+// need the labels to follow the tokens through rewrites that add and remove
+// tokens around them.  An annotated stream keeps a token stream and the
+// labels on it together, moving the labels along as the stream is edited,
+// and can write the stream out saying where each token ended up.
+//
+
+use std::ops::Range;
+
+use diff::{diff, Edit};
+use tokenizer::Token;
+use writer::{Encoder, WriteError, WriteOptions};
+
+/// A token stream with caller data attached to some of its tokens
+///
+/// Each token can have one annotation.  Editing the stream moves the
+/// annotations along with the tokens they're attached to, and hands back
+/// the annotations of tokens that were removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedStream<T> {
+    tokens: Vec<Token>,
+    annotations: Vec<Option<T>>,
+}
+
+impl<T> AnnotatedStream<T> {
+    /// A stream of tokens with no annotations
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let annotations = tokens.iter().map(|_| None).collect();
+        AnnotatedStream {
+            tokens,
+            annotations,
+        }
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Attach data to the token at `index`, returning what was attached to
+    /// it before
+    ///
+    /// Panics if there's no token at `index`.
+    pub fn annotate(&mut self, index: usize, value: T) -> Option<T> {
+        self.annotations[index].replace(value)
+    }
+
+    /// The data attached to the token at `index`
+    pub fn annotation(&self, index: usize) -> Option<&T> {
+        self.annotations.get(index)?.as_ref()
+    }
+
+    /// Detach the data attached to the token at `index`
+    pub fn take_annotation(&mut self, index: usize) -> Option<T> {
+        self.annotations.get_mut(index)?.take()
+    }
+
+    /// The annotated tokens' indices, and the data attached to each
+    pub fn annotations(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.annotations
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| Some((index, value.as_ref()?)))
+    }
+
+    /// Replace the tokens in `range` with `tokens`, returning the
+    /// annotations of the tokens replaced
+    ///
+    /// The tokens after the range keep their annotations.
+    pub fn splice(&mut self, range: Range<usize>, tokens: Vec<Token>) -> Vec<T> {
+        let count = tokens.len();
+        self.tokens.splice(range.clone(), tokens);
+        self.annotations
+            .splice(range, (0..count).map(|_| None))
+            .flatten()
+            .collect()
+    }
+
+    /// Insert tokens before the token at `index`
+    pub fn insert(&mut self, index: usize, tokens: Vec<Token>) {
+        self.splice(index..index, tokens);
+    }
+
+    /// Remove the tokens in `range`, returning their annotations
+    pub fn remove(&mut self, range: Range<usize>) -> Vec<T> {
+        self.splice(range, Vec::new())
+    }
+
+    /// Rewrite the stream with a function over its tokens, like those of the
+    /// `redact` or `minify` modules, returning the annotations of tokens
+    /// that didn't survive it
+    ///
+    /// The rewritten tokens are compared with the originals, as `diff`
+    /// does, and tokens that come through unchanged keep their annotations.
+    /// Where a run of tokens was replaced, the annotations of the tokens
+    /// replaced move to the tokens that replaced them, first to first, and
+    /// those of any left over are returned.
+    ///
+    /// ```
+    /// use rtf_grimoire::annotate::AnnotatedStream;
+    /// use rtf_grimoire::minify::minify;
+    /// use rtf_grimoire::{parse, Token};
+    ///
+    /// let tokens = parse(b"{\\rtf1\\b\\b Bold}").unwrap();
+    /// let mut stream = AnnotatedStream::new(tokens);
+    /// stream.annotate(4, "greeting");
+    /// stream.transform(minify);
+    /// assert_eq!(stream.annotations().collect::<Vec<_>>(), vec![(3, &"greeting")]);
+    /// assert_eq!(stream.tokens()[3], Token::text("Bold"));
+    /// ```
+    pub fn transform<F: FnOnce(&[Token]) -> Vec<Token>>(&mut self, f: F) -> Vec<T> {
+        let tokens = f(&self.tokens);
+        let mut old = std::mem::take(&mut self.annotations).into_iter();
+        let mut annotations: Vec<Option<T>> = Vec::with_capacity(tokens.len());
+        let mut dropped = Vec::new();
+        // The old tokens up to `carried` have been carried over
+        let mut carried = 0;
+        let end = Edit::Replace {
+            a: self.tokens.len()..self.tokens.len(),
+            b: tokens.len()..tokens.len(),
+        };
+        for edit in diff(&self.tokens, &tokens).into_iter().chain(Some(end)) {
+            let (removed, added) = match edit {
+                Edit::Delete { a, b } => (a, b..b),
+                Edit::Insert { a, b } => (a..a, b),
+                Edit::Replace { a, b } => (a, b),
+            };
+            // The unchanged tokens before the edit
+            annotations.extend(old.by_ref().take(removed.start - carried));
+            let mut moved: Vec<Option<T>> = old.by_ref().take(removed.len()).collect();
+            let rest = moved.split_off(std::cmp::min(moved.len(), added.len()));
+            dropped.extend(rest.into_iter().flatten());
+            moved.resize_with(added.len(), || None);
+            annotations.extend(moved);
+            carried = removed.end;
+        }
+        self.tokens = tokens;
+        self.annotations = annotations;
+        dropped
+    }
+
+    /// Serialize the stream, as `write_rtf` does, along with the byte range
+    /// of the output each token was written to
+    ///
+    /// The ranges don't include the delimiting spaces and line breaks
+    /// written between tokens.
+    pub fn write_rtf(
+        &self,
+        options: &WriteOptions,
+    ) -> Result<(Vec<u8>, Vec<Range<usize>>), WriteError> {
+        let mut encoder = Encoder::new(options.clone());
+        let mut rtf = Vec::new();
+        let mut spans = Vec::with_capacity(self.tokens.len());
+        for token in &self.tokens {
+            spans.push(encoder.encode_token(token, &mut rtf)?);
+        }
+        Ok((rtf, spans))
+    }
+
+    /// The tokens, and the data attached to each
+    pub fn into_parts(self) -> (Vec<Token>, Vec<Option<T>>) {
+        (self.tokens, self.annotations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redact::{redact, Pattern, ReplacementStyle};
+    use tokenizer::parse;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Label {
+        Name,
+        Greeting,
+        Closing,
+    }
+
+    #[test]
+    fn test_annotated_stream() {
+        let tokens = parse(b"{\\rtf1 Hello {\\b Jane Roe}\\par Bye}").unwrap();
+        let mut stream = AnnotatedStream::new(tokens);
+        stream.annotate(2, Label::Greeting);
+        stream.annotate(5, Label::Name);
+        stream.annotate(8, Label::Closing);
+
+        // The name is redacted in place, and keeps its label
+        let dropped = stream.transform(|tokens| {
+            redact(
+                tokens,
+                &Pattern::Text("Jane Roe".to_string()),
+                &ReplacementStyle::Char('x'),
+            )
+        });
+        assert!(dropped.is_empty());
+        assert_eq!(stream.tokens()[5], Token::text("xxxxxxxx"));
+        assert_eq!(stream.annotation(5), Some(&Label::Name));
+
+        // Tokens added before a label move it along
+        stream.insert(1, vec![Token::word("ansi"), Token::word("deff0")]);
+        let labelled: Vec<_> = stream
+            .annotations()
+            .map(|(index, label)| (index, *label))
+            .collect();
+        assert_eq!(
+            labelled,
+            vec![(4, Label::Greeting), (7, Label::Name), (10, Label::Closing)]
+        );
+
+        // Removing the group hands back the name's label
+        assert_eq!(stream.remove(5..9), vec![Label::Name]);
+        assert_eq!(stream.annotation(6), Some(&Label::Closing));
+        let dropped = stream.transform(|tokens| tokens[..4].to_vec());
+        assert_eq!(dropped, vec![Label::Greeting, Label::Closing]);
+        assert_eq!(stream.annotations().count(), 0);
+    }
+
+    #[test]
+    fn test_annotated_write() {
+        let tokens = parse(b"{\\rtf1\\b Bold\\b0  plain}").unwrap();
+        let mut stream = AnnotatedStream::new(tokens);
+        stream.annotate(3, "bold");
+        let (rtf, spans) = stream.write_rtf(&WriteOptions::default()).unwrap();
+        assert_eq!(rtf, b"{\\rtf1\\b Bold\\b0  plain}".to_vec());
+        let (index, _) = stream.annotations().next().unwrap();
+        assert_eq!(&rtf[spans[index].clone()], b"Bold");
+        assert_eq!(&rtf[spans[5].clone()], b" plain");
+    }
+}
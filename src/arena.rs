@@ -0,0 +1,171 @@
+// Arena-backed tokenization
+//
+// `parse` gives every control word name, text run and `\bin` payload its own
+// heap allocation, and documents dense with control words spend much of their
+// tokenizing time in the allocator.  `parse_in` copies those payloads into a
+// bump allocated arena instead, and the tokens borrow them from it, so that
+// tokenizing makes a handful of large allocations, all freed at once with
+// the arena.
+//
+
+use bumpalo::collections::Vec as ArenaVec;
+use bumpalo::Bump;
+use nom::types::CompleteByteSlice as Input;
+
+use raw::{read_raw_token, RawToken};
+use tokenizer::{control_word, next_token, ParseError, Token};
+
+/// A token whose payload is borrowed from an arena
+///
+/// The variants are those of `Token`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ArenaToken<'a> {
+    ControlSymbol(char),
+    ControlWord { name: &'a str, arg: Option<i64> },
+    HexByte(u8),
+    Unicode { codepoint: i32 },
+    ControlBin(&'a [u8]),
+    Text(&'a [u8]),
+    StartGroup,
+    EndGroup,
+    Newline,
+}
+
+impl<'a> ArenaToken<'a> {
+    /// Copy a token into the arena
+    pub fn from_token(arena: &'a Bump, token: &Token) -> Self {
+        match token {
+            Token::ControlSymbol(c) => ArenaToken::ControlSymbol(*c),
+            Token::ControlWord { name, arg } => ArenaToken::ControlWord {
+                name: arena.alloc_str(name),
+                arg: *arg,
+            },
+            Token::HexByte(byte) => ArenaToken::HexByte(*byte),
+            Token::Unicode { codepoint } => ArenaToken::Unicode {
+                codepoint: *codepoint,
+            },
+            Token::ControlBin(data) => ArenaToken::ControlBin(arena.alloc_slice_copy(data)),
+            Token::Text(data) => ArenaToken::Text(arena.alloc_slice_copy(data)),
+            Token::StartGroup => ArenaToken::StartGroup,
+            Token::EndGroup => ArenaToken::EndGroup,
+            Token::Newline => ArenaToken::Newline,
+        }
+    }
+
+    /// An owned copy of the token
+    pub fn to_token(&self) -> Token {
+        match *self {
+            ArenaToken::ControlSymbol(c) => Token::ControlSymbol(c),
+            ArenaToken::ControlWord { name, arg } => control_word(name, arg),
+            ArenaToken::HexByte(byte) => Token::HexByte(byte),
+            ArenaToken::Unicode { codepoint } => Token::Unicode { codepoint },
+            ArenaToken::ControlBin(data) => Token::ControlBin(data.to_vec()),
+            ArenaToken::Text(data) => Token::Text(data.to_vec()),
+            ArenaToken::StartGroup => Token::StartGroup,
+            ArenaToken::EndGroup => Token::EndGroup,
+            ArenaToken::Newline => Token::Newline,
+        }
+    }
+}
+
+fn arena_token<'a>(arena: &'a Bump, token: RawToken) -> ArenaToken<'a> {
+    match token {
+        RawToken::HexByte(byte) => ArenaToken::HexByte(byte),
+        RawToken::Symbol(c) => ArenaToken::ControlSymbol(c),
+        RawToken::Bin(data) => ArenaToken::ControlBin(arena.alloc_slice_copy(data)),
+        // \uN is the only control word that becomes another kind of token,
+        // which doesn't need its name kept
+        RawToken::Word("u", Some(arg)) => {
+            ArenaToken::from_token(arena, &control_word("u", Some(arg)))
+        }
+        RawToken::Word(name, arg) => ArenaToken::ControlWord {
+            name: arena.alloc_str(name),
+            arg,
+        },
+        RawToken::StartGroup => ArenaToken::StartGroup,
+        RawToken::EndGroup => ArenaToken::EndGroup,
+        RawToken::Newline => ArenaToken::Newline,
+        RawToken::Text(data) => ArenaToken::Text(arena.alloc_slice_copy(data)),
+    }
+}
+
+/// Tokenize a document into an arena
+///
+/// The tokens are those `parse` gives, with their payloads and the token list
+/// itself allocated in `arena`.
+pub fn parse_in<'a>(
+    arena: &'a Bump,
+    bytes: &[u8],
+) -> Result<ArenaVec<'a, ArenaToken<'a>>, ParseError> {
+    let mut tokens = ArenaVec::with_capacity_in(bytes.len() / 8, arena);
+    let mut input = Input(bytes);
+    loop {
+        match read_raw_token(input) {
+            Ok((rest, _)) if rest.len() == input.len() => break,
+            Ok((rest, token)) => {
+                tokens.push(arena_token(arena, token));
+                input = rest;
+            }
+            // Overflowing arguments and errors are rare, so they're left to
+            // the tokenizer to handle as `parse` does
+            Err(_) => match next_token(bytes, input)? {
+                Some((rest, token, _)) => {
+                    tokens.push(ArenaToken::from_token(arena, &token));
+                    input = rest;
+                }
+                None => break,
+            },
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    fn assert_matches_parse(rtf: &[u8]) {
+        let arena = Bump::new();
+        let tokens: Vec<Token> = parse_in(&arena, rtf)
+            .unwrap()
+            .iter()
+            .map(ArenaToken::to_token)
+            .collect();
+        assert_eq!(tokens, parse(rtf).unwrap());
+    }
+
+    #[test]
+    fn test_parse_in() {
+        assert_matches_parse(
+            b"{\\rtf1\\ansi{\\fonttbl{\\f0 Arial;}}\\u8212?\\u-3913 \\'e9\\~\\{x\\}\r\n\\bin3 {}}\\binary y}",
+        );
+        assert_matches_parse(b"{\\rtf1 big\\fs99999999999 arg\\li- x\\'zz never read}");
+        assert_matches_parse(include_bytes!("../tests/sample.rtf"));
+    }
+
+    #[test]
+    fn test_arena_tokens() {
+        let arena = Bump::new();
+        let tokens = parse_in(&arena, b"{\\rtf1\\b bold}").unwrap();
+        assert_eq!(
+            tokens.as_slice(),
+            &[
+                ArenaToken::StartGroup,
+                ArenaToken::ControlWord {
+                    name: "rtf",
+                    arg: Some(1)
+                },
+                ArenaToken::ControlWord {
+                    name: "b",
+                    arg: None
+                },
+                ArenaToken::Text(b"bold"),
+                ArenaToken::EndGroup,
+            ]
+        );
+        let token = Token::Text(b"x".to_vec());
+        assert_eq!(ArenaToken::from_token(&arena, &token).to_token(), token);
+    }
+}
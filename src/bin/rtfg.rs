@@ -0,0 +1,143 @@
+// rtfg: command line front end for scripting and triage
+//
+// Built with the `cli` feature:
+//
+//     cargo install rtf-grimoire --features cli
+//
+// Arguments are parsed by hand, to keep the crate free of dependencies that
+// need fetching.
+//
+
+extern crate rtf_grimoire;
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process;
+
+use rtf_grimoire::json::tokens_to_json;
+use rtf_grimoire::pict::pictures;
+use rtf_grimoire::sanitize::sanitize;
+use rtf_grimoire::{extract_text, parse, write_rtf, Token, WriteOptions};
+
+const USAGE: &str = "\
+usage: rtfg <command> [options] [FILE]
+
+Reads FILE, or standard input if no file is given.
+
+commands:
+    tokenize [--json | --debug]   print the document's tokens (default --json)
+    text                          print the document's plain text
+    images [-o DIR]               write each embedded picture to DIR
+                                  (default: the current directory)
+    sanitize                      print the document with active content
+                                  (objects, DDE and include fields) removed
+";
+
+fn fail(message: &str) -> ! {
+    eprintln!("rtfg: {}", message);
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    eprint!("{}", USAGE);
+    process::exit(2);
+}
+
+struct Options {
+    debug: bool,
+    output_dir: PathBuf,
+    file: Option<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Options {
+    let mut options = Options {
+        debug: false,
+        output_dir: PathBuf::from("."),
+        file: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.debug = false,
+            "--debug" => options.debug = true,
+            "-o" | "--output" => match args.next() {
+                Some(dir) => options.output_dir = PathBuf::from(dir),
+                None => usage(),
+            },
+            "-h" | "--help" => usage(),
+            "-" if options.file.is_none() => {}
+            _ if arg.starts_with('-') => fail(&format!("unknown option {}", arg)),
+            _ if options.file.is_none() => options.file = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    options
+}
+
+fn read_tokens(file: &Option<PathBuf>) -> Vec<Token> {
+    let mut bytes = Vec::new();
+    let read = match file {
+        Some(path) => fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)),
+        None => io::stdin().read_to_end(&mut bytes),
+    };
+    if let Err(error) = read {
+        fail(&error.to_string());
+    }
+    parse(&bytes).unwrap_or_else(|error| fail(&error.to_string()))
+}
+
+fn write_stdout(bytes: &[u8]) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    if let Err(error) = stdout.write_all(bytes).and_then(|_| stdout.flush()) {
+        // A closed pipe, from `rtfg ... | head`, isn't worth complaining about
+        if error.kind() != io::ErrorKind::BrokenPipe {
+            fail(&error.to_string());
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some(command @ "tokenize")
+        | Some(command @ "text")
+        | Some(command @ "images")
+        | Some(command @ "sanitize") => command,
+        _ => usage(),
+    };
+    let options = parse_args(&args[1..]);
+    let tokens = read_tokens(&options.file);
+
+    match command {
+        "tokenize" => {
+            let dump = if options.debug {
+                tokens
+                    .iter()
+                    .map(|token| format!("{:?}\n", token))
+                    .collect()
+            } else {
+                tokens_to_json(&tokens) + "\n"
+            };
+            write_stdout(dump.as_bytes());
+        }
+        "text" => write_stdout(extract_text(&tokens).as_bytes()),
+        "images" => {
+            for (index, picture) in pictures(&tokens).iter().enumerate() {
+                let name = format!("picture-{}.{}", index + 1, picture.detected_format().extension());
+                let path = options.output_dir.join(name);
+                if let Err(error) = fs::write(&path, &picture.data) {
+                    fail(&format!("{}: {}", path.display(), error));
+                }
+                println!("{}", path.display());
+            }
+        }
+        "sanitize" => match write_rtf(&sanitize(&tokens), &WriteOptions::default()) {
+            Ok(rtf) => write_stdout(&rtf),
+            Err(error) => fail(&error.to_string()),
+        },
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,447 @@
+// Building documents
+//
+// `RtfBuilder` appends tokens one control word or run of text at a time,
+// escaping the text as it goes.  `DocumentBuilder` is built on it, and takes
+// care of what's hard to get right by hand: the header, and the font and
+// color tables that formatting refers to by index.
+//
+
+use std;
+
+use document::{Alignment, Color};
+use tokenizer::Token;
+use writer::{write_rtf, WriteError, WriteOptions};
+
+/// Builds a token stream, escaping text as it's added
+///
+/// ```
+/// use rtf_grimoire::builder::RtfBuilder;
+///
+/// let mut rtf = RtfBuilder::new();
+/// rtf.start_group().word_arg("rtf", 1).word("b").text("{Hi}").end_group();
+/// assert_eq!(rtf.to_rtf().unwrap(), b"{\\rtf1\\b\\{Hi\\}}".to_vec());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RtfBuilder {
+    tokens: Vec<Token>,
+}
+
+impl RtfBuilder {
+    pub fn new() -> Self {
+        RtfBuilder::default()
+    }
+
+    pub fn token(&mut self, token: Token) -> &mut Self {
+        self.tokens.push(token);
+        self
+    }
+
+    pub fn start_group(&mut self) -> &mut Self {
+        self.token(Token::StartGroup)
+    }
+
+    pub fn end_group(&mut self) -> &mut Self {
+        self.token(Token::EndGroup)
+    }
+
+    /// A control word without an argument, like `\b`
+    pub fn word(&mut self, name: &str) -> &mut Self {
+        self.token(Token::word(name))
+    }
+
+    /// A control word with an argument, like `\fs24`
+    pub fn word_arg(&mut self, name: &str, arg: i64) -> &mut Self {
+        self.token(Token::word_arg(name, arg))
+    }
+
+    /// Open a destination group, `{\name`, or `{\*\name` if readers that
+    /// don't know it are to skip it
+    pub fn destination(&mut self, name: &str, ignorable: bool) -> &mut Self {
+        self.start_group();
+        if ignorable {
+            self.token(Token::ControlSymbol('*'));
+        }
+        self.word(name)
+    }
+
+    /// Text, escaped: `{`, `}` and `\` become control symbols, tabs `\tab`,
+    /// line breaks `\line`, and characters outside ASCII `\uN` escapes with
+    /// a `?` fallback, which assumes the `\uc1` default is in effect
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.tokens.extend(text_tokens(text, 1));
+        self
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+
+    /// Serialize the tokens with the default `WriteOptions`
+    pub fn to_rtf(&self) -> Result<Vec<u8>, WriteError> {
+        write_rtf(&self.tokens, &WriteOptions::default())
+    }
+}
+
+/// Tokens for text, escaped as `RtfBuilder::text` does, with `uc` `?`
+/// fallback characters after each `\uN`
+pub(crate) fn text_tokens(text: &str, uc: usize) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut ascii = Vec::new();
+    for c in text.chars() {
+        let token = match c {
+            '{' | '}' | '\\' => Token::ControlSymbol(c),
+            '\t' => Token::word("tab"),
+            '\n' => Token::word("line"),
+            '\r' => continue,
+            c if c.is_ascii() => {
+                ascii.push(c as u8);
+                continue;
+            }
+            c => {
+                if !ascii.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut ascii)));
+                }
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    tokens.push(Token::Unicode {
+                        codepoint: i32::from(*unit),
+                    });
+                    if uc > 0 {
+                        tokens.push(Token::Text(vec![b'?'; uc]));
+                    }
+                }
+                continue;
+            }
+        };
+        if !ascii.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut ascii)));
+        }
+        tokens.push(token);
+    }
+    if !ascii.is_empty() {
+        tokens.push(Token::Text(ascii));
+    }
+    tokens
+}
+
+// The font every document starts with, as \f0
+const DEFAULT_FONT: &str = "Times New Roman";
+
+/// Builds a whole document: paragraphs of formatted text, with the header,
+/// font table and color table written to match
+///
+/// ```
+/// use rtf_grimoire::builder::DocumentBuilder;
+/// use rtf_grimoire::document::Color;
+/// use rtf_grimoire::extract_text;
+///
+/// let mut doc = DocumentBuilder::new();
+/// doc.paragraph().bold().text("Hi").end();
+/// let red = Color { red: 255, green: 0, blue: 0 };
+/// doc.paragraph().font("Arial").color(red).text("there").end();
+/// assert_eq!(extract_text(&doc.tokens()), "Hi\nthere\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentBuilder {
+    fonts: Vec<String>,
+    colors: Vec<Color>,
+    title: Option<String>,
+    author: Option<String>,
+    body: RtfBuilder,
+}
+
+impl Default for DocumentBuilder {
+    fn default() -> Self {
+        DocumentBuilder {
+            fonts: vec![DEFAULT_FONT.to_string()],
+            colors: Vec::new(),
+            title: None,
+            author: None,
+            body: RtfBuilder::new(),
+        }
+    }
+}
+
+impl DocumentBuilder {
+    pub fn new() -> Self {
+        DocumentBuilder::default()
+    }
+
+    /// The font table index of a font, adding it to the table if it isn't
+    /// there yet
+    ///
+    /// Index 0 is the document's default font, Times New Roman.
+    pub fn font_index(&mut self, name: &str) -> i32 {
+        let index = match self.fonts.iter().position(|font| font == name) {
+            Some(index) => index,
+            None => {
+                self.fonts.push(name.to_string());
+                self.fonts.len() - 1
+            }
+        };
+        index as i32
+    }
+
+    /// The color table index of a color, adding it to the table if it isn't
+    /// there yet
+    ///
+    /// Index 0 is left for the reader's default color, so the first color
+    /// added is 1.
+    pub fn color_index(&mut self, color: Color) -> usize {
+        match self.colors.iter().position(|known| *known == color) {
+            Some(index) => index + 1,
+            None => {
+                self.colors.push(color);
+                self.colors.len()
+            }
+        }
+    }
+
+    /// Set the title in the document's `\info` group
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Set the author in the document's `\info` group
+    pub fn author(&mut self, author: &str) -> &mut Self {
+        self.author = Some(author.to_string());
+        self
+    }
+
+    /// Start a paragraph, which is added to the document by
+    /// `ParagraphBuilder::end`
+    pub fn paragraph(&mut self) -> ParagraphBuilder<'_> {
+        self.body.word("pard").word("plain");
+        ParagraphBuilder { doc: self }
+    }
+
+    fn header(&self) -> RtfBuilder {
+        let mut header = RtfBuilder::new();
+        header
+            .start_group()
+            .word_arg("rtf", 1)
+            .word("ansi")
+            .word_arg("ansicpg", 1252)
+            .word_arg("deff", 0);
+        header.destination("fonttbl", false);
+        for (index, name) in self.fonts.iter().enumerate() {
+            header
+                .start_group()
+                .word_arg("f", index as i64)
+                .word("fnil")
+                .text(name)
+                .text(";")
+                .end_group();
+        }
+        header.end_group();
+        if !self.colors.is_empty() {
+            header.destination("colortbl", false).text(";");
+            for color in &self.colors {
+                header
+                    .word_arg("red", i64::from(color.red))
+                    .word_arg("green", i64::from(color.green))
+                    .word_arg("blue", i64::from(color.blue))
+                    .text(";");
+            }
+            header.end_group();
+        }
+        if self.title.is_some() || self.author.is_some() {
+            header.destination("info", false);
+            let fields = [("title", &self.title), ("author", &self.author)];
+            for (name, value) in fields.iter() {
+                if let Some(value) = value {
+                    header.destination(name, false).text(value).end_group();
+                }
+            }
+            header.end_group();
+        }
+        header
+    }
+
+    /// The tokens of the document
+    pub fn tokens(&self) -> Vec<Token> {
+        let mut tokens = self.header().into_tokens();
+        tokens.extend_from_slice(self.body.tokens());
+        tokens.push(Token::EndGroup);
+        tokens
+    }
+
+    /// Serialize the document with the default `WriteOptions`
+    pub fn to_rtf(&self) -> Result<Vec<u8>, WriteError> {
+        write_rtf(&self.tokens(), &WriteOptions::default())
+    }
+}
+
+/// A paragraph being added to a `DocumentBuilder`
+///
+/// Character formatting applies to the text added after it, and paragraph
+/// formatting to the whole paragraph, wherever it's set.  Each paragraph
+/// starts out plain.
+#[must_use = "a paragraph isn't finished until `end` is called"]
+pub struct ParagraphBuilder<'a> {
+    doc: &'a mut DocumentBuilder,
+}
+
+impl<'a> ParagraphBuilder<'a> {
+    fn word(self, name: &str) -> Self {
+        self.doc.body.word(name);
+        self
+    }
+
+    fn word_arg(self, name: &str, arg: i64) -> Self {
+        self.doc.body.word_arg(name, arg);
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.word("b")
+    }
+
+    pub fn italic(self) -> Self {
+        self.word("i")
+    }
+
+    pub fn underline(self) -> Self {
+        self.word("ul")
+    }
+
+    pub fn strike(self) -> Self {
+        self.word("strike")
+    }
+
+    /// Turn off all the character formatting set so far
+    pub fn plain(self) -> Self {
+        self.word("plain")
+    }
+
+    /// Set the font, by name
+    pub fn font(self, name: &str) -> Self {
+        let index = self.doc.font_index(name);
+        self.word_arg("f", i64::from(index))
+    }
+
+    /// Set the font size, in half-points
+    pub fn size(self, half_points: i32) -> Self {
+        self.word_arg("fs", i64::from(half_points))
+    }
+
+    /// Set the color of the text
+    pub fn color(self, color: Color) -> Self {
+        let index = self.doc.color_index(color);
+        self.word_arg("cf", index as i64)
+    }
+
+    pub fn align(self, alignment: Alignment) -> Self {
+        self.word(match alignment {
+            Alignment::Left => "ql",
+            Alignment::Center => "qc",
+            Alignment::Right => "qr",
+            Alignment::Justified => "qj",
+        })
+    }
+
+    /// Set the left indent, in twips
+    pub fn left_indent(self, twips: i32) -> Self {
+        self.word_arg("li", i64::from(twips))
+    }
+
+    /// Set the space after the paragraph, in twips
+    pub fn space_after(self, twips: i32) -> Self {
+        self.word_arg("sa", i64::from(twips))
+    }
+
+    /// Add text, escaped as `RtfBuilder::text` does
+    pub fn text(self, text: &str) -> Self {
+        self.doc.body.text(text);
+        self
+    }
+
+    /// Finish the paragraph
+    pub fn end(self) -> &'a mut DocumentBuilder {
+        self.doc.body.word("par");
+        self.doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::{Block, Document};
+    use tokenizer::parse;
+    use validate::{validate, Level};
+
+    #[test]
+    fn test_rtf_builder() {
+        let mut rtf = RtfBuilder::new();
+        rtf.destination("generator", true)
+            .text("caf\u{e9}\t\u{1f600}\n")
+            .end_group();
+        assert_eq!(
+            rtf.to_rtf().unwrap(),
+            b"{\\*\\generator caf\\u233?\\tab\\u-10179?\\u-8704?\\line}".to_vec()
+        );
+        assert_eq!(parse(&rtf.to_rtf().unwrap()).unwrap(), rtf.into_tokens());
+    }
+
+    #[test]
+    fn test_document_builder() {
+        let red = Color {
+            red: 255,
+            green: 0,
+            blue: 0,
+        };
+        let mut doc = DocumentBuilder::new();
+        doc.title("Notes").author("A. Writer");
+        doc.paragraph()
+            .align(Alignment::Center)
+            .bold()
+            .text("Title")
+            .end()
+            .paragraph()
+            .font("Arial")
+            .color(red)
+            .text("red ")
+            .plain()
+            .font("Arial")
+            .italic()
+            .text("{Arial}")
+            .end();
+        let rtf = doc.to_rtf().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&rtf),
+            "{\\rtf1\\ansi\\ansicpg1252\\deff0{\\fonttbl{\\f0\\fnil Times New Roman;}\
+             {\\f1\\fnil Arial;}}{\\colortbl;\\red255\\green0\\blue0;}\
+             {\\info{\\title Notes}{\\author A. Writer}}\
+             \\pard\\plain\\qc\\b Title\\par\
+             \\pard\\plain\\f1\\cf1 red \\plain\\f1\\i\\{Arial\\}\\par}"
+        );
+        let tokens = parse(&rtf).unwrap();
+        assert_eq!(validate(&tokens, Level::Strict), Vec::new());
+
+        let document = Document::from_tokens(&tokens);
+        assert_eq!(document.font(1).unwrap().name, "Arial");
+        assert_eq!(document.colors, vec![None, Some(red)]);
+        let paragraphs: Vec<_> = document
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Paragraph(paragraph) => Some(paragraph),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(paragraphs[0].format.alignment, Alignment::Center);
+        assert!(paragraphs[0].runs[0].format.bold);
+        let runs = &paragraphs[1].runs;
+        assert_eq!(
+            (runs[0].format.color, runs[0].format.font),
+            (Some(1), Some(1))
+        );
+        assert_eq!(runs[1].text, "{Arial}");
+        assert!(runs[1].format.italic && runs[1].format.color.is_none());
+    }
+}
@@ -0,0 +1,586 @@
+// Code pages
+//
+// Text bytes are interpreted in the code page of the font they're in, or of
+// the document (`\ansicpgN`) for fonts that don't name a character set.
+// Single byte code pages are decoded by table, including the Mac and IBM PC
+// ones of old `\mac`, `\pc` and `\pca` documents; anything this module doesn't
+// know is decoded as Windows-1252, the code page of the vast majority of
+// documents.  A `CharsetDecoder` can take over any code page, including the
+// double byte ones of East Asian documents.
+//
+
+#[cfg(feature = "encoding_rs")]
+use encoding_rs;
+use std;
+
+use text::decode_cp1252;
+
+/// The code page of the Windows-1252 "ANSI" character set
+pub(crate) const DEFAULT_CODE_PAGE: u32 = 1252;
+
+// Windows-1251 from 0x80 to 0xBF; 0xC0-0xFF are U+0410-U+044F in order
+const CP1251_HIGH: [char; 64] = [
+    // 0x80
+    '\u{0402}', '\u{0403}', '\u{201a}', '\u{0453}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{20ac}', '\u{2030}', '\u{0409}', '\u{2039}', '\u{040a}', '\u{040c}', '\u{040b}', '\u{040f}',
+    // 0x90
+    '\u{0452}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{fffd}', '\u{2122}', '\u{0459}', '\u{203a}', '\u{045a}', '\u{045c}', '\u{045b}', '\u{045f}',
+    // 0xA0
+    '\u{00a0}', '\u{040e}', '\u{045e}', '\u{0408}', '\u{00a4}', '\u{0490}', '\u{00a6}', '\u{00a7}',
+    '\u{0401}', '\u{00a9}', '\u{0404}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{0407}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{0406}', '\u{0456}', '\u{0491}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{0451}', '\u{2116}', '\u{0454}', '\u{00bb}', '\u{0458}', '\u{0405}', '\u{0455}', '\u{0457}',
+];
+
+// Windows-1250 (Central European) from 0x80 to 0xFF.  Unassigned positions
+// decode to U+FFFD.
+const CP1250_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{fffd}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{fffd}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{015a}', '\u{0164}', '\u{017d}', '\u{0179}',
+    // 0x90
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{fffd}', '\u{2122}', '\u{0161}', '\u{203a}', '\u{015b}', '\u{0165}', '\u{017e}', '\u{017a}',
+    // 0xA0
+    '\u{00a0}', '\u{02c7}', '\u{02d8}', '\u{0141}', '\u{00a4}', '\u{0104}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{015e}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{017b}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{02db}', '\u{0142}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00b8}', '\u{0105}', '\u{015f}', '\u{00bb}', '\u{013d}', '\u{02dd}', '\u{013e}', '\u{017c}',
+    // 0xC0
+    '\u{0154}', '\u{00c1}', '\u{00c2}', '\u{0102}', '\u{00c4}', '\u{0139}', '\u{0106}', '\u{00c7}',
+    '\u{010c}', '\u{00c9}', '\u{0118}', '\u{00cb}', '\u{011a}', '\u{00cd}', '\u{00ce}', '\u{010e}',
+    // 0xD0
+    '\u{0110}', '\u{0143}', '\u{0147}', '\u{00d3}', '\u{00d4}', '\u{0150}', '\u{00d6}', '\u{00d7}',
+    '\u{0158}', '\u{016e}', '\u{00da}', '\u{0170}', '\u{00dc}', '\u{00dd}', '\u{0162}', '\u{00df}',
+    // 0xE0
+    '\u{0155}', '\u{00e1}', '\u{00e2}', '\u{0103}', '\u{00e4}', '\u{013a}', '\u{0107}', '\u{00e7}',
+    '\u{010d}', '\u{00e9}', '\u{0119}', '\u{00eb}', '\u{011b}', '\u{00ed}', '\u{00ee}', '\u{010f}',
+    // 0xF0
+    '\u{0111}', '\u{0144}', '\u{0148}', '\u{00f3}', '\u{00f4}', '\u{0151}', '\u{00f6}', '\u{00f7}',
+    '\u{0159}', '\u{016f}', '\u{00fa}', '\u{0171}', '\u{00fc}', '\u{00fd}', '\u{0163}', '\u{02d9}',
+];
+
+// Windows-1253 (Greek) from 0x80 to 0xFF.  Unassigned positions decode
+// to U+FFFD.
+const CP1253_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{fffd}', '\u{2030}', '\u{fffd}', '\u{2039}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0x90
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{fffd}', '\u{2122}', '\u{fffd}', '\u{203a}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0xA0
+    '\u{00a0}', '\u{0385}', '\u{0386}', '\u{00a3}', '\u{00a4}', '\u{00a5}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{fffd}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{2015}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{00b2}', '\u{00b3}', '\u{0384}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{0388}', '\u{0389}', '\u{038a}', '\u{00bb}', '\u{038c}', '\u{00bd}', '\u{038e}', '\u{038f}',
+    // 0xC0
+    '\u{0390}', '\u{0391}', '\u{0392}', '\u{0393}', '\u{0394}', '\u{0395}', '\u{0396}', '\u{0397}',
+    '\u{0398}', '\u{0399}', '\u{039a}', '\u{039b}', '\u{039c}', '\u{039d}', '\u{039e}', '\u{039f}',
+    // 0xD0
+    '\u{03a0}', '\u{03a1}', '\u{fffd}', '\u{03a3}', '\u{03a4}', '\u{03a5}', '\u{03a6}', '\u{03a7}',
+    '\u{03a8}', '\u{03a9}', '\u{03aa}', '\u{03ab}', '\u{03ac}', '\u{03ad}', '\u{03ae}', '\u{03af}',
+    // 0xE0
+    '\u{03b0}', '\u{03b1}', '\u{03b2}', '\u{03b3}', '\u{03b4}', '\u{03b5}', '\u{03b6}', '\u{03b7}',
+    '\u{03b8}', '\u{03b9}', '\u{03ba}', '\u{03bb}', '\u{03bc}', '\u{03bd}', '\u{03be}', '\u{03bf}',
+    // 0xF0
+    '\u{03c0}', '\u{03c1}', '\u{03c2}', '\u{03c3}', '\u{03c4}', '\u{03c5}', '\u{03c6}', '\u{03c7}',
+    '\u{03c8}', '\u{03c9}', '\u{03ca}', '\u{03cb}', '\u{03cc}', '\u{03cd}', '\u{03ce}', '\u{fffd}',
+];
+
+// Windows-1254 (Turkish) from 0x80 to 0xFF.  Unassigned positions decode
+// to U+FFFD.
+const CP1254_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0x90
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02dc}', '\u{2122}', '\u{0161}', '\u{203a}', '\u{0153}', '\u{fffd}', '\u{fffd}', '\u{0178}',
+    // 0xA0
+    '\u{00a0}', '\u{00a1}', '\u{00a2}', '\u{00a3}', '\u{00a4}', '\u{00a5}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{00aa}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{00af}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{00b2}', '\u{00b3}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00b8}', '\u{00b9}', '\u{00ba}', '\u{00bb}', '\u{00bc}', '\u{00bd}', '\u{00be}', '\u{00bf}',
+    // 0xC0
+    '\u{00c0}', '\u{00c1}', '\u{00c2}', '\u{00c3}', '\u{00c4}', '\u{00c5}', '\u{00c6}', '\u{00c7}',
+    '\u{00c8}', '\u{00c9}', '\u{00ca}', '\u{00cb}', '\u{00cc}', '\u{00cd}', '\u{00ce}', '\u{00cf}',
+    // 0xD0
+    '\u{011e}', '\u{00d1}', '\u{00d2}', '\u{00d3}', '\u{00d4}', '\u{00d5}', '\u{00d6}', '\u{00d7}',
+    '\u{00d8}', '\u{00d9}', '\u{00da}', '\u{00db}', '\u{00dc}', '\u{0130}', '\u{015e}', '\u{00df}',
+    // 0xE0
+    '\u{00e0}', '\u{00e1}', '\u{00e2}', '\u{00e3}', '\u{00e4}', '\u{00e5}', '\u{00e6}', '\u{00e7}',
+    '\u{00e8}', '\u{00e9}', '\u{00ea}', '\u{00eb}', '\u{00ec}', '\u{00ed}', '\u{00ee}', '\u{00ef}',
+    // 0xF0
+    '\u{011f}', '\u{00f1}', '\u{00f2}', '\u{00f3}', '\u{00f4}', '\u{00f5}', '\u{00f6}', '\u{00f7}',
+    '\u{00f8}', '\u{00f9}', '\u{00fa}', '\u{00fb}', '\u{00fc}', '\u{0131}', '\u{015f}', '\u{00ff}',
+];
+
+// Windows-1255 (Hebrew) from 0x80 to 0xFF.  Unassigned positions decode
+// to U+FFFD.
+const CP1255_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{fffd}', '\u{2039}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0x90
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02dc}', '\u{2122}', '\u{fffd}', '\u{203a}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0xA0
+    '\u{00a0}', '\u{00a1}', '\u{00a2}', '\u{00a3}', '\u{20aa}', '\u{00a5}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{00d7}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{00af}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{00b2}', '\u{00b3}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00b8}', '\u{00b9}', '\u{00f7}', '\u{00bb}', '\u{00bc}', '\u{00bd}', '\u{00be}', '\u{00bf}',
+    // 0xC0
+    '\u{05b0}', '\u{05b1}', '\u{05b2}', '\u{05b3}', '\u{05b4}', '\u{05b5}', '\u{05b6}', '\u{05b7}',
+    '\u{05b8}', '\u{05b9}', '\u{05ba}', '\u{05bb}', '\u{05bc}', '\u{05bd}', '\u{05be}', '\u{05bf}',
+    // 0xD0
+    '\u{05c0}', '\u{05c1}', '\u{05c2}', '\u{05c3}', '\u{05f0}', '\u{05f1}', '\u{05f2}', '\u{05f3}',
+    '\u{05f4}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0xE0
+    '\u{05d0}', '\u{05d1}', '\u{05d2}', '\u{05d3}', '\u{05d4}', '\u{05d5}', '\u{05d6}', '\u{05d7}',
+    '\u{05d8}', '\u{05d9}', '\u{05da}', '\u{05db}', '\u{05dc}', '\u{05dd}', '\u{05de}', '\u{05df}',
+    // 0xF0
+    '\u{05e0}', '\u{05e1}', '\u{05e2}', '\u{05e3}', '\u{05e4}', '\u{05e5}', '\u{05e6}', '\u{05e7}',
+    '\u{05e8}', '\u{05e9}', '\u{05ea}', '\u{fffd}', '\u{fffd}', '\u{200e}', '\u{200f}', '\u{fffd}',
+];
+
+// Windows-1256 (Arabic) from 0x80 to 0xFF
+const CP1256_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{067e}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{0679}', '\u{2039}', '\u{0152}', '\u{0686}', '\u{0698}', '\u{0688}',
+    // 0x90
+    '\u{06af}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{06a9}', '\u{2122}', '\u{0691}', '\u{203a}', '\u{0153}', '\u{200c}', '\u{200d}', '\u{06ba}',
+    // 0xA0
+    '\u{00a0}', '\u{060c}', '\u{00a2}', '\u{00a3}', '\u{00a4}', '\u{00a5}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{06be}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{00af}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{00b2}', '\u{00b3}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00b8}', '\u{00b9}', '\u{061b}', '\u{00bb}', '\u{00bc}', '\u{00bd}', '\u{00be}', '\u{061f}',
+    // 0xC0
+    '\u{06c1}', '\u{0621}', '\u{0622}', '\u{0623}', '\u{0624}', '\u{0625}', '\u{0626}', '\u{0627}',
+    '\u{0628}', '\u{0629}', '\u{062a}', '\u{062b}', '\u{062c}', '\u{062d}', '\u{062e}', '\u{062f}',
+    // 0xD0
+    '\u{0630}', '\u{0631}', '\u{0632}', '\u{0633}', '\u{0634}', '\u{0635}', '\u{0636}', '\u{00d7}',
+    '\u{0637}', '\u{0638}', '\u{0639}', '\u{063a}', '\u{0640}', '\u{0641}', '\u{0642}', '\u{0643}',
+    // 0xE0
+    '\u{00e0}', '\u{0644}', '\u{00e2}', '\u{0645}', '\u{0646}', '\u{0647}', '\u{0648}', '\u{00e7}',
+    '\u{00e8}', '\u{00e9}', '\u{00ea}', '\u{00eb}', '\u{0649}', '\u{064a}', '\u{00ee}', '\u{00ef}',
+    // 0xF0
+    '\u{064b}', '\u{064c}', '\u{064d}', '\u{064e}', '\u{00f4}', '\u{064f}', '\u{0650}', '\u{00f7}',
+    '\u{0651}', '\u{00f9}', '\u{0652}', '\u{00fb}', '\u{00fc}', '\u{200e}', '\u{200f}', '\u{06d2}',
+];
+
+// Windows-1257 (Baltic) from 0x80 to 0xFF.  Unassigned positions decode
+// to U+FFFD.
+const CP1257_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{fffd}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{fffd}', '\u{2030}', '\u{fffd}', '\u{2039}', '\u{fffd}', '\u{00a8}', '\u{02c7}', '\u{00b8}',
+    // 0x90
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{fffd}', '\u{2122}', '\u{fffd}', '\u{203a}', '\u{fffd}', '\u{00af}', '\u{02db}', '\u{fffd}',
+    // 0xA0
+    '\u{00a0}', '\u{fffd}', '\u{00a2}', '\u{00a3}', '\u{00a4}', '\u{fffd}', '\u{00a6}', '\u{00a7}',
+    '\u{00d8}', '\u{00a9}', '\u{0156}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{00c6}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{00b2}', '\u{00b3}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00f8}', '\u{00b9}', '\u{0157}', '\u{00bb}', '\u{00bc}', '\u{00bd}', '\u{00be}', '\u{00e6}',
+    // 0xC0
+    '\u{0104}', '\u{012e}', '\u{0100}', '\u{0106}', '\u{00c4}', '\u{00c5}', '\u{0118}', '\u{0112}',
+    '\u{010c}', '\u{00c9}', '\u{0179}', '\u{0116}', '\u{0122}', '\u{0136}', '\u{012a}', '\u{013b}',
+    // 0xD0
+    '\u{0160}', '\u{0143}', '\u{0145}', '\u{00d3}', '\u{014c}', '\u{00d5}', '\u{00d6}', '\u{00d7}',
+    '\u{0172}', '\u{0141}', '\u{015a}', '\u{016a}', '\u{00dc}', '\u{017b}', '\u{017d}', '\u{00df}',
+    // 0xE0
+    '\u{0105}', '\u{012f}', '\u{0101}', '\u{0107}', '\u{00e4}', '\u{00e5}', '\u{0119}', '\u{0113}',
+    '\u{010d}', '\u{00e9}', '\u{017a}', '\u{0117}', '\u{0123}', '\u{0137}', '\u{012b}', '\u{013c}',
+    // 0xF0
+    '\u{0161}', '\u{0144}', '\u{0146}', '\u{00f3}', '\u{014d}', '\u{00f5}', '\u{00f6}', '\u{00f7}',
+    '\u{0173}', '\u{0142}', '\u{015b}', '\u{016b}', '\u{00fc}', '\u{017c}', '\u{017e}', '\u{02d9}',
+];
+
+// Windows-1258 (Vietnamese) from 0x80 to 0xFF.  Unassigned positions
+// decode to U+FFFD.
+const CP1258_HIGH: [char; 128] = [
+    // 0x80
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{fffd}', '\u{2039}', '\u{0152}', '\u{fffd}', '\u{fffd}', '\u{fffd}',
+    // 0x90
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02dc}', '\u{2122}', '\u{fffd}', '\u{203a}', '\u{0153}', '\u{fffd}', '\u{fffd}', '\u{0178}',
+    // 0xA0
+    '\u{00a0}', '\u{00a1}', '\u{00a2}', '\u{00a3}', '\u{00a4}', '\u{00a5}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{00aa}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{00af}',
+    // 0xB0
+    '\u{00b0}', '\u{00b1}', '\u{00b2}', '\u{00b3}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00b8}', '\u{00b9}', '\u{00ba}', '\u{00bb}', '\u{00bc}', '\u{00bd}', '\u{00be}', '\u{00bf}',
+    // 0xC0
+    '\u{00c0}', '\u{00c1}', '\u{00c2}', '\u{0102}', '\u{00c4}', '\u{00c5}', '\u{00c6}', '\u{00c7}',
+    '\u{00c8}', '\u{00c9}', '\u{00ca}', '\u{00cb}', '\u{0300}', '\u{00cd}', '\u{00ce}', '\u{00cf}',
+    // 0xD0
+    '\u{0110}', '\u{00d1}', '\u{0309}', '\u{00d3}', '\u{00d4}', '\u{01a0}', '\u{00d6}', '\u{00d7}',
+    '\u{00d8}', '\u{00d9}', '\u{00da}', '\u{00db}', '\u{00dc}', '\u{01af}', '\u{0303}', '\u{00df}',
+    // 0xE0
+    '\u{00e0}', '\u{00e1}', '\u{00e2}', '\u{0103}', '\u{00e4}', '\u{00e5}', '\u{00e6}', '\u{00e7}',
+    '\u{00e8}', '\u{00e9}', '\u{00ea}', '\u{00eb}', '\u{0301}', '\u{00ed}', '\u{00ee}', '\u{00ef}',
+    // 0xF0
+    '\u{0111}', '\u{00f1}', '\u{0323}', '\u{00f3}', '\u{00f4}', '\u{01a1}', '\u{00f6}', '\u{00f7}',
+    '\u{00f8}', '\u{00f9}', '\u{00fa}', '\u{00fb}', '\u{00fc}', '\u{01b0}', '\u{20ab}', '\u{00ff}',
+];
+
+// Mac OS Roman from 0x80 to 0xFF
+const MAC_ROMAN_HIGH: [char; 128] = [
+    // 0x80
+    '\u{00c4}', '\u{00c5}', '\u{00c7}', '\u{00c9}', '\u{00d1}', '\u{00d6}', '\u{00dc}', '\u{00e1}',
+    '\u{00e0}', '\u{00e2}', '\u{00e4}', '\u{00e3}', '\u{00e5}', '\u{00e7}', '\u{00e9}', '\u{00e8}',
+    // 0x90
+    '\u{00ea}', '\u{00eb}', '\u{00ed}', '\u{00ec}', '\u{00ee}', '\u{00ef}', '\u{00f1}', '\u{00f3}',
+    '\u{00f2}', '\u{00f4}', '\u{00f6}', '\u{00f5}', '\u{00fa}', '\u{00f9}', '\u{00fb}', '\u{00fc}',
+    // 0xA0
+    '\u{2020}', '\u{00b0}', '\u{00a2}', '\u{00a3}', '\u{00a7}', '\u{2022}', '\u{00b6}', '\u{00df}',
+    '\u{00ae}', '\u{00a9}', '\u{2122}', '\u{00b4}', '\u{00a8}', '\u{2260}', '\u{00c6}', '\u{00d8}',
+    // 0xB0
+    '\u{221e}', '\u{00b1}', '\u{2264}', '\u{2265}', '\u{00a5}', '\u{00b5}', '\u{2202}', '\u{2211}',
+    '\u{220f}', '\u{03c0}', '\u{222b}', '\u{00aa}', '\u{00ba}', '\u{03a9}', '\u{00e6}', '\u{00f8}',
+    // 0xC0
+    '\u{00bf}', '\u{00a1}', '\u{00ac}', '\u{221a}', '\u{0192}', '\u{2248}', '\u{2206}', '\u{00ab}',
+    '\u{00bb}', '\u{2026}', '\u{00a0}', '\u{00c0}', '\u{00c3}', '\u{00d5}', '\u{0152}', '\u{0153}',
+    // 0xD0
+    '\u{2013}', '\u{2014}', '\u{201c}', '\u{201d}', '\u{2018}', '\u{2019}', '\u{00f7}', '\u{25ca}',
+    '\u{00ff}', '\u{0178}', '\u{2044}', '\u{20ac}', '\u{2039}', '\u{203a}', '\u{fb01}', '\u{fb02}',
+    // 0xE0
+    '\u{2021}', '\u{00b7}', '\u{201a}', '\u{201e}', '\u{2030}', '\u{00c2}', '\u{00ca}', '\u{00c1}',
+    '\u{00cb}', '\u{00c8}', '\u{00cd}', '\u{00ce}', '\u{00cf}', '\u{00cc}', '\u{00d3}', '\u{00d4}',
+    // 0xF0
+    '\u{f8ff}', '\u{00d2}', '\u{00da}', '\u{00db}', '\u{00d9}', '\u{0131}', '\u{02c6}', '\u{02dc}',
+    '\u{00af}', '\u{02d8}', '\u{02d9}', '\u{02da}', '\u{00b8}', '\u{02dd}', '\u{02db}', '\u{02c7}',
+];
+
+// IBM PC code page 437 from 0x80 to 0xFF
+const CP437_HIGH: [char; 128] = [
+    // 0x80
+    '\u{00c7}', '\u{00fc}', '\u{00e9}', '\u{00e2}', '\u{00e4}', '\u{00e0}', '\u{00e5}', '\u{00e7}',
+    '\u{00ea}', '\u{00eb}', '\u{00e8}', '\u{00ef}', '\u{00ee}', '\u{00ec}', '\u{00c4}', '\u{00c5}',
+    // 0x90
+    '\u{00c9}', '\u{00e6}', '\u{00c6}', '\u{00f4}', '\u{00f6}', '\u{00f2}', '\u{00fb}', '\u{00f9}',
+    '\u{00ff}', '\u{00d6}', '\u{00dc}', '\u{00a2}', '\u{00a3}', '\u{00a5}', '\u{20a7}', '\u{0192}',
+    // 0xA0
+    '\u{00e1}', '\u{00ed}', '\u{00f3}', '\u{00fa}', '\u{00f1}', '\u{00d1}', '\u{00aa}', '\u{00ba}',
+    '\u{00bf}', '\u{2310}', '\u{00ac}', '\u{00bd}', '\u{00bc}', '\u{00a1}', '\u{00ab}', '\u{00bb}',
+    // 0xB0
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{2561}', '\u{2562}', '\u{2556}',
+    '\u{2555}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255d}', '\u{255c}', '\u{255b}', '\u{2510}',
+    // 0xC0
+    '\u{2514}', '\u{2534}', '\u{252c}', '\u{251c}', '\u{2500}', '\u{253c}', '\u{255e}', '\u{255f}',
+    '\u{255a}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256c}', '\u{2567}',
+    // 0xD0
+    '\u{2568}', '\u{2564}', '\u{2565}', '\u{2559}', '\u{2558}', '\u{2552}', '\u{2553}', '\u{256b}',
+    '\u{256a}', '\u{2518}', '\u{250c}', '\u{2588}', '\u{2584}', '\u{258c}', '\u{2590}', '\u{2580}',
+    // 0xE0
+    '\u{03b1}', '\u{00df}', '\u{0393}', '\u{03c0}', '\u{03a3}', '\u{03c3}', '\u{00b5}', '\u{03c4}',
+    '\u{03a6}', '\u{0398}', '\u{03a9}', '\u{03b4}', '\u{221e}', '\u{03c6}', '\u{03b5}', '\u{2229}',
+    // 0xF0
+    '\u{2261}', '\u{00b1}', '\u{2265}', '\u{2264}', '\u{2320}', '\u{2321}', '\u{00f7}', '\u{2248}',
+    '\u{00b0}', '\u{2219}', '\u{00b7}', '\u{221a}', '\u{207f}', '\u{00b2}', '\u{25a0}', '\u{00a0}',
+];
+
+// IBM PC code page 850, Western European from 0x80 to 0xFF
+const CP850_HIGH: [char; 128] = [
+    // 0x80
+    '\u{00c7}', '\u{00fc}', '\u{00e9}', '\u{00e2}', '\u{00e4}', '\u{00e0}', '\u{00e5}', '\u{00e7}',
+    '\u{00ea}', '\u{00eb}', '\u{00e8}', '\u{00ef}', '\u{00ee}', '\u{00ec}', '\u{00c4}', '\u{00c5}',
+    // 0x90
+    '\u{00c9}', '\u{00e6}', '\u{00c6}', '\u{00f4}', '\u{00f6}', '\u{00f2}', '\u{00fb}', '\u{00f9}',
+    '\u{00ff}', '\u{00d6}', '\u{00dc}', '\u{00f8}', '\u{00a3}', '\u{00d8}', '\u{00d7}', '\u{0192}',
+    // 0xA0
+    '\u{00e1}', '\u{00ed}', '\u{00f3}', '\u{00fa}', '\u{00f1}', '\u{00d1}', '\u{00aa}', '\u{00ba}',
+    '\u{00bf}', '\u{00ae}', '\u{00ac}', '\u{00bd}', '\u{00bc}', '\u{00a1}', '\u{00ab}', '\u{00bb}',
+    // 0xB0
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{00c1}', '\u{00c2}', '\u{00c0}',
+    '\u{00a9}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255d}', '\u{00a2}', '\u{00a5}', '\u{2510}',
+    // 0xC0
+    '\u{2514}', '\u{2534}', '\u{252c}', '\u{251c}', '\u{2500}', '\u{253c}', '\u{00e3}', '\u{00c3}',
+    '\u{255a}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256c}', '\u{00a4}',
+    // 0xD0
+    '\u{00f0}', '\u{00d0}', '\u{00ca}', '\u{00cb}', '\u{00c8}', '\u{0131}', '\u{00cd}', '\u{00ce}',
+    '\u{00cf}', '\u{2518}', '\u{250c}', '\u{2588}', '\u{2584}', '\u{00a6}', '\u{00cc}', '\u{2580}',
+    // 0xE0
+    '\u{00d3}', '\u{00df}', '\u{00d4}', '\u{00d2}', '\u{00f5}', '\u{00d5}', '\u{00b5}', '\u{00fe}',
+    '\u{00de}', '\u{00da}', '\u{00db}', '\u{00d9}', '\u{00fd}', '\u{00dd}', '\u{00af}', '\u{00b4}',
+    // 0xF0
+    '\u{00ad}', '\u{00b1}', '\u{2017}', '\u{00be}', '\u{00b6}', '\u{00a7}', '\u{00f7}', '\u{00b8}',
+    '\u{00b0}', '\u{00a8}', '\u{00b7}', '\u{00b9}', '\u{00b3}', '\u{00b2}', '\u{25a0}', '\u{00a0}',
+];
+
+// Decode a byte in a code page that's ASCII below 0x80
+fn decode_high(high: &[char; 128], byte: u8) -> char {
+    match byte {
+        0x80..=0xff => high[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+fn decode_cp1251(byte: u8) -> char {
+    match byte {
+        0x80..=0xbf => CP1251_HIGH[(byte - 0x80) as usize],
+        0xc0..=0xff => std::char::from_u32(0x0410 + u32::from(byte - 0xc0)).unwrap_or('\u{fffd}'),
+        _ => byte as char,
+    }
+}
+
+/// The code page of a `\fcharsetN` character set, if it has one
+pub(crate) fn charset_code_page(charset: i32) -> Option<u32> {
+    let code_page = match charset {
+        0 => 1252,
+        77 => 10000,
+        128 => 932,
+        129 => 949,
+        134 => 936,
+        136 => 950,
+        161 => 1253,
+        162 => 1254,
+        163 => 1258,
+        177 => 1255,
+        178 => 1256,
+        186 => 1257,
+        204 => 1251,
+        222 => 874,
+        238 => 1250,
+        254 => 437,
+        // 1 is the "default" character set, and 2 the symbol character set
+        _ => return None,
+    };
+    Some(code_page)
+}
+
+/// The code page of a document character set named by `\ansi`, `\mac`,
+/// `\pc` or `\pca`
+pub(crate) fn charset_word_code_page(name: &str) -> Option<u32> {
+    match name {
+        "ansi" => Some(1252),
+        "mac" => Some(10000),
+        "pc" => Some(437),
+        "pca" => Some(850),
+        _ => None,
+    }
+}
+
+// Decode a byte in a code page this module has a table for
+fn decode_known(code_page: u32, byte: u8) -> Option<char> {
+    let c = match code_page {
+        1250 => decode_high(&CP1250_HIGH, byte),
+        1251 => decode_cp1251(byte),
+        1252 => decode_cp1252(byte),
+        1253 => decode_high(&CP1253_HIGH, byte),
+        1254 => decode_high(&CP1254_HIGH, byte),
+        1255 => decode_high(&CP1255_HIGH, byte),
+        1256 => decode_high(&CP1256_HIGH, byte),
+        1257 => decode_high(&CP1257_HIGH, byte),
+        1258 => decode_high(&CP1258_HIGH, byte),
+        437 => decode_high(&CP437_HIGH, byte),
+        850 => decode_high(&CP850_HIGH, byte),
+        10000 => decode_high(&MAC_ROMAN_HIGH, byte),
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Decode a byte of text in the given code page
+pub(crate) fn decode(code_page: u32, byte: u8) -> char {
+    decode_known(code_page, byte).unwrap_or_else(|| decode_cp1252(byte))
+}
+
+/// Encode a character as a byte of the given code page, or None if it isn't
+/// in the code page or the code page is one without a table here
+pub(crate) fn encode(code_page: u32, c: char) -> Option<u8> {
+    if c.is_ascii() {
+        return Some(c as u8);
+    }
+    (0x80..=0xff).find(|byte| c != '\u{fffd}' && decode_known(code_page, *byte) == Some(c))
+}
+
+/// A source of code page conversions, for decoding text bytes
+///
+/// Implement this to decode code pages with `encoding_rs`, `oem_cp` or
+/// tables of your own, and set it as `TextOptions::charset_decoder`.  Code
+/// pages it returns `None` for are decoded as they would be without it: by
+/// this module's tables, or as Windows-1252.
+pub trait CharsetDecoder: std::fmt::Debug + Send + Sync {
+    /// Whether `byte` begins a two byte character in `code_page`, as in the
+    /// double byte code pages of East Asian documents
+    fn is_lead_byte(&self, _code_page: u32, _byte: u8) -> bool {
+        false
+    }
+
+    /// Decode a character of `code_page`, one byte long or two bytes long
+    /// when the first is a lead byte, or `None` if the code page is unknown
+    fn decode(&self, code_page: u32, bytes: &[u8]) -> Option<char>;
+}
+
+/// The code pages this module has tables for: Windows-1250 to Windows-1258,
+/// IBM PC code pages 437 and 850, and Mac Roman
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WindowsCodePages;
+
+impl CharsetDecoder for WindowsCodePages {
+    fn decode(&self, code_page: u32, bytes: &[u8]) -> Option<char> {
+        match bytes {
+            [byte] => decode_known(code_page, *byte),
+            _ => None,
+        }
+    }
+}
+
+/// The `encoding_rs` encoding of a code page, for those it supports with
+/// characters of at most two bytes
+#[cfg(feature = "encoding_rs")]
+pub fn encoding(code_page: u32) -> Option<&'static encoding_rs::Encoding> {
+    let encoding = match code_page {
+        866 => encoding_rs::IBM866,
+        874 => encoding_rs::WINDOWS_874,
+        932 => encoding_rs::SHIFT_JIS,
+        936 => encoding_rs::GBK,
+        949 => encoding_rs::EUC_KR,
+        950 => encoding_rs::BIG5,
+        1250 => encoding_rs::WINDOWS_1250,
+        1251 => encoding_rs::WINDOWS_1251,
+        1252 => encoding_rs::WINDOWS_1252,
+        1253 => encoding_rs::WINDOWS_1253,
+        1254 => encoding_rs::WINDOWS_1254,
+        1255 => encoding_rs::WINDOWS_1255,
+        1256 => encoding_rs::WINDOWS_1256,
+        1257 => encoding_rs::WINDOWS_1257,
+        1258 => encoding_rs::WINDOWS_1258,
+        10000 => encoding_rs::MACINTOSH,
+        10007 => encoding_rs::X_MAC_CYRILLIC,
+        20866 => encoding_rs::KOI8_R,
+        21866 => encoding_rs::KOI8_U,
+        28592 => encoding_rs::ISO_8859_2,
+        28593 => encoding_rs::ISO_8859_3,
+        28594 => encoding_rs::ISO_8859_4,
+        28595 => encoding_rs::ISO_8859_5,
+        28596 => encoding_rs::ISO_8859_6,
+        28597 => encoding_rs::ISO_8859_7,
+        28598 => encoding_rs::ISO_8859_8,
+        28603 => encoding_rs::ISO_8859_13,
+        28605 => encoding_rs::ISO_8859_15,
+        _ => return None,
+    };
+    Some(encoding)
+}
+
+/// The `encoding_rs` encoding of a `\fcharsetN` character set, if it has one
+#[cfg(feature = "encoding_rs")]
+pub fn charset_encoding(charset: i32) -> Option<&'static encoding_rs::Encoding> {
+    charset_code_page(charset).and_then(encoding)
+}
+
+/// Code page conversions by `encoding_rs`
+///
+/// With the `encoding_rs` feature, this decodes text before the built-in
+/// tables do, so that the double byte code pages of Japanese, Chinese and
+/// Korean documents (932, 936, 949 and 950) and the others the tables don't
+/// cover, like Thai (874), are read.
+#[cfg(feature = "encoding_rs")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodingRsDecoder;
+
+#[cfg(feature = "encoding_rs")]
+impl CharsetDecoder for EncodingRsDecoder {
+    fn is_lead_byte(&self, code_page: u32, byte: u8) -> bool {
+        match code_page {
+            932 => matches!(byte, 0x81..=0x9f | 0xe0..=0xfc),
+            936 | 949 | 950 => matches!(byte, 0x81..=0xfe),
+            _ => false,
+        }
+    }
+
+    fn decode(&self, code_page: u32, bytes: &[u8]) -> Option<char> {
+        let decoded =
+            encoding(code_page)?.decode_without_bom_handling_and_without_replacement(bytes);
+        let mut chars = decoded.as_ref().map(|text| text.chars());
+        match chars.as_mut().map(|chars| (chars.next(), chars.next())) {
+            Some((Some(c), None)) => Some(c),
+            // Bytes that aren't a character of the code page
+            _ => Some('\u{fffd}'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(1251, b'a'), 'a');
+        assert_eq!(decode(1251, 0xcf), '\u{41f}');
+        assert_eq!(decode(1251, 0xff), '\u{44f}');
+        assert_eq!(decode(1251, 0xa8), '\u{401}');
+        assert_eq!(decode(1252, 0xcf), '\u{cf}');
+        assert_eq!(decode(10000, 0x8e), '\u{e9}');
+        assert_eq!(decode(10000, 0xf0), '\u{f8ff}');
+        assert_eq!(decode(437, 0x82), '\u{e9}');
+        assert_eq!(decode(437, 0xb3), '\u{2502}');
+        assert_eq!(decode(850, 0x9b), '\u{f8}');
+        assert_eq!(decode(850, b'z'), 'z');
+        assert_eq!(decode(1255, 0xe0), '\u{5d0}');
+        assert_eq!(decode(1255, 0xa4), '\u{20aa}');
+        assert_eq!(decode(1255, 0xff), '\u{fffd}');
+        assert_eq!(decode(1256, 0xc7), '\u{627}');
+        assert_eq!(decode(1256, 0xe9), '\u{e9}');
+        assert_eq!(decode(1250, 0x8a), '\u{160}');
+        assert_eq!(decode(1250, 0xf8), '\u{159}');
+        assert_eq!(decode(1253, 0xe1), '\u{3b1}');
+        assert_eq!(decode(1253, 0xaa), '\u{fffd}');
+        assert_eq!(decode(1254, 0xf0), '\u{11f}');
+        assert_eq!(decode(1257, 0xe8), '\u{10d}');
+        assert_eq!(decode(1258, 0xd2), '\u{309}');
+        // Code pages without a table fall back to Windows-1252
+        assert_eq!(decode(874, 0x80), '\u{20ac}');
+        assert_eq!(decode(874, 0xe9), '\u{e9}');
+    }
+
+    #[test]
+    fn test_windows_code_pages() {
+        assert_eq!(WindowsCodePages.decode(1250, &[0x8a]), Some('\u{160}'));
+        assert_eq!(WindowsCodePages.decode(932, &[0x82]), None);
+        assert_eq!(WindowsCodePages.decode(1252, &[0x82, 0xa0]), None);
+        assert!(!WindowsCodePages.is_lead_byte(1252, 0x82));
+    }
+
+    #[test]
+    fn test_charset_code_page() {
+        assert_eq!(charset_code_page(204), Some(1251));
+        assert_eq!(charset_code_page(0), Some(1252));
+        assert_eq!(charset_code_page(1), None);
+        assert_eq!(charset_code_page(2), None);
+    }
+
+    #[cfg(feature = "encoding_rs")]
+    #[test]
+    fn test_encoding_rs_decoder() {
+        assert_eq!(encoding(932), Some(encoding_rs::SHIFT_JIS));
+        assert_eq!(charset_encoding(222), Some(encoding_rs::WINDOWS_874));
+        assert_eq!(charset_encoding(2), None);
+        assert_eq!(encoding(437), None);
+
+        let decoder = EncodingRsDecoder;
+        assert!(decoder.is_lead_byte(932, 0x82) && !decoder.is_lead_byte(932, 0xb1));
+        assert_eq!(decoder.decode(932, &[0x82, 0xa0]), Some('\u{3042}'));
+        // Half width katakana, one byte long
+        assert_eq!(decoder.decode(932, &[0xb1]), Some('\u{ff71}'));
+        assert_eq!(decoder.decode(936, &[0xc4, 0xe3]), Some('\u{4f60}'));
+        assert_eq!(decoder.decode(874, &[0xa1]), Some('\u{e01}'));
+        assert_eq!(decoder.decode(932, &[0x82, 0x20]), Some('\u{fffd}'));
+        assert_eq!(decoder.decode(437, &[0x82]), None);
+    }
+}
@@ -0,0 +1,237 @@
+// Compressed RTF
+//
+// Outlook stores message bodies as "compressed RTF" ([MS-OXRTFCP]): an LZ77
+// variant whose dictionary starts out primed with text common to RTF
+// headers.  A 16 byte header gives the sizes, the compression type, `LZFu`
+// for compressed or `MELA` for stored as is, and a CRC of the data.
+//
+
+use std;
+
+use tokenizer::{parse, ParseError, Token};
+
+const HEADER_LEN: usize = 16;
+const COMPRESSED: u32 = 0x7546_5a4c; // "LZFu"
+const UNCOMPRESSED: u32 = 0x414c_454d; // "MELA"
+
+const DICTIONARY_LEN: usize = 4096;
+const PREBUFFER: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}\
+{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier\
+{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecompressError {
+    /// The input is shorter than the header
+    TooShort,
+    /// The header names a compression type other than `LZFu` or `MELA`
+    UnknownType(u32),
+    /// The CRC in the header doesn't match the data
+    Crc { expected: u32, actual: u32 },
+}
+
+impl std::fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecompressError::TooShort => write!(f, "compressed RTF shorter than its header"),
+            DecompressError::UnknownType(kind) => {
+                write!(f, "unknown compressed RTF type {:#010x}", kind)
+            }
+            DecompressError::Crc { expected, actual } => write!(
+                f,
+                "compressed RTF CRC mismatch: header says {:#010x}, data has {:#010x}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecompressError {}
+
+/// The error from `parse_compressed`
+#[derive(Debug)]
+pub enum CompressedRtfError {
+    Decompress(DecompressError),
+    Parse(ParseError),
+}
+
+impl std::convert::From<DecompressError> for CompressedRtfError {
+    fn from(error: DecompressError) -> Self {
+        CompressedRtfError::Decompress(error)
+    }
+}
+
+impl std::convert::From<ParseError> for CompressedRtfError {
+    fn from(error: ParseError) -> Self {
+        CompressedRtfError::Parse(error)
+    }
+}
+
+impl std::fmt::Display for CompressedRtfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CompressedRtfError::Decompress(error) => write!(f, "{}", error),
+            CompressedRtfError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CompressedRtfError {}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0])
+        | u32::from(bytes[1]) << 8
+        | u32::from(bytes[2]) << 16
+        | u32::from(bytes[3]) << 24
+}
+
+// The CRC-32 of the spec: the usual polynomial, but starting from 0 and
+// without the final inversion
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// Whether `bytes` starts with a compressed RTF header
+pub fn is_compressed_rtf(bytes: &[u8]) -> bool {
+    bytes.len() >= HEADER_LEN && [COMPRESSED, UNCOMPRESSED].contains(&read_u32(&bytes[8..]))
+}
+
+/// Decompress compressed RTF, such as the `PR_RTF_COMPRESSED` property of an
+/// Outlook message
+///
+/// Data cut short ends the output early rather than failing, unless the CRC
+/// catches it.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(DecompressError::TooShort);
+    }
+    // The compressed size counts everything after its own field
+    let end = (read_u32(bytes) as usize)
+        .checked_add(4)
+        .map_or(bytes.len(), |end| std::cmp::min(bytes.len(), end));
+    let raw_size = read_u32(&bytes[4..]) as usize;
+    let data = &bytes[HEADER_LEN..std::cmp::max(end, HEADER_LEN)];
+    match read_u32(&bytes[8..]) {
+        COMPRESSED => {}
+        UNCOMPRESSED => return Ok(data[..std::cmp::min(raw_size, data.len())].to_vec()),
+        kind => return Err(DecompressError::UnknownType(kind)),
+    }
+    let expected = read_u32(&bytes[12..]);
+    let actual = crc32(data);
+    if expected != actual {
+        return Err(DecompressError::Crc { expected, actual });
+    }
+
+    let mut dictionary = [0u8; DICTIONARY_LEN];
+    dictionary[..PREBUFFER.len()].copy_from_slice(PREBUFFER);
+    let mut write = PREBUFFER.len();
+    // The raw size comes from the header, so it's trusted only as far as
+    // the data could plausibly expand; the output grows past that if needed
+    let mut out = Vec::with_capacity(std::cmp::min(
+        raw_size,
+        data.len().saturating_mul(8) + PREBUFFER.len(),
+    ));
+    let mut data = data.iter();
+    while let Some(control) = data.next() {
+        for bit in 0..8 {
+            if control & 1 << bit == 0 {
+                let byte = match data.next() {
+                    Some(byte) => *byte,
+                    None => return Ok(out),
+                };
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % DICTIONARY_LEN;
+                continue;
+            }
+            // A reference into the dictionary: 12 bits of offset, and 4 of
+            // length less 2
+            let reference = match (data.next(), data.next()) {
+                (Some(high), Some(low)) => usize::from(*high) << 8 | usize::from(*low),
+                _ => return Ok(out),
+            };
+            let offset = reference >> 4;
+            // A reference to where the next byte would be written ends the
+            // data
+            if offset == write {
+                return Ok(out);
+            }
+            for index in 0..(reference & 0xf) + 2 {
+                let byte = dictionary[(offset + index) % DICTIONARY_LEN];
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % DICTIONARY_LEN;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Decompress and tokenize compressed RTF
+pub fn parse_compressed(bytes: &[u8]) -> Result<Vec<Token>, CompressedRtfError> {
+    Ok(parse(&decompress(bytes)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The first example from [MS-OXRTFCP]
+    const EXAMPLE: &[u8] = b"\x2d\x00\x00\x00\x2b\x00\x00\x00\x4c\x5a\x46\x75\xf1\xc5\xc7\xa7\
+\x03\x00\x0a\x00\x72\x63\x70\x67\x31\x32\x35\x42\x32\x0a\xf3\x20\x68\x65\x6c\x09\x00\x20\
+\x62\x77\x05\xb0\x6c\x64\x7d\x0a\x80\x0f\xa0";
+
+    #[test]
+    fn test_decompress() {
+        assert!(is_compressed_rtf(EXAMPLE));
+        assert_eq!(
+            String::from_utf8(decompress(EXAMPLE).unwrap()).unwrap(),
+            "{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n"
+        );
+        let tokens = parse_compressed(EXAMPLE).unwrap();
+        assert_eq!(tokens[tokens.len() - 3], Token::text("hello world"));
+    }
+
+    #[test]
+    fn test_decompress_bad_sizes() {
+        // Sizes far past the data are ignored rather than allocated
+        let mut huge = EXAMPLE.to_vec();
+        huge[..8].copy_from_slice(&[0xff; 8]);
+        assert_eq!(decompress(&huge), decompress(EXAMPLE));
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let mut stored = b"\x10\x00\x00\x00\x04\x00\x00\x00MELA\x00\x00\x00\x00".to_vec();
+        stored.extend_from_slice(b"{x}\r");
+        assert!(is_compressed_rtf(&stored));
+        assert_eq!(decompress(&stored).unwrap(), b"{x}\r".to_vec());
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        assert_eq!(decompress(b"LZFu"), Err(DecompressError::TooShort));
+        assert!(!is_compressed_rtf(b"{\\rtf1\\ansi\\deff0}"));
+        assert_eq!(
+            decompress(b"{\\rtf1\\ansi\\deff0}"),
+            Err(DecompressError::UnknownType(u32::from_le_bytes(*b"nsi\\")))
+        );
+        let mut corrupt = EXAMPLE.to_vec();
+        corrupt[20] ^= 1;
+        match decompress(&corrupt) {
+            Err(DecompressError::Crc { expected, .. }) => assert_eq!(expected, 0xa7c7_c5f1),
+            other => panic!("expected a CRC error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,430 @@
+// HTML conversion
+//
+// Renders a document as a fragment of semantic HTML: formatting becomes
+// `<b>`, `<i>`, `<u>` and `<s>` elements, and only the properties with no
+// semantic equivalent (font, size and color, and paragraph alignment,
+// indents and spacing) are written as inline styles.
+//
+
+use std::fmt::Write;
+
+use document::{
+    Alignment, Block, Border, BorderStyle, Cell, CellMerge, Color, Document, ListItem, ListKind,
+    ParaFormat, Paragraph, RevisionKind, Run, Table,
+};
+
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\n' => html.push_str("<br>"),
+            c => html.push(c),
+        }
+    }
+}
+
+fn style(doc: &Document, run: &Run) -> String {
+    let mut style = String::new();
+    let font = run.format.font.or(doc.default_font);
+    if let Some(font) = font.and_then(|index| doc.font(index)) {
+        if !font.name.is_empty() {
+            let _ = write!(style, "font-family:'{}';", font.name.replace('\'', ""));
+        }
+    }
+    if let Some(size) = run.format.size {
+        let _ = write!(style, "font-size:{}pt;", f64::from(size) / 2.0);
+    }
+    if let Some(color) = run.format.color.and_then(|index| doc.color(index)) {
+        let _ = write!(style, "color:{};", css_color(color));
+    }
+    style
+}
+
+// The schemes a link is written with; others, like `javascript:` and
+// `data:`, can run script in the page the HTML is shown in
+const LINK_SCHEMES: &[&str] = &["http", "https", "mailto", "ftp", "tel"];
+
+// Whether a link is safe to write as an `href`: one with a scheme above, or
+// a relative one
+fn safe_link(link: &str) -> bool {
+    // Browsers skip whitespace and control characters in a URL's scheme
+    let link: String = link
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    match link.find([':', '/', '?', '#']) {
+        Some(end) if link[end..].starts_with(':') => LINK_SCHEMES
+            .iter()
+            .any(|scheme| scheme.eq_ignore_ascii_case(&link[..end])),
+        _ => true,
+    }
+}
+
+fn push_run(html: &mut String, doc: &Document, run: &Run) {
+    let mut close = Vec::new();
+    // The text of a link that isn't safe is written without it
+    if let Some(link) = run.link.as_ref().filter(|link| safe_link(link)) {
+        html.push_str("<a href=\"");
+        push_escaped(html, link);
+        html.push_str("\">");
+        close.push("</a>");
+    }
+    let style = style(doc, run);
+    if !style.is_empty() {
+        html.push_str("<span style=\"");
+        push_escaped(html, &style);
+        html.push_str("\">");
+        close.push("</span>");
+    }
+    for revision in &run.revisions {
+        let (open, end) = match revision.kind {
+            RevisionKind::Insertion => ("<ins>", "</ins>"),
+            RevisionKind::Deletion => ("<del>", "</del>"),
+        };
+        html.push_str(open);
+        close.push(end);
+    }
+    let format = &run.format;
+    for &(on, open, end) in &[
+        (format.bold, "<b>", "</b>"),
+        (format.italic, "<i>", "</i>"),
+        (format.underline, "<u>", "</u>"),
+        (format.strike, "<s>", "</s>"),
+    ] {
+        if on {
+            html.push_str(open);
+            close.push(end);
+        }
+    }
+    push_escaped(html, &run.text);
+    for end in close.iter().rev() {
+        html.push_str(end);
+    }
+}
+
+fn push_runs(html: &mut String, doc: &Document, paragraph: &Paragraph) {
+    for run in &paragraph.runs {
+        push_run(html, doc, run);
+    }
+}
+
+fn paragraph_style(format: &ParaFormat) -> String {
+    let mut style = String::new();
+    match format.alignment {
+        Alignment::Left => {}
+        Alignment::Center => style.push_str("text-align:center;"),
+        Alignment::Right => style.push_str("text-align:right;"),
+        Alignment::Justified => style.push_str("text-align:justify;"),
+    }
+    for &(property, twips) in &[
+        ("margin-left", format.left_indent),
+        ("margin-right", format.right_indent),
+        ("text-indent", format.first_line_indent),
+        ("margin-top", format.space_before),
+        ("margin-bottom", format.space_after),
+    ] {
+        if twips != 0 {
+            let _ = write!(style, "{}:{}pt;", property, f64::from(twips) / 20.0);
+        }
+    }
+    style
+}
+
+fn push_paragraph(html: &mut String, doc: &Document, paragraph: &Paragraph) {
+    let style = paragraph_style(&paragraph.format);
+    if style.is_empty() {
+        html.push_str("<p>");
+    } else {
+        html.push_str("<p style=\"");
+        html.push_str(&style);
+        html.push_str("\">");
+    }
+    push_runs(html, doc, paragraph);
+    html.push_str("</p>\n");
+}
+
+fn list_tags(kind: ListKind) -> (&'static str, &'static str) {
+    match kind {
+        ListKind::Bullet => ("<ul>\n", "</ul>\n"),
+        ListKind::Numbered => ("<ol>\n", "</ol>\n"),
+    }
+}
+
+// Open and close lists so that exactly the lists needed by `item` are open.
+// Each open list has a flag for whether its last item is still open, as
+// it's left open for a list nested under it to go in.
+fn adjust_lists(html: &mut String, open: &mut Vec<(ListKind, bool)>, item: Option<ListItem>) {
+    let (depth, kind) = match item {
+        Some(item) => (item.level + 1, Some(item.kind)),
+        None => (0, None),
+    };
+    while open.len() > depth || (open.len() == depth && open.last().map(|list| list.0) != kind) {
+        match open.pop() {
+            Some((kind, item_open)) => {
+                if item_open {
+                    html.push_str("</li>\n");
+                }
+                html.push_str(list_tags(kind).1);
+            }
+            None => break,
+        }
+    }
+    // The item before, in the list `item` goes in
+    if open.len() == depth {
+        if let Some(list) = open.last_mut().filter(|list| list.1) {
+            html.push_str("</li>\n");
+            list.1 = false;
+        }
+    }
+    while let Some(kind) = kind.filter(|_| open.len() < depth) {
+        // A list nested deeper than the item before goes in an item of its
+        // own
+        if let Some(parent) = open.last_mut().filter(|parent| !parent.1) {
+            html.push_str("<li>");
+            parent.1 = true;
+        }
+        html.push_str(list_tags(kind).0);
+        open.push((kind, false));
+    }
+}
+
+// Number of cells covered by a merge, given the cells following its first
+fn span<'t, I: Iterator<Item = Option<&'t Cell>>>(
+    cells: I,
+    merge: fn(&Cell) -> CellMerge,
+) -> usize {
+    1 + cells
+        .take_while(|cell| cell.is_some_and(|cell| merge(cell) == CellMerge::Merged))
+        .count()
+}
+
+fn css_color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+}
+
+fn border_style(doc: &Document, side: &str, border: &Border) -> String {
+    let line = match border.style {
+        BorderStyle::None => return format!("border-{}:none;", side),
+        BorderStyle::Double | BorderStyle::Triple => "double",
+        BorderStyle::Dotted => "dotted",
+        BorderStyle::Dashed => "dashed",
+        _ => "solid",
+    };
+    let width = match border.width {
+        Some(width) => format!("{}pt", f64::from(width) / 20.0),
+        None => "1px".to_string(),
+    };
+    let mut style = format!("border-{}:{} {}", side, width, line);
+    if let Some(color) = border.color.and_then(|index| doc.color(index)) {
+        style.push(' ');
+        style.push_str(&css_color(color));
+    }
+    style.push(';');
+    style
+}
+
+// Inline style for a cell's background and borders
+fn cell_style(doc: &Document, cell: &Cell) -> String {
+    let mut style = String::new();
+    if let Some(color) = cell.background.and_then(|index| doc.color(index)) {
+        let _ = write!(style, "background-color:{};", css_color(color));
+    }
+    let borders = &cell.borders;
+    let sides = [
+        ("top", &borders.top),
+        ("right", &borders.right),
+        ("bottom", &borders.bottom),
+        ("left", &borders.left),
+    ];
+    for (side, border) in sides.iter() {
+        if let Some(border) = border {
+            style.push_str(&border_style(doc, side, border));
+        }
+    }
+    style
+}
+
+fn push_table(html: &mut String, doc: &Document, table: &Table) {
+    html.push_str("<table>\n");
+    for (row_index, row) in table.rows.iter().enumerate() {
+        html.push_str("<tr>");
+        for (index, cell) in row.cells.iter().enumerate() {
+            // Merged cells are covered by the first cell of their merge
+            if cell.horizontal_merge == CellMerge::Merged
+                || cell.vertical_merge == CellMerge::Merged
+            {
+                continue;
+            }
+            html.push_str("<td");
+            if cell.horizontal_merge == CellMerge::First {
+                let colspan = span(row.cells[index + 1..].iter().map(Some), |cell| {
+                    cell.horizontal_merge
+                });
+                let _ = write!(html, " colspan=\"{}\"", colspan);
+            }
+            if cell.vertical_merge == CellMerge::First {
+                let below = table.rows[row_index + 1..]
+                    .iter()
+                    .map(|row| row.cells.get(index));
+                let rowspan = span(below, |cell| cell.vertical_merge);
+                let _ = write!(html, " rowspan=\"{}\"", rowspan);
+            }
+            let style = cell_style(doc, cell);
+            if !style.is_empty() {
+                let _ = write!(html, " style=\"{}\"", style);
+            }
+            html.push('>');
+            for (index, block) in cell.blocks.iter().enumerate() {
+                match block {
+                    Block::Paragraph(paragraph) => {
+                        if index > 0 {
+                            html.push_str("<br>");
+                        }
+                        push_runs(html, doc, paragraph);
+                    }
+                    Block::Table(table) => push_table(html, doc, table),
+                }
+            }
+            html.push_str("</td>");
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+/// Render a document as an HTML fragment
+pub fn to_html(doc: &Document) -> String {
+    let mut html = String::new();
+    let mut lists = Vec::new();
+    for block in &doc.blocks {
+        match block {
+            Block::Paragraph(paragraph) => {
+                adjust_lists(&mut html, &mut lists, paragraph.list);
+                if let Some(list) = lists.last_mut().filter(|_| paragraph.list.is_some()) {
+                    // Left open until the next item
+                    html.push_str("<li>");
+                    push_runs(&mut html, doc, paragraph);
+                    list.1 = true;
+                } else {
+                    push_paragraph(&mut html, doc, paragraph);
+                }
+            }
+            Block::Table(table) => {
+                adjust_lists(&mut html, &mut lists, None);
+                push_table(&mut html, doc, table);
+            }
+        }
+    }
+    adjust_lists(&mut html, &mut lists, None);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(rtf: &[u8]) -> String {
+        to_html(&Document::parse(rtf).unwrap())
+    }
+
+    #[test]
+    fn test_formatting() {
+        assert_eq!(
+            html(br#"{\rtf1{\colortbl;\red255\green0\blue0;}a<b {\b\i bold} {\fs28\cf1 big}\par}"#),
+            "<p>a&lt;b <b><i>bold</i></b> <span style=\"font-size:14pt;color:#ff0000;\">big</span></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_paragraph_layout() {
+        assert_eq!(
+            html(br#"{\rtf1\pard\qc\li720\fi-360\sa120 Centered\par}"#),
+            "<p style=\"text-align:center;margin-left:36pt;text-indent:-18pt;margin-bottom:6pt;\">Centered</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_revisions() {
+        assert_eq!(
+            html(br#"{\rtf1 a{\revised new}{\deleted old}\par}"#),
+            "<p>a<ins>new</ins><del>old</del></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_links_and_breaks() {
+        assert_eq!(
+            html(br#"{\rtf1 {\field{\*\fldinst HYPERLINK "http://a.example/?x&y"}{\fldrslt here}}\line next\par}"#),
+            "<p><a href=\"http://a.example/?x&amp;y\">here</a><br>next</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let rtf = br#"{\rtf1{\*\listtable{\list{\listlevel\levelnfc23}{\listlevel\levelnfc0}\listid1}}{\*\listoverridetable{\listoverride\listid1\ls1}}
+\pard\ls1 A\par\pard\ls1\ilvl1 B\par\pard\ls1 C\par\pard D\par}"#;
+        assert_eq!(
+            html(rtf),
+            "<ul>\n<li>A<ol>\n<li>B</li>\n</ol>\n</li>\n<li>C</li>\n</ul>\n<p>D</p>\n"
+        );
+        // A list nested two levels under its parent, and one ending with a
+        // nested list
+        let rtf = br#"{\rtf1{\*\listtable{\list{\listlevel\levelnfc23}{\listlevel\levelnfc23}{\listlevel\levelnfc23}\listid1}}{\*\listoverridetable{\listoverride\listid1\ls1}}
+\pard\ls1 A\par\pard\ls1\ilvl2 B\par\pard\ls1\ilvl1 C\par}"#;
+        assert_eq!(
+            html(rtf),
+            "<ul>\n<li>A<ul>\n<li><ul>\n<li>B</li>\n</ul>\n</li>\n<li>C</li>\n</ul>\n</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_unsafe_links() {
+        for link in &[
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            " java\tscript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "vbscript:msgbox",
+        ] {
+            let rtf = format!(
+                "{{\\rtf1 {{\\field{{\\*\\fldinst HYPERLINK \"{}\"}}{{\\fldrslt here}}}}\\par}}",
+                link
+            );
+            assert_eq!(html(rtf.as_bytes()), "<p>here</p>\n", "{}", link);
+        }
+        for link in &["mailto:a@example.com", "#top", "/a:b", "page.html?a=b:c"] {
+            assert!(safe_link(link), "{}", link);
+        }
+    }
+
+    #[test]
+    fn test_table_merges() {
+        let rtf = br#"{\rtf1\trowd\clmgf\cellx1\clmrg\cellx2\clvmgf\cellx3\pard\intbl A\cell\cell B\cell\row
+\trowd\cellx1\cellx2\clvmrg\cellx3\pard\intbl C\cell D\cell\cell\row\pard}"#;
+        assert_eq!(
+            html(rtf),
+            "<table>\n<tr><td colspan=\"2\">A</td><td rowspan=\"2\">B</td></tr>\n<tr><td>C</td><td>D</td></tr>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_table_borders() {
+        let rtf = br#"{\rtf1{\colortbl;\red255\green0\blue0;\red255\green255\blue204;}
+\trowd\clcbpat2\clbrdrt\brdrs\brdrw20\brdrcf1\clbrdrb\brdrdb\cellx1\clbrdrl\brdrnone\cellx2\pard\intbl A\cell B\cell\row\pard}"#;
+        assert_eq!(
+            html(rtf),
+            "<table>\n<tr><td style=\"background-color:#ffffcc;border-top:1pt solid #ff0000;border-bottom:1px double;\">A</td><td style=\"border-left:none;\">B</td></tr>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            html(br#"{\rtf1\trowd\cellx1\cellx2\pard\intbl A\cell B\par C\cell\row\pard}"#),
+            "<table>\n<tr><td>A</td><td>B<br>C</td></tr>\n</table>\n"
+        );
+    }
+}
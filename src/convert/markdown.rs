@@ -0,0 +1,261 @@
+// Markdown conversion
+//
+// Renders a document as CommonMark.  Markdown can only express a subset of
+// what a document can contain: formatting without an equivalent (fonts,
+// sizes, colors, underlining) is dropped, and structures that can't be
+// expressed at all, like tables, are kept as their plain text in a fenced
+// code block.
+//
+
+use std;
+
+use document::{Block, Document, ListKind, Paragraph, Run, Table};
+
+fn push_escaped(md: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '!' | '|' => {
+                md.push('\\');
+                md.push(c);
+            }
+            // A hard line break
+            '\n' => md.push_str("\\\n"),
+            c => md.push(c),
+        }
+    }
+}
+
+// Escape characters that would start a block construct at the start of a
+// line, such as `- ` or `1. `
+fn escape_line_start(line: &str) -> String {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    match line[digits..].chars().next() {
+        Some('.') | Some(')') if digits > 0 => {
+            format!("{}\\{}", &line[..digits], &line[digits..])
+        }
+        Some('-') | Some('+') | Some('=') if digits == 0 => format!("\\{}", line),
+        _ => line.to_string(),
+    }
+}
+
+fn push_link_target(md: &mut String, target: &str) {
+    if target.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        md.push('<');
+        md.push_str(&target.replace('<', "%3C").replace('>', "%3E"));
+        md.push('>');
+    } else {
+        md.push_str(target);
+    }
+}
+
+// Consecutive runs that only differ in formatting Markdown can't express are
+// rendered together, so they don't produce redundant emphasis markers
+fn spans(runs: &[Run]) -> Vec<(String, &Run)> {
+    let mut spans: Vec<(String, &Run)> = Vec::new();
+    for run in runs {
+        if let Some((text, last)) = spans.last_mut() {
+            if last.format.bold == run.format.bold
+                && last.format.italic == run.format.italic
+                && last.link == run.link
+            {
+                text.push_str(&run.text);
+                continue;
+            }
+        }
+        spans.push((run.text.clone(), run));
+    }
+    spans
+}
+
+fn inline(paragraph: &Paragraph) -> String {
+    let mut md = String::new();
+    for (text, run) in spans(&paragraph.runs) {
+        // Emphasis markers can't be next to whitespace on their inner side
+        let trimmed = text.trim();
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+        let marker = match (run.format.bold, run.format.italic) {
+            _ if trimmed.is_empty() => "",
+            (true, true) => "***",
+            (true, false) => "**",
+            (false, true) => "*",
+            (false, false) => "",
+        };
+        push_escaped(&mut md, leading);
+        if run.link.is_some() {
+            md.push('[');
+        }
+        md.push_str(marker);
+        push_escaped(&mut md, trimmed);
+        md.push_str(marker);
+        if let Some(ref link) = run.link {
+            md.push_str("](");
+            push_link_target(&mut md, link);
+            md.push(')');
+        }
+        push_escaped(&mut md, trailing);
+    }
+    md.lines()
+        .map(|line| escape_line_start(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn push_table(md: &mut String, table: &Table) {
+    let text = table
+        .rows
+        .iter()
+        .map(|row| {
+            row.cells
+                .iter()
+                .map(|cell| {
+                    cell.paragraphs()
+                        .iter()
+                        .map(|paragraph| paragraph.text())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+                .join("\t")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // The fence has to be longer than any run of backticks in the text
+    let mut longest = 0;
+    let mut current = 0;
+    for c in text.chars() {
+        current = if c == '`' { current + 1 } else { 0 };
+        longest = std::cmp::max(longest, current);
+    }
+    let fence = "`".repeat(std::cmp::max(3, longest + 1));
+
+    md.push_str(&fence);
+    md.push('\n');
+    md.push_str(&text);
+    md.push('\n');
+    md.push_str(&fence);
+}
+
+/// Render a document as CommonMark
+///
+/// Paragraphs whose style is a heading become ATX headings, list paragraphs
+/// become (possibly nested) list items, and hyperlink fields become links.
+pub fn to_markdown(doc: &Document) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    // Indentation of each open list level, for nesting items under the last
+    // item of the level above
+    let mut indents: Vec<usize> = Vec::new();
+    let mut in_list = false;
+
+    for block in &doc.blocks {
+        let paragraph = match block {
+            Block::Paragraph(paragraph) => paragraph,
+            Block::Table(table) => {
+                let mut md = String::new();
+                push_table(&mut md, table);
+                blocks.push(md);
+                in_list = false;
+                continue;
+            }
+        };
+        let text = inline(paragraph);
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        match paragraph.list {
+            Some(item) => {
+                indents.truncate(item.level);
+                while indents.len() < item.level {
+                    let indent = indents.last().map_or(2, |indent| indent + 2);
+                    indents.push(indent);
+                }
+                let indent = " ".repeat(indents.last().cloned().unwrap_or(0));
+                let marker = match item.kind {
+                    ListKind::Bullet => "- ",
+                    ListKind::Numbered => "1. ",
+                };
+                let continuation = format!("\n{}{}", indent, " ".repeat(marker.len()));
+                let md = format!("{}{}{}", indent, marker, text.replace('\n', &continuation));
+                // Items of the same list aren't separated by blank lines
+                match blocks.last_mut() {
+                    Some(last) if in_list => {
+                        last.push('\n');
+                        last.push_str(&md);
+                    }
+                    _ => blocks.push(md),
+                }
+                indents.push(indent.len() + marker.len());
+                in_list = true;
+                continue;
+            }
+            None => {
+                indents.clear();
+                in_list = false;
+            }
+        }
+
+        match doc.heading_level(paragraph) {
+            Some(level) => {
+                let level = std::cmp::min(level, 6);
+                // Headings can't span lines
+                blocks.push(format!(
+                    "{} {}",
+                    "#".repeat(level),
+                    text.replace("\\\n", " ")
+                ));
+            }
+            None => blocks.push(text),
+        }
+    }
+
+    let mut md = blocks.join("\n\n");
+    if !md.is_empty() && !md.ends_with('\n') {
+        md.push('\n');
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(rtf: &[u8]) -> String {
+        to_markdown(&Document::parse(rtf).unwrap())
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            markdown(br#"{\rtf1 Some {\b bold }and {\i\b both} *x*\par 1. not a list\par}"#),
+            "Some **bold** and ***both*** \\*x\\*\n\n1\\. not a list\n"
+        );
+        assert_eq!(
+            markdown(br#"{\rtf1 {\field{\*\fldinst HYPERLINK "http://example.com/a b"}{\fldrslt link}}\line next\par}"#),
+            "[link](<http://example.com/a b>)\\\nnext\n"
+        );
+    }
+
+    #[test]
+    fn test_headings() {
+        let rtf = br#"{\rtf1{\stylesheet{Normal;}{\s1 heading 1;}{\s2\outlinelevel1 Sub;}}
+\pard\s1 Title\par\pard\s2 Part\par\pard Body\par}"#;
+        assert_eq!(markdown(rtf), "# Title\n\n## Part\n\nBody\n");
+    }
+
+    #[test]
+    fn test_lists() {
+        let rtf = br#"{\rtf1{\*\listtable{\list{\listlevel\levelnfc23}{\listlevel\levelnfc0}\listid1}}{\*\listoverridetable{\listoverride\listid1\ls1}}
+\pard Intro\par\pard\ls1 A\par\pard\ls1\ilvl1 B\par\pard\ls1 C\par\pard After\par}"#;
+        assert_eq!(markdown(rtf), "Intro\n\n- A\n  1. B\n- C\n\nAfter\n");
+    }
+
+    #[test]
+    fn test_table_is_fenced() {
+        assert_eq!(
+            markdown(br#"{\rtf1\trowd\cellx1\cellx2\pard\intbl A\cell B\cell\row\pard After\par}"#),
+            "```\nA\tB\n```\n\nAfter\n"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+// Conversion to other document formats
+//
+// Converters work from the decoded `document::Document` model rather than
+// from tokens, so they share a single interpretation of the formatting.
+//
+
+pub mod html;
+pub mod markdown;
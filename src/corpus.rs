@@ -0,0 +1,202 @@
+// Corpus testing
+//
+// Checks that every document in a directory survives a trip through the
+// tokenizer and writer: tokenized, written back out and tokenized again, it
+// must give the same tokens.  A large collection of real world documents
+// exercises far more of both than the handful bundled with the crate, and a
+// report per file says where each one that doesn't come back went wrong.
+//
+// Only available with the `corpus` feature.
+//
+
+use std;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokenizer::{parse, parse_spanned, ParseError, Token};
+use writer::{write_rtf, WriteError, WriteOptions};
+
+/// How a document failed to come back from a roundtrip
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Failure {
+    /// The file couldn't be read
+    Read(io::Error),
+    /// The document couldn't be tokenized
+    Parse(ParseError),
+    /// Its tokens couldn't be written
+    Write(WriteError),
+    /// What was written couldn't be tokenized
+    Reparse(ParseError),
+    /// What was written tokenized differently
+    Mismatch {
+        /// Index of the first token that differs
+        token: usize,
+        /// Offset in the document of that token, or its length if the
+        /// document's tokens ran out first
+        offset: usize,
+        /// The token read from the document, if there was one
+        expected: Option<Token>,
+        /// The token read back, if there was one
+        found: Option<Token>,
+    },
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Failure::Read(err) => write!(f, "couldn't be read: {}", err),
+            Failure::Parse(err) => write!(f, "couldn't be tokenized: {}", err),
+            Failure::Write(err) => write!(f, "couldn't be written: {}", err),
+            Failure::Reparse(err) => write!(f, "couldn't be tokenized once written: {}", err),
+            Failure::Mismatch {
+                token,
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "token {} at offset {} came back as {:?}, not {:?}",
+                token, offset, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Tokenize a document, write its tokens and tokenize them again, checking
+/// that the same tokens come back
+pub fn roundtrip(bytes: &[u8]) -> Result<(), Failure> {
+    let tokens = parse(bytes).map_err(Failure::Parse)?;
+    let rtf = write_rtf(&tokens, &WriteOptions::default()).map_err(Failure::Write)?;
+    let reparsed = parse(&rtf).map_err(Failure::Reparse)?;
+    let token = match tokens
+        .iter()
+        .zip(&reparsed)
+        .position(|(token, read)| token != read)
+    {
+        Some(token) => token,
+        None if tokens.len() == reparsed.len() => return Ok(()),
+        None => std::cmp::min(tokens.len(), reparsed.len()),
+    };
+    let offset = parse_spanned(bytes)
+        .ok()
+        .and_then(|spanned| spanned.get(token).map(|spanned| spanned.span.start))
+        .unwrap_or(bytes.len());
+    Err(Failure::Mismatch {
+        token,
+        offset,
+        expected: tokens.get(token).cloned(),
+        found: reparsed.get(token).cloned(),
+    })
+}
+
+/// The result of checking a corpus
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Number of documents checked
+    pub checked: usize,
+    /// The documents that failed, and how
+    pub failures: Vec<(PathBuf, Failure)>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (path, failure) in &self.failures {
+            writeln!(f, "{}: {}", path.display(), failure)?;
+        }
+        write!(
+            f,
+            "{} of {} documents failed to roundtrip",
+            self.failures.len(),
+            self.checked
+        )
+    }
+}
+
+fn is_rtf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("rtf"))
+}
+
+// The paths of the documents in a directory and those under it, in order
+fn documents(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            documents(&path, paths)?;
+        } else if is_rtf(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Roundtrip every `.rtf` file in a directory and its subdirectories
+///
+/// Each document is checked as `roundtrip` does, and one that fails is
+/// reported without stopping the rest from being checked.  Errors reading
+/// the directories themselves are returned.
+pub fn check_dir<P: AsRef<Path>>(dir: P) -> io::Result<Report> {
+    let mut paths = Vec::new();
+    documents(dir.as_ref(), &mut paths)?;
+    let mut report = Report::default();
+    for path in paths {
+        report.checked += 1;
+        let result = fs::read(&path)
+            .map_err(Failure::Read)
+            .and_then(|bytes| roundtrip(&bytes));
+        if let Err(failure) = result {
+            report.failures.push((path, failure));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        assert!(roundtrip(include_bytes!("../tests/sample.rtf")).is_ok());
+        assert!(roundtrip(include_bytes!("../tests/RTF-Spec-1.7.rtf")).is_ok());
+        assert!(roundtrip(b"{\\rtf1 \\'zz \\\xff}").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_dir() {
+        let dir = std::env::temp_dir().join("rtf-grimoire-corpus");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.rtf"), b"{\\rtf1 Hello}").unwrap();
+        fs::write(dir.join("notes.txt"), b"not a document").unwrap();
+        // A document that can't be read is reported like any other failure
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("nested").join("B.RTF")).unwrap();
+
+        let report = check_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.checked, 2);
+        assert!(!report.passed());
+        match &report.failures[..] {
+            [(path, Failure::Read(_))] => assert!(path.ends_with("nested/B.RTF")),
+            failures => panic!("{:?}", failures),
+        }
+        assert!(report
+            .to_string()
+            .ends_with("1 of 2 documents failed to roundtrip"));
+    }
+}
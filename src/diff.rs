@@ -0,0 +1,356 @@
+// Structural diffs
+//
+// Compares two token streams, or the text runs extracted from them, as
+// sequences rather than bytes, so that differences in layout and delimiters
+// that don't change the tokens don't show up.  Uses Myers' O(ND) algorithm
+// after trimming the common prefix and suffix, which keeps the usual case of
+// two mostly identical documents cheap.
+//
+
+use std;
+use std::ops::Range;
+
+use document::{Color, Document};
+use pict::pictures;
+use text::{extract_runs, TextRun};
+use tokenizer::Token;
+
+/// One difference between sequences `a` and `b`, by index into each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// `a[a]` isn't in `b`; it would have been at `b[b]`
+    Delete { a: Range<usize>, b: usize },
+    /// `b[b]` isn't in `a`; it would have been at `a[a]`
+    Insert { a: usize, b: Range<usize> },
+    /// `a[a]` was replaced by `b[b]`
+    Replace { a: Range<usize>, b: Range<usize> },
+}
+
+/// An edit between two token streams
+pub type TokenEdit = Edit;
+
+/// An edit between two sequences of text runs
+pub type RunEdit = Edit;
+
+// Whether each element of `a` and of `b` is part of the longest common
+// subsequence.  `a` and `b` must be trimmed of their common prefix and
+// suffix.
+fn matches<T: PartialEq>(a: &[T], b: &[T]) -> (Vec<bool>, Vec<bool>) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    // v[k] is the furthest x reached on diagonal k = x - y
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                trace.push(v.clone());
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    // Walk back through the trace to find the snakes of the shortest path
+    let mut in_a = vec![false; a.len()];
+    let mut in_b = vec![false; b.len()];
+    let (mut x, mut y) = (n, m);
+    for d in (0..trace.len() as isize - 1).rev() {
+        let v = &trace[d as usize];
+        let k = x - y;
+        let index = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            in_a[x as usize] = true;
+            in_b[y as usize] = true;
+        }
+        if d == 0 {
+            break;
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    (in_a, in_b)
+}
+
+/// Find the edits that turn `a` into `b`
+///
+/// Edits are in order, and adjacent deletions and insertions are combined
+/// into replacements.
+pub fn diff<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let prefix = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (in_a, in_b) = matches(&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < in_a.len() || j < in_b.len() {
+        if i < in_a.len() && j < in_b.len() && in_a[i] && in_b[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (start_a, start_b) = (i, j);
+        while i < in_a.len() && !in_a[i] {
+            i += 1;
+        }
+        while j < in_b.len() && !in_b[j] {
+            j += 1;
+        }
+        let a_range = prefix + start_a..prefix + i;
+        let b_range = prefix + start_b..prefix + j;
+        edits.push(match (a_range.is_empty(), b_range.is_empty()) {
+            (false, true) => Edit::Delete {
+                a: a_range,
+                b: b_range.start,
+            },
+            (true, false) => Edit::Insert {
+                a: a_range.start,
+                b: b_range,
+            },
+            _ => Edit::Replace {
+                a: a_range,
+                b: b_range,
+            },
+        });
+    }
+    edits
+}
+
+/// Find the token edits that turn `a` into `b`
+pub fn diff_tokens(a: &[Token], b: &[Token]) -> Vec<TokenEdit> {
+    diff(a, b)
+}
+
+/// Extract the text runs of two token streams, and find the edits between
+/// them
+///
+/// Compares documents by their formatted text, ignoring how the RTF that
+/// produced it is structured.
+pub fn diff_text(a: &[Token], b: &[Token]) -> (Vec<TextRun>, Vec<TextRun>, Vec<RunEdit>) {
+    let a = extract_runs(a);
+    let b = extract_runs(b);
+    let edits = diff(&a, &b);
+    (a, b, edits)
+}
+
+// A text run with its font and color looked up, so that documents with their
+// tables in different orders compare the same
+#[derive(Debug, PartialEq)]
+struct ResolvedRun<'d> {
+    text: String,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    font: Option<&'d str>,
+    size: Option<i32>,
+    color: Option<Color>,
+}
+
+impl<'d> ResolvedRun<'d> {
+    fn same_format(&self, other: &ResolvedRun) -> bool {
+        (
+            self.bold,
+            self.italic,
+            self.underline,
+            self.font,
+            self.size,
+            self.color,
+        ) == (
+            other.bold,
+            other.italic,
+            other.underline,
+            other.font,
+            other.size,
+            other.color,
+        )
+    }
+}
+
+fn resolved_runs<'d>(tokens: &[Token], document: &'d Document) -> Vec<ResolvedRun<'d>> {
+    let mut runs: Vec<ResolvedRun> = Vec::new();
+    for run in extract_runs(tokens) {
+        let run = ResolvedRun {
+            text: run.text,
+            bold: run.bold,
+            italic: run.italic,
+            underline: run.underline,
+            font: run
+                .font
+                .or(document.default_font)
+                .and_then(|font| document.font(font))
+                .map(|font| font.name.as_str()),
+            size: run.size,
+            color: run.color.and_then(|color| document.color(color)),
+        };
+        // Runs that differed only by index may now be the same
+        match runs.last_mut() {
+            Some(last) if last.same_format(&run) => last.text.push_str(&run.text),
+            _ => runs.push(run),
+        }
+    }
+    runs
+}
+
+/// Whether two documents have the same content
+///
+/// Compares the formatted text and pictures of the documents, so that RTF
+/// written differently but reading the same compares equal: the case of hex
+/// escapes, delimiter spaces and line breaks, groups that only scope
+/// formatting, the order of the font and color tables, and everything in
+/// ignored destinations such as the info group and generator.  Use this to
+/// check that a pipeline rewriting documents kept what they say.
+pub fn semantic_eq(a: &[Token], b: &[Token]) -> bool {
+    let (document_a, document_b) = (Document::from_tokens(a), Document::from_tokens(b));
+    let data = |tokens| -> Vec<Vec<u8>> {
+        pictures(tokens)
+            .into_iter()
+            .map(|picture| picture.data)
+            .collect()
+    };
+    resolved_runs(a, &document_a) == resolved_runs(b, &document_b) && data(a) == data(b)
+}
+
+impl std::fmt::Display for Edit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Edit::Delete { a, b } => write!(f, "delete a[{:?}] at b[{}]", a, b),
+            Edit::Insert { a, b } => write!(f, "insert b[{:?}] at a[{}]", b, a),
+            Edit::Replace { a, b } => write!(f, "replace a[{:?}] with b[{:?}]", a, b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    // Apply edits to `a`, which should give `b`
+    fn apply<T: Clone>(a: &[T], b: &[T], edits: &[Edit]) -> Vec<T> {
+        let mut out = Vec::new();
+        let mut i = 0;
+        for edit in edits {
+            let (skip, insert) = match edit {
+                Edit::Delete { a, .. } => (a.clone(), 0..0),
+                Edit::Insert { a, b } => (*a..*a, b.clone()),
+                Edit::Replace { a, b } => (a.clone(), b.clone()),
+            };
+            out.extend_from_slice(&a[i..skip.start]);
+            out.extend_from_slice(&b[insert]);
+            i = skip.end;
+        }
+        out.extend_from_slice(&a[i..]);
+        out
+    }
+
+    #[test]
+    fn test_diff() {
+        let a: Vec<char> = "abcabba".chars().collect();
+        let b: Vec<char> = "cbabac".chars().collect();
+        let edits = diff(&a, &b);
+        assert_eq!(apply(&a, &b, &edits), b);
+        assert_eq!(diff(&a, &a), vec![]);
+        assert_eq!(
+            diff(&['x', 'y'], &['x', 'z', 'y']),
+            vec![Edit::Insert { a: 1, b: 1..2 }]
+        );
+        assert_eq!(
+            diff(&['x', 'y', 'z'], &['x', 'z']),
+            vec![Edit::Delete { a: 1..2, b: 1 }]
+        );
+        assert_eq!(
+            diff(&['x', 'y', 'z'], &['x', 'q', 'z']),
+            vec![Edit::Replace { a: 1..2, b: 1..2 }]
+        );
+        assert_eq!(
+            diff::<char>(&[], &['x']),
+            vec![Edit::Insert { a: 0, b: 0..1 }]
+        );
+    }
+
+    #[test]
+    fn test_diff_tokens() {
+        // Line breaks are tokens too, so show up as edits
+        let a = parse(b"{\\rtf1\\b Hello\\b0  world\\par}").unwrap();
+        let b = parse(b"{\\rtf1\r\n\\b Hello\\b0  world\\par}").unwrap();
+        assert_eq!(diff_tokens(&a, &b), vec![Edit::Insert { a: 2, b: 2..3 }]);
+
+        let b = parse(b"{\\rtf1\\i Hello\\b0  world\\par}").unwrap();
+        let edits = diff_tokens(&a, &b);
+        assert_eq!(edits, vec![Edit::Replace { a: 2..3, b: 2..3 }]);
+        assert_eq!(apply(&a, &b, &edits), b);
+    }
+
+    #[test]
+    fn test_diff_text() {
+        let a = parse(b"{\\rtf1 Hello {\\b big} world}").unwrap();
+        let b = parse(b"{\\rtf1 Hello {\\b\\i big} world}").unwrap();
+        let (runs_a, runs_b, edits) = diff_text(&a, &b);
+        assert_eq!(edits, vec![Edit::Replace { a: 1..2, b: 1..2 }]);
+        assert_eq!(runs_a[1].text, "big");
+        assert!(runs_b[1].italic);
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let a = parse(b"{\\rtf1{\\fonttbl{\\f0 Arial;}{\\f1 Courier;}}{\\colortbl;\\red255\\green0\\blue0;}{\\info{\\author A}}{\\*\\generator Writer 1;}\\f0 Caf\\'e9 \\b bold\\b0  {\\f1\\cf1 code}\\par}").unwrap();
+        let b = parse(b"{\\rtf1\r\n{\\fonttbl{\\f3 Courier;}{\\f7 Arial;}}{\\colortbl;\\red0\\green0\\blue0;\\red255\\green0\\blue0;}{\\info{\\author B}}\\f7 Caf\\'E9 {\\b bold} \\cf2\\f3 co{}de\\cf0\\f7\\par}").unwrap();
+        assert!(semantic_eq(&a, &b));
+        assert!(!diff_tokens(&a, &b).is_empty());
+
+        let c = parse(b"{\\rtf1{\\fonttbl{\\f0 Arial;}{\\f1 Courier;}}\\f0 Caf\\'e9 \\b bold\\b0  {\\f1 code}\\par}").unwrap();
+        assert!(!semantic_eq(&a, &c));
+        let c = parse(b"{\\rtf1{\\fonttbl{\\f0 Arial;}{\\f1 Courier;}}{\\colortbl;\\red255\\green0\\blue0;}\\f0 Cafe \\b bold\\b0  {\\f1\\cf1 code}\\par}").unwrap();
+        assert!(!semantic_eq(&a, &c));
+
+        let pict = parse(b"{\\rtf1 x{\\pict\\pngblip 89504E}}").unwrap();
+        let other = parse(b"{\\rtf1 x{\\pict\\pngblip 89504f}}").unwrap();
+        assert!(semantic_eq(
+            &pict,
+            &parse(b"{\\rtf1 x{\\pict\\pngblip\r\n89504e}}").unwrap()
+        ));
+        assert!(!semantic_eq(&pict, &other));
+    }
+
+    #[test]
+    fn test_diff_sample_doc() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let a = parse(test_bytes).unwrap();
+        let mut b = a.clone();
+        b.remove(100);
+        b.insert(2000, Token::Text(b"inserted".to_vec()));
+        b.retain(|token| *token != Token::Newline);
+        let edits = diff_tokens(&a, &b);
+        assert_eq!(apply(&a, &b, &edits), b);
+    }
+}
@@ -0,0 +1,1993 @@
+// Document model
+//
+// Interprets a token stream into a structured view of the document's
+// content: paragraphs of formatted text runs, tables, and the font and color
+// tables needed to make sense of the formatting.
+//
+
+use std;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use fields;
+use metadata::{HexData, Protection};
+use shape::{Shape, ShapeReader};
+use text::{decode_cp1252, Decoded, Decoder};
+use tokenizer::{narrow, narrow_arg, parse, ParseError, Token};
+
+/// An entry in the document's color table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// An entry in the document's font table
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Font {
+    /// The number used to select this font with `\fN`
+    pub index: i32,
+    pub name: String,
+    /// Font family, from `\froman`, `\fswiss`, etc., without the leading `f`
+    pub family: Option<String>,
+    pub charset: Option<i32>,
+}
+
+/// Character formatting properties
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CharFormat {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strike: bool,
+    /// Hidden text (`\v`)
+    pub hidden: bool,
+    /// Font table index
+    pub font: Option<i32>,
+    /// Font size in half-points
+    pub size: Option<i32>,
+    /// Color table index
+    pub color: Option<usize>,
+    /// Tracked insertion (`\revised`)
+    pub revised: bool,
+    /// Tracked deletion (`\deleted`)
+    pub deleted: bool,
+    /// Revision table index of the insertion's author (`\revauthN`)
+    pub revision_author: Option<i32>,
+    /// Revision table index of the deletion's author (`\revauthdelN`)
+    pub deletion_author: Option<i32>,
+    /// Packed date and time of the insertion (`\revdttmN`)
+    pub revision_time: Option<i32>,
+    /// Packed date and time of the deletion (`\revdttmdelN`)
+    pub deletion_time: Option<i32>,
+    /// Language of the text, a Windows language id (`\langN`)
+    pub language: Option<i32>,
+    /// Language of right-to-left and East Asian text (`\alangN`)
+    pub associated_language: Option<i32>,
+    /// Right-to-left text (`\rtlch`), rather than left-to-right (`\ltrch`)
+    pub rtl: bool,
+}
+
+impl CharFormat {
+    /// Apply a character formatting control word, returning false if the
+    /// control word isn't one
+    pub fn apply(&mut self, name: &str, arg: Option<i32>) -> bool {
+        let on = arg != Some(0);
+        match name {
+            "plain" => *self = CharFormat::default(),
+            "b" => self.bold = on,
+            "i" => self.italic = on,
+            "strike" | "striked" => self.strike = on,
+            "v" => self.hidden = on,
+            "ulnone" => self.underline = false,
+            "ul" | "uld" | "uldash" | "uldashd" | "uldashdd" | "uldb" | "ulhwave" | "ulth"
+            | "ulw" | "ulwave" => self.underline = on,
+            "f" => self.font = arg,
+            "fs" => self.size = arg,
+            "cf" => self.color = arg.map(|i| std::cmp::max(i, 0) as usize),
+            "revised" => self.revised = on,
+            "deleted" => self.deleted = on,
+            "revauth" => self.revision_author = arg,
+            "revauthdel" => self.deletion_author = arg,
+            "revdttm" => self.revision_time = arg,
+            "revdttmdel" => self.deletion_time = arg,
+            "lang" => self.language = arg,
+            "alang" => self.associated_language = arg,
+            "rtlch" => self.rtl = true,
+            "ltrch" => self.rtl = false,
+            _ => return false,
+        }
+        true
+    }
+
+    /// The language of text in this format: the associated language for
+    /// right-to-left text, and the language for the rest
+    pub fn text_language(&self) -> Option<i32> {
+        if self.rtl {
+            self.associated_language
+        } else {
+            self.language
+        }
+    }
+}
+
+/// A date and time packed into a 32 bit "DTTM" value, as used by revision
+/// marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl Timestamp {
+    /// Unpack a DTTM value
+    pub fn from_dttm(dttm: i32) -> Self {
+        let dttm = dttm as u32;
+        Timestamp {
+            year: 1900 + (dttm >> 20 & 0x1ff) as u16,
+            month: (dttm >> 16 & 0xf) as u8,
+            day: (dttm >> 11 & 0x1f) as u8,
+            hour: (dttm >> 6 & 0x1f) as u8,
+            minute: (dttm & 0x3f) as u8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevisionKind {
+    Insertion,
+    Deletion,
+}
+
+/// A tracked change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    pub kind: RevisionKind,
+    /// The author, from the revision table
+    pub author: Option<String>,
+    pub timestamp: Option<Timestamp>,
+    /// The inserted or deleted text
+    pub text: String,
+}
+
+/// A run of text sharing the same formatting
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Run {
+    /// The text of the run - line breaks (`\line`) appear as `'\n'` and tabs as
+    /// `'\t'`
+    pub text: String,
+    pub format: CharFormat,
+    /// Target of the hyperlink field this run is part of, if any, with the
+    /// bookmark it links to, if it does, as a `#` fragment
+    pub link: Option<String>,
+    /// Tracked changes covering the run - text that was inserted and then
+    /// deleted has both an insertion and a deletion
+    pub revisions: Vec<Revision>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    Bullet,
+    Numbered,
+}
+
+/// List membership of a paragraph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListItem {
+    pub kind: ListKind,
+    /// Nesting level, starting from 0
+    pub level: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StyleKind {
+    #[default]
+    Paragraph,
+    Character,
+    Section,
+    Table,
+}
+
+/// An entry in the document's stylesheet
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Style {
+    /// The number used to select this style with `\sN`, `\csN`, etc.
+    pub index: i32,
+    pub kind: StyleKind,
+    pub name: String,
+    /// The `\outlinelevelN` of the style, 0 for top level headings
+    pub outline_level: Option<i32>,
+    pub based_on: Option<i32>,
+}
+
+impl Style {
+    /// The heading level (starting from 1) of paragraphs in this style, from
+    /// its outline level or failing that a name like `heading 2`
+    pub fn heading_level(&self) -> Option<usize> {
+        if self.kind != StyleKind::Paragraph {
+            return None;
+        }
+        if let Some(level) = self.outline_level.filter(|level| (0..9).contains(level)) {
+            return Some(level as usize + 1);
+        }
+        let name = self.name.to_lowercase();
+        name.strip_prefix("heading")
+            .and_then(|level| level.trim().parse().ok())
+            .filter(|level| *level > 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justified,
+}
+
+/// Paragraph formatting properties
+///
+/// Indents and spacing are in twips (twentieths of a point).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParaFormat {
+    pub alignment: Alignment,
+    pub left_indent: i32,
+    pub right_indent: i32,
+    /// Indent of the first line, relative to the left indent
+    pub first_line_indent: i32,
+    pub space_before: i32,
+    pub space_after: i32,
+    /// Right-to-left paragraph (`\rtlpar`), rather than left-to-right
+    /// (`\ltrpar`)
+    pub rtl: bool,
+}
+
+impl ParaFormat {
+    /// Apply a paragraph formatting control word, returning false if the
+    /// control word isn't one
+    ///
+    /// `\pard` isn't handled here, since it resets more than formatting.
+    pub fn apply(&mut self, name: &str, arg: Option<i32>) -> bool {
+        let value = arg.unwrap_or(0);
+        match name {
+            "ql" => self.alignment = Alignment::Left,
+            "qc" => self.alignment = Alignment::Center,
+            "qr" => self.alignment = Alignment::Right,
+            "qj" | "qd" => self.alignment = Alignment::Justified,
+            "li" | "lin" => self.left_indent = value,
+            "ri" | "rin" => self.right_indent = value,
+            "fi" => self.first_line_indent = value,
+            "sb" => self.space_before = value,
+            "sa" => self.space_after = value,
+            "rtlpar" => self.rtl = true,
+            "ltrpar" => self.rtl = false,
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Paragraph {
+    pub runs: Vec<Run>,
+    pub list: Option<ListItem>,
+    /// Paragraph style, from `\sN`
+    pub style: Option<i32>,
+    pub format: ParaFormat,
+}
+
+impl Paragraph {
+    /// The text of the paragraph, without formatting
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+}
+
+/// How a cell takes part in a merge with its neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellMerge {
+    #[default]
+    None,
+    /// The first cell of a merged range (`\clmgf`, `\clvmgf`)
+    First,
+    /// A cell merged into the previous one (`\clmrg`, `\clvmrg`)
+    Merged,
+}
+
+/// The line style of a border
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BorderStyle {
+    /// No border (`\brdrnone`), or a border given no style
+    #[default]
+    None,
+    /// `\brdrs`
+    Single,
+    /// `\brdrth`
+    Thick,
+    /// `\brdrdb`
+    Double,
+    /// `\brdrtriple`
+    Triple,
+    /// `\brdrdot`
+    Dotted,
+    /// `\brdrdash` and its variants
+    Dashed,
+    /// `\brdrhair`
+    Hairline,
+    /// `\brdrwavy` and `\brdrwavydb`
+    Wavy,
+    /// Any of the other styles, like embossed, engraved or thick-thin
+    Other,
+}
+
+impl BorderStyle {
+    fn from_word(name: &str) -> Option<Self> {
+        Some(match name {
+            "brdrnone" | "brdrnil" | "brdrtbl" => BorderStyle::None,
+            "brdrs" => BorderStyle::Single,
+            "brdrth" => BorderStyle::Thick,
+            "brdrdb" => BorderStyle::Double,
+            "brdrtriple" => BorderStyle::Triple,
+            "brdrdot" => BorderStyle::Dotted,
+            "brdrdash" | "brdrdashsm" | "brdrdashd" | "brdrdashdd" | "brdrdashdotstr" => {
+                BorderStyle::Dashed
+            }
+            "brdrhair" => BorderStyle::Hairline,
+            "brdrwavy" | "brdrwavydb" => BorderStyle::Wavy,
+            "brdrsh" | "brdroutset" | "brdrinset" | "brdremboss" | "brdrengrave" | "brdrframe" => {
+                BorderStyle::Other
+            }
+            _ if name.starts_with("brdrtnth") || name.starts_with("brdrthtn") => BorderStyle::Other,
+            _ => return None,
+        })
+    }
+}
+
+/// A border, from a border control word like `\clbrdrt` and the `\brdr`
+/// words following it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Border {
+    pub style: BorderStyle,
+    /// Width of the line in twips (`\brdrwN`)
+    pub width: Option<i32>,
+    /// Color of the line, as an index into the color table (`\brdrcfN`)
+    pub color: Option<usize>,
+}
+
+/// The borders of a table cell, where they're given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CellBorders {
+    /// `\clbrdrt`
+    pub top: Option<Border>,
+    /// `\clbrdrl`
+    pub left: Option<Border>,
+    /// `\clbrdrb`
+    pub bottom: Option<Border>,
+    /// `\clbrdrr`
+    pub right: Option<Border>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cell {
+    /// Paragraphs, and any nested tables, in the cell
+    pub blocks: Vec<Block>,
+    /// Position of the cell's right edge, in twips from the page margin
+    /// (`\cellxN`)
+    pub right_edge: Option<i32>,
+    /// Width of the cell in twips
+    pub width: Option<i32>,
+    /// Merge with the cells to the left and right
+    pub horizontal_merge: CellMerge,
+    /// Merge with the cells above and below
+    pub vertical_merge: CellMerge,
+    pub borders: CellBorders,
+    /// Background color, as an index into the color table (`\clcbpatN`)
+    pub background: Option<usize>,
+    /// Color of the shading pattern, as an index into the color table
+    /// (`\clcfpatN`)
+    pub pattern_color: Option<usize>,
+    /// How much of the pattern color shades the background, in hundredths of
+    /// a percent (`\clshdngN`)
+    pub shading: Option<i32>,
+}
+
+impl Cell {
+    /// All paragraphs of the cell, including those in nested tables
+    pub fn paragraphs(&self) -> Vec<&Paragraph> {
+        let mut paragraphs = Vec::new();
+        collect_paragraphs(&self.blocks, &mut paragraphs);
+        paragraphs
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Row {
+    pub cells: Vec<Cell>,
+    /// The table style the row is in, as a stylesheet index (`\tsN`)
+    pub style: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Table {
+    pub rows: Vec<Row>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Paragraph(Paragraph),
+    Table(Table),
+}
+
+/// How a section starts relative to the one before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionBreak {
+    /// Continuous with the previous section (`\sbknone`)
+    None,
+    /// On a new column (`\sbkcol`)
+    Column,
+    /// On a new page (`\sbkpage`)
+    #[default]
+    Page,
+    /// On the next even page (`\sbkeven`)
+    EvenPage,
+    /// On the next odd page (`\sbkodd`)
+    OddPage,
+}
+
+/// Section formatting properties, including page setup
+///
+/// Sizes are in twips (twentieths of a point).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionFormat {
+    pub page_width: i32,
+    pub page_height: i32,
+    pub margin_left: i32,
+    pub margin_right: i32,
+    pub margin_top: i32,
+    pub margin_bottom: i32,
+    pub landscape: bool,
+    pub columns: i32,
+    /// Space between columns
+    pub column_spacing: i32,
+    pub section_break: SectionBreak,
+}
+
+impl Default for SectionFormat {
+    fn default() -> Self {
+        // The spec's defaults: US Letter, with 1.25" side and 1" top and
+        // bottom margins
+        SectionFormat {
+            page_width: 12240,
+            page_height: 15840,
+            margin_left: 1800,
+            margin_right: 1800,
+            margin_top: 1440,
+            margin_bottom: 1440,
+            landscape: false,
+            columns: 1,
+            column_spacing: 720,
+            section_break: SectionBreak::Page,
+        }
+    }
+}
+
+impl SectionFormat {
+    /// Apply a section formatting control word, returning false if the
+    /// control word isn't one
+    ///
+    /// `\sectd` isn't handled here, since it resets to the document's page
+    /// setup rather than the spec's.
+    pub fn apply(&mut self, name: &str, arg: Option<i32>) -> bool {
+        let value = arg.unwrap_or(0);
+        match name {
+            "pgwsxn" => self.page_width = value,
+            "pghsxn" => self.page_height = value,
+            "marglsxn" => self.margin_left = value,
+            "margrsxn" => self.margin_right = value,
+            "margtsxn" => self.margin_top = value,
+            "margbsxn" => self.margin_bottom = value,
+            "lndscpsxn" => self.landscape = arg != Some(0),
+            "cols" => self.columns = value,
+            "colsx" => self.column_spacing = value,
+            "sbknone" => self.section_break = SectionBreak::None,
+            "sbkcol" => self.section_break = SectionBreak::Column,
+            "sbkpage" => self.section_break = SectionBreak::Page,
+            "sbkeven" => self.section_break = SectionBreak::EvenPage,
+            "sbkodd" => self.section_break = SectionBreak::OddPage,
+            _ => return false,
+        }
+        true
+    }
+
+    // Apply a document formatting control word that sets the page setup
+    // sections start from
+    pub(crate) fn apply_document(&mut self, name: &str, arg: Option<i32>) -> bool {
+        let value = arg.unwrap_or(0);
+        match name {
+            "paperw" => self.page_width = value,
+            "paperh" => self.page_height = value,
+            "margl" => self.margin_left = value,
+            "margr" => self.margin_right = value,
+            "margt" => self.margin_top = value,
+            "margb" => self.margin_bottom = value,
+            "landscape" => self.landscape = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A section of the document: a run of blocks sharing page setup
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Section {
+    pub format: SectionFormat,
+    /// The section's blocks, as a range of `Document::blocks`
+    pub blocks: Range<usize>,
+}
+
+/// A point in the document text: a paragraph (counting paragraphs in the
+/// order `Document::paragraphs` returns them) and a character offset within
+/// its text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TextPosition {
+    pub paragraph: usize,
+    pub offset: usize,
+}
+
+/// A named bookmark, from `{\*\bkmkstart}` and `{\*\bkmkend}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub name: String,
+    pub start: TextPosition,
+    /// The end of the bookmarked range, or `None` if the bookmark is never
+    /// closed
+    pub end: Option<TextPosition>,
+}
+
+/// An annotation (comment), from `{\*\annotation}` and the author
+/// destinations preceding it
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Comment {
+    /// Author's initials, from `{\*\atnid}`
+    pub initials: Option<String>,
+    /// Author's name, from `{\*\atnauthor}`
+    pub author: Option<String>,
+    /// `{\*\atndate}` timestamp
+    pub date: Option<i64>,
+    /// The `{\*\atnref}` id linking the comment to the range it annotates
+    pub reference: Option<String>,
+    pub text: String,
+    /// Where the comment mark appears in the text
+    pub position: TextPosition,
+    /// The annotated range, from `{\*\atrfstart}` and `{\*\atrfend}`
+    pub anchor: Option<Range<TextPosition>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Document {
+    pub fonts: Vec<Font>,
+    pub styles: Vec<Style>,
+    /// The color table - `None` entries select the reader's default color
+    pub colors: Vec<Option<Color>>,
+    /// The `\deffN` default font
+    pub default_font: Option<i32>,
+    /// The `\deflangN` default language, a Windows language id
+    pub default_language: Option<i32>,
+    /// The `\adeflangN` default language of associated (bidirectional and
+    /// East Asian) text
+    pub default_associated_language: Option<i32>,
+    /// The `\ansicpgN` code page
+    pub code_page: Option<u32>,
+    pub protection: Protection,
+    pub blocks: Vec<Block>,
+    /// The document's sections, which together cover all of `blocks`
+    pub sections: Vec<Section>,
+    pub bookmarks: Vec<Bookmark>,
+    pub comments: Vec<Comment>,
+    /// Shapes and drawing objects, with the text of their text boxes, which
+    /// isn't part of `blocks`
+    pub shapes: Vec<Shape>,
+    /// Revision authors, from `{\*\revtbl}`
+    pub revision_authors: Vec<String>,
+}
+
+impl Document {
+    /// Parse and interpret a document
+    pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+        parse(bytes).map(|tokens| Document::from_tokens(&tokens))
+    }
+
+    /// Interpret a token stream as a document
+    pub fn from_tokens(tokens: &[Token]) -> Self {
+        let mut builder = Builder::default();
+        for token in tokens {
+            builder.token(token);
+        }
+        builder.finish()
+    }
+
+    pub fn font(&self, index: i32) -> Option<&Font> {
+        self.fonts.iter().find(|font| font.index == index)
+    }
+
+    /// Look up a paragraph style
+    pub fn style(&self, index: i32) -> Option<&Style> {
+        self.styles
+            .iter()
+            .find(|style| style.index == index && style.kind == StyleKind::Paragraph)
+    }
+
+    /// The heading level of a paragraph, from its style
+    pub fn heading_level(&self, paragraph: &Paragraph) -> Option<usize> {
+        self.style(paragraph.style.unwrap_or(0))
+            .and_then(Style::heading_level)
+    }
+
+    pub fn color(&self, index: usize) -> Option<Color> {
+        self.colors.get(index).cloned().and_then(|color| color)
+    }
+
+    /// The blocks of a section
+    pub fn section_blocks(&self, section: &Section) -> &[Block] {
+        &self.blocks[section.blocks.clone()]
+    }
+
+    /// All tracked changes in the document, in document order
+    pub fn revisions(&self) -> Vec<&Revision> {
+        self.paragraphs()
+            .into_iter()
+            .flat_map(|paragraph| paragraph.runs.iter())
+            .flat_map(|run| run.revisions.iter())
+            .collect()
+    }
+
+    /// All paragraphs of the document, including those inside tables, in
+    /// document order
+    pub fn paragraphs(&self) -> Vec<&Paragraph> {
+        let mut paragraphs = Vec::new();
+        collect_paragraphs(&self.blocks, &mut paragraphs);
+        paragraphs
+    }
+}
+
+fn collect_paragraphs<'d>(blocks: &'d [Block], paragraphs: &mut Vec<&'d Paragraph>) {
+    for block in blocks {
+        match block {
+            Block::Paragraph(paragraph) => paragraphs.push(paragraph),
+            Block::Table(table) => {
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        collect_paragraphs(&cell.blocks, paragraphs);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Paragraph properties that are needed to place a paragraph
+#[derive(Debug, Clone, Default)]
+struct ParaState {
+    in_table: bool,
+    // Table nesting depth from \itapN
+    itap: Option<i32>,
+    list_override: Option<i32>,
+    list_level: usize,
+    style: Option<i32>,
+    format: ParaFormat,
+    // List kind from old-style \pn paragraph numbering
+    pn_kind: Option<ListKind>,
+}
+
+impl ParaState {
+    // Table nesting depth of the paragraph, 0 outside of tables
+    fn table_depth(&self) -> usize {
+        match self.itap {
+            Some(itap) => std::cmp::max(itap, 0) as usize,
+            None if self.in_table => 1,
+            None => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct CellDef {
+    right_edge: i32,
+    horizontal_merge: CellMerge,
+    vertical_merge: CellMerge,
+    borders: CellBorders,
+    background: Option<usize>,
+    pattern_color: Option<usize>,
+    shading: Option<i32>,
+}
+
+impl CellDef {
+    // The border of the side a cell border word like \clbrdrt is for
+    fn border(&mut self, name: &str) -> Option<&mut Option<Border>> {
+        Some(match name {
+            "clbrdrt" => &mut self.borders.top,
+            "clbrdrl" => &mut self.borders.left,
+            "clbrdrb" => &mut self.borders.bottom,
+            "clbrdrr" => &mut self.borders.right,
+            _ => return None,
+        })
+    }
+}
+
+// Row properties, from \trowd up to the \row that ends the row
+#[derive(Debug, Clone, Default)]
+struct RowDef {
+    left: i32,
+    style: Option<i32>,
+    cells: Vec<CellDef>,
+    // Properties for the next \cellx
+    pending: CellDef,
+    // The cell border word the \brdr words that follow describe, if they
+    // describe a cell border
+    border: Option<&'static str>,
+}
+
+// A table being read, with its row and cell in progress
+#[derive(Debug, Default)]
+struct OpenTable {
+    table: Table,
+    row: Row,
+    cell: Cell,
+}
+
+// The words starting a cell border
+const CELL_BORDERS: &[&str] = &["clbrdrt", "clbrdrl", "clbrdrb", "clbrdrr"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureKind {
+    BookmarkStart,
+    BookmarkEnd,
+    AnchorStart,
+    AnchorEnd,
+    Initials,
+    Author,
+    Annotation,
+    Reference,
+    Date,
+    RevisionTable,
+}
+
+impl CaptureKind {
+    fn from_destination(name: &str) -> Option<Self> {
+        Some(match name {
+            "bkmkstart" => CaptureKind::BookmarkStart,
+            "bkmkend" => CaptureKind::BookmarkEnd,
+            "atrfstart" => CaptureKind::AnchorStart,
+            "atrfend" => CaptureKind::AnchorEnd,
+            "atnid" => CaptureKind::Initials,
+            "atnauthor" => CaptureKind::Author,
+            "annotation" => CaptureKind::Annotation,
+            "atnref" => CaptureKind::Reference,
+            "atndate" => CaptureKind::Date,
+            "revtbl" => CaptureKind::RevisionTable,
+            _ => return None,
+        })
+    }
+}
+
+// The text of a destination that's being read for its content, decoded
+// independently of the document text
+#[derive(Debug)]
+struct Capture {
+    kind: CaptureKind,
+    // Decoder depth of the destination group
+    depth: usize,
+    decoder: Decoder,
+    text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct GroupState {
+    format: CharFormat,
+    para: ParaState,
+}
+
+#[derive(Debug)]
+struct Field {
+    // Decoder depth of the field group
+    depth: usize,
+    instruction: String,
+}
+
+impl Field {
+    // The target of a HYPERLINK field, with the bookmark it links to as a
+    // fragment
+    fn link(&self) -> Option<String> {
+        match fields::Field::parse(&self.instruction)? {
+            fields::Field::Hyperlink { target, anchor, .. } => {
+                let target = target.unwrap_or_default();
+                Some(match anchor {
+                    Some(anchor) => format!("{}#{}", target, anchor),
+                    None => target,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Builder {
+    decoder: Decoder,
+    doc: Document,
+    stack: Vec<GroupState>,
+    state: GroupState,
+
+    paragraph: Paragraph,
+    // Open tables, outermost first, and the row definitions for each level
+    tables: Vec<OpenTable>,
+    row_defs: Vec<RowDef>,
+    fields: Vec<Field>,
+
+    // Bookmark and annotation state
+    captures: Vec<Capture>,
+    shapes: ShapeReader,
+    paragraph_count: usize,
+    password_hash: Option<HexData>,
+
+    // Page setup from the document formatting, and the section in progress
+    page: SectionFormat,
+    section: SectionFormat,
+    section_start: usize,
+    anchors: HashMap<String, Range<TextPosition>>,
+    initials: Option<String>,
+    author: Option<String>,
+    comment: Option<Comment>,
+
+    // Font, color and style table parsing state
+    font: Option<Font>,
+    stylesheet: Option<usize>,
+    style: Option<Style>,
+    color: Option<Color>,
+    // List table parsing state: levels' \levelnfc values for the list being
+    // read, then the resulting list id -> level kinds and override -> list id
+    // mappings
+    list_levels: Vec<i32>,
+    lists: HashMap<i32, Vec<i32>>,
+    list_overrides: HashMap<i32, i32>,
+}
+
+fn list_kind(nfc: i32) -> ListKind {
+    // 23 is a bullet, 255 is no number at all
+    match nfc {
+        23 | 255 => ListKind::Bullet,
+        _ => ListKind::Numbered,
+    }
+}
+
+impl Builder {
+    fn token(&mut self, token: &Token) {
+        let decoded = self.decoder.token(token);
+        self.capture(token, &decoded);
+        if let Some(mut shape) = self.shapes.token(token, self.decoder.state()) {
+            shape.position = self.position();
+            self.doc.shapes.push(shape);
+        }
+        match decoded {
+            Decoded::GroupStart => {
+                self.stack.push(self.state.clone());
+                if self.stylesheet.map(|depth| depth + 1) == Some(self.decoder.depth()) {
+                    self.style = Some(Style::default());
+                }
+            }
+            Decoded::GroupEnd => {
+                if let Some(depth) = self.stylesheet {
+                    if self.decoder.depth() <= depth {
+                        self.end_style();
+                    }
+                    if self.decoder.depth() < depth {
+                        self.stylesheet = None;
+                    }
+                }
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+                let depth = self.decoder.depth();
+                while self.fields.last().is_some_and(|field| field.depth > depth) {
+                    self.fields.pop();
+                }
+            }
+            Decoded::Ignored => self.ignored(token),
+            // Text boxes are read by the shape reader
+            Decoded::Text(_) | Decoded::Word(..) if self.decoder.text_box() => {}
+            Decoded::Text(text) => self.text(&text),
+            Decoded::Word(name, arg) => self.word(name, arg),
+            Decoded::Nothing => {}
+        }
+    }
+
+    // Feed a token to the destination being captured, if any
+    fn capture(&mut self, token: &Token, decoded: &Decoded) {
+        let depth = self.decoder.depth();
+        if let Token::ControlWord { name, .. } = token {
+            let starts = self.decoder.destination() == Some(name.as_str())
+                && self.captures.last().map(|capture| capture.depth) != Some(depth);
+            if let Some(kind) = CaptureKind::from_destination(name).filter(|_| starts) {
+                if kind == CaptureKind::Annotation {
+                    self.comment = Some(Comment {
+                        initials: self.initials.take(),
+                        author: self.author.take(),
+                        position: self.position(),
+                        ..Comment::default()
+                    });
+                }
+                self.captures.push(Capture {
+                    kind,
+                    depth,
+                    decoder: Decoder::with_state(self.decoder.state().clone()),
+                    text: String::new(),
+                });
+                return;
+            }
+        }
+        if *decoded == Decoded::GroupEnd {
+            while self
+                .captures
+                .last()
+                .is_some_and(|capture| capture.depth > depth)
+            {
+                if let Some(capture) = self.captures.pop() {
+                    self.end_capture(capture);
+                }
+            }
+        }
+        if let Some(capture) = self.captures.last_mut() {
+            match capture.decoder.token(token) {
+                Decoded::Text(text) => capture.text.push_str(&text),
+                Decoded::Word("par", _) | Decoded::Word("line", _) => capture.text.push('\n'),
+                Decoded::Word("tab", _) => capture.text.push('\t'),
+                _ => {}
+            }
+        }
+    }
+
+    fn end_capture(&mut self, capture: Capture) {
+        let position = self.position();
+        let text = capture.text.trim().to_string();
+        match capture.kind {
+            CaptureKind::BookmarkStart => self.doc.bookmarks.push(Bookmark {
+                name: text,
+                start: position,
+                end: None,
+            }),
+            CaptureKind::BookmarkEnd => {
+                let bookmark = self
+                    .doc
+                    .bookmarks
+                    .iter_mut()
+                    .rev()
+                    .find(|bookmark| bookmark.name == text && bookmark.end.is_none());
+                if let Some(bookmark) = bookmark {
+                    bookmark.end = Some(position);
+                }
+            }
+            CaptureKind::AnchorStart => {
+                self.anchors.insert(text, position..position);
+            }
+            CaptureKind::AnchorEnd => {
+                if let Some(anchor) = self.anchors.get_mut(&text) {
+                    anchor.end = position;
+                }
+            }
+            CaptureKind::Initials => self.initials = Some(text),
+            CaptureKind::Author => self.author = Some(text),
+            CaptureKind::Annotation => {
+                if let Some(mut comment) = self.comment.take() {
+                    comment.text = text;
+                    self.doc.comments.push(comment);
+                }
+            }
+            CaptureKind::Reference => {
+                if let Some(ref mut comment) = self.comment {
+                    comment.reference = Some(text);
+                }
+            }
+            CaptureKind::RevisionTable => {
+                let mut authors: Vec<String> = text
+                    .split(';')
+                    .map(|name| name.trim().to_string())
+                    .collect();
+                if authors.last().is_some_and(String::is_empty) {
+                    authors.pop();
+                }
+                self.doc.revision_authors = authors;
+            }
+            CaptureKind::Date => {
+                if let Some(ref mut comment) = self.comment {
+                    comment.date = text.parse().ok();
+                }
+            }
+        }
+    }
+
+    // The current position in the document text
+    fn position(&self) -> TextPosition {
+        TextPosition {
+            paragraph: self.paragraph_count,
+            offset: self
+                .paragraph
+                .runs
+                .iter()
+                .map(|run| run.text.chars().count())
+                .sum(),
+        }
+    }
+
+    // Tokens inside destinations that aren't document text, but which carry
+    // information the model needs
+    fn ignored(&mut self, token: &Token) {
+        if let Some(depth) = self.stylesheet {
+            if self.decoder.depth() > depth {
+                self.style_entry(token);
+            }
+            return;
+        }
+        if let Token::ControlWord { name, .. } = token {
+            if name == "stylesheet" {
+                self.stylesheet = Some(self.decoder.depth());
+                return;
+            }
+        }
+        let destination = self.decoder.destination().unwrap_or_default().to_string();
+        match destination.as_str() {
+            "fonttbl" | "f" => self.font_table(token),
+            "colortbl" => self.color_table(token),
+            "listtable" | "list" | "listlevel" => self.list_table(token),
+            "listoverridetable" | "listoverride" => {
+                if let Token::ControlWord {
+                    name,
+                    arg: Some(arg),
+                } = token
+                {
+                    match name.as_str() {
+                        "listid" => self.list_levels = vec![narrow(*arg)],
+                        "ls" => {
+                            if let Some(id) = self.list_levels.first() {
+                                self.list_overrides.insert(narrow(*arg), *id);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "fldinst" => {
+                let text = token_text(token);
+                if let Some(field) = self.fields.last_mut() {
+                    field.instruction.push_str(&text);
+                }
+            }
+            "nesttableprops" => {
+                if let Token::ControlWord { name, arg } = token {
+                    let depth = std::cmp::max(self.state.para.table_depth(), 2);
+                    if name == "nestrow" {
+                        self.end_row(depth);
+                    } else {
+                        self.row_definition(depth, name, narrow_arg(*arg));
+                    }
+                }
+            }
+            "passwordhash" => {
+                let hash = self.password_hash.get_or_insert_with(HexData::default);
+                if let Token::Text(text) = token {
+                    hash.push(text);
+                }
+            }
+            "pn" => {
+                let kind = match token {
+                    Token::ControlWord { name, .. } => match name.as_str() {
+                        "pnlvlblt" => ListKind::Bullet,
+                        "pnlvlbody" | "pnlvlcont" => ListKind::Numbered,
+                        _ => return,
+                    },
+                    _ => return,
+                };
+                // The \pn destination describes the paragraph it appears in,
+                // so the setting outlives the destination's own group
+                self.state.para.pn_kind = Some(kind);
+                if let Some(state) = self.stack.last_mut() {
+                    state.para.pn_kind = Some(kind);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn font_table(&mut self, token: &Token) {
+        match token {
+            Token::ControlWord { name, arg } => match (name.as_str(), narrow_arg(*arg)) {
+                ("f", Some(index)) => {
+                    self.font = Some(Font {
+                        index,
+                        ..Font::default()
+                    })
+                }
+                ("fcharset", Some(charset)) => {
+                    if let Some(ref mut font) = self.font {
+                        font.charset = Some(charset);
+                    }
+                }
+                (family, None)
+                    if [
+                        "fnil", "froman", "fswiss", "fmodern", "fscript", "fdecor", "ftech",
+                        "fbidi",
+                    ]
+                    .contains(&family) =>
+                {
+                    if let Some(ref mut font) = self.font {
+                        font.family = Some(family[1..].to_string());
+                    }
+                }
+                _ => {}
+            },
+            Token::Text(_) | Token::HexByte(_) => {
+                let text = token_text(token);
+                if let Some(ref mut font) = self.font {
+                    font.name.push_str(&text);
+                    if font.name.contains(';') {
+                        let name = font.name.split(';').next().unwrap_or_default();
+                        font.name = name.trim().to_string();
+                    }
+                }
+                if text.contains(';') {
+                    if let Some(font) = self.font.take() {
+                        self.doc.fonts.push(font);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn style_entry(&mut self, token: &Token) {
+        let style = match self.style {
+            Some(ref mut style) => style,
+            None => return,
+        };
+        match token {
+            Token::ControlWord { name, arg } => match (name.as_str(), narrow_arg(*arg)) {
+                ("s", Some(index)) => style.index = index,
+                ("cs", Some(index)) => {
+                    style.index = index;
+                    style.kind = StyleKind::Character;
+                }
+                ("ds", Some(index)) => {
+                    style.index = index;
+                    style.kind = StyleKind::Section;
+                }
+                ("ts", Some(index)) => {
+                    style.index = index;
+                    style.kind = StyleKind::Table;
+                }
+                ("outlinelevel", level) => style.outline_level = level,
+                ("sbasedon", based_on) => style.based_on = based_on,
+                _ => {}
+            },
+            _ => {
+                let text = token_text(token);
+                style.name.push_str(&text);
+                if text.contains(';') {
+                    self.end_style();
+                }
+            }
+        }
+    }
+
+    fn end_style(&mut self) {
+        if let Some(mut style) = self.style.take() {
+            let name = style.name.split(';').next().unwrap_or_default();
+            style.name = name.trim().to_string();
+            self.doc.styles.push(style);
+        }
+    }
+
+    fn color_table(&mut self, token: &Token) {
+        match token {
+            Token::ControlWord {
+                name,
+                arg: Some(arg),
+            } => {
+                let value = (*arg).clamp(0, 255) as u8;
+                let color = self.color.get_or_insert_with(Color::default);
+                match name.as_str() {
+                    "red" => color.red = value,
+                    "green" => color.green = value,
+                    "blue" => color.blue = value,
+                    _ => {}
+                }
+            }
+            Token::Text(data) => {
+                for _ in data.iter().filter(|b| **b == b';') {
+                    let color = self.color.take();
+                    self.doc.colors.push(color);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn list_table(&mut self, token: &Token) {
+        if let Token::ControlWord {
+            name,
+            arg: Some(arg),
+        } = token
+        {
+            match name.as_str() {
+                "levelnfc" => self.list_levels.push(narrow(*arg)),
+                "listid" if self.decoder.destination() == Some("list") => {
+                    let levels = std::mem::take(&mut self.list_levels);
+                    self.lists.insert(narrow(*arg), levels);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        match self.decoder.destination() {
+            // List markers are represented by the paragraph's list membership
+            Some("listtext") | Some("pntext") => return,
+            _ => {}
+        }
+        let link = self.fields.iter().rev().find_map(Field::link);
+        if let Some(run) = self.paragraph.runs.last_mut() {
+            if run.format == self.state.format && run.link == link {
+                run.text.push_str(text);
+                return;
+            }
+        }
+        self.paragraph.runs.push(Run {
+            text: text.to_string(),
+            format: self.state.format.clone(),
+            link,
+            revisions: Vec::new(),
+        });
+    }
+
+    fn revision(&self, kind: RevisionKind, author: Option<i32>, time: Option<i32>) -> Revision {
+        let author = author.and_then(|index| self.doc.revision_authors.get(index as usize));
+        Revision {
+            kind,
+            author: author.cloned(),
+            // A zero DTTM means the time wasn't recorded
+            timestamp: time.filter(|time| *time != 0).map(Timestamp::from_dttm),
+            text: String::new(),
+        }
+    }
+
+    // Record the tracked changes of a finished run
+    fn revisions(&self, run: &Run) -> Vec<Revision> {
+        let format = &run.format;
+        let mut revisions = Vec::new();
+        if format.revised {
+            revisions.push(self.revision(
+                RevisionKind::Insertion,
+                format.revision_author,
+                format.revision_time,
+            ));
+        }
+        if format.deleted {
+            revisions.push(self.revision(
+                RevisionKind::Deletion,
+                format.deletion_author,
+                format.deletion_time,
+            ));
+        }
+        for revision in &mut revisions {
+            revision.text = run.text.clone();
+        }
+        revisions
+    }
+
+    fn word(&mut self, name: &str, arg: Option<i32>) {
+        if self.state.format.apply(name, arg)
+            || self.state.para.format.apply(name, arg)
+            || self.section.apply(name, arg)
+        {
+            return;
+        }
+        if self.page.apply_document(name, arg) {
+            self.section.apply_document(name, arg);
+            return;
+        }
+        if self.doc.protection.apply(name, arg) {
+            return;
+        }
+        match name {
+            "field" => self.fields.push(Field {
+                depth: self.decoder.depth(),
+                instruction: String::new(),
+            }),
+
+            // Paragraph formatting
+            "pard" => self.state.para = ParaState::default(),
+            "intbl" => self.state.para.in_table = true,
+            "itap" => self.state.para.itap = arg,
+            "ls" => self.state.para.list_override = arg,
+            "s" => self.state.para.style = arg,
+            "ilvl" => self.state.para.list_level = std::cmp::max(arg.unwrap_or(0), 0) as usize,
+
+            // Structure
+            "par" => self.end_paragraph(),
+            "sect" => {
+                self.end_paragraph();
+                self.end_section();
+            }
+            "sectd" => self.section = self.page.clone(),
+            "line" => self.text("\n"),
+            "tab" => self.text("\t"),
+            "cell" | "nestcell" => {
+                let depth = std::cmp::max(self.state.para.table_depth(), 1);
+                self.place_paragraph(depth);
+                let open = &mut self.tables[depth - 1];
+                let cell = std::mem::take(&mut open.cell);
+                open.row.cells.push(cell);
+            }
+            "row" | "nestrow" => {
+                let depth = std::cmp::max(self.state.para.table_depth(), 1);
+                self.end_row(depth);
+            }
+            _ => {
+                self.row_definition(1, name, arg);
+            }
+        }
+    }
+
+    // Apply a table row property for the table at `depth`
+    fn row_definition(&mut self, depth: usize, name: &str, arg: Option<i32>) {
+        if self.row_defs.len() < depth {
+            self.row_defs.resize(depth, RowDef::default());
+        }
+        let def = &mut self.row_defs[depth - 1];
+        let color = arg.filter(|index| *index >= 0).map(|index| index as usize);
+        match name {
+            "trowd" => *def = RowDef::default(),
+            "trleft" => def.left = arg.unwrap_or(0),
+            "ts" => def.style = arg,
+            "clmgf" => def.pending.horizontal_merge = CellMerge::First,
+            "clmrg" => def.pending.horizontal_merge = CellMerge::Merged,
+            "clvmgf" => def.pending.vertical_merge = CellMerge::First,
+            "clvmrg" => def.pending.vertical_merge = CellMerge::Merged,
+            "clcbpat" => def.pending.background = color,
+            "clcfpat" => def.pending.pattern_color = color,
+            "clshdng" => def.pending.shading = arg,
+            "clbrdrt" | "clbrdrl" | "clbrdrb" | "clbrdrr" => {
+                if let Some(border) = def.pending.border(name) {
+                    *border = Some(Border::default());
+                }
+                def.border = CELL_BORDERS.iter().find(|side| **side == name).copied();
+            }
+            "cellx" => {
+                let mut cell = std::mem::take(&mut def.pending);
+                cell.right_edge = arg.unwrap_or(0);
+                def.cells.push(cell);
+                def.border = None;
+            }
+            _ if name.starts_with("brdr") => {
+                let border = match def.border {
+                    Some(side) => def.pending.border(side).and_then(Option::as_mut),
+                    None => None,
+                };
+                let border = match border {
+                    Some(border) => border,
+                    // Paragraph and row borders start with \brdrt and the
+                    // like, and \trbrdrt and the like
+                    None => {
+                        def.border = None;
+                        return;
+                    }
+                };
+                match name {
+                    "brdrw" => border.width = arg,
+                    "brdrcf" => border.color = color,
+                    _ => match BorderStyle::from_word(name) {
+                        Some(style) => border.style = style,
+                        None => def.border = None,
+                    },
+                }
+            }
+            _ if name.starts_with("trbrdr") => def.border = None,
+            _ => {}
+        }
+    }
+
+    fn list_item(&self) -> Option<ListItem> {
+        let para = &self.state.para;
+        if let Some(ls) = para.list_override {
+            let kind = self
+                .list_overrides
+                .get(&ls)
+                .and_then(|id| self.lists.get(id))
+                .and_then(|levels| levels.get(para.list_level))
+                .map_or(ListKind::Bullet, |nfc| list_kind(*nfc));
+            return Some(ListItem {
+                kind,
+                level: para.list_level,
+            });
+        }
+        para.pn_kind.map(|kind| ListItem { kind, level: 0 })
+    }
+
+    fn end_paragraph(&mut self) {
+        let depth = self.state.para.table_depth();
+        self.place_paragraph(depth);
+    }
+
+    // End the current paragraph, placing it in the innermost cell at table
+    // nesting depth `depth`
+    fn place_paragraph(&mut self, depth: usize) {
+        let mut paragraph = std::mem::take(&mut self.paragraph);
+        paragraph.list = self.list_item();
+        paragraph.style = self.state.para.style;
+        paragraph.format = self.state.para.format.clone();
+        for index in 0..paragraph.runs.len() {
+            paragraph.runs[index].revisions = self.revisions(&paragraph.runs[index]);
+        }
+        self.nest_tables(depth);
+        self.blocks().push(Block::Paragraph(paragraph));
+        self.paragraph_count += 1;
+    }
+
+    // Where blocks are currently being added
+    fn blocks(&mut self) -> &mut Vec<Block> {
+        match self.tables.last_mut() {
+            Some(open) => &mut open.cell.blocks,
+            None => &mut self.doc.blocks,
+        }
+    }
+
+    // Open or close tables so that exactly `depth` are open
+    fn nest_tables(&mut self, depth: usize) {
+        while self.tables.len() > depth {
+            let mut open = self.tables.pop().unwrap_or_default();
+            if !open.cell.blocks.is_empty() {
+                open.row.cells.push(open.cell);
+            }
+            if !open.row.cells.is_empty() {
+                open.table.rows.push(open.row);
+            }
+            self.blocks().push(Block::Table(open.table));
+        }
+        while self.tables.len() < depth {
+            self.tables.push(OpenTable::default());
+        }
+    }
+
+    fn end_row(&mut self, depth: usize) {
+        if self.tables.len() < depth {
+            self.nest_tables(depth);
+        }
+        let def = self.row_defs.get(depth - 1).cloned().unwrap_or_default();
+        let open = &mut self.tables[depth - 1];
+        let mut row = std::mem::take(&mut open.row);
+        let mut left = def.left;
+        for (cell, def) in row.cells.iter_mut().zip(def.cells.iter()) {
+            cell.right_edge = Some(def.right_edge);
+            cell.width = Some(def.right_edge - left);
+            cell.horizontal_merge = def.horizontal_merge;
+            cell.vertical_merge = def.vertical_merge;
+            cell.borders = def.borders;
+            cell.background = def.background;
+            cell.pattern_color = def.pattern_color;
+            cell.shading = def.shading;
+            left = def.right_edge;
+        }
+        row.style = def.style;
+        open.table.rows.push(row);
+    }
+
+    fn end_section(&mut self) {
+        self.doc.sections.push(Section {
+            format: self.section.clone(),
+            blocks: self.section_start..self.doc.blocks.len(),
+        });
+        self.section_start = self.doc.blocks.len();
+    }
+
+    fn finish(mut self) -> Document {
+        if !self.paragraph.runs.is_empty() {
+            self.end_paragraph();
+        }
+        self.nest_tables(0);
+        if self.doc.sections.is_empty() || self.section_start < self.doc.blocks.len() {
+            self.end_section();
+        }
+        let state = self.decoder.state();
+        self.doc.default_font = state.default_font;
+        self.doc.default_language = state.language;
+        self.doc.default_associated_language = state.associated_language;
+        self.doc.code_page = state.code_page;
+        self.doc.protection.password_hash = self.password_hash.map(|hash| hash.data);
+        let anchors = &self.anchors;
+        for comment in &mut self.doc.comments {
+            let anchor = comment.reference.as_ref().and_then(|id| anchors.get(id));
+            comment.anchor = anchor.cloned();
+        }
+        self.doc
+    }
+}
+
+// Decode the text of a token inside an ignored destination
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Text(data) => data.iter().map(|b| decode_cp1252(*b)).collect(),
+        Token::HexByte(byte) => decode_cp1252(*byte).to_string(),
+        Token::ControlSymbol(c) if ['\\', '{', '}'].contains(c) => c.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(doc: &Document, index: usize) -> &Paragraph {
+        match doc.blocks[index] {
+            Block::Paragraph(ref paragraph) => paragraph,
+            ref block => panic!("Expected a paragraph, found {:?}", block),
+        }
+    }
+
+    #[test]
+    fn test_tables() {
+        let doc = Document::parse(
+            br#"{\rtf1{\fonttbl{\f0\froman\fcharset0 Times New Roman;}{\f1\fswiss Arial;}}{\colortbl;\red255\green0\blue0;}\deff0 Hi\par}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.fonts,
+            vec![
+                Font {
+                    index: 0,
+                    name: "Times New Roman".to_string(),
+                    family: Some("roman".to_string()),
+                    charset: Some(0),
+                },
+                Font {
+                    index: 1,
+                    name: "Arial".to_string(),
+                    family: Some("swiss".to_string()),
+                    charset: None,
+                },
+            ]
+        );
+        assert_eq!(
+            doc.colors,
+            vec![
+                None,
+                Some(Color {
+                    red: 255,
+                    green: 0,
+                    blue: 0,
+                }),
+            ]
+        );
+        assert_eq!(doc.default_font, Some(0));
+        assert_eq!(paragraph(&doc, 0).text(), "Hi");
+    }
+
+    #[test]
+    fn test_document_defaults() {
+        let doc = Document::parse(
+            br#"{\rtf1\ansi\ansicpg1251\deff0\deflang1049\adeflang1025{\fonttbl{\f0\fnil\fcharset204 Arial;}}\'c4\'e0{\*\atnauthor \'c8}\chatn{\*\annotation \'cd\'e5\'f2}\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.default_font, Some(0));
+        assert_eq!(doc.default_language, Some(1049));
+        assert_eq!(doc.default_associated_language, Some(1025));
+        assert_eq!(doc.code_page, Some(1251));
+        assert_eq!(paragraph(&doc, 0).text(), "\u{414}\u{430}");
+        assert_eq!(doc.comments[0].text, "\u{41d}\u{435}\u{442}");
+    }
+
+    #[test]
+    fn test_protection() {
+        let doc = Document::parse(
+            br#"{\rtf1\revprot\protlevel0\enforceprot1{\*\passwordhash 0102 03}\pard Hi\par}"#,
+        )
+        .unwrap();
+        assert!(doc.protection.revisions && !doc.protection.forms);
+        assert!(doc.protection.is_protected());
+        assert_eq!(doc.protection.level, Some(0));
+        assert_eq!(doc.protection.password_hash, Some(vec![1, 2, 3]));
+        assert_eq!(paragraph(&doc, 0).text(), "Hi");
+    }
+
+    #[test]
+    fn test_runs() {
+        let doc = Document::parse(
+            br#"{\rtf1 Plain {\b bold {\i both}} \ul under\ulnone\fs28\cf1 big\plain  x\par}"#,
+        )
+        .unwrap();
+        let runs = &paragraph(&doc, 0).runs;
+        let texts: Vec<&str> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Plain ", "bold ", "both", " ", "under", "big", " x"]
+        );
+        assert!(runs[1].format.bold && !runs[1].format.italic);
+        assert!(runs[2].format.bold && runs[2].format.italic);
+        assert!(runs[4].format.underline);
+        assert_eq!(runs[5].format.size, Some(28));
+        assert_eq!(runs[5].format.color, Some(1));
+        assert!(!runs[5].format.underline);
+        assert_eq!(runs[6].format, CharFormat::default());
+    }
+
+    #[test]
+    fn test_paragraph_format() {
+        let doc = Document::parse(
+            br#"{\rtf1\pard\qc\li720\ri360\fi-360\sb120\sa240\rtlpar\s3 Centered\par Still\par\pard Reset\par{\qr Grouped\par}}"#,
+        )
+        .unwrap();
+        let paragraphs = doc.paragraphs();
+        let format = ParaFormat {
+            alignment: Alignment::Center,
+            left_indent: 720,
+            right_indent: 360,
+            first_line_indent: -360,
+            space_before: 120,
+            space_after: 240,
+            rtl: true,
+        };
+        assert_eq!(paragraphs[0].format, format);
+        assert_eq!(paragraphs[0].style, Some(3));
+        assert_eq!(paragraphs[1].format, format);
+        assert_eq!(paragraphs[2].format, ParaFormat::default());
+        assert_eq!(paragraphs[2].style, None);
+        assert_eq!(paragraphs[3].format.alignment, Alignment::Right);
+    }
+
+    #[test]
+    fn test_stylesheet() {
+        let doc = Document::parse(
+            br#"{\rtf1{\stylesheet{\ql\fs24 Normal;}{\s1\outlinelevel0\b\fs32 heading 1;}{\s2\sbasedon1 Heading 2;}{\*\cs10\additive Default Paragraph Font;}}
+\pard\s1 Title\par\pard\s2 Sub\par\pard Body\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.styles.len(), 4);
+        assert_eq!(doc.styles[0].name, "Normal");
+        assert_eq!(doc.styles[3].kind, StyleKind::Character);
+        assert_eq!(doc.styles[2].based_on, Some(1));
+        let levels: Vec<Option<usize>> = doc
+            .paragraphs()
+            .iter()
+            .map(|p| doc.heading_level(p))
+            .collect();
+        assert_eq!(levels, vec![Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let doc = Document::parse(
+            br#"{\rtf1 First\par Go {\*\bkmkstart intro}here{\*\bkmkend intro} now{\*\bkmkstart open}\par}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.bookmarks,
+            vec![
+                Bookmark {
+                    name: "intro".to_string(),
+                    start: TextPosition {
+                        paragraph: 1,
+                        offset: 3,
+                    },
+                    end: Some(TextPosition {
+                        paragraph: 1,
+                        offset: 7,
+                    }),
+                },
+                Bookmark {
+                    name: "open".to_string(),
+                    start: TextPosition {
+                        paragraph: 1,
+                        offset: 11,
+                    },
+                    end: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let doc = Document::parse(
+            br#"{\rtf1 The {\*\atrfstart 1}term{\*\atrfend 1} is{\*\atnid JD}{\*\atnauthor Jane D\u246\'65}\chatn {\*\annotation{\*\atnref 1}{\*\atndate 1234567}\pard\plain Too {\b vague}.\par Fix it\par}.\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.paragraphs()[0].text(), "The term is.");
+        let position = |offset| TextPosition {
+            paragraph: 0,
+            offset,
+        };
+        assert_eq!(
+            doc.comments,
+            vec![Comment {
+                initials: Some("JD".to_string()),
+                author: Some("Jane D\u{f6}".to_string()),
+                date: Some(1234567),
+                reference: Some("1".to_string()),
+                text: "Too vague.\nFix it".to_string(),
+                position: position(11),
+                anchor: Some(position(4)..position(8)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_shapes() {
+        let doc = Document::parse(
+            br#"{\rtf1\pard Before {\shp{\*\shpinst\shpleft0\shptop0\shpright2000\shpbottom1000{\sp{\sn shapeType}{\sv 202}}{\shptxt \pard\b Boxed\par Text\par}}{\shprslt{\*\do\dptxbx{\dptxbxtext Boxed}}}}after.\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.paragraphs().len(), 1);
+        assert_eq!(doc.paragraphs()[0].text(), "Before after.");
+        assert!(!doc.paragraphs()[0].runs.iter().any(|run| run.format.bold));
+        assert_eq!(doc.shapes.len(), 1);
+        let shape = &doc.shapes[0];
+        assert_eq!(shape.shape_type(), Some(202));
+        assert_eq!(shape.right, Some(2000));
+        assert_eq!(shape.text.as_deref(), Some("Boxed\nText"));
+        assert_eq!(
+            shape.position,
+            TextPosition {
+                paragraph: 0,
+                offset: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn test_revisions() {
+        let doc = Document::parse(
+            br#"{\rtf1{\*\revtbl {Unknown;}{Jane Doe;}{John Roe;}}Keep {\revised\revauth1\revdttm661792057 added}{\deleted\revauthdel2\revdttmdel3883017529 gone}\par}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.revision_authors,
+            vec!["Unknown", "Jane Doe", "John Roe"]
+        );
+        let revisions = doc.revisions();
+        assert_eq!(
+            revisions,
+            vec![
+                &Revision {
+                    kind: RevisionKind::Insertion,
+                    author: Some("Jane Doe".to_string()),
+                    timestamp: Some(Timestamp {
+                        year: 2019,
+                        month: 2,
+                        day: 4,
+                        hour: 20,
+                        minute: 57,
+                    }),
+                    text: "added".to_string(),
+                },
+                &Revision {
+                    kind: RevisionKind::Deletion,
+                    author: Some("John Roe".to_string()),
+                    // Written unsigned, with the day of the week in the top
+                    // bits
+                    timestamp: Some(Timestamp {
+                        year: 2019,
+                        month: 2,
+                        day: 4,
+                        hour: 20,
+                        minute: 57,
+                    }),
+                    text: "gone".to_string(),
+                },
+            ]
+        );
+        assert!(doc.paragraphs()[0].runs[0].revisions.is_empty());
+    }
+
+    #[test]
+    fn test_sections() {
+        let doc = Document::parse(
+            br#"{\rtf1\paperw11906\paperh16838\margl1440\sectd\cols2 One\par Two\sect\sectd\sbknone\pgwsxn16838\pghsxn11906\lndscpsxn\marglsxn720 Three\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.sections.len(), 2);
+        let first = &doc.sections[0];
+        assert_eq!(first.blocks, 0..2);
+        assert_eq!(first.format.page_width, 11906);
+        assert_eq!(first.format.margin_left, 1440);
+        assert_eq!(first.format.margin_right, 1800);
+        assert_eq!(first.format.columns, 2);
+        let second = &doc.sections[1];
+        assert_eq!(doc.section_blocks(second).len(), 1);
+        assert_eq!(
+            second.format,
+            SectionFormat {
+                page_width: 16838,
+                page_height: 11906,
+                margin_left: 720,
+                landscape: true,
+                section_break: SectionBreak::None,
+                ..SectionFormat::default()
+            }
+        );
+
+        let doc = Document::parse(br#"{\rtf1 Only\par}"#).unwrap();
+        assert_eq!(
+            doc.sections,
+            vec![Section {
+                format: SectionFormat::default(),
+                blocks: 0..1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        let doc = Document::parse(
+            br#"{\rtf1 See {\field{\*\fldinst{HYPERLINK "https://example.com/"}}{\fldrslt{\ul here}}}.{\field{\*\fldinst HYPERLINK \\l "top"}{\fldrslt up}}\par}"#,
+        )
+        .unwrap();
+        let runs = &paragraph(&doc, 0).runs;
+        assert_eq!(runs[1].text, "here");
+        assert_eq!(runs[1].link, Some("https://example.com/".to_string()));
+        assert_eq!(runs[2].text, ".");
+        assert_eq!(runs[2].link, None);
+        assert_eq!(runs[3].link, Some("#top".to_string()));
+    }
+
+    #[test]
+    fn test_table() {
+        let doc = Document::parse(
+            br#"{\rtf1 Before\par\trowd\cellx1000\cellx2000\pard\intbl A\cell B\cell\row\trowd\cellx1000\pard\intbl C\cell\row\pard After\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.blocks.len(), 3);
+        match doc.blocks[1] {
+            Block::Table(ref table) => {
+                assert_eq!(table.rows.len(), 2);
+                assert_eq!(table.rows[0].cells.len(), 2);
+                assert_eq!(table.rows[0].cells[1].paragraphs()[0].text(), "B");
+                assert_eq!(table.rows[1].cells[0].paragraphs()[0].text(), "C");
+                assert_eq!(table.rows[0].cells[1].right_edge, Some(2000));
+                assert_eq!(table.rows[0].cells[1].width, Some(1000));
+            }
+            ref block => panic!("Expected a table, found {:?}", block),
+        }
+        assert_eq!(paragraph(&doc, 2).text(), "After");
+    }
+
+    #[test]
+    fn test_table_merges() {
+        let doc = Document::parse(
+            br#"{\rtf1\trowd\trleft-100\clmgf\cellx1000\clmrg\cellx2000\clvmgf\cellx3000\pard\intbl A\cell\cell B\cell\row
+\trowd\trleft-100\cellx1000\cellx2000\clvmrg\cellx3000\pard\intbl C\cell D\cell\cell\row\pard}"#,
+        )
+        .unwrap();
+        let table = match doc.blocks[0] {
+            Block::Table(ref table) => table,
+            ref block => panic!("Expected a table, found {:?}", block),
+        };
+        let first = &table.rows[0].cells;
+        assert_eq!(first[0].width, Some(1100));
+        assert_eq!(first[0].horizontal_merge, CellMerge::First);
+        assert_eq!(first[1].horizontal_merge, CellMerge::Merged);
+        assert_eq!(first[2].vertical_merge, CellMerge::First);
+        assert_eq!(table.rows[1].cells[2].vertical_merge, CellMerge::Merged);
+        assert_eq!(table.rows[1].cells[0].horizontal_merge, CellMerge::None);
+    }
+
+    #[test]
+    fn test_table_style() {
+        let doc = Document::parse(
+            br#"{\rtf1{\colortbl;\red0\green0\blue0;\red217\green217\blue217;}
+\trowd\ts11\trbrdrt\brdrs\brdrw10\clbrdrt\brdrdb\brdrw15\brdrcf1\clbrdrb\brdrnone\clcbpat2\clshdng2000\cellx1000\cellx2000
+\pard\intbl\brdrb\brdrs A\cell\pard\intbl\itap2 In\nestcell{\*\nesttableprops\trowd\clbrdrl\brdrdot\clcfpat1\cellx500\nestrow}{\nonesttables\par}
+\pard\intbl\itap1\cell\row\pard}"#,
+        )
+        .unwrap();
+        let table = match doc.blocks[0] {
+            Block::Table(ref table) => table,
+            ref block => panic!("Expected a table, found {:?}", block),
+        };
+        let row = &table.rows[0];
+        assert_eq!(row.style, Some(11));
+        assert_eq!(
+            row.cells[0].borders,
+            CellBorders {
+                top: Some(Border {
+                    style: BorderStyle::Double,
+                    width: Some(15),
+                    color: Some(1),
+                }),
+                bottom: Some(Border::default()),
+                ..CellBorders::default()
+            }
+        );
+        assert_eq!(row.cells[0].background, Some(2));
+        assert_eq!(row.cells[0].shading, Some(2000));
+        // The paragraph's border isn't the cell's
+        assert_eq!(row.cells[1].borders, CellBorders::default());
+        assert_eq!(row.cells[1].background, None);
+
+        let nested = match row.cells[1].blocks[0] {
+            Block::Table(ref table) => &table.rows[0],
+            ref block => panic!("Expected a table, found {:?}", block),
+        };
+        assert_eq!(nested.style, None);
+        let cell = &nested.cells[0];
+        assert_eq!(
+            cell.borders.left.map(|border| border.style),
+            Some(BorderStyle::Dotted)
+        );
+        assert_eq!(cell.pattern_color, Some(1));
+    }
+
+    #[test]
+    fn test_nested_tables() {
+        let doc = Document::parse(
+            br#"{\rtf1\pard\intbl\itap1 Outer\par
+\pard\intbl\itap2 In1\nestcell In2\nestcell{\*\nesttableprops\trowd\cellx500\cellx900\nestrow}{\nonesttables\par}
+\pard\intbl\itap1 \cell Right\cell{\trowd\cellx1000\cellx2000\row}\pard After\par}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.blocks.len(), 2);
+        let table = match doc.blocks[0] {
+            Block::Table(ref table) => table,
+            ref block => panic!("Expected a table, found {:?}", block),
+        };
+        let cells = &table.rows[0].cells;
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[0].width, Some(1000));
+        let nested = match cells[0].blocks[1] {
+            Block::Table(ref table) => table,
+            ref block => panic!("Expected a nested table, found {:?}", block),
+        };
+        assert_eq!(nested.rows[0].cells.len(), 2);
+        assert_eq!(nested.rows[0].cells[1].width, Some(400));
+        assert_eq!(nested.rows[0].cells[1].paragraphs()[0].text(), "In2");
+        let texts: Vec<String> = cells[0].paragraphs().iter().map(|p| p.text()).collect();
+        assert_eq!(texts, vec!["Outer", "In1", "In2", ""]);
+        assert_eq!(cells[1].paragraphs()[0].text(), "Right");
+        assert_eq!(doc.paragraphs().last().unwrap().text(), "After");
+    }
+
+    #[test]
+    fn test_lists() {
+        let doc = Document::parse(
+            br#"{\rtf1{\*\listtable{\list{\listlevel\levelnfc23}\listid10}{\list{\listlevel\levelnfc0}\listid20}}{\*\listoverridetable{\listoverride\listid20\listoverridecount0\ls2}{\listoverride\listid10\listoverridecount0\ls1}}
+{\listtext\'b7\tab}\pard\ls1 One\par{\listtext 1.\tab}\pard\ls2 Two\par\pard{\pntext\'b7\tab}{\*\pn\pnlvlblt{\pntxtb\'b7}}Three\par\pard Four\par}"#,
+        )
+        .unwrap();
+        let lists: Vec<Option<ListItem>> = doc.paragraphs().iter().map(|p| p.list).collect();
+        let bullet = Some(ListItem {
+            kind: ListKind::Bullet,
+            level: 0,
+        });
+        let numbered = Some(ListItem {
+            kind: ListKind::Numbered,
+            level: 0,
+        });
+        assert_eq!(lists, vec![bullet, numbered, bullet, None]);
+        assert_eq!(doc.paragraphs()[0].text(), "One");
+    }
+
+    #[test]
+    fn test_sample_doc() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let doc = Document::parse(test_bytes).unwrap();
+        assert_eq!(doc.fonts.len(), 11);
+        assert_eq!(doc.colors.len(), 17);
+        assert_eq!(
+            paragraph(&doc, 0).text(),
+            "It is an example test rtf-file to RTF2XML bean for testing"
+        );
+        assert!(paragraph(&doc, 0).runs[0].format.bold);
+    }
+}
@@ -0,0 +1,368 @@
+// Encapsulated HTML and plain text
+//
+// Outlook stores HTML mail as RTF that encapsulates the original HTML, as
+// described in [MS-OXRTFEX].  A `\fromhtml1` in the header marks such a
+// document.  The HTML markup is kept in `{\*\htmltag ...}` destinations,
+// which ordinary readers skip, and the text between tags is ordinary document
+// text.  RTF written only for ordinary readers, like the paragraph breaks
+// standing in for `<p>` tags, is enclosed in `\htmlrtf ... \htmlrtf0` and
+// left out of the HTML.
+//
+// Plain text mail is encapsulated the same way, marked by `\fromtext`, with
+// no tags: the document text outside `\htmlrtf` is the original text.
+//
+// Encapsulating HTML goes the other way, writing each tag as an
+// `{\*\htmltag}` and adding the `\htmlrtf` breaks that let ordinary readers
+// show the text with its paragraphs and line breaks.
+//
+
+use std;
+
+use metadata::{producer_info, Encapsulation};
+use text::{Decoded, Decoder};
+use tokenizer::Token;
+
+// An {\*\htmltag} destination being read
+struct Tag {
+    // Decoder depth of the destination group
+    depth: usize,
+    decoder: Decoder,
+}
+
+// Reads the original HTML or text out of an encapsulating document
+#[derive(Default)]
+struct Reader {
+    decoder: Decoder,
+    // Whether to read {\*\htmltag} destinations
+    tags: bool,
+    // Whether \htmlrtf suppression is on, for each open group and the current
+    // one
+    stack: Vec<bool>,
+    suppressed: bool,
+    tag: Option<Tag>,
+    output: String,
+}
+
+impl Reader {
+    fn token(&mut self, token: &Token) {
+        let decoded = self.decoder.token(token);
+        match token {
+            Token::StartGroup => self.stack.push(self.suppressed),
+            Token::EndGroup => self.suppressed = self.stack.pop().unwrap_or_default(),
+            _ => {}
+        }
+
+        if let Some(tag) = self.tag.as_mut() {
+            if self.decoder.depth() < tag.depth {
+                self.tag = None;
+                return;
+            }
+            match tag.decoder.token(token) {
+                Decoded::Text(text) => self.output.push_str(&text),
+                Decoded::Word("par", _) | Decoded::Word("line", _) => self.output.push_str("\r\n"),
+                Decoded::Word("tab", _) => self.output.push('\t'),
+                _ => {}
+            }
+            return;
+        }
+
+        match decoded {
+            Decoded::Ignored => {
+                if let Token::ControlWord { name, .. } = token {
+                    if self.tags
+                        && name == "htmltag"
+                        && self.decoder.destination() == Some("htmltag")
+                    {
+                        self.tag = Some(Tag {
+                            depth: self.decoder.depth(),
+                            decoder: Decoder::with_state(self.decoder.state().clone()),
+                        });
+                    }
+                }
+            }
+            Decoded::Word("htmlrtf", arg) => self.suppressed = arg != Some(0),
+            _ if self.suppressed => {}
+            Decoded::Text(text) => self.output.push_str(&text),
+            Decoded::Word("par", _) | Decoded::Word("line", _) => self.output.push_str("\r\n"),
+            Decoded::Word("tab", _) => self.output.push('\t'),
+            _ => {}
+        }
+    }
+}
+
+fn read(tokens: &[Token], tags: bool) -> String {
+    let mut reader = Reader {
+        tags,
+        ..Reader::default()
+    };
+    for token in tokens {
+        reader.token(token);
+    }
+    reader.output
+}
+
+/// Recover the original HTML from a document that encapsulates it
+///
+/// Returns `None` if the document isn't marked `\fromhtml1`.  The content of
+/// `{\*\mhtmltag}` destinations, the original form of tags whose URLs were
+/// rewritten, is skipped in favor of the rewritten `{\*\htmltag}`.
+pub fn decapsulate_html(tokens: &[Token]) -> Option<String> {
+    if producer_info(tokens).encapsulation != Some(Encapsulation::Html) {
+        return None;
+    }
+    Some(read(tokens, true))
+}
+
+/// Recover the original plain text from a document that encapsulates it
+///
+/// Returns `None` if the document isn't marked `\fromtext`.  Line breaks are
+/// `"\r\n"`, as in the original message.
+pub fn decapsulate_text(tokens: &[Token]) -> Option<String> {
+    if producer_info(tokens).encapsulation != Some(Encapsulation::Text) {
+        return None;
+    }
+    Some(read(tokens, false))
+}
+
+// Elements that end a paragraph when an ordinary reader shows the text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "tr",
+    "blockquote",
+    "pre",
+    "table",
+];
+
+// Elements whose text isn't shown, which is kept in tags
+const HIDDEN_ELEMENTS: &[&str] = &["head", "title", "style", "script"];
+
+fn control_word(name: &str) -> Token {
+    Token::ControlWord {
+        name: name.into(),
+        arg: None,
+    }
+}
+
+// Append tokens for text: reserved characters become control symbols, line
+// breaks `\par` and characters outside ASCII `\uN` with a `?` fallback
+fn push_text(tokens: &mut Vec<Token>, text: &str) {
+    let mut ascii = Vec::new();
+    for c in text.chars() {
+        let token = match c {
+            '{' | '}' | '\\' => Token::ControlSymbol(c),
+            '\t' => control_word("tab"),
+            '\n' => control_word("par"),
+            '\r' => continue,
+            c if c.is_ascii() => {
+                ascii.push(c as u8);
+                continue;
+            }
+            c => {
+                if !ascii.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut ascii)));
+                }
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    tokens.push(Token::Unicode {
+                        codepoint: i32::from(*unit),
+                    });
+                    tokens.push(Token::Text(b"?".to_vec()));
+                }
+                continue;
+            }
+        };
+        if !ascii.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut ascii)));
+        }
+        tokens.push(token);
+    }
+    if !ascii.is_empty() {
+        tokens.push(Token::Text(ascii));
+    }
+}
+
+// Append an {\*\htmltag} destination holding `html`
+fn push_tag(tokens: &mut Vec<Token>, html: &str) {
+    tokens.push(Token::StartGroup);
+    tokens.push(Token::ControlSymbol('*'));
+    tokens.push(control_word("htmltag"));
+    push_text(tokens, html);
+    tokens.push(Token::EndGroup);
+}
+
+// Append RTF for ordinary readers only
+fn push_rtf_only(tokens: &mut Vec<Token>, word: &str) {
+    tokens.push(control_word("htmlrtf"));
+    tokens.push(control_word(word));
+    tokens.push(Token::ControlWord {
+        name: "htmlrtf".into(),
+        arg: Some(0),
+    });
+}
+
+// The lowercased element name of a tag, and whether it's an end tag
+fn element(tag: &str) -> (String, bool) {
+    let inner = tag.trim_start_matches('<');
+    let end = inner.starts_with('/');
+    let name = inner
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    (name, end)
+}
+
+/// Encapsulate HTML in an RTF document, the way Outlook stores HTML mail, so
+/// that `decapsulate_html` recovers it
+///
+/// Ordinary readers see the text of the HTML, with paragraph breaks after
+/// block elements and line breaks for `<br>`.  Line breaks in the HTML come
+/// back as `"\r\n"`.  Serialize the tokens with `write_rtf`.
+pub fn encapsulate_html(html: &str) -> Vec<Token> {
+    let mut tokens = vec![
+        Token::StartGroup,
+        Token::ControlWord {
+            name: "rtf".into(),
+            arg: Some(1),
+        },
+        control_word("ansi"),
+        Token::ControlWord {
+            name: "ansicpg".into(),
+            arg: Some(1252),
+        },
+        Token::ControlWord {
+            name: "fromhtml".into(),
+            arg: Some(1),
+        },
+        Token::ControlWord {
+            name: "deff".into(),
+            arg: Some(0),
+        },
+        Token::StartGroup,
+        control_word("fonttbl"),
+        Token::StartGroup,
+        Token::ControlWord {
+            name: "f".into(),
+            arg: Some(0),
+        },
+        control_word("fswiss"),
+        Token::Text(b" Arial;".to_vec()),
+        Token::EndGroup,
+        Token::EndGroup,
+    ];
+
+    // Depth of elements whose text is hidden
+    let mut hidden = 0usize;
+    let mut rest = html;
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            if hidden > 0 {
+                push_tag(&mut tokens, text);
+            } else {
+                // Line breaks in the source aren't breaks in the text
+                for (index, line) in text.split('\n').enumerate() {
+                    if index > 0 {
+                        push_tag(&mut tokens, "\n");
+                    }
+                    push_text(&mut tokens, line);
+                }
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else {
+            rest.find('>').map(|end| end + 1)
+        };
+        let end = end.unwrap_or(rest.len());
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        push_tag(&mut tokens, tag);
+
+        let (name, closing) = element(tag);
+        if HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            if closing {
+                hidden = hidden.saturating_sub(1);
+            } else if !tag.ends_with("/>") {
+                hidden += 1;
+            }
+        } else if name == "br" {
+            push_rtf_only(&mut tokens, "line");
+        } else if closing && BLOCK_ELEMENTS.contains(&name.as_str()) {
+            push_rtf_only(&mut tokens, "par");
+        }
+    }
+
+    tokens.push(Token::EndGroup);
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::extract_text;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    #[test]
+    fn test_decapsulate_html() {
+        let rtf = br#"{\rtf1\ansi\ansicpg1252\fromhtml1 \deff0{\fonttbl{\f0\fswiss Arial;}}
+{\*\htmltag19 <html>}{\*\htmltag34 <head>\par <title>T</title>\par </head>}
+{\*\htmltag50 <body>}\htmlrtf {\f0\htmlrtf0 Caf\'e9 \{x\}
+{\*\htmltag84 <br>}\htmlrtf \line \htmlrtf0 next\tab end
+{\*\htmltag148 <p>}\htmlrtf \par\pard\htmlrtf0 {\*\mhtmltag84 <img src="a.png">}{\*\htmltag84 <img src="cid:a">}
+\htmlrtf }\htmlrtf0 {\*\htmltag58 </body>}{\*\htmltag27 </html>}}"#;
+        let html = decapsulate_html(&parse(rtf).unwrap()).unwrap();
+        assert_eq!(
+            html,
+            "<html><head>\r\n<title>T</title>\r\n</head><body>Caf\u{e9} {x}<br>next\tend<p><img src=\"cid:a\"></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_decapsulate_text() {
+        let rtf = br#"{\rtf1\ansi\ansicpg1251\fromtext \fbidis \deff0{\fonttbl{\f0\fswiss\fcharset204 Arial;}}
+{\*\generator Microsoft Exchange Server;}{\*\formatConverter converted from text;}
+\viewkind5\viewscale100{\*\bkmkstart BM_BEGIN}\pard\plain\f0\fs20 \'cf\'f0\'e8\'e2\'e5\'f2\par
+\htmlrtf {\b\htmlrtf0 Second}\htmlrtf  line\htmlrtf0 \tab x\par
+}"#;
+        let text = decapsulate_text(&parse(rtf).unwrap()).unwrap();
+        assert_eq!(
+            text,
+            "\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442}\r\nSecond\tx\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encapsulate_html() {
+        let html = "<html>\r\n<head><title>T {1}</title><style>p { x: 1 }</style></head>\r\n<body><p>Caf\u{e9}\tbar<br>\r\n\u{2014} a\\b</p><!-- <p> --></body></html>";
+        let tokens = encapsulate_html(html);
+        assert_eq!(decapsulate_html(&tokens).as_deref(), Some(html));
+
+        let rtf = write_rtf(&tokens, &WriteOptions::default()).unwrap();
+        let tokens = parse(&rtf).unwrap();
+        assert_eq!(decapsulate_html(&tokens).as_deref(), Some(html));
+        assert_eq!(extract_text(&tokens), "Caf\u{e9}\tbar\n\u{2014} a\\b\n");
+    }
+
+    #[test]
+    fn test_not_encapsulated() {
+        assert_eq!(decapsulate_html(&parse(b"{\\rtf1 Hi}").unwrap()), None);
+        let tokens = parse(b"{\\rtf1\\fromtext Hi}").unwrap();
+        assert_eq!(decapsulate_html(&tokens), None);
+        let tokens = parse(b"{\\rtf1\\fromhtml1 Hi}").unwrap();
+        assert_eq!(decapsulate_text(&tokens), None);
+    }
+}
@@ -0,0 +1,380 @@
+// Field instructions and bookmark names
+//
+// Fields keep their instructions, like `HYPERLINK "http://example.com" \l
+// "top"`, as text in a `{\*\fldinst}` destination, and bookmarks their names
+// in `{\*\bkmkstart}` and `{\*\bkmkend}`.  That text is escaped twice over:
+// once as RTF, with hex and `\uN` escapes and instructions split across
+// groups, and again in the field code syntax, with quoted arguments,
+// backslash switches and doubled backslashes.  Both are undone here, giving
+// the argument values the way the program that wrote them meant them.
+//
+
+use text::{Decoded, Decoder};
+use tokenizer::{child_group, group_contents, group_destination, group_end, Token};
+
+/// Decode the text of a destination that isn't document text, given the
+/// tokens inside its group after the word naming it
+///
+/// The text is decoded as document text is, and the text of groups nested
+/// inside it is included, but not that of destinations nested inside it.
+pub fn destination_text(contents: &[Token]) -> String {
+    let mut decoder = Decoder::new();
+    let mut text = String::new();
+    for token in contents {
+        if let Decoded::Text(decoded) = decoder.token(token) {
+            text.push_str(&decoded);
+        }
+    }
+    text
+}
+
+/// The decoded instruction of the `{\field}` group starting at
+/// `tokens[start]`
+pub fn field_instruction(tokens: &[Token], start: usize) -> Option<String> {
+    match group_destination(tokens, start) {
+        Some(("field", _, _)) => {}
+        _ => return None,
+    }
+    let end = group_end(tokens, start);
+    let (_, word, end) = child_group(tokens, start, end, "fldinst")?;
+    Some(destination_text(group_contents(tokens, word, end)))
+}
+
+/// The name of the bookmark started or ended by the `{\*\bkmkstart}` or
+/// `{\*\bkmkend}` group starting at `tokens[start]`
+pub fn bookmark_name(tokens: &[Token], start: usize) -> Option<String> {
+    match group_destination(tokens, start) {
+        Some(("bkmkstart", _, word)) | Some(("bkmkend", _, word)) => {
+            let contents = group_contents(tokens, word, group_end(tokens, start));
+            Some(destination_text(contents).trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// A piece of a field instruction after the field type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldArg {
+    /// A word, or a quoted string with its quotes and escapes removed
+    Text(String),
+    /// A switch, like the `l` of `\l`
+    Switch(char),
+}
+
+/// A field instruction split into its field type and arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInstruction {
+    /// The field type, in upper case, like `HYPERLINK`
+    pub kind: String,
+    pub args: Vec<FieldArg>,
+}
+
+// Read the text of a word or quoted string, after any opening quote, up to
+// the end of it, undoing the `\\` and `\"` escapes
+fn read_text(chars: &mut std::iter::Peekable<std::str::Chars>, quoted: bool) -> String {
+    let mut text = String::new();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '"' if quoted => {
+                chars.next();
+                break;
+            }
+            '"' => break,
+            c if c.is_whitespace() && !quoted => break,
+            '\\' => {
+                let mut ahead = chars.clone();
+                ahead.next();
+                match ahead.next() {
+                    Some(escaped) if escaped == '\\' || escaped == '"' => {
+                        text.push(escaped);
+                        *chars = ahead;
+                        continue;
+                    }
+                    // A switch ends a word, but is part of a quoted string
+                    _ if !quoted => break,
+                    _ => text.push(c),
+                }
+                chars.next();
+            }
+            _ => {
+                text.push(c);
+                chars.next();
+            }
+        }
+    }
+    text
+}
+
+impl FieldInstruction {
+    /// Split a field instruction, or return None if it's blank
+    ///
+    /// Arguments are separated by whitespace, or quoted with `"`, in which
+    /// `\"` stands for a quote.  `\\` stands for a backslash anywhere, and a
+    /// backslash followed by anything else outside quotes is a switch.
+    ///
+    /// ```
+    /// use rtf_grimoire::fields::{FieldArg, FieldInstruction};
+    ///
+    /// let instruction = FieldInstruction::parse(r#" INCLUDEPICTURE "C:\\a \"b\".png" \d "#).unwrap();
+    /// assert_eq!(instruction.kind, "INCLUDEPICTURE");
+    /// assert_eq!(
+    ///     instruction.args,
+    ///     vec![
+    ///         FieldArg::Text(r#"C:\a "b".png"#.to_string()),
+    ///         FieldArg::Switch('d'),
+    ///     ]
+    /// );
+    /// ```
+    pub fn parse(instruction: &str) -> Option<Self> {
+        let mut args = Vec::new();
+        let mut chars = instruction.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+            if c == '"' {
+                chars.next();
+                args.push(FieldArg::Text(read_text(&mut chars, true)));
+                continue;
+            }
+            if c == '\\' {
+                let mut ahead = chars.clone();
+                ahead.next();
+                match ahead.next() {
+                    Some(escaped) if escaped == '\\' || escaped == '"' => {}
+                    Some(name) if !name.is_whitespace() => {
+                        args.push(FieldArg::Switch(name));
+                        chars = ahead;
+                        continue;
+                    }
+                    // A lone backslash
+                    _ => {
+                        chars = ahead;
+                        continue;
+                    }
+                }
+            }
+            args.push(FieldArg::Text(read_text(&mut chars, false)));
+        }
+        let kind = match args.first() {
+            Some(FieldArg::Text(kind)) => kind.to_uppercase(),
+            _ => return None,
+        };
+        args.remove(0);
+        Some(FieldInstruction { kind, args })
+    }
+
+    /// The arguments before the first switch
+    pub fn arguments(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map_while(|arg| match arg {
+            FieldArg::Text(text) => Some(text.as_str()),
+            FieldArg::Switch(_) => None,
+        })
+    }
+
+    pub fn has_switch(&self, name: char) -> bool {
+        self.args.contains(&FieldArg::Switch(name))
+    }
+
+    /// The argument straight after the first `name` switch, if it has one
+    pub fn switch_value(&self, name: char) -> Option<&str> {
+        let at = self
+            .args
+            .iter()
+            .position(|arg| *arg == FieldArg::Switch(name))?;
+        match self.args.get(at + 1) {
+            Some(FieldArg::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// A field instruction, read for the field types that are commonly looked
+/// into
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Field {
+    /// `HYPERLINK "target" \l "anchor"`, a link to a URL or file, a
+    /// bookmark in this document, or both
+    Hyperlink {
+        target: Option<String>,
+        /// The bookmark linked to, from `\l`
+        anchor: Option<String>,
+        /// The tooltip, from `\o`
+        tooltip: Option<String>,
+        /// Whether it opens in a new window, from `\n`
+        new_window: bool,
+    },
+    /// `PAGEREF bookmark \h`, the page number of a bookmark
+    PageRef {
+        bookmark: String,
+        /// Whether it links to the bookmark, from `\h`
+        hyperlink: bool,
+        /// Whether it reads "above" or "below" instead, from `\p`
+        relative: bool,
+    },
+    /// `TOC \o "1-3" \h`, a table of contents
+    Toc {
+        /// The outline levels of headings listed, from `\o`
+        levels: Option<(u32, u32)>,
+        /// Styles listed, and the levels they're listed at, from `\t`
+        styles: Vec<(String, u32)>,
+        /// Whether the entries link to the headings, from `\h`
+        hyperlinks: bool,
+        /// Whether the entries have page numbers, unless `\n` leaves them
+        /// out
+        page_numbers: bool,
+        /// The bookmark whose part of the document is listed, from `\b`
+        bookmark: Option<String>,
+    },
+    /// Any other field, or one of the above missing a required argument
+    Other(FieldInstruction),
+}
+
+// The levels of a TOC \o switch, like "1-3", or all of them if it has no
+// value
+fn toc_levels(value: Option<&str>) -> Option<(u32, u32)> {
+    let value = match value {
+        Some(value) => value,
+        None => return Some((1, 9)),
+    };
+    let mut levels = value.splitn(2, '-').map(|level| level.trim().parse().ok());
+    let first = levels.next()??;
+    let last = levels.next().unwrap_or(Some(first))?;
+    Some((first, last))
+}
+
+// The styles of a TOC \t switch, like "Title,1,Subtitle,2"; the separator is
+// the list separator of the locale it was written in
+fn toc_styles(value: &str) -> Vec<(String, u32)> {
+    let parts: Vec<&str> = value.split([',', ';']).map(str::trim).collect();
+    parts
+        .chunks(2)
+        .filter_map(|pair| match pair {
+            [style, level] => Some((style.to_string(), level.parse().ok()?)),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Field {
+    /// Read a field instruction, or return None if it's blank
+    ///
+    /// ```
+    /// use rtf_grimoire::fields::Field;
+    ///
+    /// let field = Field::parse(r#"HYPERLINK "http://example.com/" \l "top" \o "Go""#);
+    /// assert_eq!(
+    ///     field,
+    ///     Some(Field::Hyperlink {
+    ///         target: Some("http://example.com/".to_string()),
+    ///         anchor: Some("top".to_string()),
+    ///         tooltip: Some("Go".to_string()),
+    ///         new_window: false,
+    ///     })
+    /// );
+    /// ```
+    pub fn parse(instruction: &str) -> Option<Self> {
+        let instruction = FieldInstruction::parse(instruction)?;
+        let field = match instruction.kind.as_str() {
+            "HYPERLINK" => Some(Field::Hyperlink {
+                target: instruction.arguments().next().map(str::to_string),
+                anchor: instruction.switch_value('l').map(str::to_string),
+                tooltip: instruction.switch_value('o').map(str::to_string),
+                new_window: instruction.has_switch('n'),
+            }),
+            "PAGEREF" => instruction
+                .arguments()
+                .next()
+                .map(|bookmark| Field::PageRef {
+                    bookmark: bookmark.to_string(),
+                    hyperlink: instruction.has_switch('h'),
+                    relative: instruction.has_switch('p'),
+                }),
+            "TOC" => Some(Field::Toc {
+                levels: if instruction.has_switch('o') {
+                    toc_levels(instruction.switch_value('o'))
+                } else {
+                    None
+                },
+                styles: instruction
+                    .switch_value('t')
+                    .map(toc_styles)
+                    .unwrap_or_default(),
+                hyperlinks: instruction.has_switch('h'),
+                page_numbers: !instruction.has_switch('n'),
+                bookmark: instruction.switch_value('b').map(str::to_string),
+            }),
+            _ => None,
+        };
+        Some(field.unwrap_or(Field::Other(instruction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    #[test]
+    fn test_field_instructions() {
+        let tokens = parse(
+            b"{\\rtf1{\\field{\\*\\fldinst {\\rtlch HYPERLINK \"http://example.com/caf\\'e9\" }{\\\\l \"sec\\u8209?2\"}}{\\fldrslt here}}}",
+        )
+        .unwrap();
+        let instruction = field_instruction(&tokens, 2).unwrap();
+        assert_eq!(
+            instruction,
+            "HYPERLINK \"http://example.com/caf\u{e9}\" \\l \"sec\u{2011}2\""
+        );
+        assert_eq!(field_instruction(&tokens, 0), None);
+        assert_eq!(
+            Field::parse(&instruction),
+            Some(Field::Hyperlink {
+                target: Some("http://example.com/caf\u{e9}".to_string()),
+                anchor: Some("sec\u{2011}2".to_string()),
+                tooltip: None,
+                new_window: false,
+            })
+        );
+
+        assert_eq!(
+            Field::parse(" PAGEREF _Toc12345 \\h "),
+            Some(Field::PageRef {
+                bookmark: "_Toc12345".to_string(),
+                hyperlink: true,
+                relative: false,
+            })
+        );
+        assert_eq!(
+            Field::parse("TOC \\o \"1-3\" \\h \\z \\t \"Title;1;Subtitle;2\" \\n"),
+            Some(Field::Toc {
+                levels: Some((1, 3)),
+                styles: vec![("Title".to_string(), 1), ("Subtitle".to_string(), 2)],
+                hyperlinks: true,
+                page_numbers: false,
+                bookmark: None,
+            })
+        );
+        assert!(matches!(Field::parse("PAGEREF \\h"), Some(Field::Other(_))));
+        assert_eq!(Field::parse("  "), None);
+
+        // Switches needn't be separated from what's around them
+        let instruction =
+            FieldInstruction::parse("MERGEFIELD Name\\* MERGEFORMAT\\b\"x\"").unwrap();
+        assert_eq!(instruction.arguments().collect::<Vec<_>>(), vec!["Name"]);
+        assert_eq!(instruction.switch_value('*'), Some("MERGEFORMAT"));
+        assert_eq!(instruction.switch_value('b'), Some("x"));
+        assert!(!instruction.has_switch('h'));
+    }
+
+    #[test]
+    fn test_bookmark_name() {
+        let tokens =
+            parse(b"{\\rtf1{\\*\\bkmkstart Caf\\'e9_1}x{\\*\\bkmkend {Caf\\u233?_1 }}}").unwrap();
+        assert_eq!(bookmark_name(&tokens, 2), Some("Caf\u{e9}_1".to_string()));
+        assert_eq!(bookmark_name(&tokens, 10), Some("Caf\u{e9}_1".to_string()));
+        assert_eq!(bookmark_name(&tokens, 0), None);
+    }
+}
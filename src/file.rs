@@ -0,0 +1,320 @@
+// Simple document editing
+//
+// A convenience layer for the common "open a document, change some text,
+// save it" task, for users who don't want to work with tokens directly.
+//
+
+use std;
+use std::ops::Range;
+use std::path::Path;
+
+use codepage::{charset_word_code_page, encode};
+use header::{validate_header, Charset};
+use metadata::{producer_info, ProducerInfo};
+use search::find_text;
+use text::{extract_text, piece_text, pieces, range_pieces, Unit, Whitespace};
+use tokenizer::{parse, parse_spanned, ParseError, SpannedToken, Token};
+
+/// An RTF document held in memory, as its original bytes
+///
+/// Edits only touch the bytes of the text being changed; everything else in
+/// the document is written back exactly as it was read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtfFile {
+    bytes: Vec<u8>,
+}
+
+// The code page a document's text is in, from its header: `\ansicpgN`, or
+// the character set, or Windows-1252 if it says neither
+fn code_page(bytes: &[u8]) -> u32 {
+    let header = validate_header(bytes).unwrap_or_default();
+    let charset = match header.charset {
+        Some(Charset::Mac) => "mac",
+        Some(Charset::Pc) => "pc",
+        Some(Charset::Pca) => "pca",
+        _ => "ansi",
+    };
+    match header.code_page {
+        Some(code_page) if code_page > 0 => code_page as u32,
+        _ => charset_word_code_page(charset).unwrap_or(1252),
+    }
+}
+
+// Encode text for insertion into a document, escaping reserved characters,
+// and writing anything outside of the document's code page as a \u escape.
+// The \u escapes are wrapped in their own group with an explicit \uc1, so
+// that the fallback character is skipped whatever the surrounding \uc value
+// is.
+fn encode_text(text: &str, code_page: u32) -> Vec<u8> {
+    let mut rtf = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '{' | '}' | '\\' => {
+                rtf.push(b'\\');
+                rtf.push(c as u8);
+            }
+            _ if c.is_ascii() => rtf.push(c as u8),
+            _ => match encode(code_page, c) {
+                Some(byte) => rtf.extend_from_slice(format!("\\'{:02x}", byte).as_bytes()),
+                None => {
+                    let mut units = [0u16; 2];
+                    rtf.extend_from_slice(b"{\\uc1");
+                    for unit in c.encode_utf16(&mut units) {
+                        rtf.extend_from_slice(format!("\\u{}?", *unit as i16).as_bytes());
+                    }
+                    rtf.push(b'}');
+                }
+            },
+        }
+    }
+    rtf
+}
+
+// Control words must be delimited from text that could be read as part of
+// their name or argument, or as the space delimiting them
+fn needs_delimiter(prev: &SpannedToken, follows: Option<u8>) -> bool {
+    match prev.token {
+        Token::ControlWord { .. } | Token::Unicode { .. } => {
+            !prev.trailing_space
+                && follows.is_some_and(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b' ')
+        }
+        _ => false,
+    }
+}
+
+impl RtfFile {
+    /// Read a document from disk
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        std::fs::read(path).map(RtfFile::from_bytes)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        RtfFile { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn tokens(&self) -> Result<Vec<Token>, ParseError> {
+        parse(&self.bytes)
+    }
+
+    /// The plain text of the document body
+    pub fn text(&self) -> Result<String, ParseError> {
+        self.tokens().map(|tokens| extract_text(&tokens))
+    }
+
+    /// Information about the program that produced the document
+    pub fn producer(&self) -> Result<ProducerInfo, ParseError> {
+        self.tokens().map(|tokens| producer_info(&tokens))
+    }
+
+    /// Replace every occurrence of `from` in the document body with `to`,
+    /// returning the number of replacements made
+    ///
+    /// Occurrences are found in the text as `search::find` finds them, so
+    /// one split between tokens, escaped, or with formatting changes part way
+    /// through is replaced too.  Only the bytes of the text being replaced
+    /// are rewritten: the replacement is written where the occurrence
+    /// begins, in the document's code page with any characters outside of
+    /// it as `\u` escapes, the rest of its text is removed, and the control
+    /// words and groups between are left as they were.
+    pub fn replace_text(&mut self, from: &str, to: &str) -> Result<usize, ParseError> {
+        let replacement = encode_text(to, code_page(&self.bytes));
+        let tokens = parse_spanned(&self.bytes)?;
+        let plain: Vec<Token> = tokens.iter().map(|token| token.token.clone()).collect();
+        let pieces = pieces(&plain);
+        let (text, chars) = piece_text(&pieces, Whitespace::Normalize);
+
+        // The bytes to rewrite, what to write there, and the token they're in
+        let mut edits: Vec<(Range<usize>, &[u8], usize)> = Vec::new();
+        let mut count = 0;
+        for range in find_text(&text, from) {
+            let (first, mut last) = match range_pieces(&chars, &range) {
+                Some(found) => found,
+                None => continue,
+            };
+            // The fallback characters of a \uN at the end go with it
+            while pieces
+                .get(last + 1)
+                .is_some_and(|piece| piece.unit == Unit::Fallback)
+            {
+                last += 1;
+            }
+            count += 1;
+            for (index, piece) in pieces[first..=last].iter().enumerate() {
+                let spanned = &tokens[piece.token];
+                let bytes = match spanned.token {
+                    Token::Text(_) => {
+                        spanned.span.start + piece.bytes.start..spanned.span.start + piece.bytes.end
+                    }
+                    _ => spanned.span.clone(),
+                };
+                if edits.last().is_some_and(|edit| edit.0 == bytes) {
+                    continue;
+                }
+                let value = if index == 0 { &replacement[..] } else { b"" };
+                edits.push((bytes, value, piece.token));
+            }
+        }
+
+        let mut edited = Vec::with_capacity(self.bytes.len());
+        let mut end = 0;
+        for (index, (range, value, token)) in edits.iter().enumerate() {
+            edited.extend_from_slice(&self.bytes[end..range.start]);
+            // A control word left before the rewritten bytes must be
+            // delimited from whatever follows it once they're rewritten
+            let after_word = *token > 0
+                && range.start == tokens[*token].span.start
+                && !(index > 0 && edits[index - 1].0.end == range.start);
+            if after_word && needs_delimiter(&tokens[token - 1], self.following(&edits[index..])) {
+                edited.push(b' ');
+            }
+            edited.extend_from_slice(value);
+            end = range.end;
+        }
+        edited.extend_from_slice(&self.bytes[end..]);
+        self.bytes = edited;
+        Ok(count)
+    }
+
+    // The first byte written once `edits` are made, from the first of them
+    // on
+    fn following(&self, edits: &[(Range<usize>, &[u8], usize)]) -> Option<u8> {
+        let mut end = edits.first()?.0.start;
+        for (range, value, _) in edits {
+            if range.start != end {
+                break;
+            }
+            if let Some(byte) = value.first() {
+                return Some(*byte);
+            }
+            end = range.end;
+        }
+        self.bytes.get(end).cloned()
+    }
+
+    /// Write the document to disk
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, &self.bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replaced(rtf: &[u8], from: &str, to: &str) -> (usize, Vec<u8>) {
+        let mut file = RtfFile::from_bytes(rtf.to_vec());
+        let count = file.replace_text(from, to).unwrap();
+        (count, file.into_bytes())
+    }
+
+    #[test]
+    fn test_replace_text_preserves_bytes() {
+        let rtf = b"{\\rtf1{\\info{\\title Dear NAME}}\r\n\\b Dear NAME,\\b0  hello NAME.\\par}";
+        let (count, bytes) = replaced(rtf, "NAME", "Jane {Doe}");
+        assert_eq!(count, 2);
+        assert_eq!(
+            bytes,
+            b"{\\rtf1{\\info{\\title Dear NAME}}\r\n\\b Dear Jane \\{Doe\\},\\b0  hello Jane \\{Doe\\}.\\par}".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_replace_text_encodes_replacement() {
+        let (count, bytes) = replaced(b"{\\rtf1 x}", "x", "\u{e9}\u{3b1}");
+        assert_eq!(count, 1);
+        assert_eq!(bytes, b"{\\rtf1 \\'e9{\\uc1\\u945?}}".to_vec());
+        let file = RtfFile::from_bytes(bytes);
+        assert_eq!(file.text().unwrap(), "\u{e9}\u{3b1}");
+    }
+
+    #[test]
+    fn test_replace_text_delimits_control_words() {
+        let (_, bytes) = replaced(b"{\\b0,x}", ",x", "1x");
+        assert_eq!(bytes, b"{\\b0 1x}".to_vec());
+        // Whatever follows once the text is gone needs delimiting too
+        let (_, bytes) = replaced(b"{\\b,x}", ",", "");
+        assert_eq!(bytes, b"{\\b x}".to_vec());
+        let (_, bytes) = replaced(b"{\\b0,5}", ",", "");
+        assert_eq!(bytes, b"{\\b0 5}".to_vec());
+        let (_, bytes) = replaced(b"{\\b,,x}", ",", "");
+        assert_eq!(bytes, b"{\\b x}".to_vec());
+        let (_, bytes) = replaced(b"{\\b,}", ",", "");
+        assert_eq!(bytes, b"{\\b}".to_vec());
+        // A leading space isn't taken for the delimiter
+        let (_, bytes) = replaced(b"{\\b,x}", ",", " ");
+        assert_eq!(bytes, b"{\\b  x}".to_vec());
+        let tokens = RtfFile::from_bytes(bytes).tokens().unwrap();
+        assert_eq!(tokens[2], Token::Text(b" x".to_vec()));
+    }
+
+    #[test]
+    fn test_replace_text_across_tokens() {
+        // Only the text's bytes are rewritten, leaving the groups and
+        // formatting between them
+        let (count, bytes) = replaced(b"{\\rtf1 Hello wor{\\b ld}!}", "world", "there");
+        assert_eq!(count, 1);
+        assert_eq!(bytes, b"{\\rtf1 Hello there{\\b }!}".to_vec());
+        let (count, bytes) = replaced(
+            b"{\\rtf1 caf\\'e9 cr\\u232\\'e8me}",
+            "caf\u{e9} cr\u{e8}me",
+            "tea",
+        );
+        assert_eq!(count, 1);
+        assert_eq!(bytes, b"{\\rtf1 tea}".to_vec());
+        // A control word before an escape that's replaced is delimited
+        let (_, bytes) = replaced(b"{\\rtf1\\b\\'e9t\\'e9}", "\u{e9}t", "et");
+        assert_eq!(bytes, b"{\\rtf1\\b et\\'e9}".to_vec());
+    }
+
+    #[test]
+    fn test_replace_text_skips_fallbacks() {
+        // The fallback characters of a \u escape aren't text to replace
+        let (count, bytes) = replaced(b"{\\rtf1\\uc2 \\u233??x ?}", "??", "!");
+        assert_eq!(count, 0);
+        assert_eq!(bytes, b"{\\rtf1\\uc2 \\u233??x ?}".to_vec());
+        let (count, bytes) = replaced(b"{\\rtf1\\uc2 \\u233??x}", "\u{e9}x", "y");
+        assert_eq!(count, 1);
+        assert_eq!(bytes, b"{\\rtf1\\uc2 y}".to_vec());
+    }
+
+    #[test]
+    fn test_replace_text_uses_code_page() {
+        let rtf = b"{\\rtf1\\ansi\\ansicpg1251 x}";
+        let (count, bytes) = replaced(rtf, "x", "\u{e9} \u{44f}");
+        assert_eq!(count, 1);
+        assert_eq!(
+            bytes,
+            b"{\\rtf1\\ansi\\ansicpg1251 {\\uc1\\u233?} \\'ff}".to_vec()
+        );
+        assert_eq!(RtfFile::from_bytes(bytes).text().unwrap(), "\u{e9} \u{44f}");
+    }
+
+    #[test]
+    fn test_open_save_roundtrip() {
+        let path = std::env::temp_dir().join("rtf-grimoire-file-roundtrip.rtf");
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        std::fs::write(&path, &test_bytes[..]).unwrap();
+
+        let mut file = RtfFile::open(&path).unwrap();
+        assert_eq!(file.as_bytes(), &test_bytes[..]);
+        // The document title also mentions RTF2XML, but isn't body text
+        assert_eq!(file.replace_text("RTF2XML", "RTF2HTML").unwrap(), 1);
+        file.save(&path).unwrap();
+
+        let saved = RtfFile::open(&path).unwrap();
+        assert_eq!(saved.as_bytes().len(), test_bytes.len() + 1);
+        assert!(saved.text().unwrap().contains("rtf-file to RTF2HTML bean"));
+        assert!(saved.tokens().unwrap().contains(&Token::Text(
+            b"It is an example test rtf-file to RTF2XML bean for testing".to_vec()
+        )));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+// Form fields
+//
+// Word's legacy forms are fields like any other, `FORMTEXT`, `FORMCHECKBOX`
+// and `FORMDROPDOWN`, with a `{\*\formfield}` destination inside their
+// instruction describing the control: its name, default, options and,
+// for check boxes and drop-down lists, what's been chosen.  What's been
+// typed into a text input is the field's result.
+//
+
+use fields::{destination_text, FieldInstruction};
+use tokenizer::{child_group, group_contents, group_destination, group_end, narrow, Token};
+
+/// `\ffres` and `\ffdefres` of a control that hasn't been set
+const UNSET: i32 = 25;
+
+// The destinations of a form field read for their text
+const FORM_DESTINATIONS: &[&str] = &["ffname", "ffdeftext", "ffhelptext", "ffl"];
+
+/// A form control, and its state
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FormControl {
+    /// A text input, `FORMTEXT`
+    Text {
+        /// The text it starts with, from `{\*\ffdeftext}`
+        default: String,
+        /// The text it holds, from the field result
+        value: String,
+        /// The most characters it takes, from `\ffmaxlen`, if limited
+        max_length: Option<u32>,
+    },
+    /// A check box, `FORMCHECKBOX`
+    Checkbox {
+        /// Whether it starts checked, from `\ffdefres`
+        default: bool,
+        /// Whether it's checked, from `\ffres`, or as it starts if that's
+        /// unset
+        checked: bool,
+    },
+    /// A drop-down list, `FORMDROPDOWN`
+    Dropdown {
+        /// The options, from `{\*\ffl}`, in order
+        options: Vec<String>,
+        /// The index of the option it starts on, from `\ffdefres`
+        default: usize,
+        /// The index of the option chosen, from `\ffres`, or the one it
+        /// starts on if that's unset
+        selected: usize,
+    },
+}
+
+/// A form field, from a field with a `{\*\formfield}` destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    /// The field's name, from `{\*\ffname}`
+    pub name: Option<String>,
+    pub control: FormControl,
+    /// Help text shown for the field, from `{\*\ffhelptext}`
+    pub help_text: Option<String>,
+}
+
+// The first group in `tokens[start..end]`, at any depth, whose destination
+// is `name`: its start, the index of the word naming it, and its end
+fn find_group(
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+    name: &str,
+) -> Option<(usize, usize, usize)> {
+    (start..end).find_map(|index| match group_destination(tokens, index) {
+        Some((found, _, word)) if tokens[index] == Token::StartGroup && found == name => {
+            Some((index, word, group_end(tokens, index)))
+        }
+        _ => None,
+    })
+}
+
+/// The form field of the `{\field}` group starting at `tokens[start]`, or
+/// None if it isn't a form field
+///
+/// The kind of control is taken from `\fftype`, or from the field type if
+/// that's missing.
+///
+/// ```
+/// use rtf_grimoire::forms::{form_field, FormControl};
+/// use rtf_grimoire::parse;
+///
+/// let tokens = parse(
+///     b"{\\field{\\*\\fldinst FORMCHECKBOX {\\*\\formfield\\fftype1\\ffres1{\\*\\ffname Agree}}}{\\fldrslt}}",
+/// )
+/// .unwrap();
+/// let field = form_field(&tokens, 0).unwrap();
+/// assert_eq!(field.name.as_deref(), Some("Agree"));
+/// assert_eq!(
+///     field.control,
+///     FormControl::Checkbox {
+///         default: false,
+///         checked: true,
+///     }
+/// );
+/// ```
+pub fn form_field(tokens: &[Token], start: usize) -> Option<FormField> {
+    match group_destination(tokens, start) {
+        Some(("field", _, _)) => {}
+        _ => return None,
+    }
+    let end = group_end(tokens, start);
+    let (_, word, instruction_end) = child_group(tokens, start, end, "fldinst")?;
+    let (_, form_word, form_end) = find_group(tokens, word, instruction_end, "formfield")?;
+
+    let mut kind = None;
+    let mut result = None;
+    let mut default_result = None;
+    let mut max_length = None;
+    let mut name = None;
+    let mut default_text = String::new();
+    let mut help_text = None;
+    let mut options = Vec::new();
+    let form = group_contents(tokens, form_word, form_end);
+    let mut index = 0;
+    while index < form.len() {
+        if form[index] == Token::StartGroup {
+            let (child, child_word) = match group_destination(form, index) {
+                Some((child, _, child_word)) if FORM_DESTINATIONS.contains(&child) => {
+                    (child, child_word)
+                }
+                // Word writes the properties in a group of their own
+                _ => {
+                    index += 1;
+                    continue;
+                }
+            };
+            let child_end = group_end(form, index);
+            let text = || destination_text(group_contents(form, child_word, child_end));
+            match child {
+                "ffname" => name = Some(text().trim().to_string()),
+                "ffdeftext" => default_text = text(),
+                "ffhelptext" => help_text = Some(text()),
+                _ => options.push(text()),
+            }
+            index = child_end;
+            continue;
+        }
+        if let Token::ControlWord {
+            name,
+            arg: Some(arg),
+        } = &form[index]
+        {
+            let arg = narrow(*arg);
+            match name.as_str() {
+                "fftype" => kind = Some(arg),
+                "ffres" => result = Some(arg),
+                "ffdefres" => default_result = Some(arg),
+                "ffmaxlen" => max_length = Some(arg),
+                _ => {}
+            }
+        }
+        index += 1;
+    }
+
+    let kind = kind.or_else(|| {
+        let instruction = destination_text(group_contents(tokens, word, instruction_end));
+        match FieldInstruction::parse(&instruction)?.kind.as_str() {
+            "FORMTEXT" => Some(0),
+            "FORMCHECKBOX" => Some(1),
+            "FORMDROPDOWN" => Some(2),
+            _ => None,
+        }
+    })?;
+    let default_result = default_result.filter(|res| *res != UNSET).unwrap_or(0);
+    let result = result.filter(|res| *res != UNSET).unwrap_or(default_result);
+    let control = match kind {
+        0 => {
+            let value = child_group(tokens, start, end, "fldrslt")
+                .map(|(_, word, end)| destination_text(group_contents(tokens, word, end)))
+                .unwrap_or_default();
+            FormControl::Text {
+                default: default_text,
+                // Word fills an empty input's result with five en spaces,
+                // to give it something to show
+                value: if value.chars().all(|c| c == '\u{2002}') {
+                    String::new()
+                } else {
+                    value
+                },
+                max_length: max_length.filter(|len| *len > 0).map(|len| len as u32),
+            }
+        }
+        1 => FormControl::Checkbox {
+            default: default_result != 0,
+            checked: result != 0,
+        },
+        2 => FormControl::Dropdown {
+            options,
+            default: default_result.max(0) as usize,
+            selected: result.max(0) as usize,
+        },
+        _ => return None,
+    };
+    Some(FormField {
+        name,
+        control,
+        help_text,
+    })
+}
+
+/// The form fields of a document, in order
+pub fn form_fields(tokens: &[Token]) -> Vec<FormField> {
+    (0..tokens.len())
+        .filter(|index| tokens[*index] == Token::StartGroup)
+        .filter_map(|index| form_field(tokens, index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    #[test]
+    fn test_form_fields() {
+        let rtf = br#"{\rtf1\ansi\uc1
+Name: {\field{\*\fldinst {FORMTEXT }{{\*\formfield{\fftype0\ffownhelp\ffmaxlen20{\*\ffname Name}{\*\ffdeftext Your name}{\*\ffhelptext Full name}}}}}{\fldrslt {Jos\'e9 Roe}}}\par
+Blank: {\field{\*\fldinst FORMTEXT {\*\formfield{\*\ffname Blank}}}{\fldrslt \u8194?\u8194?\u8194?\u8194?\u8194?}}\par
+{\field{\*\fldinst {FORMCHECKBOX {\*\formfield\fftype1\ffres25\ffdefres1{\*\ffname Check1}}}}{\fldrslt }} Subscribe\par
+Size: {\field{\*\fldinst {FORMDROPDOWN {\*\formfield\fftype2\ffres2\ffdefres0{\*\ffname Size}{\*\ffl Small}{\*\ffl Medium}{\*\ffl Large}}}}{\fldrslt }}\par
+{\field{\*\fldinst {HYPERLINK "http://example.com/"}}{\fldrslt link}}}"#;
+        let fields = form_fields(&parse(rtf).unwrap());
+        assert_eq!(
+            fields,
+            vec![
+                FormField {
+                    name: Some("Name".to_string()),
+                    control: FormControl::Text {
+                        default: "Your name".to_string(),
+                        value: "Jos\u{e9} Roe".to_string(),
+                        max_length: Some(20),
+                    },
+                    help_text: Some("Full name".to_string()),
+                },
+                FormField {
+                    name: Some("Blank".to_string()),
+                    control: FormControl::Text {
+                        default: String::new(),
+                        value: String::new(),
+                        max_length: None,
+                    },
+                    help_text: None,
+                },
+                FormField {
+                    name: Some("Check1".to_string()),
+                    control: FormControl::Checkbox {
+                        default: true,
+                        checked: true,
+                    },
+                    help_text: None,
+                },
+                FormField {
+                    name: Some("Size".to_string()),
+                    control: FormControl::Dropdown {
+                        options: vec![
+                            "Small".to_string(),
+                            "Medium".to_string(),
+                            "Large".to_string()
+                        ],
+                        default: 0,
+                        selected: 2,
+                    },
+                    help_text: None,
+                },
+            ]
+        );
+    }
+}
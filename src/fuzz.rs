@@ -0,0 +1,172 @@
+// Fuzzing support
+//
+// Structured fuzzing needs token streams built from the fuzzer's raw bytes.
+// `Token` implements the `arbitrary` crate's `Arbitrary`, which cargo-fuzz
+// targets take their input as, and `arbitrary_tokens` builds a whole stream
+// from raw bytes so the fuzz targets in `fuzz/` can share the generator with
+// the crate's own tests.
+//
+// Only available with the `fuzzing` feature.
+//
+
+use std;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use tokenizer::{control_word, Name, Token};
+use writer::{write_rtf, WriteOptions};
+
+// Up to `max` bytes, fewer if the input runs out
+fn bytes<'a>(u: &mut Unstructured<'a>, max: usize) -> Result<&'a [u8]> {
+    let len = std::cmp::min(u.int_in_range(0..=max)?, u.len());
+    u.bytes(len)
+}
+
+// Letters for control word names.  Names can't begin with "bin", which the
+// tokenizer reads as binary data.
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn name(u: &mut Unstructured) -> Result<Name> {
+    let len = u.int_in_range(1..=8)?;
+    let mut name = String::with_capacity(len);
+    for _ in 0..len {
+        name.push(*u.choose(LETTERS)? as char);
+    }
+    if name.starts_with("bin") {
+        name.replace_range(..1, "p");
+    }
+    Ok(name.into())
+}
+
+fn arg(u: &mut Unstructured) -> Result<Option<i64>> {
+    Ok(match u.int_in_range(0..=4)? {
+        0 => None,
+        1 => Some(u.int_in_range(0..=100)?),
+        2 => Some(-u.int_in_range(0..=100)?),
+        // Large values, including ones outside the 32 bit range
+        3 => Some(i64::from(u32::arbitrary(u)?)),
+        _ => Some(-i64::from(u32::arbitrary(u)?)),
+    })
+}
+
+/// Generates tokens that the tokenizer can produce: text never contains
+/// the reserved characters or line breaks, and control words have names
+/// the tokenizer reads as control words
+impl<'a> Arbitrary<'a> for Token {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=8)? {
+            0 => Token::StartGroup,
+            1 => Token::EndGroup,
+            2 => Token::Newline,
+            3 => {
+                // Printable ASCII other than letters and the hex escape
+                let symbols: Vec<u8> = (b' '..=b'~')
+                    .filter(|b| !b.is_ascii_alphabetic() && *b != b'\'')
+                    .collect();
+                Token::ControlSymbol(*u.choose(&symbols)? as char)
+            }
+            4 => Token::HexByte(u8::arbitrary(u)?),
+            5 => Token::ControlBin(bytes(u, 16)?.to_vec()),
+            7 => Token::Unicode {
+                codepoint: u.int_in_range(0..=65535)?,
+            },
+            6 => {
+                let mut text: Vec<u8> = bytes(u, 16)?
+                    .iter()
+                    .cloned()
+                    .filter(|b| !b"\\{}\r\n".contains(b))
+                    .collect();
+                if text.is_empty() {
+                    text.push(b'x');
+                }
+                Token::Text(text)
+            }
+            // \uN is read as a Token::Unicode
+            _ => control_word(&name(u)?, arg(u)?),
+        })
+    }
+}
+
+/// Build a token stream from fuzzer input
+///
+/// Adjacent text tokens are merged, as the tokenizer never produces two in
+/// a row.
+pub fn arbitrary_tokens(data: &[u8]) -> Vec<Token> {
+    let mut u = Unstructured::new(data);
+    let mut tokens: Vec<Token> = Vec::new();
+    while !u.is_empty() {
+        let token = match Token::arbitrary(&mut u) {
+            Ok(token) => token,
+            Err(_) => break,
+        };
+        match (token, tokens.last_mut()) {
+            (Token::Text(text), Some(Token::Text(last))) => last.extend_from_slice(&text),
+            (token, _) => tokens.push(token),
+        }
+    }
+    tokens
+}
+
+/// Serialize `tokens` and tokenize the result, returning the tokens read
+/// back if they differ from the originals
+pub fn roundtrip(tokens: &[Token]) -> Result<(), Vec<Token>> {
+    let rtf = write_rtf(tokens, &WriteOptions::default()).unwrap_or_default();
+    match ::tokenizer::parse(&rtf) {
+        Ok(ref reparsed) if reparsed.as_slice() == tokens => Ok(()),
+        Ok(reparsed) => Err(reparsed),
+        Err(_) => Err(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_token_runs_dry() {
+        // Once the input runs out, a token is still made from zeroes
+        let mut u = Unstructured::new(&[5, 3, 7, 8]);
+        assert_eq!(
+            Token::arbitrary(&mut u).unwrap(),
+            Token::ControlBin(vec![7, 8])
+        );
+        assert!(u.is_empty());
+        assert_eq!(Token::arbitrary(&mut u).unwrap(), Token::StartGroup);
+    }
+
+    #[test]
+    fn test_arbitrary_tokens() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 37 % 251) as u8).collect();
+        let tokens = arbitrary_tokens(&data);
+        assert!(tokens.len() > 100);
+        assert_eq!(tokens, arbitrary_tokens(&data));
+        for pair in tokens.windows(2) {
+            if let (Token::Text(_), Token::Text(_)) = (&pair[0], &pair[1]) {
+                panic!("adjacent text tokens");
+            }
+        }
+        for token in &tokens {
+            match token {
+                Token::ControlWord { name, .. } => assert!(!name.starts_with("bin")),
+                Token::Text(text) => assert!(!text.is_empty()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let tokens = vec![
+            Token::StartGroup,
+            Token::ControlWord {
+                name: "b".into(),
+                arg: Some(-3),
+            },
+            Token::Text(b"1 x".to_vec()),
+            Token::EndGroup,
+        ];
+        assert_eq!(roundtrip(&tokens), Ok(()));
+        let unmerged = vec![Token::Text(b"x".to_vec()), Token::Text(b"y".to_vec())];
+        assert_eq!(roundtrip(&unmerged), Err(vec![Token::Text(b"xy".to_vec())]));
+    }
+}
@@ -0,0 +1,152 @@
+// Group extents
+//
+// Most rewrites work a group at a time: find where the group a token is in
+// starts and ends, then keep, drop or rewrite the lot.  These find a group's
+// extent by counting braces, and a `GroupIndex` finds them all in one pass,
+// for transforms that look up many.
+//
+
+use std::ops::Range;
+
+use tokenizer::{group_end, Token};
+
+/// The range of the group starting at `tokens[start]`, from its opening
+/// brace to its closing one, or to the end of the tokens if it's never
+/// closed
+///
+/// A token other than a group's opening brace is a range of its own.
+///
+/// ```
+/// use rtf_grimoire::groups::group_span;
+/// use rtf_grimoire::parse;
+///
+/// let tokens = parse(b"{\\rtf1 {\\b Bold} plain}").unwrap();
+/// assert_eq!(group_span(&tokens, 2), 2..6);
+/// ```
+pub fn group_span(tokens: &[Token], start: usize) -> Range<usize> {
+    match tokens.get(start) {
+        Some(Token::StartGroup) => start..group_end(tokens, start),
+        _ => start..start + 1,
+    }
+}
+
+/// The range of the innermost group that the token at `index` is in, or
+/// None if it isn't in one
+///
+/// A group's braces are in the group, so a closing brace is in the group it
+/// closes and an opening brace in the group around the one it opens.  An
+/// index past the end of the tokens isn't in any group.
+pub fn enclosing_group(tokens: &[Token], index: usize) -> Option<Range<usize>> {
+    if index >= tokens.len() {
+        return None;
+    }
+    let mut depth = 0;
+    for start in (0..index).rev() {
+        match tokens[start] {
+            Token::EndGroup => depth += 1,
+            Token::StartGroup if depth == 0 => return Some(group_span(tokens, start)),
+            Token::StartGroup => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The groups of a token stream, found once, for looking up many
+///
+/// Gives the same answers as `group_span` and `enclosing_group`, each in
+/// constant time.  The index describes the tokens it was built from, and has
+/// to be built again after they're changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupIndex {
+    // The end of the group each opening brace starts
+    ends: Vec<usize>,
+    // The opening brace of the innermost group each token is in
+    parents: Vec<Option<usize>>,
+}
+
+impl GroupIndex {
+    pub fn new(tokens: &[Token]) -> Self {
+        let mut ends = vec![0; tokens.len()];
+        let mut parents = Vec::with_capacity(tokens.len());
+        let mut open: Vec<usize> = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            parents.push(open.last().copied());
+            match token {
+                Token::StartGroup => open.push(index),
+                Token::EndGroup => {
+                    if let Some(start) = open.pop() {
+                        ends[start] = index + 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        for start in open {
+            ends[start] = tokens.len();
+        }
+        GroupIndex { ends, parents }
+    }
+
+    /// The range of the group starting at `start`, as `group_span` gives it
+    pub fn group_span(&self, start: usize) -> Range<usize> {
+        match self.ends.get(start) {
+            Some(end) if *end > start => start..*end,
+            _ => start..start + 1,
+        }
+    }
+
+    /// The range of the innermost group the token at `index` is in, as
+    /// `enclosing_group` gives it
+    pub fn enclosing_group(&self, index: usize) -> Option<Range<usize>> {
+        let start = (*self.parents.get(index)?)?;
+        Some(start..self.ends[start])
+    }
+
+    /// The number of groups the token at `index` is in
+    pub fn depth(&self, index: usize) -> usize {
+        let mut depth = 0;
+        let mut parent = self.parents.get(index).copied().flatten();
+        while let Some(start) = parent {
+            depth += 1;
+            parent = self.parents[start];
+        }
+        depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    #[test]
+    fn test_group_extents() {
+        let tokens = parse(b"{\\rtf1 a{\\b b{\\i c}}}} d{\\e").unwrap();
+        assert_eq!(group_span(&tokens, 3), 3..11);
+        assert_eq!(group_span(&tokens, 1), 1..2);
+        assert_eq!(enclosing_group(&tokens, 8), Some(6..10));
+        assert_eq!(enclosing_group(&tokens, 10), Some(3..11));
+        assert_eq!(enclosing_group(&tokens, 6), Some(3..11));
+        // After a stray closing brace, and in a group never closed
+        assert_eq!(enclosing_group(&tokens, 13), None);
+        assert_eq!(enclosing_group(&tokens, 15), Some(14..16));
+        // Past the end, even of a group never closed
+        assert_eq!(enclosing_group(&tokens, tokens.len()), None);
+        assert_eq!(enclosing_group(&tokens, tokens.len() + 5), None);
+
+        let index = GroupIndex::new(&tokens);
+        assert_eq!(index.depth(8), 3);
+        assert_eq!(index.depth(13), 0);
+        for stream in [
+            &tokens,
+            &parse(include_bytes!("../tests/sample.rtf")).unwrap(),
+        ] {
+            let index = GroupIndex::new(stream);
+            for token in 0..stream.len() + 2 {
+                assert_eq!(index.group_span(token), group_span(stream, token));
+                assert_eq!(index.enclosing_group(token), enclosing_group(stream, token));
+            }
+        }
+    }
+}
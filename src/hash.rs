@@ -0,0 +1,147 @@
+// Content hashing
+//
+// Hashes what a document says rather than how it's written: the text of its
+// paragraphs, the shape of its tables and the data of its pictures, leaving
+// out fonts, sizes and every other kind of formatting.  Exports of the same
+// document from different word processors, or different versions of one,
+// hash the same, which is what deduplicating an archive of them needs.
+//
+
+use sha2::{Digest, Sha256};
+
+use document::{Block, Document};
+use pict::pictures;
+use tokenizer::{parse, ParseError};
+
+/// A hash function for `content_hash_with`
+///
+/// Implement this to hash content with something other than SHA-256.
+pub trait ContentHasher {
+    fn update(&mut self, data: &[u8]);
+    fn finish(self) -> [u8; 32];
+}
+
+/// The SHA-256 hasher `content_hash` uses
+#[derive(Debug, Clone, Default)]
+pub struct Sha256Hasher(Sha256);
+
+impl ContentHasher for Sha256Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finish(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+// Hash a tagged, length-prefixed piece of content, so that no two sequences
+// of pieces hash the same bytes
+fn piece<H: ContentHasher>(hasher: &mut H, tag: u8, data: &[u8]) {
+    hasher.update(&[tag]);
+    hasher.update(&(data.len() as u64).to_le_bytes());
+    hasher.update(data);
+}
+
+fn hash_blocks<H: ContentHasher>(hasher: &mut H, blocks: &[Block]) {
+    for block in blocks {
+        match block {
+            Block::Paragraph(paragraph) => {
+                let text = paragraph.text();
+                if !text.is_empty() {
+                    piece(hasher, b'p', text.as_bytes());
+                }
+            }
+            Block::Table(table) => {
+                hasher.update(b"t");
+                for row in &table.rows {
+                    hasher.update(b"r");
+                    for cell in &row.cells {
+                        hasher.update(b"c");
+                        hash_blocks(hasher, &cell.blocks);
+                    }
+                }
+                hasher.update(b"e");
+            }
+        }
+    }
+}
+
+/// Hash the content of a document with SHA-256
+///
+/// What's hashed is the text of each paragraph, the rows and cells of the
+/// tables, and the data of the pictures.  Formatting isn't, nor is anything
+/// outside the document body, like the info group, and nor are empty
+/// paragraphs, so documents that read the same hash the same even when their
+/// RTF looks nothing alike.
+pub fn content_hash(bytes: &[u8]) -> Result<[u8; 32], ParseError> {
+    content_hash_with(bytes, Sha256Hasher::default())
+}
+
+/// Hash the content of a document, as `content_hash` does, with `hasher`
+pub fn content_hash_with<H: ContentHasher>(
+    bytes: &[u8],
+    mut hasher: H,
+) -> Result<[u8; 32], ParseError> {
+    let tokens = parse(bytes)?;
+    let document = Document::from_tokens(&tokens);
+    hash_blocks(&mut hasher, &document.blocks);
+    for picture in pictures(&tokens) {
+        piece(&mut hasher, b'i', &picture.data);
+    }
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keeps everything it's given, to check what's hashed
+    #[derive(Default)]
+    struct Recorder(Vec<u8>);
+
+    impl ContentHasher for Recorder {
+        fn update(&mut self, data: &[u8]) {
+            self.0.extend_from_slice(data);
+        }
+
+        fn finish(self) -> [u8; 32] {
+            let mut hash = [0; 32];
+            for (index, byte) in self.0.iter().enumerate() {
+                hash[index % 32] ^= *byte;
+            }
+            hash
+        }
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let a = b"{\\rtf1\\ansi{\\fonttbl{\\f0 Arial;}}{\\info{\\author A}}\\f0\\fs20 Caf\\'e9 {\\b au} lait\\par\\par}";
+        let b = b"{\\rtf1\\ansi{\\fonttbl{\\f1 Times;}}{\\*\\generator Writer;}\r\n\\f1\\fs24\\i Caf\\u233? au lait\\i0\\par}";
+        assert_eq!(content_hash(a).unwrap(), content_hash(b).unwrap());
+
+        let c = b"{\\rtf1 Cafe au lait\\par}";
+        assert_ne!(content_hash(a).unwrap(), content_hash(c).unwrap());
+        // The same text split differently between paragraphs
+        let d = b"{\\rtf1 Caf\\'e9 au\\par lait\\par}";
+        assert_ne!(content_hash(a).unwrap(), content_hash(d).unwrap());
+    }
+
+    #[test]
+    fn test_content_hash_structure() {
+        let table = b"{\\rtf1\\trowd\\cellx1000\\cellx2000 a\\cell b\\cell\\row}";
+        let paragraphs = b"{\\rtf1 a\\par b\\par}";
+        assert_ne!(
+            content_hash(table).unwrap(),
+            content_hash(paragraphs).unwrap()
+        );
+
+        let hasher = Recorder::default();
+        let pict = b"{\\rtf1 x\\par{\\pict\\pngblip 0102}}";
+        let mut expected = b"p\x01\0\0\0\0\0\0\0x".to_vec();
+        expected.extend_from_slice(b"i\x02\0\0\0\0\0\0\0\x01\x02");
+        let mut recorder = Recorder::default();
+        recorder.update(&expected);
+        assert_eq!(content_hash_with(pict, hasher).unwrap(), recorder.finish());
+    }
+}
@@ -0,0 +1,337 @@
+// Document header
+//
+// The header is the run of control words between `{\rtf1` and the first
+// group, usually the font table: the character set, the ANSI code page and the
+// default font.  Reading just that much is a cheap check of whether a blob is
+// RTF at all, and of how its text is encoded.
+//
+// Blobs handed over by other systems don't always start cleanly, so sniffing
+// what kind of RTF a blob holds looks past byte order marks and other leading
+// junk for the opening brace.
+//
+
+use std;
+use std::ops::Range;
+
+use nom::types::CompleteByteSlice as Input;
+use tokenizer::{narrow, next_token, Token};
+
+/// The character set a document declares after `\rtf1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Charset {
+    /// `\ansi`
+    Ansi,
+    /// `\mac`, Apple Macintosh
+    Mac,
+    /// `\pc`, IBM PC code page 437
+    Pc,
+    /// `\pca`, IBM PC code page 850
+    Pca,
+}
+
+/// What the header of a document declares
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RtfHeader {
+    pub charset: Option<Charset>,
+    /// `\ansicpgN`
+    pub code_page: Option<i32>,
+    /// `\deffN`
+    pub default_font: Option<i32>,
+    /// Whether this is a Pocket Word document, which starts `{\pwdN` or
+    /// `{\pwiN` rather than `{\rtf1`
+    pub pocket_word: bool,
+}
+
+/// Why a blob's header isn't that of an RTF document
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeaderError {
+    /// It doesn't begin with `{\rtf`, or the `{\pwd` or `{\pwi` of a Pocket
+    /// Word document
+    NotRtf,
+    /// It begins with `{\rtf`, but not version 1, the only version there is
+    Version(Option<i64>),
+}
+
+impl std::fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HeaderError::NotRtf => write!(f, "not an RTF document: no {{\\rtf at the start"),
+            HeaderError::Version(Some(version)) => write!(f, "unsupported RTF version {}", version),
+            HeaderError::Version(None) => write!(f, "RTF version missing"),
+        }
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+/// The kinds of document that sniffing tells apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RtfVariant {
+    /// A standard RTF document, `{\rtf`
+    Rtf,
+    /// A Pocket Word document, `{\pwi` or `{\pwd`
+    PocketWord,
+    /// A flattened RTFD bundle, RTF with attachments, as the macOS
+    /// pasteboard carries it
+    Rtfd,
+    /// None of the above
+    Unknown,
+}
+
+/// How far into a blob sniffing looks for the start of a document
+pub const SNIFF_LIMIT: usize = 1024;
+
+// Signatures, and the kind of document each starts
+const SIGNATURES: &[(&[u8], RtfVariant)] = &[
+    (b"{\\rtf", RtfVariant::Rtf),
+    (b"{\\pwi", RtfVariant::PocketWord),
+    (b"{\\pwd", RtfVariant::PocketWord),
+];
+
+// The magic number a flattened RTFD bundle starts with, which is only taken
+// for one at the very start, as text that mentions an .rtfd is common
+const RTFD_MAGIC: &[u8] = b"rtfd";
+
+const BYTE_ORDER_MARK: &[u8] = b"\xef\xbb\xbf";
+
+/// Where in `bytes` a document starts, and what kind it is
+///
+/// Byte order marks, whitespace, NULs or anything else may come first, up
+/// to `SNIFF_LIMIT` bytes of it.  An RTFD bundle has to start at the start,
+/// or straight after a byte order mark.
+pub fn find_start(bytes: &[u8]) -> Option<(usize, RtfVariant)> {
+    let bom = if bytes.starts_with(BYTE_ORDER_MARK) {
+        BYTE_ORDER_MARK.len()
+    } else {
+        0
+    };
+    if bytes[bom..].starts_with(RTFD_MAGIC) {
+        return Some((bom, RtfVariant::Rtfd));
+    }
+    let window = &bytes[..std::cmp::min(bytes.len(), SNIFF_LIMIT)];
+    (0..window.len()).find_map(|offset| {
+        SIGNATURES
+            .iter()
+            .find(|(signature, _)| bytes[offset..].starts_with(signature))
+            .map(|(_, variant)| (offset, *variant))
+    })
+}
+
+/// What kind of document `bytes` holds
+///
+/// This looks at the first few bytes only, and tolerates leading junk as
+/// `find_start` does.
+pub fn detect_variant(bytes: &[u8]) -> RtfVariant {
+    find_start(bytes).map_or(RtfVariant::Unknown, |(_, variant)| variant)
+}
+
+/// Whether `bytes` holds an RTF document of any kind `detect_variant` knows
+pub fn is_rtf(bytes: &[u8]) -> bool {
+    detect_variant(bytes) != RtfVariant::Unknown
+}
+
+/// Offset of the opening brace of the RTF document in `bytes`, or 0 if
+/// there's no sign of one
+pub(crate) fn document_start(bytes: &[u8]) -> usize {
+    match find_start(bytes) {
+        Some((offset, RtfVariant::Rtf)) | Some((offset, RtfVariant::PocketWord)) => offset,
+        _ => 0,
+    }
+}
+
+/// The bytes around a document that aren't part of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Junk {
+    /// Anything before the opening brace, found as `find_start` finds it
+    pub leading: Range<usize>,
+    /// Anything after the brace that closes the document's group, such as
+    /// the NULs and padding Outlook leaves after a document
+    pub trailing: Range<usize>,
+}
+
+impl Junk {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty()
+    }
+}
+
+/// Find the junk before and after the document in `bytes`
+///
+/// This tokenizes the document to find where its group ends; a document
+/// whose group is never closed has no trailing junk.
+/// `ParseOptions::skip_junk` skips the same bytes without the extra pass.
+pub fn find_junk(bytes: &[u8]) -> Junk {
+    let start = document_start(bytes);
+    let mut input = Input(&bytes[start..]);
+    let mut depth = 0usize;
+    let mut end = bytes.len();
+    while let Ok(Some((rest, token, _))) = next_token(bytes, input) {
+        input = rest;
+        match token {
+            Token::StartGroup => depth += 1,
+            Token::EndGroup if depth == 1 => {
+                end = bytes.len() - input.len();
+                break;
+            }
+            Token::EndGroup => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Junk {
+        leading: 0..start,
+        trailing: end..bytes.len(),
+    }
+}
+
+/// Check that `bytes` begins like an RTF document, and read its header
+///
+/// Only the control words up to the first group, text or line break after
+/// `{\rtf1` are read, so this costs next to nothing however long the
+/// document is.
+pub fn validate_header(bytes: &[u8]) -> Result<RtfHeader, HeaderError> {
+    if !bytes.starts_with(b"{") {
+        return Err(HeaderError::NotRtf);
+    }
+    let mut header = RtfHeader::default();
+    let mut input = Input(&bytes[1..]);
+    match next_token(bytes, input) {
+        Ok(Some((rest, Token::ControlWord { ref name, arg }, _))) if name == "rtf" => {
+            if arg != Some(1) {
+                return Err(HeaderError::Version(arg));
+            }
+            input = rest;
+        }
+        // Pocket Word numbers its versions separately
+        Ok(Some((rest, Token::ControlWord { ref name, .. }, _)))
+            if name == "pwd" || name == "pwi" =>
+        {
+            header.pocket_word = true;
+            input = rest;
+        }
+        _ => return Err(HeaderError::NotRtf),
+    }
+
+    while let Ok(Some((rest, Token::ControlWord { name, arg }, _))) = next_token(bytes, input) {
+        match (name.as_str(), arg) {
+            ("ansi", _) => header.charset = Some(Charset::Ansi),
+            ("mac", _) => header.charset = Some(Charset::Mac),
+            ("pc", _) => header.charset = Some(Charset::Pc),
+            ("pca", _) => header.charset = Some(Charset::Pca),
+            ("ansicpg", Some(code_page)) => header.code_page = Some(narrow(code_page)),
+            ("deff", Some(font)) => header.default_font = Some(narrow(font)),
+            _ => {}
+        }
+        input = rest;
+    }
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_header() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        assert_eq!(
+            validate_header(test_bytes),
+            Ok(RtfHeader {
+                charset: Some(Charset::Ansi),
+                code_page: Some(1252),
+                default_font: Some(0),
+                pocket_word: false,
+            })
+        );
+        // The header ends at the first group, even if more header words follow
+        assert_eq!(
+            validate_header(b"{\\rtf1\\mac\\deff2{\\fonttbl}\\ansicpg10000 x}"),
+            Ok(RtfHeader {
+                charset: Some(Charset::Mac),
+                code_page: None,
+                default_font: Some(2),
+                pocket_word: false,
+            })
+        );
+        assert_eq!(
+            validate_header(b"{\\pwd2\\ansi\\deff0{\\fonttbl}}"),
+            Ok(RtfHeader {
+                charset: Some(Charset::Ansi),
+                code_page: None,
+                default_font: Some(0),
+                pocket_word: true,
+            })
+        );
+        assert_eq!(validate_header(b"{\\rtf1}"), Ok(RtfHeader::default()));
+    }
+
+    #[test]
+    fn test_header_errors() {
+        assert_eq!(validate_header(b""), Err(HeaderError::NotRtf));
+        assert_eq!(validate_header(b"<html>"), Err(HeaderError::NotRtf));
+        assert_eq!(validate_header(b" {\\rtf1}"), Err(HeaderError::NotRtf));
+        assert_eq!(validate_header(b"{\\rtfx}"), Err(HeaderError::NotRtf));
+        assert_eq!(
+            validate_header(b"{\\rtf2\\ansi}"),
+            Err(HeaderError::Version(Some(2)))
+        );
+        assert_eq!(
+            validate_header(b"{\\rtf\\ansi}"),
+            Err(HeaderError::Version(None))
+        );
+    }
+
+    #[test]
+    fn test_detect_variant() {
+        assert_eq!(find_start(b"{\\rtf1}"), Some((0, RtfVariant::Rtf)));
+        assert_eq!(
+            find_start(b"\xef\xbb\xbf\r\n{\\rtf1}"),
+            Some((5, RtfVariant::Rtf))
+        );
+        assert_eq!(
+            detect_variant(b"\0\0junk{ {\\pwi6\\pwd1}"),
+            RtfVariant::PocketWord
+        );
+        assert_eq!(detect_variant(b"rtfd\0\0\0\0"), RtfVariant::Rtfd);
+        assert_eq!(
+            find_start(b"\xef\xbb\xbfrtfd\0\0\0\0"),
+            Some((3, RtfVariant::Rtfd))
+        );
+        // Only at the start
+        assert_eq!(detect_variant(b"see the .rtfd bundle"), RtfVariant::Unknown);
+        assert_eq!(
+            detect_variant(b"see the .rtfd bundle {\\rtf1}"),
+            RtfVariant::Rtf
+        );
+        assert_eq!(detect_variant(b"<html>{\\rtf"), RtfVariant::Rtf);
+        assert!(is_rtf(include_bytes!("../tests/sample.rtf")));
+
+        assert_eq!(detect_variant(b""), RtfVariant::Unknown);
+        assert_eq!(
+            detect_variant(b"plain text {with braces}"),
+            RtfVariant::Unknown
+        );
+        let mut late = vec![b' '; SNIFF_LIMIT];
+        late.extend_from_slice(b"{\\rtf1}");
+        assert!(!is_rtf(&late));
+    }
+
+    #[test]
+    fn test_find_junk() {
+        let rtf = b"\xef\xbb\xbf{\\rtf1{\\b \\}}\\bin1 }}\0\0";
+        let junk = find_junk(rtf);
+        assert_eq!(junk.leading, 0..3);
+        assert_eq!(junk.trailing, rtf.len() - 2..rtf.len());
+        assert!(!junk.is_empty());
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        assert_eq!(
+            find_junk(test_bytes).trailing,
+            test_bytes.len()..test_bytes.len()
+        );
+        // Unclosed documents run to the end
+        assert!(find_junk(b"{\\rtf1{}").is_empty());
+        assert!(find_junk(b"").is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+// JSON representation of token streams
+//
+// Renders tokens as an array of structured objects, for consumers outside of
+// Rust (JavaScript front ends in particular) that want to work with the token
+// stream without reimplementing the tokenizer.  With the `wasm` feature,
+// JavaScript can call the tokenizer and text extraction directly.
+//
+
+use std::fmt::Write;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use tokenizer::parse;
+use tokenizer::Token;
+
+fn push_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Render a single token as a JSON object
+///
+/// Every object has a `"type"` field naming the token kind:
+///
+/// * `{"type":"word","name":"fs","arg":24}` - `arg` is `null` when absent
+/// * `{"type":"symbol","symbol":"~"}`
+/// * `{"type":"hex","byte":233}` - a `\\'e9` hex escape
+/// * `{"type":"unicode","codepoint":8212}` - a `\\u8212` Unicode escape
+/// * `{"type":"bin","data":"00ff"}` - binary data as a hex string
+/// * `{"type":"text","text":"..."}` - each byte of the text is mapped to the
+///   Unicode code point of the same value, so the original bytes can be
+///   recovered exactly; decoding the text is left to the consumer
+/// * `{"type":"start"}`, `{"type":"end"}`, `{"type":"newline"}`
+pub fn token_to_json(token: &Token) -> String {
+    let mut json = String::new();
+    match token {
+        Token::ControlWord { name, arg } => {
+            json.push_str("{\"type\":\"word\",\"name\":");
+            push_json_string(&mut json, name);
+            match arg {
+                Some(arg) => {
+                    let _ = write!(json, ",\"arg\":{}}}", arg);
+                }
+                None => json.push_str(",\"arg\":null}"),
+            }
+        }
+        Token::ControlSymbol(c) => {
+            json.push_str("{\"type\":\"symbol\",\"symbol\":");
+            push_json_string(&mut json, &c.to_string());
+            json.push('}');
+        }
+        Token::HexByte(byte) => {
+            let _ = write!(json, "{{\"type\":\"hex\",\"byte\":{}}}", byte);
+        }
+        Token::Unicode { codepoint } => {
+            let _ = write!(json, "{{\"type\":\"unicode\",\"codepoint\":{}}}", codepoint);
+        }
+        Token::ControlBin(data) => {
+            json.push_str("{\"type\":\"bin\",\"data\":\"");
+            for byte in data {
+                let _ = write!(json, "{:02x}", byte);
+            }
+            json.push_str("\"}");
+        }
+        Token::Text(data) => {
+            json.push_str("{\"type\":\"text\",\"text\":");
+            let text: String = data.iter().map(|b| *b as char).collect();
+            push_json_string(&mut json, &text);
+            json.push('}');
+        }
+        Token::StartGroup => json.push_str("{\"type\":\"start\"}"),
+        Token::EndGroup => json.push_str("{\"type\":\"end\"}"),
+        Token::Newline => json.push_str("{\"type\":\"newline\"}"),
+    }
+    json
+}
+
+/// Render a token stream as a JSON array of token objects
+///
+/// See `token_to_json` for the layout of each object.
+pub fn tokens_to_json(tokens: &[Token]) -> String {
+    let mut json = String::from("[");
+    for (index, token) in tokens.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&token_to_json(token));
+    }
+    json.push(']');
+    json
+}
+
+/// Tokenize a document, for JavaScript: an array of the objects
+/// `token_to_json` describes, or the tokenizing error's message thrown
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn tokenize(rtf: &[u8]) -> Result<JsValue, JsValue> {
+    let tokens = parse(rtf).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    js_sys::JSON::parse(&tokens_to_json(&tokens))
+}
+
+/// Extract the plain text of a document, for JavaScript: a string, or the
+/// tokenizing error's message thrown
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = extractText)]
+pub fn extract_text(rtf: &[u8]) -> Result<JsValue, JsValue> {
+    let tokens = parse(rtf).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    Ok(JsValue::from_str(&::text::extract_text(&tokens)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    #[test]
+    fn test_tokens_to_json() {
+        let tokens = parse(b"{\\fs24\\b \"Hi\"\\~\\'e9\\bin2 \x00\xff}\r\n").unwrap();
+        assert_eq!(
+            tokens_to_json(&tokens),
+            concat!(
+                "[{\"type\":\"start\"},",
+                "{\"type\":\"word\",\"name\":\"fs\",\"arg\":24},",
+                "{\"type\":\"word\",\"name\":\"b\",\"arg\":null},",
+                "{\"type\":\"text\",\"text\":\"\\\"Hi\\\"\"},",
+                "{\"type\":\"symbol\",\"symbol\":\"~\"},",
+                "{\"type\":\"hex\",\"byte\":233},",
+                "{\"type\":\"bin\",\"data\":\"00ff\"},",
+                "{\"type\":\"end\"},",
+                "{\"type\":\"newline\"}]"
+            )
+        );
+    }
+
+    #[test]
+    fn test_text_bytes_are_recoverable() {
+        let json = token_to_json(&Token::Text(vec![0x01, b'a', 0xe9]));
+        assert_eq!(json, "{\"type\":\"text\",\"text\":\"\\u0001a\u{e9}\"}");
+    }
+}
+
+// The JavaScript exports, run with wasm-bindgen-test-runner as the
+// wasm32-unknown-unknown target's runner
+#[cfg(all(test, feature = "wasm", target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_tokenize() {
+        let tokens = tokenize(b"{\\b Hi}").unwrap();
+        assert_eq!(
+            js_sys::JSON::stringify(&tokens).unwrap(),
+            tokens_to_json(&parse(b"{\\b Hi}").unwrap()).as_str()
+        );
+        assert!(js_sys::Array::is_array(&tokens));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_extract_text() {
+        let text = extract_text(b"{\\rtf1 Caf\\'e9\\par}").unwrap();
+        assert_eq!(text.as_string().as_deref(), Some("Caf\u{e9}\n"));
+    }
+}
@@ -0,0 +1,160 @@
+// Languages
+//
+// RTF gives languages as Windows language ids (LCIDs), in `\langN` and its
+// relatives.  The low ten bits of an id are the primary language, and the
+// bits above them the region.
+//
+
+// Ids with a region worth naming in the tag
+const LANGUAGES: &[(i32, &str)] = &[
+    (0x0401, "ar-SA"),
+    (0x0404, "zh-TW"),
+    (0x0407, "de-DE"),
+    (0x0409, "en-US"),
+    (0x040a, "es-ES"),
+    (0x040c, "fr-FR"),
+    (0x040d, "he-IL"),
+    (0x0410, "it-IT"),
+    (0x0411, "ja-JP"),
+    (0x0412, "ko-KR"),
+    (0x0413, "nl-NL"),
+    (0x0416, "pt-BR"),
+    (0x0801, "ar-IQ"),
+    (0x0804, "zh-CN"),
+    (0x0807, "de-CH"),
+    (0x0809, "en-GB"),
+    (0x080a, "es-MX"),
+    (0x080c, "fr-BE"),
+    (0x0813, "nl-BE"),
+    (0x0816, "pt-PT"),
+    (0x0c01, "ar-EG"),
+    (0x0c04, "zh-HK"),
+    (0x0c07, "de-AT"),
+    (0x0c09, "en-AU"),
+    (0x0c0a, "es-ES"),
+    (0x0c0c, "fr-CA"),
+    (0x1004, "zh-SG"),
+    (0x1009, "en-CA"),
+    (0x100c, "fr-CH"),
+    (0x1401, "ar-DZ"),
+    (0x1404, "zh-MO"),
+    (0x1801, "ar-MA"),
+    (0x2001, "ar-OM"),
+    (0x2801, "ar-SY"),
+    (0x2c01, "ar-JO"),
+    (0x3001, "ar-LB"),
+    (0x3401, "ar-KW"),
+    (0x3801, "ar-AE"),
+    (0x4001, "ar-QA"),
+];
+
+// Primary languages, by the low ten bits of their ids
+const PRIMARY_LANGUAGES: &[(i32, &str)] = &[
+    (0x01, "ar"),
+    (0x02, "bg"),
+    (0x03, "ca"),
+    (0x04, "zh"),
+    (0x05, "cs"),
+    (0x06, "da"),
+    (0x07, "de"),
+    (0x08, "el"),
+    (0x09, "en"),
+    (0x0a, "es"),
+    (0x0b, "fi"),
+    (0x0c, "fr"),
+    (0x0d, "he"),
+    (0x0e, "hu"),
+    (0x0f, "is"),
+    (0x10, "it"),
+    (0x11, "ja"),
+    (0x12, "ko"),
+    (0x13, "nl"),
+    (0x14, "no"),
+    (0x15, "pl"),
+    (0x16, "pt"),
+    (0x18, "ro"),
+    (0x19, "ru"),
+    (0x1a, "hr"),
+    (0x1b, "sk"),
+    (0x1c, "sq"),
+    (0x1d, "sv"),
+    (0x1e, "th"),
+    (0x1f, "tr"),
+    (0x20, "ur"),
+    (0x21, "id"),
+    (0x22, "uk"),
+    (0x23, "be"),
+    (0x24, "sl"),
+    (0x25, "et"),
+    (0x26, "lv"),
+    (0x27, "lt"),
+    (0x29, "fa"),
+    (0x2a, "vi"),
+    (0x2b, "hy"),
+    (0x2d, "eu"),
+    (0x2f, "mk"),
+    (0x36, "af"),
+    (0x37, "ka"),
+    (0x39, "hi"),
+    (0x3d, "yi"),
+    (0x3e, "ms"),
+    (0x41, "sw"),
+    (0x45, "bn"),
+    (0x49, "ta"),
+    (0x5a, "syr"),
+    (0x63, "ps"),
+    (0x65, "dv"),
+];
+
+// Primary languages written right to left
+const RTL_LANGUAGES: &[i32] = &[0x01, 0x0d, 0x20, 0x29, 0x3d, 0x5a, 0x63, 0x65];
+
+// \lang1024 marks text that isn't in any language, like code
+const NO_LANGUAGE: i32 = 0x0400;
+
+fn primary(id: i32) -> i32 {
+    id & 0x3ff
+}
+
+/// The BCP 47 tag for a Windows language id, such as `"he-IL"` for 1037
+///
+/// Ids for regions this doesn't know get the tag of their primary language,
+/// and `None` is returned for ids of no language at all, or of an unknown
+/// one.
+pub fn language_tag(id: i32) -> Option<&'static str> {
+    if id == NO_LANGUAGE {
+        return None;
+    }
+    LANGUAGES
+        .iter()
+        .find(|(language, _)| *language == id)
+        .or_else(|| {
+            PRIMARY_LANGUAGES
+                .iter()
+                .find(|(language, _)| *language == primary(id))
+        })
+        .map(|(_, tag)| *tag)
+}
+
+/// Whether a Windows language id is of a language written right to left
+pub fn is_rtl_language(id: i32) -> bool {
+    RTL_LANGUAGES.contains(&primary(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_tag() {
+        assert_eq!(language_tag(1033), Some("en-US"));
+        assert_eq!(language_tag(1037), Some("he-IL"));
+        assert_eq!(language_tag(3073), Some("ar-EG"));
+        // Regions without tags here
+        assert_eq!(language_tag(0x1c01), Some("ar"));
+        assert_eq!(language_tag(1024), None);
+        assert_eq!(language_tag(0x3ff), None);
+        assert!(is_rtl_language(1037) && is_rtl_language(0x1c01) && is_rtl_language(1065));
+        assert!(!is_rtl_language(1033));
+    }
+}
@@ -1,5 +1,114 @@
+//! A Rich Text Format (RTF) document tokenizer, and tools built on it
+//!
+//! The most used items are re-exported here:
+//!
+//! - [`parse`] turns a document into [`Token`]s, and [`parse_spanned`] into
+//!   [`SpannedToken`]s that record where each came from
+//! - [`StreamingTokenizer`], [`TokenReader`] and [`parse_reader`] tokenize
+//!   input as it arrives
+//! - [`extract_text`] and [`Document`] read the text and structure of a
+//!   document
+//! - [`DocumentBuilder`] writes new documents
+//! - [`write_rtf`] serializes tokens back to RTF, and [`write_tokens`] to an
+//!   `io::Write`
+//!
+//! Everything else lives in its module.  The `raw` module holds the
+//! low-level nom parsers that the tokenizer is built from.
+
+#[cfg(any(test, feature = "fuzzing"))]
+extern crate arbitrary;
+#[cfg(feature = "arena")]
+extern crate bumpalo;
+#[cfg(feature = "encoding_rs")]
+extern crate encoding_rs;
+#[cfg(feature = "async")]
+extern crate futures_io;
+#[cfg(feature = "wasm")]
+extern crate js_sys;
+extern crate memchr;
 #[macro_use]
 extern crate nom;
+#[cfg(feature = "png-converter")]
+extern crate png;
+#[cfg(test)]
+extern crate proptest;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "regex")]
+extern crate regex;
+extern crate sha2;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
 
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod annotate;
+pub mod builder;
+pub mod codepage;
+pub mod compressed;
+pub mod convert;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+pub mod diff;
+pub mod document;
+pub mod encapsulation;
+pub mod fields;
+pub mod forms;
+pub mod file;
+pub mod groups;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+pub mod hash;
+pub mod header;
+pub mod json;
+pub mod language;
+pub mod lines;
+pub mod lossless;
+pub mod math;
+pub mod merge;
+pub mod metadata;
+pub mod minify;
+pub mod name;
+pub mod normalize;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod pict;
+pub mod pretty;
+pub mod quirks;
 pub mod raw;
+pub mod redact;
+#[cfg(feature = "png-converter")]
+pub mod raster;
+pub mod reader;
+pub mod region;
+pub mod registry;
+pub mod repair;
+pub mod rtfd;
+pub mod sanitize;
+pub mod scan;
+pub mod search;
+pub mod shape;
+pub mod split;
+pub mod state;
+pub mod stream;
+pub mod subset;
+pub mod symbol;
+pub mod template;
+pub mod text;
 pub mod tokenizer;
+pub mod tree;
+pub mod validate;
+pub mod words;
+pub mod writer;
+
+pub use builder::DocumentBuilder;
+pub use document::Document;
+pub use file::RtfFile;
+pub use stream::{parse_reader, StreamingTokenizer, TokenReader};
+pub use text::{extract_text, extract_text_with, TextOptions};
+pub use tokenizer::{
+    coalesce_text, parse, parse_partial, parse_spanned, parse_strict, parse_with_options,
+    ControlCharPolicy, ControlCharacter, Excerpt, Incomplete, Name, NewlinePolicy, NumericOverflow,
+    ParseError, ParseOptions, ParseTokenError, SpannedToken, Token,
+};
+pub use writer::{write_rtf, write_spanned_rtf, write_tokens, WriteError, WriteOptions};
@@ -0,0 +1,156 @@
+// Line index
+//
+// Editors and error messages talk about places in a document as lines and
+// columns, where the tokenizer deals in byte offsets.  Finding the line an
+// offset is on by counting the line breaks before it takes time in the
+// size of the document, so the start of every line is recorded once, and
+// each lookup after that is a binary search.
+//
+
+use std::ops::Range;
+
+use memchr::memchr2;
+use nom::types::CompleteByteSlice as Input;
+
+use tokenizer::{next_token, ParseError, SpannedToken, Token};
+
+/// The lines of a document, for converting byte offsets to lines and
+/// columns
+///
+/// Lines end at a CR, an LF, or a CRLF pair, as the tokenizer reads them,
+/// including those in `\bin` data.  Lines and columns both count from zero,
+/// and columns count bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    // The offset each line starts at
+    starts: Vec<usize>,
+    len: usize,
+}
+
+// Record where the lines that begin in `range` of `bytes` start
+fn line_starts(bytes: &[u8], range: Range<usize>, starts: &mut Vec<usize>) {
+    let mut pos = range.start;
+    while let Some(found) = memchr2(b'\r', b'\n', &bytes[pos..range.end]) {
+        pos += found;
+        // The LF of a CRLF pair moves the start of the line the CR began
+        if bytes[pos] == b'\n' && pos > 0 && bytes[pos - 1] == b'\r' {
+            if let Some(start) = starts.last_mut() {
+                *start = pos + 1;
+            }
+        } else {
+            starts.push(pos + 1);
+        }
+        pos += 1;
+    }
+}
+
+impl LineIndex {
+    /// Index the lines of a document
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut starts = vec![0];
+        line_starts(bytes, 0..bytes.len(), &mut starts);
+        LineIndex {
+            starts,
+            len: bytes.len(),
+        }
+    }
+
+    /// The number of lines, counting the one after a final line break
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The line and column of a byte offset, or None if it's past the end of
+    /// the document
+    ///
+    /// An offset in the middle of a CRLF pair is on the line the pair ends.
+    pub fn line_col(&self, offset: usize) -> Option<(usize, usize)> {
+        if offset > self.len {
+            return None;
+        }
+        let line = self.starts.partition_point(|start| *start <= offset) - 1;
+        Some((line, offset - self.starts[line]))
+    }
+
+    /// The byte offset of a line and column, or None if the line doesn't
+    /// exist
+    ///
+    /// Columns past the end of the line aren't checked, and give offsets on
+    /// the lines after it.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let offset = self.starts.get(line)? + column;
+        if offset > self.len {
+            return None;
+        }
+        Some(offset)
+    }
+
+    /// The byte range of a line, including the line break that ends it
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = *self.starts.get(line)?;
+        let end = self.starts.get(line + 1).copied().unwrap_or(self.len);
+        Some(start..end)
+    }
+}
+
+/// Tokenize a document, as `parse_spanned` does, indexing its lines along
+/// the way
+///
+/// ```
+/// use rtf_grimoire::lines::parse_indexed;
+///
+/// let bytes = b"{\\rtf1\r\n{\\b Bold}\r\n\\par}";
+/// let (tokens, lines) = parse_indexed(bytes).unwrap();
+/// let par = tokens.iter().find(|token| token.token.is_word("par")).unwrap();
+/// assert_eq!(lines.line_col(par.span.start), Some((2, 0)));
+/// ```
+pub fn parse_indexed(bytes: &[u8]) -> Result<(Vec<SpannedToken>, LineIndex), ParseError> {
+    let mut tokens = Vec::new();
+    let mut starts = vec![0];
+    let mut input = Input(bytes);
+    while let Some((rest, token, _)) = next_token(bytes, input)? {
+        let start = bytes.len() - input.len();
+        let end = bytes.len() - rest.len();
+        // Text never holds line breaks, which are tokens of their own
+        if let Token::Newline | Token::ControlBin(_) = token {
+            line_starts(bytes, start..end, &mut starts);
+        }
+        tokens.push(SpannedToken::new(bytes, token, start..end));
+        input = rest;
+    }
+    let lines = LineIndex {
+        starts,
+        len: bytes.len(),
+    };
+    Ok((tokens, lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let bytes = b"{\\rtf1 a\r\nb\rc\nd\\bin3 x\r\n}\n";
+        let lines = LineIndex::new(bytes);
+        assert_eq!(lines.line_count(), 6);
+        assert_eq!(lines.line_col(0), Some((0, 0)));
+        assert_eq!(lines.line_col(7), Some((0, 7)));
+        // The LF of a CRLF ends the line the CR does
+        assert_eq!(lines.line_col(9), Some((0, 9)));
+        assert_eq!(lines.line_col(10), Some((1, 0)));
+        assert_eq!(lines.line_col(12), Some((2, 0)));
+        assert_eq!(lines.line_col(14), Some((3, 0)));
+        assert_eq!(lines.line_col(bytes.len()), Some((5, 0)));
+        assert_eq!(lines.line_col(bytes.len() + 1), None);
+        assert_eq!(lines.offset(3, 1), Some(15));
+        assert_eq!(lines.offset(6, 0), None);
+        assert_eq!(lines.line_range(0), Some(0..10));
+        assert_eq!(lines.line_range(5), Some(bytes.len()..bytes.len()));
+
+        let (_, indexed) = parse_indexed(bytes).unwrap();
+        assert_eq!(indexed, lines);
+        let sample = include_bytes!("../tests/sample.rtf");
+        assert_eq!(parse_indexed(sample).unwrap().1, LineIndex::new(sample));
+    }
+}
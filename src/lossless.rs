@@ -0,0 +1,148 @@
+// Lossless tokenization
+//
+// Tokens normally forget the details of how they were written: the case of
+// hex escapes, leading zeros in arguments, which line breaks were CR and which
+// LF.  Lossless tokens keep the exact bytes each token was read from, so a
+// document can be edited in place, with only the tokens that were changed
+// written differently.
+//
+
+use tokenizer::{parse_spanned, ParseError, Token};
+
+/// A token, with the exact bytes it was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessToken {
+    token: Token,
+    raw: Vec<u8>,
+    // Whether `raw` came from the document, rather than from encoding a token
+    // given by the caller
+    original: bool,
+}
+
+impl LosslessToken {
+    /// A token to add to a document, written the way `Token::to_rtf` writes it
+    pub fn new(token: Token) -> Self {
+        let raw = token.to_rtf();
+        LosslessToken {
+            token,
+            raw,
+            original: false,
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    /// The bytes this token is written as
+    pub fn raw_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Replace the token, which is then written the way `Token::to_rtf`
+    /// writes it
+    pub fn set_token(&mut self, token: Token) {
+        *self = LosslessToken::new(token);
+    }
+
+    pub fn into_token(self) -> Token {
+        self.token
+    }
+}
+
+/// Tokenize a document, keeping every byte of it
+///
+/// Concatenating the `raw_bytes` of the tokens gives back `bytes` exactly.
+/// Anything after the point where the tokenizer can't read any further, such
+/// as a lone backslash at the end of a truncated document, is kept as a final
+/// text token.
+pub fn parse_lossless(bytes: &[u8]) -> Result<Vec<LosslessToken>, ParseError> {
+    let mut end = 0;
+    let mut tokens: Vec<LosslessToken> = parse_spanned(bytes)?
+        .into_iter()
+        .map(|spanned| {
+            end = spanned.span.end;
+            LosslessToken {
+                token: spanned.token,
+                raw: bytes[spanned.span].to_vec(),
+                original: true,
+            }
+        })
+        .collect();
+    if end < bytes.len() {
+        tokens.push(LosslessToken {
+            token: Token::Text(bytes[end..].to_vec()),
+            raw: bytes[end..].to_vec(),
+            original: true,
+        });
+    }
+    Ok(tokens)
+}
+
+// Whether a control word's bytes end in a way that text can run on from
+fn open_ended(token: &LosslessToken) -> bool {
+    match token.token {
+        Token::ControlWord { .. } | Token::Unicode { .. } => token.raw.last() != Some(&b' '),
+        _ => false,
+    }
+}
+
+/// Serialize lossless tokens
+///
+/// Tokens read from a document are written exactly as they were read.  A
+/// delimiting space is inserted between a control word and text where
+/// either was added or changed, and the text would otherwise run on from the
+/// control word.
+pub fn write_lossless(tokens: &[LosslessToken]) -> Vec<u8> {
+    let mut rtf = Vec::with_capacity(tokens.iter().map(|token| token.raw.len()).sum());
+    let mut prev: Option<&LosslessToken> = None;
+    for token in tokens {
+        if let Some(prev) = prev {
+            if !(prev.original && token.original) && open_ended(prev) {
+                rtf.extend_from_slice(prev.token.token_delimiter_after(&token.token).as_bytes());
+            }
+        }
+        rtf.extend_from_slice(&token.raw);
+        prev = Some(token);
+    }
+    rtf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lossless_roundtrip() {
+        let rtf =
+            b"{\\rtf1\\fs024\\li-0 \\'E9\\'e9\r{\\*\\x}\n\\b0e\\bin3\x00\x01\x02\\par  x\r\n}\\";
+        let tokens = parse_lossless(rtf).unwrap();
+        let raw: Vec<u8> = tokens
+            .iter()
+            .flat_map(|token| token.raw_bytes().to_vec())
+            .collect();
+        assert_eq!(raw, rtf.to_vec());
+        assert_eq!(write_lossless(&tokens), rtf.to_vec());
+        assert_eq!(tokens[2].raw_bytes(), b"\\fs024");
+        assert_eq!(*tokens[2].token(), Token::word_arg("fs", 24));
+        assert_eq!(tokens.last().unwrap().raw_bytes(), b"\\");
+
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        assert!(write_lossless(&parse_lossless(test_bytes).unwrap()) == test_bytes.to_vec());
+    }
+
+    #[test]
+    fn test_lossless_edits() {
+        let rtf = b"{\\rtf1\\par\r\nOld \\'E9\\b0e}";
+        let mut tokens = parse_lossless(rtf).unwrap();
+        assert_eq!(*tokens[4].token(), Token::text("Old "));
+        tokens[4].set_token(Token::text("New "));
+        // Text inserted straight after a control word is delimited from it
+        tokens.insert(3, LosslessToken::new(Token::text("x")));
+        tokens.insert(9, LosslessToken::new(Token::word("i")));
+        assert_eq!(
+            String::from_utf8_lossy(&write_lossless(&tokens)),
+            "{\\rtf1\\par x\r\nNew \\'E9\\b0e\\i}"
+        );
+    }
+}
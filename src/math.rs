@@ -0,0 +1,690 @@
+// Math zones
+//
+// Word 2007 and later write equations as Office Math (OMML) spelled out in
+// control words: each OMML element is a group named by the element's name
+// with an `m` in front, so a fraction is `{\mf{\mnum ...}{\mden ...}}`.  An
+// equation is a `{\*\moMath ...}` destination inside a `{\mmath ...}` group,
+// next to an `\mmathPict` picture of it for readers that don't know math,
+// and several displayed together are wrapped in an `\moMathPara`.
+//
+// Readers of the tree get the elements as written; `to_linear` and
+// `to_latex` are best-effort renderings of the common ones.
+//
+
+use text::{Decoded, Decoder};
+use tokenizer::Token;
+
+/// A piece of a math element: text, or a nested element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathContent {
+    Text(String),
+    Element(MathElement),
+}
+
+/// An OMML element, such as a fraction, a run of text or a property
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MathElement {
+    /// The OMML name, the control word naming the group without its `m`:
+    /// `oMath`, `f`, `num`, `r`, `chr` and so on.  Empty for a group that
+    /// isn't named.
+    pub name: String,
+    pub content: Vec<MathContent>,
+}
+
+// Names of functions LaTeX has commands for
+const LATEX_FUNCTIONS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max", "min", "Pr",
+    "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+// Characters written as LaTeX commands
+const LATEX_SYMBOLS: &[(char, &str)] = &[
+    ('α', "\\alpha"),
+    ('β', "\\beta"),
+    ('γ', "\\gamma"),
+    ('δ', "\\delta"),
+    ('ε', "\\varepsilon"),
+    ('ϵ', "\\epsilon"),
+    ('ζ', "\\zeta"),
+    ('η', "\\eta"),
+    ('θ', "\\theta"),
+    ('ι', "\\iota"),
+    ('κ', "\\kappa"),
+    ('λ', "\\lambda"),
+    ('μ', "\\mu"),
+    ('ν', "\\nu"),
+    ('ξ', "\\xi"),
+    ('π', "\\pi"),
+    ('ρ', "\\rho"),
+    ('σ', "\\sigma"),
+    ('τ', "\\tau"),
+    ('υ', "\\upsilon"),
+    ('φ', "\\varphi"),
+    ('ϕ', "\\phi"),
+    ('χ', "\\chi"),
+    ('ψ', "\\psi"),
+    ('ω', "\\omega"),
+    ('Γ', "\\Gamma"),
+    ('Δ', "\\Delta"),
+    ('Θ', "\\Theta"),
+    ('Λ', "\\Lambda"),
+    ('Ξ', "\\Xi"),
+    ('Π', "\\Pi"),
+    ('Σ', "\\Sigma"),
+    ('Φ', "\\Phi"),
+    ('Ψ', "\\Psi"),
+    ('Ω', "\\Omega"),
+    ('∑', "\\sum"),
+    ('∏', "\\prod"),
+    ('∐', "\\coprod"),
+    ('∫', "\\int"),
+    ('∬', "\\iint"),
+    ('∭', "\\iiint"),
+    ('∮', "\\oint"),
+    ('⋃', "\\bigcup"),
+    ('⋂', "\\bigcap"),
+    ('⋁', "\\bigvee"),
+    ('⋀', "\\bigwedge"),
+    ('∞', "\\infty"),
+    ('∂', "\\partial"),
+    ('∇', "\\nabla"),
+    ('±', "\\pm"),
+    ('∓', "\\mp"),
+    ('×', "\\times"),
+    ('÷', "\\div"),
+    ('·', "\\cdot"),
+    ('⋅', "\\cdot"),
+    ('∘', "\\circ"),
+    ('≤', "\\leq"),
+    ('≥', "\\geq"),
+    ('≠', "\\neq"),
+    ('≈', "\\approx"),
+    ('≡', "\\equiv"),
+    ('∝', "\\propto"),
+    ('∼', "\\sim"),
+    ('→', "\\to"),
+    ('←', "\\leftarrow"),
+    ('↔', "\\leftrightarrow"),
+    ('⇒', "\\Rightarrow"),
+    ('⇐', "\\Leftarrow"),
+    ('⇔', "\\Leftrightarrow"),
+    ('∈', "\\in"),
+    ('∉', "\\notin"),
+    ('∋', "\\ni"),
+    ('⊂', "\\subset"),
+    ('⊃', "\\supset"),
+    ('⊆', "\\subseteq"),
+    ('⊇', "\\supseteq"),
+    ('∪', "\\cup"),
+    ('∩', "\\cap"),
+    ('∅', "\\emptyset"),
+    ('∀', "\\forall"),
+    ('∃', "\\exists"),
+    ('¬', "\\neg"),
+    ('∧', "\\wedge"),
+    ('∨', "\\vee"),
+    ('…', "\\ldots"),
+    ('⋯', "\\cdots"),
+    ('′', "'"),
+    ('″', "''"),
+    ('⟨', "\\langle"),
+    ('⟩', "\\rangle"),
+    ('⌊', "\\lfloor"),
+    ('⌋', "\\rfloor"),
+    ('⌈', "\\lceil"),
+    ('⌉', "\\rceil"),
+    ('‖', "\\|"),
+];
+
+// Combining accents, for `acc`
+const LATEX_ACCENTS: &[(char, &str)] = &[
+    ('\u{300}', "\\grave"),
+    ('\u{301}', "\\acute"),
+    ('\u{302}', "\\hat"),
+    ('\u{303}', "\\tilde"),
+    ('\u{304}', "\\bar"),
+    ('\u{305}', "\\bar"),
+    ('\u{306}', "\\breve"),
+    ('\u{307}', "\\dot"),
+    ('\u{308}', "\\ddot"),
+    ('\u{30c}', "\\check"),
+    ('\u{20d7}', "\\vec"),
+];
+
+impl MathElement {
+    fn named(name: &str) -> Self {
+        MathElement {
+            name: name.to_string(),
+            content: Vec::new(),
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some(MathContent::Text(last)) = self.content.last_mut() {
+            last.push_str(text);
+            return;
+        }
+        self.content.push(MathContent::Text(text.to_string()));
+    }
+
+    /// The nested elements
+    pub fn elements(&self) -> impl Iterator<Item = &MathElement> {
+        self.content.iter().filter_map(|content| match content {
+            MathContent::Element(element) => Some(element),
+            MathContent::Text(_) => None,
+        })
+    }
+
+    /// The first nested element named `name`
+    pub fn child(&self, name: &str) -> Option<&MathElement> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    /// The text directly inside this element, such as the text of a run or
+    /// the value of a property
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                MathContent::Text(text) => Some(text.as_str()),
+                MathContent::Element(_) => None,
+            })
+            .collect()
+    }
+
+    /// Whether this is a property element, like `fPr`, which says how its
+    /// parent is shown rather than holding part of the equation
+    pub fn is_property(&self) -> bool {
+        self.name.ends_with("Pr")
+    }
+
+    // The value of one of this element's properties, such as the `chr` of an
+    // `nary`
+    fn property(&self, name: &str) -> Option<String> {
+        self.child(&format!("{}Pr", self.name))
+            .and_then(|properties| properties.child(name))
+            .map(MathElement::text)
+    }
+
+    // Whether an on/off property is on; just naming it turns it on
+    fn flag(&self, name: &str) -> bool {
+        self.property(name)
+            .is_some_and(|value| !["off", "0", "false"].contains(&value.trim()))
+    }
+
+    /// The equation in the linear format Word shows when an equation is
+    /// switched to "linear", as near as plain text allows: `a/b`, `x^2`,
+    /// `√(x+1)` and so on
+    pub fn to_linear(&self) -> String {
+        let part = |name: &str| {
+            self.child(name)
+                .map_or(String::new(), MathElement::to_linear)
+        };
+        let rows = |name: &str, separator: &str| {
+            self.elements()
+                .filter(|element| element.name == name)
+                .map(MathElement::to_linear)
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        match self.name.as_str() {
+            "f" if self.property("type").as_deref() == Some("noBar") => {
+                format!("{}¦{}", bracket(&part("num")), bracket(&part("den")))
+            }
+            "f" => format!("{}/{}", bracket(&part("num")), bracket(&part("den"))),
+            "sSup" => format!("{}^{}", bracket(&part("e")), bracket(&part("sup"))),
+            "sSub" => format!("{}_{}", bracket(&part("e")), bracket(&part("sub"))),
+            "sSubSup" => format!(
+                "{}_{}^{}",
+                bracket(&part("e")),
+                bracket(&part("sub")),
+                bracket(&part("sup"))
+            ),
+            "sPre" => format!(
+                "_{}^{}{}",
+                bracket(&part("sub")),
+                bracket(&part("sup")),
+                bracket(&part("e"))
+            ),
+            "rad" => match part("deg") {
+                ref degree if degree.is_empty() || self.flag("degHide") => {
+                    format!("√{}", bracket(&part("e")))
+                }
+                degree => format!("√({}&{})", degree, part("e")),
+            },
+            "nary" => {
+                let mut linear = self.property("chr").unwrap_or_else(|| "∫".to_string());
+                for (limit, mark) in &[("sub", '_'), ("sup", '^')] {
+                    let limit = part(limit);
+                    if !limit.is_empty() {
+                        linear.push(*mark);
+                        linear.push_str(&bracket(&limit));
+                    }
+                }
+                linear.push(' ');
+                linear + &part("e")
+            }
+            "d" => format!(
+                "{}{}{}",
+                self.property("begChr").unwrap_or_else(|| "(".to_string()),
+                rows(
+                    "e",
+                    &self.property("sepChr").unwrap_or_else(|| "|".to_string())
+                ),
+                self.property("endChr").unwrap_or_else(|| ")".to_string())
+            ),
+            "func" => format!("{} {}", part("fName"), part("e")),
+            "limLow" => format!("{}_{}", part("e"), bracket(&part("lim"))),
+            "limUpp" => format!("{}^{}", part("e"), bracket(&part("lim"))),
+            "acc" => {
+                bracket(&part("e"))
+                    + &self
+                        .property("chr")
+                        .unwrap_or_else(|| "\u{302}".to_string())
+            }
+            "bar" if self.property("pos").as_deref() == Some("top") => {
+                bracket(&part("e")) + "\u{305}"
+            }
+            "bar" => bracket(&part("e")) + "\u{332}",
+            "groupChr" => {
+                self.property("chr").unwrap_or_else(|| "⏟".to_string()) + &bracket(&part("e"))
+            }
+            "m" => {
+                let rows: Vec<String> = self
+                    .elements()
+                    .filter(|element| element.name == "mr")
+                    .map(|row| {
+                        row.elements()
+                            .filter(|element| element.name == "e")
+                            .map(MathElement::to_linear)
+                            .collect::<Vec<_>>()
+                            .join("&")
+                    })
+                    .collect();
+                format!("■({})", rows.join("@"))
+            }
+            "eqArr" => format!("█({})", rows("e", "@")),
+            "phant" if !self.flag("show") && self.property("show").is_some() => String::new(),
+            _ => {
+                let mut linear = String::new();
+                for content in &self.content {
+                    match content {
+                        MathContent::Text(text) => linear.push_str(text),
+                        MathContent::Element(element) if !element.is_property() => {
+                            linear.push_str(&element.to_linear())
+                        }
+                        MathContent::Element(_) => {}
+                    }
+                }
+                linear
+            }
+        }
+    }
+
+    /// The equation as LaTeX math, without the `$` or other markup around it
+    pub fn to_latex(&self) -> String {
+        let part = |name: &str| {
+            self.child(name)
+                .map_or(String::new(), MathElement::to_latex)
+        };
+        match self.name.as_str() {
+            "r" if LATEX_FUNCTIONS.contains(&self.text().trim()) => {
+                format!("\\{}", self.text().trim())
+            }
+            "f" => {
+                let (num, den) = (part("num"), part("den"));
+                match self.property("type").as_deref() {
+                    Some("lin") | Some("skw") => format!("{{{}}}/{{{}}}", num, den),
+                    Some("noBar") => format!("\\genfrac{{}}{{}}{{0pt}}{{}}{{{}}}{{{}}}", num, den),
+                    _ => format!("\\frac{{{}}}{{{}}}", num, den),
+                }
+            }
+            "sSup" => format!("{}^{{{}}}", brace(&part("e")), part("sup")),
+            "sSub" => format!("{}_{{{}}}", brace(&part("e")), part("sub")),
+            "sSubSup" => format!(
+                "{}_{{{}}}^{{{}}}",
+                brace(&part("e")),
+                part("sub"),
+                part("sup")
+            ),
+            "sPre" => format!(
+                "{{}}_{{{}}}^{{{}}}{}",
+                part("sub"),
+                part("sup"),
+                brace(&part("e"))
+            ),
+            "rad" => match part("deg") {
+                ref degree if degree.is_empty() || self.flag("degHide") => {
+                    format!("\\sqrt{{{}}}", part("e"))
+                }
+                degree => format!("\\sqrt[{}]{{{}}}", degree, part("e")),
+            },
+            "nary" => {
+                let mut latex =
+                    latex_text(&self.property("chr").unwrap_or_else(|| "∫".to_string()));
+                for (limit, mark) in &[("sub", '_'), ("sup", '^')] {
+                    let limit = part(limit);
+                    if !limit.is_empty() {
+                        latex.push_str(&format!("{}{{{}}}", mark, limit));
+                    }
+                }
+                join(&mut latex, &part("e"));
+                latex
+            }
+            "d" => {
+                let delimiter = |name: &str, default: &str| match self.property(name) {
+                    Some(ref delimiter) if delimiter.is_empty() => ".".to_string(),
+                    Some(delimiter) => latex_text(&delimiter),
+                    None => default.to_string(),
+                };
+                let separator = delimiter("sepChr", "|");
+                let mut latex = format!("\\left{}", delimiter("begChr", "("));
+                for (index, element) in self.elements().filter(|e| e.name == "e").enumerate() {
+                    if index > 0 {
+                        join(&mut latex, &separator);
+                    }
+                    join(&mut latex, &element.to_latex());
+                }
+                latex + "\\right" + &delimiter("endChr", ")")
+            }
+            "func" => {
+                let mut latex = part("fName");
+                if !latex.starts_with('\\') {
+                    latex = format!("\\operatorname{{{}}}", latex);
+                }
+                join(&mut latex, &part("e"));
+                latex
+            }
+            "limLow" => format!("{}_{{{}}}", brace(&part("e")), part("lim")),
+            "limUpp" => format!("{}^{{{}}}", brace(&part("e")), part("lim")),
+            "acc" => {
+                let accent = self
+                    .property("chr")
+                    .and_then(|chr| chr.chars().next())
+                    .unwrap_or('\u{302}');
+                let command = LATEX_ACCENTS
+                    .iter()
+                    .find(|(c, _)| *c == accent)
+                    .map_or("\\hat", |(_, command)| command);
+                format!("{}{{{}}}", command, part("e"))
+            }
+            "bar" if self.property("pos").as_deref() == Some("top") => {
+                format!("\\overline{{{}}}", part("e"))
+            }
+            "bar" => format!("\\underline{{{}}}", part("e")),
+            "groupChr" => {
+                let top = self.property("pos").as_deref() == Some("top");
+                match self.property("chr").as_deref() {
+                    None | Some("⏟") => format!("\\underbrace{{{}}}", part("e")),
+                    Some("⏞") => format!("\\overbrace{{{}}}", part("e")),
+                    Some(chr) if top => {
+                        format!("\\overset{{{}}}{{{}}}", latex_text(chr), part("e"))
+                    }
+                    Some(chr) => format!("\\underset{{{}}}{{{}}}", latex_text(chr), part("e")),
+                }
+            }
+            "m" => {
+                let rows: Vec<String> = self
+                    .elements()
+                    .filter(|element| element.name == "mr")
+                    .map(|row| {
+                        row.elements()
+                            .filter(|element| element.name == "e")
+                            .map(MathElement::to_latex)
+                            .collect::<Vec<_>>()
+                            .join(" & ")
+                    })
+                    .collect();
+                format!("\\begin{{matrix}}{}\\end{{matrix}}", rows.join(" \\\\ "))
+            }
+            "eqArr" => {
+                let rows: Vec<String> = self
+                    .elements()
+                    .filter(|element| element.name == "e")
+                    .map(MathElement::to_latex)
+                    .collect();
+                format!(
+                    "\\begin{{gathered}}{}\\end{{gathered}}",
+                    rows.join(" \\\\ ")
+                )
+            }
+            "borderBox" => format!("\\boxed{{{}}}", part("e")),
+            "phant" if !self.flag("show") && self.property("show").is_some() => {
+                format!("\\phantom{{{}}}", part("e"))
+            }
+            _ => {
+                let mut latex = String::new();
+                for content in &self.content {
+                    match content {
+                        MathContent::Text(text) => join(&mut latex, &latex_text(text)),
+                        MathContent::Element(element) if !element.is_property() => {
+                            join(&mut latex, &element.to_latex())
+                        }
+                        MathContent::Element(_) => {}
+                    }
+                }
+                latex
+            }
+        }
+    }
+}
+
+// Parenthesize anything longer than a character, for the linear format
+fn bracket(linear: &str) -> String {
+    if linear.chars().count() > 1 {
+        format!("({})", linear)
+    } else {
+        linear.to_string()
+    }
+}
+
+// Brace a LaTeX base for a script, unless it's a character or a command
+fn brace(latex: &str) -> String {
+    let command = latex.starts_with('\\') && latex[1..].chars().all(|c| c.is_ascii_alphabetic());
+    if latex.chars().count() > 1 && !command {
+        format!("{{{}}}", latex)
+    } else {
+        latex.to_string()
+    }
+}
+
+// Append LaTeX, with a space if a command would otherwise run into a letter
+fn join(latex: &mut String, more: &str) {
+    let letters = latex.len()
+        - latex
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .len();
+    let backslashes = latex[..latex.len() - letters].len()
+        - latex[..latex.len() - letters].trim_end_matches('\\').len();
+    if letters > 0 && backslashes % 2 == 1 && more.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        latex.push(' ');
+    }
+    latex.push_str(more);
+}
+
+// Escape text for LaTeX math
+fn latex_text(text: &str) -> String {
+    let mut latex = String::new();
+    for c in text.chars() {
+        match c {
+            '{' | '}' | '#' | '%' | '&' | '$' | '_' => {
+                latex.push('\\');
+                latex.push(c);
+            }
+            '\\' => join(&mut latex, "\\backslash"),
+            '^' => latex.push_str("\\hat{}"),
+            '~' => join(&mut latex, "\\sim"),
+            _ => match LATEX_SYMBOLS.iter().find(|(symbol, _)| *symbol == c) {
+                Some((_, command)) => join(&mut latex, command),
+                None => join(&mut latex, c.encode_utf8(&mut [0; 4])),
+            },
+        }
+    }
+    latex
+}
+
+// An equation being read
+struct Equation {
+    // Depth of the document's decoder inside the \moMath group
+    depth: usize,
+    // Decodes the equation's text, from the document's fonts
+    decoder: Decoder,
+    // The elements open, starting with the oMath
+    open: Vec<MathElement>,
+}
+
+impl Equation {
+    fn finish(mut self) -> MathElement {
+        while self.open.len() > 1 {
+            self.close();
+        }
+        self.open.pop().unwrap_or_default()
+    }
+
+    fn close(&mut self) {
+        if self.open.len() > 1 {
+            if let Some(element) = self.open.pop() {
+                if let Some(parent) = self.open.last_mut() {
+                    parent.content.push(MathContent::Element(element));
+                }
+            }
+        }
+    }
+}
+
+/// The equations in a document, one for each `\moMath` destination
+///
+/// The equations of an `\moMathPara` each come separately, in order.
+pub fn math_zones(tokens: &[Token]) -> Vec<MathElement> {
+    let mut zones = Vec::new();
+    let mut decoder = Decoder::new();
+    let mut equation: Option<Equation> = None;
+    // Set after a StartGroup, and a \* following it, when the next control
+    // word names the group
+    let mut naming = false;
+    for token in tokens {
+        let named = naming;
+        naming = *token == Token::StartGroup || (named && *token == Token::ControlSymbol('*'));
+        decoder.token(token);
+
+        let inside = equation
+            .as_ref()
+            .is_some_and(|equation| decoder.depth() >= equation.depth);
+        if !inside {
+            if let Some(equation) = equation.take() {
+                zones.push(equation.finish());
+            }
+            if named && token.is_word("moMath") {
+                equation = Some(Equation {
+                    depth: decoder.depth(),
+                    decoder: Decoder::with_state(decoder.state().clone()),
+                    open: vec![MathElement::named("oMath")],
+                });
+            }
+            continue;
+        }
+        let equation = match equation.as_mut() {
+            Some(equation) => equation,
+            None => continue,
+        };
+        if let Token::ControlWord { name, .. } = token {
+            if named && name.starts_with('m') && equation.open.len() > 1 {
+                if let Some(element) = equation.open.last_mut() {
+                    element.name = name[1..].to_string();
+                }
+            }
+        }
+        match equation.decoder.token(token) {
+            Decoded::GroupStart => equation.open.push(MathElement::default()),
+            Decoded::GroupEnd => equation.close(),
+            Decoded::Text(text) => {
+                if let Some(element) = equation.open.last_mut() {
+                    element.push_text(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    zones.extend(equation.map(Equation::finish));
+    zones
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    fn zones(rtf: &str) -> Vec<MathElement> {
+        math_zones(&parse(rtf.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_math_zones() {
+        let rtf = "{\\rtf1{\\fonttbl{\\f0 Calibri;}{\\f34 Cambria Math;}}\\pard Area: \
+{\\mmath{\\*\\moMath{\\mf{\\mfPr{\\mtype bar}}{\\mnum{\\mr\\i a}}{\\mden{\\mr b}}}\
+{\\mr +}{\\msSup{\\me{\\mr x}}{\\msup{\\mr 2}}}}}{\\mmathPict{\\pict\\wmetafile8 00}}\\par}";
+        let found = zones(rtf);
+        assert_eq!(found.len(), 1);
+        let equation = &found[0];
+        assert_eq!(equation.name, "oMath");
+        let names: Vec<&str> = equation.elements().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["f", "r", "sSup"]);
+        let fraction = equation.child("f").unwrap();
+        assert!(fraction.child("fPr").unwrap().is_property());
+        assert_eq!(fraction.property("type").as_deref(), Some("bar"));
+        assert_eq!(
+            fraction.child("num").unwrap().child("r").unwrap().text(),
+            "a"
+        );
+        assert_eq!(equation.to_linear(), "a/b+x^2");
+        assert_eq!(equation.to_latex(), "\\frac{a}{b}+x^{2}");
+
+        assert!(zones("{\\rtf1 no math\\par}").is_empty());
+    }
+
+    #[test]
+    fn test_math_para() {
+        // Two equations displayed together, the second cut short
+        let rtf = "{\\rtf1{\\mmath{\\*\\moMathPara{\\moMathParaPr{\\mjc centerGroup}}\
+{\\*\\moMath{\\mr y=1}}{\\*\\moMath{\\mr z=}{\\mrad{\\me{\\mr 2}";
+        let found = zones(rtf);
+        let linear: Vec<String> = found.iter().map(MathElement::to_linear).collect();
+        assert_eq!(linear, vec!["y=1", "z=√2"]);
+    }
+
+    #[test]
+    fn test_to_latex() {
+        let sum = "{\\rtf1\\uc1{\\mmath{\\*\\moMath{\\mnary{\\mnaryPr{\\mchr \\u8721\\'3f}}\
+{\\msub{\\mr i=1}}{\\msup{\\mr n}}{\\me{\\msSub{\\me{\\mr x}}{\\msub{\\mr i}}}}}}}}";
+        let equation = &zones(sum)[0];
+        assert_eq!(equation.to_linear(), "∑_(i=1)^n x_i");
+        assert_eq!(equation.to_latex(), "\\sum_{i=1}^{n}x_{i}");
+
+        let root = "{\\rtf1{\\mmath{\\*\\moMath{\\mrad{\\mradPr{\\mdegHide on}}{\\mdeg}\
+{\\me{\\md{\\mdPr{\\mbegChr [}{\\mendChr ]}{\\msepChr ,}}{\\me{\\mr x}}{\\me{\\mr y}}}}}}}}";
+        let equation = &zones(root)[0];
+        assert_eq!(equation.to_linear(), "√([x,y])");
+        assert_eq!(equation.to_latex(), "\\sqrt{\\left[x,y\\right]}");
+
+        let limit = "{\\rtf1{\\mmath{\\*\\moMath{\\mfunc{\\mfName{\\mlimLow{\\me{\\mr lim}}\
+{\\mlim{\\mr n\\u8594\\'3f\\u8734\\'3f}}}}{\\me{\\mf{\\mnum{\\mr 1}}{\\mden{\\mr n}}}}}\
+{\\mr +\\u960\\'3fr}}}}";
+        let equation = &zones(limit)[0];
+        assert_eq!(equation.to_linear(), "lim_(n→∞) 1/n+πr");
+        assert_eq!(
+            equation.to_latex(),
+            "\\lim_{n\\to\\infty}\\frac{1}{n}+\\pi r"
+        );
+
+        let matrix =
+            "{\\rtf1{\\mmath{\\*\\moMath{\\md{\\me{\\mm{\\mmr{\\me{\\mr a}}{\\me{\\mr b}}}\
+{\\mmr{\\me{\\mr c}}{\\me{\\mr d}}}}}}}}}";
+        let equation = &zones(matrix)[0];
+        assert_eq!(equation.to_linear(), "(■(a&b@c&d))");
+        assert_eq!(
+            equation.to_latex(),
+            "\\left(\\begin{matrix}a & b \\\\ c & d\\end{matrix}\\right)"
+        );
+    }
+}
@@ -0,0 +1,701 @@
+// Combining documents
+//
+// Documents can't simply be concatenated: each has its own header, and its
+// formatting refers to its own font, color, style and list tables by index.
+// Merging keeps the first document's header, adds what the others need to
+// its tables, and renumbers the references in their bodies to match.
+//
+
+use std::collections::HashMap;
+
+use document::{Color, Document, StyleKind};
+use normalize::{normalize, NormalizeOptions};
+use tokenizer::{group_destination, group_end, narrow, parse, ParseError, Token};
+use writer::{write_rtf, WriteOptions};
+
+/// What goes between merged documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Separator {
+    /// A section break, `\sect`, after which each document starts with the
+    /// default section formatting
+    #[default]
+    Section,
+    /// A page break, `\page`
+    Page,
+    /// Nothing: each document starts a new paragraph
+    Paragraph,
+}
+
+/// Options for `merge_with`
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    pub separator: Separator,
+}
+
+// Header destinations that are merged
+pub(crate) const MERGED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "listtable",
+    "listoverridetable",
+];
+
+// Header destinations that are kept from the first document and dropped from
+// the rest
+pub(crate) const HEADER_DESTINATIONS: &[&str] = &[
+    "colorschememapping",
+    "defchp",
+    "defpap",
+    "fchars",
+    "filetbl",
+    "generator",
+    "info",
+    "lchars",
+    "mmathPr",
+    "pgdsctbl",
+    "protusertbl",
+    "revtbl",
+    "rsidtbl",
+    "themedata",
+    "userprops",
+    "xmlnstbl",
+];
+
+// Header control words that are dropped from all but the first document
+const HEADER_WORDS: &[&str] = &[
+    "rtf",
+    "ansi",
+    "mac",
+    "pc",
+    "pca",
+    "ansicpg",
+    "deff",
+    "adeff",
+    "deflang",
+    "deflangfe",
+    "adeflang",
+    "stshfdbch",
+    "stshfloch",
+    "stshfhich",
+    "stshfbi",
+    "fromtext",
+    "fromhtml",
+];
+
+// Control words whose argument is a font, color, style or list override
+const FONT_WORDS: &[&str] = &["f", "af"];
+const COLOR_WORDS: &[&str] = &[
+    "cf",
+    "cb",
+    "highlight",
+    "chcbpat",
+    "chcfpat",
+    "cbpat",
+    "cfpat",
+    "clcbpat",
+    "clcfpat",
+    "clcbpatraw",
+    "clcfpatraw",
+    "brdrcf",
+    "trcbpat",
+    "trcfpat",
+    "ulc",
+];
+const STYLE_WORDS: &[&str] = &["s", "cs", "ds", "ts", "sbasedon", "snext", "slink"];
+
+// New numbers for the indexes of a document's tables
+#[derive(Debug, Default)]
+struct Renumbering {
+    fonts: HashMap<i32, i32>,
+    colors: HashMap<i32, i32>,
+    styles: HashMap<i32, i32>,
+    lists: HashMap<i32, i32>,
+    list_ids: HashMap<i32, i32>,
+}
+
+impl Renumbering {
+    fn token(&self, token: &Token) -> Token {
+        let (name, arg) = match token {
+            Token::ControlWord {
+                name,
+                arg: Some(arg),
+            } => (name.as_str(), narrow(*arg)),
+            _ => return token.clone(),
+        };
+        let map = if FONT_WORDS.contains(&name) {
+            &self.fonts
+        } else if COLOR_WORDS.contains(&name) {
+            &self.colors
+        } else if STYLE_WORDS.contains(&name) {
+            &self.styles
+        } else if name == "ls" {
+            &self.lists
+        } else if name == "listid" {
+            &self.list_ids
+        } else {
+            return token.clone();
+        };
+        match map.get(&arg) {
+            Some(arg) => Token::word_arg(name, i64::from(*arg)),
+            None => token.clone(),
+        }
+    }
+
+    fn tokens(&self, tokens: &[Token]) -> Vec<Token> {
+        tokens.iter().map(|token| self.token(token)).collect()
+    }
+}
+
+// The groups directly inside the group `tokens[start..end]`
+fn children(tokens: &[Token], start: usize, end: usize) -> Vec<&[Token]> {
+    let mut groups = Vec::new();
+    let mut index = start + 1;
+    while index < end {
+        if tokens[index] == Token::StartGroup {
+            let group_end = group_end(tokens, index);
+            groups.push(&tokens[index..group_end]);
+            index = group_end;
+        } else {
+            index += 1;
+        }
+    }
+    groups
+}
+
+// The argument of the first of `names` in `tokens`
+fn first_arg(tokens: &[Token], names: &[&str]) -> Option<i32> {
+    tokens.iter().find_map(|token| match token {
+        Token::ControlWord {
+            name,
+            arg: Some(arg),
+        } if names.contains(&name.as_str()) => Some(narrow(*arg)),
+        _ => None,
+    })
+}
+
+// A document split into the parts that are merged separately
+#[derive(Default)]
+struct Parts<'t> {
+    // Top level tokens of the header that aren't groups, like \rtf1
+    prologue: Vec<Token>,
+    fonts: Vec<&'t [Token]>,
+    // Fonts written without groups of their own, like {\fonttbl\f0 Arial;}
+    bare_fonts: Option<&'t [Token]>,
+    styles: Vec<&'t [Token]>,
+    lists: Vec<&'t [Token]>,
+    overrides: Vec<&'t [Token]>,
+    header: Vec<&'t [Token]>,
+    body: Vec<Token>,
+}
+
+fn split(tokens: &[Token]) -> Parts<'_> {
+    let mut parts = Parts::default();
+    let start = match tokens.iter().position(|token| *token == Token::StartGroup) {
+        Some(start) => start,
+        None => {
+            parts.body = tokens.to_vec();
+            return parts;
+        }
+    };
+    let end = group_end(tokens, start);
+    let mut in_header = true;
+    let mut index = start + 1;
+    while index < end {
+        let token = &tokens[index];
+        if *token == Token::EndGroup && index + 1 == end {
+            break;
+        }
+        if *token != Token::StartGroup {
+            let header_word = match token {
+                Token::ControlWord { name, .. } => HEADER_WORDS.contains(&name.as_str()),
+                Token::Newline => true,
+                _ => false,
+            };
+            if in_header && header_word {
+                parts.prologue.push(token.clone());
+            } else {
+                in_header = false;
+                parts.body.push(token.clone());
+            }
+            index += 1;
+            continue;
+        }
+        let group_end = group_end(tokens, index);
+        let group = &tokens[index..group_end];
+        match group_destination(tokens, index).map(|(name, _, _)| name) {
+            Some("fonttbl") => {
+                parts.fonts = children(tokens, index, group_end);
+                if parts.fonts.is_empty() {
+                    parts.bare_fonts = Some(group);
+                }
+            }
+            Some("stylesheet") => parts.styles = children(tokens, index, group_end),
+            Some("listtable") => parts.lists = children(tokens, index, group_end),
+            Some("listoverridetable") => parts.overrides = children(tokens, index, group_end),
+            Some(name) if MERGED_DESTINATIONS.contains(&name) => {}
+            Some(name) if HEADER_DESTINATIONS.contains(&name) => parts.header.push(group),
+            _ => {
+                in_header = false;
+                parts.body.extend_from_slice(group);
+            }
+        }
+        index = group_end;
+    }
+    parts
+}
+
+// The number of a stylesheet entry, or None if it isn't a style, like
+// {\*\latentstyles}
+fn style_number(entry: &[Token]) -> Option<i32> {
+    match group_destination(entry, 0) {
+        Some((name, true, _)) if !["cs", "ds", "ts"].contains(&name) => None,
+        _ => Some(first_arg(entry, &["s", "cs", "ds", "ts"]).unwrap_or(0)),
+    }
+}
+
+// Append the tokens of a table destination
+fn push_table(out: &mut Vec<Token>, name: &str, ignorable: bool, entries: &[Vec<Token>]) {
+    if entries.is_empty() {
+        return;
+    }
+    out.push(Token::StartGroup);
+    if ignorable {
+        out.push(Token::ControlSymbol('*'));
+    }
+    out.push(Token::word(name));
+    for entry in entries {
+        out.extend_from_slice(entry);
+    }
+    out.push(Token::EndGroup);
+}
+
+fn color_entry(color: &Option<Color>) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    if let Some(color) = color {
+        tokens.push(Token::word_arg("red", i64::from(color.red)));
+        tokens.push(Token::word_arg("green", i64::from(color.green)));
+        tokens.push(Token::word_arg("blue", i64::from(color.blue)));
+    }
+    tokens.push(Token::text(";"));
+    tokens
+}
+
+// The tables of the merged document, as they're built up
+#[derive(Default)]
+struct Tables {
+    fonts: Vec<(String, Option<i32>, i32)>,
+    font_entries: Vec<Vec<Token>>,
+    colors: Vec<Option<Color>>,
+    styles: Vec<(StyleKind, String, i32)>,
+    style_entries: Vec<Vec<Token>>,
+    list_ids: Vec<i32>,
+    list_entries: Vec<Vec<Token>>,
+    overrides: Vec<i32>,
+    override_entries: Vec<Vec<Token>>,
+}
+
+impl Tables {
+    // Add a document's tables, and work out how to renumber its references
+    // to them
+    fn add(&mut self, parts: &Parts, doc: &Document, first: bool) -> Renumbering {
+        let mut renumbering = Renumbering::default();
+
+        for entry in &parts.fonts {
+            let index = match first_arg(entry, &["f"]) {
+                Some(index) => index,
+                None => continue,
+            };
+            let font = doc.font(index);
+            let key = (
+                font.map(|font| font.name.clone()).unwrap_or_default(),
+                font.and_then(|font| font.charset),
+            );
+            let known = self
+                .fonts
+                .iter()
+                .find(|(name, charset, _)| (name, charset) == (&key.0, &key.1));
+            let new = match known {
+                Some((_, _, new)) if !first => *new,
+                _ => {
+                    let new = if first {
+                        index
+                    } else {
+                        self.fonts.iter().map(|font| font.2 + 1).max().unwrap_or(0)
+                    };
+                    self.fonts.push((key.0, key.1, new));
+                    renumbering.fonts.insert(index, new);
+                    let entry = renumbering.tokens(entry);
+                    self.font_entries.push(entry);
+                    new
+                }
+            };
+            renumbering.fonts.insert(index, new);
+        }
+        if let (true, Some(fonts)) = (first, parts.bare_fonts) {
+            // Keep the first document's table as it was written
+            let contents = &fonts[2..fonts.len() - 1];
+            self.font_entries.push(contents.to_vec());
+            for font in &doc.fonts {
+                self.fonts
+                    .push((font.name.clone(), font.charset, font.index));
+            }
+        }
+
+        for (index, color) in doc.colors.iter().enumerate() {
+            let new = match self.colors.iter().position(|known| known == color) {
+                Some(new) if !first => new,
+                _ => {
+                    self.colors.push(*color);
+                    self.colors.len() - 1
+                }
+            };
+            renumbering.colors.insert(index as i32, new as i32);
+        }
+
+        let mut entries = Vec::new();
+        for entry in &parts.styles {
+            let number = match style_number(entry) {
+                Some(number) => number,
+                None if first => {
+                    self.style_entries.push(entry.to_vec());
+                    continue;
+                }
+                None => continue,
+            };
+            let style = doc.style(number);
+            let key = (
+                style.map(|style| style.kind).unwrap_or_default(),
+                style.map(|style| style.name.clone()).unwrap_or_default(),
+            );
+            let known = self
+                .styles
+                .iter()
+                .find(|(kind, name, _)| (kind, name) == (&key.0, &key.1));
+            let new = match known {
+                Some((_, _, new)) if !first => *new,
+                _ => {
+                    let new = if first {
+                        number
+                    } else {
+                        self.styles
+                            .iter()
+                            .map(|style| style.2 + 1)
+                            .max()
+                            .unwrap_or(0)
+                    };
+                    self.styles.push((key.0, key.1, new));
+                    entries.push(entry);
+                    new
+                }
+            };
+            renumbering.styles.insert(number, new);
+        }
+        // Styles refer to each other, so they're renumbered once they all
+        // have their numbers
+        for entry in entries {
+            self.style_entries.push(renumbering.tokens(entry));
+        }
+
+        // Lists are told apart by their \listid, which the overrides refer
+        // to them by, so an id another document already has is replaced
+        for entry in &parts.lists {
+            let id = match first_arg(entry, &["listid"]) {
+                Some(id) => id,
+                None => continue,
+            };
+            let new = if self.list_ids.contains(&id) {
+                self.list_ids
+                    .iter()
+                    .map(|id| id.saturating_add(1))
+                    .max()
+                    .unwrap_or(1)
+            } else {
+                id
+            };
+            self.list_ids.push(new);
+            renumbering.list_ids.insert(id, new);
+        }
+        for entry in &parts.lists {
+            self.list_entries.push(renumbering.tokens(entry));
+        }
+        for entry in &parts.overrides {
+            let number = match first_arg(entry, &["ls"]) {
+                Some(number) => number,
+                None => continue,
+            };
+            let new = if first {
+                number
+            } else {
+                self.overrides
+                    .iter()
+                    .map(|number| number + 1)
+                    .max()
+                    .unwrap_or(1)
+            };
+            self.overrides.push(new);
+            renumbering.lists.insert(number, new);
+            self.override_entries.push(renumbering.tokens(entry));
+        }
+        renumbering
+    }
+
+    fn push(&self, out: &mut Vec<Token>) {
+        push_table(out, "fonttbl", false, &self.font_entries);
+        let colors: Vec<Vec<Token>> = self.colors.iter().map(color_entry).collect();
+        push_table(out, "colortbl", false, &colors);
+        push_table(out, "stylesheet", false, &self.style_entries);
+        push_table(out, "listtable", true, &self.list_entries);
+        push_table(out, "listoverridetable", true, &self.override_entries);
+    }
+}
+
+// The tokens that go between two documents
+fn separator(separator: Separator) -> Vec<Token> {
+    let mut tokens = match separator {
+        Separator::Section => vec![Token::word("sect"), Token::word("sectd")],
+        Separator::Page => vec![Token::word("page")],
+        Separator::Paragraph => vec![Token::word("par")],
+    };
+    tokens.extend(vec![Token::word("pard"), Token::word("plain")]);
+    tokens
+}
+
+/// Merge token streams into one document, as `merge_with` does
+pub fn merge_tokens(docs: &[Vec<Token>], options: &MergeOptions) -> Vec<Token> {
+    let models: Vec<Document> = docs.iter().map(|doc| Document::from_tokens(doc)).collect();
+    // A document without \ansicpg is in Windows-1252
+    let code_page = |doc: &Document| doc.code_page.unwrap_or(1252);
+    let merged_code_page = models.first().map(code_page);
+    // Hex escapes are read in the document's code page, so documents in
+    // other code pages have theirs rewritten as \u escapes
+    let unicode = NormalizeOptions {
+        newlines: false,
+        unicode: true,
+        sort_info: false,
+    };
+    let docs: Vec<Vec<Token>> = docs
+        .iter()
+        .zip(&models)
+        .map(|(tokens, doc)| {
+            if Some(code_page(doc)) == merged_code_page {
+                tokens.clone()
+            } else {
+                normalize(tokens, &unicode)
+            }
+        })
+        .collect();
+
+    let mut tables = Tables::default();
+    let mut bodies = Vec::new();
+    let mut first_parts = None;
+    for (index, (tokens, doc)) in docs.iter().zip(&models).enumerate() {
+        let parts = split(tokens);
+        let renumbering = tables.add(&parts, doc, index == 0);
+        let mut body = renumbering.tokens(&parts.body);
+        // Formatting reset with \plain falls back to the default font, which
+        // for this document may not be the merged document's
+        let default_font = doc
+            .default_font
+            .map(|font| renumbering.fonts.get(&font).copied().unwrap_or(font));
+        let merged_default = models[0].default_font;
+        if let (false, Some(font)) = (index == 0 || default_font == merged_default, default_font) {
+            let font = Token::word_arg("f", i64::from(font));
+            let mut reset = vec![font.clone()];
+            for token in body {
+                let plain = token.is_word("plain");
+                reset.push(token);
+                if plain {
+                    reset.push(font.clone());
+                }
+            }
+            body = reset;
+        }
+        bodies.push(body);
+        if index == 0 {
+            first_parts = Some(parts);
+        }
+    }
+
+    let first = match first_parts {
+        Some(first) => first,
+        None => {
+            return vec![
+                Token::StartGroup,
+                Token::word_arg("rtf", 1),
+                Token::EndGroup,
+            ]
+        }
+    };
+    let mut out = vec![Token::StartGroup];
+    out.extend(first.prologue.iter().cloned());
+    if first.prologue.is_empty() {
+        out.push(Token::word_arg("rtf", 1));
+    }
+    tables.push(&mut out);
+    for group in &first.header {
+        out.extend_from_slice(group);
+    }
+    for (index, body) in bodies.into_iter().enumerate() {
+        if index > 0 {
+            out.extend(separator(options.separator));
+        }
+        out.extend(body);
+    }
+    out.push(Token::EndGroup);
+    out
+}
+
+/// Merge documents into one, with a section break between each, as
+/// `merge_with` does
+pub fn merge(docs: &[&[u8]]) -> Result<Vec<u8>, ParseError> {
+    merge_with(docs, &MergeOptions::default())
+}
+
+/// Merge documents into one
+///
+/// The merged document has the header of the first: its code page, default
+/// font, document formatting and `\info`.  The fonts, colors, styles and
+/// lists of the others are added to its tables, where they aren't already
+/// there, and the references to them in the others' bodies renumbered.
+/// Fonts are the same when they have the same name and character set, and
+/// styles when they have the same name and kind, so a `Normal` style in
+/// each document becomes the first one's.  Lists get new `\listid`s where
+/// another document already used theirs.  Hex escapes in documents with a
+/// different code page from the first, taking a document without
+/// `\ansicpg` to be in Windows-1252, are rewritten as `\uN` escapes.
+///
+/// What the tables of the others hold besides fonts, colors, styles and
+/// lists, such as their revision authors, is left out.
+pub fn merge_with(docs: &[&[u8]], options: &MergeOptions) -> Result<Vec<u8>, ParseError> {
+    let docs = docs
+        .iter()
+        .map(|doc| parse(doc))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tokens = merge_tokens(&docs, options);
+    Ok(write_rtf(&tokens, &WriteOptions::default()).expect("tokens read from documents"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::{Block, ListKind};
+    use text::extract_text;
+    use validate::{validate, Level};
+
+    const FIRST: &[u8] =
+        b"{\\rtf1\\ansi\\ansicpg1252\\deff0{\\fonttbl{\\f0\\froman Times;}{\\f1\\fswiss Arial;}}\
+        {\\colortbl;\\red255\\green0\\blue0;}{\\stylesheet{\\s0 Normal;}{\\s1\\f1 Heading;}}\
+        {\\info{\\title First}}\\pard\\plain\\s1\\f1\\cf1 One\\par}";
+    const SECOND: &[u8] = b"{\\rtf1\\ansi\\ansicpg1252\\deff1{\\fonttbl{\\f0\\fswiss Arial;}{\\f1\\fmodern Courier;}}\
+        {\\colortbl;\\red0\\green0\\blue255;\\red255\\green0\\blue0;}\
+        {\\stylesheet{\\s0 Normal;}{\\s3\\sbasedon0 Quote;}{\\s7\\f0 Heading;}}\
+        {\\info{\\title Second}}\\pard\\plain\\s3\\cf1 Two \\cf2\\f0 three\\par\\plain four\\par}";
+
+    #[test]
+    fn test_merge() {
+        let merged = merge(&[FIRST, SECOND]).unwrap();
+        let tokens = parse(&merged).unwrap();
+        assert_eq!(validate(&tokens, Level::Strict), Vec::new());
+        assert_eq!(extract_text(&tokens), "One\n\nTwo three\nfour\n");
+
+        let doc = Document::from_tokens(&tokens);
+        let fonts: Vec<_> = doc
+            .fonts
+            .iter()
+            .map(|font| (font.index, font.name.as_str()))
+            .collect();
+        assert_eq!(fonts, vec![(0, "Times"), (1, "Arial"), (2, "Courier")]);
+        assert_eq!(doc.colors.len(), 3);
+        let styles: Vec<_> = doc
+            .styles
+            .iter()
+            .map(|style| (style.index, style.name.as_str()))
+            .collect();
+        assert_eq!(styles, vec![(0, "Normal"), (1, "Heading"), (2, "Quote")]);
+        assert_eq!(doc.sections.len(), 2);
+
+        let runs: Vec<_> = doc
+            .blocks
+            .iter()
+            .flat_map(|block| match block {
+                Block::Paragraph(paragraph) => paragraph.runs.clone(),
+                _ => Vec::new(),
+            })
+            .collect();
+        let formats: Vec<_> = runs
+            .iter()
+            .map(|run| (run.text.trim(), run.format.font, run.format.color))
+            .collect();
+        assert_eq!(
+            formats,
+            vec![
+                ("One", Some(1), Some(1)),
+                ("Two", Some(2), Some(2)),
+                ("three", Some(1), Some(1)),
+                ("four", Some(2), None),
+            ]
+        );
+        // Only the first document's header
+        assert!(!String::from_utf8_lossy(&merged).contains("Second"));
+    }
+
+    #[test]
+    fn test_merge_separators_and_code_pages() {
+        let greek = b"{\\rtf1\\ansi\\ansicpg1253 \\'e1\\par}";
+        let options = MergeOptions {
+            separator: Separator::Page,
+        };
+        let merged = merge_with(&[b"{\\rtf1\\ansi \\'e9\\par}", greek], &options).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&merged),
+            "{\\rtf1\\ansi\\'e9\\par\\page\\pard\\plain\\u945\\'e1\\par}"
+        );
+        assert_eq!(
+            extract_text(&parse(&merged).unwrap()),
+            "\u{e9}\n\n\u{3b1}\n"
+        );
+        assert_eq!(merge(&[]).unwrap(), b"{\\rtf1}".to_vec());
+
+        // Without \ansicpg, a document is in Windows-1252
+        let merged = merge_with(&[greek, b"{\\rtf1\\ansi \\'e9\\par}"], &options).unwrap();
+        assert_eq!(
+            extract_text(&parse(&merged).unwrap()),
+            "\u{3b1}\n\n\u{e9}\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_lists() {
+        let list = |nfc: i32, id: i32| {
+            format!(
+                "{{\\rtf1{{\\*\\listtable{{\\list{{\\listlevel\\levelnfc{}}}\\listid{}}}}}\
+                 {{\\*\\listoverridetable{{\\listoverride\\listid{}\\listoverridecount0\\ls1}}}}\
+                 \\pard\\ls1 Item\\par}}",
+                nfc, id, id
+            )
+        };
+        let bullets = list(23, 1);
+        let numbers = list(0, 1);
+        let others = list(0, 2);
+        let merged = merge(&[bullets.as_bytes(), numbers.as_bytes(), others.as_bytes()]).unwrap();
+        let doc = Document::parse(&merged).unwrap();
+        let kinds: Vec<_> = doc
+            .paragraphs()
+            .iter()
+            .filter(|paragraph| paragraph.text() == "Item")
+            .filter_map(|paragraph| paragraph.list.map(|item| item.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![ListKind::Bullet, ListKind::Numbered, ListKind::Numbered]
+        );
+        let merged = String::from_utf8_lossy(&merged);
+        assert!(
+            merged.contains("{\\listoverride\\listid3\\listoverridecount0\\ls3}"),
+            "{}",
+            merged
+        );
+    }
+}
@@ -0,0 +1,633 @@
+// Document metadata
+//
+// Information about a document that isn't part of its content, gathered from
+// the header and from metadata destinations.
+//
+
+use minify::RSID_WORDS;
+use sanitize::field_type;
+use text::decode_cp1252;
+use tokenizer::{child_group, group_contents, group_destination, group_end, narrow_arg, Token};
+
+/// The contents of a `{\*\generator ...}` destination, split into the program
+/// name and version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generator {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl Generator {
+    /// Split generator text like `Microsoft Word 11.0.0000;` or
+    /// `LibreOffice/7.3.7.2$Linux_X86_64 LibreOffice_project/...` into a name
+    /// and version
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim().trim_end_matches(';').trim();
+
+        if let Some(slash) = text.find('/') {
+            let name = text[..slash].trim().to_string();
+            let version = text[slash + 1..]
+                .split(|c: char| c.is_whitespace() || c == '$')
+                .next()
+                .filter(|v| !v.is_empty())
+                .map(String::from);
+            return Generator { name, version };
+        }
+
+        match text.rfind(char::is_whitespace) {
+            Some(space) if text[space + 1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                Generator {
+                    name: text[..space].trim().to_string(),
+                    version: Some(text[space + 1..].to_string()),
+                }
+            }
+            _ => Generator {
+                name: text.to_string(),
+                version: None,
+            },
+        }
+    }
+}
+
+/// The application family that most likely produced a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Application {
+    Word,
+    Outlook,
+    WordPad,
+    /// The RichEdit control, used by WordPad, Outlook and many other programs,
+    /// when the specific program couldn't be narrowed down
+    RichEdit,
+    LibreOffice,
+    OpenOffice,
+    /// The macOS Cocoa text system (TextEdit)
+    TextEdit,
+    Unknown,
+}
+
+/// The format a document was converted from, for documents that encapsulate
+/// another format so it can be recovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encapsulation {
+    /// `\fromhtml1`, HTML mail stored as RTF
+    Html,
+    /// `\fromtext`, plain text mail stored as RTF
+    Text,
+}
+
+/// Everything a document reveals about the program that wrote it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducerInfo {
+    pub generator: Option<Generator>,
+    pub application: Application,
+    /// The `\cocoartfN` version, for documents written by the Cocoa text system
+    pub cocoa_version: Option<i32>,
+    /// The `\rtfN` version of the header
+    pub rtf_version: Option<i32>,
+    /// `\fbidis` - the document uses bidirectional fonts
+    pub bidi: bool,
+    pub encapsulation: Option<Encapsulation>,
+}
+
+// Fingerprint signals that identify a producer when there's no generator
+// destination, or refine an ambiguous one
+#[derive(Debug, Default)]
+struct Signals {
+    cocoa_version: Option<i32>,
+    rtf_version: Option<i32>,
+    bidi: bool,
+    encapsulation: Option<Encapsulation>,
+    nouicompat: bool,
+    word_tables: bool,
+}
+
+fn group_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Token::StartGroup => depth += 1,
+            Token::EndGroup if depth == 0 => break,
+            Token::EndGroup => depth -= 1,
+            Token::Text(data) => text.extend(data.iter().map(|b| decode_cp1252(*b))),
+            Token::HexByte(byte) => text.push(decode_cp1252(*byte)),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn classify(generator: Option<&Generator>, signals: &Signals) -> Application {
+    if signals.cocoa_version.is_some() {
+        return Application::TextEdit;
+    }
+    if let Some(generator) = generator {
+        let name = generator.name.to_lowercase();
+        if name.contains("libreoffice") {
+            return Application::LibreOffice;
+        }
+        if name.contains("openoffice") || name.contains("staroffice") {
+            return Application::OpenOffice;
+        }
+        if name.contains("microsoft word") {
+            return Application::Word;
+        }
+        if name.contains("outlook") {
+            return Application::Outlook;
+        }
+        if name.contains("riched") || name.contains("msftedit") {
+            return if signals.encapsulation.is_some() {
+                Application::Outlook
+            } else if signals.nouicompat {
+                Application::WordPad
+            } else {
+                Application::RichEdit
+            };
+        }
+        return Application::Unknown;
+    }
+    if signals.encapsulation.is_some() {
+        Application::Outlook
+    } else if signals.word_tables {
+        Application::Word
+    } else {
+        Application::Unknown
+    }
+}
+
+/// The Office theme parts of a document, which Word 2007 and later write as
+/// hex-encoded binary
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ThemeData {
+    /// `{\*\themedata}`, the document's theme as a zip package
+    pub theme: Option<Vec<u8>>,
+    /// `{\*\colorschememapping}`, an XML part mapping theme colors to the
+    /// document's colors
+    pub color_scheme_mapping: Option<Vec<u8>>,
+}
+
+impl ThemeData {
+    /// Tokens for the theme destinations, to write the parts back into a
+    /// document unchanged
+    pub fn to_tokens(&self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let parts = [
+            ("themedata", &self.theme),
+            ("colorschememapping", &self.color_scheme_mapping),
+        ];
+        for (name, data) in parts.iter() {
+            let data = match data {
+                Some(data) => data,
+                None => continue,
+            };
+            let hex: String = data.iter().map(|byte| format!("{:02x}", byte)).collect();
+            tokens.extend(vec![
+                Token::StartGroup,
+                Token::ControlSymbol('*'),
+                Token::ControlWord {
+                    name: (*name).into(),
+                    arg: None,
+                },
+                Token::Text(hex.into_bytes()),
+                Token::EndGroup,
+            ]);
+        }
+        tokens
+    }
+}
+
+// Binary data written as hex digits, which may be split between text tokens
+#[derive(Debug, Default)]
+pub(crate) struct HexData {
+    pub(crate) data: Vec<u8>,
+    high: Option<u8>,
+}
+
+impl HexData {
+    pub(crate) fn push(&mut self, text: &[u8]) {
+        let digits = text.iter().filter_map(|b| (*b as char).to_digit(16));
+        for value in digits.map(|value| value as u8) {
+            match self.high.take() {
+                Some(high) => self.data.push(high << 4 | value),
+                None => self.high = Some(value),
+            }
+        }
+    }
+}
+
+// The binary data of a destination written as hex digits, from the tokens
+// after its control word
+fn group_hex(tokens: &[Token]) -> Vec<u8> {
+    let mut hex = HexData::default();
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Token::StartGroup => depth += 1,
+            Token::EndGroup if depth == 0 => break,
+            Token::EndGroup => depth -= 1,
+            Token::Text(text) if depth == 0 => hex.push(text),
+            Token::ControlBin(bytes) if depth == 0 => hex.data.extend_from_slice(bytes),
+            _ => {}
+        }
+    }
+    hex.data
+}
+
+/// Read the theme parts of a document, keeping their data as opaque bytes
+pub fn theme_data(tokens: &[Token]) -> ThemeData {
+    let mut theme = ThemeData::default();
+    for (index, token) in tokens.iter().enumerate() {
+        if let Token::ControlWord { name, .. } = token {
+            let part = match name.as_str() {
+                "themedata" => &mut theme.theme,
+                "colorschememapping" => &mut theme.color_scheme_mapping,
+                _ => continue,
+            };
+            if part.is_none() {
+                *part = Some(group_hex(&tokens[index + 1..]));
+            }
+        }
+    }
+    theme
+}
+
+/// A document's protection settings, which limit how it may be edited
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Protection {
+    /// `\formprot` - only form fields may be filled in
+    pub forms: bool,
+    /// `\revprot` - all changes are tracked as revisions
+    pub revisions: bool,
+    /// `\annotprot` - only comments may be added
+    pub annotations: bool,
+    /// `\readprot` - the document is read-only
+    pub read_only: bool,
+    /// `\readonlyrecommended` - readers should offer to open the document
+    /// read-only
+    pub read_only_recommended: bool,
+    /// `\protlevelN` - the kind of protection Word 2002 and later apply: 0
+    /// for tracked changes, 1 for comments, 2 for forms and 3 for read-only
+    pub level: Option<i32>,
+    /// `\enforceprotN` - whether the protection is turned on, or only
+    /// configured
+    pub enforced: Option<bool>,
+    /// `{\*\passwordhash}` - the hash of the password that lifts the
+    /// protection, as Word stores it
+    pub password_hash: Option<Vec<u8>>,
+}
+
+impl Protection {
+    /// Apply a protection control word, returning whether it was one
+    pub fn apply(&mut self, name: &str, arg: Option<i32>) -> bool {
+        let on = arg != Some(0);
+        match name {
+            "formprot" => self.forms = on,
+            "revprot" => self.revisions = on,
+            "annotprot" => self.annotations = on,
+            "readprot" => self.read_only = on,
+            "readonlyrecommended" => self.read_only_recommended = on,
+            "protlevel" => self.level = arg,
+            "enforceprot" => self.enforced = Some(on),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Whether the document is protected against some kind of editing
+    pub fn is_protected(&self) -> bool {
+        if self.enforced == Some(false) {
+            return false;
+        }
+        self.forms
+            || self.revisions
+            || self.annotations
+            || self.read_only
+            || self.enforced == Some(true)
+    }
+}
+
+/// Read a document's protection settings
+pub fn protection(tokens: &[Token]) -> Protection {
+    let mut protection = Protection::default();
+    for (index, token) in tokens.iter().enumerate() {
+        if let Token::ControlWord { name, arg } = token {
+            if name == "passwordhash" {
+                protection.password_hash = Some(group_hex(&tokens[index + 1..]));
+            } else {
+                protection.apply(name, narrow_arg(*arg));
+            }
+        }
+    }
+    protection
+}
+
+/// Identify the program that produced a document from its generator
+/// destination and other characteristic control words
+pub fn producer_info(tokens: &[Token]) -> ProducerInfo {
+    let mut generator = None;
+    let mut signals = Signals::default();
+
+    for (index, token) in tokens.iter().enumerate() {
+        if let Token::ControlWord { name, arg } = token {
+            match name.as_str() {
+                "generator" if generator.is_none() => {
+                    generator = Some(Generator::from_text(&group_text(&tokens[index + 1..])));
+                }
+                "cocoartf" => signals.cocoa_version = narrow_arg(*arg),
+                "rtf" if signals.rtf_version.is_none() => signals.rtf_version = narrow_arg(*arg),
+                "fbidis" => signals.bidi = true,
+                "fromhtml" if *arg != Some(0) => signals.encapsulation = Some(Encapsulation::Html),
+                "fromtext" => signals.encapsulation = Some(Encapsulation::Text),
+                "nouicompat" => signals.nouicompat = true,
+                "rsidtbl" | "xmlnstbl" | "latentstyles" => signals.word_tables = true,
+                _ => {}
+            }
+        }
+    }
+
+    ProducerInfo {
+        application: classify(generator.as_ref(), &signals),
+        generator,
+        cocoa_version: signals.cocoa_version,
+        rtf_version: signals.rtf_version,
+        bidi: signals.bidi,
+        encapsulation: signals.encapsulation,
+    }
+}
+
+/// Destinations that `strip_metadata` removes
+const METADATA_DESTINATIONS: &[&str] = &[
+    "info",
+    "generator",
+    "userprops",
+    "docvar",
+    "template",
+    "rsidtbl",
+    "protusertbl",
+    // Comments, and the ranges of text they're on
+    "annotation",
+    "atnid",
+    "atnauthor",
+    "atntime",
+    "atnicn",
+    "atnref",
+    "atnparent",
+    "atrfstart",
+    "atrfend",
+];
+
+/// Control words naming the author of a tracked change, by their index in
+/// the revision table
+const REVISION_AUTHOR_WORDS: &[&str] = &["revauth", "revauthdel", "crauth", "pnrauth"];
+
+/// Control words giving the time of a tracked change
+const REVISION_TIME_WORDS: &[&str] = &["revdttm", "revdttmdel", "crdate", "pnrdate"];
+
+/// Fields whose instructions name the files they read
+const FILE_FIELDS: &[&str] = &["INCLUDEPICTURE", "INCLUDETEXT", "INCLUDE", "IMPORT", "LINK"];
+
+// Write the contents of a group's child destination in the group's place
+fn replace_with_child(
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+    name: &str,
+    out: &mut Vec<Token>,
+) {
+    if let Some((_, word, end)) = child_group(tokens, start, end, name) {
+        out.push(Token::StartGroup);
+        strip_into(group_contents(tokens, word, end), out);
+        out.push(Token::EndGroup);
+    }
+}
+
+fn strip_into(tokens: &[Token], out: &mut Vec<Token>) {
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        index += 1;
+        let start = index - 1;
+        match token {
+            Token::ControlWord { name, arg } => {
+                let name = name.as_str();
+                if REVISION_AUTHOR_WORDS.contains(&name) {
+                    out.push(Token::word_arg(name, 0));
+                } else if !(name == "chatn"
+                    || RSID_WORDS.contains(&name)
+                    || REVISION_TIME_WORDS.contains(&name))
+                {
+                    out.push(Token::ControlWord {
+                        name: name.into(),
+                        arg: *arg,
+                    });
+                }
+                continue;
+            }
+            Token::StartGroup => {}
+            _ => {
+                out.push(token.clone());
+                continue;
+            }
+        }
+        let end = group_end(tokens, start);
+        match group_destination(tokens, start) {
+            Some((name, _, _)) if METADATA_DESTINATIONS.contains(&name) => {}
+            Some(("revtbl", starred, _)) => {
+                out.push(Token::StartGroup);
+                if starred {
+                    out.push(Token::ControlSymbol('*'));
+                }
+                out.extend(vec![
+                    Token::word("revtbl"),
+                    Token::StartGroup,
+                    Token::text("Unknown;"),
+                    Token::EndGroup,
+                    Token::EndGroup,
+                ]);
+            }
+            Some(("field", _, _))
+                if child_group(tokens, start, end, "fldinst")
+                    .map(|(_, word, end)| field_type(group_contents(tokens, word, end)))
+                    .is_some_and(|kind| FILE_FIELDS.contains(&kind.as_str())) =>
+            {
+                replace_with_child(tokens, start, end, "fldrslt", out)
+            }
+            Some(("object", _, _))
+                if tokens[start..end]
+                    .iter()
+                    .any(|token| token.is_word("objlink") || token.is_word("objautlink")) =>
+            {
+                replace_with_child(tokens, start, end, "result", out)
+            }
+            _ => {
+                out.push(Token::StartGroup);
+                continue;
+            }
+        }
+        index = end;
+    }
+}
+
+/// Remove what a document records about who wrote it, and where
+///
+/// - the `{\info}` destination, with the title, author, company, dates and
+///   the rest, and the `{\*\generator}`, `{\*\userprops}`,
+///   `{\*\docvar}`, `{\*\template}` and `{\*\protusertbl}`
+///   destinations
+/// - the `{\*\rsidtbl}` of editing sessions, and the `\rsidN` words that
+///   refer to it
+/// - comments, along with their authors and initials
+/// - the authors and times of tracked changes: the revision table is left
+///   with just `Unknown`, which every change is then attributed to
+/// - fields that read other files (`INCLUDEPICTURE`, `INCLUDETEXT`,
+///   `INCLUDE`, `IMPORT` and `LINK`), and linked objects, whose paths say
+///   where the document was written; each is replaced by the result cached
+///   alongside it, so the document still looks the same
+///
+/// The tracked changes themselves are kept, as they're part of the content.
+pub fn strip_metadata(tokens: &[Token]) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    strip_into(tokens, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::extract_text;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    fn producer(rtf: &[u8]) -> ProducerInfo {
+        producer_info(&parse(rtf).unwrap())
+    }
+
+    #[test]
+    fn test_generator_from_text() {
+        assert_eq!(
+            Generator::from_text("Microsoft Word 11.0.0000;"),
+            Generator {
+                name: "Microsoft Word".to_string(),
+                version: Some("11.0.0000".to_string()),
+            }
+        );
+        assert_eq!(
+            Generator::from_text("LibreOffice/7.3.7.2$Linux_X86_64 LibreOffice_project/30"),
+            Generator {
+                name: "LibreOffice".to_string(),
+                version: Some("7.3.7.2".to_string()),
+            }
+        );
+        assert_eq!(
+            Generator::from_text("AbiWord"),
+            Generator {
+                name: "AbiWord".to_string(),
+                version: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_producer_info() {
+        let info = producer(b"{\\rtf1{\\*\\generator Riched20 10.0.19041;}\\viewkind4 Hi}");
+        assert_eq!(info.application, Application::RichEdit);
+        assert_eq!(
+            info.generator,
+            Some(Generator {
+                name: "Riched20".to_string(),
+                version: Some("10.0.19041".to_string()),
+            })
+        );
+
+        let info = producer(b"{\\rtf1\\fromhtml1{\\*\\generator Msftedit 5.41.21.2510;}}");
+        assert_eq!(info.application, Application::Outlook);
+        assert_eq!(info.encapsulation, Some(Encapsulation::Html));
+        assert_eq!(info.rtf_version, Some(1));
+        assert!(!info.bidi);
+
+        let info = producer(b"{\\rtf1\\ansi\\fbidis\\fromtext Hi}");
+        assert_eq!(info.application, Application::Outlook);
+        assert_eq!(info.encapsulation, Some(Encapsulation::Text));
+        assert!(info.bidi);
+
+        let info = producer(b"{\\rtf1\\ansi\\ansicpg1252\\cocoartf2639 Hi}");
+        assert_eq!(info.application, Application::TextEdit);
+        assert_eq!(info.cocoa_version, Some(2639));
+        assert_eq!(info.generator, None);
+
+        let info = producer(b"{\\rtf1{\\*\\rsidtbl \\rsid1}}");
+        assert_eq!(info.application, Application::Word);
+    }
+
+    #[test]
+    fn test_theme_data() {
+        let tokens =
+            parse(b"{\\rtf1{\\*\\themedata 504b03\r\n0414}{\\*\\colorschememapping 3c3f786d6c}Hi}")
+                .unwrap();
+        let theme = theme_data(&tokens);
+        assert_eq!(theme.theme, Some(b"PK\x03\x04\x14".to_vec()));
+        assert_eq!(theme.color_scheme_mapping, Some(b"<?xml".to_vec()));
+        assert_eq!(theme_data(&theme.to_tokens()), theme);
+
+        let theme = theme_data(&parse(b"{\\rtf1 Hi}").unwrap());
+        assert_eq!(theme, ThemeData::default());
+        assert!(theme.to_tokens().is_empty());
+    }
+
+    #[test]
+    fn test_protection() {
+        let tokens = parse(
+            b"{\\rtf1\\formprot\\protlevel2\\enforceprot1\\readonlyrecommended{\\*\\passwordhash 0a0B}\\pard Hi}",
+        )
+        .unwrap();
+        let protection = protection(&tokens);
+        assert_eq!(
+            protection,
+            Protection {
+                forms: true,
+                read_only_recommended: true,
+                level: Some(2),
+                enforced: Some(true),
+                password_hash: Some(vec![0x0a, 0x0b]),
+                ..Protection::default()
+            }
+        );
+        assert!(protection.is_protected());
+
+        let tokens = parse(b"{\\rtf1\\revprot\\enforceprot0 Hi}").unwrap();
+        assert!(!super::protection(&tokens).is_protected());
+        let tokens = parse(b"{\\rtf1\\annotprot Hi}").unwrap();
+        assert!(super::protection(&tokens).is_protected());
+        let tokens = parse(b"{\\rtf1 Hi}").unwrap();
+        assert_eq!(super::protection(&tokens), Protection::default());
+    }
+
+    #[test]
+    fn test_sample_producer() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let info = producer(test_bytes);
+        assert_eq!(info.generator, None);
+        assert_eq!(info.application, Application::Unknown);
+    }
+
+    #[test]
+    fn test_strip_metadata() {
+        let rtf = b"{\\rtf1{\\info{\\author Jane Roe}{\\company Acme}}{\\*\\generator Word;}\
+            {\\*\\revtbl {Unknown;}{Jane Roe;}}{\\*\\rsidtbl \\rsid42}\\pard\\pararsid42 \
+            {\\revised\\revauth1\\revdttm1234 new}{\\*\\atnid JR}{\\*\\atnauthor Jane Roe}\\chatn \
+            {\\*\\annotation note} \
+            {\\field{\\*\\fldinst INCLUDEPICTURE \"C:\\\\Users\\\\jroe\\\\a.png\" \\\\d}{\\fldrslt{\\pict 00}}}\
+            {\\object\\objautlink{\\*\\objdata 0102}{\\result{\\pict 01}}}\
+            {\\field{\\*\\fldinst HYPERLINK \"http://example.com\"}{\\fldrslt link}}}";
+        let tokens = parse(rtf).unwrap();
+        let stripped = strip_metadata(&tokens);
+        assert_eq!(
+            String::from_utf8(write_rtf(&stripped, &WriteOptions::default()).unwrap()).unwrap(),
+            "{\\rtf1{\\*\\revtbl{Unknown;}}\\pard{\\revised\\revauth0 new} \
+             {{\\pict 00}}{{\\pict 01}}{\\field{\\*\\fldinst HYPERLINK \"http://example.com\"}{\\fldrslt link}}}"
+        );
+        assert_eq!(extract_text(&stripped), extract_text(&tokens));
+    }
+}
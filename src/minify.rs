@@ -0,0 +1,248 @@
+// RTF minification
+//
+// Shrinks a token stream without changing the document it describes, for
+// places where size matters more than readability, like RTF bodies embedded
+// in TNEF mail attachments.
+//
+
+use std;
+use std::collections::HashMap;
+
+use text::IGNORED_DESTINATIONS;
+use tokenizer::{group_destination, group_end, narrow_arg, Token};
+
+/// Destinations that only hold metadata about how the document was written
+const REMOVABLE_DESTINATIONS: &[&str] = &["generator", "rsidtbl", "xmlnstbl"];
+
+/// Control words that refer to the revision save ids of `\rsidtbl`
+pub(crate) const RSID_WORDS: &[&str] = &[
+    "rsid", "rsidroot", "insrsid", "delrsid", "charrsid", "pararsid", "sectrsid", "tblrsid",
+    "styrsid",
+];
+
+const TOGGLES: &[&str] = &[
+    "b", "i", "strike", "caps", "scaps", "v", "outl", "shad", "ul",
+];
+
+// The on/off character formatting property a control word sets, if any
+fn toggle(name: &str, arg: Option<i32>) -> Option<(&'static str, bool)> {
+    let on = arg != Some(0);
+    let property = match name {
+        "b" => "b",
+        "i" => "i",
+        "strike" => "strike",
+        "caps" => "caps",
+        "scaps" => "scaps",
+        "v" => "v",
+        "outl" => "outl",
+        "shad" => "shad",
+        "ul" => "ul",
+        "ulnone" => return Some(("ul", false)),
+        _ => return None,
+    };
+    Some((property, on))
+}
+
+// A toggle written since the last text, which is redundant if another toggle
+// of the same property follows before any more text
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    index: usize,
+    previous: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    // Known state of each toggle property; missing properties are unknown
+    toggles: HashMap<&'static str, bool>,
+    pending: HashMap<&'static str, Pending>,
+}
+
+struct Minifier {
+    out: Vec<Option<Token>>,
+    stack: Vec<Group>,
+    group: Group,
+    // Set after a \u, until its fallback text: control words count as
+    // fallback characters, so none can be dropped
+    fallback: bool,
+}
+
+impl Minifier {
+    fn push(&mut self, token: &Token) {
+        if let Token::Text(text) = token {
+            // Merge with the last token written, if it was text too
+            if let Some(Some(Token::Text(last))) = self.out.last_mut() {
+                last.extend_from_slice(text);
+                return;
+            }
+        }
+        self.out.push(Some(token.clone()));
+    }
+
+    fn start_group(&mut self, destination: bool) {
+        let mut group = Group::default();
+        // Destinations don't inherit the formatting of the text around them
+        if !destination {
+            group.toggles = self.group.toggles.clone();
+        }
+        self.stack.push(std::mem::replace(&mut self.group, group));
+        self.push(&Token::StartGroup);
+    }
+
+    fn end_group(&mut self) {
+        // An empty group does nothing
+        if let Some(Some(Token::StartGroup)) = self.out.last() {
+            self.out.pop();
+        } else {
+            self.out.push(Some(Token::EndGroup));
+        }
+        if let Some(group) = self.stack.pop() {
+            self.group = group;
+        }
+    }
+
+    fn word(&mut self, token: &Token, name: &str, arg: Option<i32>) {
+        if self.fallback {
+            self.push(token);
+            return;
+        }
+        if RSID_WORDS.contains(&name) {
+            return;
+        }
+        match name {
+            "u" => self.fallback = true,
+            "plain" => {
+                self.group.toggles = TOGGLES.iter().map(|property| (*property, false)).collect();
+                self.group.pending.clear();
+            }
+            // A character style sets formatting this can't see
+            "cs" => self.group.toggles.clear(),
+            _ => {}
+        }
+        let (property, on) = match toggle(name, arg) {
+            Some(toggle) => toggle,
+            None => return self.push(token),
+        };
+        let mut previous = self.group.toggles.get(property).cloned();
+        if let Some(pending) = self.group.pending.remove(property) {
+            // The earlier toggle didn't apply to any text
+            self.out[pending.index] = None;
+            previous = pending.previous;
+        }
+        self.group.toggles.insert(property, on);
+        if previous == Some(on) {
+            return;
+        }
+        self.group.pending.insert(
+            property,
+            Pending {
+                index: self.out.len(),
+                previous,
+            },
+        );
+        self.out.push(Some(token.clone()));
+    }
+
+    fn text(&mut self, token: &Token) {
+        self.fallback = false;
+        self.group.pending.clear();
+        self.push(token);
+    }
+}
+
+/// Shrink a token stream without changing the document it describes
+///
+/// - line breaks are removed, and the text runs they separated merged
+/// - metadata about the writing application is removed: the
+///   `{\*\generator}`, `{\*\rsidtbl}` and `{\*\xmlnstbl}` destinations, and
+///   the `\rsidN` revision save ids that refer to the rsid table
+/// - formatting toggles that don't change anything are dropped, such as a
+///   `\b` in text that's already bold, or a `\b` immediately undone by a
+///   `\b0`
+/// - empty groups are removed
+pub fn minify(tokens: &[Token]) -> Vec<Token> {
+    let mut minifier = Minifier {
+        out: Vec::with_capacity(tokens.len()),
+        stack: Vec::new(),
+        group: Group::default(),
+        fallback: false,
+    };
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        match token {
+            Token::Newline => {}
+            Token::StartGroup => match group_destination(tokens, index) {
+                Some((name, _, _)) if REMOVABLE_DESTINATIONS.contains(&name) => {
+                    index = group_end(tokens, index);
+                    continue;
+                }
+                Some((name, starred, _)) => {
+                    minifier.start_group(starred || IGNORED_DESTINATIONS.contains(&name))
+                }
+                None => minifier.start_group(false),
+            },
+            Token::EndGroup => minifier.end_group(),
+            Token::ControlWord { name, arg } => minifier.word(token, name, narrow_arg(*arg)),
+            Token::Unicode { codepoint } => minifier.word(token, "u", Some(*codepoint)),
+            Token::Text(_) | Token::HexByte(_) => minifier.text(token),
+            Token::ControlSymbol(_) | Token::ControlBin(_) => {
+                minifier.fallback = false;
+                minifier.group.pending.clear();
+                minifier.push(token);
+            }
+        }
+        index += 1;
+    }
+    minifier.out.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::Document;
+    use text::extract_text;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    fn minified(rtf: &[u8]) -> String {
+        let tokens = minify(&parse(rtf).unwrap());
+        String::from_utf8(write_rtf(&tokens, &WriteOptions::default()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_removes_metadata() {
+        assert_eq!(
+            minified(b"{\\rtf1{\\*\\generator Foo 1.0;}{\\*\\rsidtbl \\rsid123}\r\n\\pard\\pararsid123 Hi\r\n there\\par{}}"),
+            "{\\rtf1\\pard Hi there\\par}"
+        );
+    }
+
+    #[test]
+    fn test_drops_redundant_toggles() {
+        assert_eq!(
+            minified(br"{\rtf1\b a \b b\b0\i\b c{\b d\b0 e}\plain\b0 f\ulnone\ul g}"),
+            r"{\rtf1\b a b\i c{d\b0 e}\plain f\ul g}"
+        );
+        // Fallback characters after \u are left alone
+        assert_eq!(minified(br"{\u233\b\b0 x}"), r"{\u233\b\b0 x}");
+        // as are formatting definitions in destinations
+        assert_eq!(
+            minified(br"{\b{\stylesheet{\s1\b Heading;}}x}"),
+            r"{\b{\stylesheet{\s1\b Heading;}}x}"
+        );
+    }
+
+    #[test]
+    fn test_sample_doc_text_unchanged() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let minified = minify(&tokens);
+        assert!(minified.len() < tokens.len());
+        assert_eq!(extract_text(&minified), extract_text(&tokens));
+        assert_eq!(
+            Document::from_tokens(&minified),
+            Document::from_tokens(&tokens)
+        );
+    }
+}
@@ -0,0 +1,479 @@
+// Control word names
+//
+// Documents repeat a small vocabulary of control words millions of times, so
+// names are stored as a `Name`, which refers to a static copy of the most
+// common ones instead of allocating a string for each occurrence.  Names
+// outside that vocabulary are allocated as before.
+//
+
+use std;
+use std::borrow::{Borrow, Cow};
+use std::ops::Deref;
+
+// Common control words, in byte order for binary searching
+const KNOWN: &[&str] = &[
+    "ab",
+    "adeflang",
+    "adjustright",
+    "aenddoc",
+    "af",
+    "afs",
+    "ai",
+    "alang",
+    "annotation",
+    "ansi",
+    "ansicpg",
+    "atnauthor",
+    "atndate",
+    "atnid",
+    "atnref",
+    "atrfend",
+    "atrfstart",
+    "aul",
+    "author",
+    "b",
+    "bin",
+    "bkmkend",
+    "bkmkstart",
+    "bliptag",
+    "blipuid",
+    "blipupi",
+    "blue",
+    "brdrs",
+    "brdrw",
+    "bullet",
+    "caps",
+    "cb",
+    "cell",
+    "cellx",
+    "cf",
+    "cgrid",
+    "charrsid",
+    "charscalex",
+    "chcbpat",
+    "chdate",
+    "chftn",
+    "chpgn",
+    "chtime",
+    "clbrdrb",
+    "clbrdrl",
+    "clbrdrr",
+    "clbrdrt",
+    "clcbpat",
+    "clftsWidth",
+    "clmgf",
+    "clmrg",
+    "clpadb",
+    "clpadl",
+    "clpadr",
+    "clpadt",
+    "clshdng",
+    "cltxlrtb",
+    "clvertalt",
+    "clvmgf",
+    "clvmrg",
+    "clwWidth",
+    "colorschememapping",
+    "colortbl",
+    "cols",
+    "colsx",
+    "company",
+    "creatim",
+    "cs",
+    "datastore",
+    "dbch",
+    "deff",
+    "deflang",
+    "deflangfe",
+    "deleted",
+    "dn",
+    "dpx",
+    "dpy",
+    "dy",
+    "edmins",
+    "emdash",
+    "emspace",
+    "endash",
+    "endnhere",
+    "enspace",
+    "expnd",
+    "f",
+    "fbias",
+    "fcharset",
+    "fcs",
+    "fet",
+    "fi",
+    "field",
+    "fldedit",
+    "fldinst",
+    "fldlock",
+    "fldpriv",
+    "fldrslt",
+    "fmodern",
+    "fnil",
+    "fonttbl",
+    "footer",
+    "footnote",
+    "formshade",
+    "fprq",
+    "froman",
+    "fromhtml",
+    "fromtext",
+    "fs",
+    "fswiss",
+    "ftnbj",
+    "generator",
+    "green",
+    "header",
+    "hich",
+    "highlight",
+    "hr",
+    "htmlrtf",
+    "htmltag",
+    "hyphcaps",
+    "i",
+    "ilvl",
+    "info",
+    "insrsid",
+    "intbl",
+    "itap",
+    "jclisttab",
+    "keepn",
+    "kerning",
+    "landscape",
+    "lang",
+    "lastrow",
+    "latentstyles",
+    "ldblquote",
+    "levelfollow",
+    "levelindent",
+    "leveljc",
+    "levelnfc",
+    "levelnumbers",
+    "levelspace",
+    "levelstartat",
+    "leveltext",
+    "li",
+    "lin",
+    "line",
+    "linex",
+    "list",
+    "listid",
+    "listlevel",
+    "listname",
+    "listoverride",
+    "listoverridecount",
+    "listoverridetable",
+    "listsimple",
+    "listtable",
+    "listtemplateid",
+    "lnkd",
+    "loch",
+    "lquote",
+    "ls",
+    "lsdlockedexcept",
+    "ltrch",
+    "ltrmark",
+    "ltrpar",
+    "ltrrow",
+    "ltrsect",
+    "mac",
+    "margb",
+    "margl",
+    "margr",
+    "margt",
+    "mhtmltag",
+    "min",
+    "mo",
+    "nestcell",
+    "nestrow",
+    "nesttableprops",
+    "nofchars",
+    "nofcharsws",
+    "nofpages",
+    "nofwords",
+    "nonesttables",
+    "nosupersub",
+    "nowidctlpar",
+    "objclass",
+    "objdata",
+    "object",
+    "objemb",
+    "objh",
+    "objw",
+    "operator",
+    "outlinelevel",
+    "page",
+    "panose",
+    "paperh",
+    "paperw",
+    "par",
+    "pararsid",
+    "pard",
+    "pc",
+    "pca",
+    "piccropb",
+    "piccropl",
+    "piccropr",
+    "piccropt",
+    "pich",
+    "pichgoal",
+    "picprop",
+    "picscalex",
+    "picscaley",
+    "pict",
+    "picw",
+    "picwgoal",
+    "plain",
+    "pn",
+    "pnb",
+    "pndec",
+    "pnf",
+    "pnfs",
+    "pnhang",
+    "pni",
+    "pnindent",
+    "pnlcltr",
+    "pnlcrm",
+    "pnlvl",
+    "pnlvlblt",
+    "pnlvlbody",
+    "pnlvlcont",
+    "pnqc",
+    "pnql",
+    "pnrnot",
+    "pnseclvl",
+    "pnstart",
+    "pntext",
+    "pntxta",
+    "pntxtb",
+    "pnucltr",
+    "pnucrm",
+    "qc",
+    "qd",
+    "qj",
+    "ql",
+    "qr",
+    "rdblquote",
+    "red",
+    "result",
+    "revauth",
+    "revauthdel",
+    "revdttm",
+    "revdttmdel",
+    "revised",
+    "revtbl",
+    "revtim",
+    "ri",
+    "rin",
+    "row",
+    "rquote",
+    "rsid",
+    "rsidtbl",
+    "rtf",
+    "rtlch",
+    "rtlmark",
+    "rtlpar",
+    "rtlrow",
+    "rtlsect",
+    "s",
+    "sa",
+    "sb",
+    "sbasedon",
+    "sbkcol",
+    "sbknone",
+    "sbkpage",
+    "scaps",
+    "sect",
+    "sectd",
+    "sectdefaultcl",
+    "sectrsid",
+    "shp",
+    "shpbottom",
+    "shpbxcolumn",
+    "shpbypara",
+    "shpfblwtxt",
+    "shpfhdr",
+    "shpinst",
+    "shpleft",
+    "shplid",
+    "shpright",
+    "shprslt",
+    "shptop",
+    "shptxt",
+    "shpwr",
+    "shpwrk",
+    "shpz",
+    "sl",
+    "slmult",
+    "sn",
+    "snext",
+    "sp",
+    "strike",
+    "stylesheet",
+    "sub",
+    "super",
+    "sv",
+    "tab",
+    "themedata",
+    "title",
+    "trautofit",
+    "trbrdrb",
+    "trbrdrh",
+    "trbrdrl",
+    "trbrdrr",
+    "trbrdrt",
+    "trbrdrv",
+    "trftsWidth",
+    "trgaph",
+    "trleft",
+    "trowd",
+    "trpaddfl",
+    "trpaddfr",
+    "trpaddl",
+    "trpaddr",
+    "trrh",
+    "trwWidth",
+    "tx",
+    "u",
+    "uc",
+    "ul",
+    "uld",
+    "uldb",
+    "ulnone",
+    "ulw",
+    "up",
+    "v",
+    "vern",
+    "version",
+    "viewkind",
+    "viewscale",
+    "widctlpar",
+    "widowctrl",
+    "wmetafile",
+    "xmlnstbl",
+    "yr",
+    "zwj",
+    "zwnj",
+];
+
+/// The name of a control word
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Name(Cow<'static, str>);
+
+impl Name {
+    /// Make a name, only allocating if it's outside the common vocabulary
+    pub fn new(name: &str) -> Self {
+        match KNOWN.binary_search(&name) {
+            Ok(index) => Name(Cow::Borrowed(KNOWN[index])),
+            Err(_) => Name(Cow::Owned(name.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the name refers to the common vocabulary, rather than its own
+    /// allocation
+    pub fn is_interned(&self) -> bool {
+        match self.0 {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Name {
+    fn from(name: &'a str) -> Self {
+        Name::new(name)
+    }
+}
+
+impl From<String> for Name {
+    fn from(name: String) -> Self {
+        match KNOWN.binary_search(&name.as_str()) {
+            Ok(index) => Name(Cow::Borrowed(KNOWN[index])),
+            Err(_) => Name(Cow::Owned(name)),
+        }
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> Self {
+        name.0.into_owned()
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Name {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Name {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::fmt::Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_is_sorted() {
+        assert!(KNOWN.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_interning() {
+        let par = Name::new("par");
+        assert!(par.is_interned());
+        assert_eq!(par, "par");
+        assert_eq!(par, Name::from("par".to_string()));
+
+        let unknown = Name::new("applepi");
+        assert!(!unknown.is_interned());
+        assert_eq!(unknown.as_str(), "applepi");
+        assert_eq!(unknown.to_string(), "applepi");
+    }
+}
@@ -0,0 +1,197 @@
+// Canonical token streams
+//
+// The same document can be written many ways.  Normalizing rewrites the
+// differences that don't change what a document says into a single form, so
+// that documents can be deduplicated and hashed by their tokens.  The writer
+// already writes hex escapes in lower case and only the delimiters that are
+// needed, so normalized tokens that are equal write out to equal bytes.
+//
+
+use text::{Decoded, Decoder};
+use tokenizer::{group_destination, group_end, Token};
+
+/// What `normalize` rewrites
+#[derive(Debug, Clone)]
+pub struct NormalizeOptions {
+    /// Drop line breaks, which only matter inside `\bin` data
+    pub newlines: bool,
+    /// Replace hex escapes in the document text with `\uN` escapes of the
+    /// characters they stand for, keeping the byte as the fallback, where the
+    /// `\ucN` in effect allows it.  Hex escapes of printable ASCII become
+    /// plain text, or control symbols for `{`, `}` and `\\`.
+    pub unicode: bool,
+    /// Sort the fields of the `\info` group by name
+    pub sort_info: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            newlines: true,
+            unicode: true,
+            sort_info: true,
+        }
+    }
+}
+
+// Whether a character can be written as plain text
+fn is_plain(c: char) -> bool {
+    (c.is_ascii_graphic() || c == ' ') && !is_reserved(c)
+}
+
+fn is_reserved(c: char) -> bool {
+    ['{', '}', '\\'].contains(&c)
+}
+
+fn push(out: &mut Vec<Token>, token: Token) {
+    match (out.last_mut(), token) {
+        (Some(Token::Text(last)), Token::Text(text)) => last.extend_from_slice(&text),
+        (_, token) => out.push(token),
+    }
+}
+
+// Sort the groups in each \info group by their destination names
+fn sort_info(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        let (word, end) = match group_destination(&tokens, index) {
+            Some(("info", false, word)) if tokens[index] == Token::StartGroup => {
+                (word, group_end(&tokens, index))
+            }
+            _ => {
+                out.push(tokens[index].clone());
+                index += 1;
+                continue;
+            }
+        };
+        // Leave a group that's never closed as it is
+        if tokens[end - 1] != Token::EndGroup {
+            out.extend_from_slice(&tokens[index..]);
+            break;
+        }
+        out.extend_from_slice(&tokens[index..=word]);
+        let mut fields = Vec::new();
+        let mut field = word + 1;
+        while field < end - 1 {
+            if tokens[field] == Token::StartGroup {
+                let field_end = group_end(&tokens, field);
+                let name = group_destination(&tokens, field).map(|(name, _, _)| name);
+                fields.push((name, &tokens[field..field_end]));
+                field = field_end;
+            } else {
+                // Anything between the fields goes before them
+                out.push(tokens[field].clone());
+                field += 1;
+            }
+        }
+        fields.sort_by_key(|(name, _)| *name);
+        for (_, field) in fields {
+            out.extend_from_slice(field);
+        }
+        out.push(Token::EndGroup);
+        index = end;
+    }
+    out
+}
+
+/// Rewrite a token stream into a canonical form, as `options` say
+///
+/// Adjacent text tokens are always merged.  The result describes the same
+/// document as `tokens`, and written out with `write_rtf`, documents that
+/// differ only in line breaks, the case of their hex escapes, the delimiters
+/// after their control words, how they escape the characters of their text
+/// and the order of their `\info` fields come out byte for byte the same.
+pub fn normalize(tokens: &[Token], options: &NormalizeOptions) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut decoder = Decoder::new();
+    for token in tokens {
+        let decoded = decoder.token(token);
+        match (token, decoded) {
+            (Token::Newline, _) if options.newlines => {}
+            (Token::HexByte(byte), Decoded::Text(text)) if options.unicode => {
+                let c = text.chars().next().unwrap_or('\u{fffd}');
+                match (c, decoder.uc()) {
+                    _ if is_plain(c) => push(&mut out, Token::Text(vec![c as u8])),
+                    _ if is_reserved(c) => out.push(Token::ControlSymbol(c)),
+                    // Control characters, and bytes the code page doesn't
+                    // define
+                    _ if c.is_ascii() || c == '\u{fffd}' => out.push(token.clone()),
+                    (_, 0) => out.push(Token::Unicode {
+                        codepoint: c as i32,
+                    }),
+                    (_, 1) => {
+                        out.push(Token::Unicode {
+                            codepoint: c as i32,
+                        });
+                        out.push(Token::HexByte(*byte));
+                    }
+                    _ => out.push(token.clone()),
+                }
+            }
+            _ => push(&mut out, token.clone()),
+        }
+    }
+    if options.sort_info {
+        out = sort_info(out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    fn normalized(rtf: &[u8]) -> Vec<u8> {
+        let tokens = normalize(&parse(rtf).unwrap(), &NormalizeOptions::default());
+        write_rtf(&tokens, &WriteOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_normalize() {
+        let a = b"{\\rtf1\\ansi{\\info{\\title T}{\\author A}}\\b  Caf\\'E9 \\'41\\'7b\\par\r\n}";
+        let b = b"{\\rtf1\\ansi\r\n{\\info\r\n{\\author A}{\\title T}}\r\n\\b  Caf\\u233\\'e9 A\\'7b\\par}";
+        assert_eq!(normalized(a), normalized(b));
+        assert_eq!(
+            normalized(a),
+            b"{\\rtf1\\ansi{\\info{\\author A}{\\title T}}\\b  Caf\\u233\\'e9 A\\{\\par}".to_vec()
+        );
+        // Normalizing again changes nothing
+        let tokens = normalize(&parse(a).unwrap(), &NormalizeOptions::default());
+        assert_eq!(normalize(&tokens, &NormalizeOptions::default()), tokens);
+    }
+
+    #[test]
+    fn test_normalize_unicode() {
+        // Two fallback bytes can't stand in for one
+        assert_eq!(
+            normalized(b"{\\rtf1\\uc2 \\'e9}"),
+            b"{\\rtf1\\uc2\\'e9}".to_vec()
+        );
+        assert_eq!(
+            normalized(b"{\\rtf1\\uc0 \\'e9}"),
+            b"{\\rtf1\\uc0\\u233}".to_vec()
+        );
+        // Not in the document text, or already a fallback
+        let rtf = b"{\\rtf1{\\fonttbl{\\f0 Caf\\'e9;}}\\u8212\\'97}";
+        assert_eq!(normalized(rtf), rtf.to_vec());
+
+        let options = NormalizeOptions {
+            unicode: false,
+            ..NormalizeOptions::default()
+        };
+        let tokens = parse(b"{\\rtf1 \\'e9}").unwrap();
+        assert_eq!(normalize(&tokens, &options), tokens);
+    }
+
+    #[test]
+    fn test_normalize_sample_doc() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let normal = normalize(&tokens, &NormalizeOptions::default());
+        assert!(!normal.contains(&Token::Newline));
+        assert_eq!(normalize(&normal, &NormalizeOptions::default()), normal);
+    }
+}
@@ -0,0 +1,206 @@
+// Parallel tokenization
+//
+// A document is one `{\rtf1 ...}` group, but almost everything in it lives in
+// the groups directly inside that one, and a group boundary is also a token
+// boundary.  So a large document can be cut after the closing brace of any of
+// those groups and the pieces tokenized on separate threads.
+//
+// Finding the cuts takes a quick pass over the bytes that only needs to know
+// enough RTF to count braces: escaped braces aren't group boundaries, and
+// neither is anything in the data of a `\binN`.
+//
+
+use std;
+
+use nom::types::CompleteByteSlice as Input;
+use rayon::prelude::*;
+
+use tokenizer::{next_token, parse, ParseError, Token};
+
+/// Documents smaller than this are tokenized on the calling thread
+pub const MIN_PARALLEL_LEN: usize = 256 * 1024;
+
+// The smallest piece worth handing to another thread
+const MIN_CHUNK_LEN: usize = 64 * 1024;
+
+// The number from a `\binN` starting at `bytes[start]`, and the offset of the
+// data that follows it
+fn bin_data(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let digits = bytes[start + 4..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    let end = start + 4 + digits;
+    let len = std::str::from_utf8(&bytes[start + 4..end])
+        .ok()?
+        .parse()
+        .ok()?;
+    let data = if bytes.get(end) == Some(&b' ') {
+        end + 1
+    } else {
+        end
+    };
+    Some((len, data))
+}
+
+// Offsets just after the closing brace of groups directly inside the
+// document group, at least `chunk_len` apart
+fn split_points(bytes: &[u8], chunk_len: usize) -> Vec<usize> {
+    let mut points = Vec::new();
+    let mut last = 0;
+    let mut depth = 0usize;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 1 && index + 1 - last >= chunk_len {
+                    last = index + 1;
+                    points.push(last);
+                }
+            }
+            b'\\' if bytes[index + 1..].starts_with(b"bin") => match bin_data(bytes, index) {
+                Some((len, data)) if len > 0 => match data.checked_add(len) {
+                    Some(end) if end <= bytes.len() => index = end - 1,
+                    // The data runs to the end of the document
+                    _ => break,
+                },
+                Some(_) => {}
+                // Not data the scan understands, so there's no telling
+                // where groups end after it
+                None => break,
+            },
+            // An escaped brace or backslash
+            b'\\' => index += 1,
+            _ => {}
+        }
+        index += 1;
+    }
+    points
+}
+
+// Tokenize `bytes[range]`, returning the tokens and whether the whole range
+// could be read.  Offsets in errors are from the start of `bytes`.
+fn parse_chunk(
+    bytes: &[u8],
+    range: std::ops::Range<usize>,
+) -> Result<(Vec<Token>, bool), ParseError> {
+    let bytes = &bytes[..range.end];
+    let mut input = Input(&bytes[range.start..]);
+    let mut tokens = Vec::new();
+    while let Some((rest, token, _)) = next_token(bytes, input)? {
+        tokens.push(token);
+        input = rest;
+    }
+    Ok((tokens, input.is_empty()))
+}
+
+/// Tokenize a document on the threads of rayon's global pool
+///
+/// The tokens are the same as `parse` gives.  Documents smaller than
+/// `MIN_PARALLEL_LEN` are tokenized on the calling thread.
+pub fn parse_parallel(bytes: &[u8]) -> Result<Vec<Token>, ParseError> {
+    if bytes.len() < MIN_PARALLEL_LEN {
+        return parse(bytes);
+    }
+    let chunks = rayon::current_num_threads() * 4;
+    let chunk_len = std::cmp::max(bytes.len() / chunks, MIN_CHUNK_LEN);
+    let mut bounds = vec![0];
+    bounds.extend(split_points(bytes, chunk_len));
+    bounds.push(bytes.len());
+    bounds.dedup();
+
+    let results: Vec<_> = bounds
+        .par_windows(2)
+        .map(|bounds| parse_chunk(bytes, bounds[0]..bounds[1]))
+        .collect();
+
+    let mut tokens = Vec::new();
+    for result in results {
+        let (chunk, complete) = result?;
+        tokens.extend(chunk);
+        // The tokenizer stops at the first thing it can't read
+        if !complete {
+            break;
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(groups: usize) -> Vec<u8> {
+        let mut rtf = b"{\\rtf1\\ansi{\\fonttbl{\\f0 Arial;}}".to_vec();
+        for index in 0..groups {
+            rtf.extend(format!("{{\\b group {} \\{{x\\}} \\\\}}", index).bytes());
+            rtf.extend(b"{\\*\\blob\\bin4 }}{{}\r\n");
+            rtf.extend(b"\\par plain {\\i\\'e9}\r\n");
+        }
+        rtf.extend(b"}");
+        rtf
+    }
+
+    #[test]
+    fn test_split_points() {
+        let rtf = document(3);
+        let points = split_points(&rtf, 1);
+        assert!(!points.is_empty());
+        for point in &points {
+            assert_eq!(rtf[point - 1], b'}');
+        }
+        // No cut lands inside the \bin data
+        let tokens = parse(&rtf).unwrap();
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        for end in points.iter().cloned().chain(Some(rtf.len())) {
+            pieces.extend(parse(&rtf[start..end]).unwrap());
+            start = end;
+        }
+        assert_eq!(pieces, tokens);
+    }
+
+    #[test]
+    fn test_parse_parallel() {
+        let rtf = document(20000);
+        assert!(rtf.len() > MIN_PARALLEL_LEN);
+        assert_eq!(parse_parallel(&rtf).unwrap(), parse(&rtf).unwrap());
+
+        let small = document(2);
+        assert_eq!(parse_parallel(&small).unwrap(), parse(&small).unwrap());
+    }
+
+    #[test]
+    fn test_parse_parallel_huge_bin() {
+        let mut rtf = document(20000);
+        let middle = rtf.len() / 2;
+        let middle = middle + rtf[middle..].iter().position(|b| *b == b'\n').unwrap() + 1;
+        rtf.splice(
+            middle..middle,
+            b"{\\bin18446744073709551615 x}".iter().cloned(),
+        );
+        assert_eq!(
+            split_points(&rtf, 1).last().map(|point| *point < middle),
+            Some(true)
+        );
+        assert_eq!(
+            format!("{:?}", parse_parallel(&rtf)),
+            format!("{:?}", parse(&rtf))
+        );
+    }
+
+    #[test]
+    fn test_parse_parallel_stops() {
+        // Tokenizing stops at the bad hex escape, part way through
+        let mut rtf = document(20000);
+        let middle = rtf.len() / 2;
+        let middle = middle + rtf[middle..].iter().position(|b| *b == b'\n').unwrap() + 1;
+        rtf.splice(middle..middle, b"{\\'zz}".iter().cloned());
+        let tokens = parse(&rtf).unwrap();
+        let whole = parse(&document(20000)).unwrap();
+        assert!(tokens.len() < whole.len() * 3 / 4);
+        assert_eq!(parse_parallel(&rtf).unwrap(), tokens);
+    }
+}
@@ -0,0 +1,473 @@
+// Embedded pictures
+//
+// Pictures are stored in `{\pict ...}` destinations: a few control words
+// describing the image format and size, followed by the image data, either
+// hex encoded in the text of the group or as raw `\bin` data.
+//
+
+use std;
+use std::io::{Read, Write};
+
+use stream::TokenReader;
+use tokenizer::{narrow_arg, Token};
+
+/// The format of a picture's data, from the control words of its group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum PictureFormat {
+    /// `\pngblip`
+    Png,
+    /// `\jpegblip`
+    Jpeg,
+    /// `\emfblip`, an Enhanced Metafile
+    Emf,
+    /// `\wmetafileN`, a Windows Metafile
+    Wmf,
+    /// `\macpict`, a QuickDraw picture
+    MacPict,
+    /// `\dibitmapN`, a device-independent bitmap without its file header
+    Dib,
+    /// `\wbitmapN`, a device-dependent bitmap
+    Bitmap,
+    #[default]
+    Unknown,
+}
+
+impl PictureFormat {
+    /// The format of picture data, from the signature at its start
+    ///
+    /// Returns `None` for data that doesn't start with a signature this
+    /// knows.  Device-dependent bitmaps and QuickDraw pictures have nothing to
+    /// go on, so are never detected.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let format = match data {
+            [0x89, b'P', b'N', b'G', ..] => PictureFormat::Png,
+            [0xff, 0xd8, 0xff, ..] => PictureFormat::Jpeg,
+            // An EMR_HEADER record, with the " EMF" signature
+            [1, 0, 0, 0, ..] if data.get(40..44) == Some(&b" EMF"[..]) => PictureFormat::Emf,
+            // The placeable metafile key, or the header of a memory or disk
+            // metafile
+            [0xd7, 0xcd, 0xc6, 0x9a, ..] | [1, 0, 9, 0, ..] | [2, 0, 9, 0, ..] => {
+                PictureFormat::Wmf
+            }
+            // The size of a BITMAPCOREHEADER, BITMAPINFOHEADER, or one of its
+            // successors
+            [12, 0, 0, 0, ..] | [40, 0, 0, 0, ..] | [108, 0, 0, 0, ..] | [124, 0, 0, 0, ..] => {
+                PictureFormat::Dib
+            }
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// Whether web browsers can display the format
+    pub fn is_web_displayable(self) -> bool {
+        matches!(self, PictureFormat::Png | PictureFormat::Jpeg)
+    }
+
+    fn from_word(name: &str) -> Option<Self> {
+        let format = match name {
+            "pngblip" => PictureFormat::Png,
+            "jpegblip" => PictureFormat::Jpeg,
+            "emfblip" => PictureFormat::Emf,
+            "wmetafile" => PictureFormat::Wmf,
+            "macpict" => PictureFormat::MacPict,
+            "dibitmap" => PictureFormat::Dib,
+            "wbitmap" => PictureFormat::Bitmap,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// The usual file extension for the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            PictureFormat::Png => "png",
+            PictureFormat::Jpeg => "jpg",
+            PictureFormat::Emf => "emf",
+            PictureFormat::Wmf => "wmf",
+            PictureFormat::MacPict => "pict",
+            PictureFormat::Dib | PictureFormat::Bitmap => "bmp",
+            PictureFormat::Unknown => "bin",
+        }
+    }
+}
+
+/// A picture embedded in a document
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Picture {
+    pub format: PictureFormat,
+    /// `\picwN` - pixels for bitmaps, hundredths of a millimeter for
+    /// metafiles
+    pub width: Option<i32>,
+    /// `\pichN`
+    pub height: Option<i32>,
+    /// `\picwgoalN` - the width to display the picture at, in twips
+    pub goal_width: Option<i32>,
+    /// `\pichgoalN`
+    pub goal_height: Option<i32>,
+    /// Index of the `StartGroup` token of the `\pict` group
+    pub token: usize,
+    pub data: Vec<u8>,
+}
+
+impl Picture {
+    /// The format of the picture's data: from its signature where that's
+    /// recognized, otherwise from the control words of its group
+    ///
+    /// Not every writer labels its pictures right, so this is the one to
+    /// trust when handing the data to something else.
+    pub fn detected_format(&self) -> PictureFormat {
+        PictureFormat::detect(&self.data).unwrap_or(self.format)
+    }
+
+    /// The picture as PNG or JPEG, for web pages
+    ///
+    /// Pictures already in either format are returned as they are, and the
+    /// rest are converted to PNG by `converter`.  Returns `None` for a
+    /// picture `converter` can't convert.
+    pub fn to_web(&self, converter: &dyn ImageConverter) -> Option<Picture> {
+        let format = self.detected_format();
+        let (format, data) = if format.is_web_displayable() {
+            (format, self.data.clone())
+        } else {
+            (PictureFormat::Png, converter.to_png(self)?)
+        };
+        Some(Picture {
+            format,
+            width: self.width,
+            height: self.height,
+            goal_width: self.goal_width,
+            goal_height: self.goal_height,
+            token: self.token,
+            data,
+        })
+    }
+}
+
+/// Converts pictures to PNG
+///
+/// Implement this to render the metafiles and bitmaps that web browsers can't
+/// display.  `raster::PngConverter`, behind the `png-converter` feature,
+/// handles bitmaps and the metafiles that only hold one.  Closures taking a
+/// picture and returning its PNG data are converters too.
+pub trait ImageConverter {
+    /// Convert a picture to PNG data, or return `None` if it can't be
+    fn to_png(&self, picture: &Picture) -> Option<Vec<u8>>;
+}
+
+impl<F> ImageConverter for F
+where
+    F: Fn(&Picture) -> Option<Vec<u8>>,
+{
+    fn to_png(&self, picture: &Picture) -> Option<Vec<u8>> {
+        self(picture)
+    }
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+}
+
+struct OpenPicture {
+    picture: Picture,
+    depth: usize,
+    // The first digit of a hex byte split between text tokens
+    high: Option<u8>,
+}
+
+impl OpenPicture {
+    fn word(&mut self, name: &str, arg: Option<i32>) {
+        let picture = &mut self.picture;
+        if let Some(format) = PictureFormat::from_word(name) {
+            picture.format = format;
+            return;
+        }
+        match name {
+            "picw" => picture.width = arg,
+            "pich" => picture.height = arg,
+            "picwgoal" => picture.goal_width = arg,
+            "pichgoal" => picture.goal_height = arg,
+            _ => {}
+        }
+    }
+
+    fn hex(&mut self, text: &[u8]) {
+        for value in text.iter().filter_map(|byte| hex_value(*byte)) {
+            match self.high.take() {
+                Some(high) => self.picture.data.push(high << 4 | value),
+                None => self.high = Some(value),
+            }
+        }
+    }
+}
+
+// Follows the pictures of a document token by token, decoding the data of the
+// open one into its `data`
+#[derive(Default)]
+struct Scanner {
+    open: Option<OpenPicture>,
+    depth: usize,
+    // Depth of the {\*\nonshppict} group being skipped, if any
+    skipping: Option<usize>,
+    group_start: Option<usize>,
+}
+
+impl Scanner {
+    // Read the token at `index`, returning the picture it closes, if any
+    fn token(&mut self, index: usize, token: &Token) -> Option<Picture> {
+        let start = self.group_start.take();
+        match token {
+            Token::StartGroup => {
+                self.depth += 1;
+                self.group_start = Some(index);
+            }
+            Token::EndGroup => {
+                let depth = self.depth;
+                self.depth -= std::cmp::min(depth, 1);
+                if self.skipping == Some(depth) {
+                    self.skipping = None;
+                }
+                if self.open.as_ref().is_some_and(|open| open.depth == depth) {
+                    return self.open.take().map(|open| open.picture);
+                }
+            }
+            Token::ControlSymbol('*') if start.is_some() => self.group_start = start,
+            Token::ControlWord { name, .. } if start.is_some() && name == "nonshppict" => {
+                self.skipping = self.skipping.or(Some(self.depth));
+            }
+            Token::ControlWord { name, .. }
+                if start.is_some()
+                    && name == "pict"
+                    && self.skipping.is_none()
+                    && self.open.is_none() =>
+            {
+                self.open = Some(OpenPicture {
+                    picture: Picture {
+                        token: start.unwrap_or(index),
+                        ..Picture::default()
+                    },
+                    depth: self.depth,
+                    high: None,
+                });
+            }
+            _ => {
+                let open = match self.open {
+                    // Nested groups hold properties, not data
+                    Some(ref mut open) if open.depth == self.depth => open,
+                    _ => return None,
+                };
+                match token {
+                    Token::ControlWord { name, arg } => open.word(name, narrow_arg(*arg)),
+                    Token::Text(text) => open.hex(text),
+                    Token::ControlBin(data) => open.picture.data.extend_from_slice(data),
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Find the pictures in a document, with their data decoded
+///
+/// Pictures in `{\*\nonshppict}` groups are skipped: they're copies of
+/// pictures found elsewhere in the document, in a format for older readers.
+pub fn pictures(tokens: &[Token]) -> Vec<Picture> {
+    let mut scanner = Scanner::default();
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(index, token)| scanner.token(index, token))
+        .collect()
+}
+
+/// Find the pictures in a document as it's read, writing the data of each to
+/// the writer `create` makes for it
+///
+/// The document is tokenized and the data decoded a chunk at a time, so
+/// neither the tokens nor the data of a picture are ever all in memory; this
+/// is the way to read documents holding large scans.  `create` is called once
+/// for each picture, when its data starts, by which time the control words
+/// describing it have been read.  The pictures are returned, and passed to
+/// `create`, without their data.
+pub fn write_pictures<R, W, F>(reader: R, mut create: F) -> std::io::Result<Vec<Picture>>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&Picture) -> std::io::Result<W>,
+{
+    let mut pictures = Vec::new();
+    let mut scanner = Scanner::default();
+    let mut writer = None;
+    for (index, token) in TokenReader::new(reader).enumerate() {
+        let token = token?;
+        let closed = scanner.token(index, &token);
+        let data = match scanner.open {
+            Some(ref mut open) => std::mem::take(&mut open.picture.data),
+            None => Vec::new(),
+        };
+        let picture = match (closed.as_ref(), scanner.open.as_ref()) {
+            (Some(picture), _) => picture,
+            (None, Some(open)) if !data.is_empty() => &open.picture,
+            _ => continue,
+        };
+        if writer.is_none() {
+            writer = Some(create(picture)?);
+        }
+        if let Some(ref mut writer) = writer {
+            writer.write_all(&data)?;
+        }
+        if let Some(picture) = closed {
+            if let Some(mut writer) = writer.take() {
+                writer.flush()?;
+            }
+            pictures.push(picture);
+        }
+    }
+    Ok(pictures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use tokenizer::parse;
+
+    #[test]
+    fn test_pictures() {
+        let rtf = b"{\\rtf1{\\*\\shppict{\\pict{\\*\\picprop{\\sp{\\sn x}{\\sv 1}}}\\pngblip\\picw2\\pich3\\picwgoal30\\pichgoal45 89504e\r\n470d0a}}{\\*\\nonshppict{\\pict\\wmetafile8 0102}}{\\pict\\jpegblip\\bin3 \xff\xd8\xff}}";
+        let pictures = pictures(&parse(rtf).unwrap());
+        assert_eq!(pictures.len(), 2);
+        assert_eq!(
+            pictures[0],
+            Picture {
+                format: PictureFormat::Png,
+                width: Some(2),
+                height: Some(3),
+                goal_width: Some(30),
+                goal_height: Some(45),
+                token: 5,
+                data: b"\x89PNG\r\n".to_vec(),
+            }
+        );
+        assert_eq!(pictures[0].format.extension(), "png");
+        assert_eq!(pictures[1].format, PictureFormat::Jpeg);
+        assert_eq!(pictures[1].data, b"\xff\xd8\xff".to_vec());
+    }
+
+    #[test]
+    fn test_detect() {
+        let mut emf = vec![1, 0, 0, 0];
+        emf.resize(40, 0);
+        emf.extend_from_slice(b" EMF");
+        let cases: &[(&[u8], Option<PictureFormat>)] = &[
+            (b"\x89PNG\r\n\x1a\n", Some(PictureFormat::Png)),
+            (b"\xff\xd8\xff\xe0", Some(PictureFormat::Jpeg)),
+            (&emf, Some(PictureFormat::Emf)),
+            (&emf[..43], None),
+            (b"\xd7\xcd\xc6\x9a\0\0", Some(PictureFormat::Wmf)),
+            (b"\x01\0\x09\0\0\x03", Some(PictureFormat::Wmf)),
+            (b"\x28\0\0\0\x10\0\0\0", Some(PictureFormat::Dib)),
+            (b"GIF89a", None),
+            (b"", None),
+        ];
+        for (data, format) in cases {
+            assert_eq!(PictureFormat::detect(data), *format);
+        }
+    }
+
+    #[test]
+    fn test_to_web() {
+        // Labelled a metafile, but really a PNG
+        let picture = Picture {
+            format: PictureFormat::Wmf,
+            width: Some(2),
+            data: b"\x89PNG\r\n\x1a\n".to_vec(),
+            ..Picture::default()
+        };
+        let refuse = |_: &Picture| None;
+        assert_eq!(picture.detected_format(), PictureFormat::Png);
+        let web = picture.to_web(&refuse).unwrap();
+        assert_eq!(web.format, PictureFormat::Png);
+        assert_eq!(web.data, picture.data);
+
+        let picture = Picture {
+            format: PictureFormat::Emf,
+            width: Some(2),
+            data: b"\x01\0\0\0".to_vec(),
+            ..Picture::default()
+        };
+        assert_eq!(picture.to_web(&refuse), None);
+        let convert = |picture: &Picture| Some(picture.data.iter().rev().cloned().collect());
+        let web = picture.to_web(&convert).unwrap();
+        assert_eq!(web.format, PictureFormat::Png);
+        assert_eq!(web.width, Some(2));
+        assert_eq!(web.data, b"\0\0\0\x01".to_vec());
+    }
+
+    // Shares what's written to it with the test
+    struct Sink(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_pictures() {
+        let rtf = b"{\\rtf1{\\pict\\pngblip\\picw2 89504e\r\n470d0a}{\\pict\\wmetafile8}{\\*\\nonshppict{\\pict 01}}{\\pict\\jpegblip\\bin3 \xff\xd8\xff}}";
+        let mut sinks = Vec::new();
+        let found = write_pictures(&rtf[..], |picture| {
+            assert!(picture.data.is_empty());
+            let sink = Rc::new(RefCell::new(Vec::new()));
+            sinks.push((picture.format, sink.clone()));
+            Ok(Sink(sink))
+        })
+        .unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].width, Some(2));
+        assert!(found.iter().all(|picture| picture.data.is_empty()));
+
+        let written: Vec<_> = sinks
+            .into_iter()
+            .map(|(format, sink)| (format, sink.borrow().clone()))
+            .collect();
+        let expected: Vec<_> = pictures(&parse(rtf).unwrap())
+            .into_iter()
+            .map(|picture| (picture.format, picture.data))
+            .collect();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn test_write_sample_doc_pictures() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let mut written = Vec::new();
+        write_pictures(&test_bytes[..], |_| {
+            written.push(Rc::new(RefCell::new(Vec::new())));
+            Ok(Sink(written[written.len() - 1].clone()))
+        })
+        .unwrap();
+        let expected = pictures(&parse(test_bytes).unwrap());
+        assert_eq!(written.len(), expected.len());
+        for (written, picture) in written.iter().zip(&expected) {
+            assert_eq!(*written.borrow(), picture.data);
+        }
+    }
+
+    #[test]
+    fn test_sample_doc_pictures() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let pictures = pictures(&parse(test_bytes).unwrap());
+        assert!(!pictures.is_empty());
+        assert!(pictures.iter().all(|picture| !picture.data.is_empty()));
+    }
+}
@@ -0,0 +1,182 @@
+// RTF pretty-printing
+//
+// Re-emits a token stream as RTF laid out for people to read: every group
+// starts on a new line, indented by its depth, and long lines are wrapped.
+// Line breaks are insignificant in RTF, so wrapping alone doesn't change the
+// document.  Indentation is another matter: RTF has no insignificant spaces,
+// so the indentation becomes part of the text of whatever destination it
+// lands in.  Use an indent of zero where the output has to read the same as
+// the input, such as for golden files that are also parsed.
+//
+
+use tokenizer::Token;
+use writer::{Encoder, WriteOptions};
+
+/// Layout of pretty-printed RTF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatStyle {
+    /// Spaces of indentation per group depth
+    pub indent: usize,
+    /// Lines are wrapped to fit in this many bytes, where there's somewhere
+    /// to wrap them
+    pub width: usize,
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        FormatStyle {
+            indent: 2,
+            width: 78,
+        }
+    }
+}
+
+struct Printer<'s> {
+    style: &'s FormatStyle,
+    out: Vec<u8>,
+    // Offset of the start of the current line in `out`
+    line_start: usize,
+    // Whether the current line has anything on it besides indentation
+    content: bool,
+    depth: usize,
+}
+
+impl<'s> Printer<'s> {
+    fn newline(&mut self) {
+        self.out.push(b'\n');
+        self.line_start = self.out.len();
+        let indent = self.depth * self.style.indent;
+        self.out.resize(self.line_start + indent, b' ');
+        self.content = false;
+    }
+
+    // Append a piece of output, first wrapping the line if the piece doesn't
+    // fit and may start a line
+    fn piece(&mut self, piece: &[u8], breakable: bool) {
+        let column = self.out.len() - self.line_start;
+        if breakable && self.content && column + piece.len() > self.style.width {
+            self.newline();
+        }
+        self.out.extend_from_slice(piece);
+        // Binary data can contain line breaks of its own
+        if let Some(offset) = piece.iter().rposition(|b| *b == b'\n') {
+            self.line_start = self.out.len() - piece.len() + offset + 1;
+        }
+        self.content = true;
+    }
+
+    // Append text, which can be wrapped after any space in it.  `glued` is
+    // set when the text starts with the delimiter of a preceding control
+    // word, which mustn't be separated from it.
+    fn text(&mut self, text: &[u8], glued: bool) {
+        let mut breakable = !glued;
+        let mut rest = text;
+        while !rest.is_empty() {
+            let len = rest
+                .iter()
+                .position(|b| *b == b' ')
+                .map_or(rest.len(), |space| space + 1);
+            self.piece(&rest[..len], breakable);
+            breakable = true;
+            rest = &rest[len..];
+        }
+    }
+}
+
+/// Serialize a token stream as pretty-printed RTF
+///
+/// `Token::Newline`s are dropped, as the layout is decided by `style`.
+pub fn format_rtf(tokens: &[Token], style: &FormatStyle) -> Vec<u8> {
+    let mut printer = Printer {
+        style,
+        out: Vec::new(),
+        line_start: 0,
+        content: false,
+        depth: 0,
+    };
+    let mut encoder = Encoder::new(WriteOptions::default());
+    let mut encoded = Vec::new();
+    let mut after_word = false;
+    for token in tokens {
+        if *token == Token::Newline {
+            continue;
+        }
+        encoded.clear();
+        // Escaping text payloads is the default, and can't fail
+        let _ = encoder.encode(token, &mut encoded);
+        match token {
+            Token::StartGroup => {
+                if printer.content {
+                    printer.newline();
+                }
+                printer.piece(&encoded, false);
+                printer.depth += 1;
+            }
+            Token::EndGroup => {
+                printer.depth = printer.depth.saturating_sub(1);
+                printer.piece(&encoded, true);
+            }
+            Token::Text(_) => printer.text(&encoded, after_word),
+            _ => printer.piece(&encoded, true),
+        }
+        after_word = matches!(token, Token::ControlWord { .. });
+    }
+    if printer.content {
+        printer.out.push(b'\n');
+    }
+    printer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::extract_text;
+    use tokenizer::parse;
+
+    fn format(rtf: &[u8], style: &FormatStyle) -> String {
+        String::from_utf8(format_rtf(&parse(rtf).unwrap(), style)).unwrap()
+    }
+
+    #[test]
+    fn test_indents_groups() {
+        let rtf = b"{\\rtf1{\\fonttbl{\\f0 Times;}{\\f1 Arial;}}\r\n\\f0 Hello {\\b World}\\par}";
+        assert_eq!(
+            format(rtf, &FormatStyle::default()),
+            "{\\rtf1\n  {\\fonttbl\n    {\\f0 Times;}\n    {\\f1 Arial;}}\\f0 Hello \n  {\\b World}\\par}\n"
+        );
+    }
+
+    #[test]
+    fn test_wraps_lines() {
+        let style = FormatStyle {
+            indent: 0,
+            width: 12,
+        };
+        assert_eq!(
+            format(b"{\\rtf1\\pard some words to wrap\\par}", &style),
+            "{\\rtf1\\pard \nsome words \nto wrap\\par}\n"
+        );
+    }
+
+    #[test]
+    fn test_unindented_output_is_equivalent() {
+        let style = FormatStyle {
+            indent: 0,
+            width: 40,
+        };
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let formatted = format_rtf(&tokens, &style);
+        let reparsed = parse(&formatted).unwrap();
+        assert_eq!(extract_text(&reparsed), extract_text(&tokens));
+
+        // Only text is split across lines
+        let structure = |tokens: Vec<Token>| -> Vec<Token> {
+            tokens
+                .into_iter()
+                .filter(|token| !matches!(token, Token::Newline | Token::Text(_)))
+                .collect()
+        };
+        assert_eq!(structure(reparsed), structure(tokens));
+    }
+}
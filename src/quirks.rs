@@ -0,0 +1,215 @@
+// Generator quirks
+//
+// Programs that write RTF each bend the specification their own way, and
+// reading their documents the way they meant takes a little give in the
+// right places.  The allowances made for each program are kept here, so
+// the tokenizer and the readers built on it can be told which program's
+// documents they're reading, or work it out from the document.
+//
+// Word and LibreOffice have profiles of their own although their documents
+// are read by the specification, with no allowances: they're the programs
+// the specification is written against and checked with, and naming them
+// keeps detection from mistaking their documents for another program's.
+//
+
+use metadata::{producer_info, Application};
+use tokenizer::{parse_with_options, ParseError, ParseOptions, Token};
+
+/// The program whose quirks to allow for when reading a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Quirks {
+    /// Microsoft Word, which follows the specification it's written
+    /// against, so its documents are read by it, with no allowances
+    Word,
+    /// Microsoft Outlook and Exchange, whose messages can have junk, often
+    /// NUL padding, after the closing brace of the document
+    Outlook,
+    /// LibreOffice and OpenOffice, whose documents are read by the
+    /// specification too, with no allowances
+    LibreOffice,
+    /// WordPad and other programs built on the RichEdit control, which can
+    /// leave out the fallback characters a `\uN` is followed by, so that
+    /// a control word straight after one is read rather than skipped
+    WordPad,
+    /// The macOS Cocoa text system, TextEdit, whose `\NeXTGraphic` groups
+    /// hold the file name of an attachment rather than document text
+    TextEdit,
+    /// A program that isn't known, read by the specification
+    #[default]
+    Unknown,
+}
+
+impl Quirks {
+    /// The quirks of the program that wrote a document, from its generator
+    /// destination and other characteristic control words
+    pub fn detect(tokens: &[Token]) -> Self {
+        Quirks::from_application(producer_info(tokens).application)
+    }
+
+    /// The quirks of an application family
+    pub fn from_application(application: Application) -> Self {
+        match application {
+            Application::Word => Quirks::Word,
+            Application::Outlook => Quirks::Outlook,
+            Application::WordPad | Application::RichEdit => Quirks::WordPad,
+            Application::LibreOffice | Application::OpenOffice => Quirks::LibreOffice,
+            Application::TextEdit => Quirks::TextEdit,
+            _ => Quirks::Unknown,
+        }
+    }
+
+    /// Tokenizer options allowing for these quirks
+    pub fn parse_options(self) -> ParseOptions {
+        ParseOptions {
+            skip_junk: self == Quirks::Outlook,
+            ..ParseOptions::default()
+        }
+    }
+
+    /// Whether a control word or symbol straight after a `\uN` is one of
+    /// its fallback characters, as the specification says, rather than
+    /// ending them
+    pub(crate) fn word_is_fallback(self) -> bool {
+        self != Quirks::WordPad
+    }
+
+    /// Whether a destination that the specification doesn't set aside is
+    /// nonetheless not document text
+    pub(crate) fn ignores_destination(self, name: &str) -> bool {
+        self == Quirks::TextEdit && name == "NeXTGraphic"
+    }
+}
+
+/// Tokenize a document, allowing for the quirks of the program that wrote
+/// it
+///
+/// The quirks are detected from the document as `Quirks::detect` does, and
+/// returned along with the tokens, to pass on to text extraction in
+/// `TextOptions::quirks`.
+///
+/// ```
+/// use rtf_grimoire::quirks::{parse_with_quirks, Quirks};
+/// use rtf_grimoire::{extract_text_with, TextOptions};
+///
+/// let rtf = b"{\\rtf1{\\*\\generator Riched20 10.0.19041;}\\uc1 Caf\\u233\\par}";
+/// let (tokens, quirks) = parse_with_quirks(rtf).unwrap();
+/// assert_eq!(quirks, Quirks::WordPad);
+/// let options = TextOptions {
+///     quirks,
+///     ..TextOptions::default()
+/// };
+/// assert_eq!(extract_text_with(&tokens, &options), "Caf\u{e9}\n");
+/// ```
+pub fn parse_with_quirks(bytes: &[u8]) -> Result<(Vec<Token>, Quirks), ParseError> {
+    let tokens = parse_with_options(bytes, &ParseOptions::default())?;
+    let quirks = Quirks::detect(&tokens);
+    let options = quirks.parse_options();
+    if options == ParseOptions::default() {
+        return Ok((tokens, quirks));
+    }
+    Ok((parse_with_options(bytes, &options)?, quirks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scan::scan_text;
+    use text::{extract_text, extract_text_with, TextOptions};
+
+    fn text(rtf: &[u8]) -> (String, Quirks) {
+        let (tokens, quirks) = parse_with_quirks(rtf).unwrap();
+        let options = TextOptions {
+            quirks,
+            ..TextOptions::default()
+        };
+        let text = extract_text_with(&tokens, &options);
+        let mut scanned = String::new();
+        scan_text(rtf, &options, &mut scanned);
+        assert_eq!(scanned, text);
+        (text, quirks)
+    }
+
+    // A \uN followed by a control word, read by the specification's rule
+    // that the control word is its fallback
+    const FALLBACK_WORD: &str = "\\uc1 A\\u8364\\tab B\\u8364?C}";
+
+    fn doc(header: &str, body: &str) -> Vec<u8> {
+        format!("{{\\rtf1\\ansi{} {}", header, body).into_bytes()
+    }
+
+    #[test]
+    fn test_quirks_word() {
+        let rtf = doc("{\\*\\generator Microsoft Word 11.0.0000;}", FALLBACK_WORD);
+        assert_eq!(
+            text(&rtf),
+            ("A\u{20ac}B\u{20ac}C".to_string(), Quirks::Word)
+        );
+        assert_eq!(Quirks::Word.parse_options(), ParseOptions::default());
+        assert!(Quirks::Word.word_is_fallback());
+        assert!(!Quirks::Word.ignores_destination("NeXTGraphic"));
+        // Junk after the document isn't skipped
+        let mut rtf = doc("{\\*\\rsidtbl \\rsid1}", "Hi}");
+        rtf.extend_from_slice(b"\0\0");
+        let (tokens, quirks) = parse_with_quirks(&rtf).unwrap();
+        assert_eq!(quirks, Quirks::Word);
+        assert_eq!(tokens.last(), Some(&Token::Text(b"\0\0".to_vec())));
+    }
+
+    #[test]
+    fn test_quirks_outlook() {
+        let rtf = b"{\\rtf1\\ansi\\fromhtml1 Hi}\0\0\0";
+        let (tokens, quirks) = parse_with_quirks(rtf).unwrap();
+        assert_eq!(quirks, Quirks::Outlook);
+        assert_eq!(tokens.last(), Some(&Token::EndGroup));
+        assert!(Quirks::Outlook.parse_options().skip_junk);
+    }
+
+    #[test]
+    fn test_quirks_libreoffice() {
+        let rtf = doc("{\\*\\generator LibreOffice/7.3;}", FALLBACK_WORD);
+        assert_eq!(
+            text(&rtf),
+            ("A\u{20ac}B\u{20ac}C".to_string(), Quirks::LibreOffice)
+        );
+        assert_eq!(Quirks::LibreOffice.parse_options(), ParseOptions::default());
+        assert!(Quirks::LibreOffice.word_is_fallback());
+        assert!(!Quirks::LibreOffice.ignores_destination("NeXTGraphic"));
+        assert_eq!(
+            text(&doc("{\\*\\generator OpenOffice.org 3.2;}", "Hi}")),
+            ("Hi".to_string(), Quirks::LibreOffice)
+        );
+    }
+
+    #[test]
+    fn test_quirks_wordpad() {
+        let rtf = b"{\\rtf1\\ansi{\\*\\generator Msftedit 5.41.21.2510;}\\viewkind4\\nouicompat\\uc1 A\\u8364\\tab B\\u8364?C}";
+        assert_eq!(
+            text(rtf),
+            ("A\u{20ac}\tB\u{20ac}C".to_string(), Quirks::WordPad)
+        );
+        // By the specification the \tab is the euro's fallback
+        assert_eq!(
+            extract_text(&parse_with_quirks(rtf).unwrap().0),
+            "A\u{20ac}B\u{20ac}C"
+        );
+    }
+
+    #[test]
+    fn test_quirks_textedit() {
+        let rtf = b"{\\rtf1\\ansi\\cocoartf2639 {{\\NeXTGraphic photo.png \\width1200 \\height800}\xac}Caption}";
+        assert_eq!(text(rtf), ("\u{ac}Caption".to_string(), Quirks::TextEdit));
+        assert!(Quirks::TextEdit.ignores_destination("NeXTGraphic"));
+        assert!(!Quirks::Unknown.ignores_destination("NeXTGraphic"));
+    }
+
+    #[test]
+    fn test_quirks_unknown() {
+        assert_eq!(text(b"{\\rtf1 Hi}"), ("Hi".to_string(), Quirks::Unknown));
+        let rtf = doc("", FALLBACK_WORD);
+        assert_eq!(
+            text(&rtf),
+            ("A\u{20ac}B\u{20ac}C".to_string(), Quirks::Unknown)
+        );
+    }
+}
@@ -0,0 +1,453 @@
+// Converting bitmap pictures to PNG
+//
+// Pictures pasted into documents as bitmaps - scans, screenshots - are often
+// stored as device-independent bitmaps, or as metafiles that do nothing but
+// draw one.  Web browsers can display neither, so these decode the bitmap and
+// encode it again as a PNG.
+//
+
+use std::convert::TryFrom;
+
+use png;
+
+use pict::{ImageConverter, Picture, PictureFormat};
+
+// Compression of a DIB's pixel data
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+// WMF records that draw a DIB, with the offset of the DIB in each
+const WMF_BITMAP_RECORDS: &[(u16, usize)] = &[
+    // META_STRETCHDIB
+    (0x0f43, 28),
+    // META_DIBSTRETCHBLT
+    (0x0b41, 26),
+    // META_DIBBITBLT
+    (0x0940, 22),
+];
+
+// EMF records that draw a DIB, with the offset of the offBmiSrc field in each
+const EMF_BITMAP_RECORDS: &[(u32, usize)] = &[
+    // EMR_STRETCHBLT
+    (77, 84),
+    // EMR_SETDIBITSTODEVICE
+    (80, 48),
+    // EMR_STRETCHDIBITS
+    (81, 48),
+];
+const EMR_EOF: u32 = 14;
+
+/// Converts bitmaps, and metafiles that draw a single bitmap, to PNG
+///
+/// Bitmaps of 1, 4, 8, 16, 24 and 32 bits per pixel are supported, as long as
+/// they aren't run-length encoded.  Metafiles are converted by pulling out the
+/// bitmap they draw, without rendering anything else they hold, so one that
+/// draws more than one bitmap is refused.  Other pictures are left to another
+/// converter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PngConverter;
+
+impl ImageConverter for PngConverter {
+    fn to_png(&self, picture: &Picture) -> Option<Vec<u8>> {
+        let bitmap = match picture.detected_format() {
+            PictureFormat::Dib => decode_packed_dib(&picture.data),
+            PictureFormat::Wmf => decode_packed_dib(wmf_bitmap(&picture.data)?),
+            PictureFormat::Emf => {
+                let (info, bits) = emf_bitmap(&picture.data)?;
+                decode_dib(info, bits)
+            }
+            _ => None,
+        }?;
+        encode_png(&bitmap)
+    }
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(len)?)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    slice(data, offset, 2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    slice(data, offset, 4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// The packed DIB drawn by a WMF
+fn wmf_bitmap(data: &[u8]) -> Option<&[u8]> {
+    // Skip the placeable metafile header, then the metafile's own header
+    let mut offset = if data.starts_with(&[0xd7, 0xcd, 0xc6, 0x9a]) {
+        22
+    } else {
+        0
+    };
+    offset += usize::from(u16_at(data, offset + 2)?) * 2;
+    let mut found = None;
+    loop {
+        // Sizes are in 16 bit words
+        let size = (u32_at(data, offset)? as usize).checked_mul(2)?;
+        let function = u16_at(data, offset + 4)?;
+        // META_EOF
+        if function == 0 || size < 6 {
+            break;
+        }
+        let record = slice(data, offset, size)?;
+        let dib = WMF_BITMAP_RECORDS
+            .iter()
+            .find(|(kind, _)| *kind == function)
+            .and_then(|(_, at)| record.get(*at..))
+            .filter(|dib| DibHeader::read(dib).is_some());
+        if let Some(dib) = dib {
+            if found.is_some() {
+                return None;
+            }
+            found = Some(dib);
+        }
+        offset += size;
+    }
+    found
+}
+
+// The header and pixel data of the DIB drawn by an EMF
+fn emf_bitmap(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut offset = 0;
+    let mut found = None;
+    loop {
+        let kind = u32_at(data, offset)?;
+        let size = u32_at(data, offset + 4)? as usize;
+        if kind == EMR_EOF || size < 8 {
+            break;
+        }
+        let record = slice(data, offset, size)?;
+        let at = EMF_BITMAP_RECORDS
+            .iter()
+            .find(|(bitmap_kind, _)| *bitmap_kind == kind)
+            .map(|(_, at)| *at);
+        // A bitblt without a bitmap fills with a brush instead
+        if let Some(at) = at.filter(|at| u32_at(record, at + 4).is_some_and(|len| len > 0)) {
+            let info = slice(
+                record,
+                u32_at(record, at)? as usize,
+                u32_at(record, at + 4)? as usize,
+            )?;
+            let bits = slice(
+                record,
+                u32_at(record, at + 8)? as usize,
+                u32_at(record, at + 12)? as usize,
+            )?;
+            if found.is_some() {
+                return None;
+            }
+            found = Some((info, bits));
+        }
+        offset += size;
+    }
+    found
+}
+
+// The fields of a BITMAPCOREHEADER or BITMAPINFOHEADER that matter here
+struct DibHeader {
+    size: usize,
+    width: usize,
+    height: usize,
+    top_down: bool,
+    bit_count: usize,
+    compression: u32,
+    colors_used: usize,
+    // Bytes in each entry of the color table
+    entry_size: usize,
+}
+
+impl DibHeader {
+    fn read(info: &[u8]) -> Option<Self> {
+        let size = u32_at(info, 0)? as usize;
+        let header = if size == 12 {
+            DibHeader {
+                size,
+                width: usize::from(u16_at(info, 4)?),
+                height: usize::from(u16_at(info, 6)?),
+                top_down: false,
+                bit_count: usize::from(u16_at(info, 10)?),
+                compression: BI_RGB,
+                colors_used: 0,
+                entry_size: 3,
+            }
+        } else if size >= 40 {
+            let height = u32_at(info, 8)? as i32;
+            DibHeader {
+                size,
+                width: u32_at(info, 4)? as i32 as usize,
+                height: height.unsigned_abs() as usize,
+                top_down: height < 0,
+                bit_count: usize::from(u16_at(info, 14)?),
+                compression: u32_at(info, 16)?,
+                colors_used: u32_at(info, 32)? as usize,
+                entry_size: 4,
+            }
+        } else {
+            return None;
+        };
+        Some(header)
+    }
+
+    // The red, green and blue masks of a 16 or 32 bit pixel
+    fn masks(&self, info: &[u8]) -> Option<[u32; 3]> {
+        match (self.compression, self.bit_count) {
+            // Following a BITMAPINFOHEADER, or inside its successors
+            (BI_BITFIELDS, _) => Some([u32_at(info, 40)?, u32_at(info, 44)?, u32_at(info, 48)?]),
+            (_, 16) => Some([0x7c00, 0x03e0, 0x001f]),
+            _ => Some([0x00ff_0000, 0x0000_ff00, 0x0000_00ff]),
+        }
+    }
+
+    // The length of the header and color table together
+    fn info_len(&self) -> usize {
+        let masks = if self.compression == BI_BITFIELDS && self.size == 40 {
+            12
+        } else {
+            0
+        };
+        let colors = match (self.colors_used, self.bit_count) {
+            (0, 1) | (0, 4) | (0, 8) => 1 << self.bit_count,
+            (colors, _) => colors,
+        };
+        self.size + masks + colors * self.entry_size
+    }
+}
+
+struct Bitmap {
+    width: u32,
+    height: u32,
+    // Three bytes a pixel, from the top row down
+    rgb: Vec<u8>,
+}
+
+// Scale the bits of `pixel` under `mask` to a byte
+fn channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let value = u64::from((pixel & mask) >> mask.trailing_zeros());
+    let max = (1u64 << (mask >> mask.trailing_zeros()).count_ones()) - 1;
+    (value * 255 / max) as u8
+}
+
+// Decode a DIB stored with its pixel data straight after its color table
+fn decode_packed_dib(data: &[u8]) -> Option<Bitmap> {
+    let header = DibHeader::read(data)?;
+    let info_len = header.info_len();
+    decode_dib(data.get(..info_len)?, data.get(info_len..)?)
+}
+
+fn decode_dib(info: &[u8], bits: &[u8]) -> Option<Bitmap> {
+    let header = DibHeader::read(info)?;
+    if ![BI_RGB, BI_BITFIELDS].contains(&header.compression)
+        || ![1, 4, 8, 16, 24, 32].contains(&header.bit_count)
+        || header.width == 0
+        || header.height == 0
+    {
+        return None;
+    }
+    let masks = header.masks(info)?;
+    let palette: Vec<&[u8]> = match info.get(header.size..) {
+        Some(table) if header.bit_count <= 8 => table.chunks_exact(header.entry_size).collect(),
+        _ => Vec::new(),
+    };
+    // Rows are padded to a multiple of four bytes
+    let stride = header
+        .width
+        .checked_mul(header.bit_count)?
+        .checked_add(31)?
+        / 32
+        * 4;
+    if bits.len() / stride < header.height {
+        return None;
+    }
+
+    let mut rgb = Vec::with_capacity(header.width * header.height * 3);
+    for y in 0..header.height {
+        // Rows are stored from the bottom up, unless the height was negative
+        let row = if header.top_down {
+            y
+        } else {
+            header.height - 1 - y
+        };
+        let row = &bits[row * stride..][..stride];
+        for x in 0..header.width {
+            let bit = x * header.bit_count;
+            let pixel = match header.bit_count {
+                1 | 4 | 8 => {
+                    let shift = 8 - header.bit_count - bit % 8;
+                    let index = usize::from(row[bit / 8] >> shift) & ((1 << header.bit_count) - 1);
+                    // Colors missing from the table come out black
+                    let entry = palette.get(index).cloned().unwrap_or(&[0, 0, 0]);
+                    rgb.extend_from_slice(&[entry[2], entry[1], entry[0]]);
+                    continue;
+                }
+                16 => u32::from(u16_at(row, bit / 8)?),
+                24 => u32::from_le_bytes([row[bit / 8], row[bit / 8 + 1], row[bit / 8 + 2], 0]),
+                _ => u32_at(row, bit / 8)?,
+            };
+            rgb.extend(masks.iter().map(|mask| channel(pixel, *mask)));
+        }
+    }
+    Some(Bitmap {
+        width: u32::try_from(header.width).ok()?,
+        height: u32::try_from(header.height).ok()?,
+        rgb,
+    })
+}
+
+fn encode_png(bitmap: &Bitmap) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut data, bitmap.width, bitmap.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().ok()?;
+        writer.write_image_data(&bitmap.rgb).ok()?;
+        writer.finish().ok()?;
+    }
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 2x2 bitmap: red and green on the bottom row, blue and white on top
+    fn dib_24() -> Vec<u8> {
+        let mut dib = vec![0u8; 40];
+        dib[0] = 40;
+        dib[4] = 2;
+        dib[8] = 2;
+        dib[12] = 1;
+        dib[14] = 24;
+        // Pixels are BGR, and rows padded to eight bytes
+        dib.extend_from_slice(&[0, 0, 255, 0, 255, 0, 0, 0]);
+        dib.extend_from_slice(&[255, 0, 0, 255, 255, 255, 0, 0]);
+        dib
+    }
+
+    const PIXELS: [u8; 12] = [0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0];
+
+    fn decode(png_data: &[u8]) -> (u32, u32, Vec<u8>) {
+        let decoder = png::Decoder::new(png_data);
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        pixels.truncate(info.buffer_size());
+        (info.width, info.height, pixels)
+    }
+
+    fn convert(format: PictureFormat, data: Vec<u8>) -> Option<(u32, u32, Vec<u8>)> {
+        let picture = Picture {
+            format,
+            data,
+            ..Picture::default()
+        };
+        PngConverter
+            .to_png(&picture)
+            .map(|png_data| decode(&png_data))
+    }
+
+    fn wmf(records: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![1, 0, 9, 0, 0, 3];
+        data.resize(18, 0);
+        for (function, params) in records {
+            let words = (6 + params.len() as u32) / 2;
+            data.extend_from_slice(&words.to_le_bytes());
+            data.extend_from_slice(&function.to_le_bytes());
+            data.extend_from_slice(params);
+        }
+        data.extend_from_slice(&[3, 0, 0, 0, 0, 0]);
+        data
+    }
+
+    fn stretch_dib(dib: Vec<u8>) -> (u16, Vec<u8>) {
+        let mut params = vec![0u8; 22];
+        params.extend(dib);
+        (0x0f43, params)
+    }
+
+    #[test]
+    fn test_dib() {
+        assert_eq!(
+            convert(PictureFormat::Dib, dib_24()),
+            Some((2, 2, PIXELS.to_vec()))
+        );
+        // Top down
+        let mut dib = dib_24();
+        dib[8..12].copy_from_slice(&(-2i32).to_le_bytes());
+        let (_, _, pixels) = convert(PictureFormat::Dib, dib).unwrap();
+        assert_eq!(pixels[..6], PIXELS[6..]);
+
+        // 1 bit, with black and white in its color table
+        let mut dib = vec![0u8; 40];
+        dib[0] = 40;
+        dib[4] = 3;
+        dib[8] = 1;
+        dib[12] = 1;
+        dib[14] = 1;
+        dib.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]);
+        dib.extend_from_slice(&[0b1010_0000, 0, 0, 0]);
+        assert_eq!(
+            convert(PictureFormat::Dib, dib),
+            Some((3, 1, vec![255, 255, 255, 0, 0, 0, 255, 255, 255]))
+        );
+
+        // Run-length encoded, and cut short
+        let mut dib = dib_24();
+        dib[16] = 1;
+        assert_eq!(convert(PictureFormat::Dib, dib), None);
+        let mut dib = dib_24();
+        dib.truncate(50);
+        assert_eq!(convert(PictureFormat::Dib, dib), None);
+    }
+
+    #[test]
+    fn test_wmf() {
+        let data = wmf(&[(0x0201, vec![0, 0, 0, 0]), stretch_dib(dib_24())]);
+        assert_eq!(
+            convert(PictureFormat::Wmf, data),
+            Some((2, 2, PIXELS.to_vec()))
+        );
+
+        // Two bitmaps
+        let data = wmf(&[stretch_dib(dib_24()), stretch_dib(dib_24())]);
+        assert_eq!(convert(PictureFormat::Wmf, data), None);
+        // No bitmap at all
+        let data = wmf(&[(0x0201, vec![0, 0, 0, 0])]);
+        assert_eq!(convert(PictureFormat::Wmf, data), None);
+    }
+
+    #[test]
+    fn test_emf() {
+        let mut data = vec![1, 0, 0, 0, 88, 0, 0, 0];
+        data.resize(88, 0);
+        data[40..44].copy_from_slice(b" EMF");
+
+        // EMR_STRETCHDIBITS, with the header and pixels after the fixed fields
+        let dib = dib_24();
+        let mut record = vec![0u8; 80];
+        record.extend_from_slice(&dib);
+        let fields = [81, record.len() as u32];
+        for (index, value) in fields.iter().enumerate() {
+            record[index * 4..][..4].copy_from_slice(&value.to_le_bytes());
+        }
+        let offsets = [80, 40, 120, dib.len() as u32 - 40];
+        for (index, value) in offsets.iter().enumerate() {
+            record[48 + index * 4..][..4].copy_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&record);
+        data.extend_from_slice(&[14, 0, 0, 0, 20, 0, 0, 0]);
+        data.resize(data.len() + 12, 0);
+
+        // The metafile label doesn't matter
+        assert_eq!(
+            convert(PictureFormat::Wmf, data),
+            Some((2, 2, PIXELS.to_vec()))
+        );
+    }
+}
@@ -6,6 +6,7 @@
 //     Copyright (c) 2008 Microsoft Corporation.  All Rights reserved.
 //
 
+use memchr::{memchr2, memchr3};
 use std;
 use nom;
 
@@ -16,14 +17,13 @@ use nom::is_hex_digit;
 use nom::types::CompleteByteSlice as Input;
 
 // Helper function to convert from Input to &str
-fn input_to_str(s: Input) -> Result<&str, std::str::Utf8Error> {
+fn input_to_str(s: Input<'_>) -> Result<&str, std::str::Utf8Error> {
     std::str::from_utf8(s.0)
 }
 
 // Helper function for converting &str into a signed int
-#[allow(dead_code)]
-fn str_to_int<'a>(s: &'a str, sign: Option<&str>) -> Result<i32, std::num::ParseIntError> {
-    s.parse::<i32>().map(|x| {
+fn str_to_int(s: &str, sign: Option<&str>) -> Result<i64, std::num::ParseIntError> {
+    s.parse::<i64>().map(|x| {
         x * sign.map_or(1, |x| match x {
             "-" => -1,
             "+" => 1,
@@ -32,14 +32,31 @@ fn str_to_int<'a>(s: &'a str, sign: Option<&str>) -> Result<i32, std::num::Parse
     })
 }
 
+/// The most digits read as a control word argument: enough for any 32 bit
+/// value, signed or unsigned
+pub const MAX_ARG_DIGITS: usize = 10;
+
+/// Error code of the failure for an argument with more than `MAX_ARG_DIGITS`
+/// digits.  It's a failure rather than an error so that it isn't recovered
+/// from by trying another parser, such as reading the digits as text.
+pub const ARG_OVERFLOW: u32 = 1;
+
+// Helper function for converting a signed integer's parts into its value,
+// failing if it has too many digits
+fn arg_value((sign, digits): (Option<&str>, &str)) -> Result<i64, ()> {
+    if digits.len() > MAX_ARG_DIGITS {
+        return Err(());
+    }
+    str_to_int(digits, sign).map_err(|_| ())
+}
+
 // Helper function for converting hex &str into a u8
-#[allow(dead_code)]
 fn hex_str_to_int(s: &str) -> Result<u8, std::num::ParseIntError> {
     u8::from_str_radix(s, 16)
 }
 
 // Helper function for parsing signed integers
-named!(pub signed_int_raw<Input, (Option<&str>, &str)>,
+named!(pub signed_int_raw<Input<'_>, (Option<&str>, &str)>,
     pair!(
         opt!(map_res!(tag!("-"), input_to_str)),
         map_res!(digit, input_to_str)
@@ -47,26 +64,28 @@ named!(pub signed_int_raw<Input, (Option<&str>, &str)>,
 );
 
 // Helper function for parsing hexadecimal bytes
-named!(pub hexbyte_raw<Input, &str>,
+// take_while_m_n! expands to a range check that clippy would simplify
+named!(#[allow(clippy::double_comparisons)], pub hexbyte_raw<Input<'_>, &str>,
     map_res!(take_while_m_n!(2, 2, is_hex_digit), input_to_str)
 );
 
-named!(pub hexbyte<Input, u8>,
+named!(pub hexbyte<Input<'_>, u8>,
     map_res!(hexbyte_raw, hex_str_to_int)
 );
 
-named!(signed_int<Input, i32>,
-    map_res!(
-        signed_int_raw,
-        |(sign, value)| { str_to_int(value, sign) }
+named!(signed_int<Input<'_>, i64>,
+    do_parse!(
+        raw: signed_int_raw >>
+        value: return_error!(nom::ErrorKind::Custom(ARG_OVERFLOW), expr_res!(arg_value(raw))) >>
+        (value)
     )
 );
 
-named!(pub control_symbol_raw<Input, char>,
+named!(pub control_symbol_raw<Input<'_>, char>,
     preceded!(tag!("\\"), none_of!("'abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"))
 );
 
-named!(pub control_word_raw<Input, (&str, Option<i32>)>,
+named!(pub control_word_raw<Input<'_>, (&str, Option<i64>)>,
     do_parse!(
         tag!("\\") >>
         name: map_res!(nom::alpha, input_to_str) >>
@@ -78,16 +97,11 @@ named!(pub control_word_raw<Input, (&str, Option<i32>)>,
 
 // Sample.rtf's contents and rendering suggest that \'XX *doesn't* absorb a trailing space
 // like other control words do
-named!(pub control_word_hexbyte_raw<Input, (&str, Option<i32>)>,
-    do_parse!(
-        tag!("\\") >>
-        name: map_res!(tag!("'"), input_to_str) >>
-        arg: map!(hexbyte, |x| Some(x as i32)) >>
-        (name, arg)
-    )
+named!(pub control_word_hexbyte_raw<Input<'_>, u8>,
+    preceded!(tag!("\\'"), hexbyte)
 );
 
-named!(pub control_bin_raw<Input, &[u8]>,
+named!(pub control_bin_raw<Input<'_>, &[u8]>,
     do_parse!(
         tag!("\\bin") >>
         len: opt!(
@@ -98,7 +112,7 @@ named!(pub control_bin_raw<Input, &[u8]>,
                 ), |(s, _)| s
             )
         ) >>
-        out: take!(len.unwrap_or(0)) >>
+        out: take!(len.unwrap_or(0) as usize) >>
         (&out)
     )
 );
@@ -107,36 +121,94 @@ named!(pub control_bin_raw<Input, &[u8]>,
 // or a CRLF (carriage return/line feed), the reader assumes that the character is plain text and
 // writes the character to the current destination using the current formatting properties.
 // See section "Conventions of an RTF Reader"
-named!(pub rtf_text_raw<Input, &[u8]>,
-    map!(
-        recognize!(many0!(alt!(none_of!("\\}{\r\n")))),
-        |i| i.0
-    )
-);
+//
+// Text is the bulk of most documents, so the end of a run is found with memchr's vectorized
+// searches rather than a byte at a time.  The search for line breaks only covers the text up to
+// the next brace or backslash.
+pub fn rtf_text_raw(input: Input<'_>) -> nom::IResult<Input<'_>, &[u8]> {
+    let bytes = input.0;
+    let end = memchr3(b'\\', b'{', b'}', bytes).unwrap_or(bytes.len());
+    let end = memchr2(b'\r', b'\n', &bytes[..end]).unwrap_or(end);
+    Ok((Input(&bytes[end..]), &bytes[..end]))
+}
 
-named!(pub start_group_raw<Input, char>,
+named!(pub start_group_raw<Input<'_>, char>,
     char!('{')
 );
 
-named!(pub end_group_raw<Input, char>,
+named!(pub end_group_raw<Input<'_>, char>,
     char!('}')
 );
 
 // Oddly enough, the copy of the RTF spec we have has at least one carriage return without its
 // matching line feed, so it looks like we need to be more permissive about newlines than the spec
 // says.
-named!(pub newline_raw<Input, &[u8]>,
+named!(pub newline_raw<Input<'_>, &[u8]>,
     map!(
         alt!(crlf | tag!("\n") | tag!("\r")),
         |i| i.0
     )
 );
 
+/// A token as the raw parsers read it, borrowing its payload from the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RawToken<'i> {
+    HexByte(u8),
+    Symbol(char),
+    Bin(&'i [u8]),
+    Word(&'i str, Option<i64>),
+    StartGroup,
+    EndGroup,
+    Newline,
+    Text(&'i [u8]),
+}
+
+// Ordering here is important. Plain text is all content that isn't something else:
+// If the next unparsed character is anything other than an opening brace ({), closing brace (}),
+// backslash (\), or a CRLF (carriage return/line feed), the reader assumes that the character is
+// plain text and writes the character to the current destination using the current formatting
+// properties.  Finally, a control hexbyte is a special case of a control symbol, but needs to be
+// handled specially, so hexbyte should be tested for before control symbols.
+//
+// See section "Conventions of an RTF Reader" in the RTF specification.
+named!(pub(crate) read_raw_token<Input<'_>, RawToken<'_>>,
+    alt!(
+        map!(control_word_hexbyte_raw, RawToken::HexByte)
+            | map!(control_symbol_raw, RawToken::Symbol)
+            | map!(control_bin_raw, RawToken::Bin)
+            | map!(control_word_raw, |(name, arg)| RawToken::Word(name, arg))
+            | map!(start_group_raw, |_| RawToken::StartGroup)
+            | map!(end_group_raw, |_| RawToken::EndGroup)
+            | map!(newline_raw, |_| RawToken::Newline)
+            | map!(rtf_text_raw, RawToken::Text)
+    )
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    named!(signed_ints<Input, Vec<i32> >, separated_list_complete!(tag!(","), signed_int));
+    named!(signed_ints<Input<'_>, Vec<i64> >, separated_list_complete!(tag!(","), signed_int));
+
+    #[test]
+    fn test_rtf_text() {
+        let cases: &[(&[u8], &[u8])] = &[
+            (b"plain text", b"plain text"),
+            (b"text\\par", b"text"),
+            (b"a{b", b"a"),
+            (b"a}b", b"a"),
+            (b"line\r\nnext", b"line"),
+            (b"line\nnext\\b", b"line"),
+            (b"\\b text", b""),
+            (b"\xe9t\xe9 \x00", b"\xe9t\xe9 \x00"),
+            (b"", b""),
+        ];
+        for (input, text) in cases {
+            let (rest, found) = rtf_text_raw(Input(input)).unwrap();
+            assert_eq!(found, *text);
+            assert_eq!(rest.0, &input[text.len()..]);
+        }
+    }
 
     #[test]
     fn test_signed_int() {
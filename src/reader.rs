@@ -0,0 +1,274 @@
+// Event-based document reader
+//
+// Reads a token stream as a sequence of events, the way a SAX parser reads
+// XML: group boundaries, decoded text, paragraph and section ends, fields and
+// pictures.  Formatting control words aren't events of their own; the
+// formatting in effect is tracked, and can be read between events.
+//
+// Destinations that aren't document text, like the font table, are skipped,
+// and appear only as a `GroupStart` and `GroupEnd`.
+//
+
+use document::{CharFormat, ParaFormat};
+use pict::{pictures, Picture};
+use state::StateTracker;
+use text::{Decoded, Decoder};
+use tokenizer::Token;
+
+/// Something read from a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// A group starts, and the destination it starts, if any
+    GroupStart(Option<String>),
+    GroupEnd,
+    /// Document text, with `\line` as `'\n'` and `\tab` as `'\t'`
+    Text(String),
+    /// `\par`
+    ParagraphEnd,
+    /// `\sect`
+    SectionEnd,
+    /// A `{\field}` group starts
+    FieldStart,
+    /// The instruction of the current field, from `{\*\fldinst}`
+    FieldInstruction(String),
+    /// The `{\field}` group ends
+    FieldEnd,
+    /// A `{\pict}` group, decoded
+    Picture(Picture),
+    /// A control word in the document text that's not formatting, like
+    /// `\cell` or `\page`
+    ControlWord(String, Option<i32>),
+}
+
+/// Reads the events of a token stream
+///
+/// `Reader` is an iterator of `Event`s, and the formatting in effect after
+/// each event can be read from it before asking for the next.
+#[derive(Debug)]
+pub struct Reader<'t> {
+    tokens: &'t [Token],
+    index: usize,
+    tracker: StateTracker,
+    // Depths of the open {\field} groups
+    fields: Vec<usize>,
+    // The GroupEnd of a skipped destination
+    pending: Option<Event>,
+}
+
+impl<'t> Reader<'t> {
+    pub fn new(tokens: &'t [Token]) -> Self {
+        Reader {
+            tokens,
+            index: 0,
+            tracker: StateTracker::new(),
+            fields: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// The character formatting in effect
+    pub fn char_format(&self) -> &CharFormat {
+        self.tracker.char_format()
+    }
+
+    /// The paragraph formatting in effect
+    pub fn para_format(&self) -> &ParaFormat {
+        self.tracker.para_format()
+    }
+
+    /// Index of the next token to be read
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    fn read(&mut self) -> Option<Decoded<'t>> {
+        let token = &self.tokens[self.index];
+        self.index += 1;
+        self.tracker.read(token)
+    }
+
+    // Read up to the end of the group at `depth`, returning the decoded text
+    // of the rest of the group if `text` is set
+    fn skip_group(&mut self, depth: usize, text: bool) -> String {
+        let mut decoder = Decoder::with_state(self.tracker.decoder().state().clone());
+        let mut skipped = String::new();
+        while self.index < self.tokens.len() && self.tracker.depth() >= depth {
+            let token = &self.tokens[self.index];
+            self.read();
+            if let (true, Decoded::Text(t)) = (text, decoder.token(token)) {
+                skipped.push_str(&t);
+            }
+        }
+        skipped
+    }
+
+    fn group_start(&mut self, start: usize) -> Event {
+        let depth = self.tracker.depth();
+        let mut word = self.index;
+        if self.tokens.get(word) == Some(&Token::ControlSymbol('*')) {
+            word += 1;
+        }
+        let name = match self.tokens.get(word) {
+            Some(Token::ControlWord { name, .. }) => name.as_str(),
+            _ => return Event::GroupStart(None),
+        };
+        while self.index <= word {
+            self.read();
+        }
+        let destination = self.tracker.decoder().destination() == Some(name);
+        match name {
+            "field" => {
+                self.fields.push(depth);
+                return Event::FieldStart;
+            }
+            "fldinst" => {
+                let instruction = self.skip_group(depth, true);
+                return Event::FieldInstruction(instruction);
+            }
+            "pict" => {
+                self.skip_group(depth, false);
+                if let Some(picture) = pictures(&self.tokens[start..self.index]).pop() {
+                    return Event::Picture(picture);
+                }
+                self.pending = Some(Event::GroupEnd);
+            }
+            // The picture of a shape, which older readers skip
+            "shppict" => {}
+            _ if !self.tracker.in_text() => {
+                self.skip_group(depth, false);
+                self.pending = Some(Event::GroupEnd);
+            }
+            _ => {}
+        }
+        Event::GroupStart(Some(name.to_string()).filter(|_| destination))
+    }
+}
+
+impl<'t> Iterator for Reader<'t> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        while self.index < self.tokens.len() {
+            let start = self.index;
+            let depth = self.tracker.depth();
+            let token = &self.tokens[start];
+            let decoded = self.read();
+            let event = match token {
+                Token::StartGroup => self.group_start(start),
+                Token::EndGroup if self.fields.last() == Some(&depth) => {
+                    self.fields.pop();
+                    Event::FieldEnd
+                }
+                Token::EndGroup => Event::GroupEnd,
+                _ => match decoded {
+                    Some(Decoded::Text(text)) => Event::Text(text),
+                    Some(Decoded::Word(name, arg)) => match name {
+                        "par" => Event::ParagraphEnd,
+                        "sect" => Event::SectionEnd,
+                        "line" => Event::Text("\n".to_string()),
+                        "tab" => Event::Text("\t".to_string()),
+                        _ => Event::ControlWord(name.to_string(), arg),
+                    },
+                    _ => continue,
+                },
+            };
+            return Some(event);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pict::PictureFormat;
+    use tokenizer::parse;
+
+    fn events(rtf: &[u8]) -> Vec<Event> {
+        Reader::new(&parse(rtf).unwrap()).collect()
+    }
+
+    fn text(text: &str) -> Event {
+        Event::Text(text.to_string())
+    }
+
+    #[test]
+    fn test_events() {
+        let events = events(
+            br#"{\rtf1\ansi{\fonttbl{\f0 Arial;}}\pard\b Caf\'e9\b0\tab x\par{\field{\*\fldinst HYPERLINK "http://a"}{\fldrslt link}}\sect\page}"#,
+        );
+        assert_eq!(
+            events,
+            vec![
+                Event::GroupStart(Some("rtf".to_string())),
+                Event::ControlWord("ansi".to_string(), None),
+                Event::GroupStart(Some("fonttbl".to_string())),
+                Event::GroupEnd,
+                text("Caf"),
+                text("\u{e9}"),
+                text("\t"),
+                text("x"),
+                Event::ParagraphEnd,
+                Event::FieldStart,
+                Event::FieldInstruction("HYPERLINK \"http://a\"".to_string()),
+                Event::GroupStart(Some("fldrslt".to_string())),
+                text("link"),
+                Event::GroupEnd,
+                Event::FieldEnd,
+                Event::SectionEnd,
+                Event::ControlWord("page".to_string(), None),
+                Event::GroupEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_formatting_between_events() {
+        let tokens = parse(br#"{\rtf1 a{\b\qc b}c}"#).unwrap();
+        let mut reader = Reader::new(&tokens);
+        let mut bold = Vec::new();
+        while let Some(event) = reader.next() {
+            if let Event::Text(text) = event {
+                bold.push((text, reader.char_format().bold));
+            }
+        }
+        assert_eq!(
+            bold,
+            vec![
+                ("a".to_string(), false),
+                ("b".to_string(), true),
+                ("c".to_string(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_picture_events() {
+        let events = events(
+            br#"{\rtf1{\*\shppict{\pict\pngblip\picw1\pich1 89504e47}}{\*\nonshppict{\pict\wmetafile8 0102}}x}"#,
+        );
+        assert_eq!(events.len(), 8);
+        assert_eq!(events[1], Event::GroupStart(Some("shppict".to_string())));
+        match &events[2] {
+            Event::Picture(picture) => {
+                assert_eq!(picture.format, PictureFormat::Png);
+                assert_eq!(picture.data, b"\x89PNG".to_vec());
+            }
+            event => panic!("expected a picture, got {:?}", event),
+        }
+        assert_eq!(events[3], Event::GroupEnd);
+        assert_eq!(
+            events[4..],
+            [
+                Event::GroupStart(Some("nonshppict".to_string())),
+                Event::GroupEnd,
+                text("x"),
+                Event::GroupEnd
+            ]
+        );
+    }
+}
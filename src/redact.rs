@@ -0,0 +1,398 @@
+// Redaction
+//
+// Blacks out text in a document.  The text is replaced where it's stored,
+// not covered over, and the places besides the body that hold copies of it
+// are scrubbed too: field instructions, bookmark names, form fields,
+// document info and variables, comments, headers and footers, the Unicode
+// copies of `\upr` text, and the authors and deleted text of tracked
+// changes.  Redacting what's shown and leaving a copy in any of those is how
+// redacted documents leak.
+//
+
+use std::ops::Range;
+
+use groups::GroupIndex;
+pub use search::Pattern;
+use search::{find_ranges, find_text};
+use template::replace_pieces;
+use text::{piece_text, pieces, range_pieces, Piece, Unit, Whitespace};
+use tokenizer::{group_contents, group_destination, group_end, Token};
+
+/// What redacted text is replaced with
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ReplacementStyle {
+    /// A black box, U+2588 FULL BLOCK, for each character
+    #[default]
+    Block,
+    /// A fixed character for each character, like `X`
+    Char(char),
+    /// A fixed text for each stretch of redacted text, like `[REDACTED]`,
+    /// which hides its length too
+    Text(String),
+}
+
+// Destinations holding text that isn't part of the body, but can hold
+// copies of it
+const SCRUBBED_DESTINATIONS: &[&str] = &[
+    // Fields and bookmarks
+    "fldinst",
+    "bkmkstart",
+    "bkmkend",
+    "ffname",
+    "ffdeftext",
+    "ffhelptext",
+    "ffstattext",
+    "txfieldtext",
+    // Document info and properties
+    "title",
+    "subject",
+    "author",
+    "operator",
+    "manager",
+    "company",
+    "category",
+    "keywords",
+    "comment",
+    "doccomm",
+    "hlinkbase",
+    "staticval",
+    "docvar",
+    // Comments
+    "annotation",
+    "atnauthor",
+    "atnid",
+    // Headers, footers and notes
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+    "footnote",
+    // The Unicode copy of text in a `\upr` group
+    "ud",
+    // The authors of tracked changes
+    "revtbl",
+];
+
+// What's looked for outside the body: the pattern, unless it's ranges of
+// the body, and the text redacted from the body
+struct Needles<'p> {
+    pattern: Option<&'p Pattern>,
+    literals: Vec<String>,
+}
+
+impl<'p> Needles<'p> {
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let mut found = match self.pattern {
+            Some(pattern) => find_ranges(pattern, text),
+            None => Vec::new(),
+        };
+        for literal in &self.literals {
+            found.extend(find_text(text, literal));
+        }
+        found
+    }
+}
+
+// Sort ranges and join those that overlap
+fn coalesce(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut joined: Vec<Range<usize>> = Vec::new();
+    for range in ranges.into_iter().filter(|range| !range.is_empty()) {
+        match joined.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => joined.push(range),
+        }
+    }
+    joined
+}
+
+fn replacement(style: &ReplacementStyle, chars: usize, first: bool) -> String {
+    match style {
+        ReplacementStyle::Block => "\u{2588}".repeat(chars),
+        ReplacementStyle::Char(c) => c.to_string().repeat(chars),
+        ReplacementStyle::Text(text) if first => text.clone(),
+        ReplacementStyle::Text(_) => String::new(),
+    }
+}
+
+// The replacements for ranges of text, as ranges of pieces.  Paragraph
+// breaks, tabs and the like in a range are kept, so the replacements are
+// the stretches of characters between them.  A stretch also ends where the
+// group its characters are in does, so each group's text is replaced in
+// that group: text isn't moved into or out of a field result, say.
+fn replacements(
+    groups: &GroupIndex,
+    pieces: &[Piece],
+    chars: &[(usize, usize)],
+    ranges: &[Range<usize>],
+    style: &ReplacementStyle,
+) -> Vec<(Range<usize>, String)> {
+    let mut replacements = Vec::new();
+    for range in ranges {
+        let (first, last) = match range_pieces(chars, range) {
+            Some(pieces) => pieces,
+            None => continue,
+        };
+        // The stretches of characters, and how many there are in each
+        let mut stretches = Vec::new();
+        let mut stretch: Option<(usize, usize)> = None;
+        let group = |index: usize| groups.enclosing_group(pieces[index].token).map(|g| g.start);
+        for index in first..=last + 1 {
+            if let Some((start, count)) = stretch {
+                if index <= last && group(index) != group(start) {
+                    stretches.push((start..index, count));
+                    stretch = None;
+                }
+            }
+            match pieces.get(index).map(|piece| piece.unit) {
+                Some(Unit::Char(_)) if index <= last => {
+                    let (start, count) = stretch.unwrap_or((index, 0));
+                    stretch = Some((start, count + 1));
+                }
+                Some(Unit::Fallback) if index <= last => {}
+                _ => {
+                    if let Some((start, count)) = stretch.take() {
+                        stretches.push((start..index, count));
+                    }
+                }
+            }
+        }
+        // Only the first stretch of a range gets a fixed text
+        for (index, (range, count)) in stretches.into_iter().enumerate() {
+            replacements.push((range, replacement(style, count, index == 0)));
+        }
+    }
+    replacements
+}
+
+// Redact the text that `find` finds in a stretch of tokens, as the body of
+// a document or destination, and then the destinations in it that aren't
+// part of its text
+fn redact_text<F: Fn(&str) -> Vec<Range<usize>>>(
+    tokens: &[Token],
+    find: F,
+    needles: &Needles,
+    style: &ReplacementStyle,
+) -> Vec<Token> {
+    let pieces = pieces(tokens);
+    let (text, chars) = piece_text(&pieces, Whitespace::Normalize);
+    let ranges = coalesce(find(&text));
+    let groups = GroupIndex::new(tokens);
+    let replacements = replacements(&groups, &pieces, &chars, &ranges, style);
+    let redacted = replace_pieces(tokens, &pieces, replacements);
+    scrub(&redacted, needles, style)
+}
+
+// Redact the destinations that can hold copies of the body's text
+fn scrub(tokens: &[Token], needles: &Needles, style: &ReplacementStyle) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        if let Some((name, _, word)) = group_destination(tokens, index) {
+            if tokens[index] == Token::StartGroup && SCRUBBED_DESTINATIONS.contains(&name) {
+                let end = group_end(tokens, index);
+                let contents = group_contents(tokens, word, end);
+                out.extend_from_slice(&tokens[index..=word]);
+                out.extend(redact_text(
+                    contents,
+                    |text| needles.find(text),
+                    needles,
+                    style,
+                ));
+                if word + 1 + contents.len() < end {
+                    out.push(Token::EndGroup);
+                }
+                index = end;
+                continue;
+            }
+        }
+        out.push(tokens[index].clone());
+        index += 1;
+    }
+    out
+}
+
+/// Redact text from a document, replacing it where it's stored
+///
+/// The pattern is looked for in the text of the document, as `extract_text`
+/// extracts it, and each match is replaced as `style` says, in the
+/// formatting the text had.  Paragraph breaks and tabs inside a match are
+/// kept.
+///
+/// The text redacted from the body is then redacted wherever else it
+/// appears: elsewhere in the body, including tracked deletions, and in
+/// field instructions, bookmark names, form fields, document info,
+/// properties and variables, comments, headers, footers, footnotes, the
+/// Unicode copies of `\upr` text and the authors of tracked changes.  A
+/// `Text` or `Regex` pattern is looked for in all of those as well.  Text in
+/// pictures, embedded objects and shape properties isn't looked at.
+///
+/// ```
+/// use rtf_grimoire::redact::{redact, Pattern, ReplacementStyle};
+/// use rtf_grimoire::{extract_text, parse};
+///
+/// let tokens = parse(b"{\\rtf1{\\info{\\title Jane's file}} Jane was {\\b here}.}").unwrap();
+/// let pattern = Pattern::Text("Jane".to_string());
+/// let redacted = redact(&tokens, &pattern, &ReplacementStyle::Char('X'));
+/// assert_eq!(extract_text(&redacted), "XXXX was here.");
+/// ```
+pub fn redact(tokens: &[Token], pattern: &Pattern, style: &ReplacementStyle) -> Vec<Token> {
+    let pieces = pieces(tokens);
+    let (text, _) = piece_text(&pieces, Whitespace::Normalize);
+    let mut literals: Vec<String> = coalesce(find_ranges(pattern, &text))
+        .into_iter()
+        .filter_map(|range| text.get(range))
+        .map(str::trim)
+        .filter(|literal| !literal.is_empty())
+        .map(str::to_string)
+        .collect();
+    // Longer text first, so it's redacted whole rather than around the
+    // shorter text inside it
+    literals.sort_by_key(|literal| std::cmp::Reverse(literal.len()));
+    literals.dedup();
+    let needles = Needles {
+        pattern: match pattern {
+            Pattern::Ranges(_) => None,
+            _ => Some(pattern),
+        },
+        literals,
+    };
+    redact_text(
+        tokens,
+        |text| {
+            let mut found = find_ranges(pattern, text);
+            found.extend(needles.find(text));
+            found
+        },
+        &needles,
+        style,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    const DOC: &[u8] = b"{\\rtf1\\ansi{\\*\\revtbl{Unknown;}{Jane Roe;}}{\\info{\\author Jane Roe}}\
+        {\\header Jane Roe, confidential\\par}\
+        Call {\\b Jane} Roe at {\\field{\\*\\fldinst HYPERLINK \"mailto:jane@example.com\"}{\\fldrslt jane@example.com}}\
+        {\\*\\bkmkstart Jane_Roe}{\\*\\bkmkend Jane_Roe}\\par\
+        {\\deleted\\revauth1 Roe, Jane}\\tab Jane\\par Roe.\\par}";
+
+    fn rtf(tokens: &[Token]) -> String {
+        String::from_utf8(write_rtf(tokens, &WriteOptions::default()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_redact_text() {
+        let tokens = parse(DOC).unwrap();
+        let pattern = Pattern::Text("Jane".to_string());
+        let redacted = rtf(&redact(&tokens, &pattern, &ReplacementStyle::Char('X')));
+        assert!(!redacted.contains("Jane"), "{}", redacted);
+        assert!(redacted.contains("Call {\\b XXXX} Roe at"));
+        assert!(redacted.contains("{\\*\\bkmkstart XXXX_Roe}{\\*\\bkmkend XXXX_Roe}"));
+        assert!(redacted.contains("{\\*\\revtbl{Unknown;}{XXXX Roe;}}"));
+        assert!(redacted.contains("mailto:jane@example.com"));
+    }
+
+    #[test]
+    fn test_redact_ranges() {
+        let tokens = parse(DOC).unwrap();
+        let text = ::text::extract_text(&tokens);
+        let start = text.find("Jane").unwrap();
+        let name = start..start + "Jane Roe".len();
+        let pattern = Pattern::Ranges(vec![name]);
+        let style = ReplacementStyle::Text("[REDACTED]".to_string());
+        let redacted = redact(&tokens, &pattern, &style);
+        let redacted_rtf = rtf(&redacted);
+        // The name goes from the tracked changes and header as well as the
+        // body, but its parts on their own are left
+        assert!(!redacted_rtf.contains("Jane Roe"), "{}", redacted_rtf);
+        assert!(
+            redacted_rtf.contains("Call {\\b[REDACTED]} at"),
+            "{}",
+            redacted_rtf
+        );
+        assert!(redacted_rtf.contains("{\\*\\revtbl{Unknown;}{[REDACTED];}}"));
+        assert!(redacted_rtf.contains("{\\header[REDACTED], confidential\\par}"));
+        assert!(redacted_rtf.contains("\\tab Jane\\par Roe."));
+    }
+
+    #[test]
+    fn test_redact_blocks() {
+        let tokens = parse(b"{\\rtf1\\uc0 a secret\\par word b}").unwrap();
+        let pattern = Pattern::Text("secret\nword".to_string());
+        let redacted = redact(&tokens, &pattern, &ReplacementStyle::Block);
+        assert_eq!(
+            ::text::extract_text(&redacted),
+            "a \u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\n\u{2588}\u{2588}\u{2588}\u{2588} b"
+        );
+        assert_eq!(
+            rtf(&redacted),
+            "{\\rtf1\\uc0 a \\u9608\\u9608\\u9608\\u9608\\u9608\\u9608\\par\\u9608\\u9608\\u9608\\u9608  b}"
+        );
+    }
+
+    #[test]
+    fn test_redact_surrogate_pairs() {
+        // Both halves of a pair go with the character they stand for
+        let tokens = parse(b"{\\rtf1 a\\u-10179?\\u-8704?b}").unwrap();
+        let pattern = Pattern::Text("\u{1f600}".to_string());
+        let redacted = redact(&tokens, &pattern, &ReplacementStyle::Char('X'));
+        assert_eq!(rtf(&redacted), "{\\rtf1 aXb}");
+    }
+
+    #[test]
+    fn test_redact_copies() {
+        let pattern = Pattern::Text("Jane".to_string());
+        let style = ReplacementStyle::Char('X');
+        for (rtf_in, expected) in [
+            (
+                &b"{\\rtf1 {\\upr{Jane}{\\*\\ud{Jane}}}}"[..],
+                "{\\rtf1{\\upr{XXXX}{\\*\\ud{XXXX}}}}",
+            ),
+            (
+                b"{\\rtf1 {\\*\\docvar {client}{Jane}} Jane}",
+                "{\\rtf1{\\*\\docvar{client}{XXXX}} XXXX}",
+            ),
+            (
+                b"{\\rtf1 {\\*\\txfieldtext Jane} Jane}",
+                "{\\rtf1{\\*\\txfieldtext XXXX} XXXX}",
+            ),
+        ] {
+            let tokens = parse(rtf_in).unwrap();
+            assert_eq!(rtf(&redact(&tokens, &pattern, &style)), expected);
+        }
+    }
+
+    #[test]
+    fn test_redact_across_groups() {
+        // Matches next to each other are joined, but each group's part is
+        // replaced in that group
+        let tokens =
+            parse(b"{\\rtf1 Jane{\\field{\\*\\fldinst HYPERLINK \"x\"}{\\fldrslt Jane}}}").unwrap();
+        let pattern = Pattern::Text("Jane".to_string());
+        let redacted = redact(&tokens, &pattern, &ReplacementStyle::Char('X'));
+        assert_eq!(
+            rtf(&redacted),
+            "{\\rtf1 XXXX{\\field{\\*\\fldinst HYPERLINK \"x\"}{\\fldrslt XXXX}}}"
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_redact_regex() {
+        let tokens = parse(DOC).unwrap();
+        let pattern = Pattern::Regex(::regex::Regex::new(r"[a-z]+@example\.com").unwrap());
+        let redacted = rtf(&redact(&tokens, &pattern, &ReplacementStyle::Char('*')));
+        assert!(!redacted.contains("example.com"), "{}", redacted);
+        assert!(redacted.contains("HYPERLINK \"mailto:****************\""));
+    }
+}
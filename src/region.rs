@@ -0,0 +1,175 @@
+// Region-of-interest parsing
+//
+// Re-parses a single byte range of a document (typically one group, located
+// by an earlier spanned parse) without tokenizing the rest of the document.
+// The reader state in effect at the start of the range can't be recovered
+// from the range itself, so it's captured up front as a `RegionContext` and
+// supplied by the caller.
+//
+
+use std;
+use std::ops::Range;
+
+use nom::types::CompleteByteSlice as Input;
+
+use text::{extract_text_resuming, Decoder, TextOptions};
+use tokenizer::{next_token, ParseError, SpannedToken, Token};
+
+/// Reader state in effect at a point in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionContext {
+    /// Number of groups open at this point
+    pub depth: usize,
+    /// The innermost destination containing this point, if any
+    pub destination: Option<String>,
+    /// Whether this point is inside a destination that isn't document text
+    pub ignored: bool,
+    /// The `\uc` value in effect
+    pub uc: usize,
+}
+
+impl Default for RegionContext {
+    fn default() -> Self {
+        RegionContext {
+            depth: 0,
+            destination: None,
+            ignored: false,
+            uc: 1,
+        }
+    }
+}
+
+impl RegionContext {
+    /// Capture the reader state immediately before `tokens[index]`
+    pub fn capture(tokens: &[Token], index: usize) -> Self {
+        let mut decoder = Decoder::new();
+        for token in &tokens[..std::cmp::min(index, tokens.len())] {
+            decoder.token(token);
+        }
+        RegionContext {
+            depth: decoder.depth(),
+            destination: decoder.destination().map(String::from),
+            ignored: decoder.ignored(),
+            uc: decoder.uc(),
+        }
+    }
+}
+
+/// A range of a document, re-parsed along with its context
+#[derive(Debug, PartialEq)]
+pub struct Region {
+    pub context: RegionContext,
+    /// Tokens in the range, with spans relative to the whole document
+    pub tokens: Vec<SpannedToken>,
+}
+
+impl Region {
+    /// Extract the text of the region, as it would appear in the document text
+    pub fn text(&self, options: &TextOptions) -> String {
+        extract_text_resuming(
+            self.tokens.iter().map(|spanned| &spanned.token),
+            options,
+            self.context.uc,
+            self.context.ignored,
+        )
+    }
+}
+
+/// Tokenize only `range` of a document
+///
+/// Spans of the returned tokens are relative to the start of `bytes`, so they
+/// can be used interchangeably with spans from a full `parse_spanned`.
+///
+/// Panics if `range` is out of bounds for `bytes`.
+pub fn parse_range(bytes: &[u8], range: Range<usize>) -> Result<Vec<SpannedToken>, ParseError> {
+    let region = &bytes[range.clone()];
+    let mut tokens = Vec::new();
+    let mut input = Input(region);
+    while let Some((rest, token, _)) = next_token(region, input)? {
+        let start = range.start + region.len() - input.len();
+        let end = range.start + region.len() - rest.len();
+        tokens.push(SpannedToken::new(bytes, token, start..end));
+        input = rest;
+    }
+    Ok(tokens)
+}
+
+/// Re-parse `range` of a document, given the context captured for its start
+pub fn parse_region(
+    bytes: &[u8],
+    range: Range<usize>,
+    context: RegionContext,
+) -> Result<Region, ParseError> {
+    parse_range(bytes, range).map(|tokens| Region { context, tokens })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::{parse, parse_spanned};
+
+    #[test]
+    fn test_parse_range_matches_full_parse() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let full = parse_spanned(test_bytes).unwrap();
+        let (first, last) = (&full[40], &full[80]);
+        let tokens = parse_range(test_bytes, first.span.start..last.span.end).unwrap();
+        assert_eq!(tokens.as_slice(), &full[40..81]);
+    }
+
+    #[test]
+    fn test_parse_range_numeric_overflow() {
+        let bytes = b"{\\rtf1 a\\fs99999999999 b}";
+        let full = parse_spanned(bytes).unwrap();
+        assert_eq!(parse_range(bytes, 1..bytes.len()).unwrap(), &full[1..]);
+    }
+
+    #[test]
+    fn test_capture_context() {
+        let tokens = parse(b"{\\rtf1\\uc2{\\info{\\title T}}{\\*\\foo{x}}Body}").unwrap();
+        // Inside the title
+        let context = RegionContext::capture(&tokens, 7);
+        assert_eq!(context.depth, 3);
+        assert_eq!(context.destination, Some("title".to_string()));
+        assert!(context.ignored);
+        assert_eq!(context.uc, 2);
+        // Inside the {x} group of the \*\foo destination
+        let context = RegionContext::capture(&tokens, 15);
+        assert_eq!(context.destination, Some("foo".to_string()));
+        assert!(context.ignored);
+        // Body text, where the only destination is the document itself
+        let context = RegionContext::capture(&tokens, 18);
+        assert_eq!(context.depth, 1);
+        assert_eq!(context.destination, Some("rtf".to_string()));
+        assert!(!context.ignored);
+    }
+
+    #[test]
+    fn test_region_text_uses_context() {
+        let bytes = b"{\\rtf1\\uc2 {\\b caf\\u233\\'e9\\'65!}}";
+        let spanned = parse_spanned(bytes).unwrap();
+        let tokens: Vec<Token> = parse(bytes).unwrap();
+        let start = spanned
+            .iter()
+            .position(|t| t.token == Token::StartGroup && t.span.start > 0);
+        let start = start.unwrap();
+        let end = spanned.len() - 2;
+
+        let context = RegionContext::capture(&tokens, start);
+        let region = parse_region(
+            bytes,
+            spanned[start].span.start..spanned[end].span.end,
+            context,
+        )
+        .unwrap();
+        assert_eq!(region.text(&TextOptions::default()), "caf\u{e9}!");
+        // Without the context, the second fallback byte would be shown
+        let region = parse_region(
+            bytes,
+            spanned[start].span.start..spanned[end].span.end,
+            RegionContext::default(),
+        )
+        .unwrap();
+        assert_eq!(region.text(&TextOptions::default()), "caf\u{e9}e!");
+    }
+}
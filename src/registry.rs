@@ -0,0 +1,1870 @@
+// Control word registry
+//
+// Every control word in the index of the RTF 1.9.1 specification, with the
+// kind of control word it is there, so that documents can be checked
+// against the vocabulary the spec defines.  The words of Word's extensions
+// that postdate 1.9.1, and those of other writers, aren't here.
+//
+
+/// What kind of control word the spec says a word is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordKind {
+    /// A word that sets a property, ignoring any argument
+    Flag,
+    /// A word that turns a property on, or off with an argument of 0
+    Toggle,
+    /// A word that requires an argument
+    Value,
+    /// A word that stands for a special character
+    Symbol,
+    /// A word that starts a destination
+    Destination,
+}
+
+// The control symbols of the spec, a backslash and a character other than a
+// letter.  A backslash before a line break is an old way of writing \par.
+const SYMBOLS: &[char] = &[
+    '\'', '-', '*', ':', '\\', '_', '{', '|', '}', '~', '\n', '\r',
+];
+
+// Control words, in byte order for binary searching
+const WORDS: &[(&str, WordKind)] = &[
+    ("ApplyBrkRules", WordKind::Flag),
+    ("ab", WordKind::Toggle),
+    ("absh", WordKind::Value),
+    ("abslock", WordKind::Flag),
+    ("absnoovrlp", WordKind::Toggle),
+    ("absw", WordKind::Value),
+    ("acaps", WordKind::Toggle),
+    ("acccircle", WordKind::Toggle),
+    ("acccomma", WordKind::Toggle),
+    ("accdot", WordKind::Toggle),
+    ("accnone", WordKind::Toggle),
+    ("accunderdot", WordKind::Toggle),
+    ("acf", WordKind::Value),
+    ("additive", WordKind::Flag),
+    ("adeff", WordKind::Value),
+    ("adeflang", WordKind::Value),
+    ("adjustright", WordKind::Flag),
+    ("adn", WordKind::Value),
+    ("aenddoc", WordKind::Flag),
+    ("aendnotes", WordKind::Flag),
+    ("aexpnd", WordKind::Value),
+    ("af", WordKind::Value),
+    ("afelev", WordKind::Flag),
+    ("afs", WordKind::Value),
+    ("aftnbj", WordKind::Flag),
+    ("aftncn", WordKind::Destination),
+    ("aftnnalc", WordKind::Flag),
+    ("aftnnar", WordKind::Flag),
+    ("aftnnauc", WordKind::Flag),
+    ("aftnnchi", WordKind::Flag),
+    ("aftnnchosung", WordKind::Flag),
+    ("aftnncnum", WordKind::Flag),
+    ("aftnndbar", WordKind::Flag),
+    ("aftnndbnum", WordKind::Flag),
+    ("aftnndbnumd", WordKind::Flag),
+    ("aftnndbnumk", WordKind::Flag),
+    ("aftnndbnumt", WordKind::Flag),
+    ("aftnnganada", WordKind::Flag),
+    ("aftnngbnum", WordKind::Flag),
+    ("aftnngbnumd", WordKind::Flag),
+    ("aftnngbnumk", WordKind::Flag),
+    ("aftnngbnuml", WordKind::Flag),
+    ("aftnnrlc", WordKind::Flag),
+    ("aftnnruc", WordKind::Flag),
+    ("aftnnzodiac", WordKind::Flag),
+    ("aftnnzodiacd", WordKind::Flag),
+    ("aftnnzodiacl", WordKind::Flag),
+    ("aftnrestart", WordKind::Flag),
+    ("aftnrstcont", WordKind::Flag),
+    ("aftnsep", WordKind::Destination),
+    ("aftnsepc", WordKind::Destination),
+    ("aftnstart", WordKind::Value),
+    ("aftntj", WordKind::Flag),
+    ("ai", WordKind::Toggle),
+    ("alang", WordKind::Value),
+    ("allowfieldendsel", WordKind::Flag),
+    ("allprot", WordKind::Flag),
+    ("alntblind", WordKind::Flag),
+    ("alt", WordKind::Flag),
+    ("animtext", WordKind::Value),
+    ("annotation", WordKind::Destination),
+    ("annotprot", WordKind::Flag),
+    ("ansi", WordKind::Flag),
+    ("ansicpg", WordKind::Value),
+    ("aoutl", WordKind::Toggle),
+    ("ascaps", WordKind::Toggle),
+    ("ashad", WordKind::Toggle),
+    ("asianbrkrule", WordKind::Flag),
+    ("aspalpha", WordKind::Toggle),
+    ("aspnum", WordKind::Toggle),
+    ("astrike", WordKind::Toggle),
+    ("atnauthor", WordKind::Destination),
+    ("atndate", WordKind::Destination),
+    ("atnicn", WordKind::Destination),
+    ("atnid", WordKind::Destination),
+    ("atnparent", WordKind::Destination),
+    ("atnref", WordKind::Destination),
+    ("atntime", WordKind::Destination),
+    ("atrfend", WordKind::Destination),
+    ("atrfstart", WordKind::Destination),
+    ("aul", WordKind::Toggle),
+    ("auld", WordKind::Toggle),
+    ("auldb", WordKind::Toggle),
+    ("aulnone", WordKind::Toggle),
+    ("aulw", WordKind::Toggle),
+    ("aup", WordKind::Value),
+    ("author", WordKind::Destination),
+    ("autofmtoverride", WordKind::Flag),
+    ("b", WordKind::Toggle),
+    ("background", WordKind::Destination),
+    ("bdbfhdr", WordKind::Flag),
+    ("bdrrlswsix", WordKind::Flag),
+    ("bgbdiag", WordKind::Flag),
+    ("bgcross", WordKind::Flag),
+    ("bgdcross", WordKind::Flag),
+    ("bgdkbdiag", WordKind::Flag),
+    ("bgdkcross", WordKind::Flag),
+    ("bgdkdcross", WordKind::Flag),
+    ("bgdkfdiag", WordKind::Flag),
+    ("bgdkhoriz", WordKind::Flag),
+    ("bgdkvert", WordKind::Flag),
+    ("bgfdiag", WordKind::Flag),
+    ("bghoriz", WordKind::Flag),
+    ("bgvert", WordKind::Flag),
+    ("bin", WordKind::Value),
+    ("binfsxn", WordKind::Value),
+    ("binsxn", WordKind::Value),
+    ("bkmkcolf", WordKind::Value),
+    ("bkmkcoll", WordKind::Value),
+    ("bkmkend", WordKind::Destination),
+    ("bkmkpub", WordKind::Flag),
+    ("bkmkstart", WordKind::Destination),
+    ("bliptag", WordKind::Value),
+    ("blipuid", WordKind::Destination),
+    ("blipupi", WordKind::Value),
+    ("blue", WordKind::Value),
+    ("bookfold", WordKind::Flag),
+    ("bookfoldrev", WordKind::Flag),
+    ("bookfoldsheets", WordKind::Value),
+    ("box", WordKind::Flag),
+    ("brdrart", WordKind::Value),
+    ("brdrb", WordKind::Flag),
+    ("brdrbar", WordKind::Flag),
+    ("brdrbtw", WordKind::Flag),
+    ("brdrcf", WordKind::Value),
+    ("brdrdash", WordKind::Flag),
+    ("brdrdashd", WordKind::Flag),
+    ("brdrdashdd", WordKind::Flag),
+    ("brdrdashdot", WordKind::Flag),
+    ("brdrdashdotdot", WordKind::Flag),
+    ("brdrdashdotstr", WordKind::Flag),
+    ("brdrdashsm", WordKind::Flag),
+    ("brdrdb", WordKind::Flag),
+    ("brdrdot", WordKind::Flag),
+    ("brdremboss", WordKind::Flag),
+    ("brdrengrave", WordKind::Flag),
+    ("brdrframe", WordKind::Flag),
+    ("brdrhair", WordKind::Flag),
+    ("brdrinset", WordKind::Flag),
+    ("brdrl", WordKind::Flag),
+    ("brdrnil", WordKind::Flag),
+    ("brdrnone", WordKind::Flag),
+    ("brdroutset", WordKind::Flag),
+    ("brdrr", WordKind::Flag),
+    ("brdrs", WordKind::Flag),
+    ("brdrsh", WordKind::Flag),
+    ("brdrt", WordKind::Flag),
+    ("brdrtbl", WordKind::Flag),
+    ("brdrth", WordKind::Flag),
+    ("brdrthtnlg", WordKind::Flag),
+    ("brdrthtnmg", WordKind::Flag),
+    ("brdrthtnsg", WordKind::Flag),
+    ("brdrtnthlg", WordKind::Flag),
+    ("brdrtnthmg", WordKind::Flag),
+    ("brdrtnthsg", WordKind::Flag),
+    ("brdrtnthtnlg", WordKind::Flag),
+    ("brdrtnthtnmg", WordKind::Flag),
+    ("brdrtnthtnsg", WordKind::Flag),
+    ("brdrtriple", WordKind::Flag),
+    ("brdrw", WordKind::Value),
+    ("brdrwavy", WordKind::Flag),
+    ("brdrwavydb", WordKind::Flag),
+    ("brkfrm", WordKind::Flag),
+    ("brsp", WordKind::Value),
+    ("bullet", WordKind::Symbol),
+    ("buptim", WordKind::Destination),
+    ("bxe", WordKind::Flag),
+    ("caccentfive", WordKind::Flag),
+    ("caccentfour", WordKind::Flag),
+    ("caccentone", WordKind::Flag),
+    ("caccentsix", WordKind::Flag),
+    ("caccentthree", WordKind::Flag),
+    ("caccenttwo", WordKind::Flag),
+    ("cachedcolbal", WordKind::Flag),
+    ("caps", WordKind::Toggle),
+    ("category", WordKind::Destination),
+    ("cb", WordKind::Value),
+    ("cbackgroundone", WordKind::Flag),
+    ("cbackgroundtwo", WordKind::Flag),
+    ("cbpat", WordKind::Value),
+    ("cchs", WordKind::Value),
+    ("cell", WordKind::Symbol),
+    ("cellx", WordKind::Value),
+    ("cf", WordKind::Value),
+    ("cfollowedhyperlink", WordKind::Flag),
+    ("cfpat", WordKind::Value),
+    ("cgrid", WordKind::Value),
+    ("charrsid", WordKind::Value),
+    ("charscalex", WordKind::Value),
+    ("chatn", WordKind::Symbol),
+    ("chbgbdiag", WordKind::Flag),
+    ("chbgcross", WordKind::Flag),
+    ("chbgdcross", WordKind::Flag),
+    ("chbgdkbdiag", WordKind::Flag),
+    ("chbgdkcross", WordKind::Flag),
+    ("chbgdkdcross", WordKind::Flag),
+    ("chbgdkfdiag", WordKind::Flag),
+    ("chbgdkhoriz", WordKind::Flag),
+    ("chbgdkvert", WordKind::Flag),
+    ("chbgfdiag", WordKind::Flag),
+    ("chbghoriz", WordKind::Flag),
+    ("chbgvert", WordKind::Flag),
+    ("chbrdr", WordKind::Flag),
+    ("chcbpat", WordKind::Value),
+    ("chcfpat", WordKind::Value),
+    ("chdate", WordKind::Symbol),
+    ("chdpa", WordKind::Symbol),
+    ("chdpl", WordKind::Symbol),
+    ("chftn", WordKind::Symbol),
+    ("chftnsep", WordKind::Symbol),
+    ("chftnsepc", WordKind::Symbol),
+    ("chhres", WordKind::Value),
+    ("chpgn", WordKind::Symbol),
+    ("chshdng", WordKind::Value),
+    ("chtime", WordKind::Symbol),
+    ("chyperlink", WordKind::Flag),
+    ("clFitText", WordKind::Flag),
+    ("clNoWrap", WordKind::Flag),
+    ("clbgbdiag", WordKind::Flag),
+    ("clbgcross", WordKind::Flag),
+    ("clbgdcross", WordKind::Flag),
+    ("clbgdkbdiag", WordKind::Flag),
+    ("clbgdkcross", WordKind::Flag),
+    ("clbgdkdcross", WordKind::Flag),
+    ("clbgdkfdiag", WordKind::Flag),
+    ("clbgdkhor", WordKind::Flag),
+    ("clbgdkvert", WordKind::Flag),
+    ("clbgfdiag", WordKind::Flag),
+    ("clbghoriz", WordKind::Flag),
+    ("clbgvert", WordKind::Flag),
+    ("clbrdrb", WordKind::Flag),
+    ("clbrdrl", WordKind::Flag),
+    ("clbrdrr", WordKind::Flag),
+    ("clbrdrt", WordKind::Flag),
+    ("clcbpat", WordKind::Value),
+    ("clcbpatraw", WordKind::Value),
+    ("clcfpat", WordKind::Value),
+    ("clcfpatraw", WordKind::Value),
+    ("cldel", WordKind::Flag),
+    ("cldelauth", WordKind::Value),
+    ("cldeldttm", WordKind::Value),
+    ("cldgll", WordKind::Flag),
+    ("cldglu", WordKind::Flag),
+    ("clftsWidth", WordKind::Value),
+    ("clhidemark", WordKind::Flag),
+    ("clins", WordKind::Flag),
+    ("clinsauth", WordKind::Value),
+    ("clinsdttm", WordKind::Value),
+    ("clmgf", WordKind::Flag),
+    ("clmrg", WordKind::Flag),
+    ("clmrgd", WordKind::Flag),
+    ("clmrgdauth", WordKind::Value),
+    ("clmrgddttm", WordKind::Value),
+    ("clmrgdr", WordKind::Flag),
+    ("clpadb", WordKind::Value),
+    ("clpadfb", WordKind::Value),
+    ("clpadfl", WordKind::Value),
+    ("clpadfr", WordKind::Value),
+    ("clpadft", WordKind::Value),
+    ("clpadl", WordKind::Value),
+    ("clpadr", WordKind::Value),
+    ("clpadt", WordKind::Value),
+    ("clshdng", WordKind::Value),
+    ("clshdngraw", WordKind::Value),
+    ("clshdrawnil", WordKind::Flag),
+    ("clspb", WordKind::Value),
+    ("clspfb", WordKind::Value),
+    ("clspfl", WordKind::Value),
+    ("clspfr", WordKind::Value),
+    ("clspft", WordKind::Value),
+    ("clspl", WordKind::Value),
+    ("clsplit", WordKind::Flag),
+    ("clsplitr", WordKind::Flag),
+    ("clspr", WordKind::Value),
+    ("clspt", WordKind::Value),
+    ("cltxbtlr", WordKind::Flag),
+    ("cltxlrtb", WordKind::Flag),
+    ("cltxlrtbv", WordKind::Flag),
+    ("cltxtbrl", WordKind::Flag),
+    ("cltxtbrlv", WordKind::Flag),
+    ("clvertalb", WordKind::Flag),
+    ("clvertalc", WordKind::Flag),
+    ("clvertalt", WordKind::Flag),
+    ("clvmgf", WordKind::Flag),
+    ("clvmrg", WordKind::Flag),
+    ("clwWidth", WordKind::Value),
+    ("cmaindarkone", WordKind::Flag),
+    ("cmaindarktwo", WordKind::Flag),
+    ("cmainlightone", WordKind::Flag),
+    ("cmainlighttwo", WordKind::Flag),
+    ("collapsed", WordKind::Flag),
+    ("colno", WordKind::Value),
+    ("colorschememapping", WordKind::Destination),
+    ("colortbl", WordKind::Destination),
+    ("cols", WordKind::Value),
+    ("colsr", WordKind::Value),
+    ("colsx", WordKind::Value),
+    ("column", WordKind::Symbol),
+    ("colw", WordKind::Value),
+    ("comment", WordKind::Destination),
+    ("company", WordKind::Destination),
+    ("contextualspace", WordKind::Flag),
+    ("cpg", WordKind::Value),
+    ("crauth", WordKind::Value),
+    ("crdate", WordKind::Value),
+    ("creatim", WordKind::Destination),
+    ("cs", WordKind::Value),
+    ("cshade", WordKind::Value),
+    ("ctextone", WordKind::Flag),
+    ("ctexttwo", WordKind::Flag),
+    ("ctint", WordKind::Value),
+    ("ctrl", WordKind::Flag),
+    ("cts", WordKind::Value),
+    ("cufi", WordKind::Value),
+    ("culi", WordKind::Value),
+    ("curi", WordKind::Value),
+    ("cvmme", WordKind::Flag),
+    ("datafield", WordKind::Destination),
+    ("datastore", WordKind::Destination),
+    ("date", WordKind::Flag),
+    ("dbch", WordKind::Flag),
+    ("defchp", WordKind::Destination),
+    ("deff", WordKind::Value),
+    ("defformat", WordKind::Flag),
+    ("deflang", WordKind::Value),
+    ("deflangfe", WordKind::Value),
+    ("defpap", WordKind::Destination),
+    ("defshp", WordKind::Flag),
+    ("deftab", WordKind::Value),
+    ("deleted", WordKind::Toggle),
+    ("delrsid", WordKind::Value),
+    ("dfrauth", WordKind::Value),
+    ("dfrdate", WordKind::Value),
+    ("dfrmtxtx", WordKind::Value),
+    ("dfrmtxty", WordKind::Value),
+    ("dfrstart", WordKind::Value),
+    ("dfrstop", WordKind::Value),
+    ("dfrxst", WordKind::Value),
+    ("dghorigin", WordKind::Value),
+    ("dghshow", WordKind::Value),
+    ("dghspace", WordKind::Value),
+    ("dgmargin", WordKind::Flag),
+    ("dgsnap", WordKind::Flag),
+    ("dgvorigin", WordKind::Value),
+    ("dgvshow", WordKind::Value),
+    ("dgvspace", WordKind::Value),
+    ("dibitmap", WordKind::Value),
+    ("disabled", WordKind::Toggle),
+    ("dn", WordKind::Value),
+    ("dntblnsbdb", WordKind::Flag),
+    ("do", WordKind::Destination),
+    ("dobxcolumn", WordKind::Flag),
+    ("dobxmargin", WordKind::Flag),
+    ("dobxpage", WordKind::Flag),
+    ("dobymargin", WordKind::Flag),
+    ("dobypage", WordKind::Flag),
+    ("dobypara", WordKind::Flag),
+    ("doccomm", WordKind::Destination),
+    ("doctemp", WordKind::Flag),
+    ("doctype", WordKind::Value),
+    ("docvar", WordKind::Destination),
+    ("dodhgt", WordKind::Value),
+    ("dolock", WordKind::Flag),
+    ("donotembedlingdata", WordKind::Value),
+    ("donotembedsysfont", WordKind::Value),
+    ("donotshowcomments", WordKind::Flag),
+    ("donotshowinsdel", WordKind::Flag),
+    ("donotshowmarkup", WordKind::Flag),
+    ("donotshowprops", WordKind::Flag),
+    ("dpaendhol", WordKind::Flag),
+    ("dpaendl", WordKind::Value),
+    ("dpaendsol", WordKind::Flag),
+    ("dpaendw", WordKind::Value),
+    ("dparc", WordKind::Flag),
+    ("dparcflipx", WordKind::Flag),
+    ("dparcflipy", WordKind::Flag),
+    ("dpastarthol", WordKind::Flag),
+    ("dpastartl", WordKind::Value),
+    ("dpastartsol", WordKind::Flag),
+    ("dpastartw", WordKind::Value),
+    ("dpcallout", WordKind::Flag),
+    ("dpcoa", WordKind::Value),
+    ("dpcoaccent", WordKind::Flag),
+    ("dpcobestfit", WordKind::Flag),
+    ("dpcoborder", WordKind::Flag),
+    ("dpcodabs", WordKind::Flag),
+    ("dpcodbottom", WordKind::Flag),
+    ("dpcodcenter", WordKind::Flag),
+    ("dpcodescent", WordKind::Value),
+    ("dpcodtop", WordKind::Flag),
+    ("dpcolength", WordKind::Value),
+    ("dpcominusx", WordKind::Flag),
+    ("dpcominusy", WordKind::Flag),
+    ("dpcooffset", WordKind::Value),
+    ("dpcosmarta", WordKind::Flag),
+    ("dpcotdouble", WordKind::Flag),
+    ("dpcotright", WordKind::Flag),
+    ("dpcotsingle", WordKind::Flag),
+    ("dpcottriple", WordKind::Flag),
+    ("dpcount", WordKind::Value),
+    ("dpellipse", WordKind::Flag),
+    ("dpendgroup", WordKind::Flag),
+    ("dpfillbgcb", WordKind::Value),
+    ("dpfillbgcg", WordKind::Value),
+    ("dpfillbgcr", WordKind::Value),
+    ("dpfillbggray", WordKind::Value),
+    ("dpfillbgpal", WordKind::Flag),
+    ("dpfillfgcb", WordKind::Value),
+    ("dpfillfgcg", WordKind::Value),
+    ("dpfillfgcr", WordKind::Value),
+    ("dpfillfggray", WordKind::Value),
+    ("dpfillfgpal", WordKind::Flag),
+    ("dpfillpat", WordKind::Value),
+    ("dpgroup", WordKind::Flag),
+    ("dpline", WordKind::Flag),
+    ("dplinecob", WordKind::Value),
+    ("dplinecog", WordKind::Value),
+    ("dplinecor", WordKind::Value),
+    ("dplinedado", WordKind::Flag),
+    ("dplinedadodo", WordKind::Flag),
+    ("dplinedash", WordKind::Flag),
+    ("dplinedot", WordKind::Flag),
+    ("dplinegray", WordKind::Value),
+    ("dplinehollow", WordKind::Flag),
+    ("dplinepal", WordKind::Flag),
+    ("dplinesolid", WordKind::Flag),
+    ("dplinew", WordKind::Value),
+    ("dppolycount", WordKind::Value),
+    ("dppolygon", WordKind::Flag),
+    ("dppolyline", WordKind::Flag),
+    ("dpptx", WordKind::Value),
+    ("dppty", WordKind::Value),
+    ("dprect", WordKind::Flag),
+    ("dproundr", WordKind::Flag),
+    ("dpshadow", WordKind::Flag),
+    ("dpshadx", WordKind::Value),
+    ("dpshady", WordKind::Value),
+    ("dptxbtlr", WordKind::Flag),
+    ("dptxbx", WordKind::Flag),
+    ("dptxbxmar", WordKind::Value),
+    ("dptxbxtext", WordKind::Destination),
+    ("dptxlrtb", WordKind::Flag),
+    ("dptxlrtbv", WordKind::Flag),
+    ("dptxtbrl", WordKind::Flag),
+    ("dptxtbrlv", WordKind::Flag),
+    ("dpx", WordKind::Value),
+    ("dpxsize", WordKind::Value),
+    ("dpy", WordKind::Value),
+    ("dpysize", WordKind::Value),
+    ("dropcapli", WordKind::Value),
+    ("dropcapt", WordKind::Value),
+    ("ds", WordKind::Value),
+    ("dxfrtext", WordKind::Value),
+    ("dy", WordKind::Value),
+    ("ebcend", WordKind::Destination),
+    ("ebcstart", WordKind::Destination),
+    ("edmins", WordKind::Value),
+    ("embo", WordKind::Toggle),
+    ("emdash", WordKind::Symbol),
+    ("emfblip", WordKind::Flag),
+    ("emspace", WordKind::Symbol),
+    ("endash", WordKind::Symbol),
+    ("enddoc", WordKind::Flag),
+    ("endnhere", WordKind::Flag),
+    ("endnotes", WordKind::Flag),
+    ("enforceprot", WordKind::Value),
+    ("enspace", WordKind::Symbol),
+    ("expnd", WordKind::Value),
+    ("expndtw", WordKind::Value),
+    ("expshrtn", WordKind::Flag),
+    ("f", WordKind::Value),
+    ("faauto", WordKind::Flag),
+    ("facenter", WordKind::Flag),
+    ("facingp", WordKind::Flag),
+    ("factoidname", WordKind::Destination),
+    ("fafixed", WordKind::Flag),
+    ("fahang", WordKind::Flag),
+    ("falt", WordKind::Destination),
+    ("faroman", WordKind::Flag),
+    ("favar", WordKind::Flag),
+    ("fbias", WordKind::Value),
+    ("fbidi", WordKind::Flag),
+    ("fbidis", WordKind::Flag),
+    ("fbimajor", WordKind::Flag),
+    ("fbiminor", WordKind::Flag),
+    ("fchars", WordKind::Destination),
+    ("fcharset", WordKind::Value),
+    ("fcs", WordKind::Value),
+    ("fdbmajor", WordKind::Flag),
+    ("fdbminor", WordKind::Flag),
+    ("fdecor", WordKind::Flag),
+    ("felnbrelev", WordKind::Flag),
+    ("fet", WordKind::Value),
+    ("fetch", WordKind::Flag),
+    ("ffdefres", WordKind::Value),
+    ("ffdeftext", WordKind::Destination),
+    ("ffentrymcr", WordKind::Destination),
+    ("ffexitmcr", WordKind::Destination),
+    ("ffformat", WordKind::Destination),
+    ("ffhaslistbox", WordKind::Value),
+    ("ffhelptext", WordKind::Destination),
+    ("ffhps", WordKind::Value),
+    ("ffl", WordKind::Destination),
+    ("ffmaxlen", WordKind::Value),
+    ("ffname", WordKind::Destination),
+    ("ffownhelp", WordKind::Value),
+    ("ffownstat", WordKind::Value),
+    ("ffprot", WordKind::Value),
+    ("ffrecalc", WordKind::Value),
+    ("ffres", WordKind::Value),
+    ("ffsize", WordKind::Value),
+    ("ffstattext", WordKind::Destination),
+    ("fftype", WordKind::Value),
+    ("fftypetxt", WordKind::Value),
+    ("fhimajor", WordKind::Flag),
+    ("fhiminor", WordKind::Flag),
+    ("fi", WordKind::Value),
+    ("fid", WordKind::Value),
+    ("field", WordKind::Destination),
+    ("file", WordKind::Destination),
+    ("filetbl", WordKind::Destination),
+    ("fittext", WordKind::Value),
+    ("fjgothic", WordKind::Flag),
+    ("fjminchou", WordKind::Flag),
+    ("fldalt", WordKind::Flag),
+    ("flddirty", WordKind::Flag),
+    ("fldedit", WordKind::Flag),
+    ("fldinst", WordKind::Destination),
+    ("fldlock", WordKind::Flag),
+    ("fldpriv", WordKind::Flag),
+    ("fldrslt", WordKind::Destination),
+    ("fldtype", WordKind::Destination),
+    ("flomajor", WordKind::Flag),
+    ("flominor", WordKind::Flag),
+    ("fmodern", WordKind::Flag),
+    ("fn", WordKind::Value),
+    ("fname", WordKind::Destination),
+    ("fnetwork", WordKind::Flag),
+    ("fnil", WordKind::Flag),
+    ("fnonfilesys", WordKind::Flag),
+    ("fontemb", WordKind::Destination),
+    ("fontfile", WordKind::Destination),
+    ("fonttbl", WordKind::Destination),
+    ("footer", WordKind::Destination),
+    ("footerf", WordKind::Destination),
+    ("footerl", WordKind::Destination),
+    ("footerr", WordKind::Destination),
+    ("footery", WordKind::Value),
+    ("footnote", WordKind::Destination),
+    ("forceupgrade", WordKind::Flag),
+    ("formdisp", WordKind::Flag),
+    ("formfield", WordKind::Destination),
+    ("formprot", WordKind::Flag),
+    ("formshade", WordKind::Flag),
+    ("fosnum", WordKind::Value),
+    ("fprq", WordKind::Value),
+    ("fracwidth", WordKind::Flag),
+    ("frelative", WordKind::Value),
+    ("frmtxbtlr", WordKind::Flag),
+    ("frmtxlrtb", WordKind::Flag),
+    ("frmtxlrtbv", WordKind::Flag),
+    ("frmtxtbrl", WordKind::Flag),
+    ("frmtxtbrlv", WordKind::Flag),
+    ("froman", WordKind::Flag),
+    ("fromhtml", WordKind::Value),
+    ("fromtext", WordKind::Flag),
+    ("fs", WordKind::Value),
+    ("fscript", WordKind::Flag),
+    ("fswiss", WordKind::Flag),
+    ("ftech", WordKind::Flag),
+    ("ftnalt", WordKind::Flag),
+    ("ftnbj", WordKind::Flag),
+    ("ftncn", WordKind::Destination),
+    ("ftnil", WordKind::Flag),
+    ("ftnlytwnine", WordKind::Flag),
+    ("ftnnalc", WordKind::Flag),
+    ("ftnnar", WordKind::Flag),
+    ("ftnnauc", WordKind::Flag),
+    ("ftnnchi", WordKind::Flag),
+    ("ftnnchosung", WordKind::Flag),
+    ("ftnncnum", WordKind::Flag),
+    ("ftnndbar", WordKind::Flag),
+    ("ftnndbnum", WordKind::Flag),
+    ("ftnndbnumd", WordKind::Flag),
+    ("ftnndbnumk", WordKind::Flag),
+    ("ftnndbnumt", WordKind::Flag),
+    ("ftnnganada", WordKind::Flag),
+    ("ftnngbnum", WordKind::Flag),
+    ("ftnngbnumd", WordKind::Flag),
+    ("ftnngbnumk", WordKind::Flag),
+    ("ftnngbnuml", WordKind::Flag),
+    ("ftnnrlc", WordKind::Flag),
+    ("ftnnruc", WordKind::Flag),
+    ("ftnnzodiac", WordKind::Flag),
+    ("ftnnzodiacd", WordKind::Flag),
+    ("ftnnzodiacl", WordKind::Flag),
+    ("ftnrestart", WordKind::Flag),
+    ("ftnrstcont", WordKind::Flag),
+    ("ftnrstpg", WordKind::Flag),
+    ("ftnsep", WordKind::Destination),
+    ("ftnsepc", WordKind::Destination),
+    ("ftnstart", WordKind::Value),
+    ("ftntj", WordKind::Flag),
+    ("fttruetype", WordKind::Flag),
+    ("fvaliddos", WordKind::Flag),
+    ("fvalidhpfs", WordKind::Flag),
+    ("fvalidmac", WordKind::Flag),
+    ("fvalidntfs", WordKind::Flag),
+    ("g", WordKind::Destination),
+    ("gcw", WordKind::Value),
+    ("generator", WordKind::Destination),
+    ("green", WordKind::Value),
+    ("grfdocevents", WordKind::Value),
+    ("gridtbl", WordKind::Destination),
+    ("gutter", WordKind::Value),
+    ("gutterprl", WordKind::Flag),
+    ("guttersxn", WordKind::Value),
+    ("header", WordKind::Destination),
+    ("headerf", WordKind::Destination),
+    ("headerl", WordKind::Destination),
+    ("headerr", WordKind::Destination),
+    ("headery", WordKind::Value),
+    ("hich", WordKind::Flag),
+    ("highlight", WordKind::Value),
+    ("hl", WordKind::Destination),
+    ("hlfr", WordKind::Destination),
+    ("hlinkbase", WordKind::Destination),
+    ("hlloc", WordKind::Destination),
+    ("hlsrc", WordKind::Destination),
+    ("horzdoc", WordKind::Flag),
+    ("horzsect", WordKind::Flag),
+    ("horzvert", WordKind::Value),
+    ("hr", WordKind::Value),
+    ("hres", WordKind::Value),
+    ("hrule", WordKind::Flag),
+    ("hsv", WordKind::Destination),
+    ("htmautsp", WordKind::Flag),
+    ("htmlbase", WordKind::Flag),
+    ("htmlrtf", WordKind::Toggle),
+    ("htmltag", WordKind::Destination),
+    ("hwelev", WordKind::Flag),
+    ("hyphauto", WordKind::Toggle),
+    ("hyphcaps", WordKind::Toggle),
+    ("hyphconsec", WordKind::Value),
+    ("hyphhotz", WordKind::Value),
+    ("hyphpar", WordKind::Toggle),
+    ("i", WordKind::Toggle),
+    ("id", WordKind::Value),
+    ("ignoremixedcontent", WordKind::Value),
+    ("ilfomacatclnup", WordKind::Value),
+    ("ilvl", WordKind::Value),
+    ("impr", WordKind::Toggle),
+    ("indmirror", WordKind::Flag),
+    ("indrlsweleven", WordKind::Flag),
+    ("info", WordKind::Destination),
+    ("insrsid", WordKind::Value),
+    ("intbl", WordKind::Flag),
+    ("ipgp", WordKind::Value),
+    ("irow", WordKind::Value),
+    ("irowband", WordKind::Value),
+    ("itap", WordKind::Value),
+    ("ixe", WordKind::Flag),
+    ("jclisttab", WordKind::Flag),
+    ("jcompress", WordKind::Flag),
+    ("jexpand", WordKind::Flag),
+    ("jis", WordKind::Flag),
+    ("jpegblip", WordKind::Flag),
+    ("jsksu", WordKind::Flag),
+    ("keep", WordKind::Flag),
+    ("keepn", WordKind::Flag),
+    ("kerning", WordKind::Value),
+    ("keycode", WordKind::Destination),
+    ("keywords", WordKind::Destination),
+    ("krnprsnet", WordKind::Flag),
+    ("ksulang", WordKind::Value),
+    ("landscape", WordKind::Flag),
+    ("lang", WordKind::Value),
+    ("langfe", WordKind::Value),
+    ("langfenp", WordKind::Value),
+    ("langnp", WordKind::Value),
+    ("lastrow", WordKind::Flag),
+    ("latentstyles", WordKind::Destination),
+    ("lbr", WordKind::Value),
+    ("lchars", WordKind::Destination),
+    ("ldblquote", WordKind::Symbol),
+    ("level", WordKind::Value),
+    ("levelfollow", WordKind::Value),
+    ("levelindent", WordKind::Value),
+    ("leveljc", WordKind::Value),
+    ("leveljcn", WordKind::Value),
+    ("levellegal", WordKind::Value),
+    ("levelnfc", WordKind::Value),
+    ("levelnfcn", WordKind::Value),
+    ("levelnorestart", WordKind::Value),
+    ("levelnumbers", WordKind::Destination),
+    ("levelold", WordKind::Value),
+    ("levelpicture", WordKind::Value),
+    ("levelpicturenosize", WordKind::Flag),
+    ("levelprev", WordKind::Value),
+    ("levelprevspace", WordKind::Value),
+    ("levelspace", WordKind::Value),
+    ("levelstartat", WordKind::Value),
+    ("leveltemplateid", WordKind::Value),
+    ("leveltext", WordKind::Destination),
+    ("lfolevel", WordKind::Destination),
+    ("li", WordKind::Value),
+    ("lin", WordKind::Value),
+    ("line", WordKind::Symbol),
+    ("linebetcol", WordKind::Flag),
+    ("linecont", WordKind::Flag),
+    ("linemod", WordKind::Value),
+    ("lineppage", WordKind::Flag),
+    ("linerestart", WordKind::Flag),
+    ("linestart", WordKind::Value),
+    ("linestarts", WordKind::Value),
+    ("linex", WordKind::Value),
+    ("linkself", WordKind::Flag),
+    ("linkstyles", WordKind::Flag),
+    ("linkval", WordKind::Destination),
+    ("lisa", WordKind::Value),
+    ("lisb", WordKind::Value),
+    ("list", WordKind::Destination),
+    ("listhybrid", WordKind::Flag),
+    ("listid", WordKind::Value),
+    ("listlevel", WordKind::Destination),
+    ("listname", WordKind::Destination),
+    ("listoverride", WordKind::Destination),
+    ("listoverridecount", WordKind::Value),
+    ("listoverrideformat", WordKind::Value),
+    ("listoverridestartat", WordKind::Flag),
+    ("listoverridetable", WordKind::Destination),
+    ("listpicture", WordKind::Destination),
+    ("listrestarthdn", WordKind::Value),
+    ("listsimple", WordKind::Value),
+    ("liststyleid", WordKind::Value),
+    ("liststylename", WordKind::Destination),
+    ("listtable", WordKind::Destination),
+    ("listtemplateid", WordKind::Value),
+    ("listtext", WordKind::Destination),
+    ("lnbrkrule", WordKind::Flag),
+    ("lndscpsxn", WordKind::Flag),
+    ("lnongrid", WordKind::Flag),
+    ("loch", WordKind::Flag),
+    ("lquote", WordKind::Symbol),
+    ("ls", WordKind::Value),
+    ("lsdlocked", WordKind::Value),
+    ("lsdlockeddef", WordKind::Value),
+    ("lsdlockedexcept", WordKind::Destination),
+    ("lsdpriority", WordKind::Value),
+    ("lsdprioritydef", WordKind::Value),
+    ("lsdqformat", WordKind::Value),
+    ("lsdqformatdef", WordKind::Value),
+    ("lsdsemihidden", WordKind::Value),
+    ("lsdsemihiddendef", WordKind::Value),
+    ("lsdstimax", WordKind::Value),
+    ("lsdunhideused", WordKind::Value),
+    ("lsdunhideuseddef", WordKind::Value),
+    ("ltrch", WordKind::Flag),
+    ("ltrdoc", WordKind::Flag),
+    ("ltrmark", WordKind::Symbol),
+    ("ltrpar", WordKind::Flag),
+    ("ltrrow", WordKind::Flag),
+    ("ltrsect", WordKind::Flag),
+    ("lvltentative", WordKind::Flag),
+    ("lytcalctblwd", WordKind::Flag),
+    ("lytexcttp", WordKind::Flag),
+    ("lytprtmet", WordKind::Flag),
+    ("lyttblrtgr", WordKind::Flag),
+    ("mac", WordKind::Flag),
+    ("macc", WordKind::Destination),
+    ("maccPr", WordKind::Destination),
+    ("macpict", WordKind::Flag),
+    ("mailmerge", WordKind::Destination),
+    ("makebackup", WordKind::Flag),
+    ("maln", WordKind::Destination),
+    ("malnScr", WordKind::Destination),
+    ("manager", WordKind::Destination),
+    ("margPr", WordKind::Destination),
+    ("margSz", WordKind::Value),
+    ("margb", WordKind::Value),
+    ("margbsxn", WordKind::Value),
+    ("margl", WordKind::Value),
+    ("marglsxn", WordKind::Value),
+    ("margmirror", WordKind::Flag),
+    ("margmirsxn", WordKind::Flag),
+    ("margr", WordKind::Value),
+    ("margrsxn", WordKind::Value),
+    ("margt", WordKind::Value),
+    ("margtsxn", WordKind::Value),
+    ("mbar", WordKind::Destination),
+    ("mbarPr", WordKind::Destination),
+    ("mbaseJc", WordKind::Destination),
+    ("mbegChr", WordKind::Destination),
+    ("mborderBox", WordKind::Destination),
+    ("mborderBoxPr", WordKind::Destination),
+    ("mbox", WordKind::Destination),
+    ("mboxPr", WordKind::Destination),
+    ("mbrk", WordKind::Value),
+    ("mbrkBin", WordKind::Value),
+    ("mbrkBinSub", WordKind::Value),
+    ("mcGp", WordKind::Value),
+    ("mcGpRule", WordKind::Value),
+    ("mcSp", WordKind::Value),
+    ("mchr", WordKind::Destination),
+    ("mcount", WordKind::Destination),
+    ("mctrlPr", WordKind::Destination),
+    ("md", WordKind::Destination),
+    ("mdPr", WordKind::Destination),
+    ("mdefJc", WordKind::Value),
+    ("mdeg", WordKind::Destination),
+    ("mdegHide", WordKind::Destination),
+    ("mden", WordKind::Destination),
+    ("mdiff", WordKind::Destination),
+    ("mdiffSty", WordKind::Value),
+    ("mdispdef", WordKind::Value),
+    ("me", WordKind::Destination),
+    ("mendChr", WordKind::Destination),
+    ("meqArr", WordKind::Destination),
+    ("meqArrPr", WordKind::Destination),
+    ("mf", WordKind::Destination),
+    ("mfName", WordKind::Destination),
+    ("mfPr", WordKind::Destination),
+    ("mfunc", WordKind::Destination),
+    ("mfuncPr", WordKind::Destination),
+    ("mgroupChr", WordKind::Destination),
+    ("mgroupChrPr", WordKind::Destination),
+    ("mgrow", WordKind::Destination),
+    ("mhideBot", WordKind::Destination),
+    ("mhideLeft", WordKind::Destination),
+    ("mhideRight", WordKind::Destination),
+    ("mhideTop", WordKind::Destination),
+    ("mhtmltag", WordKind::Destination),
+    ("min", WordKind::Value),
+    ("mintLim", WordKind::Value),
+    ("minterSp", WordKind::Value),
+    ("mintraSp", WordKind::Value),
+    ("mjc", WordKind::Value),
+    ("mlMargin", WordKind::Value),
+    ("mlim", WordKind::Destination),
+    ("mlimloc", WordKind::Destination),
+    ("mlimlow", WordKind::Destination),
+    ("mlimlowPr", WordKind::Destination),
+    ("mlimupp", WordKind::Destination),
+    ("mlimuppPr", WordKind::Destination),
+    ("mlit", WordKind::Flag),
+    ("mm", WordKind::Destination),
+    ("mmPr", WordKind::Destination),
+    ("mmaddfieldname", WordKind::Destination),
+    ("mmath", WordKind::Destination),
+    ("mmathFont", WordKind::Value),
+    ("mmathPict", WordKind::Destination),
+    ("mmathPr", WordKind::Destination),
+    ("mmattach", WordKind::Flag),
+    ("mmaxdist", WordKind::Destination),
+    ("mmblanklines", WordKind::Flag),
+    ("mmc", WordKind::Destination),
+    ("mmcJc", WordKind::Destination),
+    ("mmcPr", WordKind::Destination),
+    ("mmconnectstr", WordKind::Destination),
+    ("mmconnectstrdata", WordKind::Destination),
+    ("mmcs", WordKind::Destination),
+    ("mmdatasource", WordKind::Destination),
+    ("mmdatatypeaccess", WordKind::Flag),
+    ("mmdatatypeexcel", WordKind::Flag),
+    ("mmdatatypefile", WordKind::Flag),
+    ("mmdatatypeodbc", WordKind::Flag),
+    ("mmdatatypeodso", WordKind::Flag),
+    ("mmdatatypeqt", WordKind::Flag),
+    ("mmdefaultsql", WordKind::Flag),
+    ("mmdestemail", WordKind::Flag),
+    ("mmdestfax", WordKind::Flag),
+    ("mmdestnewdoc", WordKind::Flag),
+    ("mmdestprinter", WordKind::Flag),
+    ("mmerrors", WordKind::Value),
+    ("mmfttypeaddress", WordKind::Flag),
+    ("mmfttypebarcode", WordKind::Flag),
+    ("mmfttypedbcolumn", WordKind::Flag),
+    ("mmfttypemapped", WordKind::Flag),
+    ("mmfttypenull", WordKind::Flag),
+    ("mmfttypesalutation", WordKind::Flag),
+    ("mmheadersource", WordKind::Destination),
+    ("mmjdsotype", WordKind::Value),
+    ("mmlinktoquery", WordKind::Flag),
+    ("mmmailsubject", WordKind::Destination),
+    ("mmmaintypecatalog", WordKind::Flag),
+    ("mmmaintypeemail", WordKind::Flag),
+    ("mmmaintypeenvelopes", WordKind::Flag),
+    ("mmmaintypefax", WordKind::Flag),
+    ("mmmaintypelabels", WordKind::Flag),
+    ("mmmaintypeletters", WordKind::Flag),
+    ("mmodso", WordKind::Destination),
+    ("mmodsoactive", WordKind::Value),
+    ("mmodsocoldelim", WordKind::Value),
+    ("mmodsocolumn", WordKind::Value),
+    ("mmodsodynaddr", WordKind::Value),
+    ("mmodsofhdr", WordKind::Value),
+    ("mmodsofilter", WordKind::Destination),
+    ("mmodsofldmpdata", WordKind::Destination),
+    ("mmodsofmcolumn", WordKind::Value),
+    ("mmodsohash", WordKind::Value),
+    ("mmodsolid", WordKind::Value),
+    ("mmodsomappedname", WordKind::Destination),
+    ("mmodsoname", WordKind::Destination),
+    ("mmodsorecipdata", WordKind::Destination),
+    ("mmodsosort", WordKind::Destination),
+    ("mmodsosrc", WordKind::Destination),
+    ("mmodsotable", WordKind::Destination),
+    ("mmodsoudl", WordKind::Destination),
+    ("mmodsoudldata", WordKind::Destination),
+    ("mmodsouniquetag", WordKind::Destination),
+    ("mmquery", WordKind::Destination),
+    ("mmr", WordKind::Destination),
+    ("mmreccur", WordKind::Value),
+    ("mmshowdata", WordKind::Flag),
+    ("mnary", WordKind::Destination),
+    ("mnaryLim", WordKind::Value),
+    ("mnaryPr", WordKind::Destination),
+    ("mnoBreak", WordKind::Destination),
+    ("mnor", WordKind::Flag),
+    ("mnum", WordKind::Destination),
+    ("mo", WordKind::Value),
+    ("moMath", WordKind::Destination),
+    ("moMathPara", WordKind::Destination),
+    ("moMathParaPr", WordKind::Destination),
+    ("mobjDist", WordKind::Destination),
+    ("mopEmu", WordKind::Destination),
+    ("mphant", WordKind::Destination),
+    ("mphantPr", WordKind::Destination),
+    ("mplcHide", WordKind::Destination),
+    ("mpos", WordKind::Destination),
+    ("mpostSp", WordKind::Value),
+    ("mpreSp", WordKind::Value),
+    ("mr", WordKind::Destination),
+    ("mrMargin", WordKind::Value),
+    ("mrPr", WordKind::Destination),
+    ("mrSp", WordKind::Value),
+    ("mrSpRule", WordKind::Value),
+    ("mrad", WordKind::Destination),
+    ("mradPr", WordKind::Destination),
+    ("msPre", WordKind::Destination),
+    ("msPrePr", WordKind::Destination),
+    ("msSub", WordKind::Destination),
+    ("msSubPr", WordKind::Destination),
+    ("msSubSup", WordKind::Destination),
+    ("msSubSupPr", WordKind::Destination),
+    ("msSup", WordKind::Destination),
+    ("msSupPr", WordKind::Destination),
+    ("mscr", WordKind::Value),
+    ("msepChr", WordKind::Destination),
+    ("mshow", WordKind::Destination),
+    ("mshp", WordKind::Destination),
+    ("msmallFrac", WordKind::Value),
+    ("msmcap", WordKind::Flag),
+    ("mstrikeBLTR", WordKind::Destination),
+    ("mstrikeH", WordKind::Destination),
+    ("mstrikeTLBR", WordKind::Destination),
+    ("mstrikeV", WordKind::Destination),
+    ("msty", WordKind::Value),
+    ("msub", WordKind::Destination),
+    ("msubHide", WordKind::Destination),
+    ("msup", WordKind::Destination),
+    ("msupHide", WordKind::Destination),
+    ("mtransp", WordKind::Destination),
+    ("mtype", WordKind::Destination),
+    ("muser", WordKind::Flag),
+    ("mvauth", WordKind::Value),
+    ("mvdate", WordKind::Value),
+    ("mvertJc", WordKind::Destination),
+    ("mvf", WordKind::Flag),
+    ("mvfmf", WordKind::Destination),
+    ("mvfml", WordKind::Destination),
+    ("mvt", WordKind::Flag),
+    ("mvtof", WordKind::Destination),
+    ("mvtol", WordKind::Destination),
+    ("mwrapIndent", WordKind::Value),
+    ("mwrapRight", WordKind::Value),
+    ("mzeroAsc", WordKind::Destination),
+    ("mzeroDesc", WordKind::Destination),
+    ("mzeroWid", WordKind::Destination),
+    ("nestcell", WordKind::Symbol),
+    ("nestrow", WordKind::Symbol),
+    ("nesttableprops", WordKind::Destination),
+    ("newtblstyruls", WordKind::Flag),
+    ("nextfile", WordKind::Destination),
+    ("noafcnsttbl", WordKind::Flag),
+    ("nobrkwrptbl", WordKind::Flag),
+    ("nocolbal", WordKind::Flag),
+    ("nocompatoptions", WordKind::Flag),
+    ("nocwrap", WordKind::Flag),
+    ("nocxsptable", WordKind::Flag),
+    ("noextrasprl", WordKind::Flag),
+    ("nofchars", WordKind::Value),
+    ("nofcharsws", WordKind::Value),
+    ("nofeaturethrottle", WordKind::Flag),
+    ("nofpages", WordKind::Value),
+    ("nofwords", WordKind::Value),
+    ("nogrowautofit", WordKind::Flag),
+    ("noindnmbrts", WordKind::Flag),
+    ("nojkernpunct", WordKind::Flag),
+    ("nolead", WordKind::Flag),
+    ("noline", WordKind::Flag),
+    ("nolnhtadjtbl", WordKind::Flag),
+    ("nonesttables", WordKind::Destination),
+    ("nonshppict", WordKind::Flag),
+    ("nooverflow", WordKind::Flag),
+    ("noproof", WordKind::Flag),
+    ("noqfpromote", WordKind::Flag),
+    ("nosectexpand", WordKind::Flag),
+    ("nosnaplinegrid", WordKind::Flag),
+    ("nospaceforul", WordKind::Flag),
+    ("nosupersub", WordKind::Flag),
+    ("notabind", WordKind::Flag),
+    ("notbrkcnstfrctbl", WordKind::Flag),
+    ("notcvasp", WordKind::Flag),
+    ("notvatxbx", WordKind::Flag),
+    ("nouicompat", WordKind::Flag),
+    ("noultrlspc", WordKind::Flag),
+    ("nowidctlpar", WordKind::Flag),
+    ("nowrap", WordKind::Flag),
+    ("nowwrap", WordKind::Flag),
+    ("noxlattoyen", WordKind::Flag),
+    ("objalias", WordKind::Destination),
+    ("objalign", WordKind::Value),
+    ("objattph", WordKind::Flag),
+    ("objautlink", WordKind::Flag),
+    ("objclass", WordKind::Destination),
+    ("objcropb", WordKind::Value),
+    ("objcropl", WordKind::Value),
+    ("objcropr", WordKind::Value),
+    ("objcropt", WordKind::Value),
+    ("objdata", WordKind::Destination),
+    ("object", WordKind::Destination),
+    ("objemb", WordKind::Flag),
+    ("objh", WordKind::Value),
+    ("objhtml", WordKind::Flag),
+    ("objicemb", WordKind::Flag),
+    ("objlink", WordKind::Flag),
+    ("objlock", WordKind::Flag),
+    ("objname", WordKind::Destination),
+    ("objocx", WordKind::Flag),
+    ("objpub", WordKind::Flag),
+    ("objscalex", WordKind::Value),
+    ("objscaley", WordKind::Value),
+    ("objsect", WordKind::Destination),
+    ("objsetsize", WordKind::Flag),
+    ("objsub", WordKind::Flag),
+    ("objtime", WordKind::Destination),
+    ("objtransy", WordKind::Value),
+    ("objupdate", WordKind::Flag),
+    ("objw", WordKind::Value),
+    ("ogutter", WordKind::Value),
+    ("oldas", WordKind::Flag),
+    ("oldcprops", WordKind::Destination),
+    ("oldlinewrap", WordKind::Flag),
+    ("oldpprops", WordKind::Destination),
+    ("oldsprops", WordKind::Destination),
+    ("oldtprops", WordKind::Destination),
+    ("oleclsid", WordKind::Destination),
+    ("operator", WordKind::Destination),
+    ("otblrul", WordKind::Flag),
+    ("outl", WordKind::Toggle),
+    ("outlinelevel", WordKind::Value),
+    ("overlay", WordKind::Flag),
+    ("page", WordKind::Symbol),
+    ("pagebb", WordKind::Flag),
+    ("panose", WordKind::Destination),
+    ("paperh", WordKind::Value),
+    ("paperw", WordKind::Value),
+    ("par", WordKind::Symbol),
+    ("pararsid", WordKind::Value),
+    ("pard", WordKind::Flag),
+    ("password", WordKind::Destination),
+    ("passwordhash", WordKind::Destination),
+    ("pc", WordKind::Flag),
+    ("pca", WordKind::Flag),
+    ("pgbrdrb", WordKind::Flag),
+    ("pgbrdrfoot", WordKind::Flag),
+    ("pgbrdrhead", WordKind::Flag),
+    ("pgbrdrl", WordKind::Flag),
+    ("pgbrdropt", WordKind::Value),
+    ("pgbrdrr", WordKind::Flag),
+    ("pgbrdrsnap", WordKind::Flag),
+    ("pgbrdrt", WordKind::Flag),
+    ("pghsxn", WordKind::Value),
+    ("pgnbidia", WordKind::Flag),
+    ("pgnbidib", WordKind::Flag),
+    ("pgnchosung", WordKind::Flag),
+    ("pgncnum", WordKind::Flag),
+    ("pgncont", WordKind::Flag),
+    ("pgndbnum", WordKind::Flag),
+    ("pgndbnumd", WordKind::Flag),
+    ("pgndbnumk", WordKind::Flag),
+    ("pgndbnumt", WordKind::Flag),
+    ("pgndec", WordKind::Flag),
+    ("pgndecd", WordKind::Flag),
+    ("pgnganada", WordKind::Flag),
+    ("pgngbnum", WordKind::Flag),
+    ("pgngbnumd", WordKind::Flag),
+    ("pgngbnumk", WordKind::Flag),
+    ("pgngbnuml", WordKind::Flag),
+    ("pgnhindia", WordKind::Flag),
+    ("pgnhindib", WordKind::Flag),
+    ("pgnhindic", WordKind::Flag),
+    ("pgnhindid", WordKind::Flag),
+    ("pgnhn", WordKind::Value),
+    ("pgnhnsc", WordKind::Flag),
+    ("pgnhnsh", WordKind::Flag),
+    ("pgnhnsm", WordKind::Flag),
+    ("pgnhnsn", WordKind::Flag),
+    ("pgnhnsp", WordKind::Flag),
+    ("pgnid", WordKind::Flag),
+    ("pgnlcltr", WordKind::Flag),
+    ("pgnlcrm", WordKind::Flag),
+    ("pgnrestart", WordKind::Flag),
+    ("pgnstart", WordKind::Value),
+    ("pgnstarts", WordKind::Value),
+    ("pgnthaia", WordKind::Flag),
+    ("pgnthaib", WordKind::Flag),
+    ("pgnthaic", WordKind::Flag),
+    ("pgnucltr", WordKind::Flag),
+    ("pgnucrm", WordKind::Flag),
+    ("pgnvieta", WordKind::Flag),
+    ("pgnx", WordKind::Value),
+    ("pgny", WordKind::Value),
+    ("pgnzodiac", WordKind::Flag),
+    ("pgnzodiacd", WordKind::Flag),
+    ("pgnzodiacl", WordKind::Flag),
+    ("pgp", WordKind::Destination),
+    ("pgptbl", WordKind::Destination),
+    ("pgwsxn", WordKind::Value),
+    ("phcol", WordKind::Flag),
+    ("phmrg", WordKind::Flag),
+    ("phpg", WordKind::Flag),
+    ("picbmp", WordKind::Flag),
+    ("picbpp", WordKind::Value),
+    ("piccropb", WordKind::Value),
+    ("piccropl", WordKind::Value),
+    ("piccropr", WordKind::Value),
+    ("piccropt", WordKind::Value),
+    ("pich", WordKind::Value),
+    ("pichgoal", WordKind::Value),
+    ("picprop", WordKind::Destination),
+    ("picscaled", WordKind::Flag),
+    ("picscalex", WordKind::Value),
+    ("picscaley", WordKind::Value),
+    ("pict", WordKind::Destination),
+    ("picw", WordKind::Value),
+    ("picwgoal", WordKind::Value),
+    ("pindtabqc", WordKind::Flag),
+    ("pindtabql", WordKind::Flag),
+    ("pindtabqr", WordKind::Flag),
+    ("plain", WordKind::Flag),
+    ("pmartabqc", WordKind::Flag),
+    ("pmartabql", WordKind::Flag),
+    ("pmartabqr", WordKind::Flag),
+    ("pmmetafile", WordKind::Value),
+    ("pn", WordKind::Destination),
+    ("pnacross", WordKind::Flag),
+    ("pnaiu", WordKind::Flag),
+    ("pnaiud", WordKind::Flag),
+    ("pnaiueo", WordKind::Flag),
+    ("pnaiueod", WordKind::Flag),
+    ("pnb", WordKind::Toggle),
+    ("pnbidia", WordKind::Flag),
+    ("pnbidib", WordKind::Flag),
+    ("pncaps", WordKind::Toggle),
+    ("pncard", WordKind::Flag),
+    ("pncf", WordKind::Value),
+    ("pnchosung", WordKind::Flag),
+    ("pncnum", WordKind::Flag),
+    ("pndbnum", WordKind::Flag),
+    ("pndbnumd", WordKind::Flag),
+    ("pndbnumk", WordKind::Flag),
+    ("pndbnuml", WordKind::Flag),
+    ("pndbnumt", WordKind::Flag),
+    ("pndec", WordKind::Flag),
+    ("pndecd", WordKind::Flag),
+    ("pnf", WordKind::Value),
+    ("pnfs", WordKind::Value),
+    ("pnganada", WordKind::Flag),
+    ("pngblip", WordKind::Flag),
+    ("pngbnum", WordKind::Flag),
+    ("pngbnumd", WordKind::Flag),
+    ("pngbnumk", WordKind::Flag),
+    ("pngbnuml", WordKind::Flag),
+    ("pnhang", WordKind::Flag),
+    ("pni", WordKind::Toggle),
+    ("pnindent", WordKind::Value),
+    ("pniroha", WordKind::Flag),
+    ("pnirohad", WordKind::Flag),
+    ("pnlcltr", WordKind::Flag),
+    ("pnlcrm", WordKind::Flag),
+    ("pnlvl", WordKind::Value),
+    ("pnlvlblt", WordKind::Flag),
+    ("pnlvlbody", WordKind::Flag),
+    ("pnlvlcont", WordKind::Flag),
+    ("pnnumonce", WordKind::Flag),
+    ("pnord", WordKind::Flag),
+    ("pnordt", WordKind::Flag),
+    ("pnprev", WordKind::Flag),
+    ("pnqc", WordKind::Flag),
+    ("pnql", WordKind::Flag),
+    ("pnqr", WordKind::Flag),
+    ("pnrauth", WordKind::Value),
+    ("pnrdate", WordKind::Value),
+    ("pnrestart", WordKind::Flag),
+    ("pnrnfc", WordKind::Value),
+    ("pnrnot", WordKind::Flag),
+    ("pnrpnbr", WordKind::Value),
+    ("pnrrgb", WordKind::Value),
+    ("pnrstart", WordKind::Value),
+    ("pnrstop", WordKind::Value),
+    ("pnrxst", WordKind::Value),
+    ("pnscaps", WordKind::Toggle),
+    ("pnseclvl", WordKind::Destination),
+    ("pnsp", WordKind::Value),
+    ("pnstart", WordKind::Value),
+    ("pnstrike", WordKind::Toggle),
+    ("pntext", WordKind::Destination),
+    ("pntxta", WordKind::Destination),
+    ("pntxtb", WordKind::Destination),
+    ("pnucltr", WordKind::Flag),
+    ("pnucrm", WordKind::Flag),
+    ("pnul", WordKind::Toggle),
+    ("pnuld", WordKind::Flag),
+    ("pnuldash", WordKind::Flag),
+    ("pnuldashd", WordKind::Flag),
+    ("pnuldashdd", WordKind::Flag),
+    ("pnuldb", WordKind::Flag),
+    ("pnulhair", WordKind::Flag),
+    ("pnulnone", WordKind::Flag),
+    ("pnulth", WordKind::Flag),
+    ("pnulw", WordKind::Flag),
+    ("pnulwave", WordKind::Flag),
+    ("pnzodiac", WordKind::Flag),
+    ("pnzodiacd", WordKind::Flag),
+    ("pnzodiacl", WordKind::Flag),
+    ("posnegx", WordKind::Value),
+    ("posnegy", WordKind::Value),
+    ("posx", WordKind::Value),
+    ("posxc", WordKind::Flag),
+    ("posxi", WordKind::Flag),
+    ("posxl", WordKind::Flag),
+    ("posxo", WordKind::Flag),
+    ("posxr", WordKind::Flag),
+    ("posy", WordKind::Value),
+    ("posyb", WordKind::Flag),
+    ("posyc", WordKind::Flag),
+    ("posyil", WordKind::Flag),
+    ("posyin", WordKind::Flag),
+    ("posyout", WordKind::Flag),
+    ("posyt", WordKind::Flag),
+    ("prauth", WordKind::Value),
+    ("prcolbl", WordKind::Flag),
+    ("prdate", WordKind::Value),
+    ("printdata", WordKind::Flag),
+    ("printim", WordKind::Destination),
+    ("private", WordKind::Destination),
+    ("propname", WordKind::Destination),
+    ("proptype", WordKind::Value),
+    ("protect", WordKind::Toggle),
+    ("protend", WordKind::Destination),
+    ("protlevel", WordKind::Value),
+    ("protstart", WordKind::Destination),
+    ("protusertbl", WordKind::Destination),
+    ("psover", WordKind::Flag),
+    ("psz", WordKind::Value),
+    ("ptabldot", WordKind::Flag),
+    ("ptablmdot", WordKind::Flag),
+    ("ptablminus", WordKind::Flag),
+    ("ptablnone", WordKind::Flag),
+    ("ptabluscore", WordKind::Flag),
+    ("pubauto", WordKind::Flag),
+    ("pvmrg", WordKind::Flag),
+    ("pvpara", WordKind::Flag),
+    ("pvpg", WordKind::Flag),
+    ("pwd", WordKind::Value),
+    ("pxe", WordKind::Destination),
+    ("qc", WordKind::Flag),
+    ("qd", WordKind::Flag),
+    ("qj", WordKind::Flag),
+    ("qk", WordKind::Value),
+    ("ql", WordKind::Flag),
+    ("qmspace", WordKind::Symbol),
+    ("qr", WordKind::Flag),
+    ("qt", WordKind::Flag),
+    ("rawclbgbdiag", WordKind::Flag),
+    ("rawclbgcross", WordKind::Flag),
+    ("rawclbgdcross", WordKind::Flag),
+    ("rawclbgdkbdiag", WordKind::Flag),
+    ("rawclbgdkcross", WordKind::Flag),
+    ("rawclbgdkdcross", WordKind::Flag),
+    ("rawclbgdkfdiag", WordKind::Flag),
+    ("rawclbgdkhor", WordKind::Flag),
+    ("rawclbgdkvert", WordKind::Flag),
+    ("rawclbgfdiag", WordKind::Flag),
+    ("rawclbghoriz", WordKind::Flag),
+    ("rawclbgvert", WordKind::Flag),
+    ("rdblquote", WordKind::Symbol),
+    ("readonlyrecommended", WordKind::Flag),
+    ("readprot", WordKind::Flag),
+    ("red", WordKind::Value),
+    ("relyonvml", WordKind::Value),
+    ("remdttm", WordKind::Flag),
+    ("rempersonalinfo", WordKind::Flag),
+    ("result", WordKind::Destination),
+    ("revauth", WordKind::Value),
+    ("revauthdel", WordKind::Value),
+    ("revbar", WordKind::Value),
+    ("revdttm", WordKind::Value),
+    ("revdttmdel", WordKind::Value),
+    ("revised", WordKind::Toggle),
+    ("revisions", WordKind::Flag),
+    ("revprop", WordKind::Value),
+    ("revprot", WordKind::Flag),
+    ("revtbl", WordKind::Destination),
+    ("revtim", WordKind::Destination),
+    ("ri", WordKind::Value),
+    ("rin", WordKind::Value),
+    ("row", WordKind::Symbol),
+    ("rquote", WordKind::Symbol),
+    ("rsid", WordKind::Value),
+    ("rsidroot", WordKind::Value),
+    ("rsidtbl", WordKind::Destination),
+    ("rsltbmp", WordKind::Flag),
+    ("rslthtml", WordKind::Flag),
+    ("rsltmerge", WordKind::Flag),
+    ("rsltpict", WordKind::Flag),
+    ("rsltrtf", WordKind::Flag),
+    ("rslttxt", WordKind::Flag),
+    ("rtf", WordKind::Destination),
+    ("rtlch", WordKind::Flag),
+    ("rtldoc", WordKind::Flag),
+    ("rtlgutter", WordKind::Flag),
+    ("rtlmark", WordKind::Symbol),
+    ("rtlpar", WordKind::Flag),
+    ("rtlrow", WordKind::Flag),
+    ("rtlsect", WordKind::Flag),
+    ("rxe", WordKind::Destination),
+    ("s", WordKind::Value),
+    ("sa", WordKind::Value),
+    ("saauto", WordKind::Toggle),
+    ("saftnnalc", WordKind::Flag),
+    ("saftnnar", WordKind::Flag),
+    ("saftnnauc", WordKind::Flag),
+    ("saftnnchi", WordKind::Flag),
+    ("saftnnchosung", WordKind::Flag),
+    ("saftnncnum", WordKind::Flag),
+    ("saftnndbar", WordKind::Flag),
+    ("saftnndbnum", WordKind::Flag),
+    ("saftnndbnumd", WordKind::Flag),
+    ("saftnndbnumk", WordKind::Flag),
+    ("saftnndbnumt", WordKind::Flag),
+    ("saftnnganada", WordKind::Flag),
+    ("saftnngbnum", WordKind::Flag),
+    ("saftnngbnumd", WordKind::Flag),
+    ("saftnngbnumk", WordKind::Flag),
+    ("saftnngbnuml", WordKind::Flag),
+    ("saftnnrlc", WordKind::Flag),
+    ("saftnnruc", WordKind::Flag),
+    ("saftnnzodiac", WordKind::Flag),
+    ("saftnnzodiacd", WordKind::Flag),
+    ("saftnnzodiacl", WordKind::Flag),
+    ("saftnrestart", WordKind::Flag),
+    ("saftnrstcont", WordKind::Flag),
+    ("saftnstart", WordKind::Value),
+    ("sautoupd", WordKind::Flag),
+    ("saveinvalidxml", WordKind::Flag),
+    ("saveprevpict", WordKind::Flag),
+    ("sb", WordKind::Value),
+    ("sbasedon", WordKind::Value),
+    ("sbauto", WordKind::Toggle),
+    ("sbkcol", WordKind::Flag),
+    ("sbkeven", WordKind::Flag),
+    ("sbknone", WordKind::Flag),
+    ("sbkodd", WordKind::Flag),
+    ("sbkpage", WordKind::Flag),
+    ("sbys", WordKind::Flag),
+    ("scaps", WordKind::Toggle),
+    ("scompose", WordKind::Flag),
+    ("sec", WordKind::Value),
+    ("sect", WordKind::Symbol),
+    ("sectd", WordKind::Flag),
+    ("sectdefaultcl", WordKind::Flag),
+    ("sectexpand", WordKind::Value),
+    ("sectlinegrid", WordKind::Value),
+    ("sectnum", WordKind::Symbol),
+    ("sectrsid", WordKind::Value),
+    ("sectspecifycl", WordKind::Flag),
+    ("sectspecifygen", WordKind::Flag),
+    ("sectspecifyl", WordKind::Flag),
+    ("sectunlocked", WordKind::Flag),
+    ("sftnbj", WordKind::Flag),
+    ("sftnnalc", WordKind::Flag),
+    ("sftnnar", WordKind::Flag),
+    ("sftnnauc", WordKind::Flag),
+    ("sftnnchi", WordKind::Flag),
+    ("sftnnchosung", WordKind::Flag),
+    ("sftnncnum", WordKind::Flag),
+    ("sftnndbar", WordKind::Flag),
+    ("sftnndbnum", WordKind::Flag),
+    ("sftnndbnumd", WordKind::Flag),
+    ("sftnndbnumk", WordKind::Flag),
+    ("sftnndbnumt", WordKind::Flag),
+    ("sftnnganada", WordKind::Flag),
+    ("sftnngbnum", WordKind::Flag),
+    ("sftnngbnumd", WordKind::Flag),
+    ("sftnngbnumk", WordKind::Flag),
+    ("sftnngbnuml", WordKind::Flag),
+    ("sftnnrlc", WordKind::Flag),
+    ("sftnnruc", WordKind::Flag),
+    ("sftnnzodiac", WordKind::Flag),
+    ("sftnnzodiacd", WordKind::Flag),
+    ("sftnnzodiacl", WordKind::Flag),
+    ("sftnrestart", WordKind::Flag),
+    ("sftnrstcont", WordKind::Flag),
+    ("sftnrstpg", WordKind::Flag),
+    ("sftnstart", WordKind::Value),
+    ("sftntj", WordKind::Flag),
+    ("shad", WordKind::Toggle),
+    ("shading", WordKind::Value),
+    ("shidden", WordKind::Flag),
+    ("shift", WordKind::Flag),
+    ("showplaceholdtext", WordKind::Value),
+    ("showxmlerrors", WordKind::Value),
+    ("shp", WordKind::Destination),
+    ("shpbottom", WordKind::Value),
+    ("shpbxcolumn", WordKind::Flag),
+    ("shpbxignore", WordKind::Flag),
+    ("shpbxmargin", WordKind::Flag),
+    ("shpbxpage", WordKind::Flag),
+    ("shpbyignore", WordKind::Flag),
+    ("shpbymargin", WordKind::Flag),
+    ("shpbypage", WordKind::Flag),
+    ("shpbypara", WordKind::Flag),
+    ("shpfblwtxt", WordKind::Value),
+    ("shpfhdr", WordKind::Value),
+    ("shpgrp", WordKind::Destination),
+    ("shpinst", WordKind::Destination),
+    ("shpleft", WordKind::Value),
+    ("shplid", WordKind::Value),
+    ("shplockanchor", WordKind::Flag),
+    ("shppict", WordKind::Destination),
+    ("shpright", WordKind::Value),
+    ("shprslt", WordKind::Destination),
+    ("shptop", WordKind::Value),
+    ("shptxt", WordKind::Destination),
+    ("shpwr", WordKind::Value),
+    ("shpwrk", WordKind::Value),
+    ("shpz", WordKind::Value),
+    ("sl", WordKind::Value),
+    ("slink", WordKind::Value),
+    ("slmult", WordKind::Value),
+    ("slocked", WordKind::Flag),
+    ("sn", WordKind::Destination),
+    ("snaptogridincell", WordKind::Flag),
+    ("snext", WordKind::Value),
+    ("softcol", WordKind::Flag),
+    ("softlheight", WordKind::Value),
+    ("softline", WordKind::Flag),
+    ("softpage", WordKind::Flag),
+    ("sp", WordKind::Destination),
+    ("spersonal", WordKind::Flag),
+    ("spltpgpar", WordKind::Flag),
+    ("splytwnine", WordKind::Flag),
+    ("spriority", WordKind::Value),
+    ("sprsbsp", WordKind::Flag),
+    ("sprslnsp", WordKind::Flag),
+    ("sprsspbf", WordKind::Flag),
+    ("sprstsm", WordKind::Flag),
+    ("sprstsp", WordKind::Flag),
+    ("spv", WordKind::Flag),
+    ("sqformat", WordKind::Flag),
+    ("srauth", WordKind::Value),
+    ("srdate", WordKind::Value),
+    ("sreply", WordKind::Flag),
+    ("ssemihidden", WordKind::Value),
+    ("staticval", WordKind::Destination),
+    ("stextflow", WordKind::Value),
+    ("strike", WordKind::Toggle),
+    ("striked", WordKind::Toggle),
+    ("stshfbi", WordKind::Value),
+    ("stshfdbch", WordKind::Value),
+    ("stshfhich", WordKind::Value),
+    ("stshfloch", WordKind::Value),
+    ("stylelock", WordKind::Flag),
+    ("stylelockbackcomp", WordKind::Flag),
+    ("stylelockenforced", WordKind::Flag),
+    ("stylelockqfset", WordKind::Flag),
+    ("stylelocktheme", WordKind::Flag),
+    ("stylesheet", WordKind::Destination),
+    ("stylesortmethod", WordKind::Value),
+    ("styrsid", WordKind::Value),
+    ("sub", WordKind::Flag),
+    ("subdocument", WordKind::Value),
+    ("subfontbysize", WordKind::Flag),
+    ("subject", WordKind::Destination),
+    ("sunhideused", WordKind::Value),
+    ("super", WordKind::Flag),
+    ("sv", WordKind::Destination),
+    ("svb", WordKind::Destination),
+    ("swpbdr", WordKind::Flag),
+    ("tab", WordKind::Symbol),
+    ("tabsnoovrlp", WordKind::Flag),
+    ("taprtl", WordKind::Flag),
+    ("tb", WordKind::Value),
+    ("tblind", WordKind::Value),
+    ("tblindtype", WordKind::Value),
+    ("tbllkbestfit", WordKind::Flag),
+    ("tbllkborder", WordKind::Flag),
+    ("tbllkcolor", WordKind::Flag),
+    ("tbllkfont", WordKind::Flag),
+    ("tbllkhdrcols", WordKind::Flag),
+    ("tbllkhdrrows", WordKind::Flag),
+    ("tbllklastcol", WordKind::Flag),
+    ("tbllklastrow", WordKind::Flag),
+    ("tbllknocolband", WordKind::Flag),
+    ("tbllknorowband", WordKind::Flag),
+    ("tbllkshading", WordKind::Flag),
+    ("tblrsid", WordKind::Value),
+    ("tc", WordKind::Destination),
+    ("tcelld", WordKind::Flag),
+    ("tcf", WordKind::Value),
+    ("tcl", WordKind::Value),
+    ("tcn", WordKind::Flag),
+    ("tdfrmtxtBottom", WordKind::Value),
+    ("tdfrmtxtLeft", WordKind::Value),
+    ("tdfrmtxtRight", WordKind::Value),
+    ("tdfrmtxtTop", WordKind::Value),
+    ("template", WordKind::Destination),
+    ("themedata", WordKind::Destination),
+    ("themelang", WordKind::Value),
+    ("themelangcs", WordKind::Value),
+    ("themelangfe", WordKind::Value),
+    ("time", WordKind::Flag),
+    ("title", WordKind::Destination),
+    ("titlepg", WordKind::Flag),
+    ("tldot", WordKind::Flag),
+    ("tleq", WordKind::Flag),
+    ("tlhyph", WordKind::Flag),
+    ("tlmdot", WordKind::Flag),
+    ("tlth", WordKind::Flag),
+    ("tlul", WordKind::Flag),
+    ("toplinepunct", WordKind::Flag),
+    ("tphcol", WordKind::Flag),
+    ("tphmrg", WordKind::Flag),
+    ("tphpg", WordKind::Flag),
+    ("tposnegx", WordKind::Value),
+    ("tposnegy", WordKind::Value),
+    ("tposx", WordKind::Value),
+    ("tposxc", WordKind::Flag),
+    ("tposxi", WordKind::Flag),
+    ("tposxl", WordKind::Flag),
+    ("tposxo", WordKind::Flag),
+    ("tposxr", WordKind::Flag),
+    ("tposy", WordKind::Value),
+    ("tposyb", WordKind::Flag),
+    ("tposyc", WordKind::Flag),
+    ("tposyil", WordKind::Flag),
+    ("tposyin", WordKind::Flag),
+    ("tposyout", WordKind::Flag),
+    ("tposyt", WordKind::Flag),
+    ("tpvmrg", WordKind::Flag),
+    ("tpvpara", WordKind::Flag),
+    ("tpvpg", WordKind::Flag),
+    ("tqc", WordKind::Flag),
+    ("tqdec", WordKind::Flag),
+    ("tqr", WordKind::Flag),
+    ("trackformatting", WordKind::Value),
+    ("trackmoves", WordKind::Value),
+    ("transmf", WordKind::Flag),
+    ("trauth", WordKind::Value),
+    ("trautofit", WordKind::Toggle),
+    ("trbgbdiag", WordKind::Flag),
+    ("trbgcross", WordKind::Flag),
+    ("trbgdcross", WordKind::Flag),
+    ("trbgdkbdiag", WordKind::Flag),
+    ("trbgdkcross", WordKind::Flag),
+    ("trbgdkdcross", WordKind::Flag),
+    ("trbgdkfdiag", WordKind::Flag),
+    ("trbgdkhor", WordKind::Flag),
+    ("trbgdkvert", WordKind::Flag),
+    ("trbgfdiag", WordKind::Flag),
+    ("trbghoriz", WordKind::Flag),
+    ("trbgvert", WordKind::Flag),
+    ("trbrdrb", WordKind::Flag),
+    ("trbrdrh", WordKind::Flag),
+    ("trbrdrl", WordKind::Flag),
+    ("trbrdrr", WordKind::Flag),
+    ("trbrdrt", WordKind::Flag),
+    ("trbrdrv", WordKind::Flag),
+    ("trcbpat", WordKind::Value),
+    ("trcfpat", WordKind::Value),
+    ("trdate", WordKind::Value),
+    ("trftsWidth", WordKind::Value),
+    ("trftsWidthA", WordKind::Value),
+    ("trftsWidthB", WordKind::Value),
+    ("trgaph", WordKind::Value),
+    ("trhdr", WordKind::Flag),
+    ("trkeep", WordKind::Flag),
+    ("trkeepfollow", WordKind::Flag),
+    ("trleft", WordKind::Value),
+    ("trowd", WordKind::Flag),
+    ("trpaddb", WordKind::Value),
+    ("trpaddfb", WordKind::Value),
+    ("trpaddfl", WordKind::Value),
+    ("trpaddfr", WordKind::Value),
+    ("trpaddft", WordKind::Value),
+    ("trpaddl", WordKind::Value),
+    ("trpaddr", WordKind::Value),
+    ("trpaddt", WordKind::Value),
+    ("trpadob", WordKind::Value),
+    ("trpadofb", WordKind::Value),
+    ("trpadofl", WordKind::Value),
+    ("trpadofr", WordKind::Value),
+    ("trpadoft", WordKind::Value),
+    ("trpadol", WordKind::Value),
+    ("trpador", WordKind::Value),
+    ("trpadot", WordKind::Value),
+    ("trpat", WordKind::Value),
+    ("trqc", WordKind::Flag),
+    ("trql", WordKind::Flag),
+    ("trqr", WordKind::Flag),
+    ("trrh", WordKind::Value),
+    ("trshdng", WordKind::Value),
+    ("trspdb", WordKind::Value),
+    ("trspdfb", WordKind::Value),
+    ("trspdfl", WordKind::Value),
+    ("trspdfr", WordKind::Value),
+    ("trspdft", WordKind::Value),
+    ("trspdl", WordKind::Value),
+    ("trspdr", WordKind::Value),
+    ("trspdt", WordKind::Value),
+    ("trspob", WordKind::Value),
+    ("trspofb", WordKind::Value),
+    ("trspofl", WordKind::Value),
+    ("trspofr", WordKind::Value),
+    ("trspoft", WordKind::Value),
+    ("trspol", WordKind::Value),
+    ("trspor", WordKind::Value),
+    ("trspot", WordKind::Value),
+    ("truncatefontheight", WordKind::Flag),
+    ("truncex", WordKind::Flag),
+    ("trwWidth", WordKind::Value),
+    ("trwWidthA", WordKind::Value),
+    ("trwWidthB", WordKind::Value),
+    ("ts", WordKind::Value),
+    ("tsbgbdiag", WordKind::Flag),
+    ("tsbgcross", WordKind::Flag),
+    ("tsbgdcross", WordKind::Flag),
+    ("tsbgdkbdiag", WordKind::Flag),
+    ("tsbgdkcross", WordKind::Flag),
+    ("tsbgdkdcross", WordKind::Flag),
+    ("tsbgdkfdiag", WordKind::Flag),
+    ("tsbgdkhor", WordKind::Flag),
+    ("tsbgdkvert", WordKind::Flag),
+    ("tsbgfdiag", WordKind::Flag),
+    ("tsbghoriz", WordKind::Flag),
+    ("tsbgvert", WordKind::Flag),
+    ("tsbrdrb", WordKind::Flag),
+    ("tsbrdrdgl", WordKind::Flag),
+    ("tsbrdrdgr", WordKind::Flag),
+    ("tsbrdrh", WordKind::Flag),
+    ("tsbrdrl", WordKind::Flag),
+    ("tsbrdrr", WordKind::Flag),
+    ("tsbrdrt", WordKind::Flag),
+    ("tsbrdrv", WordKind::Flag),
+    ("tscbandhorzeven", WordKind::Flag),
+    ("tscbandhorzodd", WordKind::Flag),
+    ("tscbandsh", WordKind::Value),
+    ("tscbandsv", WordKind::Value),
+    ("tscbandverteven", WordKind::Flag),
+    ("tscbandvertodd", WordKind::Flag),
+    ("tscellcbpat", WordKind::Value),
+    ("tscellcfpat", WordKind::Value),
+    ("tscellpaddb", WordKind::Value),
+    ("tscellpaddfb", WordKind::Value),
+    ("tscellpaddfl", WordKind::Value),
+    ("tscellpaddfr", WordKind::Value),
+    ("tscellpaddft", WordKind::Value),
+    ("tscellpaddl", WordKind::Value),
+    ("tscellpaddr", WordKind::Value),
+    ("tscellpaddt", WordKind::Value),
+    ("tscellpct", WordKind::Value),
+    ("tscellwidth", WordKind::Value),
+    ("tscellwidthfts", WordKind::Value),
+    ("tscfirstcol", WordKind::Flag),
+    ("tscfirstrow", WordKind::Flag),
+    ("tsclastcol", WordKind::Flag),
+    ("tsclastrow", WordKind::Flag),
+    ("tscnecell", WordKind::Flag),
+    ("tscnwcell", WordKind::Flag),
+    ("tscsecell", WordKind::Flag),
+    ("tscswcell", WordKind::Flag),
+    ("tsd", WordKind::Flag),
+    ("tsnowrap", WordKind::Flag),
+    ("tsrowd", WordKind::Flag),
+    ("tsvertalb", WordKind::Flag),
+    ("tsvertalc", WordKind::Flag),
+    ("tsvertalt", WordKind::Flag),
+    ("twoinone", WordKind::Value),
+    ("twoonone", WordKind::Flag),
+    ("tx", WordKind::Value),
+    ("txbxtwalways", WordKind::Flag),
+    ("txbxtwfirst", WordKind::Flag),
+    ("txbxtwfirstlast", WordKind::Flag),
+    ("txbxtwlast", WordKind::Flag),
+    ("txbxtwno", WordKind::Flag),
+    ("txe", WordKind::Destination),
+    ("u", WordKind::Value),
+    ("uc", WordKind::Value),
+    ("ud", WordKind::Destination),
+    ("ul", WordKind::Toggle),
+    ("ulc", WordKind::Value),
+    ("uld", WordKind::Flag),
+    ("uldash", WordKind::Toggle),
+    ("uldashd", WordKind::Toggle),
+    ("uldashdd", WordKind::Toggle),
+    ("uldb", WordKind::Toggle),
+    ("ulhair", WordKind::Toggle),
+    ("ulhwave", WordKind::Toggle),
+    ("ulldash", WordKind::Toggle),
+    ("ulnone", WordKind::Flag),
+    ("ulth", WordKind::Toggle),
+    ("ulthd", WordKind::Toggle),
+    ("ulthdash", WordKind::Toggle),
+    ("ulthdashd", WordKind::Toggle),
+    ("ulthdashdd", WordKind::Toggle),
+    ("ulthldash", WordKind::Toggle),
+    ("ululdbwave", WordKind::Toggle),
+    ("ulw", WordKind::Flag),
+    ("ulwave", WordKind::Toggle),
+    ("up", WordKind::Value),
+    ("upr", WordKind::Destination),
+    ("urtf", WordKind::Value),
+    ("useltbaln", WordKind::Flag),
+    ("usenormstyforlist", WordKind::Flag),
+    ("userprops", WordKind::Destination),
+    ("usexform", WordKind::Flag),
+    ("utinl", WordKind::Flag),
+    ("v", WordKind::Toggle),
+    ("validatexml", WordKind::Value),
+    ("vern", WordKind::Value),
+    ("version", WordKind::Value),
+    ("vertal", WordKind::Flag),
+    ("vertalb", WordKind::Flag),
+    ("vertalc", WordKind::Flag),
+    ("vertalj", WordKind::Flag),
+    ("vertalt", WordKind::Flag),
+    ("vertdoc", WordKind::Flag),
+    ("vertsect", WordKind::Flag),
+    ("viewbksp", WordKind::Value),
+    ("viewkind", WordKind::Value),
+    ("viewnobound", WordKind::Flag),
+    ("viewscale", WordKind::Value),
+    ("viewzk", WordKind::Value),
+    ("wbitmap", WordKind::Value),
+    ("wbmbitspixel", WordKind::Value),
+    ("wbmplanes", WordKind::Value),
+    ("wbmwidthbyte", WordKind::Value),
+    ("webhidden", WordKind::Flag),
+    ("wgrffmtfilter", WordKind::Destination),
+    ("widctlpar", WordKind::Flag),
+    ("widowctrl", WordKind::Flag),
+    ("windowcaption", WordKind::Destination),
+    ("wmetafile", WordKind::Value),
+    ("wpeqn", WordKind::Flag),
+    ("wpjst", WordKind::Flag),
+    ("wpsp", WordKind::Flag),
+    ("wraparound", WordKind::Flag),
+    ("wrapdefault", WordKind::Flag),
+    ("wrapthrough", WordKind::Flag),
+    ("wraptight", WordKind::Flag),
+    ("wraptrsp", WordKind::Flag),
+    ("writereservation", WordKind::Destination),
+    ("writereservhash", WordKind::Destination),
+    ("wrppunct", WordKind::Flag),
+    ("xe", WordKind::Destination),
+    ("xef", WordKind::Value),
+    ("xform", WordKind::Destination),
+    ("xmlattr", WordKind::Flag),
+    ("xmlattrname", WordKind::Destination),
+    ("xmlattrns", WordKind::Value),
+    ("xmlattrvalue", WordKind::Destination),
+    ("xmlclose", WordKind::Destination),
+    ("xmlname", WordKind::Destination),
+    ("xmlns", WordKind::Value),
+    ("xmlnstbl", WordKind::Destination),
+    ("xmlopen", WordKind::Destination),
+    ("xmlsdttcell", WordKind::Flag),
+    ("xmlsdttpara", WordKind::Flag),
+    ("xmlsdttregular", WordKind::Flag),
+    ("xmlsdttrow", WordKind::Flag),
+    ("xmlsdttunknown", WordKind::Flag),
+    ("yr", WordKind::Value),
+    ("yts", WordKind::Value),
+    ("yxe", WordKind::Flag),
+    ("zwbo", WordKind::Symbol),
+    ("zwj", WordKind::Symbol),
+    ("zwnbo", WordKind::Symbol),
+    ("zwnj", WordKind::Symbol),
+];
+
+/// The kind of a control word, or `None` if the spec doesn't define it
+pub fn word_kind(name: &str) -> Option<WordKind> {
+    WORDS
+        .binary_search_by(|(word, _)| word.as_bytes().cmp(name.as_bytes()))
+        .ok()
+        .map(|index| WORDS[index].1)
+}
+
+/// Whether the spec defines a control symbol, a backslash and `c`
+pub fn is_control_symbol(c: char) -> bool {
+    SYMBOLS.contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_sorted() {
+        assert!(WORDS
+            .windows(2)
+            .all(|pair| pair[0].0.as_bytes() < pair[1].0.as_bytes()));
+    }
+
+    #[test]
+    fn test_word_kind() {
+        assert_eq!(word_kind("b"), Some(WordKind::Toggle));
+        assert_eq!(word_kind("fs"), Some(WordKind::Value));
+        assert_eq!(word_kind("par"), Some(WordKind::Symbol));
+        assert_eq!(word_kind("fonttbl"), Some(WordKind::Destination));
+        assert_eq!(word_kind("ansi"), Some(WordKind::Flag));
+        assert_eq!(word_kind("mmathPr"), Some(WordKind::Destination));
+        assert_eq!(word_kind("nosuchword"), None);
+        assert!(is_control_symbol('~') && !is_control_symbol('#'));
+    }
+}
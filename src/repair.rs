@@ -0,0 +1,92 @@
+// Group repair
+//
+// Word opens documents whose braces don't balance, closing whatever is still
+// open at the end of the file and ignoring closing braces with nothing to
+// close.  Repairing a token stream does the same, so that tools that need
+// balanced groups can take such documents too, and says what it changed.
+//
+
+use std;
+
+use tokenizer::Token;
+
+/// A change `repair` made to a token stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Repair {
+    /// The group starting at this token index was never closed, and is
+    /// closed at the end
+    ClosedGroup { start: usize },
+    /// The `}` at this token index had no group to close, and was dropped
+    DroppedEndGroup { index: usize },
+}
+
+impl std::fmt::Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Repair::ClosedGroup { start } => {
+                write!(f, "closed the group opened at token {}", start)
+            }
+            Repair::DroppedEndGroup { index } => {
+                write!(f, "dropped the unmatched }} at token {}", index)
+            }
+        }
+    }
+}
+
+/// Balance the groups of a token stream, as Word does
+///
+/// Closing braces with no group to close are dropped, and groups still open
+/// at the end are closed, innermost first.  Returns the repaired tokens and
+/// each repair made, in order; the indices in the repairs are those of the
+/// original tokens.  A balanced stream comes back unchanged, with no
+/// repairs.
+pub fn repair(tokens: &[Token]) -> (Vec<Token>, Vec<Repair>) {
+    let mut repaired = Vec::with_capacity(tokens.len());
+    let mut repairs = Vec::new();
+    let mut open = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::StartGroup => open.push(index),
+            Token::EndGroup if open.pop().is_none() => {
+                repairs.push(Repair::DroppedEndGroup { index });
+                continue;
+            }
+            _ => {}
+        }
+        repaired.push(token.clone());
+    }
+    while let Some(start) = open.pop() {
+        repaired.push(Token::EndGroup);
+        repairs.push(Repair::ClosedGroup { start });
+    }
+    (repaired, repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+    use validate::{validate, Level};
+
+    #[test]
+    fn test_repair() {
+        let tokens = parse(br"{\rtf1 a}} b {{\b c").unwrap();
+        let (repaired, repairs) = repair(&tokens);
+        assert_eq!(repaired, parse(br"{\rtf1 a} b {{\b c}}").unwrap());
+        assert_eq!(
+            repairs,
+            vec![
+                Repair::DroppedEndGroup { index: 4 },
+                Repair::ClosedGroup { start: 7 },
+                Repair::ClosedGroup { start: 6 },
+            ]
+        );
+        assert_eq!(repairs[0].to_string(), "dropped the unmatched } at token 4");
+        assert!(validate(&repaired, Level::Structure).is_empty());
+
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        assert_eq!(repair(&tokens), (tokens, vec![]));
+    }
+}
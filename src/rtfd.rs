@@ -0,0 +1,183 @@
+// RTFD bundles
+//
+// macOS saves rich text with attachments as an RTFD bundle: a directory
+// holding the document as `TXT.rtf`, alongside the attached files.  The
+// document refers to each attachment by file name, in a
+// `{{\NeXTGraphic name \width... \height...}¬}` group that stands where the
+// attachment appears.
+//
+
+use std;
+use std::path::Path;
+
+use document::Document;
+use tokenizer::{narrow_arg, parse, ParseError, Token};
+
+/// The name of the document inside a bundle
+pub const DOCUMENT_NAME: &str = "TXT.rtf";
+
+/// An attachment the document refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// The file name, relative to the bundle
+    pub name: String,
+    /// The `\width` and `\height` the attachment is shown at, in twips
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// The contents of the file, or `None` if the bundle doesn't have it
+    pub data: Option<Vec<u8>>,
+}
+
+/// An RTFD bundle: a document and its attachments
+#[derive(Debug)]
+pub struct RtfdBundle {
+    pub document: Document,
+    pub tokens: Vec<Token>,
+    /// The attachments, in the order the document refers to them
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug)]
+pub enum RtfdError {
+    Io(std::io::Error),
+    Parse(ParseError),
+}
+
+impl std::convert::From<std::io::Error> for RtfdError {
+    fn from(error: std::io::Error) -> Self {
+        RtfdError::Io(error)
+    }
+}
+
+impl std::convert::From<ParseError> for RtfdError {
+    fn from(error: ParseError) -> Self {
+        RtfdError::Parse(error)
+    }
+}
+
+impl std::fmt::Display for RtfdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RtfdError::Io(error) => write!(f, "{}", error),
+            RtfdError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for RtfdError {}
+
+/// The attachments referred to by `\NeXTGraphic` groups, without their data
+pub fn attachment_refs(tokens: &[Token]) -> Vec<Attachment> {
+    let mut attachments = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if !token.is_word("NeXTGraphic") {
+            continue;
+        }
+        let mut attachment = Attachment {
+            name: String::new(),
+            width: None,
+            height: None,
+            data: None,
+        };
+        // The file name is the text up to the first control word
+        let mut named = false;
+        for token in &tokens[index + 1..] {
+            match token {
+                Token::Text(text) if !named => {
+                    attachment.name.push_str(&String::from_utf8_lossy(text))
+                }
+                Token::ControlWord { name, arg } => {
+                    named = true;
+                    match name.as_str() {
+                        "width" => attachment.width = narrow_arg(*arg),
+                        "height" => attachment.height = narrow_arg(*arg),
+                        _ => {}
+                    }
+                }
+                Token::StartGroup | Token::EndGroup => break,
+                _ => {}
+            }
+        }
+        attachment.name = attachment.name.trim().to_string();
+        attachments.push(attachment);
+    }
+    attachments
+}
+
+impl RtfdBundle {
+    /// Read a bundle directory, and the attachments its document refers to
+    ///
+    /// Only names of files directly inside the bundle are read, so a
+    /// document can't refer to files elsewhere.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RtfdError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path.join(DOCUMENT_NAME))?;
+        let tokens = parse(&bytes)?;
+        let mut attachments = attachment_refs(&tokens);
+        for attachment in &mut attachments {
+            let name = Path::new(&attachment.name);
+            if name.file_name() == Some(name.as_os_str()) {
+                attachment.data = std::fs::read(path.join(name)).ok();
+            }
+        }
+        Ok(RtfdBundle {
+            document: Document::from_tokens(&tokens),
+            tokens,
+            attachments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::extract_text;
+
+    const TXT: &[u8] = b"{\\rtf1\\ansi\\ansicpg1252\\cocoartf2639\n\
+{\\fonttbl\\f0\\fswiss\\fcharset0 Helvetica;}\n\
+\\pard\\f0\\fs24 Before \n\
+{{\\NeXTGraphic Screen Shot.png \\width2400 \\height1200 \\appleattachmentpadding0 \\appleembedtype0 \\appleaqc\n\
+}\\'ac}\n\
+{{\\NeXTGraphic ../secret \\width20 \\height20\n\
+}\\'ac}\n\
+{{\\NeXTGraphic missing.tiff}\\'ac} after}";
+
+    #[test]
+    fn test_attachment_refs() {
+        let refs = attachment_refs(&parse(TXT).unwrap());
+        let names: Vec<&str> = refs.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Screen Shot.png", "../secret", "missing.tiff"]);
+        assert_eq!(refs[0].width, Some(2400));
+        assert_eq!(refs[0].height, Some(1200));
+        assert_eq!(refs[2].width, None);
+    }
+
+    #[test]
+    fn test_open_bundle() {
+        let dir = std::env::temp_dir().join("rtf-grimoire-rtfd-test.rtfd");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(DOCUMENT_NAME), TXT).unwrap();
+        std::fs::write(dir.join("Screen Shot.png"), b"\x89PNG").unwrap();
+        std::fs::write(dir.with_file_name("secret"), b"no").unwrap();
+
+        let bundle = RtfdBundle::open(&dir).unwrap();
+        let data: Vec<Option<&[u8]>> = bundle
+            .attachments
+            .iter()
+            .map(|a| a.data.as_deref())
+            .collect();
+        assert_eq!(data, vec![Some(&b"\x89PNG"[..]), None, None]);
+        // File names aren't part of the text
+        let text = extract_text(&bundle.tokens);
+        assert!(text.contains("Before"));
+        assert!(!text.contains("Screen"));
+
+        assert!(matches!(
+            RtfdBundle::open(dir.join("nowhere")),
+            Err(RtfdError::Io(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_file(dir.with_file_name("secret")).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+// Active content removal
+//
+// RTF documents can do more than describe text: embedded OLE objects, DDE
+// links and fields that pull in other files all run or fetch something when
+// the document is opened.  Sanitizing replaces each of them with the result
+// the writer cached alongside it, which is what a reader shows anyway, so the
+// document still looks the same.
+//
+
+use fields::destination_text;
+use tokenizer::{child_group, group_contents, group_destination, group_end, Token};
+
+/// Destinations that are removed outright: the OLE data store, and the
+/// attached template, which Word loads from wherever it names
+const REMOVED_DESTINATIONS: &[&str] = &["datastore", "template"];
+
+/// Field instructions that run something or read other files
+const DANGEROUS_FIELDS: &[&str] = &[
+    "DDE",
+    "DDEAUTO",
+    "IMPORT",
+    "INCLUDE",
+    "INCLUDEPICTURE",
+    "INCLUDETEXT",
+    "LINK",
+];
+
+// The field type, the first word of an instruction like ` HYPERLINK "..." `
+//
+// The instruction is decoded as Word reads it, so hex and `\u` escapes are
+// undone and destinations inside it, like bookmarks, are skipped.
+pub(crate) fn field_type(instruction: &[Token]) -> String {
+    destination_text(instruction)
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .to_uppercase()
+}
+
+// Write a group's cached result in its place
+fn replace_with_result(
+    tokens: &[Token],
+    result: Option<(usize, usize, usize)>,
+    out: &mut Vec<Token>,
+) {
+    if let Some((_, word, end)) = result {
+        out.push(Token::StartGroup);
+        sanitize_into(group_contents(tokens, word, end), out);
+        out.push(Token::EndGroup);
+    }
+}
+
+fn sanitize_into(tokens: &[Token], out: &mut Vec<Token>) {
+    let mut index = 0;
+    while index < tokens.len() {
+        if tokens[index] != Token::StartGroup {
+            out.push(tokens[index].clone());
+            index += 1;
+            continue;
+        }
+        let end = group_end(tokens, index);
+        match group_destination(tokens, index) {
+            Some((name, _, _)) if REMOVED_DESTINATIONS.contains(&name) => {}
+            Some(("object", _, _)) => {
+                let result = child_group(tokens, index, end, "result");
+                replace_with_result(tokens, result, out);
+            }
+            Some(("field", _, _)) => {
+                let dangerous = child_group(tokens, index, end, "fldinst")
+                    .map(|(_, word, end)| field_type(group_contents(tokens, word, end)))
+                    .is_some_and(|kind| DANGEROUS_FIELDS.contains(&kind.as_str()));
+                if dangerous {
+                    let result = child_group(tokens, index, end, "fldrslt");
+                    replace_with_result(tokens, result, out);
+                } else {
+                    out.push(Token::StartGroup);
+                    index += 1;
+                    continue;
+                }
+            }
+            _ => {
+                out.push(Token::StartGroup);
+                index += 1;
+                continue;
+            }
+        }
+        index = end;
+    }
+}
+
+/// Remove active content from a document
+///
+/// - `{\object}` groups, holding embedded OLE objects, are replaced by the
+///   contents of their `{\result}`, if they have one
+/// - fields that run commands or read other files (`DDE`, `DDEAUTO`,
+///   `IMPORT`, `INCLUDE`, `INCLUDEPICTURE`, `INCLUDETEXT` and `LINK`) are
+///   replaced by the contents of their `{\fldrslt}`; other fields, such as
+///   hyperlinks, are kept
+/// - the `{\*\datastore}` and `{\*\template}` destinations are removed
+pub fn sanitize(tokens: &[Token]) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    sanitize_into(tokens, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::extract_text;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    fn sanitized(rtf: &[u8]) -> String {
+        let tokens = sanitize(&parse(rtf).unwrap());
+        String::from_utf8(write_rtf(&tokens, &WriteOptions::default()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_replaces_objects() {
+        assert_eq!(
+            sanitized(br"{\rtf1 a{\object\objemb{\*\objclass Package}{\*\objdata 0102}{\result{\pict 00}}}b{\object{\*\objdata 03}}c}"),
+            r"{\rtf1 a{{\pict 00}}bc}"
+        );
+    }
+
+    #[test]
+    fn test_replaces_dangerous_fields() {
+        assert_eq!(
+            sanitized(br#"{\rtf1{\field{\*\fldinst  ddeauto c:\\cmd.exe "/c calc"}{\fldrslt x}}}"#),
+            r"{\rtf1{x}}"
+        );
+        assert_eq!(
+            sanitized(br#"{\rtf1{\field{\*\fldinst INCLUDEPICTURE "http://example.com/a.png"}{\fldrslt}}}"#),
+            r"{\rtf1{}}"
+        );
+        // However the type is escaped or interrupted
+        assert_eq!(
+            sanitized(
+                br#"{\rtf1{\field{\*\fldinst \'49NCLUDETEXT "c:\\secret.txt"}{\fldrslt x}}}"#
+            ),
+            r"{\rtf1{x}}"
+        );
+        assert_eq!(
+            sanitized(br#"{\rtf1{\field{\*\fldinst {\*\bkmkstart a}INCLUDEPICTURE "http://evil/a.png"}{\fldrslt}}}"#),
+            r"{\rtf1{}}"
+        );
+        assert_eq!(
+            sanitized(
+                br#"{\rtf1{\field{\*\fldinst \u73?nclude\u84?EXT "c:\\secret.txt"}{\fldrslt}}}"#
+            ),
+            r"{\rtf1{}}"
+        );
+        // Hyperlinks are kept
+        let link = r#"{\rtf1{\field{\*\fldinst HYPERLINK "http://example.com"}{\fldrslt here}}}"#;
+        assert_eq!(sanitized(link.as_bytes()), link);
+    }
+
+    #[test]
+    fn test_removes_destinations() {
+        assert_eq!(
+            sanitized(br"{\rtf1{\*\template c:\\evil.dotm}{\*\datastore 0105}x}"),
+            r"{\rtf1 x}"
+        );
+    }
+
+    #[test]
+    fn test_sample_doc_text_unchanged() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        assert_eq!(extract_text(&sanitize(&tokens)), extract_text(&tokens));
+    }
+}
@@ -0,0 +1,516 @@
+// Quick text scanning
+//
+// A single pass over the raw bytes of a document that produces the same text
+// as tokenizing it and running `text::extract_text_with` over the tokens,
+// without building any tokens along the way.  Only the reader state that
+// affects the text is tracked: which groups are skipped, the `\uc` value,
+// pending `\u` fallback characters, and the document defaults and font table
+// that determine how bytes are decoded.
+//
+
+use std;
+
+use raw::MAX_ARG_DIGITS;
+use text::{
+    ignored_destination, push_text_char, special_char, utf16_unit, DecoderState, TextOptions,
+    TEXT_WORDS,
+};
+use tokenizer::narrow_arg;
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    ignored: bool,
+    font_table: bool,
+    uc: usize,
+    font: Option<i32>,
+    shape: bool,
+    hidden: bool,
+}
+
+struct Scanner<'a> {
+    options: &'a TextOptions,
+    text: &'a mut String,
+    stack: Vec<Frame>,
+    frame: Frame,
+    state: DecoderState,
+    group_start: bool,
+    fallback: usize,
+    // The lead byte of a two byte character, waiting for its second byte
+    lead: Option<u8>,
+    // A high surrogate from a \u escape, waiting for its low surrogate
+    surrogate: Option<u32>,
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+}
+
+// Read a control word's optional numeric argument the way the tokenizer does,
+// returning the argument and the number of bytes it occupies.  An argument
+// with too many digits saturates.
+fn read_arg(bytes: &[u8]) -> (Option<i64>, usize) {
+    let negative = bytes.first() == Some(&b'-');
+    let start = if negative { 1 } else { 0 };
+    let digits = bytes[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if digits == 0 {
+        return (None, 0);
+    }
+    if digits > MAX_ARG_DIGITS {
+        let value = if negative { i64::MIN } else { i64::MAX };
+        return (Some(value), start + digits);
+    }
+    let value = std::str::from_utf8(&bytes[start..start + digits])
+        .ok()
+        .and_then(|digits| digits.parse::<i64>().ok());
+    match value {
+        Some(value) if negative => (Some(-value), start + digits),
+        Some(value) => (Some(value), start + digits),
+        None => (None, 0),
+    }
+}
+
+impl<'a> Scanner<'a> {
+    // Push a character of text, which leaves a high surrogate before it
+    // standing alone, as `Decoder` does
+    fn push(&mut self, c: char) {
+        if self.surrogate.take().is_some() {
+            self.push_char('\u{fffd}');
+        }
+        self.push_char(c);
+    }
+
+    fn push_char(&mut self, c: char) {
+        if !self.options.hidden.keeps(self.frame.hidden) {
+            return;
+        }
+        push_text_char(self.text, c, self.options.whitespace);
+    }
+
+    // Returns true if a \u fallback character was pending, and consumed
+    fn skip_fallback(&mut self) -> bool {
+        if self.fallback > 0 {
+            self.fallback -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Returns true if a control word or symbol was consumed as a \u fallback
+    // character, as `Decoder` does
+    fn skip_fallback_word(&mut self) -> bool {
+        if self.options.quirks.word_is_fallback() {
+            self.skip_fallback()
+        } else {
+            self.fallback = 0;
+            false
+        }
+    }
+
+    fn special_char(&self, name: &str) -> Option<char> {
+        special_char(self.options.special_chars.as_deref(), name)
+    }
+
+    fn decode(&mut self, byte: u8) -> Option<char> {
+        self.state
+            .decode_char(self.frame.font, &mut self.lead, byte)
+    }
+
+    fn word(&mut self, name: &[u8], arg: Option<i32>, group_start: bool) {
+        let name = std::str::from_utf8(name).unwrap_or_default();
+        if group_start
+            && ignored_destination(name, self.options.quirks, self.options.headers_footers)
+        {
+            self.frame.ignored = true;
+        }
+        if group_start && name == "fonttbl" {
+            self.frame.font_table = true;
+        }
+        if group_start {
+            match name {
+                "shpinst" | "do" if !self.stack.last().is_some_and(|frame| frame.ignored) => {
+                    self.frame.shape = true
+                }
+                "shptxt" | "dptxbxtext" if self.frame.shape => self.frame.ignored = false,
+                "shprslt" => self.frame.shape = false,
+                _ => {}
+            }
+        }
+        match (name, arg) {
+            ("uc", Some(count)) => self.frame.uc = std::cmp::max(count, 0) as usize,
+            _ if self.frame.ignored && self.frame.font_table => {
+                self.state.font_table_word(name, arg)
+            }
+            _ if self.frame.ignored => {}
+            ("u", Some(codepoint)) => {
+                let codepoint = if codepoint < 0 {
+                    codepoint + 65536
+                } else {
+                    codepoint
+                };
+                for c in utf16_unit(&mut self.surrogate, codepoint as u32)
+                    .iter()
+                    .flatten()
+                {
+                    self.push_char(*c);
+                }
+                self.fallback = self.frame.uc;
+            }
+            _ if self.skip_fallback_word() => {}
+            _ => match self.special_char(name) {
+                // Breaks are structure, which `Decoder` leaves to its caller
+                Some(c) if TEXT_WORDS.contains(&name) => self.push_char(c),
+                Some(c) => self.push(c),
+                None => match name {
+                    "f" => self.frame.font = arg,
+                    "plain" => {
+                        self.frame.font = None;
+                        self.frame.hidden = false;
+                    }
+                    "v" => self.frame.hidden = arg != Some(0),
+                    _ => {
+                        let options = self.options;
+                        if let Some(text) = options.dynamic.text(name) {
+                            for c in text.chars() {
+                                self.push_char(c);
+                            }
+                        }
+                        self.state.word(name, arg);
+                    }
+                },
+            },
+        }
+    }
+
+    fn symbol(&mut self, symbol: u8, group_start: bool) {
+        if symbol == b'*' && group_start {
+            // The destination name follows the \*
+            self.group_start = true;
+            self.frame.ignored = true;
+            self.frame.font_table = false;
+            return;
+        }
+        if self.frame.ignored || self.skip_fallback_word() {
+            return;
+        }
+        match symbol {
+            b'{' | b'}' | b'\\' => self.push(symbol as char),
+            _ => {
+                if let Some(c) = self.special_char(&(symbol as char).to_string()) {
+                    self.push(c);
+                }
+            }
+        }
+    }
+
+    // Handle the control sequence at the start of `bytes`, returning its
+    // length, or None if it can't be read
+    fn control(&mut self, bytes: &[u8], group_start: bool) -> Option<usize> {
+        let next = *bytes.get(1)?;
+        if next == b'\'' {
+            let high = hex_value(*bytes.get(2)?)?;
+            let low = hex_value(*bytes.get(3)?)?;
+            let byte = high << 4 | low;
+            if self.frame.ignored {
+                if self.frame.font_table {
+                    self.state.font_table_byte(byte);
+                }
+            } else if !self.skip_fallback() {
+                if let Some(c) = self.decode(byte) {
+                    self.push(c);
+                }
+            }
+            return Some(4);
+        }
+        self.lead = None;
+        if !next.is_ascii_alphabetic() {
+            self.symbol(next, group_start);
+            return Some(2);
+        }
+
+        let name_len = bytes[1..]
+            .iter()
+            .take_while(|b| b.is_ascii_alphabetic())
+            .count();
+        let (arg, arg_len) = read_arg(&bytes[1 + name_len..]);
+
+        // The tokenizer reads \bin as binary data whatever follows it, so long
+        // as the data fits in what's left of the document
+        if bytes[1..].starts_with(b"bin") {
+            let mut len = 4;
+            let (arg, arg_len) = read_arg(&bytes[len..]);
+            len += arg_len;
+            if arg.is_some() && bytes.get(len) == Some(&b' ') {
+                len += 1;
+            }
+            let data = arg.unwrap_or(0);
+            if data >= 0 && len + data as usize <= bytes.len() {
+                if !self.frame.ignored {
+                    self.skip_fallback();
+                }
+                return Some(len + data as usize);
+            }
+        }
+
+        let mut len = 1 + name_len + arg_len;
+        if bytes.get(len) == Some(&b' ') {
+            len += 1;
+        }
+        self.word(&bytes[1..1 + name_len], narrow_arg(arg), group_start);
+        Some(len)
+    }
+
+    fn text_run(&mut self, run: &[u8]) {
+        if self.frame.ignored {
+            if self.frame.font_table {
+                for byte in run {
+                    self.state.font_table_byte(*byte);
+                }
+            }
+            return;
+        }
+        for byte in run {
+            if !self.skip_fallback() {
+                if let Some(c) = self.decode(*byte) {
+                    self.push(c);
+                }
+            }
+        }
+    }
+
+    fn scan(&mut self, bytes: &[u8]) -> usize {
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let group_start = self.group_start;
+            self.group_start = false;
+            if matches!(bytes[pos], b'{' | b'}' | b'\r' | b'\n') {
+                self.lead = None;
+            }
+            match bytes[pos] {
+                b'{' => {
+                    self.fallback = 0;
+                    self.stack.push(self.frame);
+                    self.group_start = true;
+                    pos += 1;
+                }
+                b'}' => {
+                    self.fallback = 0;
+                    if let Some(frame) = self.stack.pop() {
+                        self.frame = frame;
+                    }
+                    pos += 1;
+                }
+                // Line breaks are ignored, even between a group's `{` and
+                // the destination it starts
+                b'\r' if bytes.get(pos + 1) == Some(&b'\n') => {
+                    self.group_start = group_start;
+                    pos += 2;
+                }
+                b'\r' | b'\n' => {
+                    self.group_start = group_start;
+                    pos += 1;
+                }
+                b'\\' => match self.control(&bytes[pos..], group_start) {
+                    Some(len) => pos += len,
+                    None => break,
+                },
+                _ => {
+                    let len = text_run_len(&bytes[pos..]);
+                    self.text_run(&bytes[pos..pos + len]);
+                    pos += len;
+                }
+            }
+        }
+        pos
+    }
+}
+
+// Length of the run of plain text at the start of `bytes`
+fn text_run_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|b| matches!(b, b'\\' | b'{' | b'}' | b'\r' | b'\n'))
+        .unwrap_or(bytes.len())
+}
+
+/// Append the document text of `bytes` to `text`, without tokenizing
+///
+/// Produces the same text as `extract_text_with(&parse(bytes)?, options)`,
+/// but in a single pass and without allocating per token, for workloads that
+/// only want the text of a large number of documents.  Text is appended to
+/// whatever `text` already contains, so one buffer can be reused across
+/// documents.
+///
+/// Returns the number of bytes scanned.  Like the tokenizer, scanning stops
+/// early at a control sequence that can't be read, such as a malformed `\'`
+/// escape.
+pub fn scan_text(bytes: &[u8], options: &TextOptions, text: &mut String) -> usize {
+    let mut scanner = Scanner {
+        options,
+        text,
+        stack: Vec::new(),
+        frame: Frame {
+            ignored: false,
+            font_table: false,
+            uc: 1,
+            font: None,
+            shape: false,
+            hidden: false,
+        },
+        state: DecoderState::with_charsets(options.charset_decoder.clone()),
+        group_start: false,
+        fallback: 0,
+        lead: None,
+        surrogate: None,
+    };
+    scanner.scan(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codepage::CharsetDecoder;
+    use std::sync::Arc;
+    use text::{extract_text_with, DynamicText, DynamicValues, HiddenText, Whitespace};
+    use tokenizer::parse;
+
+    fn assert_matches_extract(bytes: &[u8]) {
+        for whitespace in &[Whitespace::Normalize, Whitespace::Preserve] {
+            for hidden in &[HiddenText::Include, HiddenText::Skip, HiddenText::Only] {
+                let options = TextOptions {
+                    whitespace: *whitespace,
+                    hidden: *hidden,
+                    ..TextOptions::default()
+                };
+                let mut scanned = String::new();
+                scan_text(bytes, &options, &mut scanned);
+                let extracted = extract_text_with(&parse(bytes).unwrap(), &options);
+                assert_eq!(scanned, extracted);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scan_matches_extract() {
+        assert_matches_extract(
+            br#"{\rtf1\ansi{\fonttbl{\f0 Times;}}{\*\generator Foo;}\f0 Hello {\b World}\par}"#,
+        );
+        assert_matches_extract(
+            br#"{\rtf1\uc2 caf\u233\'e9\'65 x\u-3913 ??\tab \~\_\{\}\\ \binary}"#,
+        );
+        assert_matches_extract(b"{\\rtf1 A\\bin3 \\}{B\r\n{\r\n\\info x}C  \\par  D\\'zz E}");
+        assert_matches_extract(
+            br#"{\rtf1{\fonttbl{\f0 Times;}{\f1\fcharset2{\*\panose 0}Symbol;}}{\f1\'b7 a}\f1 p\plain p}"#,
+        );
+        assert_matches_extract(
+            br#"{\rtf1\ansicpg1251\deff0{\fonttbl{\f0\fcharset204 Arial;}{\f1\fcharset0 Arial;}}\'cf{\f1\'cf}\f2\'cf}"#,
+        );
+        assert_matches_extract(
+            b"{\\rtf1 big\\fs99999999999 arg\\par neg\\li- x\\bin99999999999 y}",
+        );
+        assert_matches_extract(
+            br#"{\rtf1\trowd\cellx1000 A\cell{\itap2 x\nestcell\nestrow}\cell\row}"#,
+        );
+        assert_matches_extract(
+            br#"{\rtf1 A\emdash b\endash\lquote c\rquote\bullet\~d\-e\_f\zwj\chdate}"#,
+        );
+        assert_matches_extract(
+            br#"{\rtf1 Shown {\v hidden\par\u8220?x\v0  shown}\v again\plain  shown}"#,
+        );
+        assert_matches_extract(
+            br#"{\rtf1{\shp{\*\shpinst{\shptxt Box\par}}{\shprslt{\*\do{\dptxbxtext Box}}}}{\*\do{\dptxbxtext Old}}{\header{\shp{\*\shpinst{\shptxt x}}}}}"#,
+        );
+    }
+
+    #[test]
+    fn test_scan_surrogate_pairs() {
+        assert_matches_extract(br#"{\rtf1 \u-10179?\u-8704? a\u-10179?b\u-8704?c}"#);
+        assert_matches_extract(br#"{\rtf1 \u-10179?\par{\v \u-10179?\v0 x}\u-10179?\chpgn\~}"#);
+    }
+
+    #[test]
+    fn test_scan_destinations_after_newlines() {
+        assert_matches_extract(
+            b"{\\rtf1\\ansi {\r\n\\*\\generator x;}{\n\\*\r\n\\foo y}{\r\n\\info z}b}",
+        );
+    }
+
+    #[test]
+    fn test_scan_sample_doc() {
+        assert_matches_extract(include_bytes!("../tests/sample.rtf"));
+    }
+
+    #[test]
+    fn test_scan_with_charset_decoder() {
+        // Reads every byte from 0x80 up as the first of a pair
+        #[derive(Debug)]
+        struct Pairs;
+
+        impl CharsetDecoder for Pairs {
+            fn is_lead_byte(&self, _code_page: u32, byte: u8) -> bool {
+                byte >= 0x80
+            }
+
+            fn decode(&self, _code_page: u32, bytes: &[u8]) -> Option<char> {
+                Some(if bytes.len() == 2 { '#' } else { '-' })
+            }
+        }
+
+        let options = TextOptions {
+            charset_decoder: Some(Arc::new(Pairs)),
+            ..TextOptions::default()
+        };
+        let bytes = b"{\\rtf1 a\\'82\\'a0\\'82b\\'82\r\nc\\'82{\\'a0}\\'82\\par d}";
+        let mut scanned = String::new();
+        scan_text(bytes, &options, &mut scanned);
+        assert_eq!(scanned, extract_text_with(&parse(bytes).unwrap(), &options));
+        assert_eq!(scanned, "-##-\n-");
+    }
+
+    #[test]
+    fn test_scan_dynamic_text() {
+        let bytes = br#"{\rtf1 Page \chpgn  of {\v\chpgn} \chtime}"#;
+        for dynamic in &[
+            DynamicText::Placeholders,
+            DynamicText::Values(DynamicValues::at(7, 0)),
+        ] {
+            for hidden in &[HiddenText::Include, HiddenText::Skip] {
+                let options = TextOptions {
+                    hidden: *hidden,
+                    dynamic: dynamic.clone(),
+                    ..TextOptions::default()
+                };
+                let mut scanned = String::new();
+                scan_text(bytes, &options, &mut scanned);
+                assert_eq!(scanned, extract_text_with(&parse(bytes).unwrap(), &options));
+            }
+        }
+    }
+
+    #[test]
+    fn test_scan_headers_footers() {
+        let bytes = br#"{\rtf1{\header A}{\footerl Page \chpgn}{\headerf{\*\bkmkstart x}B}Body}"#;
+        for headers_footers in &[false, true] {
+            let options = TextOptions {
+                dynamic: DynamicText::Placeholders,
+                headers_footers: *headers_footers,
+                ..TextOptions::default()
+            };
+            let mut scanned = String::new();
+            scan_text(bytes, &options, &mut scanned);
+            assert_eq!(scanned, extract_text_with(&parse(bytes).unwrap(), &options));
+            assert_eq!(scanned.contains("Page {PAGE}"), *headers_footers);
+        }
+    }
+
+    #[test]
+    fn test_scan_appends() {
+        let mut text = String::from("Before ");
+        let scanned = scan_text(b"{\\rtf1 After}", &TextOptions::default(), &mut text);
+        assert_eq!(scanned, 13);
+        assert_eq!(text, "Before After");
+    }
+}
@@ -0,0 +1,265 @@
+// Searching text
+//
+// Finds text in a document as a reader sees it, decoded, and maps what it
+// finds back to the tokens it was read from and the bytes of the document
+// those were read from.  Editing, redacting or annotating a match needs to
+// know where it's stored, which the decoded text alone can't say, and
+// replacing text uses the same mapping to rewrite it where it's stored.
+//
+
+use std::ops::Range;
+
+#[cfg(feature = "regex")]
+use regex;
+use template::replace_pieces;
+use text::{piece_text, pieces, range_pieces, Unit, Whitespace};
+use tokenizer::{parse_spanned, ParseError, SpannedToken, Token};
+
+/// What to look for in the text of a document
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Pattern {
+    /// Byte ranges in the text that `extract_text` extracts from the document
+    Ranges(Vec<Range<usize>>),
+    /// Each occurrence of a string
+    Text(String),
+    /// Each match of a regular expression in the text of the document
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+/// Text found in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// The byte range of the match in the text that `extract_text` extracts
+    pub text_range: Range<usize>,
+    /// The range of the tokens it was read from, including the fallback
+    /// characters of a `\uN` at its end
+    pub token_range: Range<usize>,
+    /// The range of the bytes of the document it was read from, which starts
+    /// and ends part way through a text token when the match does
+    pub byte_range: Range<usize>,
+}
+
+pub(crate) fn find_text(text: &str, needle: &str) -> Vec<Range<usize>> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    text.match_indices(needle)
+        .map(|(start, found)| start..start + found.len())
+        .collect()
+}
+
+/// The ranges of a text that a pattern matches
+pub(crate) fn find_ranges(pattern: &Pattern, text: &str) -> Vec<Range<usize>> {
+    match pattern {
+        Pattern::Ranges(ranges) => ranges.clone(),
+        Pattern::Text(needle) => find_text(text, needle),
+        #[cfg(feature = "regex")]
+        Pattern::Regex(regex) => regex
+            .find_iter(text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect(),
+    }
+}
+
+/// Find text in a token stream, as `find` does
+pub fn find_spanned(tokens: &[SpannedToken], pattern: &Pattern) -> Vec<Match> {
+    let plain: Vec<Token> = tokens.iter().map(|token| token.token.clone()).collect();
+    let pieces = pieces(&plain);
+    let (text, chars) = piece_text(&pieces, Whitespace::Normalize);
+    let mut matches = Vec::new();
+    for text_range in find_ranges(pattern, &text) {
+        let (first, mut last) = match range_pieces(&chars, &text_range) {
+            Some(found) => found,
+            None => continue,
+        };
+        while pieces
+            .get(last + 1)
+            .is_some_and(|piece| piece.unit == Unit::Fallback)
+        {
+            last += 1;
+        }
+        let (first, last) = (&pieces[first], &pieces[last]);
+        let start = &tokens[first.token];
+        let end = &tokens[last.token];
+        let byte_start = match start.token {
+            Token::Text(_) => start.span.start + first.bytes.start,
+            _ => start.span.start,
+        };
+        let byte_end = match end.token {
+            Token::Text(_) => end.span.start + last.bytes.end,
+            _ => end.span.end,
+        };
+        matches.push(Match {
+            text_range,
+            token_range: first.token..last.token + 1,
+            byte_range: byte_start..byte_end,
+        });
+    }
+    matches
+}
+
+/// Find text in a document
+///
+/// The pattern is looked for in the text that `extract_text` extracts, and
+/// each match is mapped back to the tokens and the bytes of the document it
+/// was read from.  Text is matched however the document happens to store it:
+/// split between tokens, escaped, or with formatting changes part way
+/// through.  `Pattern::Ranges` matches the ranges it's given, to map ranges
+/// of the text found some other way.
+///
+/// ```
+/// use rtf_grimoire::search::{find, Pattern};
+///
+/// let doc = b"{\\rtf1 caf\\'e9 {\\b cr\\u232?me}}";
+/// let found = find(doc, &Pattern::Text("\u{e9} cr\u{e8}".to_string())).unwrap();
+/// assert_eq!(found[0].text_range, 3..10);
+/// assert_eq!(&doc[found[0].byte_range.clone()], b"\\'e9 {\\b cr\\u232?");
+/// ```
+pub fn find(doc: &[u8], pattern: &Pattern) -> Result<Vec<Match>, ParseError> {
+    Ok(find_spanned(&parse_spanned(doc)?, pattern))
+}
+
+// The ranges of a text that a pattern matches, and what to replace each
+// with: the replacement, with a regular expression's `$name` references to
+// its groups expanded
+fn replacements(pattern: &Pattern, text: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+    match pattern {
+        #[cfg(feature = "regex")]
+        Pattern::Regex(regex) => regex
+            .captures_iter(text)
+            .filter_map(|captures| {
+                let found = captures.get(0).filter(|found| !found.is_empty())?;
+                let mut value = String::new();
+                captures.expand(replacement, &mut value);
+                Some((found.range(), value))
+            })
+            .collect(),
+        _ => find_ranges(pattern, text)
+            .into_iter()
+            .map(|range| (range, replacement.to_string()))
+            .collect(),
+    }
+}
+
+/// Replace text in a document, keeping its formatting
+///
+/// The pattern is looked for in the text that `extract_text` extracts, as
+/// `find` does, and each match is replaced, with a regular expression's
+/// `$1` or `$name` references to its groups expanded.  The replacement is
+/// written where the match begins, in the formatting the match begins with,
+/// and escaped as the document needs; line breaks in it become `\line`.
+/// The text of the match is removed from the tokens it was read from, and
+/// the groups and formatting between them are left as they were, so the
+/// rest of the document keeps its formatting too.  Where ranges overlap,
+/// the first is replaced.
+///
+/// ```
+/// use rtf_grimoire::search::{replace_text, Pattern};
+/// use rtf_grimoire::{parse, write_rtf, WriteOptions};
+///
+/// let tokens = parse(b"{\\rtf1 Dear {\\b Sir}, hi}").unwrap();
+/// let replaced = replace_text(&tokens, &Pattern::Text("Sir".to_string()), "Madam");
+/// let rtf = write_rtf(&replaced, &WriteOptions::default()).unwrap();
+/// assert_eq!(rtf, b"{\\rtf1 Dear {\\b Madam}, hi}".to_vec());
+/// ```
+pub fn replace_text(tokens: &[Token], pattern: &Pattern, replacement: &str) -> Vec<Token> {
+    let pieces = pieces(tokens);
+    let (text, chars) = piece_text(&pieces, Whitespace::Normalize);
+    let mut found = replacements(pattern, &text, replacement);
+    found.sort_by_key(|(range, _)| range.start);
+    let mut replaced = Vec::new();
+    let mut end = 0;
+    for (range, value) in found {
+        if range.start < end {
+            continue;
+        }
+        end = range.end;
+        if let Some((first, last)) = range_pieces(&chars, &range) {
+            replaced.push((first..last + 1, value));
+        }
+    }
+    replace_pieces(tokens, &pieces, replaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::extract_text;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    const DOC: &[u8] = b"{\\rtf1{\\fonttbl{\\f0 Times;}}\\pard Hello {\\b wor}ld,\\par\\u8220?hello\\u8221? again}";
+
+    #[test]
+    fn test_find() {
+        let found = find(DOC, &Pattern::Text("world".to_string())).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text_range, 6..11);
+        assert_eq!(&DOC[found[0].byte_range.clone()], b"wor}ld");
+
+        let tokens = parse_spanned(DOC).unwrap();
+        let found = find_spanned(&tokens, &Pattern::Text("\u{201c}hello\u{201d}".to_string()));
+        let quoted = &tokens[found[0].token_range.clone()];
+        assert_eq!(
+            quoted.first().unwrap().token,
+            Token::Unicode { codepoint: 8220 }
+        );
+        assert_eq!(quoted.last().unwrap().token, Token::text("? again"));
+        assert_eq!(&DOC[found[0].byte_range.clone()], b"\\u8220?hello\\u8221?");
+
+        let found = find(DOC, &Pattern::Ranges(vec![0..5, 12..13, 100..200])).unwrap();
+        let bytes: Vec<&[u8]> = found
+            .iter()
+            .map(|found| &DOC[found.byte_range.clone()])
+            .collect();
+        assert_eq!(bytes, vec![&b"Hello"[..], &b"\\par"[..]]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_find_regex() {
+        let pattern = Pattern::Regex(regex::Regex::new("(?i)hel+o").unwrap());
+        let found = find(DOC, &pattern).unwrap();
+        let texts: Vec<_> = found.iter().map(|found| found.text_range.clone()).collect();
+        assert_eq!(texts, vec![0..5, 16..21]);
+    }
+
+    fn replaced(rtf: &[u8], pattern: &Pattern, replacement: &str) -> String {
+        let tokens = replace_text(&parse(rtf).unwrap(), pattern, replacement);
+        String::from_utf8(write_rtf(&tokens, &WriteOptions::default()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_replace_text() {
+        let world = Pattern::Text("world".to_string());
+        // The replacement takes the formatting the match begins with
+        assert_eq!(
+            replaced(DOC, &world, "there"),
+            "{\\rtf1{\\fonttbl{\\f0 Times;}}\\pard Hello {\\b there},\\par\\u8220?hello\\u8221? again}"
+        );
+        let quoted = Pattern::Text("\u{201c}hello\u{201d}".to_string());
+        assert_eq!(
+            replaced(DOC, &quoted, "\u{ab}bonjour\u{bb}"),
+            "{\\rtf1{\\fonttbl{\\f0 Times;}}\\pard Hello {\\b wor}ld,\\par\\u171?bonjour\\u187? again}"
+        );
+        let tokens = parse(DOC).unwrap();
+        let both = Pattern::Ranges(vec![0..3, 2..8, 12..13]);
+        assert_eq!(
+            extract_text(&replace_text(&tokens, &both, "-")),
+            "-lo world,-\u{201c}hello\u{201d} again"
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_replace_regex() {
+        let pattern = Pattern::Regex(regex::Regex::new(r"(?i)(h)el+o").unwrap());
+        assert_eq!(
+            extract_text(&replace_text(&parse(DOC).unwrap(), &pattern, "${1}i")),
+            "Hi world,\n\u{201c}hi\u{201d} again"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+// Shapes and drawing objects
+//
+// Word 97 and later write drawings, text boxes and floating pictures as
+// `{\shp{\*\shpinst ...}{\shprslt ...}}` groups.  The instructions give the
+// shape's bounds as control words, and everything else as a list of
+// `{\sp{\sn name}{\sv value}}` properties; a text box's text is in a
+// `{\shptxt ...}` group among them.  The result is the same shape drawn for
+// older readers, usually as a Word 95 `{\*\do ...}` drawing object, whose
+// properties are all `\dp` control words and whose text box text is in a
+// `{\dptxbxtext ...}` group.
+//
+
+use std;
+
+use document::TextPosition;
+use text::{Decoded, Decoder, DecoderState};
+use tokenizer::{narrow_arg, Token};
+
+/// How a shape was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ShapeKind {
+    /// A `{\shp}` shape, or a `{\shpgrp}` group of shapes
+    #[default]
+    Shape,
+    /// A legacy `{\*\do}` drawing object
+    Drawing,
+}
+
+/// A shape or drawing object
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Shape {
+    pub kind: ShapeKind,
+    /// Bounds in twips, from `\shpleft`, `\shptop`, `\shpright` and
+    /// `\shpbottom`, or a drawing object's `\dpx`, `\dpy`, `\dpxsize` and
+    /// `\dpysize`
+    pub left: Option<i32>,
+    pub top: Option<i32>,
+    pub right: Option<i32>,
+    pub bottom: Option<i32>,
+    /// Property names and values in the order they're given: the `\sn` and
+    /// `\sv` of each `\sp`, or a drawing object's `\dp` control words with
+    /// their arguments, like `("dpx", "120")`
+    pub properties: Vec<(String, String)>,
+    /// The text of the shape's text box, if it has one, with paragraph and
+    /// line breaks as `'\n'`
+    pub text: Option<String>,
+    /// Where the shape is anchored in the text
+    pub position: TextPosition,
+}
+
+impl Shape {
+    /// The value of a property, the last one if it's given more than once
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .rev()
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The `shapeType` property, such as 1 for a rectangle, 75 for a picture
+    /// frame or 202 for a text box
+    pub fn shape_type(&self) -> Option<i32> {
+        self.property("shapeType")?.trim().parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Name,
+    Value,
+    Text,
+}
+
+// A destination inside a shape being read for its text
+#[derive(Debug)]
+struct Capture {
+    part: Part,
+    depth: usize,
+    decoder: Decoder,
+    text: String,
+}
+
+#[derive(Debug)]
+struct OpenShape {
+    // Depth of the shape's group
+    depth: usize,
+    shape: Shape,
+    // The \sn of the property whose \sv is next
+    name: String,
+    capture: Option<Capture>,
+}
+
+impl OpenShape {
+    fn word(&mut self, name: &str, arg: Option<i64>) {
+        let arg = narrow_arg(arg);
+        match (self.shape.kind, name) {
+            (ShapeKind::Shape, "shpleft") => self.shape.left = arg,
+            (ShapeKind::Shape, "shptop") => self.shape.top = arg,
+            (ShapeKind::Shape, "shpright") => self.shape.right = arg,
+            (ShapeKind::Shape, "shpbottom") => self.shape.bottom = arg,
+            (ShapeKind::Drawing, _) if name.starts_with("dp") => {
+                let value = arg.map_or(String::new(), |arg| arg.to_string());
+                self.shape.properties.push((name.to_string(), value));
+            }
+            _ => {}
+        }
+    }
+
+    fn end_capture(&mut self, capture: Capture) {
+        let text = capture.text.trim().to_string();
+        match capture.part {
+            Part::Name => self.name = text,
+            Part::Value => {
+                let name = std::mem::take(&mut self.name);
+                self.shape.properties.push((name, text));
+            }
+            Part::Text => self.shape.text = Some(text),
+        }
+    }
+
+    fn finish(mut self) -> Shape {
+        if self.shape.kind == ShapeKind::Drawing {
+            let dp = |name: &str| {
+                self.shape
+                    .property(name)
+                    .and_then(|v| v.parse::<i32>().ok())
+            };
+            let (x, y) = (dp("dpx"), dp("dpy"));
+            let right = x.and_then(|x| Some(x + dp("dpxsize")?));
+            let bottom = y.and_then(|y| Some(y + dp("dpysize")?));
+            self.shape.left = x;
+            self.shape.top = y;
+            self.shape.right = right;
+            self.shape.bottom = bottom;
+        }
+        self.shape
+    }
+}
+
+/// Reads the shapes in a token stream, alongside the document's decoder
+#[derive(Debug, Default)]
+pub(crate) struct ShapeReader {
+    depth: usize,
+    // Set after a StartGroup, and a \* following it, when the next control
+    // word names the group
+    naming: bool,
+    // Shapes being read, outermost first: a group of shapes holds the shapes
+    // in it
+    open: Vec<OpenShape>,
+    // Depth of a shape result being skipped
+    result: Option<usize>,
+}
+
+impl ShapeReader {
+    /// Read a token, and return the shape it finishes, if any
+    ///
+    /// `state` is the document's decoder state, for decoding text with the
+    /// document's fonts.
+    pub(crate) fn token(&mut self, token: &Token, state: &DecoderState) -> Option<Shape> {
+        let named = self.naming;
+        self.naming = *token == Token::StartGroup || (named && *token == Token::ControlSymbol('*'));
+        if *token == Token::StartGroup {
+            self.depth += 1;
+        }
+        let depth = self.depth;
+        if *token == Token::EndGroup {
+            self.depth = self.depth.saturating_sub(1);
+        }
+        if self.result.is_some() {
+            if *token == Token::EndGroup && self.result == Some(depth) {
+                self.result = None;
+            }
+            return None;
+        }
+
+        if let Some(shape) = self.open.last_mut() {
+            if let Some(capture) = shape.capture.as_mut() {
+                match capture.decoder.token(token) {
+                    Decoded::Text(text) => capture.text.push_str(&text),
+                    Decoded::Word("par", _) | Decoded::Word("line", _) => capture.text.push('\n'),
+                    Decoded::Word("tab", _) => capture.text.push('\t'),
+                    _ => {}
+                }
+                if *token == Token::EndGroup && capture.depth == depth {
+                    if let Some(capture) = shape.capture.take() {
+                        shape.end_capture(capture);
+                    }
+                }
+                return None;
+            }
+        }
+
+        match token {
+            Token::EndGroup if self.open.last().is_some_and(|shape| shape.depth == depth) => {
+                self.open.pop().map(OpenShape::finish)
+            }
+            Token::ControlWord { name, .. } if named => {
+                let kind = match name.as_str() {
+                    "shp" | "shpgrp" => ShapeKind::Shape,
+                    "do" => ShapeKind::Drawing,
+                    _ => {
+                        self.destination(name, depth, state);
+                        return None;
+                    }
+                };
+                self.open.push(OpenShape {
+                    depth,
+                    shape: Shape {
+                        kind,
+                        ..Shape::default()
+                    },
+                    name: String::new(),
+                    capture: None,
+                });
+                None
+            }
+            Token::ControlWord { name, arg } => {
+                if let Some(shape) = self.open.last_mut() {
+                    shape.word(name, *arg);
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    // A destination starting inside a shape
+    fn destination(&mut self, name: &str, depth: usize, state: &DecoderState) {
+        let shape = match self.open.last_mut() {
+            Some(shape) => shape,
+            None => return,
+        };
+        let part = match name {
+            "shprslt" => {
+                self.result = Some(depth);
+                return;
+            }
+            "sn" => Part::Name,
+            "sv" => Part::Value,
+            "shptxt" | "dptxbxtext" => Part::Text,
+            _ => return shape.word(name, None),
+        };
+        shape.capture = Some(Capture {
+            part,
+            depth,
+            decoder: Decoder::with_state(state.clone()),
+            text: String::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    fn shapes(rtf: &[u8]) -> Vec<Shape> {
+        let mut reader = ShapeReader::default();
+        let mut decoder = Decoder::new();
+        let mut shapes = Vec::new();
+        for token in &parse(rtf).unwrap() {
+            decoder.token(token);
+            shapes.extend(reader.token(token, decoder.state()));
+        }
+        shapes
+    }
+
+    #[test]
+    fn test_shapes() {
+        let found = shapes(
+            br#"{\rtf1{\shp{\*\shpinst\shpleft100\shptop200\shpright1100\shpbottom700{\sp{\sn shapeType}{\sv 202}}{\sp{\sn fillColor}{\sv 16777215}}{\sp{\sn wzName}{\sv Caf\'e9}}{\shptxt \pard First\par Second\tab x\par}}{\shprslt{\*\do\dobxcolumn\dptxbx{\dptxbxtext First}}}}}"#,
+        );
+        assert_eq!(found.len(), 1);
+        let shape = &found[0];
+        assert_eq!(shape.kind, ShapeKind::Shape);
+        assert_eq!(
+            (shape.left, shape.top, shape.right, shape.bottom),
+            (Some(100), Some(200), Some(1100), Some(700))
+        );
+        assert_eq!(shape.shape_type(), Some(202));
+        assert_eq!(shape.property("fillColor"), Some("16777215"));
+        assert_eq!(shape.property("wzName"), Some("Caf\u{e9}"));
+        assert_eq!(shape.property("lineColor"), None);
+        assert_eq!(shape.text.as_deref(), Some("First\nSecond\tx"));
+    }
+
+    #[test]
+    fn test_drawing_objects() {
+        let found = shapes(
+            br#"{\rtf1{\*\do\dobxpage\dobypage\dprect\dpx1440\dpy720\dpxsize2880\dpysize1440\dpfillpat1}{\*\do\dptxbx{\dptxbxtext\pard Legacy\par}\dpx0\dpy0}}"#,
+        );
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].kind, ShapeKind::Drawing);
+        assert_eq!(
+            (found[0].left, found[0].top, found[0].right, found[0].bottom),
+            (Some(1440), Some(720), Some(4320), Some(2160))
+        );
+        assert_eq!(found[0].property("dprect"), Some(""));
+        assert_eq!(found[0].property("dpfillpat"), Some("1"));
+        assert_eq!(found[0].text, None);
+        assert_eq!(found[1].text.as_deref(), Some("Legacy"));
+        assert_eq!(found[1].right, None);
+    }
+
+    #[test]
+    fn test_shape_groups() {
+        let found = shapes(
+            br#"{\rtf1{\shpgrp{\*\shpinst{\sp{\sn groupLeft}{\sv 0}}{\shp{\*\shpinst{\sp{\sn shapeType}{\sv 1}}}}{\shp{\*\shpinst{\shptxt Inner}}}}}}"#,
+        );
+        let types: Vec<Option<i32>> = found.iter().map(Shape::shape_type).collect();
+        assert_eq!(types, vec![Some(1), None, None]);
+        assert_eq!(found[1].text.as_deref(), Some("Inner"));
+        assert_eq!(found[2].property("groupLeft"), Some("0"));
+    }
+}
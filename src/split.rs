@@ -0,0 +1,180 @@
+// Splitting documents
+//
+// A document cut at its section or page breaks gives fragments that are each
+// a document of their own: the header, with the font, color and style tables
+// the body refers to, is copied into every one.
+//
+
+use merge::{HEADER_DESTINATIONS, MERGED_DESTINATIONS};
+use tokenizer::{group_destination, group_end, parse, ParseError, Token};
+use writer::{write_rtf, WriteOptions};
+
+/// Where `split_tokens` cuts a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Boundary {
+    /// At section breaks, `\sect`
+    Section,
+    /// At page breaks, `\page`, as well as section breaks
+    Page,
+}
+
+impl Boundary {
+    fn is_break(self, token: &Token) -> bool {
+        match self {
+            Boundary::Section => token.is_word("sect"),
+            Boundary::Page => token.is_word("sect") || token.is_word("page"),
+        }
+    }
+}
+
+// The index in `tokens` of the first token of the body of the document whose
+// group starts at `start`
+pub(crate) fn body_start(tokens: &[Token], start: usize, end: usize) -> usize {
+    let mut index = start + 1;
+    while index < end {
+        match &tokens[index] {
+            Token::ControlWord { .. } | Token::Newline => index += 1,
+            Token::StartGroup => match group_destination(tokens, index) {
+                Some((name, _, _))
+                    if MERGED_DESTINATIONS.contains(&name)
+                        || HEADER_DESTINATIONS.contains(&name) =>
+                {
+                    index = group_end(tokens, index)
+                }
+                _ => break,
+            },
+            _ => break,
+        }
+    }
+    index
+}
+
+// Whether a fragment has anything to show
+fn has_content(tokens: &[Token]) -> bool {
+    tokens.iter().any(|token| {
+        !matches!(
+            token,
+            Token::ControlWord { .. } | Token::Newline | Token::EndGroup
+        )
+    })
+}
+
+/// Split a token stream at its top level breaks
+///
+/// Each fragment is the header of the document, everything up to the first
+/// text or group that isn't a table or other header destination, followed by
+/// the body between two breaks.  The breaks themselves are left out, and so is
+/// a last fragment with nothing in it besides control words, like the `\par`
+/// after a closing page break.
+///
+/// Only breaks at the top level of the document are cut at, as cutting inside
+/// a group would leave it unbalanced.  Formatting set in one fragment's body
+/// isn't carried over into the next, so a fragment that relies on paragraph
+/// or section formatting from before the break starts with the header's
+/// instead.
+pub fn split_tokens(tokens: &[Token], boundary: Boundary) -> Vec<Vec<Token>> {
+    let start = match tokens.iter().position(|token| *token == Token::StartGroup) {
+        Some(start) => start,
+        None => return vec![tokens.to_vec()],
+    };
+    let end = group_end(tokens, start);
+    let body = body_start(tokens, start, end);
+    // The closing brace of the document, if it has one
+    let body_end = match tokens[..end].last() {
+        Some(Token::EndGroup) if end > body => end - 1,
+        _ => end,
+    };
+    let header = &tokens[start..body];
+
+    let mut bodies = Vec::new();
+    let mut fragment = body;
+    let mut index = body;
+    while index < body_end {
+        if tokens[index] == Token::StartGroup {
+            index = group_end(tokens, index);
+        } else if boundary.is_break(&tokens[index]) {
+            bodies.push(&tokens[fragment..index]);
+            index += 1;
+            fragment = index;
+        } else {
+            index += 1;
+        }
+    }
+    let last = &tokens[fragment..body_end];
+    if bodies.is_empty() || has_content(last) {
+        bodies.push(last);
+    }
+
+    bodies
+        .into_iter()
+        .map(|body| {
+            let mut fragment = header.to_vec();
+            fragment.extend_from_slice(body);
+            fragment.push(Token::EndGroup);
+            fragment
+        })
+        .collect()
+}
+
+/// Split a document at its section breaks into standalone documents, as
+/// `split_tokens` does
+pub fn split_sections(bytes: &[u8]) -> Result<Vec<Vec<u8>>, ParseError> {
+    split(bytes, Boundary::Section)
+}
+
+/// Split a document at its page and section breaks into standalone
+/// documents, as `split_tokens` does
+pub fn split_pages(bytes: &[u8]) -> Result<Vec<Vec<u8>>, ParseError> {
+    split(bytes, Boundary::Page)
+}
+
+fn split(bytes: &[u8], boundary: Boundary) -> Result<Vec<Vec<u8>>, ParseError> {
+    let tokens = parse(bytes)?;
+    Ok(split_tokens(&tokens, boundary)
+        .iter()
+        .map(|fragment| {
+            write_rtf(fragment, &WriteOptions::default()).expect("tokens read from a document")
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::Document;
+    use text::extract_text;
+    use validate::{validate, Level};
+
+    const REPORT: &[u8] = b"{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Times;}{\\f1 Arial;}}{\\colortbl;\\red255\\green0\\blue0;}\
+        \\paperw12240\\pard\\plain\\f1 One\\par\\page Two{\\cf1\\page not a break}\\par\\sect\\sectd Three\\par\\page\\par}";
+
+    #[test]
+    fn test_split() {
+        let sections = split_sections(REPORT).unwrap();
+        assert_eq!(sections.len(), 2);
+        let pages = split_pages(REPORT).unwrap();
+        assert_eq!(pages.len(), 3);
+
+        let texts: Vec<_> = pages
+            .iter()
+            .map(|page| {
+                let tokens = parse(page).unwrap();
+                assert_eq!(validate(&tokens, Level::Strict), Vec::new());
+                let doc = Document::from_tokens(&tokens);
+                assert_eq!(doc.fonts.len(), 2);
+                assert_eq!(doc.colors.len(), 2);
+                extract_text(&tokens)
+            })
+            .collect();
+        assert_eq!(texts, vec!["One\n", "Two\nnot a break\n", "Three\n"]);
+        assert_eq!(
+            String::from_utf8_lossy(&pages[2]),
+            "{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Times;}{\\f1 Arial;}}{\\colortbl;\\red255\\green0\\blue0;}\
+             \\paperw12240\\pard\\plain\\f1\\sectd Three\\par}"
+        );
+
+        let single = b"{\\rtf1 Just one}";
+        assert_eq!(split_pages(single).unwrap(), vec![single.to_vec()]);
+    }
+}
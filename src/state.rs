@@ -0,0 +1,233 @@
+// Formatting state tracking
+//
+// RTF formatting is group scoped: a group starts with the formatting of the
+// text around it, and when it ends, whatever the group changed is undone.
+// Control words like `\b` and `\fsN` change one property, `\plain` resets
+// them all, and control words inside destinations such as the font table,
+// where `\fN` starts a font entry, don't change the formatting at all.
+//
+// Paragraph formatting is group scoped in the same way, and `\pard` resets
+// it.  Section formatting isn't: it lasts until `\sectd` resets it to the
+// page setup given in the document formatting.
+//
+
+use document::{CharFormat, ParaFormat, SectionFormat};
+use text::{Decoded, Decoder};
+use tokenizer::Token;
+
+/// The formatting in effect at a point in a document
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FormatState {
+    pub char_format: CharFormat,
+    pub para_format: ParaFormat,
+}
+
+/// Tracks the formatting in effect as a document is read, one token at a
+/// time
+///
+/// Feed every token of the document to `token`, in order, and read the
+/// formatting of the text that follows from `state` and `section_format`.
+#[derive(Debug, Default)]
+pub struct StateTracker {
+    decoder: Decoder,
+    stack: Vec<FormatState>,
+    state: FormatState,
+    // The document's page setup, and the formatting of the current section
+    page: SectionFormat,
+    section: SectionFormat,
+}
+
+impl StateTracker {
+    pub fn new() -> Self {
+        StateTracker::default()
+    }
+
+    /// Read a token, returning whether the tracker used it: a group boundary,
+    /// or a formatting control word in the document text
+    pub fn token(&mut self, token: &Token) -> bool {
+        self.read(token).is_none()
+    }
+
+    /// Read a token, returning what it decodes to unless the tracker used it
+    pub(crate) fn read<'t>(&mut self, token: &'t Token) -> Option<Decoded<'t>> {
+        match self.decoder.token(token) {
+            Decoded::GroupStart => self.stack.push(self.state.clone()),
+            Decoded::GroupEnd => self.state = self.stack.pop().unwrap_or_default(),
+            Decoded::Word(name, arg) if self.word(name, arg) => {}
+            decoded => return Some(decoded),
+        }
+        None
+    }
+
+    // Apply a formatting control word, returning false if it isn't one
+    fn word(&mut self, name: &str, arg: Option<i32>) -> bool {
+        match name {
+            "pard" => self.state.para_format = ParaFormat::default(),
+            "sectd" => self.section = self.page.clone(),
+            _ if self.page.apply_document(name, arg) => {
+                self.section.apply_document(name, arg);
+            }
+            _ => {
+                return self.state.char_format.apply(name, arg)
+                    || self.state.para_format.apply(name, arg)
+                    || self.section.apply(name, arg)
+            }
+        }
+        true
+    }
+
+    /// The formatting in effect
+    pub fn state(&self) -> &FormatState {
+        &self.state
+    }
+
+    /// The character formatting in effect
+    pub fn char_format(&self) -> &CharFormat {
+        &self.state.char_format
+    }
+
+    /// The paragraph formatting in effect
+    pub fn para_format(&self) -> &ParaFormat {
+        &self.state.para_format
+    }
+
+    /// The formatting of the current section
+    pub fn section_format(&self) -> &SectionFormat {
+        &self.section
+    }
+
+    /// Number of groups currently open
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The decoder the tracker reads tokens with
+    pub(crate) fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Whether the last token was part of the document text, rather than
+    /// inside a destination like the font table
+    pub fn in_text(&self) -> bool {
+        !self.decoder.ignored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::Alignment;
+    use tokenizer::parse;
+
+    // The character formatting after each text token
+    fn formats(rtf: &[u8]) -> Vec<CharFormat> {
+        let mut tracker = StateTracker::new();
+        let mut formats = Vec::new();
+        for token in &parse(rtf).unwrap() {
+            tracker.token(token);
+            if let Token::Text(_) = token {
+                if tracker.in_text() {
+                    formats.push(tracker.char_format().clone());
+                }
+            }
+        }
+        formats
+    }
+
+    #[test]
+    fn test_state_tracker() {
+        let formats = formats(
+            br#"{\rtf1{\fonttbl{\f1\fswiss Arial;}}\f1\fs24 a{\b\i b{\plain c}d\b0 e}\ul f\ul0 g}"#,
+        );
+        let base = CharFormat {
+            font: Some(1),
+            size: Some(24),
+            ..CharFormat::default()
+        };
+        let bold_italic = CharFormat {
+            bold: true,
+            italic: true,
+            ..base.clone()
+        };
+        assert_eq!(
+            formats,
+            vec![
+                base.clone(),
+                bold_italic.clone(),
+                CharFormat::default(),
+                bold_italic.clone(),
+                CharFormat {
+                    bold: false,
+                    ..bold_italic
+                },
+                CharFormat {
+                    underline: true,
+                    ..base.clone()
+                },
+                base,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_para_and_section_state() {
+        let tokens = parse(
+            br#"{\rtf1\paperw11906\margl1000 \sectd\lndscpsxn\pard\qc\li720 a{\qr b}c\par\pard d\sect\sectd e}"#,
+        )
+        .unwrap();
+        let mut tracker = StateTracker::new();
+        let mut states = Vec::new();
+        for token in &tokens {
+            tracker.token(token);
+            if let Token::Text(_) = token {
+                states.push((
+                    tracker.para_format().clone(),
+                    tracker.section_format().clone(),
+                ));
+            }
+        }
+        let page = SectionFormat {
+            page_width: 11906,
+            margin_left: 1000,
+            ..SectionFormat::default()
+        };
+        let landscape = SectionFormat {
+            landscape: true,
+            ..page.clone()
+        };
+        let centered = ParaFormat {
+            alignment: Alignment::Center,
+            left_indent: 720,
+            ..ParaFormat::default()
+        };
+        assert_eq!(
+            states,
+            vec![
+                (centered.clone(), landscape.clone()),
+                (
+                    ParaFormat {
+                        alignment: Alignment::Right,
+                        ..centered.clone()
+                    },
+                    landscape.clone()
+                ),
+                (centered, landscape.clone()),
+                (ParaFormat::default(), landscape),
+                (ParaFormat::default(), page),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_tracker_tokens() {
+        let mut tracker = StateTracker::new();
+        let tokens = parse(br#"{\rtf1{\*\generator x\b;}\b x}"#).unwrap();
+        let used: Vec<bool> = tokens.iter().map(|token| tracker.token(token)).collect();
+        assert_eq!(
+            used,
+            vec![true, false, true, false, false, false, false, false, true, true, false, true]
+        );
+        assert_eq!(tracker.depth(), 0);
+        assert!(!tracker.char_format().bold);
+    }
+}
@@ -0,0 +1,478 @@
+// Incremental RTF tokenizer
+//
+// Tokenizes a document as it arrives in chunks, rather than requiring the
+// whole document be in memory up front.
+//
+
+use std;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::io::Read;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "async")]
+use futures_io::AsyncRead;
+
+use tokenizer::{is_partial, next_token, parse, ParseError, Token};
+
+use nom::types::CompleteByteSlice as Input;
+
+const READ_CHUNK_SIZE: usize = 8192;
+
+// The longest control sequence the tokenizer can't read yet that more data
+// could still make readable: a `\'` and one hex digit, which another digit
+// would complete
+const UNREADABLE_LEN: usize = 3;
+
+/// Tokenizer that accepts a document in arbitrarily sized chunks
+///
+/// Tokens are only returned once they're known to be complete - a text run or
+/// control word that reaches the end of the data seen so far is held back until
+/// more data arrives, or until `finish` is called.
+#[derive(Debug, Default)]
+pub struct StreamingTokenizer {
+    buffer: Vec<u8>,
+    // Bytes received so far, including those in the buffer
+    received: u64,
+}
+
+// Marks the serialized form of a `TokenizerState`, and its version
+const STATE_MAGIC: &[u8] = b"RTFGTS1";
+
+/// A checkpoint of a `StreamingTokenizer`, to carry on tokenizing from later
+///
+/// All a tokenizer holds between chunks is the bytes of a token that hasn't
+/// been completed yet, so a checkpoint is small, and can be kept with the
+/// tokens returned before it was taken to pick up where they left off, even
+/// in another process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenizerState {
+    pending: Vec<u8>,
+    received: u64,
+}
+
+impl TokenizerState {
+    /// The number of bytes of the document received before the checkpoint;
+    /// tokenizing resumes with the bytes after them
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// The checkpoint as bytes, to persist
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.received.to_le_bytes());
+        bytes.extend_from_slice(&self.pending);
+        bytes
+    }
+
+    /// Read a checkpoint back from the bytes `to_bytes` gave, or None if
+    /// they aren't one
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let rest = bytes.strip_prefix(STATE_MAGIC)?;
+        if rest.len() < 8 {
+            return None;
+        }
+        let (received, pending) = rest.split_at(8);
+        let mut count = [0; 8];
+        count.copy_from_slice(received);
+        let received = u64::from_le_bytes(count);
+        if (pending.len() as u64) > received {
+            return None;
+        }
+        Some(TokenizerState {
+            pending: pending.to_vec(),
+            received,
+        })
+    }
+}
+
+impl StreamingTokenizer {
+    pub fn new() -> Self {
+        StreamingTokenizer::default()
+    }
+
+    /// Append a chunk of the document, returning every token completed by it
+    ///
+    /// An error is one `parse` would have failed the document with; the
+    /// tokenizer can't carry on past it.  Its excerpt is of the bytes not yet
+    /// returned as tokens, not the whole document.
+    ///
+    /// `parse` stops at a control sequence it can't read, so once one is
+    /// reached, the chunks after it are dropped rather than kept.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<Token>, ParseError> {
+        self.received += bytes.len() as u64;
+        if self.stuck() {
+            return Ok(Vec::new());
+        }
+        self.buffer.extend_from_slice(bytes);
+
+        let mut tokens = Vec::new();
+        let consumed = {
+            let mut input = Input(&self.buffer);
+            // Nothing read means the parser needs to see more data to make
+            // progress
+            while let Some((rest, token, _)) = next_token(&self.buffer, input)? {
+                // A token that runs up to the end of the buffer might continue
+                // in the next chunk
+                if rest.is_empty() || is_partial(&token, &rest) {
+                    break;
+                }
+                tokens.push(token);
+                input = rest;
+            }
+            self.buffer.len() - input.len()
+        };
+        self.buffer.drain(..consumed);
+        if self.stuck() {
+            self.buffer.truncate(UNREADABLE_LEN + 1);
+        }
+        Ok(tokens)
+    }
+
+    // Whether the buffer starts with a control sequence that can't be read
+    // however the document goes on
+    fn stuck(&self) -> bool {
+        self.buffer.len() > UNREADABLE_LEN
+            && matches!(next_token(&self.buffer, Input(&self.buffer)), Ok(None))
+    }
+
+    /// Signal the end of the document, returning any remaining tokens
+    pub fn finish(self) -> Result<Vec<Token>, ParseError> {
+        parse(&self.buffer)
+    }
+
+    /// The number of bytes received and kept, but not yet returned as tokens
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Checkpoint the tokenizer, to resume from with `resume`
+    pub fn save_state(&self) -> TokenizerState {
+        TokenizerState {
+            pending: self.buffer.clone(),
+            received: self.received,
+        }
+    }
+
+    /// Carry on tokenizing from a checkpoint, with the next chunk of the
+    /// document after the bytes the checkpoint had received, returning the
+    /// tokenizer and every token completed by the chunk
+    ///
+    /// ```
+    /// use rtf_grimoire::stream::{StreamingTokenizer, TokenizerState};
+    /// use rtf_grimoire::Token;
+    ///
+    /// let mut tokenizer = StreamingTokenizer::new();
+    /// tokenizer.push(b"{\\rtf1 Hel").unwrap();
+    /// let saved = tokenizer.save_state().to_bytes();
+    ///
+    /// // Later, perhaps in another process
+    /// let state = TokenizerState::from_bytes(&saved).unwrap();
+    /// assert_eq!(state.received(), 10);
+    /// let (tokenizer, tokens) = StreamingTokenizer::resume(state, b"lo}").unwrap();
+    /// assert_eq!(tokens, vec![Token::text("Hello")]);
+    /// assert_eq!(tokenizer.finish().unwrap(), vec![Token::EndGroup]);
+    /// ```
+    pub fn resume(state: TokenizerState, bytes: &[u8]) -> Result<(Self, Vec<Token>), ParseError> {
+        let mut tokenizer = StreamingTokenizer {
+            buffer: state.pending,
+            received: state.received,
+        };
+        let tokens = tokenizer.push(bytes)?;
+        Ok((tokenizer, tokens))
+    }
+}
+
+/// An iterator over the tokens of a document read from an `io::Read`
+pub struct TokenReader<R: Read> {
+    reader: Option<R>,
+    tokenizer: StreamingTokenizer,
+    tokens: std::collections::VecDeque<Token>,
+}
+
+impl<R: Read> TokenReader<R> {
+    pub fn new(reader: R) -> Self {
+        TokenReader {
+            reader: Some(reader),
+            tokenizer: StreamingTokenizer::new(),
+            tokens: std::collections::VecDeque::new(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for TokenReader<R> {
+    type Item = std::io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        while self.tokens.is_empty() {
+            let count = match self.reader.as_mut()?.read(&mut chunk) {
+                Ok(count) => count,
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            let tokens = if count == 0 {
+                self.reader = None;
+                std::mem::take(&mut self.tokenizer).finish()
+            } else {
+                self.tokenizer.push(&chunk[..count])
+            };
+            match tokens {
+                Ok(tokens) => self.tokens.extend(tokens),
+                Err(error) => {
+                    self.reader = None;
+                    return Some(Err(invalid_data(error)));
+                }
+            }
+        }
+        self.tokens.pop_front().map(Ok)
+    }
+}
+
+// A tokenizing error, as the I/O error readers report
+fn invalid_data(error: ParseError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+/// Tokenize a document from an `io::Read` without buffering the whole input
+///
+/// A document that doesn't tokenize is an `InvalidData` error, with the
+/// `ParseError` inside it.
+pub fn parse_reader<R: Read>(reader: R) -> std::io::Result<Vec<Token>> {
+    TokenReader::new(reader).collect()
+}
+
+/// Tokenize a document from an `AsyncRead` without buffering the whole
+/// input, the asynchronous `parse_reader`
+///
+/// Readers for tokio and other runtimes that don't implement
+/// `futures_io::AsyncRead` themselves have adapters that do.
+#[cfg(feature = "async")]
+pub fn parse_async<R: AsyncRead + Unpin>(reader: R) -> ParseAsync<R> {
+    ParseAsync {
+        reader,
+        tokenizer: StreamingTokenizer::new(),
+        tokens: Vec::new(),
+        chunk: vec![0; READ_CHUNK_SIZE],
+    }
+}
+
+/// The future `parse_async` returns
+#[cfg(feature = "async")]
+#[must_use = "futures do nothing unless polled"]
+pub struct ParseAsync<R> {
+    reader: R,
+    tokenizer: StreamingTokenizer,
+    tokens: Vec<Token>,
+    chunk: Vec<u8>,
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead + Unpin> Future for ParseAsync<R> {
+    type Output = std::io::Result<Vec<Token>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            let count = match Pin::new(&mut this.reader).poll_read(cx, &mut this.chunk) {
+                Poll::Ready(Ok(count)) => count,
+                Poll::Ready(Err(ref e)) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => return Poll::Pending,
+            };
+            if count == 0 {
+                let tokenizer = std::mem::take(&mut this.tokenizer);
+                let mut tokens = std::mem::take(&mut this.tokens);
+                return Poll::Ready(match tokenizer.finish() {
+                    Ok(rest) => {
+                        tokens.extend(rest);
+                        Ok(tokens)
+                    }
+                    Err(error) => Err(invalid_data(error)),
+                });
+            }
+            match this.tokenizer.push(&this.chunk[..count]) {
+                Ok(tokens) => this.tokens.extend(tokens),
+                Err(error) => return Poll::Ready(Err(invalid_data(error))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    fn parse_chunked(bytes: &[u8], chunk_size: usize) -> Vec<Token> {
+        let mut tokenizer = StreamingTokenizer::new();
+        let mut tokens = Vec::new();
+        for chunk in bytes.chunks(chunk_size) {
+            tokens.extend(tokenizer.push(chunk).unwrap());
+        }
+        tokens.extend(tokenizer.finish().unwrap());
+        tokens
+    }
+
+    #[test]
+    fn test_streaming_matches_parse() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let expected = parse(test_bytes).unwrap();
+        for chunk_size in &[1, 2, 7, 100, 4096] {
+            assert_eq!(parse_chunked(test_bytes, *chunk_size), expected);
+        }
+    }
+
+    #[test]
+    fn test_streaming_split_tokens() {
+        let bytes = b"{\\fs24 Hello\r\n\\bin5 AB{}C\\'e9\\fi-360\\li-123456789012 x}";
+        let expected = parse(bytes).unwrap();
+        assert_eq!(parse_chunked(bytes, 1), expected);
+        assert_eq!(parse_chunked(bytes, 3), expected);
+    }
+
+    #[test]
+    fn test_streaming_holds_back_incomplete() {
+        let mut tokenizer = StreamingTokenizer::new();
+        assert_eq!(tokenizer.push(b"{\\fs2").unwrap(), vec![Token::StartGroup]);
+        assert_eq!(tokenizer.pending(), 4);
+        assert_eq!(
+            tokenizer.push(b"4 Hi").unwrap(),
+            vec![Token::ControlWord {
+                name: "fs".into(),
+                arg: Some(24),
+            }]
+        );
+        assert_eq!(
+            tokenizer.finish().unwrap(),
+            vec![Token::Text(b"Hi".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_streaming_stops_at_unreadable() {
+        let bytes = b"{\\fs24 Hi\\'zz there\\par}";
+        let expected = parse(bytes).unwrap();
+        for chunk_size in &[1, 2, 5, 100] {
+            assert_eq!(parse_chunked(bytes, *chunk_size), expected);
+        }
+
+        let mut tokenizer = StreamingTokenizer::new();
+        tokenizer.push(b"{Hi\\'zz").unwrap();
+        for _ in 0..1000 {
+            assert_eq!(tokenizer.push(b"more text\\par ").unwrap(), vec![]);
+        }
+        assert_eq!(tokenizer.pending(), 4);
+        assert_eq!(tokenizer.save_state().received(), 7 + 1000 * 14);
+        assert_eq!(tokenizer.finish().unwrap(), vec![]);
+
+        // A hex escape cut short isn't unreadable yet
+        let mut tokenizer = StreamingTokenizer::new();
+        assert_eq!(tokenizer.push(b"a\\'e").unwrap(), vec![Token::text("a")]);
+        assert_eq!(tokenizer.push(b"9b").unwrap(), parse(b"\\'e9").unwrap());
+        assert_eq!(tokenizer.finish().unwrap(), vec![Token::text("b")]);
+    }
+
+    #[test]
+    fn test_streaming_resume() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let expected = parse(test_bytes).unwrap();
+        for chunk_size in &[1, 7, 100] {
+            let mut chunks = test_bytes.chunks(*chunk_size);
+            let mut tokenizer = StreamingTokenizer::new();
+            let mut tokens = Vec::new();
+            // Checkpointing and resuming between every chunk
+            while let Some(chunk) = chunks.next() {
+                tokens.extend(tokenizer.push(chunk).unwrap());
+                let state = tokenizer.save_state().to_bytes();
+                let state = TokenizerState::from_bytes(&state).unwrap();
+                let received = state.received() as usize;
+                let next = match chunks.next() {
+                    Some(next) => next,
+                    None => break,
+                };
+                assert_eq!(&test_bytes[received..received + next.len()], next);
+                let (resumed, more) = StreamingTokenizer::resume(state, next).unwrap();
+                tokens.extend(more);
+                tokenizer = resumed;
+            }
+            tokens.extend(tokenizer.finish().unwrap());
+            assert_eq!(tokens, expected);
+        }
+
+        assert_eq!(TokenizerState::from_bytes(b"not a checkpoint"), None);
+        assert_eq!(
+            TokenizerState::from_bytes(b"RTFGTS1\x01\0\0\0\0\0\0\0ab"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse_reader(&test_bytes[..]).unwrap();
+        assert_eq!(tokens, parse(test_bytes).unwrap());
+    }
+
+    // Gives a few bytes at a time, and isn't ready every other time it's
+    // read from
+    #[cfg(feature = "async")]
+    struct SlowReader<'a> {
+        bytes: &'a [u8],
+        ready: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl<'a> AsyncRead for SlowReader<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = std::cmp::min(std::cmp::min(buf.len(), 7), self.bytes.len());
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn block_on<F: Future>(future: F) -> F::Output {
+        use std::task::{RawWaker, RawWakerVTable, Waker};
+
+        fn raw() -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| raw(), |_| {}, |_| {}, |_| {});
+
+        let waker = unsafe { Waker::from_raw(raw()) };
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_parse_async() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let reader = SlowReader {
+            bytes: test_bytes,
+            ready: false,
+        };
+        let tokens = block_on(parse_async(reader)).unwrap();
+        assert_eq!(tokens, parse(test_bytes).unwrap());
+    }
+}
@@ -0,0 +1,286 @@
+// Subsetting
+//
+// Cuts a stretch of a document's text out into a document of its own that
+// looks the way the stretch did: it gets the header and tables of the
+// document, and the groups and formatting in effect where the stretch
+// begins.
+//
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use document::{CharFormat, ParaFormat, SectionFormat};
+use split::body_start;
+use text::{piece_text, pieces, range_pieces, Decoded, Decoder, Unit, Whitespace, TEXT_WORDS};
+use tokenizer::{group_end, parse, ParseError, Token};
+use writer::{write_rtf, WriteOptions};
+
+// What a control word formats, for leaving out the ones that later ones
+// override
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Char,
+    Para,
+    Section,
+}
+
+fn kind(name: &str, arg: Option<i32>) -> Option<Kind> {
+    if CharFormat::default().apply(name, arg) {
+        Some(Kind::Char)
+    } else if name == "pard" || ParaFormat::default().apply(name, arg) {
+        Some(Kind::Para)
+    } else if name == "sectd" || SectionFormat::default().apply(name, arg) {
+        Some(Kind::Section)
+    } else {
+        None
+    }
+}
+
+// Whether a control word stands for text, like \par or \emdash
+fn is_text_word(token: &Token) -> bool {
+    match Decoder::new().token(token) {
+        Decoded::Text(_) => true,
+        Decoded::Word(name, _) => TEXT_WORDS.contains(&name),
+        _ => false,
+    }
+}
+
+// The control words of a group that set its formatting, less those that
+// later ones override: `\b` before `\b0`, or `\qc` before `\pard`
+fn formatting(words: &[&Token]) -> Vec<Token> {
+    let mut reset = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    for token in words.iter().rev() {
+        if let Token::ControlWord { name, arg } = token {
+            if let Some(kind) = kind(name, arg.map(|arg| arg as i32)) {
+                if reset.contains(&kind) || !seen.insert(name.as_str()) {
+                    continue;
+                }
+                if ["plain", "pard", "sectd"].contains(&name.as_str()) {
+                    reset.insert(kind);
+                }
+            }
+        }
+        kept.push((*token).clone());
+    }
+    kept.reverse();
+    kept
+}
+
+// The groups open at `start`, and the formatting in effect in each, as
+// tokens that reopen them
+fn context(tokens: &[Token], body: usize, start: usize) -> (Vec<Token>, usize) {
+    let mut levels: Vec<(Option<usize>, Vec<&Token>)> = vec![(None, Vec::new())];
+    let mut index = body;
+    while index < start {
+        let token = &tokens[index];
+        match token {
+            Token::StartGroup => {
+                let end = group_end(tokens, index);
+                if end <= start {
+                    index = end;
+                    continue;
+                }
+                levels.push((Some(index), Vec::new()));
+            }
+            Token::ControlWord { .. } if !is_text_word(token) => {
+                if let Some((_, words)) = levels.last_mut() {
+                    words.push(token);
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    let mut out = Vec::new();
+    for (group, words) in &levels {
+        if let Some(group) = group {
+            out.push(Token::StartGroup);
+            if tokens.get(group + 1) == Some(&Token::ControlSymbol('*')) {
+                out.push(Token::ControlSymbol('*'));
+            }
+        }
+        out.extend(formatting(words));
+    }
+    (out, levels.len() - 1)
+}
+
+// The \trowd starting the table row that `index` is inside, if it's in one
+fn in_row(tokens: &[Token], index: usize) -> Option<usize> {
+    tokens[..index]
+        .iter()
+        .rposition(|token| token.is_word("trowd") || token.is_word("row"))
+        .filter(|row| tokens[*row].is_word("trowd"))
+}
+
+/// Cut the text in `range` out of a document into a document of its own
+///
+/// `range` is a byte range in the text that `extract_text` extracts from
+/// `tokens`.  The result has the header of the document, with its font,
+/// color and style tables, and reopens the groups that the text begins
+/// inside with the formatting they had, so it looks as it did in the
+/// document.  Text that's part of a table row brings the whole row with it,
+/// as cells can't stand on their own.
+///
+/// Formatting is carried over as the control words that set it, less those
+/// overridden before the text begins, so styles and formatting this crate
+/// doesn't model come along too.
+///
+/// ```
+/// use rtf_grimoire::subset::subset;
+/// use rtf_grimoire::{extract_text, parse};
+///
+/// let tokens = parse(b"{\\rtf1 Some {\\b bold text} here}").unwrap();
+/// let quote = subset(&tokens, 5..9);
+/// assert_eq!(extract_text(&quote), "bold");
+/// ```
+pub fn subset(tokens: &[Token], range: Range<usize>) -> Vec<Token> {
+    let doc = tokens
+        .iter()
+        .position(|token| *token == Token::StartGroup)
+        .unwrap_or(0);
+    let doc_end = group_end(tokens, doc);
+    let body = body_start(tokens, doc, doc_end);
+    let mut out = tokens[doc..body].to_vec();
+
+    let pieces = pieces(tokens);
+    let (_, chars) = piece_text(&pieces, Whitespace::Normalize);
+    let (first, mut last) = match range_pieces(&chars, &range) {
+        Some(pieces) => pieces,
+        None => {
+            out.push(Token::EndGroup);
+            return out;
+        }
+    };
+    // The fallback characters of a \uN at the end go with it
+    while pieces
+        .get(last + 1)
+        .is_some_and(|piece| piece.unit == Unit::Fallback)
+    {
+        last += 1;
+    }
+
+    let (mut start, mut start_byte) = (pieces[first].token, pieces[first].bytes.start);
+    let (mut end, mut end_byte) = (pieces[last].token, Some(pieces[last].bytes.end));
+    if let Some(row) = in_row(tokens, start) {
+        start = row;
+        start_byte = 0;
+    }
+    if in_row(tokens, end).is_some() {
+        if let Some(row) = tokens[end..].iter().position(|token| token.is_word("row")) {
+            end += row;
+            end_byte = None;
+        }
+    }
+
+    let (context, mut depth) = context(tokens, body, start);
+    out.extend(context);
+    for (index, token) in tokens.iter().enumerate().take(end + 1).skip(start) {
+        let token = match token {
+            Token::Text(data) => {
+                let from = if index == start { start_byte } else { 0 };
+                let to = match end_byte {
+                    Some(to) if index == end => to,
+                    _ => data.len(),
+                };
+                if from >= to {
+                    continue;
+                }
+                Token::Text(data[from..to].to_vec())
+            }
+            Token::StartGroup => {
+                depth += 1;
+                Token::StartGroup
+            }
+            Token::EndGroup if depth == 0 => continue,
+            Token::EndGroup => {
+                depth -= 1;
+                Token::EndGroup
+            }
+            _ => token.clone(),
+        };
+        out.push(token);
+    }
+    out.extend(std::iter::repeat(Token::EndGroup).take(depth + 1));
+    out
+}
+
+/// Cut the text in `range` out of a document into a document of its own, as
+/// `subset` does
+pub fn subset_rtf(bytes: &[u8], range: Range<usize>) -> Result<Vec<u8>, ParseError> {
+    let tokens = parse(bytes)?;
+    Ok(write_rtf(&subset(&tokens, range), &WriteOptions::default())
+        .expect("tokens read from a document"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::{Block, Document};
+    use text::{extract_runs, extract_text};
+    use validate::{validate, Level};
+
+    const DOC: &[u8] = b"{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Times;}{\\f1 Arial;}}{\\colortbl;\\red255\\green0\\blue0;}\
+        \\pard\\qc\\f1\\fs20 Title\\par\\pard\\b\\fs24 Plain {\\cf1 red {\\i both} red} \\b0 na\\u239?ve\\par\
+        \\trowd\\cellx2000\\cellx4000\\pard\\intbl One\\cell Two\\cell\\row\\pard After\\par}";
+
+    fn text_of(tokens: &[Token], pattern: &str) -> Range<usize> {
+        let text = extract_text(tokens);
+        let start = text.find(pattern).unwrap();
+        start..start + pattern.len()
+    }
+
+    #[test]
+    fn test_subset() {
+        let tokens = parse(DOC).unwrap();
+        let quote = subset(&tokens, text_of(&tokens, "d both r"));
+        assert_eq!(validate(&quote, Level::Strict), Vec::new());
+        assert_eq!(extract_text(&quote), "d both r");
+        let runs = extract_runs(&quote);
+        let formats: Vec<_> = runs
+            .iter()
+            .map(|run| (run.text.as_str(), run.bold, run.italic, run.color, run.size))
+            .collect();
+        assert_eq!(
+            formats,
+            vec![
+                ("d ", true, false, Some(1), Some(24)),
+                ("both", true, true, Some(1), Some(24)),
+                (" r", true, false, Some(1), Some(24)),
+            ]
+        );
+        // The paragraph's formatting, without what \pard reset
+        let rtf = String::from_utf8(write_rtf(&quote, &WriteOptions::default()).unwrap()).unwrap();
+        assert!(
+            rtf.ends_with("\\pard\\b\\fs24{\\cf1 d {\\i both} r}}"),
+            "{}",
+            rtf
+        );
+
+        let quote = subset(&tokens, text_of(&tokens, "na\u{ef}v"));
+        assert_eq!(extract_text(&quote), "na\u{ef}v");
+        assert!(!extract_runs(&quote)[0].bold);
+    }
+
+    #[test]
+    fn test_subset_table() {
+        let tokens = parse(DOC).unwrap();
+        let quote = subset(&tokens, text_of(&tokens, "Two"));
+        assert_eq!(validate(&quote, Level::Strict), Vec::new());
+        let doc = Document::from_tokens(&quote);
+        match &doc.blocks[..] {
+            [Block::Table(table)] => assert_eq!(table.rows[0].cells.len(), 2),
+            blocks => panic!("{:?}", blocks),
+        }
+        assert_eq!(doc.fonts.len(), 2);
+
+        assert_eq!(
+            subset_rtf(DOC, 1000..1001).unwrap(),
+            b"{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Times;}{\\f1 Arial;}}{\\colortbl;\\red255\\green0\\blue0;}\
+              \\pard\\qc\\f1\\fs20}"
+                .to_vec()
+        );
+    }
+}
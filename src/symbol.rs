@@ -0,0 +1,155 @@
+// Symbol font character mapping
+//
+// Text in symbol fonts (`\fcharset2`) isn't in any code page: each byte
+// selects a glyph of the font itself.  The Symbol font's glyphs all have
+// Unicode equivalents, and so do the commonly used bullet and check mark
+// glyphs of Wingdings.  Anything else is mapped into the U+F000-U+F0FF
+// private use range, the same way Windows maps symbol fonts, so that text
+// displayed in the original font still shows the right glyph.
+//
+
+use std;
+
+/// A font whose characters need mapping to Unicode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolFont {
+    Symbol,
+    Wingdings,
+    /// Any other font using the symbol character set
+    Other,
+}
+
+// The Symbol font from 0x20 to 0xFF.  Positions with no glyph are '\0', and
+// are mapped to the private use range like other symbol fonts.
+const SYMBOL: [char; 224] = [
+    // 0x20
+    ' ', '!', '\u{2200}', '#', '\u{2203}', '%', '&', '\u{220b}', '(', ')', '\u{2217}', '+', ',',
+    '\u{2212}', '.', '/', // 0x30
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?',
+    // 0x40
+    '\u{2245}', '\u{391}', '\u{392}', '\u{3a7}', '\u{394}', '\u{395}', '\u{3a6}', '\u{393}',
+    '\u{397}', '\u{399}', '\u{3d1}', '\u{39a}', '\u{39b}', '\u{39c}', '\u{39d}', '\u{39f}',
+    // 0x50
+    '\u{3a0}', '\u{398}', '\u{3a1}', '\u{3a3}', '\u{3a4}', '\u{3a5}', '\u{3c2}', '\u{3a9}',
+    '\u{39e}', '\u{3a8}', '\u{396}', '[', '\u{2234}', ']', '\u{22a5}', '_',
+    // 0x60
+    '\u{203e}', '\u{3b1}', '\u{3b2}', '\u{3c7}', '\u{3b4}', '\u{3b5}', '\u{3c6}', '\u{3b3}',
+    '\u{3b7}', '\u{3b9}', '\u{3d5}', '\u{3ba}', '\u{3bb}', '\u{3bc}', '\u{3bd}', '\u{3bf}',
+    // 0x70
+    '\u{3c0}', '\u{3b8}', '\u{3c1}', '\u{3c3}', '\u{3c4}', '\u{3c5}', '\u{3d6}', '\u{3c9}',
+    '\u{3be}', '\u{3c8}', '\u{3b6}', '{', '|', '}', '\u{223c}', '\0', // 0x80
+    '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0',
+    // 0x90
+    '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0',
+    // 0xA0
+    '\u{20ac}', '\u{3d2}', '\u{2032}', '\u{2264}', '\u{2044}', '\u{221e}', '\u{192}', '\u{2663}',
+    '\u{2666}', '\u{2665}', '\u{2660}', '\u{2194}', '\u{2190}', '\u{2191}', '\u{2192}', '\u{2193}',
+    // 0xB0
+    '\u{b0}', '\u{b1}', '\u{2033}', '\u{2265}', '\u{d7}', '\u{221d}', '\u{2202}', '\u{2022}',
+    '\u{f7}', '\u{2260}', '\u{2261}', '\u{2248}', '\u{2026}', '\u{23d0}', '\u{23af}', '\u{21b5}',
+    // 0xC0
+    '\u{2135}', '\u{2111}', '\u{211c}', '\u{2118}', '\u{2297}', '\u{2295}', '\u{2205}', '\u{2229}',
+    '\u{222a}', '\u{2283}', '\u{2287}', '\u{2284}', '\u{2282}', '\u{2286}', '\u{2208}', '\u{2209}',
+    // 0xD0
+    '\u{2220}', '\u{2207}', '\u{ae}', '\u{a9}', '\u{2122}', '\u{220f}', '\u{221a}', '\u{22c5}',
+    '\u{ac}', '\u{2227}', '\u{2228}', '\u{21d4}', '\u{21d0}', '\u{21d1}', '\u{21d2}', '\u{21d3}',
+    // 0xE0
+    '\u{25ca}', '\u{2329}', '\u{ae}', '\u{a9}', '\u{2122}', '\u{2211}', '\u{239b}', '\u{239c}',
+    '\u{239d}', '\u{23a1}', '\u{23a2}', '\u{23a3}', '\u{23a7}', '\u{23a8}', '\u{23a9}', '\u{23aa}',
+    // 0xF0
+    '\0', '\u{232a}', '\u{222b}', '\u{2320}', '\u{23ae}', '\u{2321}', '\u{239e}', '\u{239f}',
+    '\u{23a0}', '\u{23a4}', '\u{23a5}', '\u{23a6}', '\u{23ab}', '\u{23ac}', '\u{23ad}', '\0',
+];
+
+// Wingdings glyphs commonly used as bullets and check marks
+const WINGDINGS: &[(u8, char)] = &[
+    (0x4a, '\u{263a}'),
+    (0x4c, '\u{2639}'),
+    (0x6c, '\u{25cf}'),
+    (0x6e, '\u{25a0}'),
+    (0x6f, '\u{25a1}'),
+    (0x71, '\u{2751}'),
+    (0x75, '\u{25c6}'),
+    (0x76, '\u{2756}'),
+    (0xa7, '\u{25aa}'),
+    (0xd8, '\u{27a2}'),
+    (0xfc, '\u{2714}'),
+    (0xfd, '\u{2612}'),
+    (0xfe, '\u{2611}'),
+];
+
+fn private_use(byte: u8) -> char {
+    std::char::from_u32(0xf000 + u32::from(byte)).unwrap_or('\u{fffd}')
+}
+
+impl SymbolFont {
+    /// Identify a symbol font from its font table entry, or None if the font
+    /// uses a regular character set
+    pub fn from_font(name: &str, charset: Option<i32>) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        if name == "symbol" {
+            Some(SymbolFont::Symbol)
+        } else if name == "wingdings" {
+            Some(SymbolFont::Wingdings)
+        } else if charset == Some(2) {
+            Some(SymbolFont::Other)
+        } else {
+            None
+        }
+    }
+
+    /// Map a character of the font to Unicode
+    pub fn decode(self, byte: u8) -> char {
+        let mapped = match self {
+            SymbolFont::Symbol if byte >= 0x20 => SYMBOL[usize::from(byte - 0x20)],
+            SymbolFont::Wingdings => WINGDINGS
+                .iter()
+                .find(|(wingding, _)| *wingding == byte)
+                .map_or('\0', |(_, c)| *c),
+            _ => '\0',
+        };
+        match mapped {
+            '\0' if byte < 0x20 => byte as char,
+            '\0' => private_use(byte),
+            c => c,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_font() {
+        assert_eq!(
+            SymbolFont::from_font("Symbol", Some(2)),
+            Some(SymbolFont::Symbol)
+        );
+        assert_eq!(
+            SymbolFont::from_font("Wingdings", None),
+            Some(SymbolFont::Wingdings)
+        );
+        // Wingdings 2 and 3 have their own layouts
+        assert_eq!(
+            SymbolFont::from_font("Wingdings 2", Some(2)),
+            Some(SymbolFont::Other)
+        );
+        assert_eq!(
+            SymbolFont::from_font("Webdings", Some(2)),
+            Some(SymbolFont::Other)
+        );
+        assert_eq!(SymbolFont::from_font("Times New Roman", Some(0)), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(SymbolFont::Symbol.decode(0xb7), '\u{2022}');
+        assert_eq!(SymbolFont::Symbol.decode(b'a'), '\u{3b1}');
+        assert_eq!(SymbolFont::Symbol.decode(b'1'), '1');
+        assert_eq!(SymbolFont::Symbol.decode(0x80), '\u{f080}');
+        assert_eq!(SymbolFont::Wingdings.decode(0xa7), '\u{25aa}');
+        assert_eq!(SymbolFont::Wingdings.decode(0x21), '\u{f021}');
+        assert_eq!(SymbolFont::Other.decode(0x41), '\u{f041}');
+    }
+}
@@ -0,0 +1,438 @@
+// Templates and mail merge
+//
+// Fills in the blanks of a template document: placeholders written into its
+// text, like `{{name}}`, and Word's `MERGEFIELD` fields.  Placeholders are
+// found in the decoded text, so they match however the document happens to
+// split and escape them, and values are escaped the way the document needs.
+// Formatting is left where it was, so a value takes on the formatting of the
+// placeholder it replaces.
+//
+
+use std;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use builder::text_tokens;
+use fields::FieldInstruction;
+use text::{decode_cp1252, pieces, Piece, Unit};
+use tokenizer::{child_group, group_contents, group_destination, group_end, Token};
+
+/// What `fill` looks for
+#[derive(Debug, Clone)]
+pub struct TemplateOptions {
+    /// The text that starts a placeholder
+    pub open: String,
+    /// The text that ends a placeholder
+    pub close: String,
+    /// Fill in `MERGEFIELD` fields too, named by their instructions
+    pub merge_fields: bool,
+}
+
+impl Default for TemplateOptions {
+    fn default() -> Self {
+        TemplateOptions {
+            open: "{{".to_string(),
+            close: "}}".to_string(),
+            merge_fields: true,
+        }
+    }
+}
+
+// Find the placeholders in a stretch of text between breaks, given its
+// characters and the pieces they're from
+fn find_placeholders(
+    chars: &[(char, usize)],
+    open: &[char],
+    close: &[char],
+    found: &mut Vec<(Range<usize>, String)>,
+) {
+    let text: Vec<char> = chars.iter().map(|(c, _)| *c).collect();
+    let mut start = 0;
+    while let Some(at) = find(&text[start..], open) {
+        let name_start = start + at + open.len();
+        let name_len = match find(&text[name_start..], close) {
+            Some(len) => len,
+            None => break,
+        };
+        let name = &text[name_start..name_start + name_len];
+        // An unclosed placeholder before this one
+        if let Some(inner) = find(name, open) {
+            start += at + inner + open.len();
+            continue;
+        }
+        let end = name_start + name_len + close.len();
+        let name: String = name.iter().collect();
+        let name = name.trim();
+        if !name.is_empty() {
+            found.push((chars[start + at].1..chars[end - 1].1 + 1, name.to_string()));
+        }
+        start = end;
+    }
+}
+
+// The placeholders in the decoded text: the range of pieces each covers,
+// and its name
+fn placeholders(pieces: &[Piece], options: &TemplateOptions) -> Vec<(Range<usize>, String)> {
+    let open: Vec<char> = options.open.chars().collect();
+    let close: Vec<char> = options.close.chars().collect();
+    let mut found = Vec::new();
+    if open.is_empty() || close.is_empty() {
+        return found;
+    }
+    let mut chars = Vec::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        match piece.unit {
+            Unit::Char(c) => chars.push((c, index)),
+            Unit::Fallback => {}
+            Unit::Break(_) => {
+                find_placeholders(&chars, &open, &close, &mut found);
+                chars.clear();
+            }
+        }
+    }
+    find_placeholders(&chars, &open, &close, &mut found);
+    found
+}
+
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+// Append a token, joining text to the text before it
+fn push(out: &mut Vec<Token>, token: Token) {
+    match (out.last_mut(), token) {
+        (Some(Token::Text(last)), Token::Text(text)) => last.extend_from_slice(&text),
+        (_, token) => out.push(token),
+    }
+}
+
+// Replace the placeholders in the text that `values` has values for
+fn fill_text<F: FnMut(&str) -> Option<String>>(
+    tokens: &[Token],
+    options: &TemplateOptions,
+    values: &mut F,
+) -> Vec<Token> {
+    let pieces = pieces(tokens);
+    let replacements = placeholders(&pieces, options)
+        .into_iter()
+        .filter_map(|(range, name)| values(&name).map(|value| (range, value)))
+        .collect();
+    replace_pieces(tokens, &pieces, replacements)
+}
+
+/// Replace stretches of decoded text, given the range of pieces of each and
+/// the text to replace it with, leaving the formatting where it was
+///
+/// The replacement is written where the stretch began, escaped for the
+/// `\uc` value in effect there.
+pub(crate) fn replace_pieces(
+    tokens: &[Token],
+    pieces: &[Piece],
+    replacements: Vec<(Range<usize>, String)>,
+) -> Vec<Token> {
+    // The bytes of text tokens, and the other tokens, to leave out
+    let mut removed_bytes: HashMap<usize, Vec<Range<usize>>> = HashMap::new();
+    let mut removed = vec![false; tokens.len()];
+    // Values to write before a byte of a text token, or before a token
+    let mut inserts: HashMap<(usize, usize), Vec<Token>> = HashMap::new();
+    for (range, value) in replacements {
+        // The fallback characters of a \uN at the end go with it
+        let mut end = range.end;
+        while pieces
+            .get(end)
+            .is_some_and(|piece| piece.unit == Unit::Fallback)
+        {
+            end += 1;
+        }
+        let first = &pieces[range.start];
+        let value = text_tokens(&value, first.uc);
+        inserts
+            .entry((first.token, first.bytes.start))
+            .or_default()
+            .extend(value);
+        for piece in &pieces[range.start..end] {
+            match tokens[piece.token] {
+                Token::Text(_) => removed_bytes
+                    .entry(piece.token)
+                    .or_default()
+                    .push(piece.bytes.clone()),
+                _ => removed[piece.token] = true,
+            }
+        }
+    }
+
+    let mut out = Vec::with_capacity(tokens.len());
+    for (index, token) in tokens.iter().enumerate() {
+        let data = match token {
+            Token::Text(data) if removed_bytes.contains_key(&index) => data,
+            _ => {
+                if let Some(value) = inserts.remove(&(index, 0)) {
+                    for token in value {
+                        push(&mut out, token);
+                    }
+                }
+                if !removed[index] {
+                    out.push(token.clone());
+                }
+                continue;
+            }
+        };
+        let ranges = &removed_bytes[&index];
+        let mut run = Vec::new();
+        for (offset, byte) in data.iter().enumerate() {
+            if let Some(value) = inserts.remove(&(index, offset)) {
+                if !run.is_empty() {
+                    push(&mut out, Token::Text(std::mem::take(&mut run)));
+                }
+                for token in value {
+                    push(&mut out, token);
+                }
+            }
+            if !ranges.iter().any(|range| range.contains(&offset)) {
+                run.push(*byte);
+            }
+        }
+        if !run.is_empty() {
+            push(&mut out, Token::Text(run));
+        }
+    }
+    out
+}
+
+// The text of a field instruction
+fn instruction(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(data) => text.extend(data.iter().map(|byte| decode_cp1252(*byte))),
+            Token::HexByte(byte) => text.push(decode_cp1252(*byte)),
+            Token::ControlSymbol(c) if *c != '*' => text.push(*c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// The field name of a `MERGEFIELD` instruction, like `MERGEFIELD "First
+/// Name" \* MERGEFORMAT`
+pub fn merge_field_name(instruction: &str) -> Option<String> {
+    let instruction = FieldInstruction::parse(instruction)?;
+    if instruction.kind != "MERGEFIELD" {
+        return None;
+    }
+    let name = instruction.arguments().next()?.to_string();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+// A field's result with its text replaced by `value`, keeping its formatting
+fn fill_result(result: &[Token], value: &str, out: &mut Vec<Token>) {
+    let value = text_tokens(value, 1);
+    // The fallbacks are written for \uc1, whatever the document uses
+    if value
+        .iter()
+        .any(|token| matches!(token, Token::Unicode { .. }))
+    {
+        out.push(Token::word_arg("uc", 1));
+    }
+    let mut value = Some(value);
+    for token in result {
+        match token {
+            Token::Text(_) | Token::HexByte(_) | Token::Unicode { .. } => {}
+            Token::ControlSymbol(c) if *c != '*' => {}
+            _ => {
+                out.push(token.clone());
+                continue;
+            }
+        }
+        // The value goes where the result's text began
+        out.extend(value.take().unwrap_or_default());
+    }
+    out.extend(value.unwrap_or_default());
+}
+
+// Replace the merge fields that `values` has values for with their values
+fn fill_fields<F: FnMut(&str) -> Option<String>>(tokens: &[Token], values: &mut F) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        if let Some(("field", _, _)) = group_destination(tokens, index) {
+            if tokens[index] == Token::StartGroup {
+                let end = group_end(tokens, index);
+                let value = child_group(tokens, index, end, "fldinst")
+                    .and_then(|(_, word, end)| {
+                        merge_field_name(&instruction(group_contents(tokens, word, end)))
+                    })
+                    .and_then(|name| values(&name));
+                if let Some(value) = value {
+                    let result = child_group(tokens, index, end, "fldrslt")
+                        .map_or(&[][..], |(_, word, end)| group_contents(tokens, word, end));
+                    out.push(Token::StartGroup);
+                    fill_result(result, &value, &mut out);
+                    out.push(Token::EndGroup);
+                    index = end;
+                    continue;
+                }
+            }
+        }
+        out.push(tokens[index].clone());
+        index += 1;
+    }
+    out
+}
+
+/// Fill in a template, replacing each placeholder and merge field that
+/// `values` has a value for
+///
+/// `values` is given the name of each placeholder, trimmed of whitespace,
+/// and of each `MERGEFIELD`.  Placeholders can be split between tokens, and
+/// formatting inside them is kept, but they can't span paragraphs, cells or
+/// tabs.  Values are escaped as they're written, with `\uN` escapes for the
+/// characters outside ASCII, and line breaks in them become `\line`.  A
+/// merge field is replaced by a group with the formatting of its result.
+///
+/// ```
+/// use rtf_grimoire::template::{fill, TemplateOptions};
+/// use rtf_grimoire::{extract_text, parse};
+///
+/// let tokens = parse(b"{\\rtf1 Dear \\b \\{\\{name\\}\\}\\b0 ,\\par}").unwrap();
+/// let filled = fill(&tokens, &TemplateOptions::default(), |name| match name {
+///     "name" => Some("Zo\u{eb}".to_string()),
+///     _ => None,
+/// });
+/// assert_eq!(extract_text(&filled), "Dear Zo\u{eb},\n");
+/// ```
+pub fn fill<F: FnMut(&str) -> Option<String>>(
+    tokens: &[Token],
+    options: &TemplateOptions,
+    mut values: F,
+) -> Vec<Token> {
+    let filled = fill_text(tokens, options, &mut values);
+    if options.merge_fields {
+        fill_fields(&filled, &mut values)
+    } else {
+        filled
+    }
+}
+
+/// The names of the placeholders and merge fields in a template, in the
+/// order they're found, each once
+pub fn placeholder_names(tokens: &[Token], options: &TemplateOptions) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    fill(tokens, options, |name| {
+        if !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+        None
+    });
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::{Block, Document};
+    use text::extract_text;
+    use tokenizer::parse;
+    use writer::{write_rtf, WriteOptions};
+
+    fn filled(rtf: &[u8], values: &[(&str, &str)]) -> String {
+        let tokens = parse(rtf).unwrap();
+        let filled = fill(&tokens, &TemplateOptions::default(), |name| {
+            values
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        });
+        String::from_utf8(write_rtf(&filled, &WriteOptions::default()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        let rtf = b"{\\rtf1 Dear \\{\\{ name \\}\\}, re: \\{\\{ref\\}\\}\\par}";
+        assert_eq!(
+            filled(rtf, &[("name", "Ann {B}"), ("ref", "caf\u{e9}")]),
+            "{\\rtf1 Dear Ann \\{B\\}, re: caf\\u233?\\par}"
+        );
+        // Only what there are values for
+        assert_eq!(
+            filled(rtf, &[("ref", "1")]),
+            "{\\rtf1 Dear \\{\\{ name \\}\\}, re: 1\\par}"
+        );
+        assert_eq!(
+            filled(b"{\\rtf1 <<x>> <<y>}", &[("x", "1")]),
+            "{\\rtf1<<x>> <<y>}"
+        );
+        let options = TemplateOptions {
+            open: "<<".to_string(),
+            close: ">>".to_string(),
+            ..TemplateOptions::default()
+        };
+        let tokens = parse(b"{\\rtf1 <<<<x>> <<y>}").unwrap();
+        assert_eq!(
+            extract_text(&fill(&tokens, &options, |_| Some("1".to_string()))),
+            "<<1 <<y>"
+        );
+    }
+
+    #[test]
+    fn test_fill_keeps_formatting() {
+        // Split by formatting, with the value taking the formatting of the
+        // start of the placeholder
+        let rtf = b"{\\rtf1\\uc2 a\\{\\{na{\\i m}e\\}\\}\\'e9\\par \\{\\{na\\par me\\}\\}}";
+        assert_eq!(
+            filled(rtf, &[("name", "\u{3b1}\n")]),
+            "{\\rtf1\\uc2 a\\u945??\\line{\\i}\\'e9\\par\\{\\{na\\par me\\}\\}}"
+        );
+        // A \u escape and its fallback
+        assert_eq!(
+            filled(b"{\\rtf1 \\{\\{\\u233?\\}\\} x}", &[("\u{e9}", "y")]),
+            "{\\rtf1 y x}"
+        );
+    }
+
+    #[test]
+    fn test_fill_merge_fields() {
+        let rtf = br#"{\rtf1 Dear {\field{\*\fldinst { MERGEFIELD "First Name" \\* MERGEFORMAT }}{\fldrslt {\b\'ab First Name\'bb}}},\par}"#;
+        let tokens = parse(rtf).unwrap();
+        assert_eq!(
+            placeholder_names(&tokens, &TemplateOptions::default()),
+            vec!["First Name".to_string()]
+        );
+        assert_eq!(
+            filled(rtf, &[("First Name", "J\u{f8}rn")]),
+            "{\\rtf1 Dear {\\uc1{\\b J\\u248?rn}},\\par}"
+        );
+        let document = Document::parse(filled(rtf, &[("First Name", "Jo")]).as_bytes()).unwrap();
+        match &document.blocks[0] {
+            Block::Paragraph(paragraph) => {
+                assert_eq!(paragraph.text(), "Dear Jo,");
+                assert!(paragraph.runs[1].format.bold);
+            }
+            block => panic!("{:?}", block),
+        }
+        assert_eq!(
+            filled(rtf, &[]),
+            String::from_utf8_lossy(&write_rtf(&tokens, &WriteOptions::default()).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_merge_field_name() {
+        assert_eq!(
+            merge_field_name(" MERGEFIELD Name \\* MERGEFORMAT "),
+            Some("Name".to_string())
+        );
+        assert_eq!(
+            merge_field_name("mergefield \"A B\""),
+            Some("A B".to_string())
+        );
+        assert_eq!(merge_field_name("HYPERLINK \"x\""), None);
+        assert_eq!(merge_field_name("MERGEFIELD"), None);
+    }
+}
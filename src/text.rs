@@ -0,0 +1,1957 @@
+// Plain text extraction
+//
+// Walks a token stream the way an RTF reader would, skipping destinations
+// that don't contribute to the document text, and decodes what remains into
+// a String.
+//
+
+use codepage;
+use codepage::CharsetDecoder;
+use document::{CharFormat, ParaFormat};
+use language::language_tag;
+use quirks::Quirks;
+use std;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use state::StateTracker;
+use symbol::SymbolFont;
+use tokenizer::{narrow, narrow_arg, Name, Token};
+
+/// Destinations whose content is never part of the document text
+pub(crate) const IGNORED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "listtable",
+    "listoverridetable",
+    "info",
+    "pict",
+    "object",
+    "fldinst",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+    "footnote",
+    "rsidtbl",
+    "revtbl",
+    "xmlnstbl",
+    "themedata",
+    "colorschememapping",
+    "datastore",
+    "latentstyles",
+    "pn",
+    "pntxta",
+    "pntxtb",
+    "nesttableprops",
+    "annotation",
+    "nonesttables",
+    // The result of a shape, a copy of it for readers that don't know shapes
+    "shprslt",
+    // RTFD attachment references, whose text is a file name
+    "NeXTGraphic",
+];
+
+/// The headers and footers, which are ignored unless
+/// `TextOptions::headers_footers` asks for them
+const HEADER_FOOTER_DESTINATIONS: &[&str] = &[
+    "header", "headerl", "headerr", "headerf", "footer", "footerl", "footerr", "footerf",
+];
+
+/// Whether a destination isn't part of the document text
+pub(crate) fn ignored_destination(name: &str, quirks: Quirks, headers_footers: bool) -> bool {
+    if headers_footers && HEADER_FOOTER_DESTINATIONS.contains(&name) {
+        return false;
+    }
+    IGNORED_DESTINATIONS.contains(&name) || quirks.ignores_destination(name)
+}
+
+/// Destinations whose content is part of the document text
+const TEXT_DESTINATIONS: &[&str] = &["rtf", "field", "fldrslt", "listtext", "pntext", "shptxt"];
+
+// Windows-1252 assignments for 0x80-0x9F; the rest of the code page is
+// identical to ISO-8859-1.  Unassigned positions decode to U+FFFD.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{fffd}', '\u{017d}', '\u{fffd}',
+    '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02dc}', '\u{2122}', '\u{0161}', '\u{203a}', '\u{0153}', '\u{fffd}', '\u{017e}', '\u{0178}',
+];
+
+pub(crate) fn decode_cp1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9f => CP1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+/// The result of feeding a token to a `Decoder`
+#[derive(Debug, PartialEq)]
+pub(crate) enum Decoded<'t> {
+    /// The token was consumed by the decoder, or contributes nothing
+    Nothing,
+    /// The token is inside a destination that isn't document text
+    Ignored,
+    GroupStart,
+    GroupEnd,
+    /// Decoded document text
+    Text(String),
+    /// A control word the decoder doesn't handle itself, left for the caller
+    Word(&'t str, Option<i32>),
+}
+
+// Which of the fonts associated with a run its text is in, from the last of
+// \loch, \hich and \dbch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharSet {
+    Low,
+    High,
+    Double,
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    destination: Option<Name>,
+    ignored: bool,
+    // Inside the font table, outside of any starred destination in it
+    font_table: bool,
+    // Number of fallback characters following a \u control word
+    uc: usize,
+    // The current \fN font, or None for the default font
+    font: Option<i32>,
+    // The fonts of low ANSI, high ANSI and double byte text, set by an \fN
+    // or \afN following \loch, \hich or \dbch
+    char_set: Option<CharSet>,
+    associated: [Option<i32>; 3],
+    // The \afN font of right-to-left text, and whether \fcs1 or \rtlch
+    // says an \afN is setting it
+    complex_font: Option<i32>,
+    complex: bool,
+    rtl: bool,
+    // Inside a shape or drawing object in the document text, where text boxes
+    // are document text too
+    shape: bool,
+    // Inside the text box of such a shape
+    text_box: bool,
+    // Hidden text, \v
+    hidden: bool,
+}
+
+impl Frame {
+    // Apply a control word that selects the font text is in
+    fn font_word(&mut self, name: &str, arg: Option<i32>) {
+        match name {
+            "f" => {
+                self.font = arg;
+                if let Some(char_set) = self.char_set {
+                    self.associated[char_set as usize] = arg;
+                }
+            }
+            "af" => match self.char_set {
+                Some(char_set) if !self.complex => self.associated[char_set as usize] = arg,
+                _ => self.complex_font = arg,
+            },
+            "loch" => self.char_set = Some(CharSet::Low),
+            "hich" => self.char_set = Some(CharSet::High),
+            "dbch" => self.char_set = Some(CharSet::Double),
+            "fcs" => self.complex = arg == Some(1),
+            "rtlch" => {
+                self.rtl = true;
+                self.complex = true;
+            }
+            "ltrch" => {
+                self.rtl = false;
+                self.complex = false;
+            }
+            "plain" => {
+                self.font = None;
+                self.char_set = None;
+                self.associated = [None; 3];
+                self.complex_font = None;
+                self.complex = false;
+                self.rtl = false;
+            }
+            _ => {}
+        }
+    }
+
+    // The font text is in: the associated font of right-to-left text, or of
+    // the character set \loch, \hich or \dbch chose, if there is one
+    fn text_font(&self) -> Option<i32> {
+        let associated = match self.char_set {
+            Some(char_set) => self.associated[char_set as usize],
+            None => None,
+        };
+        self.complex_font
+            .filter(|_| self.rtl)
+            .or(associated)
+            .or(self.font)
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        // The spec's default \uc value is 1
+        Frame {
+            destination: None,
+            ignored: false,
+            font_table: false,
+            uc: 1,
+            font: None,
+            char_set: None,
+            associated: [None; 3],
+            complex_font: None,
+            complex: false,
+            rtl: false,
+            shape: false,
+            text_box: false,
+            hidden: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FontEntry {
+    index: i32,
+    name: String,
+    charset: Option<i32>,
+    code_page: Option<i32>,
+}
+
+// How the text of a font is decoded
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    Symbol(SymbolFont),
+    CodePage(u32),
+}
+
+/// The parts of the font table that affect how text is decoded
+#[derive(Debug, Clone, Default)]
+struct FontTable {
+    encodings: HashMap<i32, Encoding>,
+    entry: Option<FontEntry>,
+}
+
+impl FontTable {
+    fn word(&mut self, name: &str, arg: Option<i32>) {
+        match (name, arg) {
+            ("f", Some(index)) => {
+                self.entry = Some(FontEntry {
+                    index,
+                    ..FontEntry::default()
+                })
+            }
+            ("fcharset", Some(charset)) => {
+                if let Some(ref mut entry) = self.entry {
+                    entry.charset = Some(charset);
+                }
+            }
+            ("cpg", Some(code_page)) => {
+                if let Some(ref mut entry) = self.entry {
+                    entry.code_page = Some(code_page);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn byte(&mut self, byte: u8) {
+        if byte != b';' {
+            if let Some(ref mut entry) = self.entry {
+                entry.name.push(decode_cp1252(byte));
+            }
+            return;
+        }
+        if let Some(entry) = self.entry.take() {
+            let code_page = entry
+                .code_page
+                .and_then(|code_page| u32::try_from(code_page).ok())
+                .or_else(|| entry.charset.and_then(codepage::charset_code_page));
+            let encoding = match SymbolFont::from_font(&entry.name, entry.charset) {
+                Some(symbol) => Encoding::Symbol(symbol),
+                None => match code_page {
+                    Some(code_page) => Encoding::CodePage(code_page),
+                    None => return,
+                },
+            };
+            self.encodings.insert(entry.index, encoding);
+        }
+    }
+}
+
+/// Document-level reader state: the defaults that apply to text with no
+/// explicit font or language, and the font table
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecoderState {
+    /// The `\deffN` default font
+    pub(crate) default_font: Option<i32>,
+    /// The `\deflangN` default language
+    pub(crate) language: Option<i32>,
+    /// The `\adeflangN` default language of associated (bidirectional and
+    /// East Asian) text
+    pub(crate) associated_language: Option<i32>,
+    /// The `\ansicpgN` code page, or that of a `\mac`, `\pc` or `\pca`
+    /// character set
+    pub(crate) code_page: Option<u32>,
+    // Conversions to try before the built-in code page tables
+    charsets: Option<Arc<dyn CharsetDecoder>>,
+    fonts: FontTable,
+}
+
+impl DecoderState {
+    /// A state that decodes with `charsets` before the built-in tables
+    pub(crate) fn with_charsets(charsets: Option<Arc<dyn CharsetDecoder>>) -> Self {
+        DecoderState {
+            charsets,
+            ..DecoderState::default()
+        }
+    }
+
+    /// Feed a control word from the document body, returning true if it was
+    /// a document-level default
+    pub(crate) fn word(&mut self, name: &str, arg: Option<i32>) -> bool {
+        match name {
+            "deff" => self.default_font = arg,
+            "deflang" => self.language = arg,
+            "adeflang" => self.associated_language = arg,
+            "ansicpg" => self.code_page = arg.and_then(|arg| u32::try_from(arg).ok()),
+            // Old Mac and DOS documents name their character set instead,
+            // which an \ansicpgN still overrides
+            "mac" | "pc" | "pca" => {
+                if self.code_page.is_none() {
+                    self.code_page = codepage::charset_word_code_page(name);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Feed a control word from the font table
+    pub(crate) fn font_table_word(&mut self, name: &str, arg: Option<i32>) {
+        self.fonts.word(name, arg);
+    }
+
+    /// Feed a byte of text from the font table
+    pub(crate) fn font_table_byte(&mut self, byte: u8) {
+        self.fonts.byte(byte);
+    }
+
+    fn encoding(&self, font: Option<i32>) -> Encoding {
+        font.or(self.default_font)
+            .and_then(|font| self.fonts.encodings.get(&font))
+            .copied()
+            .unwrap_or_else(|| {
+                Encoding::CodePage(self.code_page.unwrap_or(codepage::DEFAULT_CODE_PAGE))
+            })
+    }
+
+    fn is_lead_byte(&self, code_page: u32, byte: u8) -> bool {
+        let lead = self
+            .charsets
+            .as_ref()
+            .is_some_and(|charsets| charsets.is_lead_byte(code_page, byte));
+        #[cfg(feature = "encoding_rs")]
+        let lead = lead || codepage::EncodingRsDecoder.is_lead_byte(code_page, byte);
+        lead
+    }
+
+    fn decode_code_page(&self, code_page: u32, bytes: &[u8]) -> char {
+        let decoded = self
+            .charsets
+            .as_ref()
+            .and_then(|charsets| charsets.decode(code_page, bytes));
+        #[cfg(feature = "encoding_rs")]
+        let decoded = decoded.or_else(|| codepage::EncodingRsDecoder.decode(code_page, bytes));
+        match (decoded, bytes) {
+            (Some(c), _) => c,
+            (None, [byte]) => codepage::decode(code_page, *byte),
+            (None, _) => '\u{fffd}',
+        }
+    }
+
+    /// Decode a byte of text in the given `\fN` font, or the default font,
+    /// that may be half of a two byte character
+    ///
+    /// `lead` holds the lead byte of a character still waiting for its
+    /// second byte; `None` is returned while it does.
+    pub(crate) fn decode_char(
+        &self,
+        font: Option<i32>,
+        lead: &mut Option<u8>,
+        byte: u8,
+    ) -> Option<char> {
+        let code_page = match self.encoding(font) {
+            Encoding::Symbol(symbol) => {
+                *lead = None;
+                return Some(symbol.decode(byte));
+            }
+            Encoding::CodePage(code_page) => code_page,
+        };
+        if let Some(lead) = lead.take() {
+            return Some(self.decode_code_page(code_page, &[lead, byte]));
+        }
+        if self.is_lead_byte(code_page, byte) {
+            *lead = Some(byte);
+            return None;
+        }
+        Some(self.decode_code_page(code_page, &[byte]))
+    }
+}
+
+/// The reading state machine shared by everything that interprets document
+/// content
+///
+/// Tracks group-scoped reader state - which destination we're in, whether
+/// it's part of the document text, and the `\uc` value - and decodes text,
+/// hex escapes, `\u` escapes (skipping their fallback characters) and
+/// control symbols.  Control words with meaning beyond that are passed
+/// through to the caller.
+#[derive(Debug, Default)]
+pub(crate) struct Decoder {
+    stack: Vec<Frame>,
+    frame: Frame,
+    // Set immediately after a StartGroup (or a StartGroup and \*), to detect
+    // destinations
+    group_start: bool,
+    starred: bool,
+    // Fallback characters still to be skipped after a \u control word
+    fallback: usize,
+    // The lead byte of a two byte character, waiting for its second byte
+    lead: Option<u8>,
+    // A high surrogate from a \u escape, waiting for the low surrogate that
+    // completes it
+    surrogate: Option<u32>,
+    state: DecoderState,
+    // The characters control words and symbols stand for, if not the
+    // default ones
+    special_chars: Option<Arc<SpecialChars>>,
+    quirks: Quirks,
+    // Whether headers and footers are read as document text
+    headers_footers: bool,
+}
+
+impl Decoder {
+    pub(crate) fn new() -> Self {
+        Decoder::default()
+    }
+
+    /// A decoder for a destination read separately from the document, which
+    /// decodes text with the document's defaults and fonts
+    pub(crate) fn with_state(state: DecoderState) -> Self {
+        Decoder {
+            state,
+            ..Decoder::default()
+        }
+    }
+
+    /// A decoder for tokens that begin part way through a document, given the
+    /// `\uc` value in effect and whether they're inside an ignored destination
+    pub(crate) fn resuming(uc: usize, ignored: bool) -> Self {
+        let mut decoder = Decoder::new();
+        decoder.frame.uc = uc;
+        decoder.frame.ignored = ignored;
+        decoder
+    }
+
+    /// Number of groups currently open
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The innermost destination, if any
+    pub(crate) fn destination(&self) -> Option<&str> {
+        self.frame.destination.as_deref()
+    }
+
+    /// Whether we're inside a destination that isn't part of the document text
+    pub(crate) fn ignored(&self) -> bool {
+        self.frame.ignored
+    }
+
+    /// Whether we're inside the text box of a shape, text that's part of the
+    /// document but not of the paragraph the shape is anchored in
+    pub(crate) fn text_box(&self) -> bool {
+        self.frame.text_box
+    }
+
+    pub(crate) fn uc(&self) -> usize {
+        self.frame.uc
+    }
+
+    /// Whether the next character read is a `\u` fallback, to be skipped
+    pub(crate) fn in_fallback(&self) -> bool {
+        self.fallback > 0
+    }
+
+    /// Whether a high surrogate is waiting for the low surrogate that
+    /// completes it
+    pub(crate) fn surrogate_pending(&self) -> bool {
+        self.surrogate.is_some()
+    }
+
+    /// Decode control words and symbols with `table` in place of the default
+    /// table
+    pub(crate) fn set_special_chars(&mut self, table: Option<Arc<SpecialChars>>) {
+        self.special_chars = table;
+    }
+
+    fn special_char(&self, name: &str) -> Option<char> {
+        special_char(self.special_chars.as_deref(), name)
+    }
+
+    /// Allow for the quirks of the program that wrote the document
+    pub(crate) fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Read headers and footers as document text, where they're written
+    pub(crate) fn set_headers_footers(&mut self, headers_footers: bool) {
+        self.headers_footers = headers_footers;
+    }
+
+    /// Whether text read now is hidden (`\v`)
+    pub(crate) fn hidden(&self) -> bool {
+        self.frame.hidden
+    }
+
+    /// The document-level state read so far
+    pub(crate) fn state(&self) -> &DecoderState {
+        &self.state
+    }
+
+    fn decode_byte(&mut self, byte: u8) -> Option<char> {
+        self.state
+            .decode_char(self.frame.text_font(), &mut self.lead, byte)
+    }
+
+    // Returns true if the token was consumed as a \u fallback character
+    fn skip_fallback(&mut self) -> bool {
+        if self.fallback > 0 {
+            self.fallback -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Returns true if a control word or symbol was consumed as a \u fallback
+    // character.  Where the quirks say it can't be one, it ends them.
+    fn skip_fallback_word(&mut self) -> bool {
+        if self.quirks.word_is_fallback() {
+            self.skip_fallback()
+        } else {
+            self.fallback = 0;
+            false
+        }
+    }
+
+    pub(crate) fn token<'t>(&mut self, token: &'t Token) -> Decoded<'t> {
+        match self.decode_token(token) {
+            // A high surrogate followed by text instead of a low surrogate
+            // stands alone
+            Decoded::Text(text)
+                if !text.is_empty()
+                    && self.surrogate.is_some()
+                    && !matches!(token, Token::Unicode { .. }) =>
+            {
+                self.surrogate = None;
+                Decoded::Text(format!("\u{fffd}{}", text))
+            }
+            decoded => decoded,
+        }
+    }
+
+    fn decode_token<'t>(&mut self, token: &'t Token) -> Decoded<'t> {
+        let group_start = self.group_start;
+        // Line breaks are ignored, even between a group's `{` and the
+        // destination it starts
+        if *token != Token::Newline {
+            self.group_start = false;
+        }
+        // The two bytes of a character are written one after the other
+        if !matches!(token, Token::HexByte(_) | Token::Text(_)) {
+            self.lead = None;
+        }
+
+        match token {
+            Token::StartGroup => {
+                self.fallback = 0;
+                self.stack.push(self.frame.clone());
+                self.group_start = true;
+                self.starred = false;
+                return Decoded::GroupStart;
+            }
+            Token::EndGroup => {
+                self.fallback = 0;
+                if let Some(frame) = self.stack.pop() {
+                    self.frame = frame;
+                }
+                return Decoded::GroupEnd;
+            }
+            Token::ControlSymbol('*') if group_start => {
+                // The destination name follows the \*
+                self.group_start = true;
+                self.starred = true;
+                self.frame.ignored = true;
+                self.frame.font_table = false;
+                return Decoded::Ignored;
+            }
+            Token::ControlWord { name, .. } if group_start => {
+                // Groups nested inside an ignored destination are generally
+                // destinations themselves, like the fields of \info
+                let ignored = ignored_destination(name, self.quirks, self.headers_footers);
+                if self.starred
+                    || self.frame.ignored
+                    || ignored
+                    || TEXT_DESTINATIONS.contains(&name.as_str())
+                {
+                    self.frame.destination = Some(name.clone());
+                }
+                if ignored {
+                    self.frame.ignored = true;
+                }
+                if name == "fonttbl" {
+                    self.frame.font_table = true;
+                }
+                match name.as_str() {
+                    "shpinst" | "do" if !self.stack.last().is_some_and(|frame| frame.ignored) => {
+                        self.frame.shape = true
+                    }
+                    "shptxt" | "dptxbxtext" if self.frame.shape => {
+                        self.frame.ignored = false;
+                        self.frame.text_box = true;
+                    }
+                    "shprslt" => self.frame.shape = false,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        if self.frame.ignored {
+            match token {
+                Token::ControlWord {
+                    name,
+                    arg: Some(uc),
+                } if name == "uc" => self.frame.uc = std::cmp::max(narrow(*uc), 0) as usize,
+                Token::ControlWord { name, arg } if self.frame.font_table => {
+                    self.state.font_table_word(name, narrow_arg(*arg))
+                }
+                Token::Text(data) if self.frame.font_table => {
+                    for byte in data {
+                        self.state.font_table_byte(*byte);
+                    }
+                }
+                Token::HexByte(byte) if self.frame.font_table => self.state.font_table_byte(*byte),
+                _ => {}
+            }
+            return Decoded::Ignored;
+        }
+
+        match token {
+            Token::ControlWord { name, arg } => self.control_word(name, narrow_arg(*arg)),
+            Token::ControlSymbol(c) => {
+                if self.skip_fallback_word() {
+                    return Decoded::Nothing;
+                }
+                match c {
+                    '{' | '}' | '\\' => Decoded::Text(c.to_string()),
+                    _ => match self.special_char(&c.to_string()) {
+                        Some(c) => Decoded::Text(c.to_string()),
+                        None => Decoded::Nothing,
+                    },
+                }
+            }
+            Token::Unicode { codepoint } => {
+                self.fallback = self.frame.uc;
+                let text: String = utf16_unit(&mut self.surrogate, *codepoint as u32)
+                    .iter()
+                    .flatten()
+                    .collect();
+                if text.is_empty() {
+                    Decoded::Nothing
+                } else {
+                    Decoded::Text(text)
+                }
+            }
+            Token::HexByte(byte) => {
+                if self.skip_fallback() {
+                    return Decoded::Nothing;
+                }
+                match self.decode_byte(*byte) {
+                    Some(c) => Decoded::Text(c.to_string()),
+                    None => Decoded::Nothing,
+                }
+            }
+            Token::Text(data) => {
+                let mut text = String::with_capacity(data.len());
+                for byte in data {
+                    if !self.skip_fallback() {
+                        text.extend(self.decode_byte(*byte));
+                    }
+                }
+                Decoded::Text(text)
+            }
+            Token::ControlBin(_) => {
+                self.skip_fallback();
+                Decoded::Nothing
+            }
+            Token::Newline | Token::StartGroup | Token::EndGroup => Decoded::Nothing,
+        }
+    }
+
+    fn control_word<'t>(&mut self, name: &'t str, arg: Option<i32>) -> Decoded<'t> {
+        match (name, arg) {
+            ("uc", Some(count)) => {
+                self.frame.uc = std::cmp::max(count, 0) as usize;
+                Decoded::Nothing
+            }
+            _ => {
+                if self.skip_fallback_word() {
+                    return Decoded::Nothing;
+                }
+                // The breaks are left for the caller, as they're structure
+                // as well as text
+                if !TEXT_WORDS.contains(&name) {
+                    if let Some(c) = self.special_char(name) {
+                        return Decoded::Text(c.to_string());
+                    }
+                }
+                self.frame.font_word(name, arg);
+                match name {
+                    "v" => self.frame.hidden = arg != Some(0),
+                    "plain" => self.frame.hidden = false,
+                    _ => {}
+                }
+                self.state.word(name, arg);
+                Decoded::Word(name, arg)
+            }
+        }
+    }
+}
+
+/// How whitespace in the document text is reproduced in the extracted text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Whitespace {
+    /// Collapse runs of spaces into a single space, drop spaces at the start
+    /// and end of lines, and treat nonbreaking spaces (`\~`) as regular spaces
+    #[default]
+    Normalize,
+    /// Reproduce spacing exactly as written, including runs of spaces, and
+    /// emit nonbreaking spaces (`\~`) as U+00A0 NO-BREAK SPACE
+    ///
+    /// Useful for documents like code listings and ASCII tables, where the
+    /// spacing carries meaning
+    Preserve,
+}
+
+// The characters that control words and symbols stand for
+const STANDARD_CHARS: &[(&str, char)] = &[
+    ("par", '\n'),
+    ("sect", '\n'),
+    ("line", '\n'),
+    ("page", '\n'),
+    ("tab", '\t'),
+    // The end of a table cell and of a row, so that tables come out as
+    // tab separated lines
+    ("cell", '\t'),
+    ("nestcell", '\t'),
+    ("row", '\n'),
+    ("nestrow", '\n'),
+    ("emdash", '\u{2014}'),
+    ("endash", '\u{2013}'),
+    ("emspace", '\u{2003}'),
+    ("enspace", '\u{2002}'),
+    ("qmspace", '\u{2005}'),
+    ("lquote", '\u{2018}'),
+    ("rquote", '\u{2019}'),
+    ("ldblquote", '\u{201c}'),
+    ("rdblquote", '\u{201d}'),
+    ("bullet", '\u{2022}'),
+    ("zwj", '\u{200d}'),
+    ("zwnj", '\u{200c}'),
+    ("ltrmark", '\u{200e}'),
+    ("rtlmark", '\u{200f}'),
+    ("~", '\u{a0}'),
+    ("-", '\u{ad}'),
+    ("_", '-'),
+];
+
+/// The characters that control words and symbols standing for one are
+/// extracted as
+///
+/// Control words are looked up by name, like `"emdash"`, and control symbols
+/// by their character, like `"~"`.  The default table has the paragraph,
+/// line, section and page breaks as `'\n'` and `\tab` as `'\t'`, the ends of
+/// table cells (`\cell`, `\nestcell`) as `'\t'` and of rows (`\row`,
+/// `\nestrow`) as `'\n'`, the dashes, spaces, quotes and marks as their
+/// Unicode characters, `\~` as U+00A0 NO-BREAK SPACE and `\-` as U+00AD
+/// SOFT HYPHEN.  `\_`, a nonbreaking hyphen, is extracted as a plain `'-'`,
+/// so that text reads as it's spelled.  Control words and symbols the table
+/// leaves out are left out of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecialChars {
+    chars: HashMap<String, char>,
+}
+
+impl Default for SpecialChars {
+    fn default() -> Self {
+        SpecialChars {
+            chars: STANDARD_CHARS
+                .iter()
+                .map(|(name, c)| (name.to_string(), *c))
+                .collect(),
+        }
+    }
+}
+
+impl SpecialChars {
+    /// A table with nothing in it, which leaves them all out
+    pub fn empty() -> Self {
+        SpecialChars {
+            chars: HashMap::new(),
+        }
+    }
+
+    /// The character a control word or symbol is extracted as
+    pub fn get(&self, name: &str) -> Option<char> {
+        self.chars.get(name).copied()
+    }
+
+    /// Extract a control word or symbol as `c`, returning the character it
+    /// was extracted as before
+    pub fn insert(&mut self, name: &str, c: char) -> Option<char> {
+        self.chars.insert(name.to_string(), c)
+    }
+
+    /// Leave a control word or symbol out of the text, returning the
+    /// character it was extracted as before
+    pub fn remove(&mut self, name: &str) -> Option<char> {
+        self.chars.remove(name)
+    }
+}
+
+/// The characters a `\u` escape's UTF-16 code unit completes, given the high
+/// surrogate waiting in `pending`: none for a high surrogate, which waits in
+/// turn, and U+FFFD REPLACEMENT CHARACTER for a surrogate with no partner
+pub(crate) fn utf16_unit(pending: &mut Option<u32>, unit: u32) -> [Option<char>; 2] {
+    let stale = |high: Option<u32>| high.map(|_| '\u{fffd}');
+    match (pending.take(), unit) {
+        (high, 0xd800..=0xdbff) => {
+            *pending = Some(unit);
+            [stale(high), None]
+        }
+        (Some(high), 0xdc00..=0xdfff) => {
+            let c = 0x10000 + ((high - 0xd800) << 10) + (unit - 0xdc00);
+            [std::char::from_u32(c), None]
+        }
+        (high, _) => [
+            stale(high),
+            Some(std::char::from_u32(unit).unwrap_or('\u{fffd}')),
+        ],
+    }
+}
+
+/// The character a control word or symbol is extracted as, from `table` or
+/// else the default table
+pub(crate) fn special_char(table: Option<&SpecialChars>, name: &str) -> Option<char> {
+    match table {
+        Some(table) => table.get(name),
+        None => STANDARD_CHARS
+            .iter()
+            .find(|(standard, _)| *standard == name)
+            .map(|(_, c)| *c),
+    }
+}
+
+/// What text extraction does with hidden text (`\v`)
+///
+/// Hidden text isn't shown or printed, but it's still in the document, and
+/// often holds what its authors meant to take out of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HiddenText {
+    /// Extract hidden text along with the rest, as if it were shown
+    #[default]
+    Include,
+    /// Leave hidden text out, extracting the text as a reader sees it
+    Skip,
+    /// Extract only the hidden text
+    Only,
+}
+
+impl HiddenText {
+    // Whether text is extracted, given whether it's hidden
+    pub(crate) fn keeps(self, hidden: bool) -> bool {
+        match self {
+            HiddenText::Include => true,
+            HiddenText::Skip => !hidden,
+            HiddenText::Only => hidden,
+        }
+    }
+}
+
+/// What text extraction does with the control words standing for text that
+/// isn't known until the document is shown: `\chpgn`, the page number,
+/// `\chdate`, `\chdpl` and `\chdpa`, the date in short, long and
+/// abbreviated forms, and `\chtime`, the time
+///
+/// They're most often found in headers and footers, which are extracted
+/// with `TextOptions::headers_footers`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DynamicText {
+    /// Leave them out
+    #[default]
+    Skip,
+    /// Extract a placeholder for each: `{PAGE}`, `{DATE}` or `{TIME}`
+    Placeholders,
+    /// Extract the values given
+    Values(DynamicValues),
+}
+
+impl DynamicText {
+    // The text a control word is extracted as, if it's one of these
+    pub(crate) fn text(&self, name: &str) -> Option<&str> {
+        match self {
+            DynamicText::Skip => None,
+            DynamicText::Placeholders => match name {
+                "chpgn" => Some("{PAGE}"),
+                "chdate" | "chdpl" | "chdpa" => Some("{DATE}"),
+                "chtime" => Some("{TIME}"),
+                _ => None,
+            },
+            DynamicText::Values(values) => match name {
+                "chpgn" => Some(&values.page),
+                "chdate" => Some(&values.date),
+                "chdpl" => Some(&values.long_date),
+                "chdpa" => Some(&values.abbreviated_date),
+                "chtime" => Some(&values.time),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The text extracted for `\chpgn`, `\chdate`, `\chdpl`, `\chdpa` and
+/// `\chtime`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DynamicValues {
+    pub page: String,
+    pub date: String,
+    pub long_date: String,
+    pub abbreviated_date: String,
+    pub time: String,
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+// The year, month and day of a number of days since 1970-01-01, in the
+// proleptic Gregorian calendar
+fn civil_date(days: i64) -> (i64, usize, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Counting months from March, so that leap days come last
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as usize, day)
+}
+
+impl DynamicValues {
+    /// The values for a page, at a time given in seconds since the Unix
+    /// epoch, UTC
+    ///
+    /// Dates and times are written the way Word writes them in US English.
+    ///
+    /// ```
+    /// use rtf_grimoire::text::DynamicValues;
+    ///
+    /// let values = DynamicValues::at(3, 1_792_159_500);
+    /// assert_eq!(values.page, "3");
+    /// assert_eq!(values.date, "10/16/2026");
+    /// assert_eq!(values.long_date, "Friday, October 16, 2026");
+    /// assert_eq!(values.abbreviated_date, "Fri, Oct 16, 2026");
+    /// assert_eq!(values.time, "2:05 PM");
+    /// ```
+    pub fn at(page: u32, seconds: i64) -> Self {
+        let days = seconds.div_euclid(86400);
+        let minutes = seconds.rem_euclid(86400) / 60;
+        let (year, month, day) = civil_date(days);
+        // 1970-01-01 was a Thursday
+        let weekday = WEEKDAYS[(days + 3).rem_euclid(7) as usize];
+        let month_name = MONTHS[month - 1];
+        let (hour, minute) = (minutes / 60, minutes % 60);
+        DynamicValues {
+            page: page.to_string(),
+            date: format!("{}/{}/{}", month, day, year),
+            long_date: format!("{}, {} {}, {}", weekday, month_name, day, year),
+            abbreviated_date: format!("{}, {} {}, {}", &weekday[..3], &month_name[..3], day, year),
+            time: format!(
+                "{}:{:02} {}",
+                (hour + 11) % 12 + 1,
+                minute,
+                if hour < 12 { "AM" } else { "PM" }
+            ),
+        }
+    }
+
+    /// The values for a page, at the current time, UTC
+    pub fn now(page: u32) -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        DynamicValues::at(page, seconds)
+    }
+}
+
+/// Options controlling text extraction
+#[derive(Debug, Clone, Default)]
+pub struct TextOptions {
+    pub whitespace: Whitespace,
+    pub hidden: HiddenText,
+    /// Code page conversions to use before the built-in tables, such as
+    /// one for the double byte code pages the tables don't cover
+    pub charset_decoder: Option<Arc<dyn CharsetDecoder>>,
+    /// The characters that control words like `\emdash` are extracted as,
+    /// in place of the default table
+    pub special_chars: Option<Arc<SpecialChars>>,
+    /// The quirks of the program that wrote the document, to allow for
+    pub quirks: Quirks,
+    /// What `\chpgn`, `\chdate` and the like are extracted as
+    pub dynamic: DynamicText,
+    /// Extract the text of headers and footers (`\header`, `\footerl` and
+    /// the like) too, where they're written, which is usually before the
+    /// body text of the section they belong to
+    pub headers_footers: bool,
+}
+
+/// Append a character of document text, applying the whitespace policy
+pub(crate) fn push_text_char(text: &mut String, c: char, whitespace: Whitespace) {
+    if whitespace == Whitespace::Preserve {
+        text.push(c);
+        return;
+    }
+    match c {
+        ' ' | '\u{a0}' => match text.chars().last() {
+            None | Some(' ') | Some('\n') => {}
+            _ => text.push(' '),
+        },
+        '\n' => {
+            let trimmed = text.trim_end_matches(' ').len();
+            text.truncate(trimmed);
+            text.push(c);
+        }
+        _ => text.push(c),
+    }
+}
+
+struct Extractor<'o> {
+    options: &'o TextOptions,
+    text: String,
+    decoder: Decoder,
+}
+
+impl<'o> Extractor<'o> {
+    fn new(options: &'o TextOptions, decoder: Decoder) -> Self {
+        Extractor {
+            options,
+            text: String::new(),
+            decoder,
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        push_text_char(&mut self.text, c, self.options.whitespace);
+    }
+
+    fn token(&mut self, token: &Token) {
+        let decoded = self.decoder.token(token);
+        if !self.options.hidden.keeps(self.decoder.hidden()) {
+            return;
+        }
+        match decoded {
+            Decoded::Text(text) => {
+                for c in text.chars() {
+                    self.push_char(c);
+                }
+            }
+            Decoded::Word(name, _) => {
+                if let Some(c) = special_char(self.options.special_chars.as_deref(), name) {
+                    self.push_char(c);
+                }
+                if let Some(text) = self.options.dynamic.text(name) {
+                    for c in text.chars() {
+                        self.push_char(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn extract<'t, I: IntoIterator<Item = &'t Token>>(
+    tokens: I,
+    options: &TextOptions,
+    mut decoder: Decoder,
+) -> String {
+    if options.charset_decoder.is_some() {
+        decoder.state.charsets = options.charset_decoder.clone();
+    }
+    if options.special_chars.is_some() {
+        decoder.set_special_chars(options.special_chars.clone());
+    }
+    decoder.set_quirks(options.quirks);
+    decoder.set_headers_footers(options.headers_footers);
+    let mut extractor = Extractor::new(options, decoder);
+    for token in tokens {
+        extractor.token(token);
+    }
+    extractor.text
+}
+
+/// Extract the document text from a token stream, using the default options
+pub fn extract_text(tokens: &[Token]) -> String {
+    extract_text_with(tokens, &TextOptions::default())
+}
+
+/// Extract the document text from a token stream
+///
+/// Destinations that aren't part of the document body (font and color tables,
+/// stylesheets, document info, pictures, field instructions, and any
+/// destination marked with `\*`) are skipped.  The text boxes of shapes and
+/// drawing objects are extracted where the shape is anchored.
+pub fn extract_text_with(tokens: &[Token], options: &TextOptions) -> String {
+    extract(tokens, options, Decoder::new())
+}
+
+/// Extract text from tokens that begin part way through a document, given
+/// the `\uc` value in effect and whether they're inside an ignored destination
+pub(crate) fn extract_text_resuming<'t, I: IntoIterator<Item = &'t Token>>(
+    tokens: I,
+    options: &TextOptions,
+    uc: usize,
+    ignored: bool,
+) -> String {
+    extract(tokens, options, Decoder::resuming(uc, ignored))
+}
+
+/// Control words that stand for text
+pub(crate) const TEXT_WORDS: &[&str] = &[
+    "par", "line", "sect", "page", "tab", "cell", "row", "nestcell", "nestrow",
+];
+
+/// A unit of the decoded text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Unit {
+    Char(char),
+    // A \uN fallback character, which readers skip
+    Fallback,
+    // A control word that stands for text, like \par, and the character
+    // it's extracted as, if any
+    Break(Option<char>),
+}
+
+/// A unit of the decoded text, and where it came from: the token, and the
+/// bytes of it when it's a text token
+#[derive(Debug)]
+pub(crate) struct Piece {
+    pub(crate) unit: Unit,
+    pub(crate) token: usize,
+    pub(crate) bytes: Range<usize>,
+    /// The `\uc` value in effect
+    pub(crate) uc: usize,
+}
+
+/// Decode a token stream into the units of its text
+pub(crate) fn pieces(tokens: &[Token]) -> Vec<Piece> {
+    let mut pieces: Vec<Piece> = Vec::new();
+    let mut decoder = Decoder::new();
+    // The piece of a high surrogate waiting for its low surrogate, which
+    // holds U+FFFD until it gets one
+    let mut surrogate: Option<usize> = None;
+    for (index, token) in tokens.iter().enumerate() {
+        let data = match token {
+            Token::Text(data) => data,
+            _ => {
+                let fallback = decoder.in_fallback();
+                let uc = decoder.uc();
+                let pending = decoder.surrogate_pending();
+                let unit = match decoder.token(token) {
+                    Decoded::Text(text) => {
+                        let mut chars = text.chars();
+                        // The first character completes the high surrogate:
+                        // the character the pair stands for, which goes in
+                        // the high surrogate's piece, leaving the low one as
+                        // a fallback to be removed with it, or the U+FFFD
+                        // already there
+                        if pending {
+                            match (surrogate.take(), chars.next()) {
+                                (Some(high), Some(c)) if c != '\u{fffd}' => {
+                                    pieces[high].unit = Unit::Char(c);
+                                    pieces.push(Piece {
+                                        unit: Unit::Fallback,
+                                        token: index,
+                                        bytes: 0..0,
+                                        uc,
+                                    });
+                                }
+                                _ => {}
+                            }
+                        }
+                        for c in chars {
+                            pieces.push(Piece {
+                                unit: Unit::Char(c),
+                                token: index,
+                                bytes: 0..0,
+                                uc,
+                            });
+                        }
+                        if decoder.surrogate_pending() {
+                            surrogate = Some(pieces.len());
+                            pieces.push(Piece {
+                                unit: Unit::Char('\u{fffd}'),
+                                token: index,
+                                bytes: 0..0,
+                                uc,
+                            });
+                        }
+                        continue;
+                    }
+                    Decoded::Nothing if decoder.surrogate_pending() && !pending => {
+                        surrogate = Some(pieces.len());
+                        Unit::Char('\u{fffd}')
+                    }
+                    Decoded::Nothing if fallback => Unit::Fallback,
+                    Decoded::Word(name, _) if TEXT_WORDS.contains(&name) => {
+                        Unit::Break(special_char(None, name))
+                    }
+                    _ => continue,
+                };
+                pieces.push(Piece {
+                    unit,
+                    token: index,
+                    bytes: 0..0,
+                    uc,
+                });
+                continue;
+            }
+        };
+        // Text is decoded a byte at a time, to know which bytes each
+        // character came from
+        let mut lead = None;
+        for (offset, byte) in data.iter().enumerate() {
+            let fallback = decoder.in_fallback();
+            let uc = decoder.uc();
+            let pending = decoder.surrogate_pending();
+            let text = match decoder.token(&Token::Text(vec![*byte])) {
+                Decoded::Text(text) => text,
+                _ => continue,
+            };
+            let mut chars = text.chars();
+            if pending && !text.is_empty() {
+                // The high surrogate before stands alone, as the U+FFFD its
+                // piece holds
+                chars.next();
+                surrogate = None;
+            }
+            let start = lead.take().unwrap_or(offset);
+            if fallback {
+                pieces.push(Piece {
+                    unit: Unit::Fallback,
+                    token: index,
+                    bytes: offset..offset + 1,
+                    uc,
+                });
+            } else if text.is_empty() {
+                // The lead byte of a two byte character
+                lead = Some(start);
+            }
+            for c in chars {
+                pieces.push(Piece {
+                    unit: Unit::Char(c),
+                    token: index,
+                    bytes: start..offset + 1,
+                    uc,
+                });
+            }
+        }
+    }
+    pieces
+}
+
+/// The text of a token stream's pieces as `extract_text_with` extracts it,
+/// with the byte offset in the text of each character and the piece it's
+/// from
+pub(crate) fn piece_text(
+    pieces: &[Piece],
+    whitespace: Whitespace,
+) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::new();
+    let mut chars: Vec<(usize, usize)> = Vec::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        let c = match piece.unit {
+            Unit::Char(c) | Unit::Break(Some(c)) => c,
+            _ => continue,
+        };
+        let len = text.len();
+        push_text_char(&mut text, c, whitespace);
+        // Spaces can be dropped, and line breaks drop the spaces before them
+        if let Some(pushed) = text
+            .chars()
+            .last()
+            .filter(|_| text.len() > len || c == '\n')
+        {
+            let offset = text.len() - pushed.len_utf8();
+            while chars.last().is_some_and(|(last, _)| *last >= offset) {
+                chars.pop();
+            }
+            chars.push((offset, index));
+        }
+    }
+    (text, chars)
+}
+
+/// The first and last of the pieces that the characters in a range of text
+/// are from, given the characters as `piece_text` gives them
+pub(crate) fn range_pieces(
+    chars: &[(usize, usize)],
+    range: &Range<usize>,
+) -> Option<(usize, usize)> {
+    let start = chars.partition_point(|(offset, _)| *offset < range.start);
+    let end = chars.partition_point(|(offset, _)| *offset < range.end);
+    if start < end {
+        Some((chars[start].1, chars[end - 1].1))
+    } else {
+        None
+    }
+}
+
+/// A run of document text sharing the same character formatting
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TextRun {
+    /// The text of the run - paragraph and line breaks appear as `'\n'` and
+    /// tabs as `'\t'`
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    /// Font table index
+    pub font: Option<i32>,
+    /// Font size in half-points
+    pub size: Option<i32>,
+    /// Color table index
+    pub color: Option<usize>,
+    /// Language of the text, a Windows language id: `\alangN` for
+    /// right-to-left text and `\langN` for the rest, or the document's
+    /// defaults for each
+    pub language: Option<i32>,
+    /// Right-to-left text (`\rtlch`)
+    pub rtl: bool,
+    /// Part of a right-to-left paragraph (`\rtlpar`)
+    ///
+    /// Text is always extracted in the order it's stored, which is the
+    /// order it's read in; laying out right-to-left text is left to the
+    /// Unicode bidirectional algorithm, with this as the paragraph direction.
+    pub rtl_paragraph: bool,
+    /// Hidden text (`\v`), which isn't shown or printed
+    pub hidden: bool,
+}
+
+impl TextRun {
+    fn with_format(format: &CharFormat, para_format: &ParaFormat, state: &DecoderState) -> Self {
+        let default_language = if format.rtl {
+            state.associated_language
+        } else {
+            state.language
+        };
+        TextRun {
+            text: String::new(),
+            bold: format.bold,
+            italic: format.italic,
+            underline: format.underline,
+            font: format.font,
+            size: format.size,
+            color: format.color,
+            language: format.text_language().or(default_language),
+            rtl: format.rtl,
+            rtl_paragraph: para_format.rtl,
+            hidden: format.hidden,
+        }
+    }
+
+    /// The BCP 47 tag of the run's language, such as `"he-IL"`
+    pub fn language_tag(&self) -> Option<&'static str> {
+        self.language.and_then(language_tag)
+    }
+
+    fn same_format(&self, other: &TextRun) -> bool {
+        (
+            self.bold,
+            self.italic,
+            self.underline,
+            self.font,
+            self.size,
+            self.color,
+            self.language,
+            self.rtl,
+            self.rtl_paragraph,
+            self.hidden,
+        ) == (
+            other.bold,
+            other.italic,
+            other.underline,
+            other.font,
+            other.size,
+            other.color,
+            other.language,
+            other.rtl,
+            other.rtl_paragraph,
+            other.hidden,
+        )
+    }
+}
+
+/// Extract the document text from a token stream as runs of formatted text
+///
+/// Character formatting is tracked the way a reader would, including
+/// restoring it at the end of each group.  Adjacent text with the same
+/// formatting is merged into a single run, and whitespace is reproduced
+/// exactly as written.  Hidden text (`\v`) is extracted in runs of its own,
+/// marked as `hidden`.
+pub fn extract_runs(tokens: &[Token]) -> Vec<TextRun> {
+    let mut tracker = StateTracker::new();
+    let mut runs: Vec<TextRun> = Vec::new();
+
+    for token in tokens {
+        let text = match tracker.read(token) {
+            Some(Decoded::Text(text)) => text,
+            Some(Decoded::Word(name, _)) if TEXT_WORDS.contains(&name) => {
+                match special_char(None, name) {
+                    Some(c) => c.to_string(),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        let run = TextRun::with_format(
+            tracker.char_format(),
+            tracker.para_format(),
+            tracker.decoder().state(),
+        );
+        match runs.last_mut() {
+            Some(last) if last.same_format(&run) => last.text.push_str(&text),
+            _ => runs.push(TextRun { text, ..run }),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    fn preserve() -> TextOptions {
+        TextOptions {
+            whitespace: Whitespace::Preserve,
+            ..TextOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_piece_text() {
+        let tokens = parse(b"{\\rtf1 A  b \\par\\tab{\\f0 \\'e9}\\u945?x}").unwrap();
+        let pieces = pieces(&tokens);
+        for whitespace in [Whitespace::Normalize, Whitespace::Preserve] {
+            let options = TextOptions {
+                whitespace,
+                ..TextOptions::default()
+            };
+            let (text, chars) = piece_text(&pieces, whitespace);
+            assert_eq!(text, extract_text_with(&tokens, &options));
+            assert_eq!(chars.len(), text.chars().count());
+        }
+        let (text, chars) = piece_text(&pieces, Whitespace::Normalize);
+        let tokens: Vec<usize> = chars
+            .iter()
+            .map(|(_, piece)| pieces[*piece].token)
+            .collect();
+        assert_eq!(text, "A b\n\t\u{e9}\u{3b1}x");
+        assert_eq!(tokens, vec![2, 2, 2, 3, 4, 7, 9, 10]);
+    }
+
+    #[test]
+    fn test_extract_runs() {
+        let tokens = parse(br#"{\rtf1{\fonttbl{\f0 Times;}}\f0\fs24 Plain {\b\cf2 bold {\i both}}\par\ul under\ulnone  done}"#).unwrap();
+        let runs = extract_runs(&tokens);
+        let texts: Vec<&str> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Plain ", "bold ", "both", "\n", "under", " done"]
+        );
+        assert_eq!(runs[0].font, Some(0));
+        assert_eq!(runs[0].size, Some(24));
+        assert!(runs[1].bold && !runs[1].italic);
+        assert_eq!(runs[1].color, Some(2));
+        assert!(runs[2].bold && runs[2].italic);
+        assert!(!runs[3].bold && runs[3].color.is_none());
+        assert!(runs[4].underline);
+        assert_eq!(
+            runs[5],
+            TextRun {
+                text: " done".to_string(),
+                font: Some(0),
+                size: Some(24),
+                ..TextRun::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_extract_special_chars() {
+        let tokens = parse(
+            br#"{\rtf1 Wait\emdash {\ldblquote}What?\rdblquote\par\bullet\tab Page\~1\endash 2\line co\-op\_ish\page}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            extract_text_with(&tokens, &preserve()),
+            "Wait\u{2014}\u{201c}What?\u{201d}\n\u{2022}\tPage\u{a0}1\u{2013}2\nco\u{ad}op-ish\n"
+        );
+        let runs: String = extract_runs(&tokens)
+            .into_iter()
+            .map(|run| run.text)
+            .collect();
+        assert_eq!(runs, extract_text_with(&tokens, &preserve()));
+
+        let mut table = SpecialChars::default();
+        table.insert("line", '\u{2028}');
+        table.insert("page", '\u{c}');
+        table.insert("emdash", '-');
+        table.remove("-");
+        let options = TextOptions {
+            special_chars: Some(Arc::new(table)),
+            ..TextOptions::default()
+        };
+        assert_eq!(
+            extract_text_with(&tokens, &options),
+            "Wait-\u{201c}What?\u{201d}\n\u{2022}\tPage 1\u{2013}2\u{2028}coop-ish\u{c}"
+        );
+        let options = TextOptions {
+            special_chars: Some(Arc::new(SpecialChars::empty())),
+            ..TextOptions::default()
+        };
+        assert_eq!(
+            extract_text_with(&tokens, &options),
+            "WaitWhat?Page12coopish"
+        );
+    }
+
+    #[test]
+    fn test_extract_dynamic_text() {
+        let tokens = parse(
+            br#"{\rtf1\ansi{\fonttbl{\f0 Arial;}}{\footer\pard\qc Page \chpgn  - \chdate\par}\pard Body \chpgn\par}"#,
+        )
+        .unwrap();
+        let dynamic = |dynamic| {
+            let options = TextOptions {
+                dynamic,
+                headers_footers: true,
+                ..TextOptions::default()
+            };
+            extract_text_with(&tokens, &options)
+        };
+        assert_eq!(dynamic(DynamicText::Skip), "Page -\nBody\n");
+        assert_eq!(
+            dynamic(DynamicText::Placeholders),
+            "Page {PAGE} - {DATE}\nBody {PAGE}\n"
+        );
+        let values = DynamicValues::at(2, 0);
+        assert_eq!(values.long_date, "Thursday, January 1, 1970");
+        assert_eq!(values.time, "12:00 AM");
+        assert_eq!(
+            dynamic(DynamicText::Values(values)),
+            "Page 2 - 1/1/1970\nBody 2\n"
+        );
+        // Without the headers and footers, only the body's page number
+        let options = TextOptions {
+            dynamic: DynamicText::Placeholders,
+            ..TextOptions::default()
+        };
+        assert_eq!(extract_text_with(&tokens, &options), "Body {PAGE}\n");
+        assert_eq!(extract_text(&tokens), "Body\n");
+
+        assert_eq!(DynamicValues::at(1, 951_825_600).date, "2/29/2000");
+        assert_eq!(DynamicValues::at(1, -1).time, "11:59 PM");
+        assert_eq!(DynamicValues::at(1, -1).date, "12/31/1969");
+    }
+
+    #[test]
+    fn test_extract_hidden_text() {
+        let tokens = parse(
+            br#"{\rtf1 Total: {\v internal estimate }\u8364?5\v  (was 9)\v0  due\par {\v\plain Note}}"#,
+        )
+        .unwrap();
+        let hidden = |hidden| {
+            let options = TextOptions {
+                hidden,
+                ..TextOptions::default()
+            };
+            extract_text_with(&tokens, &options)
+        };
+        assert_eq!(
+            hidden(HiddenText::Include),
+            "Total: internal estimate \u{20ac}5 (was 9) due\nNote"
+        );
+        assert_eq!(hidden(HiddenText::Skip), "Total: \u{20ac}5 due\nNote");
+        assert_eq!(hidden(HiddenText::Only), "internal estimate (was 9)");
+
+        let runs: Vec<_> = extract_runs(&tokens)
+            .into_iter()
+            .map(|run| (run.text, run.hidden))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                ("Total: ".to_string(), false),
+                ("internal estimate ".to_string(), true),
+                ("\u{20ac}5".to_string(), false),
+                (" (was 9)".to_string(), true),
+                (" due\nNote".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_runs_bidi() {
+        let tokens = parse(br#"{\rtf1\ansi\deflang1033\adeflang1025{\fonttbl{\f0 Arial;}{\f1\fcharset177 Arial;}{\f2\fcharset178 Arial;}}\f0 Hi\par
+\pard\rtlpar{\rtlch\f1\alang1037 \'f9\'ec\'e5\'ed} {\rtlch\f2 \'e3\'d1}\par}"#).unwrap();
+        let runs = extract_runs(&tokens);
+        let runs: Vec<_> = runs
+            .iter()
+            .map(|run| {
+                (
+                    run.text.as_str(),
+                    run.language_tag(),
+                    run.rtl,
+                    run.rtl_paragraph,
+                )
+            })
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                ("Hi\n", Some("en-US"), false, false),
+                ("\u{5e9}\u{5dc}\u{5d5}\u{5dd}", Some("he-IL"), true, true),
+                (" ", Some("en-US"), false, true),
+                ("\u{645}\u{631}", Some("ar-SA"), true, true),
+                ("\n", Some("en-US"), false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_maps_symbol_fonts() {
+        let tokens = parse(
+            br#"{\rtf1{\fonttbl{\f0\froman Times;}{\f1\ftech\fcharset2{\*\panose 05050102010706020507}Symbol;}{\f2\fnil\fcharset2 Wingdings;}}
+{\f1\'b7} a{\f1 a\'b3b}{\f2\'fc}\f1 p\plain p}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            extract_text(&tokens),
+            "\u{2022} a\u{3b1}\u{2265}\u{3b2}\u{2714}\u{3c0}p"
+        );
+    }
+
+    #[test]
+    fn test_extract_old_dialects() {
+        // Mac and DOS documents are in their character set's code page
+        let tokens = parse(br"{\rtf1\mac Caf\'8e {\f1\'a5}}").unwrap();
+        assert_eq!(extract_text(&tokens), "Caf\u{e9} \u{2022}");
+        let tokens = parse(br"{\rtf1\pc Caf\'82}").unwrap();
+        assert_eq!(extract_text(&tokens), "Caf\u{e9}");
+        let tokens = parse(br"{\rtf1\pca\ansicpg1252 Caf\'e9}").unwrap();
+        assert_eq!(extract_text(&tokens), "Caf\u{e9}");
+        // Pocket Word documents read like any other
+        let tokens = parse(br"{\pwd2\ansi\deff0{\fonttbl{\f0 Tahoma;}}\pard Note\par}").unwrap();
+        assert_eq!(extract_text(&tokens), "Note\n");
+    }
+
+    #[test]
+    fn test_extract_uses_document_code_page() {
+        // Text with no explicit font is in the default font's character set
+        let tokens = parse(
+            br#"{\rtf1\ansi\ansicpg1251\deff0\deflang1049{\fonttbl{\f0\fnil\fcharset204 Arial;}{\f1\fnil\fcharset0 Arial;}}\'cf\'f0\'e8{\f1\'e9}}"#,
+        )
+        .unwrap();
+        assert_eq!(extract_text(&tokens), "\u{41f}\u{440}\u{438}\u{e9}");
+
+        // and with no default font, in the document's code page
+        let tokens = parse(br#"{\rtf1\ansi\ansicpg1251 \'cf{\f1\plain\'e8}}"#).unwrap();
+        assert_eq!(extract_text(&tokens), "\u{41f}\u{438}");
+    }
+
+    // A few characters of Shift-JIS, and Windows-1250 taken over
+    #[derive(Debug)]
+    struct ShiftJis;
+
+    impl CharsetDecoder for ShiftJis {
+        fn is_lead_byte(&self, code_page: u32, byte: u8) -> bool {
+            code_page == 932 && byte == 0x82
+        }
+
+        fn decode(&self, code_page: u32, bytes: &[u8]) -> Option<char> {
+            match (code_page, bytes) {
+                (932, [0x82, 0xa0]) => Some('\u{3042}'),
+                (932, [0x82, _]) => Some('\u{fffd}'),
+                (932, [byte]) if *byte < 0x80 => Some(*byte as char),
+                (1250, [0x8a]) => Some('S'),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_with_charset_decoder() {
+        let rtf = br#"{\rtf1\ansi{\fonttbl{\f0\fcharset128 MS Mincho;}{\f1\fcharset238 Arial;}{\f2\fcharset204 Arial;}}\f0 a\'82\'a0\'82 b\'82{\'41}{\f1\'8a\'9a}{\f2\'cf}}"#;
+        let tokens = parse(rtf).unwrap();
+        let options = TextOptions {
+            charset_decoder: Some(Arc::new(ShiftJis)),
+            ..preserve()
+        };
+        assert_eq!(
+            extract_text_with(&tokens, &options),
+            "a\u{3042}\u{fffd}bAS\u{161}\u{41f}"
+        );
+        // Without one, Shift-JIS is read as Windows-1252
+        #[cfg(not(feature = "encoding_rs"))]
+        assert_eq!(
+            extract_text_with(&tokens, &preserve()),
+            "a\u{201a}\u{a0}\u{201a} b\u{201a}A\u{160}\u{161}\u{41f}"
+        );
+    }
+
+    #[cfg(feature = "encoding_rs")]
+    #[test]
+    fn test_extract_double_byte_code_pages() {
+        let rtf = br#"{\rtf1\ansi\ansicpg932{\fonttbl{\f0\fcharset128 MS Mincho;}{\f1\fcharset134 SimSun;}{\f2\fcharset0 Arial;}}\f0 \'82\'a0\'82\'a2 {\f1\'c4\'e3}{\f2\'82\'a0}\f3\'93\'fa}"#;
+        let tokens = parse(rtf).unwrap();
+        assert_eq!(
+            extract_text_with(&tokens, &preserve()),
+            "\u{3042}\u{3044} \u{4f60}\u{201a}\u{a0}\u{65e5}"
+        );
+    }
+
+    #[test]
+    fn test_extract_uses_associated_fonts() {
+        let fonts =
+            br#"{\rtf1\ansi{\fonttbl{\f0 Times;}{\f1\fcharset204 Cyr;}{\f2\fcharset177 Heb;}}"#;
+        let cases: &[(&[u8], &str)] = &[
+            // The font of the character set chosen last
+            (br#"{\loch\f0\hich\af1 \'c0}"#, "\u{410}"),
+            (br#"{\hich\af1\dbch\af2\loch\f0 \'c0}"#, "\u{c0}"),
+            (br#"{\loch\af0\hich\af1\loch\f0 {\hich\'c0}}"#, "\u{410}"),
+            // The associated font of right-to-left text
+            (br#"{\ltrch\fcs0\f0\rtlch\fcs1\af2 \'e0}"#, "\u{5d0}"),
+            (br#"{\rtlch\fcs1\af2\ltrch\fcs0\f0 \'e0}"#, "\u{e0}"),
+            (br#"{\rtlch\af2\f1 \'e0}"#, "\u{5d0}"),
+            (br#"{\hich\af1\plain\'c0}"#, "\u{c0}"),
+        ];
+        for (rtf, expected) in cases {
+            let mut document = fonts.to_vec();
+            document.extend_from_slice(rtf);
+            document.push(b'}');
+            let tokens = parse(&document).unwrap();
+            assert_eq!(
+                extract_text(&tokens),
+                *expected,
+                "{}",
+                String::from_utf8_lossy(rtf)
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_skips_destinations() {
+        let tokens = parse(
+            br#"{\rtf1\ansi{\fonttbl{\f0 Times;}}{\*\generator Foo;}\f0 Hello {\b World}\par}"#,
+        )
+        .unwrap();
+        assert_eq!(extract_text(&tokens), "Hello World\n");
+    }
+
+    #[test]
+    fn test_extract_tables() {
+        let tokens = parse(
+            br#"{\rtf1 Before\par\trowd\cellx1000\cellx2000\intbl A\cell B\cell\row
+\trowd\cellx1000\cellx2000\intbl C\cell{\itap2 x\nestcell y\nestcell{\*\nesttableprops\trowd\nestrow}}\cell\row\pard After}"#,
+        )
+        .unwrap();
+        // The \nestrow ending a nested table's row is written in its
+        // properties, which are ignored
+        assert_eq!(extract_text(&tokens), "Before\nA\tB\t\nC\tx\ty\t\t\nAfter");
+        let options = TextOptions {
+            whitespace: Whitespace::Preserve,
+            ..TextOptions::default()
+        };
+        let tokens = parse(br#"{\rtf1 A\cell B\cell\row After}"#).unwrap();
+        assert_eq!(extract_text_with(&tokens, &options), "A\tB\t\nAfter");
+    }
+
+    #[test]
+    fn test_extract_skips_destinations_after_newlines() {
+        let tokens =
+            parse(b"{\\rtf1\\ansi {\r\n\\*\\generator x;}{\n\\*\r\n\\foo y}{\r\n\\info z}b}")
+                .unwrap();
+        assert!(tokens.contains(&Token::Newline));
+        assert_eq!(extract_text(&tokens), "b");
+        // As it is with the line breaks taken out
+        let minified = ::minify::minify(&tokens);
+        assert_eq!(extract_text(&minified), "b");
+    }
+
+    #[test]
+    fn test_extract_decodes_escapes() {
+        let tokens = parse(br#"{\rtf1 L\'e1szl\'f3 \'80 \{x\}\par}"#).unwrap();
+        assert_eq!(extract_text(&tokens), "L\u{e1}szl\u{f3} \u{20ac} {x}\n");
+    }
+
+    #[test]
+    fn test_extract_text_boxes() {
+        // The shape result repeats the text box for older readers
+        let tokens = parse(
+            br#"{\rtf1 See {\shp{\*\shpinst{\sp{\sn shapeType}{\sv 202}}{\shptxt Boxed\par}}{\shprslt{\*\do\dptxbx{\dptxbxtext Boxed}}}}here.{\*\do\dprect{\dptxbx{\dptxbxtext Old}}}{\header{\shp{\*\shpinst{\shptxt Head}}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(extract_text(&tokens), "See Boxed\nhere.Old");
+    }
+
+    #[test]
+    fn test_decoder_destinations() {
+        let tokens =
+            parse(b"{\\rtf1{\\*\\bkmkstart x}{\\field{\\*\\fldinst A}{\\fldrslt B}}}").unwrap();
+        let mut decoder = Decoder::new();
+        let mut seen = Vec::new();
+        for token in &tokens {
+            decoder.token(token);
+            seen.push((decoder.destination().map(String::from), decoder.ignored()));
+        }
+        let seen_at = |i: usize| (seen[i].0.as_deref(), seen[i].1);
+        assert_eq!(seen_at(1), (Some("rtf"), false));
+        assert_eq!(seen_at(5), (Some("bkmkstart"), true));
+        assert_eq!(seen_at(8), (Some("field"), false));
+        assert_eq!(seen_at(12), (Some("fldinst"), true));
+        assert_eq!(seen_at(16), (Some("fldrslt"), false));
+        assert_eq!(seen_at(17), (Some("field"), false));
+        assert_eq!(seen_at(18), (Some("rtf"), false));
+    }
+
+    #[test]
+    fn test_decoder_unicode_fallback() {
+        let tokens = parse(b"{\\uc2\\u8212\\'97\\'97x{\\uc0\\u-3913 y}\\u233 ?z}").unwrap();
+        let mut decoder = Decoder::new();
+        let text: String = tokens
+            .iter()
+            .filter_map(|token| match decoder.token(token) {
+                Decoded::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "\u{2014}x\u{f0b7}y\u{e9}");
+    }
+
+    #[test]
+    fn test_unicode_surrogate_pairs() {
+        let tokens = parse(b"{\\rtf1 \\u-10179?\\u-8704?}").unwrap();
+        assert_eq!(extract_text(&tokens), "\u{1f600}");
+        // A surrogate without its partner is replaced
+        let tokens =
+            parse(b"{\\rtf1 a\\u-10179?b\\u-8704?c\\u-10179?\\u-10179?\\u-8704?}").unwrap();
+        assert_eq!(
+            extract_text(&tokens),
+            "a\u{fffd}b\u{fffd}c\u{fffd}\u{1f600}"
+        );
+
+        // As the builder and the writer escape characters outside the Basic
+        // Multilingual Plane
+        let mut tokens = vec![Token::StartGroup, Token::word_arg("rtf", 1)];
+        tokens.extend(::builder::text_tokens("x\u{1f600}\u{1d11e}", 1));
+        tokens.push(Token::EndGroup);
+        assert_eq!(extract_text(&tokens), "x\u{1f600}\u{1d11e}");
+        let options = ::writer::WriteOptions {
+            non_ascii: ::writer::NonAsciiEscaping::Unicode,
+            ..::writer::WriteOptions::default()
+        };
+        let rtf = ::writer::write_rtf(
+            &[
+                Token::StartGroup,
+                Token::word_arg("rtf", 1),
+                Token::text("x\u{1f600}\u{1d11e}"),
+                Token::EndGroup,
+            ],
+            &options,
+        )
+        .unwrap();
+        assert_eq!(extract_text(&parse(&rtf).unwrap()), "x\u{1f600}\u{1d11e}");
+    }
+
+    #[test]
+    fn test_whitespace_normalize() {
+        let tokens = parse(b"{\\rtf1 a   b\\~\\~c  \\par   d\\tab e}").unwrap();
+        assert_eq!(extract_text(&tokens), "a b c\nd\te");
+    }
+
+    #[test]
+    fn test_whitespace_preserve() {
+        let tokens = parse(b"{\\rtf1 a   b\\~\\~c  \\par   d\\tab e}").unwrap();
+        assert_eq!(
+            extract_text_with(&tokens, &preserve()),
+            "a   b\u{a0}\u{a0}c  \n  d\te"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_preserve_ignores_newlines() {
+        let tokens = parse(b"{\\rtf1 int x  =\r\n 1;\\line   return x;}").unwrap();
+        assert_eq!(
+            extract_text_with(&tokens, &preserve()),
+            "int x  = 1;\n  return x;"
+        );
+    }
+}
@@ -8,38 +8,285 @@
 
 use std;
 use raw::{control_bin_raw, control_symbol_raw, control_word_hexbyte_raw, control_word_raw};
-use raw::{end_group_raw, newline_raw, rtf_text_raw, start_group_raw};
+use raw::{end_group_raw, newline_raw, rtf_text_raw, start_group_raw, ARG_OVERFLOW};
+use raw::{read_raw_token, RawToken};
+
+pub use name::Name;
+use header::document_start;
+use writer::{escape_non_ascii, escape_text, NonAsciiEscaping, TextEscaping};
 
 use nom;
 use nom::types::CompleteByteSlice as Input;
 
+/// A control word whose argument has more digits than
+/// `raw::MAX_ARG_DIGITS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumericOverflow {
+    /// The control word as written, such as `\fs99999999999`
+    pub text: String,
+    /// Offset of the control word in the input
+    pub offset: usize,
+}
+
+impl NumericOverflow {
+    /// The control word, with its argument saturated to the `i64` range
+    pub fn token(&self) -> Token {
+        let word = self.text.trim_start_matches('\\');
+        let name_len = word.bytes().take_while(|b| b.is_ascii_alphabetic()).count();
+        let arg = if word[name_len..].starts_with('-') {
+            i64::MIN
+        } else {
+            i64::MAX
+        };
+        control_word(&word[..name_len], Some(arg))
+    }
+}
+
+impl std::fmt::Display for NumericOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "numeric argument out of range at byte {}: {}",
+            self.offset, self.text
+        )
+    }
+}
+
+/// A control character in a run of text, which `ControlCharPolicy::Error`
+/// fails at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlCharacter {
+    /// The byte
+    pub byte: u8,
+    /// Offset of the byte in the input
+    pub offset: usize,
+}
+
+impl std::fmt::Display for ControlCharacter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "control character 0x{:02x} in text at byte {}",
+            self.byte, self.offset
+        )
+    }
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    Nom(nom::ErrorKind<u32>),
+    NumericOverflow(NumericOverflow),
+    ControlCharacter(ControlCharacter),
+    BudgetExceeded(usize),
+}
+
+impl ErrorKind {
+    // The part of the grammar that failed
+    fn rule(&self) -> &str {
+        match self {
+            ErrorKind::Nom(kind) => kind.description(),
+            ErrorKind::NumericOverflow(_) => "control word argument",
+            ErrorKind::ControlCharacter(_) => "text",
+            ErrorKind::BudgetExceeded(_) => "token budget",
+        }
+    }
+}
+
+// How many bytes either side of a failure an excerpt shows
+const EXCERPT_CONTEXT: usize = 16;
+
+// Bytes per line of an excerpt's hex dump
+const EXCERPT_WIDTH: usize = 16;
+
+/// The input around where tokenizing failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excerpt {
+    /// Offset of the failure in the input
+    pub offset: usize,
+    /// Offset in the input of the first byte of `bytes`
+    pub start: usize,
+    /// The input from `EXCERPT_CONTEXT` bytes before the failure to as many
+    /// after it, give or take the alignment of the hex dump's lines
+    pub bytes: Vec<u8>,
+}
+
+impl Excerpt {
+    fn new(input: &[u8], offset: usize) -> Self {
+        let start = offset.saturating_sub(EXCERPT_CONTEXT) / EXCERPT_WIDTH * EXCERPT_WIDTH;
+        let end = std::cmp::min(input.len(), offset + EXCERPT_CONTEXT + 1);
+        Excerpt {
+            offset,
+            start,
+            bytes: input[start.min(end)..end].to_vec(),
+        }
+    }
+}
+
+/// A hex dump of the excerpt, with a line under the failure pointing at it
+impl std::fmt::Display for Excerpt {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut line = self.start;
+        // The failure can be at the end of the input, past the last byte
+        let end = std::cmp::max(self.start + self.bytes.len(), self.offset + 1);
+        while line < end {
+            let bytes = self.bytes.get(line - self.start..).unwrap_or(&[]);
+            let bytes = &bytes[..std::cmp::min(bytes.len(), EXCERPT_WIDTH)];
+            if line != self.start {
+                writeln!(f)?;
+            }
+            write!(f, "{:08x} ", line)?;
+            for column in 0..EXCERPT_WIDTH {
+                match bytes.get(column) {
+                    Some(byte) => write!(f, " {:02x}", byte)?,
+                    None => write!(f, "   ")?,
+                }
+            }
+            let ascii: String = bytes
+                .iter()
+                .map(|&byte| match byte {
+                    b' '..=b'~' => byte as char,
+                    _ => '.',
+                })
+                .collect();
+            write!(f, "  |{}|", ascii)?;
+            if (line..line + EXCERPT_WIDTH).contains(&self.offset) {
+                let column = self.offset - line;
+                write!(f, "\n{:width$}^^", "", width = 10 + 3 * column)?;
+            }
+            line += EXCERPT_WIDTH;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct ParseError {
-    inner: nom::ErrorKind<u32>,
+    inner: ErrorKind,
+    excerpt: Option<Excerpt>,
+}
+
+impl ParseError {
+    fn new(inner: ErrorKind) -> Self {
+        ParseError {
+            inner,
+            excerpt: None,
+        }
+    }
+
+    // Show the input around `offset` with this error
+    pub(crate) fn at(mut self, input: &[u8], offset: usize) -> Self {
+        self.excerpt = Some(Excerpt::new(input, offset));
+        self
+    }
+
+    /// The input around where tokenizing failed, if it's known
+    pub fn excerpt(&self) -> Option<&Excerpt> {
+        self.excerpt.as_ref()
+    }
+
+    /// The overflowing argument that `parse_strict` stopped at, if that's
+    /// what this error is
+    pub fn numeric_overflow(&self) -> Option<&NumericOverflow> {
+        match self.inner {
+            ErrorKind::NumericOverflow(ref overflow) => Some(overflow),
+            _ => None,
+        }
+    }
+
+    /// The byte offset at which the token budget or deadline of the
+    /// `ParseOptions` was reached, if that's what this error is
+    pub fn budget_exceeded(&self) -> Option<usize> {
+        match self.inner {
+            ErrorKind::BudgetExceeded(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// The control character that `ControlCharPolicy::Error` stopped at, if
+    /// that's what this error is
+    pub fn control_character(&self) -> Option<&ControlCharacter> {
+        match self.inner {
+            ErrorKind::ControlCharacter(ref control) => Some(control),
+            _ => None,
+        }
+    }
 }
 
 impl<I> std::convert::From<nom::Err<I, u32>> for ParseError {
     fn from(error: nom::Err<I, u32>) -> Self {
-        Self {
-            inner: error.into_error_kind(),
-        }
+        Self::new(ErrorKind::Nom(error.into_error_kind()))
+    }
+}
+
+impl std::convert::From<NumericOverflow> for ParseError {
+    fn from(overflow: NumericOverflow) -> Self {
+        Self::new(ErrorKind::NumericOverflow(overflow))
     }
 }
 
+impl std::convert::From<ControlCharacter> for ParseError {
+    fn from(control: ControlCharacter) -> Self {
+        Self::new(ErrorKind::ControlCharacter(control))
+    }
+}
+
+/// The error, then where it was and what the input looked like there, when
+/// that's known:
+///
+/// ```text
+/// Parser Error: control character 0x00 in text at byte 8
+///   at byte 8, in text
+/// 00000000  7b 5c 72 74 66 31 20 61 00 62 7d                 |{\rtf1 a.b}|
+///                                   ^^
+/// ```
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Parser Error: {}", self.inner.description())
+        match self.inner {
+            ErrorKind::Nom(ref kind) => write!(f, "Parser Error: {}", kind.description())?,
+            ErrorKind::NumericOverflow(ref overflow) => write!(f, "Parser Error: {}", overflow)?,
+            ErrorKind::ControlCharacter(ref control) => write!(f, "Parser Error: {}", control)?,
+            ErrorKind::BudgetExceeded(offset) => {
+                write!(f, "Parser Error: budget exceeded at byte {}", offset)?
+            }
+        }
+        if let Some(ref excerpt) = self.excerpt {
+            write!(
+                f,
+                "\n  at byte {}, in {}\n{}",
+                excerpt.offset,
+                self.inner.rule(),
+                excerpt
+            )?;
+        }
+        Ok(())
     }
 }
 
+impl std::error::Error for ParseError {}
+
 type Result<T> = std::result::Result<T, ParseError>;
 
-#[derive(PartialEq)]
+/// New kinds of token may be added in minor releases, so matches on tokens
+/// outside this crate need a wildcard arm
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Token {
     ControlSymbol(char),
     ControlWord {
-        name: String,
-        arg: Option<i32>,
+        name: Name,
+        /// Arguments are 16 or 32 bit values in the spec, but some writers
+        /// emit larger ones, such as unsigned 32 bit `\revdttm` timestamps
+        arg: Option<i64>,
+    },
+    /// A hex escape, `\'xx`: a byte of text in the document's code page
+    HexByte(u8),
+    /// A Unicode escape, `\uN`, which is followed by `\ucN` fallback
+    /// characters for readers that don't understand it
+    Unicode {
+        /// The code point, or UTF-16 code unit for characters outside the
+        /// Basic Multilingual Plane; the negative arguments that stand for
+        /// values above 32767 are converted
+        codepoint: i32,
     },
     ControlBin(Vec<u8>),
     /// Text is not str because it can be in any of various encodings -
@@ -61,6 +308,8 @@ impl std::fmt::Debug for Token {
                 name,
                 arg.map(|i| format!(":{}", i)).unwrap_or_default()
             ),
+            Token::HexByte(byte) => write!(f, "Token::HexByte({:02x})", byte),
+            Token::Unicode { codepoint } => write!(f, "Token::Unicode({})", codepoint),
             Token::ControlBin(data) => {
                 write!(f, "Token::ControlBin(")?;
                 for byte in data {
@@ -82,24 +331,111 @@ impl std::fmt::Debug for Token {
     }
 }
 
+/// Shows tokens the way they're written in RTF, for diagnostics: `\fs24`,
+/// `\'e9`, `{`.  Text is quoted, and binary data and line breaks, which
+/// don't print usefully, are described instead.
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::ControlSymbol(c) => write!(f, "\\{}", c),
+            Token::ControlWord { name, arg } => match arg {
+                Some(num) => write!(f, "\\{}{}", name, num),
+                None => write!(f, "\\{}", name),
+            },
+            Token::HexByte(byte) => write!(f, "\\'{:02x}", byte),
+            Token::Unicode { codepoint } => write!(f, "\\u{}", unicode_arg(*codepoint)),
+            Token::ControlBin(data) => {
+                write!(f, "\\bin{} <{} bytes of data>", data.len(), data.len())
+            }
+            Token::Text(data) => write!(f, "\"{}\"", String::from_utf8_lossy(data).escape_debug()),
+            Token::StartGroup => write!(f, "{{"),
+            Token::EndGroup => write!(f, "}}"),
+            Token::Newline => write!(f, "<newline>"),
+        }
+    }
+}
+
+/// The error from parsing a `Token` from a string that isn't a single control
+/// word, control symbol or group delimiter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTokenError {
+    input: String,
+}
+
+impl std::fmt::Display for ParseTokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "not a control word, control symbol or group delimiter: {:?}",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseTokenError {}
+
+/// Parses the simple forms of token: a control word like `\fs24` or
+/// `\'e9`, a control symbol like `\~`, or `{` or `}`
+impl std::str::FromStr for Token {
+    type Err = ParseTokenError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || ParseTokenError {
+            input: s.to_string(),
+        };
+        let mut tokens = parse(s.as_bytes()).map_err(|_| error())?;
+        match tokens.pop() {
+            Some(token) if tokens.is_empty() => match token {
+                Token::ControlWord { .. }
+                | Token::HexByte(_)
+                | Token::Unicode { .. }
+                | Token::ControlSymbol(_)
+                | Token::StartGroup
+                | Token::EndGroup => Ok(token),
+                _ => Err(error()),
+            },
+            _ => Err(error()),
+        }
+    }
+}
+
 impl Token {
+    /// Serialize this token, escaping any `{`, `}` or `\` in text payloads
+    ///
+    /// See `writer::write_rtf` for serializing whole token streams, with
+    /// control over the escaping policy.
     pub fn to_rtf(&self) -> Vec<u8> {
+        self.to_rtf_escaped(NonAsciiEscaping::Verbatim)
+    }
+
+    /// Serialize this token, escaping any `{`, `}` or `\` in text payloads,
+    /// and bytes outside ASCII as `non_ascii` says
+    pub fn to_rtf_escaped(&self, non_ascii: NonAsciiEscaping) -> Vec<u8> {
         match self {
+            // The tokenizer reads a symbol's byte as the character with that
+            // code, so it's written back as that byte
+            Token::ControlSymbol(c) if (*c as u32) < 0x100 => vec![b'\\', *c as u8],
             Token::ControlSymbol(c) => format!("\\{}", c).as_bytes().to_vec(),
             Token::ControlWord { name, arg } => match arg {
                 Some(num) => format!("\\{}{}", name, num).as_bytes().to_vec(),
                 None => format!("\\{}", name).as_bytes().to_vec(),
             },
+            Token::HexByte(byte) => format!("\\'{:02x}", byte).as_bytes().to_vec(),
+            Token::Unicode { codepoint } => format!("\\u{}", unicode_arg(*codepoint))
+                .as_bytes()
+                .to_vec(),
             Token::ControlBin(data) => {
                 let mut rtf: Vec<u8> = Vec::with_capacity(12 + data.len());
                 rtf.extend_from_slice(format!("\\bin{} ", data.len()).as_bytes());
                 rtf.extend_from_slice(data);
                 rtf
             }
-            Token::Text(data) => data.to_vec(),
+            Token::Text(data) => escape_text(data, TextEscaping::Escape)
+                .map(|text| escape_non_ascii(&text, non_ascii).into_owned())
+                .unwrap_or_default(),
             Token::StartGroup => b"{".to_vec(),
             Token::EndGroup => b"}".to_vec(),
-            Token::Newline => b"\\r\\n".to_vec(),
+            Token::Newline => b"\r\n".to_vec(),
         }
     }
 
@@ -110,14 +446,25 @@ impl Token {
     /// if the subsequent content could be alphanumeric, a space (' ') delimiter
     /// must be inserted
     pub fn token_delimiter_after(&self, next_token: &Token) -> &'static str {
-        if let Token::ControlWord { .. } = self {
-            // TODO: actually check the content of Text to see if a space is needed
-            // it's safe to be lazy here, but less efficient
-            if let Token::Text(_) = next_token {
-                return " ";
+        let (arg, text) = match (self, next_token) {
+            (Token::ControlWord { arg, .. }, Token::Text(text)) => (*arg, text),
+            (Token::Unicode { codepoint }, Token::Text(text)) => {
+                (Some(i64::from(*codepoint)), text)
             }
+            _ => return "",
+        };
+        // Text would otherwise be read as more of the name or argument, or
+        // in the case of a space, as the delimiter itself
+        let needed = match text.first() {
+            Some(b) if b.is_ascii_alphanumeric() || *b == b' ' => true,
+            Some(b'-') => arg.is_none(),
+            _ => false,
+        };
+        if needed {
+            " "
+        } else {
+            ""
         }
-        ""
     }
 
     /// This function returns a control word delimiter if one is required, or an
@@ -130,15 +477,53 @@ impl Token {
         prev_token.token_delimiter_after(self)
     }
 
+    /// A control word without an argument, like `\par`
+    pub fn word<N: Into<Name>>(name: N) -> Self {
+        Token::ControlWord {
+            name: name.into(),
+            arg: None,
+        }
+    }
+
+    /// A control word with an argument, like `\fs24`
+    pub fn word_arg<N: Into<Name>>(name: N, arg: i64) -> Self {
+        Token::ControlWord {
+            name: name.into(),
+            arg: Some(arg),
+        }
+    }
+
+    /// Text, unescaped
+    pub fn text<T: Into<Vec<u8>>>(text: T) -> Self {
+        Token::Text(text.into())
+    }
+
+    /// Whether this is the control word `name`, with or without an argument
+    pub fn is_word(&self, name: &str) -> bool {
+        match self {
+            Token::ControlWord { name: word, .. } => word == name,
+            _ => false,
+        }
+    }
+
+    /// The argument of a control word, or `default` if it has none or this
+    /// isn't a control word
+    ///
+    /// Toggles like `\b` are on when they have no argument, so
+    /// `word_arg_or(1) != 0` tells whether one turns its property on.
+    pub fn word_arg_or(&self, default: i64) -> i64 {
+        self.get_arg().unwrap_or(default)
+    }
+
     pub fn get_name(&self) -> Option<String> {
         if let Token::ControlWord { ref name, .. } = self {
-            Some(name.clone())
+            Some(name.to_string())
         } else {
             None
         }
     }
 
-    pub fn get_arg(&self) -> Option<i32> {
+    pub fn get_arg(&self) -> Option<i64> {
         if let Token::ControlWord { ref arg, .. } = self {
             *arg
         } else {
@@ -171,22 +556,29 @@ impl Token {
     }
 }
 
-// Ordering here is important. Plain text is all content that isn't something else:
-// If the next unparsed character is anything other than an opening brace ({), closing brace (}),
-// backslash (\), or a CRLF (carriage return/line feed), the reader assumes that the character is
-// plain text and writes the character to the current destination using the current formatting
-// properties.  Finally, a control hexbyte is a special case of a control symbol, but needs to be
-// handled specially, so hexbyte should be tested for before control symbols.
-//
-// See section "Conventions of an RTF Reader" in the RTF specification.
+// The token of what the raw parsers read
+fn raw_token(raw: RawToken) -> Token {
+    match raw {
+        RawToken::HexByte(byte) => Token::HexByte(byte),
+        RawToken::Symbol(c) => Token::ControlSymbol(c),
+        RawToken::Bin(bytes) => Token::ControlBin(bytes.to_vec()),
+        RawToken::Word(name, arg) => control_word(name, arg),
+        RawToken::StartGroup => Token::StartGroup,
+        RawToken::EndGroup => Token::EndGroup,
+        RawToken::Newline => Token::Newline,
+        RawToken::Text(text_bytes) => Token::Text(text_bytes.to_vec()),
+    }
+}
+
+// The alternatives, and the order they're tried in, are `raw::read_raw_token`'s
 named!(pub read_token<Input, Token>,
-    alt!(read_control_hexbyte | read_control_symbol | read_control_bin | read_control_word | read_start_group | read_end_group | read_newline | read_rtf_text)
+    map!(read_raw_token, raw_token)
 );
 
 named!(pub read_control_hexbyte<Input, Token>,
     map!(
         control_word_hexbyte_raw,
-        |(name, arg)| Token::ControlWord { name: String::from(name), arg }
+        Token::HexByte
     )
 );
 
@@ -200,7 +592,7 @@ named!(pub read_control_symbol<Input, Token>,
 named!(pub read_control_word<Input, Token>,
     map!(
         control_word_raw,
-        |(name, arg)| Token::ControlWord { name: String::from(name), arg }
+        |(name, arg)| control_word(name, arg)
     )
 );
 
@@ -241,15 +633,699 @@ named!(pub read_rtf_text<Input, Token>,
 
 named!(pub read_token_stream<Input, Vec<Token> >, many0!(read_token));
 
+/// Why `parse_partial` stopped before the end of a document, and the byte
+/// offset where it did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Incomplete {
+    /// The document was cut off
+    TruncatedAt(usize),
+    /// `ParseOptions::max_tokens` or `ParseOptions::deadline` was reached
+    BudgetExceeded(usize),
+}
+
+// Some tokens can't be told apart from a prefix of a longer token when the
+// data ends after them:
+//  * a \bin control word that couldn't read all of its data is parsed as a
+//    plain control word
+//  * a control word followed by a lone '-' may be the start of a negative
+//    argument
+pub(crate) fn is_partial(token: &Token, rest: &[u8]) -> bool {
+    match token {
+        Token::ControlWord { name, arg } => {
+            (name == "bin" && arg.is_some()) || (arg.is_none() && rest == b"-")
+        }
+        _ => false,
+    }
+}
+
+// Whether a token read from the end of a document was cut off: a partial
+// token, or a control word without the delimiter that ends it
+fn is_cut_off(token: &Token, rest: &[u8]) -> bool {
+    let word = matches!(token, Token::ControlWord { .. } | Token::Unicode { .. });
+    (word && rest.is_empty()) || is_partial(token, rest)
+}
+
+// Read the next token of `bytes`, starting at `input`, or None at the end of
+// what can be tokenized.  A control word whose argument overflows is read
+// with the argument saturated, and the overflow returned alongside it.
+pub(crate) fn next_token<'a>(
+    bytes: &[u8],
+    input: Input<'a>,
+) -> Result<Option<(Input<'a>, Token, Option<NumericOverflow>)>> {
+    match read_token(input) {
+        Ok((rest, _)) if rest.len() == input.len() => Ok(None),
+        Ok((rest, token)) => Ok(Some((rest, token, None))),
+        Err(nom::Err::Failure(nom::Context::Code(rest, nom::ErrorKind::Custom(ARG_OVERFLOW)))) => {
+            let len = input.len() - rest.len();
+            let overflow = NumericOverflow {
+                text: String::from_utf8_lossy(&input[..len]).into_owned(),
+                offset: bytes.len() - input.len(),
+            };
+            // The overflowing argument still ends the control word, so a
+            // delimiting space belongs to it
+            let rest = match rest.0.split_first() {
+                Some((b' ', after)) => Input(after),
+                _ => rest,
+            };
+            let token = overflow.token();
+            Ok(Some((rest, token, Some(overflow))))
+        }
+        Err(nom::Err::Error(_)) => Ok(None),
+        Err(error) => Err(ParseError::from(error).at(bytes, bytes.len() - input.len())),
+    }
+}
+
+/// What the tokenizer does with line breaks
+///
+/// Line breaks in RTF don't mean anything (readers are to ignore CR and LF),
+/// so most consumers have no use for `Token::Newline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum NewlinePolicy {
+    /// Emit a `Token::Newline` for each line break
+    #[default]
+    Emit,
+    /// Leave line breaks out of the token stream
+    Drop,
+    /// Leave line breaks out, and join the text on either side of one into a
+    /// single token, so that text wrapped across lines reads as one run
+    Merge,
+}
+
+/// What the tokenizer does with control characters in runs of text
+///
+/// The spec neither allows nor forbids the C0 control characters, 0x00 to
+/// 0x1F, in text, and documents do contain them, NULs especially.  Tabs are
+/// left alone, and line breaks are always read as `Token::Newline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ControlCharPolicy {
+    /// Leave them in the text
+    #[default]
+    Keep,
+    /// Leave them out of the text
+    Strip,
+    /// Replace each with a `Token::Unicode` of U+FFFD REPLACEMENT CHARACTER,
+    /// followed by `?` fallback characters for the `\ucN` in effect
+    Replace,
+    /// Fail at the first one, with an error whose `control_character` says
+    /// where it was
+    Error,
+}
+
+/// Tokenizer options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub newlines: NewlinePolicy,
+    /// Fail at the first control word argument with more than
+    /// `raw::MAX_ARG_DIGITS` digits, rather than saturating it
+    pub strict: bool,
+    /// Join text split up by line breaks and hex escapes into single tokens,
+    /// as `coalesce_text` does
+    pub coalesce_text: bool,
+    /// Leave out the fallback characters after each `Token::Unicode`, as
+    /// many as the `\ucN` in effect says
+    ///
+    /// The tokens then no longer describe the same document to a reader that
+    /// skips fallback characters itself, which includes `write_rtf` and the
+    /// decoders in this crate, so this is for consumers that want the text
+    /// as the tokens give it.
+    pub skip_unicode_fallback: bool,
+    /// Skip anything before the document's opening brace and after the
+    /// brace that closes it, rather than reading it as text; see
+    /// `header::find_junk` to find out what that was
+    pub skip_junk: bool,
+    /// Read each run of consecutive hex escapes as one `Token::Text` of the
+    /// bytes they stand for, rather than a `Token::HexByte` apiece
+    ///
+    /// Documents in East Asian code pages are mostly hex escapes, and read
+    /// this way come out as a fraction of the tokens.  Readers decode the
+    /// bytes just as they would the escapes.
+    pub batch_hex: bool,
+    /// What to do with control characters in runs of text
+    pub control_chars: ControlCharPolicy,
+    /// Stop after reading this many tokens
+    pub max_tokens: Option<usize>,
+    /// Stop once this time has passed, which is checked every thousand or so
+    /// tokens
+    pub deadline: Option<std::time::Instant>,
+}
+
+// Tracks the \uc value in effect through groups
+#[derive(Debug)]
+struct UcTracker {
+    stack: Vec<usize>,
+    uc: usize,
+}
+
+impl UcTracker {
+    fn new() -> Self {
+        // The spec's default for \uc
+        UcTracker {
+            stack: Vec::new(),
+            uc: 1,
+        }
+    }
+
+    fn token(&mut self, token: &Token) {
+        match token {
+            Token::StartGroup => self.stack.push(self.uc),
+            Token::EndGroup => {
+                if let Some(uc) = self.stack.pop() {
+                    self.uc = uc;
+                }
+            }
+            Token::ControlWord {
+                name,
+                arg: Some(uc),
+            } if name == "uc" => self.uc = std::cmp::max(narrow(*uc), 0) as usize,
+            _ => {}
+        }
+    }
+}
+
+// Drops the fallback characters after each \u, tracking \uc through groups
+#[derive(Debug)]
+struct FallbackSkipper {
+    uc: UcTracker,
+    // Fallback characters still to be skipped
+    pending: usize,
+}
+
+impl FallbackSkipper {
+    fn new() -> Self {
+        FallbackSkipper {
+            uc: UcTracker::new(),
+            pending: 0,
+        }
+    }
+
+    fn token(&mut self, token: Token) -> Option<Token> {
+        self.uc.token(&token);
+        match token {
+            // A fallback can't extend past the end of its group
+            Token::StartGroup | Token::EndGroup => self.pending = 0,
+            Token::Unicode { .. } => self.pending = self.uc.uc,
+            Token::ControlWord {
+                ref name,
+                arg: Some(_),
+            } if name == "uc" => {}
+            Token::Newline => {}
+            Token::Text(mut text) if self.pending > 0 => {
+                // Each byte of text is a character
+                let skip = std::cmp::min(self.pending, text.len());
+                self.pending -= skip;
+                text.drain(..skip);
+                return if text.is_empty() {
+                    None
+                } else {
+                    Some(Token::Text(text))
+                };
+            }
+            // Hex escapes, control symbols, \bin data and other control words
+            // each count as one
+            _ if self.pending > 0 => {
+                self.pending -= 1;
+                return None;
+            }
+            _ => {}
+        }
+        Some(token)
+    }
+}
+
+// The bytes a token stands for in a run of text, if it's text or a hex escape
+fn text_bytes(token: &Token) -> Option<Vec<u8>> {
+    match token {
+        Token::Text(data) => Some(data.clone()),
+        Token::HexByte(byte) => Some(vec![*byte]),
+        _ => None,
+    }
+}
+
+// Push a token, joining it to the text before it, past any line breaks, if
+// both are part of one run of text
+fn push_coalesced(tokens: &mut Vec<Token>, token: Token) {
+    let data = match text_bytes(&token) {
+        Some(data) => data,
+        None => return tokens.push(token),
+    };
+    let last = tokens.iter().rposition(|token| *token != Token::Newline);
+    if let Some(last) = last {
+        if let Token::Text(_) = tokens[last] {
+            tokens.truncate(last + 1);
+        }
+        if let Some(Token::Text(text)) = tokens.last_mut() {
+            text.extend_from_slice(&data);
+            return;
+        }
+    }
+    tokens.push(Token::Text(data));
+}
+
+/// Join each run of text that's split up by line breaks and hex escapes
+/// into a single `Token::Text`
+///
+/// Hex escapes become the bytes they stand for, which readers decode just as
+/// they would the escape, and line breaks inside a run are dropped.  A
+/// document with accented text in every word otherwise comes out as a token
+/// or two per character.
+pub fn coalesce_text(tokens: &[Token]) -> Vec<Token> {
+    let mut coalesced = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        push_coalesced(&mut coalesced, token.clone());
+    }
+    coalesced
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+}
+
+// Read a run of consecutive hex escapes, \'xx\'xx..., as a text token of the
+// bytes they stand for
+fn read_hex_run(input: Input) -> Option<(Input, Token)> {
+    let mut data = Vec::new();
+    let mut rest = input.0;
+    while let [b'\\', b'\'', high, low, after @ ..] = rest {
+        match (hex_digit(*high), hex_digit(*low)) {
+            (Some(high), Some(low)) => data.push(high << 4 | low),
+            _ => break,
+        }
+        rest = after;
+    }
+    if data.is_empty() {
+        None
+    } else {
+        Some((Input(rest), Token::Text(data)))
+    }
+}
+
+// Push a token read from a document, as `options` say
+fn push_token(
+    tokens: &mut Vec<Token>,
+    skipper: &mut FallbackSkipper,
+    options: &ParseOptions,
+    token: Token,
+) {
+    let token = if options.skip_unicode_fallback {
+        match skipper.token(token) {
+            Some(token) => token,
+            None => return,
+        }
+    } else {
+        token
+    };
+    match (options.newlines, token, tokens.last_mut()) {
+        (NewlinePolicy::Drop, Token::Newline, _) | (NewlinePolicy::Merge, Token::Newline, _) => {}
+        // Text only follows text when there were line breaks between them
+        (NewlinePolicy::Merge, Token::Text(text), Some(Token::Text(last))) => {
+            last.extend_from_slice(&text)
+        }
+        (_, token, _) if options.coalesce_text => push_coalesced(tokens, token),
+        (_, token, _) => tokens.push(token),
+    }
+}
+
+fn is_control_char(byte: u8) -> bool {
+    byte < 0x20 && byte != b'\t'
+}
+
+// Apply the control character policy to a run of text starting at `offset`,
+// returning the tokens it becomes
+fn control_chars(
+    text: Vec<u8>,
+    offset: usize,
+    uc: &UcTracker,
+    policy: ControlCharPolicy,
+) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut run = Vec::with_capacity(text.len());
+    for (index, byte) in text.into_iter().enumerate() {
+        if !is_control_char(byte) {
+            run.push(byte);
+            continue;
+        }
+        match policy {
+            ControlCharPolicy::Keep => run.push(byte),
+            ControlCharPolicy::Strip => {}
+            ControlCharPolicy::Replace => {
+                if !run.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut run)));
+                }
+                tokens.push(Token::Unicode { codepoint: 0xfffd });
+                run.resize(uc.uc, b'?');
+            }
+            ControlCharPolicy::Error => {
+                return Err(ControlCharacter {
+                    byte,
+                    offset: offset + index,
+                }
+                .into())
+            }
+        }
+    }
+    if !run.is_empty() {
+        tokens.push(Token::Text(run));
+    }
+    Ok(tokens)
+}
+
+// How many tokens to read between looking at the clock
+const DEADLINE_INTERVAL: usize = 1024;
+
+fn budget_reached(options: &ParseOptions, count: usize) -> bool {
+    options.max_tokens.is_some_and(|max| count >= max)
+        || options.deadline.is_some_and(|deadline| {
+            count % DEADLINE_INTERVAL == 0 && std::time::Instant::now() >= deadline
+        })
+}
+
+// Tokenize a document, and find where it was cut off, if it was.  Tokens
+// that were cut off are only left out when `partial` is set.
+fn read_tokens(
+    bytes: &[u8],
+    options: &ParseOptions,
+    partial: bool,
+) -> Result<(Vec<Token>, Option<Incomplete>)> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut skipper = FallbackSkipper::new();
+    let start = if options.skip_junk {
+        document_start(bytes)
+    } else {
+        0
+    };
+    let mut input = Input(&bytes[start..]);
+    let mut depth = 0usize;
+    let mut closed = false;
+    let mut incomplete = None;
+    let mut uc = UcTracker::new();
+    let mut count = 0;
+    loop {
+        if !input.is_empty() && budget_reached(options, count) {
+            incomplete = Some(Incomplete::BudgetExceeded(bytes.len() - input.len()));
+            break;
+        }
+        count += 1;
+        let hex_run = if options.batch_hex {
+            read_hex_run(input)
+        } else {
+            None
+        };
+        let offset = bytes.len() - input.len();
+        let from_hex = hex_run.is_some();
+        let (rest, token, overflow) = match hex_run {
+            Some((rest, token)) => (rest, token, None),
+            None => match next_token(bytes, input)? {
+                Some(next) => next,
+                None => {
+                    if !input.is_empty() {
+                        incomplete = Some(Incomplete::TruncatedAt(offset));
+                    }
+                    break;
+                }
+            },
+        };
+        if partial && is_cut_off(&token, &rest) {
+            incomplete = Some(Incomplete::TruncatedAt(offset));
+            break;
+        }
+        input = rest;
+        if let (true, Some(overflow)) = (options.strict, overflow) {
+            return Err(ParseError::from(overflow).at(bytes, offset));
+        }
+        match token {
+            Token::StartGroup => depth += 1,
+            Token::EndGroup => {
+                closed = options.skip_junk && depth == 1;
+                depth = depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+        uc.token(&token);
+        match token {
+            Token::Text(text)
+                if !from_hex
+                    && options.control_chars != ControlCharPolicy::Keep
+                    && text.iter().any(|byte| is_control_char(*byte)) =>
+            {
+                let replaced =
+                    control_chars(text, offset, &uc, options.control_chars).map_err(|error| {
+                        let at = error.control_character().map_or(offset, |c| c.offset);
+                        error.at(bytes, at)
+                    })?;
+                for token in replaced {
+                    push_token(&mut tokens, &mut skipper, options, token);
+                }
+            }
+            token => push_token(&mut tokens, &mut skipper, options, token),
+        }
+        if closed {
+            break;
+        }
+    }
+    // Groups left open mean the document ended early, even if it ended
+    // between tokens
+    if incomplete.is_none() && depth > 0 {
+        incomplete = Some(Incomplete::TruncatedAt(bytes.len()));
+    }
+    Ok((tokens, incomplete))
+}
+
+/// Tokenize a document, as `options` say
+///
+/// Fails if the budget the options set is reached; see `parse_partial` for
+/// the tokens read until then.
+pub fn parse_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Vec<Token>> {
+    match read_tokens(bytes, options, false)? {
+        (_, Some(Incomplete::BudgetExceeded(offset))) => {
+            Err(ParseError::new(ErrorKind::BudgetExceeded(offset)).at(bytes, offset))
+        }
+        (tokens, _) => Ok(tokens),
+    }
+}
+
+/// Tokenize a document that may have been cut off, as `options` say
+///
+/// Documents truncated by mail size limits and the like end part way through
+/// a control word, a hex escape or the data of a `\bin`.  Rather than read
+/// what's left of those as something else, this returns the tokens before
+/// them, and where the document was cut off.  The tokenizer also stops at a
+/// control sequence it can't read anywhere else in a document, and that's
+/// reported the same way, as is a document that ends with groups still
+/// open.  A complete document is tokenized just as `parse_with_options`
+/// does, with no `Incomplete`.
+///
+/// Reaching the budget the options set isn't an error either: the tokens
+/// read until then are returned, with `Incomplete::BudgetExceeded`.
+pub fn parse_partial(
+    bytes: &[u8],
+    options: &ParseOptions,
+) -> Result<(Vec<Token>, Option<Incomplete>)> {
+    read_tokens(bytes, options, true)
+}
+
+/// Tokenize a document
+///
+/// A control word argument with more than `raw::MAX_ARG_DIGITS` digits is
+/// saturated to `i64::MAX` or `i64::MIN`, rather than failing the whole
+/// document; see `parse_strict` to catch those.
 pub fn parse(bytes: &[u8]) -> Result<Vec<Token>> {
-    read_token_stream(Input(bytes))
-        .map_err(ParseError::from)
-        .map(|(_, tokens)| tokens)
+    parse_with_options(bytes, &ParseOptions::default())
+}
+
+/// Tokenize a document, failing at the first control word argument with
+/// more than `raw::MAX_ARG_DIGITS` digits
+///
+/// The error's `numeric_overflow` says where it was.
+pub fn parse_strict(bytes: &[u8]) -> Result<Vec<Token>> {
+    let options = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    parse_with_options(bytes, &options)
+}
+
+/// A control word argument as the 32 bit value the document model uses
+///
+/// Values up to `u32::MAX` are packed 32 bit fields written unsigned, like
+/// `\revdttm` timestamps, and keep their bits; anything further out of range
+/// saturates.
+pub(crate) fn narrow(arg: i64) -> i32 {
+    if arg > i64::from(i32::MAX) && arg <= i64::from(u32::MAX) {
+        arg as u32 as i32
+    } else {
+        arg.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+    }
+}
+
+pub(crate) fn narrow_arg(arg: Option<i64>) -> Option<i32> {
+    arg.map(narrow)
+}
+
+// The token for a control word read from a document: a `Token::Unicode` for
+// `\uN`, and a `Token::ControlWord` for anything else
+pub(crate) fn control_word(name: &str, arg: Option<i64>) -> Token {
+    match arg {
+        Some(arg) if name == "u" => {
+            // Values above 32767 are written as negative numbers
+            let codepoint = match narrow(arg) {
+                codepoint @ -32768..=-1 => codepoint + 65536,
+                codepoint => codepoint,
+            };
+            Token::Unicode { codepoint }
+        }
+        _ => Token::ControlWord {
+            name: Name::new(name),
+            arg,
+        },
+    }
+}
+
+// The argument `\u` is written with for a code point: a signed 16 bit value
+fn unicode_arg(codepoint: i32) -> i32 {
+    match codepoint {
+        32768..=65535 => codepoint - 65536,
+        _ => codepoint,
+    }
+}
+
+/// Index of the token after the group starting at `tokens[start]`, or the
+/// end of the tokens if the group is never closed
+pub(crate) fn group_end(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::StartGroup => depth += 1,
+            Token::EndGroup => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// The destination named at the start of the group starting at
+/// `tokens[start]`: its name, whether it was starred, and the index of the
+/// control word naming it
+pub(crate) fn group_destination(tokens: &[Token], start: usize) -> Option<(&str, bool, usize)> {
+    let mut rest = tokens
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .filter(|(_, token)| **token != Token::Newline);
+    match rest.next()? {
+        (_, Token::ControlSymbol('*')) => match rest.next()? {
+            (index, Token::ControlWord { name, .. }) => Some((name.as_str(), true, index)),
+            _ => None,
+        },
+        (index, Token::ControlWord { name, .. }) => Some((name.as_str(), false, index)),
+        _ => None,
+    }
+}
+
+/// The first group directly inside `tokens[start..end]`, a group itself,
+/// whose destination is `name`: its start, the index of the word naming it,
+/// and its end
+pub(crate) fn child_group(
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+    name: &str,
+) -> Option<(usize, usize, usize)> {
+    let mut index = start + 1;
+    while index < end {
+        if tokens[index] == Token::StartGroup {
+            let child_end = group_end(tokens, index);
+            if let Some((child, _, word)) = group_destination(tokens, index) {
+                if child == name {
+                    return Some((index, word, child_end));
+                }
+            }
+            index = child_end;
+        } else {
+            index += 1;
+        }
+    }
+    None
+}
+
+/// The contents of a group after the word naming it, given the index of
+/// the word and the group's end
+pub(crate) fn group_contents(tokens: &[Token], word: usize, end: usize) -> &[Token] {
+    match tokens[end - 1] {
+        Token::EndGroup if end - 1 > word => &tokens[word + 1..end - 1],
+        _ => &tokens[word + 1..end],
+    }
+}
+
+/// A token, along with the range of input bytes it was read from
+#[derive(Debug, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: std::ops::Range<usize>,
+    /// Whether the token is a control word that consumed a delimiting
+    /// space, which `writer::write_spanned_rtf` writes back
+    pub trailing_space: bool,
+}
+
+impl SpannedToken {
+    pub(crate) fn new(bytes: &[u8], token: Token, span: std::ops::Range<usize>) -> Self {
+        let trailing_space = match token {
+            Token::ControlWord { .. } | Token::Unicode { .. } => bytes[span.end - 1] == b' ',
+            _ => false,
+        };
+        SpannedToken {
+            token,
+            span,
+            trailing_space,
+        }
+    }
+}
+
+/// Tokenize a document, recording where in the input each token came from
+///
+/// A control word's span includes the delimiting space it consumed, if any.
+pub fn parse_spanned(bytes: &[u8]) -> Result<Vec<SpannedToken>> {
+    let mut tokens = Vec::new();
+    let mut input = Input(bytes);
+    while let Some((rest, token, _)) = next_token(bytes, input)? {
+        let start = bytes.len() - input.len();
+        let end = bytes.len() - rest.len();
+        tokens.push(SpannedToken::new(bytes, token, start..end));
+        input = rest;
+    }
+    Ok(tokens)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use text::extract_text;
+
+    #[test]
+    fn test_read_token_consumes_nothing() {
+        // What can't be read is matched as empty text, which ends the
+        // tokenizer's loop rather than spinning it
+        for (bytes, read) in &[(&b"\\"[..], 0), (b"\\'zz", 0), (b"ab\\'zz", 1)] {
+            let mut input = Input(bytes);
+            let mut tokens = 0;
+            while let Some((rest, _, _)) = next_token(bytes, input).unwrap() {
+                assert!(rest.len() < input.len());
+                input = rest;
+                tokens += 1;
+            }
+            assert_eq!(tokens, *read, "{:?}", bytes);
+            let (rest, token) = read_token(input).unwrap();
+            assert_eq!(rest.len(), input.len());
+            assert_eq!(token, Token::Text(Vec::new()));
+        }
+    }
 
     #[test]
     fn test_control_symbol_tokens() {
@@ -270,27 +1346,27 @@ mod tests {
         let words_str = br#"\par\b0\b\uncle\foo-5\applepi314159"#;
         let valid_words = vec![
             Token::ControlWord {
-                name: "par".to_string(),
+                name: "par".into(),
                 arg: None,
             },
             Token::ControlWord {
-                name: "b".to_string(),
+                name: "b".into(),
                 arg: Some(0),
             },
             Token::ControlWord {
-                name: "b".to_string(),
+                name: "b".into(),
                 arg: None,
             },
             Token::ControlWord {
-                name: "uncle".to_string(),
+                name: "uncle".into(),
                 arg: None,
             },
             Token::ControlWord {
-                name: "foo".to_string(),
+                name: "foo".into(),
                 arg: Some(-5),
             },
             Token::ControlWord {
-                name: "applepi".to_string(),
+                name: "applepi".into(),
                 arg: Some(314159),
             },
         ];
@@ -323,19 +1399,19 @@ mod tests {
             Token::ControlSymbol('*'),
             Token::ControlBin(b"ABC{}".to_vec()),
             Token::ControlWord {
-                name: "b".to_string(),
+                name: "b".into(),
                 arg: None,
             },
             Token::ControlBin(b"{".to_vec()),
             Token::ControlBin(b"".to_vec()),
             Token::ControlWord {
-                name: "b".to_string(),
+                name: "b".into(),
                 arg: Some(0),
             },
             Token::ControlBin(b"".to_vec()),
             Token::ControlBin(b" ".to_vec()),
             Token::ControlWord {
-                name: "supercalifragilistic".to_string(),
+                name: "supercalifragilistic".into(),
                 arg: Some(31415),
             },
             Token::ControlBin(b"\x01".to_vec()),
@@ -352,16 +1428,16 @@ mod tests {
         let group_content_str = b"\\b Hello World \\b0 \\par\r\nThis is a test {\\*\\nothing}";
         let valid_group_content = vec![
             Token::ControlWord {
-                name: "b".to_string(),
+                name: "b".into(),
                 arg: None,
             },
             Token::Text(b"Hello World ".to_vec()),
             Token::ControlWord {
-                name: "b".to_string(),
+                name: "b".into(),
                 arg: Some(0),
             },
             Token::ControlWord {
-                name: "par".to_string(),
+                name: "par".into(),
                 arg: None,
             },
             Token::Newline,
@@ -369,7 +1445,7 @@ mod tests {
             Token::StartGroup,
             Token::ControlSymbol('*'),
             Token::ControlWord {
-                name: "nothing".to_string(),
+                name: "nothing".into(),
                 arg: None,
             },
             Token::EndGroup,
@@ -386,7 +1462,7 @@ mod tests {
     fn test_sample_doc() {
         let test_bytes = include_bytes!("../tests/sample.rtf");
         if let Err(e) = parse(test_bytes) {
-            panic!("Parsing error: {:?}", e);
+            panic!("Parsing error: {}", e);
         }
         match read_token_stream(Input(test_bytes)) {
             Ok((unparsed, _)) => assert_eq!(
@@ -400,12 +1476,465 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_spanned_tokens() {
+        let bytes = b"{\\b Hi\\'e9}\r\n";
+        let spans: Vec<_> = parse_spanned(bytes)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.span)
+            .collect();
+        assert_eq!(spans, vec![0..1, 1..4, 4..6, 6..10, 10..11, 11..13]);
+
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let spanned = parse_spanned(test_bytes).unwrap();
+        assert_eq!(spanned.last().unwrap().span.end, test_bytes.len());
+        let tokens: Vec<Token> = spanned.into_iter().map(|t| t.token).collect();
+        assert_eq!(tokens, parse(test_bytes).unwrap());
+    }
+
+    #[test]
+    fn test_to_rtf() {
+        assert_eq!(Token::Newline.to_rtf(), b"\r\n".to_vec());
+        let hex = Token::HexByte(0xe9);
+        assert_eq!(hex.to_rtf(), b"\\'e9".to_vec());
+        assert_eq!(hex.token_delimiter_after(&Token::Text(b"x".to_vec())), "");
+        assert_eq!(parse(&hex.to_rtf()).unwrap(), vec![hex]);
+        let text = Token::Text(b"{\xe9}".to_vec());
+        assert_eq!(text.to_rtf(), b"\\{\xe9\\}".to_vec());
+        assert_eq!(
+            text.to_rtf_escaped(NonAsciiEscaping::Hex),
+            b"\\{\\'e9\\}".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let tokens = parse(b"{\\fs24\\'e9\\~\\bin2 \x00\x01\r\n\"caf\xe9\"\n\\par}").unwrap();
+        let shown: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+        assert_eq!(
+            shown,
+            vec![
+                "{",
+                "\\fs24",
+                "\\'e9",
+                "\\~",
+                "\\bin2 <2 bytes of data>",
+                "<newline>",
+                "\"\\\"caf\u{fffd}\\\"\"",
+                "<newline>",
+                "\\par",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        for s in &["\\b", "\\fs-24", "\\'e9", "\\~", "{", "}"] {
+            let token: Token = s.parse().unwrap();
+            assert_eq!(token.to_string(), *s);
+        }
+        assert_eq!(
+            "\\b0 ".parse(),
+            Ok(Token::ControlWord {
+                name: "b".into(),
+                arg: Some(0),
+            })
+        );
+        for s in &["", "text", "\\b\\i", "\\b text", "\\bin1 x"] {
+            assert!(s.parse::<Token>().is_err(), "{:?}", s);
+        }
+        assert_eq!(
+            "x".parse::<Token>().unwrap_err().to_string(),
+            "not a control word, control symbol or group delimiter: \"x\""
+        );
+    }
+
+    #[test]
+    fn test_constructors() {
+        let tokens = parse(b"{\\b\\fs24 Hello}").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::StartGroup,
+                Token::word("b"),
+                Token::word_arg("fs", 24),
+                Token::text("Hello"),
+                Token::EndGroup,
+            ]
+        );
+        assert!(tokens[1].is_word("b"));
+        assert!(!tokens[1].is_word("fs"));
+        assert!(!tokens[3].is_word("Hello"));
+        assert_eq!(tokens[1].word_arg_or(1), 1);
+        assert_eq!(tokens[2].word_arg_or(1), 24);
+        assert_eq!(tokens[3].word_arg_or(-1), -1);
+        assert_eq!(Token::text(b"\xe9".to_vec()), Token::Text(vec![0xe9]));
+    }
+
+    #[test]
+    fn test_large_args() {
+        assert_eq!(
+            parse(b"\\revdttm3000000000 x\\li-9999999999").unwrap(),
+            vec![
+                Token::word_arg("revdttm", 3_000_000_000),
+                Token::text("x"),
+                Token::word_arg("li", -9_999_999_999),
+            ]
+        );
+        assert_eq!(narrow(3_000_000_000), 3_000_000_000u32 as i32);
+        assert_eq!(narrow(-99_999_999_999), i32::MIN);
+        assert_eq!(narrow(99_999_999_999), i32::MAX);
+        assert_eq!(narrow(-5), -5);
+    }
+
+    #[test]
+    fn test_numeric_overflow() {
+        let rtf = b"{\\fs99999999999 x\\li-099999999999\\bin12345678901 y}";
+        assert_eq!(
+            parse(rtf).unwrap(),
+            vec![
+                Token::StartGroup,
+                Token::word_arg("fs", i64::MAX),
+                Token::text("x"),
+                Token::word_arg("li", i64::MIN),
+                Token::word_arg("bin", i64::MAX),
+                Token::text("y"),
+                Token::EndGroup,
+            ]
+        );
+        let spans: Vec<_> = parse_spanned(rtf)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.span)
+            .collect();
+        assert_eq!(spans[1], 1..16);
+
+        let error = parse_strict(rtf).unwrap_err();
+        assert_eq!(
+            error.numeric_overflow(),
+            Some(&NumericOverflow {
+                text: "\\fs99999999999".to_string(),
+                offset: 1,
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            "Parser Error: numeric argument out of range at byte 1: \\fs99999999999\n  \
+             at byte 1, in control word argument\n\
+             00000000  7b 5c 66 73 39 39 39 39 39 39 39 39 39 39 39 20  |{\\fs99999999999 |\n\
+             \x20            ^^\n\
+             00000010  78 5c                                            |x\\|"
+        );
+        assert!(parse_strict(b"{\\fs9999999999 x}").is_ok());
+    }
+
+    #[test]
+    fn test_newline_policy() {
+        let rtf = b"{\\b Hello\r\nwor\nld\r\n\\par\r\n}";
+        let options = |newlines| ParseOptions {
+            newlines,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(rtf, &options(NewlinePolicy::Emit)).unwrap(),
+            parse(rtf).unwrap()
+        );
+        assert_eq!(
+            parse_with_options(rtf, &options(NewlinePolicy::Drop)).unwrap(),
+            vec![
+                Token::StartGroup,
+                Token::word("b"),
+                Token::text("Hello"),
+                Token::text("wor"),
+                Token::text("ld"),
+                Token::word("par"),
+                Token::EndGroup,
+            ]
+        );
+        assert_eq!(
+            parse_with_options(rtf, &options(NewlinePolicy::Merge)).unwrap(),
+            vec![
+                Token::StartGroup,
+                Token::word("b"),
+                Token::text("Helloworld"),
+                Token::word("par"),
+                Token::EndGroup,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_text() {
+        let rtf = b"{\\b Caf\\'e9 cr\\'E8me\r\nbr\\'fbl\\'e9e\r\n\\par\r\n\\'e9}";
+        let expected = vec![
+            Token::StartGroup,
+            Token::word("b"),
+            Token::text(&b"Caf\xe9 cr\xe8mebr\xfbl\xe9e"[..]),
+            Token::Newline,
+            Token::word("par"),
+            Token::Newline,
+            Token::text(&b"\xe9"[..]),
+            Token::EndGroup,
+        ];
+        let tokens = parse(rtf).unwrap();
+        assert_eq!(coalesce_text(&tokens), expected);
+        let options = ParseOptions {
+            coalesce_text: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(parse_with_options(rtf, &options).unwrap(), expected);
+        assert_eq!(extract_text(&coalesce_text(&tokens)), extract_text(&tokens));
+    }
+
+    #[test]
+    fn test_unicode() {
+        let rtf = b"{\\uc2\\u8212\\'97\\'97x{\\uc0\\u-3913 y}\\u233 ??z\\u\\ul}";
+        let tokens = parse(rtf).unwrap();
+        assert_eq!(tokens[2], Token::Unicode { codepoint: 8212 });
+        assert_eq!(tokens[8], Token::Unicode { codepoint: 61623 });
+        assert_eq!(tokens[8].to_rtf(), b"\\u-3913".to_vec());
+        assert_eq!(tokens[11], Token::Unicode { codepoint: 233 });
+        // Without an argument, \u is just a control word
+        assert_eq!(tokens[13], Token::word("u"));
+
+        let options = ParseOptions {
+            skip_unicode_fallback: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(rtf, &options).unwrap(),
+            vec![
+                Token::StartGroup,
+                Token::word_arg("uc", 2),
+                Token::Unicode { codepoint: 8212 },
+                Token::text("x"),
+                Token::StartGroup,
+                Token::word_arg("uc", 0),
+                Token::Unicode { codepoint: 61623 },
+                Token::text("y"),
+                Token::EndGroup,
+                Token::Unicode { codepoint: 233 },
+                Token::text("z"),
+                Token::word("u"),
+                Token::word("ul"),
+                Token::EndGroup,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_partial() {
+        let options = ParseOptions::default();
+        let complete = b"{\\rtf1 abc\\par}";
+        assert_eq!(
+            parse_partial(complete, &options).unwrap(),
+            (parse(complete).unwrap(), None)
+        );
+
+        let before = parse(b"{\\rtf1 abc").unwrap();
+        for (truncated, rest) in &[
+            (&b"{\\rtf1 abc"[..], ""),
+            (b"{\\rtf1 abc\\pa", "\\pa"),
+            (b"{\\rtf1 abc\\fs-", "\\fs-"),
+            (b"{\\rtf1 abc\\u12", "\\u12"),
+            (b"{\\rtf1 abc\\'4", "\\'4"),
+            (b"{\\rtf1 abc\\", "\\"),
+            (b"{\\rtf1 abc\\bin10 xyz", "\\bin10 xyz"),
+        ] {
+            let offset = truncated.len() - rest.len();
+            assert_eq!(
+                parse_partial(truncated, &options).unwrap(),
+                (before.clone(), Some(Incomplete::TruncatedAt(offset))),
+                "{}",
+                String::from_utf8_lossy(truncated)
+            );
+        }
+        // parse reads on as best it can
+        assert_eq!(
+            parse(b"{\\rtf1 abc\\bin10 xyz").unwrap().len(),
+            before.len() + 2
+        );
+    }
+
+    #[test]
+    fn test_parse_budget() {
+        let rtf = b"{\\rtf1 abc\\par def}";
+        let options = ParseOptions {
+            max_tokens: Some(3),
+            ..ParseOptions::default()
+        };
+        let (tokens, incomplete) = parse_partial(rtf, &options).unwrap();
+        assert_eq!(tokens, parse(b"{\\rtf1 abc").unwrap());
+        assert_eq!(incomplete, Some(Incomplete::BudgetExceeded(10)));
+        let error = parse_with_options(rtf, &options).unwrap_err();
+        assert_eq!(error.budget_exceeded(), Some(10));
+
+        // Enough for the whole document
+        let options = ParseOptions {
+            max_tokens: Some(6),
+            ..ParseOptions::default()
+        };
+        assert_eq!(parse_partial(rtf, &options).unwrap().1, None);
+
+        let options = ParseOptions {
+            deadline: Some(std::time::Instant::now()),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_partial(rtf, &options).unwrap(),
+            (Vec::new(), Some(Incomplete::BudgetExceeded(0)))
+        );
+    }
+
+    #[test]
+    fn test_control_chars() {
+        let rtf = b"{\\rtf1 a\0b\tc{\\uc2 \x01}\\'00}";
+        let parse_with = |control_chars| {
+            let options = ParseOptions {
+                control_chars,
+                ..ParseOptions::default()
+            };
+            parse_with_options(rtf, &options)
+        };
+        assert_eq!(
+            parse_with(ControlCharPolicy::Keep).unwrap(),
+            parse(rtf).unwrap()
+        );
+        assert_eq!(
+            parse_with(ControlCharPolicy::Strip).unwrap(),
+            parse(b"{\\rtf1 ab\tc{\\uc2 }\\'00}").unwrap()
+        );
+        let replaced = parse_with(ControlCharPolicy::Replace).unwrap();
+        assert_eq!(
+            replaced,
+            parse(b"{\\rtf1 a\\u65533?b\tc{\\uc2 \\u65533??}\\'00}").unwrap()
+        );
+        assert_eq!(extract_text(&replaced), "a\u{fffd}b\tc\u{fffd}\0");
+
+        let error = parse_with(ControlCharPolicy::Error).unwrap_err();
+        assert_eq!(
+            error.control_character(),
+            Some(&ControlCharacter { byte: 0, offset: 8 })
+        );
+        assert!(error
+            .to_string()
+            .starts_with("Parser Error: control character 0x00 in text at byte 8\n"));
+    }
+
+    #[test]
+    fn test_error_excerpt() {
+        let options = ParseOptions {
+            control_chars: ControlCharPolicy::Error,
+            ..ParseOptions::default()
+        };
+        let error = parse_with_options(b"{\\rtf1 a\0b}", &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parser Error: control character 0x00 in text at byte 8\n  \
+             at byte 8, in text\n\
+             00000000  7b 5c 72 74 66 31 20 61 00 62 7d                 |{\\rtf1 a.b}|\n\
+             \x20                                 ^^"
+        );
+
+        // Only the 16 bytes either side are shown
+        let rtf = b"{\\rtf1 0123456789abcdef0123456789abcdef0123456789abcdef}";
+        let excerpt = Excerpt::new(rtf, 36);
+        assert_eq!(excerpt.start, 16);
+        assert_eq!(excerpt.bytes, &rtf[16..53]);
+        // Pointing past the end of the input
+        let excerpt = Excerpt::new(b"{\\rtf1 abc\\par ", 16);
+        assert_eq!(
+            excerpt.to_string(),
+            "00000000  7b 5c 72 74 66 31 20 61 62 63 5c 70 61 72 20     |{\\rtf1 abc\\par |\n\
+             00000010                                                   ||\n\
+             \x20         ^^"
+        );
+    }
+
+    #[test]
+    fn test_batch_hex() {
+        let rtf = b"{\\rtf1\\ansicpg932 \\'93\\'fa\\'96\\'7b\\'8c\\'ea x\\'41\r\n\\'42{\\uc2\\u8212\\'97\\'97y}}";
+        let options = ParseOptions {
+            batch_hex: true,
+            ..ParseOptions::default()
+        };
+        let tokens = parse_with_options(rtf, &options).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::StartGroup,
+                Token::word_arg("rtf", 1),
+                Token::word_arg("ansicpg", 932),
+                Token::text(&b"\x93\xfa\x96\x7b\x8c\xea"[..]),
+                Token::text(" x"),
+                Token::text("A"),
+                Token::Newline,
+                Token::text("B"),
+                Token::StartGroup,
+                Token::word_arg("uc", 2),
+                Token::Unicode { codepoint: 8212 },
+                Token::text(&b"\x97\x97"[..]),
+                Token::text("y"),
+                Token::EndGroup,
+                Token::EndGroup,
+            ]
+        );
+        assert_eq!(extract_text(&tokens), extract_text(&parse(rtf).unwrap()));
+        assert_eq!(coalesce_text(&tokens), coalesce_text(&parse(rtf).unwrap()));
+
+        // Each byte of a run still counts towards a \\u fallback
+        let options = ParseOptions {
+            batch_hex: true,
+            skip_unicode_fallback: true,
+            ..ParseOptions::default()
+        };
+        let tokens = parse_with_options(rtf, &options).unwrap();
+        assert_eq!(tokens[tokens.len() - 3], Token::text("y"));
+    }
+
+    #[test]
+    fn test_skip_junk() {
+        let rtf = b"\xef\xbb\xbf\r\n{\\rtf1{\\b x}\\bin1 }}\0\0\0\r\n";
+        let options = ParseOptions {
+            skip_junk: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(rtf, &options).unwrap(),
+            vec![
+                Token::StartGroup,
+                Token::word_arg("rtf", 1),
+                Token::StartGroup,
+                Token::word("b"),
+                Token::text("x"),
+                Token::EndGroup,
+                Token::ControlBin(b"}".to_vec()),
+                Token::EndGroup,
+            ]
+        );
+        assert_eq!(parse(rtf).unwrap().len(), 12);
+        // An unclosed document is read to the end
+        assert_eq!(
+            parse_with_options(b"{\\rtf1 x", &options).unwrap(),
+            parse(b"{\\rtf1 x").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash() {
+        let tokens = parse(b"{\\b x\\b y}").unwrap();
+        let distinct: std::collections::HashSet<&Token> = tokens.iter().collect();
+        assert_eq!(distinct.len(), 5);
+    }
+
     // The spec doc is interested because it has unmatched "{}" groups
     #[test]
     fn test_spec_doc() {
         let test_bytes = include_bytes!("../tests/RTF-Spec-1.7.rtf");
         if let Err(e) = parse(test_bytes) {
-            panic!("Parsing error: {:?}", e);
+            panic!("Parsing error: {}", e);
         }
         match read_token_stream(Input(test_bytes)) {
             Ok((unparsed, _)) => assert_eq!(
@@ -0,0 +1,240 @@
+// Document tree
+//
+// A token stream nested into its groups, for looking things up without
+// tracking group depth by hand.  Groups are named after their first control
+// word, which for destinations is the destination: `{\*\generator ...}` is
+// the `generator` group and `{\rtf1 ...}` the `rtf` group.
+//
+
+use std;
+
+use text::decode_cp1252;
+use tokenizer::{parse, ParseError, Token};
+
+/// A token, or a group of nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Token(Token),
+    Group(Group),
+}
+
+/// A group and its contents
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Group {
+    /// The nodes between the braces
+    pub children: Vec<Node>,
+}
+
+impl Group {
+    /// The first control word of the group, after any `\*`
+    pub fn name(&self) -> Option<&str> {
+        let mut children = self.children.iter();
+        let mut first = children.next();
+        if let Some(Node::Token(Token::ControlSymbol('*'))) = first {
+            first = children.next();
+        }
+        match first {
+            Some(Node::Token(Token::ControlWord { name, .. })) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Whether the group is marked with `\*` as an optional destination
+    pub fn is_starred(&self) -> bool {
+        self.children.first() == Some(&Node::Token(Token::ControlSymbol('*')))
+    }
+
+    /// The groups directly inside this one
+    pub fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Group(group) => Some(group),
+            Node::Token(_) => None,
+        })
+    }
+
+    /// The argument of the first control word in the group itself, outside
+    /// nested groups, with the given name
+    pub fn arg(&self, name: &str) -> Option<i64> {
+        self.children.iter().find_map(|node| match node {
+            Node::Token(Token::ControlWord { name: word, arg }) if word == name => *arg,
+            _ => None,
+        })
+    }
+
+    /// The text of the group and the groups inside it, decoded as Windows-1252
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        push_text(&self.children, &mut text);
+        text
+    }
+
+    /// The group's tokens, including its braces
+    pub fn tokens(&self) -> Vec<Token> {
+        let mut tokens = vec![Token::StartGroup];
+        push_tokens(&self.children, &mut tokens);
+        tokens.push(Token::EndGroup);
+        tokens
+    }
+}
+
+fn push_text(nodes: &[Node], text: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Token(Token::Text(data)) => text.extend(data.iter().map(|b| decode_cp1252(*b))),
+            Node::Token(Token::HexByte(byte)) => text.push(decode_cp1252(*byte)),
+            Node::Token(Token::Unicode { codepoint }) => {
+                text.push(std::char::from_u32(*codepoint as u32).unwrap_or('\u{fffd}'))
+            }
+            Node::Token(Token::ControlSymbol(c)) if "{}\\".contains(*c) => text.push(*c),
+            Node::Group(group) => push_text(&group.children, text),
+            Node::Token(_) => {}
+        }
+    }
+}
+
+fn push_tokens(nodes: &[Node], tokens: &mut Vec<Token>) {
+    for node in nodes {
+        match node {
+            Node::Token(token) => tokens.push(token.clone()),
+            Node::Group(group) => tokens.extend(group.tokens()),
+        }
+    }
+}
+
+fn find<'g, F: FnMut(&Group) -> bool>(
+    nodes: &'g [Node],
+    predicate: &mut F,
+    found: &mut Vec<&'g Group>,
+) {
+    for node in nodes {
+        if let Node::Group(group) = node {
+            if predicate(group) {
+                found.push(group);
+            }
+            find(&group.children, predicate, found);
+        }
+    }
+}
+
+/// A document as a tree of groups
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Tree {
+    /// The top level nodes, usually just the `rtf` group
+    pub nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// Parse a document into a tree
+    pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+        parse(bytes).map(|tokens| Tree::from_tokens(&tokens))
+    }
+
+    /// Nest a token stream into a tree
+    ///
+    /// Groups still open at the end of the tokens are closed, and a closing
+    /// brace with no group to close is dropped.
+    pub fn from_tokens(tokens: &[Token]) -> Self {
+        let mut stack: Vec<Vec<Node>> = Vec::new();
+        let mut nodes = Vec::new();
+        for token in tokens {
+            match token {
+                Token::StartGroup => stack.push(std::mem::take(&mut nodes)),
+                Token::EndGroup => {
+                    if let Some(parent) = stack.pop() {
+                        let children = std::mem::replace(&mut nodes, parent);
+                        nodes.push(Node::Group(Group { children }));
+                    }
+                }
+                token => nodes.push(Node::Token(token.clone())),
+            }
+        }
+        while let Some(parent) = stack.pop() {
+            let children = std::mem::replace(&mut nodes, parent);
+            nodes.push(Node::Group(Group { children }));
+        }
+        Tree { nodes }
+    }
+
+    /// Every group a predicate accepts, outer groups before the groups inside
+    /// them
+    pub fn find_groups<F: FnMut(&Group) -> bool>(&self, mut predicate: F) -> Vec<&Group> {
+        let mut found = Vec::new();
+        find(&self.nodes, &mut predicate, &mut found);
+        found
+    }
+
+    /// Every group with the given name, at any depth
+    pub fn destinations(&self, name: &str) -> Vec<&Group> {
+        self.find_groups(|group| group.name() == Some(name))
+    }
+
+    /// The group at a path of group names separated by `/`, like
+    /// `rtf/info/title`
+    ///
+    /// Each name after the first is looked for among the groups directly
+    /// inside the previous one, and the first match is taken.
+    pub fn at(&self, path: &str) -> Option<&Group> {
+        let mut names = path.split('/').filter(|name| !name.is_empty());
+        let first = names.next()?;
+        let mut group = self.groups().find(|group| group.name() == Some(first))?;
+        for name in names {
+            group = group.groups().find(|group| group.name() == Some(name))?;
+        }
+        Some(group)
+    }
+
+    /// The top level groups
+    pub fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.nodes.iter().filter_map(|node| match node {
+            Node::Group(group) => Some(group),
+            Node::Token(_) => None,
+        })
+    }
+
+    /// The tree's tokens, in document order
+    pub fn tokens(&self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        push_tokens(&self.nodes, &mut tokens);
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RTF: &[u8] = br#"{\rtf1{\info{\title Caf\'e9 \{1\}}{\author A}{\*\company B}}{\*\shppict{\pict\pngblip 00}}{\pict\wmetafile8 01}Text}"#;
+
+    #[test]
+    fn test_tree_round_trip() {
+        let tokens = parse(RTF).unwrap();
+        let tree = Tree::from_tokens(&tokens);
+        assert_eq!(tree.tokens(), tokens);
+        assert_eq!(tree.groups().count(), 1);
+    }
+
+    #[test]
+    fn test_queries() {
+        let tree = Tree::parse(RTF).unwrap();
+        assert_eq!(tree.at("rtf/info/title").unwrap().text(), "Caf\u{e9} {1}");
+        assert_eq!(tree.at("/rtf/info/company/").unwrap().text(), "B");
+        assert!(tree.at("rtf/info/company").unwrap().is_starred());
+        assert_eq!(tree.at("rtf/title"), None);
+        assert_eq!(tree.at("info"), None);
+
+        let pictures = tree.destinations("pict");
+        assert_eq!(pictures.len(), 2);
+        assert_eq!(pictures[1].arg("wmetafile"), Some(8));
+        assert_eq!(pictures[0].arg("wmetafile"), None);
+
+        let starred = tree.find_groups(Group::is_starred);
+        let names: Vec<Option<&str>> = starred.iter().map(|group| group.name()).collect();
+        assert_eq!(names, vec![Some("company"), Some("shppict")]);
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        let tree = Tree::parse(br"{\rtf1 a}} b {{\b c").unwrap();
+        assert_eq!(tree.tokens(), parse(br"{\rtf1 a} b {{\b c}}").unwrap());
+    }
+}
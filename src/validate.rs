@@ -0,0 +1,373 @@
+// Spec conformance
+//
+// The tokenizer and everything built on it read documents the way word
+// processors do, forgiving whatever they can.  Validation is for the other
+// side of that: finding where a document departs from the RTF 1.9.1
+// specification, as a lint before accepting documents from elsewhere.
+//
+
+use std;
+
+use registry::{is_control_symbol, word_kind, WordKind};
+use tokenizer::Token;
+
+/// How strictly `validate` checks a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Only what every reader depends on: the `{\rtf1` header, and groups
+    /// that balance
+    Structure,
+    /// Everything the spec says as well: the character set, control words
+    /// the spec defines, with arguments in their documented ranges, the
+    /// order of the header tables, and nothing after the document
+    Strict,
+}
+
+/// A way a document departs from the spec
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// The document doesn't begin with `{\rtf`
+    MissingHeader,
+    /// `\rtfN` with a version other than 1, the only version there is
+    Version(Option<i64>),
+    /// No character set, `\ansi`, `\mac`, `\pc` or `\pca`, follows `\rtf1`
+    MissingCharset,
+    /// A group that's never closed
+    UnclosedGroup,
+    /// A `}` with no group to close
+    UnmatchedEndGroup,
+    /// Something other than line breaks after the document's group closes
+    TrailingContent,
+    /// A control word the spec doesn't define, outside a `\*` destination
+    UnknownControlWord(String),
+    /// A control symbol the spec doesn't define, outside a `\*` destination
+    UnknownControlSymbol(char),
+    /// A control word that requires an argument, without one
+    MissingArgument(String),
+    /// An argument outside the range the spec gives for its control word
+    ArgumentOutOfRange { word: String, arg: i64 },
+    /// A table of the document header after one the spec puts after it
+    DestinationOrder { destination: String, after: String },
+}
+
+impl ViolationKind {
+    /// The level that reports this kind of violation
+    pub fn level(&self) -> Level {
+        match self {
+            ViolationKind::MissingHeader
+            | ViolationKind::Version(_)
+            | ViolationKind::UnclosedGroup
+            | ViolationKind::UnmatchedEndGroup => Level::Structure,
+            _ => Level::Strict,
+        }
+    }
+}
+
+impl std::fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ViolationKind::MissingHeader => write!(f, "document doesn't begin with {{\\rtf"),
+            ViolationKind::Version(Some(version)) => {
+                write!(f, "unsupported RTF version {}", version)
+            }
+            ViolationKind::Version(None) => write!(f, "RTF version missing"),
+            ViolationKind::MissingCharset => write!(f, "no character set after \\rtf1"),
+            ViolationKind::UnclosedGroup => write!(f, "group never closed"),
+            ViolationKind::UnmatchedEndGroup => write!(f, "unmatched }}"),
+            ViolationKind::TrailingContent => write!(f, "content after the end of the document"),
+            ViolationKind::UnknownControlWord(name) => write!(f, "unknown control word \\{}", name),
+            ViolationKind::UnknownControlSymbol(c) => {
+                write!(f, "unknown control symbol \\{}", c.escape_debug())
+            }
+            ViolationKind::MissingArgument(name) => write!(f, "\\{} requires an argument", name),
+            ViolationKind::ArgumentOutOfRange { word, arg } => {
+                write!(f, "\\{}{} is out of range", word, arg)
+            }
+            ViolationKind::DestinationOrder { destination, after } => {
+                write!(f, "\\{} belongs before \\{}", destination, after)
+            }
+        }
+    }
+}
+
+/// A violation, and the index of the token where it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub index: usize,
+    pub kind: ViolationKind,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "token {}: {}", self.index, self.kind)
+    }
+}
+
+// Arguments with ranges the spec documents
+const RANGES: &[(&str, i64, i64)] = &[
+    ("blue", 0, 255),
+    ("brdrw", 0, 255),
+    ("fcharset", 0, 255),
+    ("fprq", 0, 2),
+    ("green", 0, 255),
+    // 10 to 12 have special meanings in Word 6 documents
+    ("ilvl", 0, 12),
+    ("itap", 0, i32::MAX as i64),
+    // 0 is no list
+    ("ls", 0, 2000),
+    ("outlinelevel", 0, 8),
+    ("picbpp", 1, 32),
+    ("red", 0, 255),
+    ("uc", 0, i32::MAX as i64),
+];
+
+// The tables of the document header, in the order the spec gives them, and
+// the info group and XML namespace table that follow them
+const HEADER_ORDER: &[&str] = &[
+    "fonttbl",
+    "filetbl",
+    "colortbl",
+    "stylesheet",
+    "stylerestrictions",
+    "listtable",
+    "listoverridetable",
+    "revtbl",
+    "rsidtbl",
+    "mmathPr",
+    "generator",
+    "info",
+    "xmlnstbl",
+];
+
+// Words the spec gives an argument, that its own examples, like the
+// documents Word writes, use without one
+const OPTIONAL_ARGUMENTS: &[&str] = &["cgrid"];
+
+const CHARSETS: &[&str] = &["ansi", "mac", "pc", "pca"];
+
+// The header: `{\rtf1`, and the words up to the first group or text after it
+fn check_header(tokens: &[Token], violations: &mut Vec<Violation>) {
+    let version = match tokens.get(..2) {
+        Some([Token::StartGroup, Token::ControlWord { name, arg }]) if name == "rtf" => *arg,
+        _ => {
+            violations.push(Violation {
+                index: 0,
+                kind: ViolationKind::MissingHeader,
+            });
+            return;
+        }
+    };
+    if version != Some(1) {
+        violations.push(Violation {
+            index: 1,
+            kind: ViolationKind::Version(version),
+        });
+    }
+    let charset = tokens[2..]
+        .iter()
+        .take_while(|token| matches!(token, Token::ControlWord { .. } | Token::Newline))
+        .any(|token| match token {
+            Token::ControlWord { name, .. } => CHARSETS.contains(&name.as_str()),
+            _ => false,
+        });
+    if !charset {
+        violations.push(Violation {
+            index: 1,
+            kind: ViolationKind::MissingCharset,
+        });
+    }
+}
+
+fn check_word(name: &str, arg: Option<i64>) -> Option<ViolationKind> {
+    match word_kind(name) {
+        None => return Some(ViolationKind::UnknownControlWord(name.to_string())),
+        Some(WordKind::Value) if arg.is_none() && !OPTIONAL_ARGUMENTS.contains(&name) => {
+            return Some(ViolationKind::MissingArgument(name.to_string()))
+        }
+        _ => {}
+    }
+    let arg = arg?;
+    RANGES
+        .iter()
+        .find(|(word, _, _)| *word == name)
+        .filter(|(_, min, max)| arg < *min || arg > *max)
+        .map(|_| ViolationKind::ArgumentOutOfRange {
+            word: name.to_string(),
+            arg,
+        })
+}
+
+/// Check a document against the RTF 1.9.1 specification
+///
+/// Returns the violations that `level` checks for, in the order of the
+/// tokens they're found at.  Nothing inside a `\*` destination is checked
+/// against the spec's vocabulary, since readers skip what they don't know
+/// there.  Use `parse_spanned` to find the bytes a violation's token came
+/// from.
+pub fn validate(tokens: &[Token], level: Level) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_header(tokens, &mut violations);
+
+    // For each open group, where it starts and whether it's inside a \*
+    // destination
+    let mut stack: Vec<(usize, bool)> = Vec::new();
+    let mut starred = false;
+    let mut group_start = false;
+    // Whether the document's group has closed, and whether anything has
+    // come after it
+    let mut ended = false;
+    let mut trailing = false;
+    // The last of the header tables so far, by its place in HEADER_ORDER
+    let mut header_table = None;
+    for (index, token) in tokens.iter().enumerate() {
+        let start = group_start;
+        group_start = false;
+        if ended && *token != Token::Newline {
+            violations.push(Violation {
+                index,
+                kind: ViolationKind::TrailingContent,
+            });
+            ended = false;
+            trailing = true;
+        }
+        if let Token::ControlWord { name, .. } = token {
+            let rank = HEADER_ORDER
+                .iter()
+                .position(|table| *table == name.as_str());
+            match (rank, header_table) {
+                (Some(rank), Some(latest)) if start && stack.len() == 2 && rank < latest => {
+                    violations.push(Violation {
+                        index,
+                        kind: ViolationKind::DestinationOrder {
+                            destination: name.to_string(),
+                            after: HEADER_ORDER[latest].to_string(),
+                        },
+                    })
+                }
+                (Some(rank), _) if start && stack.len() == 2 => header_table = Some(rank),
+                _ => {}
+            }
+        }
+        let kind = match token {
+            Token::StartGroup => {
+                stack.push((index, starred));
+                group_start = true;
+                None
+            }
+            Token::EndGroup => match stack.pop() {
+                Some((_, outer)) => {
+                    starred = outer;
+                    ended = stack.is_empty() && !trailing;
+                    None
+                }
+                None => Some(ViolationKind::UnmatchedEndGroup),
+            },
+            Token::ControlSymbol('*') if start => {
+                starred = true;
+                group_start = true;
+                None
+            }
+            Token::ControlWord { .. } | Token::ControlSymbol(_) if starred => None,
+            Token::ControlWord { name, arg } => check_word(name, *arg),
+            Token::ControlSymbol(c) if !is_control_symbol(*c) => {
+                Some(ViolationKind::UnknownControlSymbol(*c))
+            }
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            violations.push(Violation { index, kind });
+        }
+    }
+    for (index, _) in stack {
+        violations.push(Violation {
+            index,
+            kind: ViolationKind::UnclosedGroup,
+        });
+    }
+
+    violations.retain(|violation| violation.kind.level() <= level);
+    violations.sort_by_key(|violation| violation.index);
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizer::parse;
+
+    fn kinds(rtf: &[u8], level: Level) -> Vec<(usize, ViolationKind)> {
+        validate(&parse(rtf).unwrap(), level)
+            .into_iter()
+            .map(|violation| (violation.index, violation.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_samples() {
+        let sample = include_bytes!("../tests/sample.rtf");
+        assert_eq!(validate(&parse(sample).unwrap(), Level::Strict), vec![]);
+        let spec = include_bytes!("../tests/RTF-Spec-1.7.rtf");
+        assert_eq!(validate(&parse(spec).unwrap(), Level::Strict), vec![]);
+    }
+
+    #[test]
+    fn test_validate_structure() {
+        assert_eq!(
+            kinds(b"{\\rtf2\\ansi {\\b x}}}", Level::Structure),
+            vec![
+                (1, ViolationKind::Version(Some(2))),
+                (8, ViolationKind::UnmatchedEndGroup)
+            ]
+        );
+        assert_eq!(
+            kinds(b"x{\\rtf1 {\\b x}", Level::Structure),
+            vec![
+                (0, ViolationKind::MissingHeader),
+                (1, ViolationKind::UnclosedGroup)
+            ]
+        );
+        // Strict checks aren't made at the structure level
+        assert_eq!(kinds(b"{\\rtf1\\nosuchword}", Level::Structure), vec![]);
+    }
+
+    #[test]
+    fn test_validate_strict() {
+        let rtf = b"{\\rtf1\\ansi{\\colortbl;\\red256\\green0\\blue0;}{\\fonttbl{\\f0 A;}}\\nosuchword\\fs\\#{\\*\\custom\\other\\#}\\b1 x}\r\n{}";
+        assert_eq!(
+            kinds(rtf, Level::Strict),
+            vec![
+                (
+                    6,
+                    ViolationKind::ArgumentOutOfRange {
+                        word: "red".to_string(),
+                        arg: 256,
+                    }
+                ),
+                (
+                    12,
+                    ViolationKind::DestinationOrder {
+                        destination: "fonttbl".to_string(),
+                        after: "colortbl".to_string(),
+                    }
+                ),
+                (
+                    18,
+                    ViolationKind::UnknownControlWord("nosuchword".to_string())
+                ),
+                (19, ViolationKind::MissingArgument("fs".to_string())),
+                (20, ViolationKind::UnknownControlSymbol('#')),
+                (31, ViolationKind::TrailingContent),
+            ]
+        );
+        assert_eq!(
+            kinds(b"{\\rtf1\\deff0{\\fonttbl}}", Level::Strict),
+            vec![(1, ViolationKind::MissingCharset)]
+        );
+        let violation = &validate(&parse(rtf).unwrap(), Level::Strict)[1];
+        assert_eq!(
+            violation.to_string(),
+            "token 12: \\fonttbl belongs before \\colortbl"
+        );
+    }
+}
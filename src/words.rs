@@ -0,0 +1,173 @@
+// Words
+//
+// Splits the text of a document into words as a reader sees them, for spell
+// checkers and language models: hidden and deleted text is left out without
+// breaking the word it's in, and so are field instructions and everything
+// else that isn't shown.  Each word keeps where it came from, so what's
+// found in it can be mapped back onto the document.
+//
+
+use std::ops::Range;
+
+use state::StateTracker;
+use text::{piece_text, pieces, Unit, Whitespace};
+use tokenizer::Token;
+
+/// A word of a document's text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// The word, as it's shown
+    pub text: String,
+    /// The byte range of the word in the text that `extract_text` extracts,
+    /// which includes any hidden or deleted text inside it
+    pub text_range: Range<usize>,
+    /// The range of the tokens it was read from
+    pub token_range: Range<usize>,
+}
+
+// Characters that join the letters either side of them into one word, like
+// the apostrophe in "don't"
+fn is_joiner(c: char) -> bool {
+    matches!(c, '\'' | '\u{2019}' | '-' | '\u{2011}')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// The words of the text of `tokens`, given whether each token is shown
+fn split_words(tokens: &[Token], shown: &[bool]) -> Vec<Word> {
+    let pieces = pieces(tokens);
+    let (text, chars) = piece_text(&pieces, Whitespace::Normalize);
+    let mut words = Vec::new();
+    // The word so far: its text, and its characters' offsets and pieces
+    let mut word = String::new();
+    let mut found: Vec<(usize, usize)> = Vec::new();
+    let mut flush = |word: &mut String, found: &mut Vec<(usize, usize)>| {
+        while word.ends_with(is_joiner) {
+            word.pop();
+            found.pop();
+        }
+        if let (Some(first), Some(last)) = (found.first(), found.last()) {
+            let c = text[last.0..].chars().next().map_or(0, char::len_utf8);
+            let mut end = last.1;
+            while pieces
+                .get(end + 1)
+                .is_some_and(|piece| piece.unit == Unit::Fallback)
+            {
+                end += 1;
+            }
+            words.push(Word {
+                text: std::mem::take(word),
+                text_range: first.0..last.0 + c,
+                token_range: pieces[first.1].token..pieces[end].token + 1,
+            });
+        }
+        word.clear();
+        found.clear();
+    };
+    for (offset, piece) in chars.iter().copied() {
+        if !shown[pieces[piece].token] {
+            continue;
+        }
+        let c = match text[offset..].chars().next() {
+            Some(c) => c,
+            None => continue,
+        };
+        // A soft hyphen (`\-`) only shows where a word may be broken
+        if c == '\u{ad}' {
+            continue;
+        }
+        if is_word_char(c) || (is_joiner(c) && !word.is_empty()) {
+            word.push(c);
+            found.push((offset, piece));
+        } else {
+            flush(&mut word, &mut found);
+        }
+    }
+    flush(&mut word, &mut found);
+    words
+}
+
+/// The words of a document's text, as a reader sees them
+///
+/// Words are runs of letters, digits and underscores, along with the
+/// apostrophes and hyphens between them, so "don't" and "well-known" are
+/// one word each.  Only text that's shown is looked at: control words,
+/// field instructions and the other destinations that `extract_text`
+/// skips are left out, and so is hidden text (`\v`) and text marked as a
+/// tracked deletion.  Left out text inside a word doesn't split it, so a
+/// word spelled with a hidden part in the middle is read as shown.
+///
+/// A word's `text_range` can be passed on, as a `search::Pattern::Ranges`,
+/// to `search::find` to find the bytes it was read from, or to
+/// `search::replace_text` to correct it.
+///
+/// ```
+/// use rtf_grimoire::parse;
+/// use rtf_grimoire::words::words;
+///
+/// let tokens = parse(b"{\\rtf1 Don't sp{\\v HIDDEN}ell {\\b well}-known}").unwrap();
+/// let words: Vec<String> = words(&tokens).into_iter().map(|word| word.text).collect();
+/// assert_eq!(words, vec!["Don't", "spell", "well-known"]);
+/// ```
+pub fn words(tokens: &[Token]) -> Vec<Word> {
+    let mut tracker = StateTracker::new();
+    let shown: Vec<bool> = tokens
+        .iter()
+        .map(|token| {
+            let format = tracker.char_format();
+            let shown = !format.hidden && !format.deleted;
+            tracker.token(token);
+            shown
+        })
+        .collect();
+    split_words(tokens, &shown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use search::{replace_text, Pattern};
+    use text::extract_text;
+    use tokenizer::parse;
+
+    #[test]
+    fn test_words() {
+        let tokens = parse(
+            b"{\\rtf1{\\fonttbl{\\f0 Times;}}\\pard Teh {\\field{\\*\\fldinst HYPERLINK \"x\"}{\\fldrslt caf\\u233?}}\
+              {\\deleted old}\\par {\\v secret} na\\u239?ve -- 'quoted' hy\\-phen 42\\tab end}",
+        )
+        .unwrap();
+        let found = words(&tokens);
+        let texts: Vec<&str> = found.iter().map(|word| word.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Teh",
+                "caf\u{e9}",
+                "na\u{ef}ve",
+                "quoted",
+                "hyphen",
+                "42",
+                "end"
+            ]
+        );
+
+        let text = extract_text(&tokens);
+        assert_eq!(&text[found[1].text_range.clone()], "caf\u{e9}");
+        assert_eq!(
+            tokens[found[1].token_range.clone()],
+            [
+                Token::text("caf"),
+                Token::Unicode { codepoint: 233 },
+                Token::text("?"),
+            ][..]
+        );
+
+        // Correcting a word in place
+        let teh = Pattern::Ranges(vec![found[0].text_range.clone()]);
+        let fixed = replace_text(&tokens, &teh, "The");
+        assert_eq!(words(&fixed)[0].text, "The");
+    }
+}
@@ -0,0 +1,947 @@
+// RTF document serialization
+//
+// Turns a token stream back into RTF, inserting control word delimiters
+// where they're needed and protecting the document structure from text
+// payloads that contain RTF's reserved characters.
+//
+
+use std;
+use std::borrow::Cow;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::io::Write;
+use std::ops::Range;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "async")]
+use futures_io::AsyncWrite;
+
+use tokenizer::{Name, SpannedToken, Token};
+
+/// How `{`, `}` and `\` in `Token::Text` payloads are handled on output
+///
+/// Text tokens read from a document never contain these characters, but
+/// tokens constructed programmatically can, and writing them out verbatim
+/// changes the structure of the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum TextEscaping {
+    /// Escape reserved characters as the control symbols `\{`, `\}` and `\\`
+    #[default]
+    Escape,
+    /// Write text bytes exactly as they are
+    Verbatim,
+    /// Refuse to write text containing reserved characters
+    Error,
+}
+
+/// How bytes of 0x80 and above in `Token::Text` payloads are written
+///
+/// RTF is a 7-bit format: readers are only required to understand text
+/// outside ASCII when it's escaped.  Tokens read from a document can hold
+/// such bytes when the document itself was written carelessly, but text
+/// that has been modified programmatically is where they usually come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum NonAsciiEscaping {
+    /// Write the bytes as they are, which preserves the token stream exactly
+    #[default]
+    Verbatim,
+    /// Write each byte as a `\'xx` hex escape, in the document's code page
+    Hex,
+    /// Read the payload as UTF-8 and write each character outside ASCII as a
+    /// `\uN` escape with a `?` fallback character, which assumes the `\uc1`
+    /// default is in effect.  Bytes that aren't UTF-8 are hex escaped.
+    Unicode,
+}
+
+/// Options controlling serialization
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub text_escaping: TextEscaping,
+    pub non_ascii: NonAsciiEscaping,
+    /// Start a new line, with a CRLF, before a token that would take a line
+    /// past this many bytes, as Word does
+    ///
+    /// Lines are only broken between tokens, and readers ignore the line
+    /// breaks.  A token longer than this, like the data of a picture, gets a
+    /// line to itself, and a group's `{` is kept on the line of the control
+    /// word after it, so lines can run past the width.  `None`, the default,
+    /// writes only the `Token::Newline`s in the stream.
+    pub line_width: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum WriteError {
+    /// A text payload contained a reserved character, and the escaping policy
+    /// was `TextEscaping::Error`
+    ReservedCharInText {
+        /// Index of the offending token in the stream
+        token: usize,
+        /// Offset of the reserved character within the text payload
+        offset: usize,
+        byte: u8,
+    },
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WriteError::ReservedCharInText {
+                token,
+                offset,
+                byte,
+            } => write!(
+                f,
+                "Write Error: reserved character '{}' at offset {} of text token {}",
+                *byte as char, offset, token
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+fn is_reserved(byte: u8) -> bool {
+    byte == b'{' || byte == b'}' || byte == b'\\'
+}
+
+/// Apply an escaping policy to a text payload
+///
+/// On error, returns the offset of the first reserved character.
+pub fn escape_text(data: &[u8], escaping: TextEscaping) -> Result<Cow<'_, [u8]>, usize> {
+    let first = match data.iter().position(|b| is_reserved(*b)) {
+        Some(offset) => offset,
+        None => return Ok(Cow::Borrowed(data)),
+    };
+    match escaping {
+        TextEscaping::Verbatim => Ok(Cow::Borrowed(data)),
+        TextEscaping::Error => Err(first),
+        TextEscaping::Escape => {
+            let mut escaped = Vec::with_capacity(data.len() + 8);
+            escaped.extend_from_slice(&data[..first]);
+            for byte in &data[first..] {
+                if is_reserved(*byte) {
+                    escaped.push(b'\\');
+                }
+                escaped.push(*byte);
+            }
+            Ok(Cow::Owned(escaped))
+        }
+    }
+}
+
+fn push_hex(escaped: &mut Vec<u8>, byte: u8) {
+    escaped.extend_from_slice(format!("\\'{:02x}", byte).as_bytes());
+}
+
+fn push_unicode(escaped: &mut Vec<u8>, c: char) {
+    let mut units = [0; 2];
+    for unit in c.encode_utf16(&mut units) {
+        // \u takes a signed 16-bit value
+        escaped.extend_from_slice(format!("\\u{}?", *unit as i16).as_bytes());
+    }
+}
+
+/// Escape the bytes of a text payload that are outside ASCII
+pub fn escape_non_ascii(data: &[u8], escaping: NonAsciiEscaping) -> Cow<'_, [u8]> {
+    let first = match data.iter().position(|b| !b.is_ascii()) {
+        Some(offset) if escaping != NonAsciiEscaping::Verbatim => offset,
+        _ => return Cow::Borrowed(data),
+    };
+    let mut escaped = Vec::with_capacity(data.len() + 16);
+    escaped.extend_from_slice(&data[..first]);
+    let mut rest = &data[first..];
+    while let Some(&byte) = rest.first() {
+        if byte.is_ascii() {
+            escaped.push(byte);
+            rest = &rest[1..];
+            continue;
+        }
+        if escaping == NonAsciiEscaping::Unicode {
+            // Take the character starting here, if it's valid UTF-8
+            let len = std::cmp::min(4, rest.len());
+            let valid = match std::str::from_utf8(&rest[..len]) {
+                Ok(text) => text,
+                Err(error) => std::str::from_utf8(&rest[..error.valid_up_to()]).unwrap_or(""),
+            };
+            if let Some(c) = valid.chars().next() {
+                push_unicode(&mut escaped, c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        }
+        push_hex(&mut escaped, byte);
+        rest = &rest[1..];
+    }
+    Cow::Owned(escaped)
+}
+
+// A payload-free copy of a token, which is all that's needed to decide on the
+// delimiter between it and the next token
+fn shape(token: &Token) -> Token {
+    match token {
+        Token::ControlSymbol(c) => Token::ControlSymbol(*c),
+        Token::ControlWord { arg, .. } => Token::ControlWord {
+            name: Name::default(),
+            arg: *arg,
+        },
+        Token::HexByte(_) => Token::HexByte(0),
+        Token::Unicode { .. } => Token::Unicode { codepoint: 0 },
+        Token::ControlBin(_) => Token::ControlBin(Vec::new()),
+        Token::Text(_) => Token::Text(Vec::new()),
+        Token::StartGroup => Token::StartGroup,
+        Token::EndGroup => Token::EndGroup,
+        Token::Newline => Token::Newline,
+    }
+}
+
+/// Incremental serializer that encodes one token at a time
+///
+/// This is the core shared by the writers: it remembers just enough about
+/// the previous token to insert a control word delimiter when one is needed,
+/// and applies the escaping policy to text payloads.
+#[derive(Debug, Default)]
+pub struct Encoder {
+    options: WriteOptions,
+    prev: Option<Token>,
+    index: usize,
+    // Bytes written since the last line break
+    column: usize,
+    // Whether a line can be broken before the next token
+    breakable: bool,
+}
+
+// Whether a line can be broken after a token.  Not after a group's `{` or a
+// `\*`, which some readers expect right before the destination's control
+// word, and not after a `\uN`, whose fallback characters follow it.
+fn breaks_after(token: &Token) -> bool {
+    !matches!(
+        token,
+        Token::StartGroup | Token::ControlSymbol('*') | Token::Unicode { .. }
+    )
+}
+
+impl Encoder {
+    pub fn new(options: WriteOptions) -> Self {
+        Encoder {
+            options,
+            prev: None,
+            index: 0,
+            column: 0,
+            breakable: false,
+        }
+    }
+
+    // Append bytes to `rtf`, keeping track of the column they end in
+    fn push(&mut self, bytes: &[u8], rtf: &mut Vec<u8>) {
+        match bytes.iter().rposition(|&byte| byte == b'\n') {
+            Some(newline) => self.column = bytes.len() - newline - 1,
+            None => self.column += bytes.len(),
+        }
+        rtf.extend_from_slice(bytes);
+    }
+
+    /// Append the serialized form of `token` to `rtf`
+    ///
+    /// On error, nothing is appended.
+    pub fn encode(&mut self, token: &Token, rtf: &mut Vec<u8>) -> Result<(), WriteError> {
+        self.encode_token(token, rtf).map(|_| ())
+    }
+
+    /// Append the serialized form of `token` to `rtf`, returning the range
+    /// of `rtf` the token itself was written to, after any delimiter or line
+    /// break written before it
+    pub(crate) fn encode_token(
+        &mut self,
+        token: &Token,
+        rtf: &mut Vec<u8>,
+    ) -> Result<Range<usize>, WriteError> {
+        let text = match token {
+            Token::Text(data) => {
+                let text = escape_text(data, self.options.text_escaping).map_err(|offset| {
+                    WriteError::ReservedCharInText {
+                        token: self.index,
+                        offset,
+                        byte: data[offset],
+                    }
+                })?;
+                Some(escape_non_ascii(&text, self.options.non_ascii).into_owned())
+            }
+            _ => None,
+        };
+        let delimiter = match self.prev {
+            Some(ref prev) => prev.token_delimiter_after(token),
+            None => "",
+        };
+        let body = match text {
+            Some(text) => text,
+            None => token.to_rtf(),
+        };
+        self.push(delimiter.as_bytes(), rtf);
+        if let Some(width) = self.options.line_width {
+            let wraps = self.column > 0 && self.column + body.len() > width;
+            if wraps && self.breakable && *token != Token::Newline {
+                self.push(b"\r\n", rtf);
+            }
+        }
+        let start = rtf.len();
+        self.push(&body, rtf);
+        self.breakable = breaks_after(token);
+        self.prev = Some(shape(token));
+        self.index += 1;
+        Ok(start..rtf.len())
+    }
+
+    /// Append the serialized form of `token` to `rtf`, followed by a
+    /// delimiting space if `trailing_space` is set and it's a control word
+    /// that can take one
+    pub fn encode_spaced(
+        &mut self,
+        token: &Token,
+        trailing_space: bool,
+        rtf: &mut Vec<u8>,
+    ) -> Result<(), WriteError> {
+        self.encode(token, rtf)?;
+        match token {
+            Token::ControlWord { .. } | Token::Unicode { .. } if trailing_space => {
+                self.push(b" ", rtf);
+                // Nothing can run on from a control word after its delimiter
+                self.prev = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Serialize a token stream, inserting delimiters after control words as needed
+pub fn write_rtf(tokens: &[Token], options: &WriteOptions) -> Result<Vec<u8>, WriteError> {
+    let mut encoder = Encoder::new(options.clone());
+    let mut rtf = Vec::new();
+    for token in tokens {
+        encoder.encode(token, &mut rtf)?;
+    }
+    Ok(rtf)
+}
+
+/// Serialize spanned tokens, keeping the delimiting spaces recorded by
+/// `SpannedToken::trailing_space`
+///
+/// Control words that didn't consume a space in the original document are
+/// still given one where the following text needs it, as after an edit.
+pub fn write_spanned_rtf(
+    tokens: &[SpannedToken],
+    options: &WriteOptions,
+) -> Result<Vec<u8>, WriteError> {
+    let mut encoder = Encoder::new(options.clone());
+    let mut rtf = Vec::new();
+    for spanned in tokens {
+        encoder.encode_spaced(&spanned.token, spanned.trailing_space, &mut rtf)?;
+    }
+    Ok(rtf)
+}
+
+// How much serialized RTF the writers gather before writing it out
+const CHUNK_SIZE: usize = 64 * 1024;
+
+fn invalid_data(error: WriteError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+// Serialize tokens from the front of `tokens` into `buffer` until it holds a
+// chunk, returning how many were taken
+fn encode_chunk(
+    encoder: &mut Encoder,
+    tokens: &[Token],
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let mut count = 0;
+    for token in tokens {
+        if buffer.len() >= CHUNK_SIZE {
+            break;
+        }
+        encoder.encode(token, buffer).map_err(invalid_data)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Serialize a token stream to `writer`, as `write_rtf` does, without
+/// building the whole document in memory
+///
+/// The RTF is written in chunks of 64 KiB or so, so there's no need for a
+/// `BufWriter`.  Escaping policy violations are reported as
+/// `io::ErrorKind::InvalidData` errors wrapping a `WriteError`, and what was
+/// serialized before the token at fault has been written by then.
+pub fn write_tokens<W: Write>(
+    mut writer: W,
+    tokens: &[Token],
+    options: &WriteOptions,
+) -> std::io::Result<()> {
+    let mut encoder = Encoder::new(options.clone());
+    let mut buffer = Vec::with_capacity(CHUNK_SIZE);
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        buffer.clear();
+        let encoded = encode_chunk(&mut encoder, rest, &mut buffer);
+        writer.write_all(&buffer)?;
+        rest = &rest[encoded?..];
+    }
+    writer.flush()
+}
+
+/// Serialize a token stream to an `AsyncWrite`, as `write_tokens` does
+///
+/// The future resolves once everything has been written and the writer
+/// flushed.  Writers for tokio and other runtimes that don't implement
+/// `futures_io::AsyncWrite` themselves have adapters that do.
+#[cfg(feature = "async")]
+pub fn write_tokens_async<'a, W: AsyncWrite + Unpin>(
+    writer: W,
+    tokens: &'a [Token],
+    options: &WriteOptions,
+) -> WriteTokens<'a, W> {
+    WriteTokens {
+        writer,
+        tokens,
+        encoder: Encoder::new(options.clone()),
+        buffer: Vec::with_capacity(CHUNK_SIZE),
+        written: 0,
+        error: None,
+    }
+}
+
+/// The future `write_tokens_async` returns
+#[cfg(feature = "async")]
+#[must_use = "futures do nothing unless polled"]
+pub struct WriteTokens<'a, W> {
+    writer: W,
+    // The tokens left to serialize
+    tokens: &'a [Token],
+    encoder: Encoder,
+    buffer: Vec<u8>,
+    // How much of `buffer` has been written
+    written: usize,
+    // An escaping error, reported once what came before it is written
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, W: AsyncWrite + Unpin> Future for WriteTokens<'a, W> {
+    type Output = std::io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            if this.written < this.buffer.len() {
+                let pending = &this.buffer[this.written..];
+                match Pin::new(&mut this.writer).poll_write(cx, pending) {
+                    Poll::Ready(Ok(0)) => {
+                        return Poll::Ready(Err(std::io::ErrorKind::WriteZero.into()))
+                    }
+                    Poll::Ready(Ok(n)) => this.written += n,
+                    Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                    Poll::Pending => return Poll::Pending,
+                }
+                continue;
+            }
+            if let Some(error) = this.error.take() {
+                return Poll::Ready(Err(error));
+            }
+            if this.tokens.is_empty() {
+                return Pin::new(&mut this.writer).poll_flush(cx);
+            }
+            this.buffer.clear();
+            this.written = 0;
+            match encode_chunk(&mut this.encoder, this.tokens, &mut this.buffer) {
+                Ok(encoded) => this.tokens = &this.tokens[encoded..],
+                Err(error) => this.error = Some(error),
+            }
+        }
+    }
+}
+
+/// Streaming serializer that writes tokens to an `io::Write` as they're given
+///
+/// Output is passed straight through to the underlying writer, so wrap it in
+/// a `BufWriter` if it's expensive to write to in small pieces.
+pub struct RtfWriter<W: Write> {
+    inner: W,
+    encoder: Encoder,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> RtfWriter<W> {
+    pub fn new(inner: W, options: WriteOptions) -> Self {
+        RtfWriter {
+            inner,
+            encoder: Encoder::new(options),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Serialize and write a single token
+    ///
+    /// Escaping policy violations are reported as `io::ErrorKind::InvalidData`
+    /// errors wrapping a `WriteError`.
+    pub fn write_token(&mut self, token: &Token) -> std::io::Result<()> {
+        self.buffer.clear();
+        self.encoder
+            .encode(token, &mut self.buffer)
+            .map_err(invalid_data)?;
+        self.inner.write_all(&self.buffer)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Streaming serializer that writes tokens to an `AsyncWrite` as they're
+/// given, the asynchronous `RtfWriter`
+///
+/// Each token is serialized when it's given, and written by awaiting the
+/// future its method returns.  A token whose future is dropped before it
+/// completes is still written, before the next token.
+#[cfg(feature = "async")]
+pub struct AsyncRtfWriter<W: AsyncWrite + Unpin> {
+    inner: W,
+    encoder: Encoder,
+    buffer: Vec<u8>,
+    // How much of `buffer` has been written
+    written: usize,
+}
+
+#[cfg(feature = "async")]
+impl<W: AsyncWrite + Unpin> AsyncRtfWriter<W> {
+    pub fn new(inner: W, options: WriteOptions) -> Self {
+        AsyncRtfWriter {
+            inner,
+            encoder: Encoder::new(options),
+            buffer: Vec::new(),
+            written: 0,
+        }
+    }
+
+    /// Serialize and write a single token
+    ///
+    /// Escaping policy violations are reported as `io::ErrorKind::InvalidData`
+    /// errors wrapping a `WriteError`, and nothing of the token is written.
+    pub fn write_token(&mut self, token: &Token) -> WriteBuffered<'_, W> {
+        let len = self.buffer.len();
+        let error = match self.encoder.encode(token, &mut self.buffer) {
+            Ok(()) => None,
+            Err(error) => {
+                self.buffer.truncate(len);
+                Some(invalid_data(error))
+            }
+        };
+        WriteBuffered {
+            writer: self,
+            error,
+            flush: false,
+        }
+    }
+
+    /// Finish writing the tokens given so far, and flush the writer
+    pub fn flush(&mut self) -> WriteBuffered<'_, W> {
+        WriteBuffered {
+            writer: self,
+            error: None,
+            flush: true,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The underlying writer, with anything not yet written dropped
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// The future `AsyncRtfWriter`'s methods return
+#[cfg(feature = "async")]
+#[must_use = "futures do nothing unless polled"]
+pub struct WriteBuffered<'a, W: AsyncWrite + Unpin> {
+    writer: &'a mut AsyncRtfWriter<W>,
+    // An escaping error, reported once what came before it is written
+    error: Option<std::io::Error>,
+    flush: bool,
+}
+
+#[cfg(feature = "async")]
+impl<'a, W: AsyncWrite + Unpin> Future for WriteBuffered<'a, W> {
+    type Output = std::io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let writer = &mut *this.writer;
+        while writer.written < writer.buffer.len() {
+            let pending = &writer.buffer[writer.written..];
+            match Pin::new(&mut writer.inner).poll_write(cx, pending) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(std::io::ErrorKind::WriteZero.into()))
+                }
+                Poll::Ready(Ok(n)) => writer.written += n,
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        writer.buffer.clear();
+        writer.written = 0;
+        if let Some(error) = this.error.take() {
+            return Poll::Ready(Err(error));
+        }
+        if this.flush {
+            return Pin::new(&mut writer.inner).poll_flush(cx);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuzz::{arbitrary_tokens, roundtrip};
+    use proptest::prelude::*;
+    use text::extract_text;
+    use tokenizer::{parse, parse_spanned, parse_with_options, NewlinePolicy, ParseOptions};
+
+    fn options(text_escaping: TextEscaping) -> WriteOptions {
+        WriteOptions {
+            text_escaping,
+            ..WriteOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_escape_text() {
+        let text = br"a{b}c\d";
+        assert_eq!(
+            escape_text(text, TextEscaping::Escape).unwrap().as_ref(),
+            br"a\{b\}c\\d"
+        );
+        assert_eq!(
+            escape_text(text, TextEscaping::Verbatim).unwrap().as_ref(),
+            text
+        );
+        assert_eq!(escape_text(text, TextEscaping::Error), Err(1));
+        assert_eq!(
+            escape_text(b"plain", TextEscaping::Error).unwrap().as_ref(),
+            b"plain"
+        );
+    }
+
+    #[test]
+    fn test_escape_non_ascii() {
+        let text = "caf\u{e9} \u{1f600}".as_bytes();
+        assert_eq!(
+            escape_non_ascii(text, NonAsciiEscaping::Verbatim).as_ref(),
+            text
+        );
+        assert_eq!(
+            escape_non_ascii(text, NonAsciiEscaping::Hex).as_ref(),
+            &br"caf\'c3\'a9 \'f0\'9f\'98\'80"[..]
+        );
+        assert_eq!(
+            escape_non_ascii(text, NonAsciiEscaping::Unicode).as_ref(),
+            &br"caf\u233? \u-10179?\u-8704?"[..]
+        );
+        // Bytes that aren't UTF-8 fall back to hex escapes
+        assert_eq!(
+            escape_non_ascii(b"\xe9t\xe9", NonAsciiEscaping::Unicode).as_ref(),
+            &br"\'e9t\'e9"[..]
+        );
+    }
+
+    #[test]
+    fn test_write_non_ascii_text() {
+        let tokens = vec![
+            Token::ControlWord {
+                name: "b".into(),
+                arg: None,
+            },
+            Token::Text("\u{e9}t\u{e9} {x}".as_bytes().to_vec()),
+        ];
+        let options = WriteOptions {
+            non_ascii: NonAsciiEscaping::Unicode,
+            ..WriteOptions::default()
+        };
+        let rtf = write_rtf(&tokens, &options).unwrap();
+        assert_eq!(rtf, br"\b\u233?t\u233? \{x\}".to_vec());
+        assert_eq!(extract_text(&parse(&rtf).unwrap()), "\u{e9}t\u{e9} {x}");
+    }
+
+    #[test]
+    fn test_write_spanned_keeps_spaces() {
+        let rtf = b"{\\rtf1\\b . x\\b0.\\par  two\\'e9 \\fs24-}";
+        let tokens = parse_spanned(rtf).unwrap();
+        let written = write_spanned_rtf(&tokens, &WriteOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(rtf)
+        );
+        // Without the spans, only the spaces that are needed are written
+        let tokens: Vec<Token> = tokens.into_iter().map(|spanned| spanned.token).collect();
+        assert_eq!(
+            write_rtf(&tokens, &WriteOptions::default()).unwrap(),
+            b"{\\rtf1\\b. x\\b0.\\par  two\\'e9 \\fs24-}".to_vec()
+        );
+
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse_spanned(test_bytes).unwrap();
+        let written = write_spanned_rtf(&tokens, &WriteOptions::default()).unwrap();
+        assert!(written == test_bytes.to_vec());
+    }
+
+    #[test]
+    fn test_write_escaped_text_roundtrips() {
+        let tokens = vec![
+            Token::StartGroup,
+            Token::ControlWord {
+                name: "b".into(),
+                arg: None,
+            },
+            Token::Text(br"set {x} \ y".to_vec()),
+            Token::EndGroup,
+        ];
+        let rtf = write_rtf(&tokens, &WriteOptions::default()).unwrap();
+        assert_eq!(rtf, br"{\b set \{x\} \\ y}".to_vec());
+        let reparsed = parse(&rtf).unwrap();
+        assert_eq!(extract_text(&reparsed), r"set {x} \ y");
+    }
+
+    #[test]
+    fn test_write_non_ascii_control_symbol() {
+        let tokens = parse(b"{\\rtf1 a\\\xffb}").unwrap();
+        assert_eq!(tokens[3], Token::ControlSymbol('\u{ff}'));
+        let rtf = write_rtf(&tokens, &WriteOptions::default()).unwrap();
+        assert_eq!(rtf, b"{\\rtf1 a\\\xffb}".to_vec());
+    }
+
+    #[test]
+    fn test_streaming_writer_matches_write_rtf() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let mut writer = RtfWriter::new(Vec::new(), WriteOptions::default());
+        for token in &tokens {
+            writer.write_token(token).unwrap();
+        }
+        let streamed = writer.into_inner();
+        assert_eq!(
+            streamed,
+            write_rtf(&tokens, &WriteOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_tokens() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let mut written = Vec::new();
+        write_tokens(&mut written, &tokens, &WriteOptions::default()).unwrap();
+        assert_eq!(
+            written,
+            write_rtf(&tokens, &WriteOptions::default()).unwrap()
+        );
+
+        let tokens = vec![Token::StartGroup, Token::Text(b"a}".to_vec())];
+        let mut written = Vec::new();
+        let err = write_tokens(&mut written, &tokens, &options(TextEscaping::Error)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(written, b"{".to_vec());
+    }
+
+    // Takes a few bytes at a time, and isn't ready every other time it's
+    // written to
+    #[cfg(feature = "async")]
+    #[derive(Default)]
+    struct SlowWriter {
+        written: Vec<u8>,
+        ready: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncWrite for SlowWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = std::cmp::min(buf.len(), 7);
+            self.written.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn block_on<F: Future>(future: F) -> F::Output {
+        use std::task::{RawWaker, RawWakerVTable, Waker};
+
+        fn raw() -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| raw(), |_| {}, |_| {}, |_| {});
+
+        let waker = unsafe { Waker::from_raw(raw()) };
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_write_tokens_async() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let mut writer = SlowWriter::default();
+        let future = write_tokens_async(&mut writer, &tokens, &WriteOptions::default());
+        block_on(future).unwrap();
+        assert_eq!(
+            writer.written,
+            write_rtf(&tokens, &WriteOptions::default()).unwrap()
+        );
+
+        let tokens = vec![Token::StartGroup, Token::Text(b"a}".to_vec())];
+        let mut writer = SlowWriter::default();
+        let future = write_tokens_async(&mut writer, &tokens, &options(TextEscaping::Error));
+        let err = block_on(future).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(writer.written, b"{".to_vec());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_writer() {
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse(test_bytes).unwrap();
+        let mut writer = AsyncRtfWriter::new(SlowWriter::default(), WriteOptions::default());
+        for token in &tokens {
+            block_on(writer.write_token(token)).unwrap();
+        }
+        block_on(writer.flush()).unwrap();
+        assert_eq!(
+            writer.into_inner().written,
+            write_rtf(&tokens, &WriteOptions::default()).unwrap()
+        );
+
+        let mut writer = AsyncRtfWriter::new(SlowWriter::default(), options(TextEscaping::Error));
+        block_on(writer.write_token(&Token::StartGroup)).unwrap();
+        // Dropped before it's polled, but written before the next token
+        drop(writer.write_token(&Token::text("a")));
+        let err = block_on(writer.write_token(&Token::text("}"))).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(writer.get_ref().written, b"{a".to_vec());
+    }
+
+    #[test]
+    fn test_streaming_writer_error() {
+        let mut writer = RtfWriter::new(Vec::new(), options(TextEscaping::Error));
+        writer.write_token(&Token::StartGroup).unwrap();
+        let err = writer.write_token(&Token::Text(b"{".to_vec())).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(writer.into_inner(), b"{".to_vec());
+    }
+
+    // Token streams the tokenizer can produce, from the generator the fuzz
+    // targets use, so proptest shrinks the bytes they're built from
+    fn token_stream() -> impl Strategy<Value = Vec<Token>> {
+        prop::collection::vec(any::<u8>(), 0..512).prop_map(|data| arbitrary_tokens(&data))
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        // Token streams the tokenizer can produce survive serialization
+        #[test]
+        fn test_roundtrip_property(tokens in token_stream()) {
+            if let Err(reparsed) = roundtrip(&tokens) {
+                prop_assert!(false, "{:?}\nread back as\n{:?}", tokens, reparsed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_width() {
+        let options = WriteOptions {
+            line_width: Some(16),
+            ..WriteOptions::default()
+        };
+        let rtf =
+            b"{\\rtf1{\\*\\generator x;}\\b bold\\b0  and a long run of text\\par\\u233?\\par}";
+        let tokens = parse(rtf).unwrap();
+        let written = write_rtf(&tokens, &options).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            "{\\rtf1{\\*\\generator \r\nx;}\\b bold\\b0 \r\n and a long run of text\r\n\\par\\u233?\\par}"
+        );
+
+        let merge = ParseOptions {
+            newlines: NewlinePolicy::Merge,
+            ..ParseOptions::default()
+        };
+        let test_bytes = include_bytes!("../tests/sample.rtf");
+        let tokens = parse_with_options(test_bytes, &merge).unwrap();
+        let options = WriteOptions {
+            line_width: Some(80),
+            ..WriteOptions::default()
+        };
+        let written = write_rtf(&tokens, &options).unwrap();
+        // Lines only run long where they can't be broken, as in the data of
+        // pictures
+        let lines = written.split(|&byte| byte == b'\n');
+        let short = lines.clone().filter(|line| line.len() <= 82).count();
+        assert!(short > lines.count() * 4 / 5);
+        assert_eq!(parse_with_options(&written, &merge).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_write_error_policy() {
+        let tokens = vec![Token::StartGroup, Token::Text(b"a}".to_vec())];
+        assert_eq!(
+            write_rtf(&tokens, &options(TextEscaping::Error)),
+            Err(WriteError::ReservedCharInText {
+                token: 1,
+                offset: 1,
+                byte: b'}',
+            })
+        );
+        assert_eq!(
+            write_rtf(&tokens, &options(TextEscaping::Verbatim)),
+            Ok(b"{a}".to_vec())
+        );
+    }
+}
@@ -0,0 +1,25 @@
+// Roundtrips a corpus of documents through the tokenizer and writer
+//
+// Checks the documents bundled in `tests/` by default.  Set `RTF_CORPUS` to
+// a directory to check every `.rtf` file under it instead:
+//
+//     RTF_CORPUS=/path/to/corpus cargo test --features corpus --test corpus
+//
+
+extern crate rtf_grimoire;
+
+use std::env;
+use std::path::PathBuf;
+
+use rtf_grimoire::corpus::check_dir;
+
+#[test]
+fn test_corpus_roundtrips() {
+    let dir = env::var_os("RTF_CORPUS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests"));
+    let report = check_dir(&dir).unwrap();
+    println!("{}", report);
+    assert!(report.checked > 0, "no documents in {}", dir.display());
+    assert!(report.passed(), "{}", report);
+}